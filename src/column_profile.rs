@@ -0,0 +1,361 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use parquet::{
+    basic::{ConvertedType, LogicalType, Type as PhysicalType},
+    schema::types::Type,
+};
+
+use crate::parquet_buffer::ProfileValue;
+
+/// Number of bits of each hash used to pick a register of the HyperLogLog sketch. The sketch has
+/// `2^PRECISION` registers of one byte each, independent of the number of values.
+const PRECISION: u32 = 12;
+const NUM_REGISTERS: usize = 1 << PRECISION;
+
+/// How the physical values of a column are ordered, in order to track minimum and maximum.
+#[derive(Clone, Copy)]
+pub enum Order {
+    /// Values are not ordered, e.g. `INT96` timestamps. No minimum and maximum are tracked.
+    Unordered,
+    Bool,
+    Signed,
+    Unsigned,
+    Float,
+    /// Two's complement integers, rendered with `scale` fractional digits.
+    Decimal {
+        scale: u32,
+    },
+    /// Lexicographical order of the bytes. Rendered as strings, if `is_text` or as hex otherwise.
+    Bytes {
+        is_text: bool,
+    },
+}
+
+/// Ordered representation of a value. Generic over the bytes, so we can compare borrowed values
+/// with the owned minimum and maximum, without allocating for each value.
+#[derive(PartialEq, PartialOrd)]
+pub enum Ordered<B> {
+    Bool(bool),
+    Int(i128),
+    Float(f64),
+    Bytes(B),
+}
+
+impl Ordered<&[u8]> {
+    pub fn to_owned(&self) -> Ordered<Vec<u8>> {
+        match *self {
+            Ordered::Bool(value) => Ordered::Bool(value),
+            Ordered::Int(value) => Ordered::Int(value),
+            Ordered::Float(value) => Ordered::Float(value),
+            Ordered::Bytes(value) => Ordered::Bytes(value.to_owned()),
+        }
+    }
+}
+
+impl Ordered<Vec<u8>> {
+    pub fn as_borrowed(&self) -> Ordered<&[u8]> {
+        match self {
+            Ordered::Bool(value) => Ordered::Bool(*value),
+            Ordered::Int(value) => Ordered::Int(*value),
+            Ordered::Float(value) => Ordered::Float(*value),
+            Ordered::Bytes(value) => Ordered::Bytes(value.as_slice()),
+        }
+    }
+}
+
+/// Number of rows for each distinct value of a column. Only the first `max_values` distinct values
+/// are tracked, so memory usage is bounded. Rows with any other value are counted together.
+pub struct Histogram {
+    pub max_values: usize,
+    pub counts: Vec<(Ordered<Vec<u8>>, u64)>,
+    /// Rows with values beyond the first `max_values` distinct ones, or values which are not
+    /// ordered (e.g. `NaN`).
+    pub num_other: u64,
+}
+
+impl Histogram {
+    fn add(&mut self, value: Option<&Ordered<&[u8]>>) {
+        let Some(value) = value else {
+            self.num_other += 1;
+            return;
+        };
+        // Linear search is fine, since the histogram is intended for low cardinality columns.
+        if let Some((_, count)) = self
+            .counts
+            .iter_mut()
+            .find(|(known, _)| known.as_borrowed() == *value)
+        {
+            *count += 1;
+        } else if self.counts.len() < self.max_values {
+            self.counts.push((value.to_owned(), 1));
+        } else {
+            self.num_other += 1;
+        }
+    }
+}
+
+/// A value of a column kept as example, owning its bytes.
+pub enum Sample {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    I96([u32; 3]),
+    F32(f32),
+    F64(f64),
+    Bytes(Vec<u8>),
+}
+
+impl Sample {
+    fn new(value: &ProfileValue) -> Self {
+        match *value {
+            ProfileValue::Bool(value) => Sample::Bool(value),
+            ProfileValue::I32(value) => Sample::I32(value),
+            ProfileValue::I64(value) => Sample::I64(value),
+            ProfileValue::I96(value) => Sample::I96([value[0], value[1], value[2]]),
+            ProfileValue::F32(value) => Sample::F32(value),
+            ProfileValue::F64(value) => Sample::F64(value),
+            ProfileValue::Bytes(value) => Sample::Bytes(value.to_owned()),
+        }
+    }
+
+    pub fn as_value(&self) -> ProfileValue<'_> {
+        match self {
+            Sample::Bool(value) => ProfileValue::Bool(*value),
+            Sample::I32(value) => ProfileValue::I32(*value),
+            Sample::I64(value) => ProfileValue::I64(*value),
+            Sample::I96(value) => ProfileValue::I96(value),
+            Sample::F32(value) => ProfileValue::F32(*value),
+            Sample::F64(value) => ProfileValue::F64(*value),
+            Sample::Bytes(value) => ProfileValue::Bytes(value),
+        }
+    }
+
+    /// Floating points are compared by their bits, so `NaN` is kept only once.
+    fn is(&self, value: &ProfileValue) -> bool {
+        match (self.as_value(), value) {
+            (ProfileValue::Bool(a), ProfileValue::Bool(b)) => a == *b,
+            (ProfileValue::I32(a), ProfileValue::I32(b)) => a == *b,
+            (ProfileValue::I64(a), ProfileValue::I64(b)) => a == *b,
+            (ProfileValue::I96(a), ProfileValue::I96(b)) => a == *b,
+            (ProfileValue::F32(a), ProfileValue::F32(b)) => a.to_bits() == b.to_bits(),
+            (ProfileValue::F64(a), ProfileValue::F64(b)) => a.to_bits() == b.to_bits(),
+            (ProfileValue::Bytes(a), ProfileValue::Bytes(b)) => a == *b,
+            _ => false,
+        }
+    }
+}
+
+/// The first `max_values` distinct values of a column, as examples for documenting the schema.
+pub struct Samples {
+    /// Type of the column, in order to render the samples according to its logical type.
+    pub parquet_type: Type,
+    pub max_values: usize,
+    pub values: Vec<Sample>,
+}
+
+impl Samples {
+    fn add(&mut self, value: &ProfileValue) {
+        // Once full, we are done with this column. Linear search is fine, since only a handful of
+        // samples is intended.
+        if self.values.len() < self.max_values && !self.values.iter().any(|s| s.is(value)) {
+            self.values.push(Sample::new(value));
+        }
+    }
+}
+
+/// Null count, minimum, maximum and an estimate of the number of distinct values of a column,
+/// accumulated from the values written to it. Optionally also the number of rows per value and
+/// samples of the values.
+pub struct ColumnProfile {
+    pub order: Order,
+    pub null_count: u64,
+    pub min: Option<Ordered<Vec<u8>>>,
+    pub max: Option<Ordered<Vec<u8>>>,
+    /// Registers of a HyperLogLog sketch. Each holds the maximum rank of the hashes assigned to it.
+    pub registers: Vec<u8>,
+    /// Number of rows per value, if requested.
+    pub histogram: Option<Histogram>,
+    /// Example values, if requested.
+    pub samples: Option<Samples>,
+}
+
+impl ColumnProfile {
+    /// Profile for values written into a column of type `parquet_type`.
+    pub fn new(parquet_type: &Type) -> Self {
+        let info = parquet_type.get_basic_info();
+        let is_decimal = info.converted_type() == ConvertedType::DECIMAL;
+        let order = match parquet_type.get_physical_type() {
+            _ if is_decimal => Order::Decimal {
+                scale: parquet_type.get_scale().try_into().unwrap_or(0),
+            },
+            PhysicalType::BOOLEAN => Order::Bool,
+            PhysicalType::INT32 | PhysicalType::INT64 => match info.logical_type() {
+                Some(LogicalType::Integer {
+                    is_signed: false, ..
+                }) => Order::Unsigned,
+                _ => Order::Signed,
+            },
+            PhysicalType::INT96 => Order::Unordered,
+            PhysicalType::FLOAT | PhysicalType::DOUBLE => Order::Float,
+            PhysicalType::BYTE_ARRAY | PhysicalType::FIXED_LEN_BYTE_ARRAY => Order::Bytes {
+                is_text: info.converted_type() == ConvertedType::UTF8
+                    || matches!(
+                        info.logical_type(),
+                        Some(LogicalType::String | LogicalType::Json | LogicalType::Enum)
+                    ),
+            },
+        };
+        Self {
+            order,
+            null_count: 0,
+            min: None,
+            max: None,
+            registers: vec![0; NUM_REGISTERS],
+            histogram: None,
+            samples: None,
+        }
+    }
+
+    /// Also count the number of rows for each of the first `max_values` distinct values.
+    pub fn with_histogram(mut self, max_values: usize) -> Self {
+        self.histogram = Some(Histogram {
+            max_values,
+            counts: Vec::new(),
+            num_other: 0,
+        });
+        self
+    }
+
+    /// Also keep the first `max_values` distinct values as examples.
+    pub fn with_samples(mut self, parquet_type: &Type, max_values: usize) -> Self {
+        self.samples = Some(Samples {
+            parquet_type: parquet_type.clone(),
+            max_values,
+            values: Vec::new(),
+        });
+        self
+    }
+
+    pub fn add_null(&mut self) {
+        self.null_count += 1;
+    }
+
+    pub fn add(&mut self, value: ProfileValue) {
+        let mut hasher = DefaultHasher::new();
+        match value {
+            ProfileValue::Bool(value) => value.hash(&mut hasher),
+            ProfileValue::I32(value) => value.hash(&mut hasher),
+            ProfileValue::I64(value) => value.hash(&mut hasher),
+            ProfileValue::I96(value) => value.hash(&mut hasher),
+            ProfileValue::F32(value) => value.to_bits().hash(&mut hasher),
+            ProfileValue::F64(value) => value.to_bits().hash(&mut hasher),
+            ProfileValue::Bytes(value) => value.hash(&mut hasher),
+        }
+        let hash = hasher.finish();
+        let index = (hash >> (64 - PRECISION)) as usize;
+        // Position of the first set bit within the remaining bits of the hash.
+        let rank = ((hash << PRECISION).leading_zeros() + 1).min(64 - PRECISION + 1) as u8;
+        self.registers[index] = self.registers[index].max(rank);
+
+        if let Some(samples) = &mut self.samples {
+            samples.add(&value);
+        }
+        let ordered = self.ordered(&value);
+        if let Some(histogram) = &mut self.histogram {
+            histogram.add(ordered.as_ref());
+        }
+        if let Some(ordered) = ordered {
+            if self
+                .min
+                .as_ref()
+                .is_none_or(|min| ordered < min.as_borrowed())
+            {
+                self.min = Some(ordered.to_owned());
+            }
+            if self
+                .max
+                .as_ref()
+                .is_none_or(|max| ordered > max.as_borrowed())
+            {
+                self.max = Some(ordered.to_owned());
+            }
+        }
+    }
+
+    /// Value in the order of the column. `None` for values which are not ordered, like `NaN`.
+    fn ordered<'a>(&self, value: &ProfileValue<'a>) -> Option<Ordered<&'a [u8]>> {
+        let ordered = match (self.order, value) {
+            (Order::Bool, ProfileValue::Bool(value)) => Ordered::Bool(*value),
+            (Order::Signed | Order::Decimal { .. }, ProfileValue::I32(value)) => {
+                Ordered::Int(*value as i128)
+            }
+            (Order::Signed | Order::Decimal { .. }, ProfileValue::I64(value)) => {
+                Ordered::Int(*value as i128)
+            }
+            (Order::Unsigned, ProfileValue::I32(value)) => Ordered::Int(*value as u32 as i128),
+            (Order::Unsigned, ProfileValue::I64(value)) => Ordered::Int(*value as u64 as i128),
+            (Order::Float, ProfileValue::F32(value)) if !value.is_nan() => {
+                Ordered::Float(*value as f64)
+            }
+            (Order::Float, ProfileValue::F64(value)) if !value.is_nan() => Ordered::Float(*value),
+            (Order::Decimal { .. }, ProfileValue::Bytes(bytes))
+                if !bytes.is_empty() && bytes.len() <= 16 =>
+            {
+                let sign = if bytes[0] & 0x80 != 0 { 0xff } else { 0 };
+                let mut be_bytes = [sign; 16];
+                be_bytes[16 - bytes.len()..].copy_from_slice(bytes);
+                Ordered::Int(i128::from_be_bytes(be_bytes))
+            }
+            (Order::Bytes { .. }, ProfileValue::Bytes(bytes)) => Ordered::Bytes(*bytes),
+            _ => return None,
+        };
+        Some(ordered)
+    }
+
+    /// Estimated number of distinct values (excluding NULL). The standard error of the estimate is
+    /// about `1.04 / sqrt(2^PRECISION)`, i.e. 1.6%. Small cardinalities are estimated using linear
+    /// counting and are close to exact.
+    pub fn distinct_count_estimate(&self) -> u64 {
+        let m = NUM_REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|&rank| 2f64.powi(-(rank as i32)))
+            .sum();
+        let raw = alpha * m * m / sum;
+        let num_empty = self.registers.iter().filter(|&&rank| rank == 0).count();
+        let estimate = if raw <= 2.5 * m && num_empty != 0 {
+            m * (m / num_empty as f64).ln()
+        } else {
+            raw
+        };
+        estimate.round() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parquet::schema::parser::parse_message_type;
+
+    use crate::parquet_buffer::ProfileValue;
+
+    use super::ColumnProfile;
+
+    #[test]
+    fn estimate_distinct_count() {
+        let schema = parse_message_type("message schema { OPTIONAL INT64 a; }").unwrap();
+        let mut profile = ColumnProfile::new(&schema.get_fields()[0]);
+        for _ in 0..3 {
+            for value in 0..100_000 {
+                profile.add(ProfileValue::I64(value));
+            }
+        }
+        let estimate = profile.distinct_count_estimate() as f64;
+        // Well within three standard errors.
+        assert!((estimate - 100_000.0).abs() < 5_000.0, "{estimate}");
+    }
+}
@@ -0,0 +1,138 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use anyhow::{bail, Context, Error};
+
+/// Credentials printed by the command passed to `--credential-helper`. Each field replaces the
+/// respective option of the command line, if present.
+#[derive(Debug, Default, PartialEq)]
+pub struct Credentials {
+    pub connection_string: Option<String>,
+    pub user: Option<String>,
+    pub password: Option<String>,
+}
+
+impl Credentials {
+    /// Executes `command` using the shell of the platform. `dsn` and `user` are written to its
+    /// standard input as `key=value` lines, so one helper can serve several data sources. Its
+    /// standard error is passed through, its standard output is parsed by [`parse_output`].
+    pub fn from_helper(
+        command: &str,
+        dsn: Option<&str>,
+        user: Option<&str>,
+    ) -> Result<Self, Error> {
+        let mut child = shell(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .with_context(|| format!("Could not execute credential helper `{command}`."))?;
+        let mut input = String::new();
+        for (key, value) in [("dsn", dsn), ("user", user)] {
+            if let Some(value) = value {
+                input.push_str(&format!("{key}={value}\n"));
+            }
+        }
+        // The helper is free to ignore its input, so a closed pipe is not an error.
+        let _ = child.stdin.take().unwrap().write_all(input.as_bytes());
+        let output = child
+            .wait_with_output()
+            .with_context(|| format!("Could not execute credential helper `{command}`."))?;
+        if !output.status.success() {
+            bail!(
+                "Credential helper `{command}` failed with {}.",
+                output.status
+            )
+        }
+        let stdout = String::from_utf8(output.stdout)
+            .with_context(|| format!("Output of credential helper `{command}` is not UTF-8."))?;
+        parse_output(&stdout)
+            .with_context(|| format!("Output of credential helper `{command}` is invalid."))
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(target_os = "windows")]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+/// Parses lines like `password=secret`. Keys are `connection-string`, `user` and `password`.
+/// Everything after the first `=` is the value, so values may contain `=` themselves. Empty lines
+/// are ignored. Values are never part of error messages, as they are likely secret.
+pub fn parse_output(output: &str) -> Result<Credentials, Error> {
+    let mut credentials = Credentials::default();
+    for (index, line) in output.lines().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            bail!("Line {} is not of the form `key=value`.", index + 1)
+        };
+        let field = match key {
+            "connection-string" => &mut credentials.connection_string,
+            "user" => &mut credentials.user,
+            "password" => &mut credentials.password,
+            _ => bail!(
+                "Unknown key '{key}' in line {}. Expected `connection-string`, `user` or \
+                `password`.",
+                index + 1
+            ),
+        };
+        if field.replace(value.to_owned()).is_some() {
+            bail!("Key '{key}' is specified more than once.")
+        }
+    }
+    if credentials == Credentials::default() {
+        bail!("Neither `connection-string`, `user` nor `password` is specified.")
+    }
+    Ok(credentials)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_output, Credentials};
+
+    #[test]
+    fn parse_helper_output() {
+        assert_eq!(
+            Credentials {
+                connection_string: None,
+                user: Some("sa".to_owned()),
+                password: Some("a=b;c".to_owned()),
+            },
+            parse_output("user=sa\r\n\npassword=a=b;c\n").unwrap()
+        );
+        assert!(parse_output("").is_err());
+        assert!(parse_output("secret").is_err());
+        assert!(parse_output("pwd=secret").is_err());
+        assert!(parse_output("password=a\npassword=b").is_err());
+        // The value is never part of an error message
+        let error = parse_output("secret\n").unwrap_err();
+        assert!(!format!("{error:#}").contains("secret"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn execute_helper() {
+        let credentials = Credentials::from_helper(
+            "read line; echo \"password=for $line\"",
+            Some("orders"),
+            None,
+        )
+        .unwrap();
+        assert_eq!(Some("for dsn=orders".to_owned()), credentials.password);
+
+        assert!(Credentials::from_helper("echo password=secret; exit 1", None, None).is_err());
+    }
+}
@@ -0,0 +1,216 @@
+use std::{
+    fmt::{self, Write as _},
+    fs,
+    io::{stderr, IsTerminal},
+    path::Path,
+    sync::Mutex,
+};
+
+use anyhow::{bail, Context, Error};
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use odbc_api::handles::{slice_to_cow_utf8, Record as OdbcRecord};
+use stderrlog::{ColorChoice, StdErrLog};
+
+use crate::query::json_string;
+
+/// First warning logged, if `--abort-on-warning` is set.
+static FIRST_WARNING: Mutex<Option<String>> = Mutex::new(None);
+
+/// A single ODBC diagnostic record, as returned by `SQLGetDiagRec`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DiagnosticRecord {
+    pub state: String,
+    pub native_error: i32,
+    pub message: String,
+}
+
+impl DiagnosticRecord {
+    fn from_odbc(record: &OdbcRecord) -> Self {
+        Self {
+            state: record.state.as_str().to_owned(),
+            native_error: record.native_error,
+            message: slice_to_cow_utf8(&record.message).into_owned(),
+        }
+    }
+
+    fn to_json(&self) -> String {
+        format!(
+            "{{\"state\":{},\"native_error\":{},\"message\":{}}}",
+            json_string(&self.state),
+            self.native_error,
+            json_string(&self.message)
+        )
+    }
+}
+
+impl fmt::Display for DiagnosticRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "State: {}, Native error: {}, Message: {}",
+            self.state, self.native_error, self.message
+        )
+    }
+}
+
+/// Diagnostic record held by an error of `odbc-api`, if any.
+fn odbc_record(error: &odbc_api::Error) -> Option<&OdbcRecord> {
+    match error {
+        odbc_api::Error::Diagnostics { record, .. }
+        | odbc_api::Error::InvalidRowArraySize { record, .. }
+        | odbc_api::Error::UnsupportedOdbcApiVersion(record)
+        | odbc_api::Error::UnableToRepresentNull(record) => Some(record),
+        _ => None,
+    }
+}
+
+/// Passes everything on to the inner logger, but remembers the first warning, so
+/// [`check_warnings`] fails. Only installed for `--abort-on-warning`.
+struct TrapWarnings {
+    inner: StdErrLog,
+}
+
+impl Log for TrapWarnings {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() == Level::Warn || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        // Drivers use SQLSTATE 01000 for purely informational diagnostics, which `odbc-api` logs
+        // as warnings, too.
+        let is_informational = record.target().starts_with("odbc_api")
+            && record.args().to_string().starts_with("State: 01000,");
+        if record.level() == Level::Warn && !is_informational {
+            FIRST_WARNING
+                .lock()
+                .unwrap()
+                .get_or_insert_with(|| record.args().to_string());
+        }
+        self.inner.log(record)
+    }
+
+    fn flush(&self) {
+        self.inner.flush()
+    }
+}
+
+/// Installs `logger`, and remembers the first warning logged from now on, so [`check_warnings`]
+/// fails. Warnings are remembered even if they are not printed, e.g. due to `--quiet`. Diagnostic
+/// records with SQLSTATE `01000` are exempt, since drivers use it for purely informational
+/// messages, like changing the database context after connecting.
+pub fn abort_on_warning(mut logger: StdErrLog, verbosity: usize) -> Result<(), SetLoggerError> {
+    let level = match verbosity {
+        0 => LevelFilter::Error,
+        1 => LevelFilter::Warn,
+        2 => LevelFilter::Info,
+        3 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    // Warnings must reach our logger, even if they are not printed. `StdErrLog::init` would check
+    // whether standard error is a terminal, which we do ourselves, since we do not call it.
+    if !stderr().is_terminal() {
+        logger.color(ColorChoice::Never);
+    }
+    log::set_max_level(level.max(LevelFilter::Warn));
+    log::set_boxed_logger(Box::new(TrapWarnings { inner: logger }))
+}
+
+/// An error holding the first warning, if a warning has been logged since [`abort_on_warning`].
+/// Called between batches and once a command has completed, so the command stops at the next
+/// opportunity after the warning.
+pub fn check_warnings() -> Result<(), Error> {
+    if let Some(warning) = FIRST_WARNING.lock().unwrap().as_deref() {
+        bail!("Aborting, since `--abort-on-warning` is set. Warning: {warning}")
+    }
+    Ok(())
+}
+
+/// Diagnostic records of all ODBC errors in the chain of causes of `error`, outermost first.
+/// Empty if the error has not been caused by an ODBC function call returning diagnostics.
+pub fn diagnostic_chain(error: &Error) -> Vec<DiagnosticRecord> {
+    error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<odbc_api::Error>())
+        .filter_map(odbc_record)
+        .map(DiagnosticRecord::from_odbc)
+        .collect()
+}
+
+/// Adds the complete chain of diagnostic records to the error, if it consists of more than the
+/// first record which is already part of the error message. If `error_log` is specified the error
+/// and its diagnostic records are written to it as JSON.
+pub fn report_diagnostics(error: Error, error_log: Option<&Path>) -> Error {
+    let chain = diagnostic_chain(&error);
+    if let Some(path) = error_log {
+        let records: Vec<_> = chain.iter().map(DiagnosticRecord::to_json).collect();
+        let json = format!(
+            "{{\"error\":{},\"diagnostics\":[{}]}}\n",
+            json_string(&format!("{error:#}")),
+            records.join(",")
+        );
+        if let Err(write_error) = fs::write(path, json)
+            .with_context(|| format!("Could not write error log '{}'.", path.display()))
+        {
+            log::error!("{write_error:#}");
+        }
+    }
+    if chain.len() < 2 {
+        return error;
+    }
+    let mut text = "ODBC diagnostic records:".to_owned();
+    for (index, record) in chain.iter().enumerate() {
+        write!(text, "\n  {}. {record}", index + 1).unwrap();
+    }
+    error.context(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+    use odbc_api::handles::{Record, State};
+
+    use super::{diagnostic_chain, DiagnosticRecord};
+
+    fn record(state: &[u8; 5], native_error: i32, message: &str) -> Record {
+        Record {
+            state: State(*state),
+            native_error,
+            #[cfg(not(target_os = "windows"))]
+            message: message.as_bytes().to_owned(),
+            #[cfg(target_os = "windows")]
+            message: message.encode_utf16().collect(),
+        }
+    }
+
+    #[test]
+    fn collect_diagnostic_records_from_error_chain() {
+        let inner = odbc_api::Error::Diagnostics {
+            record: record(b"42S02", 208, "Invalid object name 'Foo'."),
+            function: "SQLExecDirect",
+        };
+        let error = anyhow::Error::from(inner).context("Could not execute query.");
+
+        let chain = diagnostic_chain(&error);
+
+        assert_eq!(
+            vec![DiagnosticRecord {
+                state: "42S02".to_owned(),
+                native_error: 208,
+                message: "Invalid object name 'Foo'.".to_owned(),
+            }],
+            chain
+        );
+        assert_eq!(
+            "{\"state\":\"42S02\",\"native_error\":208,\"message\":\"Invalid object name 'Foo'.\"}",
+            chain[0].to_json()
+        );
+    }
+
+    #[test]
+    fn no_diagnostic_records_without_odbc_error() {
+        let error = Err::<(), _>(std::fmt::Error)
+            .context("Not an ODBC error.")
+            .unwrap_err();
+        assert!(diagnostic_chain(&error).is_empty());
+    }
+}
@@ -1,8 +1,12 @@
+use std::{path::PathBuf, sync::Arc};
+
 use anyhow::{anyhow, bail, Error};
 use clap::ValueEnum;
 use parquet::{
     basic::{BrotliLevel, Compression, Encoding, GzipLevel, ZstdLevel},
     errors::ParquetError,
+    format::SortingColumn,
+    schema::types::{SchemaDescriptor, Type},
 };
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -29,6 +33,153 @@ impl EncodingArgument {
     }
 }
 
+/// How to match the columns of the result set with the columns of a target schema.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SchemaMatchBy {
+    /// Columns are matched by their name.
+    Name,
+    /// The n-th column of the result set is matched with the n-th column of the target schema.
+    Position,
+}
+
+/// Catalog function of the driver, whose result set is exported with `--catalog-function`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CatalogFunction {
+    /// `SQLTables`: A row for each table, with its catalog, schema, name, type and remarks.
+    Tables,
+    /// `SQLColumns`: A row for each column, with its table, name, SQL type, size, nullability,
+    /// default value and position.
+    Columns,
+}
+
+/// Dialect of the `CREATE TABLE` statement written with `--emit-ddl`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DdlDialect {
+    /// `CREATE EXTERNAL TABLE ... STORED AS PARQUET` for the Hive metastore or AWS Glue.
+    Hive,
+    /// Like `hive`. AWS Athena uses Hive DDL for external tables.
+    Athena,
+    /// `CREATE TABLE ... USING PARQUET` for Spark SQL. Timestamps without time zone are declared
+    /// as `TIMESTAMP_NTZ`.
+    Spark,
+}
+
+/// What to do with dates which can not be represented in the output, e.g. `0000-00-00`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DateOutOfRange {
+    /// Write NULL instead.
+    Null,
+    /// Pin the date to the earliest or latest representable date, or the closest valid day of
+    /// the month.
+    Clamp,
+    /// Abort the export.
+    Error,
+}
+
+/// What `--skip-bad-rows` does with a row holding a value which can not be converted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BadRowAction {
+    /// Write the value as NULL and keep the rest of the row.
+    Null,
+    /// Leave out the entire row.
+    Drop,
+}
+
+/// How to round decimals with more fractional digits than the scale of their column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DecimalRounding {
+    /// Round to the nearest value, ties away from zero.
+    HalfUp,
+    /// Round to the nearest value, ties to the even neighbour (banker's rounding).
+    HalfEven,
+    /// Drop the excess digits, i.e. round towards zero.
+    Truncate,
+    /// Round towards negative infinity.
+    Floor,
+    /// Round towards positive infinity.
+    Ceil,
+}
+
+/// What to do with decimals having more digits than the precision they are written with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DecimalPrecisionCheck {
+    /// Write the value anyway, but log and count it.
+    Warn,
+    /// Fail the export with an error naming the value.
+    Strict,
+}
+
+/// What to do once a split output reached `--max-file-count` files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OnMaxFiles {
+    /// Fail the export, if further rows would require another file.
+    Stop,
+    /// Keep writing into the last file, ignoring further split boundaries.
+    Merge,
+}
+
+/// What to do with timestamps, whose time zone is not known to the time zone database. See
+/// `--ts-tz-from`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum UnknownTimeZone {
+    /// Write NULL instead.
+    Null,
+    /// Interpret the timestamp as UTC.
+    AssumeUtc,
+    /// Abort the export.
+    Error,
+}
+
+/// File format of the output. See `--format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Parquet,
+    /// Comma separated values.
+    Csv,
+    /// Tab separated values.
+    Tsv,
+    /// Newline delimited JSON, one object per row.
+    Ndjson,
+}
+
+/// How decimals are written by `--format ndjson`. See `--json-decimal`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum JsonDecimal {
+    /// JSON numbers, e.g. `12.30`.
+    Number,
+    /// JSON strings, e.g. `"12.30"`, preserving all digits for readers parsing numbers as doubles.
+    String,
+}
+
+/// How NULLs are written by `--format ndjson`. See `--json-nulls`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum JsonNulls {
+    /// Write the key with the value `null`.
+    Null,
+    /// Omit the key from the object.
+    Omit,
+}
+
+/// When to put values of delimited text output in quotes. See `--csv-quote`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CsvQuote {
+    /// Only values containing the delimiter, a quote or a line break, or values equal to the NULL
+    /// representation.
+    Necessary,
+    /// Every value, except NULL.
+    Always,
+    /// No value. The output may not be parsable if values contain delimiters or line breaks.
+    Never,
+}
+
+/// Which files of delimited text output start with a header. See `--csv-header`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CsvHeader {
+    EveryFile,
+    FirstFile,
+    None,
+}
+
 /// Mirrors parquets `Compression` enum in order to parse it from the command line
 #[derive(Debug, Clone, Copy, ValueEnum)]
 pub enum CompressionVariants {
@@ -95,3 +246,531 @@ pub fn column_encoding_from_str(source: &str) -> Result<(String, Encoding), Erro
     let (name, encoding) = source.split_at(pos);
     Ok((name.to_owned(), encoding_from_str(&encoding[1..])?))
 }
+
+/// Parses the `--also-write` command line option in format `CODEC:PATH`. The path may itself
+/// contain colons.
+pub fn also_write_from_str(source: &str) -> Result<(CompressionVariants, PathBuf), Error> {
+    let (codec, path) = source
+        .split_once(':')
+        .ok_or_else(|| anyhow!("Additional output must be specified in format: 'CODEC:PATH'"))?;
+    let codec = CompressionVariants::from_str(codec, true).map_err(|message| anyhow!(message))?;
+    if path.is_empty() {
+        bail!("Path of additional output must not be empty.")
+    }
+    Ok((codec, PathBuf::from(path)))
+}
+
+/// Parses command line options assigning a value to a column, like `--null-fill` or
+/// `--column-comment`, in format `COLUMN=VALUE`. The value may itself contain `=`.
+pub fn column_value_from_str(source: &str) -> Result<(String, String), Error> {
+    let (column, value) = source
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Value must be specified in format: 'COLUMN=VALUE'"))?;
+    if column.is_empty() {
+        bail!("Column name must not be empty.")
+    }
+    Ok((column.to_owned(), value.to_owned()))
+}
+
+/// Parses `--extension`. A leading dot is removed, so `parquet` and `.parquet` are the same.
+pub fn extension_from_str(source: &str) -> Result<String, Error> {
+    let extension = source.strip_prefix('.').unwrap_or(source);
+    if extension.is_empty() {
+        bail!("Extension must not be empty.")
+    }
+    if extension.contains(['/', '\\']) {
+        bail!("Extension must not contain path separators.")
+    }
+    Ok(extension.to_owned())
+}
+
+/// Parses `--column-max-length` in format `COLUMN=LENGTH`. The length must be positive.
+pub fn column_max_length_from_str(source: &str) -> Result<(String, usize), Error> {
+    let (column, length) = column_value_from_str(source)?;
+    let length = length
+        .parse()
+        .ok()
+        .filter(|&length| length != 0)
+        .ok_or_else(|| anyhow!("Maximum length must be a positive integer, not '{length}'."))?;
+    Ok((column, length))
+}
+
+/// Parquet type a column of a vendor specific SQL type is mapped to by a `--type-rule`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TypeRuleTarget {
+    /// UTF-8 text. Length is taken from the column size reported by the driver.
+    Text,
+    /// Variable length binary. Length is taken from the column size reported by the driver.
+    Binary,
+    /// Signed 32-Bit integer.
+    Int32,
+    /// Signed 64-Bit integer.
+    Int64,
+    /// 32-Bit floating point number.
+    Float,
+    /// 64-Bit floating point number.
+    Double,
+}
+
+/// Parsed value of the `--type-rule` command line option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypeRule {
+    /// Vendor specific SQL data type code as reported by the driver.
+    pub sql_type: i16,
+    pub target: TypeRuleTarget,
+}
+
+/// Parses the `--type-rule` command line option in format `SQL_TYPE_CODE=TARGET`.
+pub fn type_rule_from_str(source: &str) -> Result<TypeRule, Error> {
+    let (sql_type, target) = source
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Type rule must be specified in format: 'SQL_TYPE_CODE=TARGET'"))?;
+    let sql_type = sql_type
+        .trim()
+        .parse()
+        .map_err(|_| anyhow!("'{sql_type}' is not a valid SQL type code."))?;
+    let target =
+        TypeRuleTarget::from_str(target.trim(), true).map_err(|message| anyhow!(message))?;
+    Ok(TypeRule { sql_type, target })
+}
+
+/// ODBC C type a column is bound as with `--bind-as`. Each C type has a fixed parquet type it is
+/// written as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindCType {
+    /// Narrow text, written as UTF-8 text.
+    Char,
+    /// Wide (UTF-16) text, written as UTF-8 text.
+    WChar,
+    /// Raw bytes, written as variable length binary.
+    Binary,
+    /// Signed 32-Bit integer, written as `INT32`.
+    SLong,
+    /// Signed 64-Bit integer, written as `INT64`.
+    SBigInt,
+    /// 32-Bit floating point number, written as `FLOAT`.
+    Float,
+    /// 64-Bit floating point number, written as `DOUBLE`.
+    Double,
+    /// Written as `BOOLEAN`.
+    Bit,
+    /// Written as `DATE`.
+    TypeDate,
+    /// Written as `TIMESTAMP` with microsecond precision.
+    TypeTimestamp,
+}
+
+impl BindCType {
+    const ALL: [BindCType; 10] = [
+        BindCType::Char,
+        BindCType::WChar,
+        BindCType::Binary,
+        BindCType::SLong,
+        BindCType::SBigInt,
+        BindCType::Float,
+        BindCType::Double,
+        BindCType::Bit,
+        BindCType::TypeDate,
+        BindCType::TypeTimestamp,
+    ];
+
+    /// Name of the C type as used in the ODBC API, e.g. `SQL_C_CHAR`.
+    pub fn name(self) -> &'static str {
+        match self {
+            BindCType::Char => "SQL_C_CHAR",
+            BindCType::WChar => "SQL_C_WCHAR",
+            BindCType::Binary => "SQL_C_BINARY",
+            BindCType::SLong => "SQL_C_SLONG",
+            BindCType::SBigInt => "SQL_C_SBIGINT",
+            BindCType::Float => "SQL_C_FLOAT",
+            BindCType::Double => "SQL_C_DOUBLE",
+            BindCType::Bit => "SQL_C_BIT",
+            BindCType::TypeDate => "SQL_C_TYPE_DATE",
+            BindCType::TypeTimestamp => "SQL_C_TYPE_TIMESTAMP",
+        }
+    }
+}
+
+/// C types defined by ODBC, which can not be bound with `--bind-as`, because there is no parquet
+/// type their values could be written as, or because they are deprecated aliases.
+const UNSUPPORTED_C_TYPES: &[&str] = &[
+    "SQL_C_DEFAULT",
+    "SQL_C_SHORT",
+    "SQL_C_SSHORT",
+    "SQL_C_USHORT",
+    "SQL_C_LONG",
+    "SQL_C_ULONG",
+    "SQL_C_UBIGINT",
+    "SQL_C_TINYINT",
+    "SQL_C_STINYINT",
+    "SQL_C_UTINYINT",
+    "SQL_C_NUMERIC",
+    "SQL_C_GUID",
+    "SQL_C_DATE",
+    "SQL_C_TIME",
+    "SQL_C_TIMESTAMP",
+    "SQL_C_TYPE_TIME",
+];
+
+/// Parsed value of the `--bind-as` command line option.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindAs {
+    pub column_name: String,
+    pub c_type: BindCType,
+}
+
+/// Parses the `--bind-as` command line option in format `COLUMN=C_TYPE`. The C type is case
+/// insensitive and the `SQL_C_` prefix is optional.
+pub fn bind_as_from_str(source: &str) -> Result<BindAs, Error> {
+    let (column_name, c_type) = source
+        .rsplit_once('=')
+        .ok_or_else(|| anyhow!("Value must be specified in format: 'COLUMN=C_TYPE'"))?;
+    if column_name.is_empty() {
+        bail!("Column name must not be empty.")
+    }
+    let mut name = c_type.trim().to_uppercase();
+    if !name.starts_with("SQL_C_") {
+        name.insert_str(0, "SQL_C_");
+    }
+    let supported = || {
+        BindCType::ALL
+            .iter()
+            .map(|c_type| c_type.name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let Some(c_type) = BindCType::ALL
+        .into_iter()
+        .find(|c_type| c_type.name() == name)
+    else {
+        if UNSUPPORTED_C_TYPES.contains(&name.as_str()) || name.starts_with("SQL_C_INTERVAL_") {
+            bail!(
+                "No conversion into a parquet type is defined for C type '{name}'. Supported C \
+                types are: {}",
+                supported()
+            )
+        }
+        bail!(
+            "'{c_type}' is not a known ODBC C type. Supported C types are: {}",
+            supported()
+        )
+    };
+    Ok(BindAs {
+        column_name: column_name.to_owned(),
+        c_type,
+    })
+}
+
+/// Parsed value of the `--group-columns` command line option.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnGroup {
+    /// Name of the group in the output.
+    pub name: String,
+    /// Names of the columns nested in the group, in the order they appear within it.
+    pub columns: Vec<String>,
+}
+
+/// Parses the `--group-columns` command line option in format `NAME=COLUMN1,COLUMN2,...`.
+pub fn column_group_from_str(source: &str) -> Result<ColumnGroup, Error> {
+    let (name, columns) = source
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Value must be specified in format: 'NAME=COLUMN1,COLUMN2,...'"))?;
+    if name.is_empty() {
+        bail!("Group name must not be empty.")
+    }
+    let columns: Vec<String> = columns.split(',').map(str::to_owned).collect();
+    if columns.iter().any(String::is_empty) {
+        bail!("Column names of group '{name}' must not be empty.")
+    }
+    if let Some(column) = columns
+        .iter()
+        .enumerate()
+        .find_map(|(index, column)| columns[..index].contains(column).then_some(column))
+    {
+        bail!("Column '{column}' is listed more than once in group '{name}'.")
+    }
+    Ok(ColumnGroup {
+        name: name.to_owned(),
+        columns,
+    })
+}
+
+/// Parsed value of the `--sorted-by` command line option.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortedBy {
+    pub column_name: String,
+    pub descending: bool,
+    pub nulls_first: bool,
+}
+
+impl SortedBy {
+    /// Resolves the column name against the leaf columns of the schema, so columns nested with
+    /// `--group-columns` are found by their own name.
+    pub fn to_sorting_column(&self, schema: &Type) -> Result<SortingColumn, Error> {
+        let column_idx = SchemaDescriptor::new(Arc::new(schema.clone()))
+            .columns()
+            .iter()
+            .position(|column| column.name() == self.column_name)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Column '{}' specified in `--sorted-by` is not part of the result set.",
+                    self.column_name
+                )
+            })?;
+        Ok(SortingColumn::new(
+            column_idx.try_into().unwrap(),
+            self.descending,
+            self.nulls_first,
+        ))
+    }
+}
+
+/// Parses `COLUMN[:asc|desc][:nulls-first|nulls-last]`. Default is ascending with nulls last.
+pub fn sorted_by_from_str(source: &str) -> Result<SortedBy, Error> {
+    let mut column_name = source;
+    let mut descending = None;
+    let mut nulls_first = None;
+    // Consume known modifiers from the end, so column names containing colons still work.
+    while let Some(pos) = column_name.rfind(':') {
+        let modifier = &column_name[(pos + 1)..];
+        match modifier {
+            "asc" if descending.is_none() => descending = Some(false),
+            "desc" if descending.is_none() => descending = Some(true),
+            "nulls-first" if descending.is_none() && nulls_first.is_none() => {
+                nulls_first = Some(true)
+            }
+            "nulls-last" if descending.is_none() && nulls_first.is_none() => {
+                nulls_first = Some(false)
+            }
+            _ => break,
+        }
+        column_name = &column_name[..pos];
+    }
+    if column_name.is_empty() {
+        bail!(
+            "Sorted by must be passed in format: 'COLUMN_NAME[:asc|desc][:nulls-first|nulls-last]'"
+        )
+    }
+    Ok(SortedBy {
+        column_name: column_name.to_owned(),
+        descending: descending.unwrap_or(false),
+        nulls_first: nulls_first.unwrap_or(false),
+    })
+}
+
+/// Parsed value of the `--ts-tz-from` command line option.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimestampTzFrom {
+    /// Column holding timestamps in local time.
+    pub timestamp_column: String,
+    /// Column holding the IANA name of the time zone for each row, e.g. `America/New_York`.
+    pub tz_column: String,
+}
+
+/// Parses the `--ts-tz-from` command line option in format `TIMESTAMP_COLUMN:TZ_COLUMN`.
+pub fn timestamp_tz_from_str(source: &str) -> Result<TimestampTzFrom, Error> {
+    let (timestamp_column, tz_column) = source.rsplit_once(':').ok_or_else(|| {
+        anyhow!("Value must be specified in format: 'TIMESTAMP_COLUMN:TZ_COLUMN'")
+    })?;
+    if timestamp_column.is_empty() || tz_column.is_empty() {
+        bail!("Column names must not be empty.")
+    }
+    if timestamp_column == tz_column {
+        bail!("Timestamp and time zone column must differ.")
+    }
+    Ok(TimestampTzFrom {
+        timestamp_column: timestamp_column.to_owned(),
+        tz_column: tz_column.to_owned(),
+    })
+}
+
+/// Granularity of the partitions written with `--partition-by-date`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DateGranularity {
+    /// Partitions named like `dt=2024-01-15`.
+    Day,
+    /// Partitions named like `dt=2024-01`.
+    Month,
+    /// Partitions named like `dt=2024`.
+    Year,
+}
+
+/// Date or timestamp column the output is partitioned by. See `--partition-by-date`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionByDate {
+    pub column: String,
+    pub granularity: DateGranularity,
+}
+
+/// Parses the `--partition-by-date` command line option in format `COLUMN:GRANULARITY`.
+pub fn partition_by_date_from_str(source: &str) -> Result<PartitionByDate, Error> {
+    let (column, granularity) = source
+        .rsplit_once(':')
+        .ok_or_else(|| anyhow!("Value must be specified in format: 'COLUMN:GRANULARITY'"))?;
+    if column.is_empty() {
+        bail!("Column name must not be empty.")
+    }
+    let granularity = DateGranularity::from_str(granularity, true).map_err(|_| {
+        anyhow!("Unknown granularity '{granularity}'. Must be one of 'day', 'month' or 'year'.")
+    })?;
+    Ok(PartitionByDate {
+        column: column.to_owned(),
+        granularity,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{
+        also_write_from_str, bind_as_from_str, column_group_from_str, column_value_from_str,
+        extension_from_str, partition_by_date_from_str, sorted_by_from_str, timestamp_tz_from_str,
+        type_rule_from_str, BindAs, BindCType, ColumnGroup, CompressionVariants, DateGranularity,
+        PartitionByDate, SortedBy, TimestampTzFrom, TypeRule, TypeRuleTarget,
+    };
+
+    #[test]
+    fn parse_column_group() {
+        assert_eq!(
+            ColumnGroup {
+                name: "address".to_owned(),
+                columns: vec!["street".to_owned(), "city".to_owned()]
+            },
+            column_group_from_str("address=street,city").unwrap()
+        );
+        assert!(column_group_from_str("address").is_err());
+        assert!(column_group_from_str("=street").is_err());
+        assert!(column_group_from_str("address=street,").is_err());
+        assert!(column_group_from_str("address=street,street").is_err());
+    }
+
+    #[test]
+    fn parse_partition_by_date() {
+        assert_eq!(
+            PartitionByDate {
+                column: "created_at".to_owned(),
+                granularity: DateGranularity::Month
+            },
+            partition_by_date_from_str("created_at:month").unwrap()
+        );
+        assert!(partition_by_date_from_str("created_at").is_err());
+        assert!(partition_by_date_from_str(":day").is_err());
+        assert!(partition_by_date_from_str("created_at:week").is_err());
+    }
+
+    #[test]
+    fn parse_timestamp_tz_from() {
+        assert_eq!(
+            TimestampTzFrom {
+                timestamp_column: "created_at".to_owned(),
+                tz_column: "tz".to_owned()
+            },
+            timestamp_tz_from_str("created_at:tz").unwrap()
+        );
+        assert!(timestamp_tz_from_str("created_at").is_err());
+        assert!(timestamp_tz_from_str("created_at:").is_err());
+        assert!(timestamp_tz_from_str("tz:tz").is_err());
+    }
+
+    #[test]
+    fn parse_bind_as() {
+        let expected = BindAs {
+            column_name: "amount".to_owned(),
+            c_type: BindCType::Char,
+        };
+        assert_eq!(expected, bind_as_from_str("amount=SQL_C_CHAR").unwrap());
+        assert_eq!(expected, bind_as_from_str("amount=char").unwrap());
+        assert_eq!(
+            BindCType::TypeTimestamp,
+            bind_as_from_str("a=b=sql_c_type_timestamp").unwrap().c_type
+        );
+        let error = bind_as_from_str("amount=SQL_C_NUMERIC").unwrap_err();
+        assert!(error.to_string().starts_with(
+            "No conversion into a parquet type is defined for C type 'SQL_C_NUMERIC'"
+        ));
+        assert!(bind_as_from_str("amount=VARCHAR").is_err());
+        assert!(bind_as_from_str("amount").is_err());
+        assert!(bind_as_from_str("=char").is_err());
+    }
+
+    #[test]
+    fn parse_column_value() {
+        assert_eq!(
+            ("category".to_owned(), "a=b".to_owned()),
+            column_value_from_str("category=a=b").unwrap()
+        );
+        assert_eq!(
+            ("count".to_owned(), String::new()),
+            column_value_from_str("count=").unwrap()
+        );
+        assert!(column_value_from_str("count").is_err());
+        assert!(column_value_from_str("=0").is_err());
+    }
+
+    #[test]
+    fn parse_type_rule() {
+        assert_eq!(
+            TypeRule {
+                sql_type: -151,
+                target: TypeRuleTarget::Binary
+            },
+            type_rule_from_str("-151=binary").unwrap()
+        );
+        assert_eq!(
+            TypeRule {
+                sql_type: -151,
+                target: TypeRuleTarget::Text
+            },
+            type_rule_from_str(" -151 = TEXT ").unwrap()
+        );
+        assert!(type_rule_from_str("-151").is_err());
+        assert!(type_rule_from_str("=text").is_err());
+        assert!(type_rule_from_str("40000=text").is_err());
+        assert!(type_rule_from_str("geometry=text").is_err());
+        assert!(type_rule_from_str("-151=uuid").is_err());
+    }
+
+    #[test]
+    fn parse_also_write() {
+        let (codec, path) = also_write_from_str("snappy:C:\\out.par").unwrap();
+        assert!(matches!(codec, CompressionVariants::Snappy));
+        assert_eq!(PathBuf::from("C:\\out.par"), path);
+        assert!(also_write_from_str("out.par").is_err());
+        assert!(also_write_from_str("foo:out.par").is_err());
+    }
+
+    #[test]
+    fn parse_extension() {
+        assert_eq!("parquet", extension_from_str(".parquet").unwrap());
+        assert_eq!("parquet", extension_from_str("parquet").unwrap());
+        assert!(extension_from_str(".").is_err());
+        assert!(extension_from_str("a/b").is_err());
+    }
+
+    #[test]
+    fn parse_sorted_by() {
+        let sorted_by = |column_name: &str, descending, nulls_first| SortedBy {
+            column_name: column_name.to_owned(),
+            descending,
+            nulls_first,
+        };
+        assert_eq!(
+            sorted_by("a", false, false),
+            sorted_by_from_str("a").unwrap()
+        );
+        assert_eq!(
+            sorted_by("a", true, false),
+            sorted_by_from_str("a:desc").unwrap()
+        );
+        assert_eq!(
+            sorted_by("a", false, true),
+            sorted_by_from_str("a:asc:nulls-first").unwrap()
+        );
+        assert_eq!(
+            sorted_by("a:b", true, false),
+            sorted_by_from_str("a:b:desc").unwrap()
+        );
+        assert!(sorted_by_from_str(":desc").is_err());
+    }
+}
@@ -0,0 +1,279 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    ffi::OsString,
+    fs, mem,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context, Error};
+use clap::{parser::ValueSource, ArgAction, Command};
+use serde::Deserialize;
+
+/// Positional arguments of the `query` subcommand, in the order they are passed.
+const POSITIONALS: [&str; 3] = ["output", "query", "parameters"];
+
+/// Value of a key in a job file.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum JobValue {
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<JobValue>),
+    /// Written as `{ env = "NAME" }`. Value of the environment variable `NAME`, so secrets like
+    /// connection strings can be referenced, rather than stored in the job file.
+    Env(EnvReference),
+}
+
+/// Reference to an environment variable, e.g. `{ env = "ORDERS_DB" }`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnvReference {
+    pub env: String,
+}
+
+impl JobValue {
+    /// Command line arguments for this value. One for each element of an array.
+    fn to_arguments(&self, key: &str) -> Result<Vec<String>, Error> {
+        let argument = match self {
+            JobValue::String(text) => text.clone(),
+            JobValue::Integer(integer) => integer.to_string(),
+            JobValue::Float(float) => float.to_string(),
+            JobValue::Boolean(boolean) => boolean.to_string(),
+            JobValue::Env(EnvReference { env: name }) => env::var(name).with_context(|| {
+                format!("Environment variable '{name}' referenced by key '{key}' is not set.")
+            })?,
+            JobValue::Array(elements) => {
+                let mut arguments = Vec::new();
+                for element in elements {
+                    if matches!(element, JobValue::Array(_)) {
+                        bail!("Value of key '{key}' must not contain nested arrays.")
+                    }
+                    arguments.extend(element.to_arguments(key)?);
+                }
+                return Ok(arguments);
+            }
+        };
+        Ok(vec![argument])
+    }
+}
+
+/// Adds the options of the job file passed with `--job-file` to the arguments of the `query`
+/// subcommand. Options specified on the command line take precedence over the ones in the job file.
+/// The positional arguments `output`, `query` and `parameters` of the job file are only used, if
+/// none are passed on the command line. `args` are returned unchanged if no job file is specified.
+pub fn expand_job_file(command: Command, mut args: Vec<OsString>) -> Result<Vec<OsString>, Error> {
+    // Parsing fails later on, with a helpful message, if the arguments are invalid. We only need
+    // to know which options are already present.
+    let Ok(matches) = command
+        .clone()
+        .ignore_errors(true)
+        .try_get_matches_from(&args)
+    else {
+        return Ok(args);
+    };
+    let Some(query_matches) = matches.subcommand_matches("query") else {
+        return Ok(args);
+    };
+    let Some(path) = query_matches.get_one::<PathBuf>("job_file") else {
+        return Ok(args);
+    };
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Could not read job file '{}'.", path.display()))?;
+    let entries = parse_job_file(path, &text)
+        .with_context(|| format!("Invalid job file '{}'.", path.display()))?;
+
+    let query_command = command.find_subcommand("query").unwrap();
+    let from_command_line =
+        |id: &str| query_matches.value_source(id) == Some(ValueSource::CommandLine);
+    let use_positionals = !POSITIONALS
+        .iter()
+        .chain(&["query_text", "parameter"])
+        .any(|&id| from_command_line(id));
+    let mut options = Vec::new();
+    let mut positionals: [Vec<String>; 3] = Default::default();
+    for (key, value) in entries {
+        let long = key.replace('_', "-");
+        if long == "job-file" {
+            bail!("Job files can not refer to other job files.")
+        }
+        let arg = query_command
+            .get_arguments()
+            .find(|arg| {
+                if arg.is_positional() {
+                    arg.get_id() == long.replace('-', "_").as_str()
+                } else {
+                    arg.get_long() == Some(long.as_str())
+                        || arg
+                            .get_all_aliases()
+                            .is_some_and(|aliases| aliases.contains(&long.as_str()))
+                }
+            })
+            .ok_or_else(|| {
+                anyhow!(
+                    "Unknown key '{key}' in job file '{}'. Keys are the long names of the options \
+                    of the `query` subcommand, or one of 'output', 'query' and 'parameters'.",
+                    path.display()
+                )
+            })?;
+        let id = arg.get_id().as_str();
+        if let Some(index) = POSITIONALS.iter().position(|&positional| positional == id) {
+            if use_positionals {
+                positionals[index] = value.to_arguments(&key)?;
+            }
+            continue;
+        }
+        if from_command_line(id) {
+            continue;
+        }
+        match arg.get_action() {
+            ArgAction::SetTrue => match value {
+                JobValue::Boolean(true) => options.push(format!("--{long}")),
+                JobValue::Boolean(false) => (),
+                _ => bail!("Value of key '{key}' must be a boolean."),
+            },
+            _ => options.extend(
+                value
+                    .to_arguments(&key)?
+                    .into_iter()
+                    .map(|argument| format!("--{long}={argument}")),
+            ),
+        }
+    }
+
+    // Without an output, i.e. together with `--output-dir`, the query can not be passed
+    // positionally.
+    if positionals[0].is_empty() {
+        let [_, query, parameters] = mem::take(&mut positionals);
+        let named = query
+            .into_iter()
+            .map(|query| format!("--query={query}"))
+            .chain(
+                parameters
+                    .into_iter()
+                    .map(|value| format!("--parameter={value}")),
+            );
+        options.extend(named);
+    }
+    // Positionals follow a `--`, so queries starting with a comment are not mistaken for options.
+    let separator = args.iter().position(|arg| arg == "--");
+    let positionals: Vec<OsString> = positionals.into_iter().flatten().map(Into::into).collect();
+    match separator {
+        Some(index) => {
+            args.splice(index + 1..index + 1, positionals);
+            args.splice(index..index, options.into_iter().map(Into::into));
+        }
+        None => {
+            args.extend(options.into_iter().map(Into::into));
+            if !positionals.is_empty() {
+                args.push("--".into());
+                args.extend(positionals);
+            }
+        }
+    }
+    Ok(args)
+}
+
+/// Parses the keys of a job file together with their values. Files ending in `.yaml` or `.yml` are
+/// parsed as YAML, all others as TOML. Values are strings, integers, floats, booleans, arrays of
+/// these and `{ env = "NAME" }` to reference environment variables. Tables are not supported.
+pub fn parse_job_file(path: &Path, text: &str) -> Result<Vec<(String, JobValue)>, Error> {
+    let is_yaml = path
+        .extension()
+        .is_some_and(|extension| extension == "yaml" || extension == "yml");
+    // Keys are sorted, the order of the options does not matter.
+    let entries: BTreeMap<String, JobValue> = if is_yaml {
+        serde_yaml::from_str(text)?
+    } else {
+        toml::from_str(text)?
+    };
+    Ok(entries.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{parse_job_file, EnvReference, JobValue};
+
+    fn env(name: &str) -> JobValue {
+        JobValue::Env(EnvReference {
+            env: name.to_owned(),
+        })
+    }
+
+    fn text(value: &str) -> JobValue {
+        JobValue::String(value.to_owned())
+    }
+
+    #[test]
+    fn parse_toml() {
+        let toml = r#"
+# Nightly export of all orders
+connection-string = { env = "ORDERS_DB" }
+output = 'orders.par'
+query = """
+-- All of them
+SELECT * FROM "Orders"
+"""
+batch-size-row = 100_000
+"compression_level" = 3
+read-only = true
+null-token = [
+    "n/a", # trailing comment
+    "é",
+]
+"#;
+        let entries = parse_job_file(Path::new("job.toml"), toml).unwrap();
+        let expected = vec![
+            ("batch-size-row".to_owned(), JobValue::Integer(100_000)),
+            ("compression_level".to_owned(), JobValue::Integer(3)),
+            ("connection-string".to_owned(), env("ORDERS_DB")),
+            (
+                "null-token".to_owned(),
+                JobValue::Array(vec![text("n/a"), text("é")]),
+            ),
+            ("output".to_owned(), text("orders.par")),
+            (
+                "query".to_owned(),
+                text("-- All of them\nSELECT * FROM \"Orders\"\n"),
+            ),
+            ("read-only".to_owned(), JobValue::Boolean(true)),
+        ];
+        assert_eq!(expected, entries);
+    }
+
+    #[test]
+    fn parse_yaml() {
+        let yaml = "
+connection-string: { env: ORDERS_DB }
+query: |
+  SELECT * FROM Orders
+sorted-by: [a, 'b:desc']
+row-groups-per-file: 2
+";
+        let entries = parse_job_file(Path::new("job.yml"), yaml).unwrap();
+        let expected = vec![
+            ("connection-string".to_owned(), env("ORDERS_DB")),
+            ("query".to_owned(), text("SELECT * FROM Orders\n")),
+            ("row-groups-per-file".to_owned(), JobValue::Integer(2)),
+            (
+                "sorted-by".to_owned(),
+                JobValue::Array(vec![text("a"), text("b:desc")]),
+            ),
+        ];
+        assert_eq!(expected, entries);
+    }
+
+    #[test]
+    fn reject_invalid_job_files() {
+        let error = parse_job_file(Path::new("job.toml"), "a = 1\n\nb = unquoted\n").unwrap_err();
+        assert!(error.to_string().contains("line 3"), "{error}");
+        assert!(parse_job_file(Path::new("job.toml"), "a = 1\na = 2").is_err());
+        assert!(parse_job_file(Path::new("job.toml"), "[query]\na = 1").is_err());
+        assert!(parse_job_file(Path::new("job.toml"), "a = { env = \"A\", b = 1 }").is_err());
+        assert!(parse_job_file(Path::new("job.toml"), "a = \"open").is_err());
+    }
+}
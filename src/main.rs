@@ -1,22 +1,46 @@
+mod column_profile;
+mod credential_helper;
+mod diagnostics;
 mod enum_args;
 mod insert;
+mod job_file;
 mod parquet_buffer;
 mod query;
+mod self_test;
 
-use crate::enum_args::{column_encoding_from_str, EncodingArgument};
+use crate::{
+    credential_helper::Credentials,
+    diagnostics::{abort_on_warning, check_warnings, report_diagnostics},
+    enum_args::{
+        also_write_from_str, bind_as_from_str, column_encoding_from_str, column_group_from_str,
+        column_max_length_from_str, column_value_from_str, extension_from_str,
+        partition_by_date_from_str, sorted_by_from_str, timestamp_tz_from_str, type_rule_from_str,
+        BadRowAction, BindAs, CatalogFunction, ColumnGroup, CsvHeader, CsvQuote, DateOutOfRange,
+        DdlDialect, DecimalPrecisionCheck, DecimalRounding, EncodingArgument, JsonDecimal,
+        JsonNulls, OnMaxFiles, OutputFormat, PartitionByDate, SchemaMatchBy, SortedBy,
+        TimestampTzFrom, TypeRule, UnknownTimeZone,
+    },
+    job_file::expand_job_file,
+    query::{derive_from_str, Derivation, MaxFileCountReached, Quirk},
+};
 use anyhow::{bail, Error};
 use bytesize::ByteSize;
 use enum_args::CompressionVariants;
 use io_arg::IoArg;
 use odbc_api::{
-    escape_attribute_value, handles::OutputStringBuffer, Connection, ConnectionOptions,
-    DriverCompleteOption, Environment,
+    escape_attribute_value, handles::OutputStringBuffer, sys::AttrConnectionPooling, Connection,
+    ConnectionOptions, DriverCompleteOption, Environment,
 };
 use parquet::basic::Encoding;
-use std::{fs::File, path::PathBuf};
+use std::{
+    env,
+    fs::File,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
 use stderrlog::ColorChoice;
 
-use clap::{ArgAction, Args, CommandFactory, Parser};
+use clap::{ArgAction, ArgGroup, Args, CommandFactory, Parser};
 use clap_complete::{generate, Shell};
 
 /// Query an ODBC data source at store the result in a Parquet file.
@@ -41,6 +65,35 @@ struct Cli {
     /// If not specified the tool will try to emit Colors, but not force it. If `TERM=dumb` or
     /// `NO_COLOR` is defined, then colors will not be used.
     no_color: bool,
+    /// Enable driver aware connection pooling of the ODBC driver manager. Connections are then
+    /// returned to a pool, rather than closed, and reused if a matching connection is opened again
+    /// by the same process. Pools do not outlive the process. Independent of this flag, all
+    /// statements of one invocation (e.g. the count query of `--verify-row-count`) share a single
+    /// connection. Pooling requires the ODBC driver to be fully thread safe and connections to not
+    /// have thread affinity, since the driver manager may use and close pooled connections from
+    /// threads of its own.
+    #[arg(long)]
+    connection_pooling: bool,
+    /// If the command fails, write the error and the diagnostic records of all ODBC errors which
+    /// caused it (SQLSTATE, native error and message of each record) as JSON into this file.
+    /// Independent of this option, the records are listed after the error message, if there is
+    /// more than one.
+    #[arg(long)]
+    error_log: Option<PathBuf>,
+    /// Fail on the first warning, with the warning as error, instead of continuing. Covers every
+    /// message logged at warning level, whether it is printed or not (`--quiet`), e.g.: values
+    /// written as NULL by `--skip-bad-rows`, truncated values, decimals violating their precision
+    /// with `--decimal-validate-precision warn`, text with invalid UTF-8, implicit conversions
+    /// reported by `--warn-on-implicit-conversion`, decimals fetched as `DOUBLE` losing digits,
+    /// columns skipped by `--skip-columns-on-error`, a schema differing from `--schema-cache`,
+    /// reaching `--max-file-count` with `--on-max-files merge`, catalog comments which could not
+    /// be fetched, batch sizes rejected by the driver, and deadlock retries. It also covers
+    /// diagnostic records reported by the ODBC driver for calls which succeeded with info, e.g.
+    /// string data right truncation (SQLSTATE 01004), except for SQLSTATE 01000, which drivers use
+    /// for informational messages like a changed database context. The export is checked before
+    /// each batch is fetched and once all rows are written, so the output may be incomplete.
+    #[arg(long)]
+    abort_on_warning: bool,
     #[command(subcommand)]
     command: Command,
 }
@@ -50,7 +103,14 @@ enum Command {
     /// Query a data source and write the result as parquet.
     Query {
         #[clap(flatten)]
-        query_opt: QueryOpt,
+        query_opt: Box<QueryOpt>,
+    },
+    /// Prepare a parameterized query without executing it and print the SQL type, size and
+    /// nullability the driver describes for each placeholder (`?`). Useful to figure out which
+    /// parameter values the driver expects.
+    DescribeParameters {
+        #[clap(flatten)]
+        describe_opt: DescribeParametersOpt,
     },
     /// List available drivers and their attributes.
     ListDrivers,
@@ -61,6 +121,13 @@ enum Command {
         #[clap(flatten)]
         insert_opt: InsertOpt,
     },
+    /// Export a value of each supported SQL type through the full fetch and write path, and
+    /// compare the parquet output with the values fetched as text. Reports a result for each
+    /// type and fails if any type fails. Useful as a smoke test for a new driver or database.
+    SelfTest {
+        #[clap(flatten)]
+        self_test_opt: SelfTestOpt,
+    },
     /// Generate shell completions
     Completions {
         #[arg(long, short = 'o', default_value = ".")]
@@ -72,7 +139,7 @@ enum Command {
 }
 
 /// Command line arguments used to establish a connection with the ODBC data source
-#[derive(Args)]
+#[derive(Args, Clone)]
 struct ConnectOpts {
     #[arg(long, conflicts_with = "dsn")]
     /// Prompts the user for missing information from the connection string. Only supported on
@@ -96,18 +163,44 @@ struct ConnectOpts {
     /// password is going to be appended at the end of it as the `PWD` attribute.
     #[arg(long, short = 'p', env = "ODBC_PASSWORD", hide_env_values = true)]
     password: Option<String>,
+    /// Command printing the credentials to connect with, e.g. a wrapper around a secret manager,
+    /// so secrets are neither part of the arguments nor of a job file. The command is executed by
+    /// the shell (`sh -c`, or `cmd /C` on windows) before connecting. It receives the lines
+    /// `dsn=<DSN>` and `user=<USER>` on standard input, for each option specified. It is expected
+    /// to print lines of the form `key=value` to standard output, with the keys `password`,
+    /// `user` and `connection-string`, e.g. `password=secret`. Each key printed replaces the
+    /// respective option. Standard error is passed through. A nonzero exit status aborts the
+    /// run.
+    #[arg(long)]
+    credential_helper: Option<String>,
 }
 
 #[derive(Args)]
+#[command(group(ArgGroup::new("catalog_source").args(["table", "catalog_function"])))]
+#[command(group(ArgGroup::new("destination").args(["output", "output_dir"]).required(true)))]
 pub struct QueryOpt {
     #[clap(flatten)]
     connect_opts: ConnectOpts,
+    /// Read options from a job file, e.g. to keep scheduled exports under version control. The job
+    /// file uses TOML syntax, or YAML if its name ends in `.yaml` or `.yml`. Each key is the long
+    /// name of an option, e.g. `batch-size-row = 100000` or `read-only = true`, or one of the
+    /// positional arguments `output`, `query` and `parameters`. Multi-line strings (`"""..."""`)
+    /// are convenient for queries. Options which can be specified multiple times take an array,
+    /// e.g. `sorted-by = ["a", "b:desc"]`. Secrets can be referenced instead of stored in the file,
+    /// e.g. `connection-string = { env = "ORDERS_DB" }` uses the value of the environment variable
+    /// `ORDERS_DB` (`{ env: ORDERS_DB }` in YAML). Tables (sections) are not supported and unknown
+    /// keys are an error. Options passed on the command line take precedence over the ones in the
+    /// job file. The positional arguments of the job file are only used, if none are passed on the
+    /// command line.
+    #[arg(long)]
+    job_file: Option<PathBuf>,
     /// Size of a single batch in rows. The content of the data source is written into the output
     /// parquet files in batches. This way the content does never need to be materialized completely
     /// in memory at once. If `--batch-size-memory` is not specified this value defaults to 65535.
     /// This avoids issues with some ODBC drivers using 16Bit integers to represent batch sizes. If
     /// `--batch-size-memory` is specified no other limit is applied by default. If both option are
-    /// specified the batch size is the largest possible which satisfies both constraints.
+    /// specified the batch size is the largest possible which satisfies both constraints. Should
+    /// the driver reject the batch size with an error, it is halved until the driver accepts it.
     #[arg(long)]
     batch_size_row: Option<usize>,
     /// Limits the size of a single batch. It does so by calculating the amount of memory each row
@@ -124,11 +217,27 @@ pub struct QueryOpt {
     /// `600Mb` and so on.
     #[arg(long)]
     batch_size_memory: Option<ByteSize>,
+    /// Global cap for the memory used by the buffers of this tool. In addition to the fetch
+    /// buffers accounted for by `--batch-size-memory`, this also takes into account the copies of
+    /// variadic values (text and binary) held while converting them into parquet, assuming the
+    /// maximum element length. If necessary the batch size, and therefore the row group size, is
+    /// reduced to stay within the limit. Any reduction is logged. Buffers are sized once, before
+    /// the first batch is fetched. With `--compression-threads` greater than one, the second fetch
+    /// buffer and the conversion buffer of each thread are accounted for, too. Fetching waits for
+    /// the writing thread to hand back a buffer, so no more than these buffers are in use at any
+    /// time. Memory allocated by the ODBC driver itself is not accounted for. Specified in SI
+    /// units, e.g. `--memory-limit 500MiB`.
+    #[arg(long)]
+    memory_limit: Option<ByteSize>,
     /// Maximum number of batches in a single output parquet file. If this option is omitted or 0 a
     /// single output file is produces. Otherwise each output file is closed after the maximum
     /// number of batches have been written and a new one with the suffix `_n` is started. There n
     /// is the of the produced output file starting at one for the first one. E.g. `out_01.par`,
     /// `out_2.par`, ...
+    ///
+    /// Splitting never reorders rows. Rows are written in the order they are fetched, so each file
+    /// holds a contiguous part of the result set in source order, and the parts follow each other
+    /// in the order of their suffixes. This holds for splitting by `--file-size-threshold`, too.
     #[arg(long, default_value = "0")]
     row_groups_per_file: u32,
     /// Then the size of the currently written parquet files goes beyond this threshold the current
@@ -147,6 +256,17 @@ pub struct QueryOpt {
     /// specified in SI units. E.g. `--file-size-threshold 1GiB`.
     #[arg(long)]
     file_size_threshold: Option<ByteSize>,
+    /// Maximum number of files, if the output is split by `--row-groups-per-file` or
+    /// `--file-size-threshold`. A safety cap against a misestimated result set creating thousands
+    /// of small files. What happens once it is reached is controlled by `--on-max-files`.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    max_file_count: Option<u32>,
+    /// What to do, if the rows do not fit into the files of `--max-file-count`. `stop` fails the
+    /// export with exit status 3 instead of starting another file. The files written so far are
+    /// complete and kept. `merge` keeps writing into the last file, ignoring further split
+    /// boundaries. A warning names the policy which has been triggered.
+    #[arg(long, value_enum, default_value = "stop", requires = "max_file_count")]
+    on_max_files: OnMaxFiles,
     /// You can use this to limit the transfer buffer size which is used for an individual variadic
     /// sized column.
     ///
@@ -166,6 +286,106 @@ pub struct QueryOpt {
     /// setting or determining buffer sizes.
     #[arg(long)]
     column_length_limit: Option<usize>,
+    /// Maximum length of the values of a single text or binary column, in format
+    /// `COLUMN=LENGTH`, e.g. `--column-max-length description=100000`. Overrides
+    /// `--column-length-limit` and `--max-binary-length` for this column, so one column can hold
+    /// large values, while all others are tightly bounded. The length is measured like for
+    /// `--column-length-limit`. Longer values are truncated. The number of truncated values is
+    /// reported per column at the end of the export. Can be specified multiple times, once for
+    /// each column.
+    #[arg(long, value_parser = column_max_length_from_str, action = ArgAction::Append)]
+    column_max_length: Vec<(String, usize)>,
+    /// Upper bound in bytes for values of binary columns (e.g. `VARBINARY` or `BLOB`). The fetch
+    /// buffer is sized accordingly, even if the driver reports a larger or no size at all. Longer
+    /// values are truncated. The number of truncated values is reported per column at the end of
+    /// the export, and each truncated value is logged together with its row at debug level
+    /// (`-vvv`). Also see `--column-length-limit`, which does not report truncation.
+    #[arg(long, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    max_binary_length: Option<usize>,
+    /// Limit the rate at which rows are fetched from the data source, e.g. to export from a
+    /// production database without impacting other workloads. Before fetching the next batch,
+    /// the export waits until the rows fetched so far are within the limit for the time elapsed
+    /// since the start. This trades a longer total runtime for a lower load on the data source.
+    /// Rows are still fetched in batches, so use a smaller `--batch-size-row` to smooth the load
+    /// further.
+    #[arg(long, value_parser = clap::builder::RangedU64ValueParser::<u64>::new().range(1..))]
+    max_rows_per_second: Option<u64>,
+    /// Write a profile of each column as JSON into this file, once the export is finished. For
+    /// each column it contains the number of NULLs, the minimum and maximum (for ordered types) and
+    /// an estimate of the number of distinct values. The profile is accumulated from the values
+    /// written, so it requires no additional pass over the data. Values are rendered in their
+    /// physical representation, like `--stats-only` does, except for decimals, which are rendered
+    /// with their scale. The distinct count is estimated with a HyperLogLog sketch of 4 KiB per
+    /// column, so memory usage does not depend on the number of rows. Its standard error is about
+    /// 1.6%. Small counts (up to a few thousand) are close to exact.
+    #[arg(long)]
+    profile: Option<PathBuf>,
+    /// Count the number of rows for each distinct value of this column while exporting, and print
+    /// the counts to standard error at the end. Useful for sanity checks, e.g. of the balance of
+    /// partitions, without an additional `GROUP BY` query against the data source. Intended for
+    /// columns with few distinct values. Only the first `--group-count-max-values` distinct values
+    /// are tracked, all further ones are counted together as `other`. Values are rendered in their
+    /// physical representation, like with `--profile`. Can be specified multiple times.
+    #[arg(long, action = ArgAction::Append)]
+    group_count: Vec<String>,
+    /// Maximum number of distinct values tracked for each column passed to `--group-count`.
+    #[arg(long, default_value = "100")]
+    group_count_max_values: usize,
+    /// Add up to this many distinct values of each column as examples to the `--profile`, e.g. for
+    /// documenting the schema. Unlike minimum and maximum, samples are rendered according to the
+    /// logical type of the column, the same way `--format ndjson` renders values. E.g. dates are
+    /// ISO 8601 strings and decimals are numbers with their scale. Samples are the first distinct
+    /// values written, and collecting them stops for a column once it has enough.
+    #[arg(long, requires = "profile")]
+    sample_values: Option<usize>,
+    /// Do not add samples for these columns, e.g. because they hold sensitive data. Comma
+    /// separated list of column names. Requires `--sample-values`.
+    #[arg(long, value_delimiter = ',', requires = "sample_values")]
+    no_sample_columns: Vec<String>,
+    /// Once the export succeeded, write the number of rows, the duration of the export, as well as
+    /// the number of files and bytes written into this file, using the Prometheus text format.
+    /// Point it into the directory of the textfile collector of the node exporter to monitor
+    /// scheduled exports. The file is replaced atomically, so it is never read partially written.
+    #[arg(long)]
+    metrics_file: Option<PathBuf>,
+    /// Value of the `job` label attached to each metric in `--metrics-file`. Note that Prometheus
+    /// renames it to `exported_job` unless `honor_labels` is set for the scrape job.
+    #[arg(long, requires = "metrics_file")]
+    metrics_job: Option<String>,
+    /// Write at most this many rows into the output, e.g. to cheaply sample a large table. The
+    /// driver is asked to stop the result set after this many rows, by setting the statement
+    /// attribute `SQL_ATTR_MAX_ROWS`, so the data source does not need to produce more rows than
+    /// written. If the driver does not support the attribute, further rows are still produced by
+    /// the data source, but the export stops once the limit is reached and discards the rows
+    /// beyond. Which of the two applies is logged at info level (`-v`). If the query is executed
+    /// for multiple parameter sets, the limit applies to the output as a whole.
+    #[arg(long, conflicts_with = "verify_row_count")]
+    limit: Option<u64>,
+    /// Append filler rows at the end, until the output holds this many rows. Useful to generate
+    /// fixtures of a fixed size from sources with a varying number of rows. The values of the
+    /// filler rows are specified with `--pad-value`. If the result set already holds more rows,
+    /// nothing is appended, unless `--pad-strict` is set. Filler rows are counted as written, so
+    /// this can not be combined with `--verify-row-count`.
+    #[arg(long, conflicts_with_all = ["verify_row_count", "count_query"])]
+    pad_to_rows: Option<u64>,
+    /// Value of a column in filler rows appended by `--pad-to-rows`. Format is `COLUMN=VALUE`,
+    /// e.g. `--pad-value count=0`. The value is parsed like the ones of `--null-fill`. Columns
+    /// without a value are NULL in filler rows. Can be specified multiple times, once for each
+    /// column.
+    #[arg(
+        long,
+        value_parser = column_value_from_str,
+        action = ArgAction::Append,
+        requires = "pad_to_rows"
+    )]
+    pad_value: Vec<(String, String)>,
+    /// Name of an additional boolean column appended to the output, which is `true` for filler
+    /// rows appended by `--pad-to-rows` and `false` for all other rows.
+    #[arg(long, requires = "pad_to_rows")]
+    pad_flag_column: Option<String>,
+    /// Fail if the result set holds more rows than passed to `--pad-to-rows`.
+    #[arg(long, requires = "pad_to_rows")]
+    pad_strict: bool,
     /// Default compression used by the parquet file writer.
     #[arg(long, value_enum, default_value = "zstd")]
     column_compression_default: CompressionVariants,
@@ -176,6 +396,39 @@ pub struct QueryOpt {
     /// Default compression level for `zstd` is 3
     #[arg(long)]
     column_compression_level_default: Option<u32>,
+    /// Compression used for text columns, overriding `--column-compression-default` for them.
+    /// A column is considered text, if its physical type is `BYTE_ARRAY` or `FIXED_LEN_BYTE_ARRAY`
+    /// and it is annotated as `STRING`, `JSON` or `ENUM`. The compression level is taken from
+    /// `--column-compression-level-default`.
+    #[arg(long, value_enum)]
+    compression_strings: Option<CompressionVariants>,
+    /// Compression used for numeric columns, overriding `--column-compression-default` for them.
+    /// Numeric columns are all columns with a physical type of `BOOLEAN`, `INT32`, `INT64`,
+    /// `INT96`, `FLOAT` or `DOUBLE`, which includes dates, times and timestamps, as well as
+    /// decimals independent of their physical type. The compression level is taken from
+    /// `--column-compression-level-default`.
+    #[arg(long, value_enum)]
+    compression_numeric: Option<CompressionVariants>,
+    /// Compression used for binary columns, overriding `--column-compression-default` for them.
+    /// Binary columns are columns with a physical type of `BYTE_ARRAY` or `FIXED_LEN_BYTE_ARRAY`,
+    /// which are neither text nor decimal. The compression level is taken from
+    /// `--column-compression-level-default`.
+    #[arg(long, value_enum)]
+    compression_binary: Option<CompressionVariants>,
+    /// Number of threads converting, encoding and compressing the columns of a row group. Columns
+    /// are distributed across the threads and written into memory first, before they are appended
+    /// to the output in column order, so the output does not depend on the number of threads.
+    /// With more than one thread, the next batch is fetched while the previous one is written,
+    /// using a second fetch buffer. At most two batches are in flight, one being fetched and one
+    /// being written, so additional memory is bounded by one fetch buffer, the compressed size of
+    /// one row group and one conversion buffer per thread. Speeds up exports of wide tables with
+    /// expensive compression (e.g. high `zstd` levels).
+    #[arg(
+        long,
+        default_value = "1",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    compression_threads: usize,
     /// Encoding used for character data requested from the data source.
     ///
     /// `Utf16`: The tool will use 16Bit characters for requesting text from the data source,
@@ -193,8 +446,42 @@ pub struct QueryOpt {
     encoding: EncodingArgument,
     /// Map `BINARY` SQL columns to `BYTE_ARRAY` instead of `FIXED_LEN_BYTE_ARRAY`. This flag has
     /// been introduced in an effort to increase the compatibility of the output with Apache Spark.
+    /// Without it `BINARY(n)` is written as `FIXED_LEN_BYTE_ARRAY(n)`, and values shorter than `n`,
+    /// which some drivers return unpadded, are padded with trailing zero bytes.
     #[clap(long)]
     prefer_varbinary: bool,
+    /// Fetch every column as text and write it as a UTF-8 column, regardless of its type. The size
+    /// of the buffers is derived from the display size reported by the driver. All type information
+    /// is lost in the output. This is a last resort to get data out of a driver misbehaving when
+    /// binding typed buffers, and a baseline for diagnosing problems with type mappings.
+    #[clap(long)]
+    fetch_as_text_all: bool,
+    /// Write values of fixed width character columns (`CHAR`, `NCHAR`) which are empty or consist
+    /// only of whitespace as NULL. In these columns an empty value is padded with spaces and can
+    /// not be told apart from a value consisting of spaces. This matches the semantics of Oracle,
+    /// which treats empty strings as NULL. Columns which are declared not nullable are not
+    /// affected.
+    #[clap(long)]
+    char_blank_as_null: bool,
+    /// Comma separated list of text values, which are written as NULL, e.g. `NULL,N/A,\N`. Useful
+    /// for sources which encode missing values as sentinel strings. Applies to character columns.
+    /// Tokens are compared with the complete value as fetched from the data source, before any
+    /// other text transformation like `--char-blank-as-null`. Comparison is case sensitive, unless
+    /// `--null-string-tokens-ignore-case` is set. Columns which are declared not nullable are not
+    /// affected.
+    #[arg(long, value_delimiter = ',')]
+    null_string_tokens: Vec<String>,
+    /// Compare values with `--null-string-tokens` ignoring case.
+    #[arg(long, requires = "null_string_tokens")]
+    null_string_tokens_ignore_case: bool,
+    /// Write character data as raw bytes, exactly as received from the data source, instead of
+    /// transcoding it into UTF-8. Text columns are written as `BYTE_ARRAY` without a `UTF8`
+    /// annotation and the name passed to this option (e.g. `latin1`) is recorded in the file
+    /// metadata under the key `odbc2parquet.charset`. Useful for archiving data, where transcoding
+    /// would be lossy and decoding should be deferred. Text is always fetched using narrow (8-Bit)
+    /// characters in this mode, so it conflicts with `--encoding`.
+    #[arg(long, conflicts_with = "encoding")]
+    preserve_charset: Option<String>,
     /// Specify the fallback encoding of the parquet output column. You can parse multiple values
     /// in format `COLUMN:ENCODING`. `ENCODING` must be one of: `plain`, `delta-binary-packed`,
     /// `delta-byte-array`, `delta-length-byte-array` or `rle`.
@@ -204,6 +491,50 @@ pub struct QueryOpt {
         action = ArgAction::Append
     )]
     parquet_column_encoding: Vec<(String, Encoding)>,
+    /// Separator used to split column names passed to per column options (e.g.
+    /// `--parquet-column-encoding`) into the components of a nested column path. E.g. with `.`,
+    /// `a.b` addresses the leaf `b` of the group `a`. Prefix the separator with a backslash to use
+    /// it literally, e.g. `a\.b` addresses a column named `a.b`. If not specified, column names are
+    /// never split, so names containing dots can be used verbatim.
+    #[arg(long)]
+    column_path_separator: Option<String>,
+    /// Declare that the result set is sorted by a column, e.g. because the query has an `ORDER BY`
+    /// clause. This is recorded as sorting column metadata in each row group, so query engines can
+    /// leverage it. Pass `COLUMN[:asc|desc][:nulls-first|nulls-last]`. Default is ascending with
+    /// nulls last. Repeat the option for a sort key of multiple columns. The ordering is not
+    /// verified, so it is on you to ensure it matches the query.
+    #[arg(
+        long,
+        value_parser=sorted_by_from_str,
+        action = ArgAction::Append
+    )]
+    sorted_by: Vec<SortedBy>,
+    /// Maximum number of rows in each data page. Smaller pages allow readers using the page index
+    /// to skip more data for selective queries, at the cost of a larger file. If not specified the
+    /// default of the parquet writer (20000 rows) applies. Values are handed to the parquet writer
+    /// in batches of at most this many rows, so the limit is met, unless filler rows of
+    /// `--pad-to-rows` are appended to a page.
+    #[arg(long, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    page_row_count_limit: Option<usize>,
+    /// Write the page index into the footer of each file. It consists of the column index, holding
+    /// minimum, maximum and null count of each data page, and the offset index, holding the
+    /// location and first row of each data page. Query engines use it to skip pages during
+    /// selective scans, e.g. of sorted columns. On by default. Pass `--page-index=false` to omit
+    /// the column index and only collect statistics for each column chunk. The offset index is
+    /// always written.
+    #[arg(
+        long,
+        default_value_t = true,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        action = ArgAction::Set
+    )]
+    page_index: bool,
+    /// Maximum length in bytes of the minimum and maximum values of binary and text columns in the
+    /// column index. Longer values are truncated. Default is 64.
+    #[arg(long, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    column_index_truncate_length: Option<usize>,
     /// Tells the odbc2parquet, that the ODBC driver does not support binding 64-Bit integers (aka
     /// S_C_BIGINT in ODBC speak). This will cause the odbc2parquet to query large integers as text
     /// instead and convert them to 64-Bit integers itself. Setting this flag will not affect the
@@ -211,6 +542,13 @@ pub struct QueryOpt {
     /// can make queries work which did not before, because Oracle does not support 64-Bit integers.
     #[clap(long)]
     driver_does_not_support_64bit_integers: bool,
+    /// `odbc2parquet` detects the database management system it is connected to and automatically
+    /// applies workarounds for known quirks of it. E.g. Oracle is known to not support 64-Bit
+    /// integers. Use this option to opt out of such an automatically applied workaround. The
+    /// detected system and applied workarounds are logged in verbose mode (`-v`). May be specified
+    /// multiple times.
+    #[arg(long, value_enum, action = ArgAction::Append)]
+    ignore_driver_quirk: Vec<Quirk>,
     /// The IBM DB2 Linux ODBC drivers have been reported to return memory garbage instead of
     /// indicators for the string length. Setting this flag will cause `odbc2parquet` to rely on
     /// terminating zeroes, instead of indicators. This prevents `odbc2parquet` from disambiguating
@@ -219,6 +557,195 @@ pub struct QueryOpt {
     /// on non-window platforms by default, or if the `System` encoding is active.
     #[clap(long)]
     avoid_decimal: bool,
+    /// Store all decimals as `FIXED_LEN_BYTE_ARRAY` of this many bytes, instead of picking the
+    /// smallest physical type for their precision (`INT32`, `INT64` or the shortest possible byte
+    /// array). Values are sign extended to the full width. Useful for consumers which require a
+    /// uniform representation, e.g. `16` for 128-Bit decimals. Must be large enough to hold all
+    /// values of the column's precision. Values exceeding the width are an error, rather than being
+    /// truncated like the ones written without this option. Has no effect if decimals are not
+    /// written as decimals, e.g. due to `--avoid-decimal` or a precision above 38.
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=16))]
+    decimal_byte_length: Option<u8>,
+    /// How to round decimals, whose values have more fractional digits than the scale they are
+    /// written with, e.g. because `--schema-from` declares a smaller scale than the data source.
+    /// `half-up` rounds ties away from zero, `half-even` to the even neighbour (banker's rounding).
+    /// `truncate` drops the excess digits, `floor` and `ceil` round towards negative and positive
+    /// infinity. Applies to decimals fetched as text, i.e. all but those with scale zero and a
+    /// precision up to 18.
+    #[arg(long, value_enum, default_value = "half-up")]
+    decimal_rounding: DecimalRounding,
+    /// Verify that decimals fetched as text have no more digits than the precision declared by
+    /// the data source, e.g. because its metadata understates the actual precision. Without this
+    /// such values are written as they are, violating the precision of the parquet column. `warn`
+    /// writes them anyway, but logs and counts them. `strict` fails the export with an error
+    /// naming the offending value, unless `--skip-bad-rows` is set, in which case it is written
+    /// as NULL. Decimals with scale zero and a precision up to 18 are fetched as integers and are
+    /// not verified.
+    #[arg(long, value_enum)]
+    decimal_validate_precision: Option<DecimalPrecisionCheck>,
+    /// Fetch all `DECIMAL` and `NUMERIC` columns as 64-Bit floating points and write them as
+    /// `DOUBLE`. The driver converts the values, so they are neither fetched as text nor encoded as
+    /// parquet decimals, which is considerably faster for large result sets with many decimal
+    /// columns. This trades precision for speed: a `DOUBLE` represents only about 15 significant
+    /// decimal digits exactly, and fractions like `0.1` are approximated. A warning is emitted for
+    /// every column with a larger precision. Intended for analytics and dashboards, where exact
+    /// values are not required.
+    #[arg(long, conflicts_with_all = ["avoid_decimal", "decimal_byte_length"])]
+    fast_decimals: bool,
+    /// Fetch `DECIMAL` and `NUMERIC` columns with a scale other than zero and a precision up to 38
+    /// as `SQL_C_NUMERIC` struct, rather than as text. The driver converts the values into a binary
+    /// mantissa, which is written without parsing text. Columns the driver refuses to bind this way
+    /// are fetched as text, with a warning. Not every driver fills the struct correctly (e.g.
+    /// SQLite leaves it zeroed), so compare the output against an export without this flag before
+    /// relying on it. `--decimal-rounding` and `--decimal-validate-precision` do not apply to
+    /// these columns.
+    #[arg(long, conflicts_with_all = ["avoid_decimal", "fast_decimals"])]
+    numeric_decimals: bool,
+    /// Write these integer columns as `BOOLEAN`, rather than as integers. Zero is written as
+    /// `false`, any other value as `true`. Useful for sources representing booleans as `0` and
+    /// `1`. Pass a comma separated list of column names, e.g. `--coerce-bool-from-int a,b`. Fails
+    /// if one of the columns is not an integer.
+    #[arg(long, value_delimiter = ',')]
+    coerce_bool_from_int: Vec<String>,
+    /// Write these `BIT` columns as 32-Bit integers, rather than as `BOOLEAN`. `true` is written as
+    /// `1` and `false` as `0`. Pass a comma separated list of column names, e.g.
+    /// `--coerce-int-from-bool c,d`. Fails if one of the columns is not a `BIT` column.
+    #[arg(long, value_delimiter = ',')]
+    coerce_int_from_bool: Vec<String>,
+    /// Write zero-length values of binary columns (`BINARY`, `VARBINARY`, ...) as NULL. Useful for
+    /// drivers which report NULLs as zero-length values, or to harmonize the output of sources
+    /// which conflate the two. Applies to binary columns mapped from their column description.
+    /// These columns are declared `OPTIONAL`, even if the driver reports them as not nullable.
+    #[arg(long, conflicts_with = "coerce_null_binary_as_empty")]
+    coerce_empty_binary_as_null: bool,
+    /// Write NULLs of binary columns as zero-length values. The inverse of
+    /// `--coerce-empty-binary-as-null`. Has no effect on binary columns written as
+    /// `FIXED_LEN_BYTE_ARRAY`, since their values can not be empty.
+    #[arg(long)]
+    coerce_null_binary_as_empty: bool,
+    /// Write these binary columns as GeoParquet geometry columns. The values must be Well Known
+    /// Binary (WKB) and are passed through as they are, so select the WKB representation of
+    /// geometry or geography columns, e.g. `ST_AsBinary(geom) AS geom` on PostGIS or
+    /// `geom.STAsBinary() AS geom` on Microsoft SQL Server. Their native binary formats are not
+    /// WKB. The `geo` file metadata describes the columns, the first one being the primary
+    /// column. Pass a comma separated list of column names. Fails if one of the columns is not
+    /// binary.
+    #[arg(long, value_delimiter = ',')]
+    geometry_column: Vec<String>,
+    /// Coordinate reference system of the columns of `--geometry-column` as PROJJSON, written
+    /// into the `geo` metadata as it is. WKB does not carry the SRID of the data source, so it is
+    /// neither detected nor are coordinates transformed. Without this option the CRS is omitted,
+    /// which readers interpret as longitude and latitude on WGS 84 (`OGC:CRS84`). Pass `null` for
+    /// an unknown CRS.
+    #[arg(long, requires = "geometry_column")]
+    geometry_crs: Option<String>,
+    /// Parquet files record the sort order used for the statistics of each column, derived from
+    /// its logical type. By default unsigned `SMALLINT` and `INTEGER` columns are written as
+    /// signed integers, so their statistics are ordered as signed, too. Set this flag to write
+    /// unsigned `SMALLINT` columns as unsigned 16-Bit integers and unsigned `INTEGER` columns as
+    /// `DECIMAL(10,0)`, so minimum and maximum are ordered correctly and values above 2147483647
+    /// can be represented. Old readers may not support unsigned types.
+    #[clap(long)]
+    unsigned_column_order: bool,
+    /// Microsoft SQL Server `DATETIMEOFFSET` columns are normalized to UTC then written to parquet,
+    /// which loses the original offset. Setting this flag emits an additional column named
+    /// `<column>_offset` right after each of them, holding the original offset in minutes east of
+    /// UTC. E.g. `330` for `+05:30` or `-480` for `-08:00`.
+    #[clap(long)]
+    timestamp_tz_offset_column: bool,
+    /// Deprecated: Write timestamps without time zone using the legacy `INT96` physical type
+    /// (nanoseconds of the day and Julian day number), instead of `INT64` timestamps. Only use
+    /// this for old readers, e.g. legacy versions of Spark or Impala, which do not understand
+    /// `INT64` timestamps. `INT96` is deprecated by the parquet format and carries no information
+    /// about the time unit or time zone. Timestamps with time zone are not affected.
+    #[clap(long)]
+    timestamp_int96: bool,
+    /// Like `--timestamp-int96`, but only for the named columns, separated by commas, e.g.
+    /// `--int96-columns created,updated` for a legacy reader of these columns. All other
+    /// timestamps are written as `INT64`. Each column must be a timestamp without time zone, or a
+    /// date with `--date-as-timestamp`.
+    #[arg(long, value_delimiter = ',', conflicts_with = "timestamp_int96")]
+    int96_columns: Vec<String>,
+    /// Write `DATE` columns as timestamps at midnight (`INT64` milliseconds since epoch), instead
+    /// of using the `DATE` logical type (`INT32` days since epoch). For readers which do not
+    /// understand the `DATE` logical type. Like other timestamps without time zone, the values are
+    /// not adjusted to UTC. Combined with `--timestamp-int96` dates are written as `INT96`.
+    /// `--coerce-date-out-of-range` does not apply to these columns.
+    #[clap(long)]
+    date_as_timestamp: bool,
+    /// Path to an existing parquet file. Its schema is enforced on the output, rather than deriving
+    /// the parquet types from the column types reported by the driver. Useful to keep a series of
+    /// exports (e.g. daily partitions) schema identical. Values are converted by the ODBC driver
+    /// into the buffer suitable for the target type. The export fails if a column can not be
+    /// converted into the target type, or if the columns of the result set do not match the
+    /// target schema. Only flat schemas are supported.
+    #[arg(long, conflicts_with_all = ["timestamp_tz_offset_column", "fetch_as_text_all"])]
+    schema_from: Option<PathBuf>,
+    /// How to match the columns of the result set with the columns of the schema specified with
+    /// `--schema-from`. Either by `name` or by `position`. If matched by position, the column
+    /// names of the target schema are used in the output.
+    #[arg(long, value_enum, default_value = "name", requires = "schema_from")]
+    schema_match_by: SchemaMatchBy,
+    /// Write a `CREATE TABLE` statement matching the schema of the output into this file, e.g. to
+    /// register the output in a data catalog. Column types are derived from the parquet types of
+    /// the output. The location of the table is left as a `<location>` placeholder. Requires
+    /// `--table-name`.
+    #[arg(long, requires = "table_name")]
+    emit_ddl: Option<PathBuf>,
+    /// Name of the table in the statement written with `--emit-ddl`.
+    #[arg(long, requires = "emit_ddl")]
+    table_name: Option<String>,
+    /// Dialect of the statement written with `--emit-ddl`.
+    #[arg(long, value_enum, default_value = "hive", requires = "emit_ddl")]
+    ddl_dialect: DdlDialect,
+    /// How to handle dates which can not be represented in the output. E.g. `0000-00-00` as used
+    /// by MySQL, or dates after `9999-12-31`. `error` aborts the export. `null` writes NULL
+    /// instead. `clamp` writes the earliest (`0001-01-01`) or latest (`9999-12-31`) valid date, or
+    /// the closest valid day of the month for dates like `2023-02-30`.
+    #[arg(long, value_enum, default_value = "error")]
+    coerce_date_out_of_range: DateOutOfRange,
+    /// Convert a timestamp column without time zone to UTC, interpreting each value as local time
+    /// of the time zone named in the same row of another column. Format is
+    /// `TIMESTAMP_COLUMN:TZ_COLUMN`, e.g. `--ts-tz-from created_at:tz`. The time zone column must
+    /// be of text type and hold IANA names like `America/New_York` or `UTC`. Time zones are looked
+    /// up in the IANA time zone database built into odbc2parquet, so the conversion does not
+    /// depend on the system it runs on. Local times which occur twice, because
+    /// clocks are set back at the end of daylight saving time, are resolved to the earlier
+    /// instant. Local times skipped when clocks are set forward are converted using the offset in
+    /// effect before the transition. The parquet column is written as timestamp adjusted to UTC.
+    /// The time zone column is still written as is. Can be specified multiple times, once for each
+    /// timestamp column.
+    #[arg(
+        long,
+        alias = "coerce-timestamp-timezone-from-column",
+        value_parser = timestamp_tz_from_str,
+        action = ArgAction::Append
+    )]
+    ts_tz_from: Vec<TimestampTzFrom>,
+    /// How to handle timestamps of `--ts-tz-from` whose time zone column is NULL or names a time
+    /// zone unknown to the time zone database. `error` aborts the export, or writes NULL with
+    /// `--skip-bad-rows`. `null` writes NULL instead, making the column `OPTIONAL`. `assume-utc`
+    /// interprets the timestamp as UTC.
+    #[arg(long, value_enum, default_value = "error", requires = "ts_tz_from")]
+    unknown_timezone: UnknownTimeZone,
+    /// Write the values of a binary column (e.g. stored documents) into separate files, instead of
+    /// embedding them in the parquet output. The parquet column holds the path of each file
+    /// relative to `--blob-dir`, or NULL if the value is NULL. Files are named after the value of
+    /// `--blob-key-column` in the same row and placed in a subdirectory named after the column.
+    /// Characters not safe for file names are replaced by `_`. Should a file of that name already
+    /// exist, a numeric suffix is appended. Values are streamed into the files in chunks, so their
+    /// size is neither limited by `--column-length-limit` nor by memory. In exchange the other
+    /// columns are fetched one row at a time. Most drivers require the extracted columns to follow
+    /// all other columns in the select list. Can be specified multiple times.
+    #[arg(long, action = ArgAction::Append, requires_all = ["blob_dir", "blob_key_column"])]
+    extract_blobs: Vec<String>,
+    /// Directory the files of columns specified with `--extract-blobs` are written to.
+    #[arg(long, requires = "extract_blobs")]
+    blob_dir: Option<PathBuf>,
+    /// Column whose values name the files written for `--extract-blobs`. Must be of integer or
+    /// text type and should be unique.
+    #[arg(long, requires = "extract_blobs")]
+    blob_key_column: Option<String>,
     /// In case fetch results gets split into multiple files a suffix with a number will be appended
     /// to each file name. Default suffix length is 2 leading to suffixes like e.g. `_03`. In case
     /// you would expect thousands of files in your output you may want to set this to say `4` so
@@ -231,18 +758,662 @@ pub struct QueryOpt {
     /// result set is empty you can set this flag.
     #[clap(long)]
     no_empty_file: bool,
+    /// Write only statistics (number of rows, null count, minimum and maximum of each column) for
+    /// each row group as JSON into the output, instead of a parquet file. Useful to profile the
+    /// value distributions of a large table cheaply in terms of disk space. Note that this still
+    /// fetches all rows from the data source. Minimum and maximum are rendered in their physical
+    /// parquet representation, e.g. dates as days since epoch.
+    #[clap(long, conflicts_with_all = ["row_groups_per_file", "file_size_threshold"])]
+    stats_only: bool,
+    /// File format of the output. `csv` and `tsv` write delimited text, one line per row, using
+    /// the same fetching and column mapping as parquet. Values are rendered according to their
+    /// parquet type, e.g. decimals with their scale and dates with `--csv-date-format`. Binary
+    /// values are written as upper case hex. Values containing the delimiter, quotes or line
+    /// breaks are quoted as described in RFC 4180. Lines end with `\n`. Splitting the output with
+    /// `--row-groups-per-file` or `--file-size-threshold` works the same as for parquet, with
+    /// the size referring to the text written before compression.
+    ///
+    /// `ndjson` writes one JSON object per row, keyed by column name. Booleans and integers are
+    /// JSON numbers, decimals depend on `--json-decimal`. Dates, times and timestamps are ISO 8601
+    /// strings like `2024-01-15T10:30:00.5`, timestamps with time zone in UTC with the suffix
+    /// `Z`. Binary values are base64 strings. The `--csv-*` options do not apply.
+    #[arg(
+        long,
+        value_enum,
+        default_value = "parquet",
+        conflicts_with_all = [
+            "stats_only", "also_write", "emit_ddl", "emit_hadoop_crc", "round_trip_check"
+        ]
+    )]
+    format: OutputFormat,
+    /// Separates the values of a line in delimited text output. Defaults to `,` for `csv` and to
+    /// a tab for `tsv`.
+    #[arg(long)]
+    csv_delimiter: Option<char>,
+    /// When to put values of delimited text output in quotes.
+    #[arg(long, value_enum, default_value = "necessary")]
+    csv_quote: CsvQuote,
+    /// Written for NULL in delimited text output. Values equal to it are quoted, unless
+    /// `--csv-quote` is `never`, so they can be told apart from NULL.
+    #[arg(long, default_value = "")]
+    csv_null: String,
+    /// Which files of delimited text output start with a line holding the column names. If the
+    /// output is split, the header is repeated in every file by default.
+    #[arg(long, value_enum, default_value = "every-file")]
+    csv_header: CsvHeader,
+    /// Format of dates in delimited text output, using the `strftime` syntax of chrono.
+    #[arg(long, default_value = "%Y-%m-%d")]
+    csv_date_format: String,
+    /// Format of times in delimited text output, using the `strftime` syntax of chrono.
+    #[arg(long, default_value = "%H:%M:%S%.f")]
+    csv_time_format: String,
+    /// Format of timestamps in delimited text output, using the `strftime` syntax of chrono.
+    /// Timestamps with time zone are rendered in UTC, so `%z` renders `+0000` for them.
+    #[arg(long, default_value = "%Y-%m-%d %H:%M:%S%.f")]
+    csv_timestamp_format: String,
+    /// Separates integer and fractional digits of decimals in delimited text output.
+    #[arg(long, default_value = ".")]
+    csv_decimal_separator: char,
+    /// How decimals are written by `--format ndjson`. `number` writes JSON numbers, `string`
+    /// writes JSON strings, preserving all digits for readers which parse numbers as doubles.
+    #[arg(long, value_enum, default_value = "number")]
+    json_decimal: JsonDecimal,
+    /// How NULLs are written by `--format ndjson`. `null` writes the key with the value `null`,
+    /// `omit` leaves the key out of the object.
+    #[arg(long, value_enum, default_value = "null")]
+    json_nulls: JsonNulls,
+    /// Compress text output of `--format csv`, `tsv` or `ndjson` as a whole, e.g. for `.gz` or
+    /// `.zst` files. Only `uncompressed`, `gzip` and `zstd` are supported. The level is taken
+    /// from `--column-compression-level-default`.
+    #[arg(long, value_enum, default_value = "uncompressed")]
+    text_compression: CompressionVariants,
+    /// Declare columns `REQUIRED`, if they turn out not to contain any NULL, rather than relying
+    /// on the nullability reported by the driver. Some drivers report every column as nullable.
+    /// Since the schema of a parquet file can only be changed by encoding its pages anew, each file
+    /// is written as usual first and rewritten once all rows have been fetched. This requires
+    /// about twice the time to write and, for a short time, the disk space of the file twice.
+    /// The schema cache and DDL (`--schema-cache`, `--emit-ddl`) still reflect the nullability
+    /// reported by the driver. Columns nested in an optional group stay optional. Requires the
+    /// output to be a parquet file, i.e. not standard out or a named pipe.
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "stats_only", "also_write", "emit_hadoop_crc", "emit_stats_parquet"
+        ]
+    )]
+    column_nullable_from_data: bool,
+    /// Declare every column of the result set `OPTIONAL`, regardless of the nullability reported
+    /// by the driver. Useful if the downstream schema is uniformly nullable. Columns of
+    /// `--null-fill` are still written as `REQUIRED`.
+    #[arg(long, conflicts_with_all = ["force_required_all", "column_nullable_from_data"])]
+    force_nullable_all: bool,
+    /// Declare every column of the result set `REQUIRED`, regardless of the nullability reported
+    /// by the driver. The export fails at the first NULL, reporting column, batch and row, unless
+    /// the column has a fill value given by `--null-fill`. Derived columns (`--derive`) stay
+    /// `OPTIONAL`.
+    #[arg(long, conflicts_with = "column_nullable_from_data")]
+    force_required_all: bool,
+    /// Sort the result set by these columns, separated by commas, so each run of the same export
+    /// writes the rows in the same order, e.g. to compare exports of an unordered source. The
+    /// query is wrapped as `SELECT * FROM (<query>) t ORDER BY <columns>`, with the column names
+    /// quoted for the database. Some databases, e.g. Microsoft SQL Server, reject an `ORDER BY`
+    /// within the wrapped query. Rows with equal values are still returned in any order, so list
+    /// enough columns to identify each row. This is independent of `--sorted-by`, which only
+    /// declares the order in the metadata.
+    #[arg(long, value_delimiter = ',', conflicts_with = "catalog_function")]
+    stable_sort: Vec<String>,
+    /// Sort by the columns of `--stable-sort` after fetching, rather than in the database. Useful
+    /// if the database can not sort the result set cheaply. Each row group is sorted in memory and
+    /// spilled into a temporary file next to the output, before the sorted row groups are merged
+    /// back into the output files. Sorting holds one row group decoded in memory, so its memory
+    /// is capped by `--batch-size-row` and `--batch-size-memory`. Merging holds 1024 rows of each
+    /// row group in memory, so it requires more memory the more row groups are written. The disk
+    /// space of the output is required twice, and each row is written twice more. Rows with equal
+    /// keys keep the order they have been fetched in. NULL is ordered before any other value.
+    /// Text is ordered by its UTF-8 bytes, not by the collation of the database. Requires the
+    /// output to be a parquet file, i.e. not standard out or a named pipe.
+    #[arg(
+        long,
+        requires = "stable_sort",
+        conflicts_with_all = [
+            "stats_only", "also_write", "emit_hadoop_crc", "emit_stats_parquet"
+        ]
+    )]
+    stable_sort_client: bool,
+    /// Write only the rows which changed compared to a previous export of the same query, e.g. to
+    /// turn full exports into incremental ones. Rows are identified by the columns of
+    /// `--key-column` and compared using a hash of all their other columns. The previous export
+    /// must be a single parquet file holding all rows, with every column of the current result
+    /// set and of the same physical type. The output holds inserted and updated rows, followed by
+    /// one row for each key missing in the current result set, whose other columns are NULL. An
+    /// additional column (see `--delta-op-column`) marks each row with `I` (inserted), `U`
+    /// (updated) or `D` (deleted), and all columns but the keys are declared `OPTIONAL`. The
+    /// output is written as usual first and rewritten once all rows have been fetched. The keys of
+    /// the previous export, together with the keys of inserted rows, are held in memory, unless
+    /// `--delta-sorted` is specified. Keys must identify a single row in both exports. Requires the
+    /// output to be a single parquet file.
+    #[arg(
+        long,
+        value_name = "FILE",
+        requires = "key_column",
+        conflicts_with_all = [
+            "row_groups_per_file", "file_size_threshold", "no_empty_file", "stats_only",
+            "also_write", "emit_ddl", "emit_hadoop_crc", "emit_stats_parquet", "schema_cache",
+            "column_nullable_from_data", "round_trip_check", "parameter_file", "partition_by_date"
+        ]
+    )]
+    delta_against: Option<PathBuf>,
+    /// Columns identifying a row for `--delta-against`, separated by commas.
+    #[arg(long, value_delimiter = ',', requires = "delta_against")]
+    key_column: Vec<String>,
+    /// Name of the column marking each row of `--delta-against` as inserted, updated or deleted.
+    #[arg(long, default_value = "op", requires = "delta_against")]
+    delta_op_column: String,
+    /// Compare with `--delta-against` in a single pass over both exports, rather than holding the
+    /// keys of the previous export in memory. Both exports must be sorted by the columns of
+    /// `--key-column` in the order given, e.g. by an `ORDER BY` clause in the query. Keys are
+    /// compared by value with NULL first. Text is compared byte by byte, so the query must order
+    /// text keys with a binary collation. The export fails at the first key which is out of order.
+    /// The output is sorted by the keys, with deleted keys in between the inserted and updated
+    /// rows rather than at the end.
+    #[arg(long, requires = "delta_against")]
+    delta_sorted: bool,
+    /// Write the rows into Hive style partitions by the date of a date or timestamp column, e.g.
+    /// `--partition-by-date created_at:day`. Format is `COLUMN:GRANULARITY`, with granularity
+    /// `day`, `month` or `year`. The output is a directory, created if missing, holding one
+    /// subdirectory for each partition like `dt=2024-01-15/part.par`. Rows with NULL are written
+    /// to `dt=__HIVE_DEFAULT_PARTITION__`. Timestamps with time zone are truncated in UTC, all
+    /// others as they are, i.e. in the local time assumed by the data source. The partition column
+    /// is still written as part of the data.
+    #[arg(
+        long,
+        value_parser = partition_by_date_from_str,
+        conflicts_with_all = [
+            "row_groups_per_file", "file_size_threshold", "stats_only", "also_write",
+            "emit_hadoop_crc", "emit_stats_parquet", "column_nullable_from_data",
+            "stable_sort_client"
+        ]
+    )]
+    partition_by_date: Option<PartitionByDate>,
+    /// Maximum number of partition files open at the same time with `--partition-by-date`. If a
+    /// row of another partition is written, the least recently written file is closed first. A
+    /// partition whose file has been closed continues in a new one, e.g. `part_02.par`. Each open
+    /// file holds the pages of its current row group in memory.
+    #[arg(long, default_value = "32", requires = "partition_by_date")]
+    max_open_partitions: usize,
+    /// Number of threads writing the files of `--partition-by-date`. Each partition file is
+    /// written by one of the threads, so encoding, compression and I/O of different partitions
+    /// overlap. The contents of the files do not depend on the number of threads. Rows are queued
+    /// for each thread, so a higher number of threads holds more rows in memory. `1` writes the
+    /// files one after the other on the thread fetching the rows.
+    #[arg(
+        long,
+        default_value = "1",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        requires = "partition_by_date"
+    )]
+    partition_concurrency: usize,
+    /// By default the export is aborted if a single value fails to convert (e.g. an unparsable
+    /// timestamp). With this flag set, the export continues and a warning with batch and row index
+    /// is logged for each such value. A summary per column is logged at the end. What happens to
+    /// the row is chosen with `--bad-row-action`.
+    #[clap(long)]
+    skip_bad_rows: bool,
+    /// What `--skip-bad-rows` does with a row holding a value which can not be converted. `null`
+    /// writes the value as NULL and keeps the rest of the row. Columns which are declared not
+    /// nullable still cause the export to fail. `drop` leaves out the entire row, including its
+    /// values in all other columns, whether they are nullable or not. Dropping requires
+    /// converting each batch twice, once to find the rows to drop and once to write the rest. It
+    /// can not be combined with `--extract-blobs`.
+    #[arg(
+        long,
+        value_enum,
+        default_value = "null",
+        requires = "skip_bad_rows",
+        conflicts_with = "extract_blobs"
+    )]
+    bad_row_action: BadRowAction,
+    /// Write each value skipped by `--skip-bad-rows` into this file, as one JSON object per line
+    /// with the fields `batch`, `row` (index within the batch), `column`, `hex` and `text`
+    /// (the value as fetched, text fetched as UTF-16 is transcoded into UTF-8) and `error`. `hex`
+    /// and `text` are `null` if the fetched value is not available as bytes. Lines are written as
+    /// the values are converted, so the file is complete up to a crash of the export. The file is
+    /// created anew for each export.
+    #[arg(long, requires = "skip_bad_rows")]
+    bad_rows_file: Option<PathBuf>,
+    /// Log ordinal, number of rows and size of each row group written, together with the reason
+    /// it has been flushed: the batch reached its row count or byte limit, the file reached its
+    /// size threshold and is split, or the end of the result set has been reached. Messages are
+    /// logged at debug level, so they are only visible in combination with `-vv`. Helps to
+    /// understand why the output consists of many small row groups.
+    #[clap(long)]
+    debug_row_groups: bool,
+    /// Write an additional parquet file from the same fetched batches, compressing all its
+    /// columns with a different codec. Format is `CODEC:PATH`, e.g. `--also-write snappy:out.par`.
+    /// Can be specified multiple times. Useful to compare codecs without reading a slow data
+    /// source more than once. Additional files are never split, and the values of each batch are
+    /// converted once per file. The size of each file is logged at the end (`-v`). The
+    /// compression level is taken from `--column-compression-level-default`.
+    #[arg(
+        long,
+        value_parser = also_write_from_str,
+        action = ArgAction::Append,
+        conflicts_with_all = ["stats_only", "skip_bad_rows", "extract_blobs"]
+    )]
+    also_write: Vec<(CompressionVariants, PathBuf)>,
+    /// Write a checksum file next to each output file, as expected by Hadoop's
+    /// `ChecksumFileSystem`, so files can be copied into Hadoop file systems without a separate
+    /// checksum step. For `out.par` the checksums are written to `.out.par.crc`. The checksum file
+    /// uses version 1 of the format (magic bytes `crc\0`): The number of bytes per checksum (512)
+    /// as big endian 32-Bit integer, followed by the big endian CRC32 of each chunk of 512 bytes.
+    /// Hadoop reads these files with CRC32, not CRC32C. The checksums are computed while the file
+    /// is written. Not supported if writing to standard out or a named pipe.
+    #[arg(long, conflicts_with = "stats_only")]
+    emit_hadoop_crc: bool,
+    /// Write the statistics of each row group of the output into a small parquet file at this
+    /// path, e.g. for a planner pruning files and row groups before reading them. It holds one row
+    /// per row group with the columns `file` (path of the file holding the row group, NULL for
+    /// standard out), `row_group` (zero based index of the row group within the file) and
+    /// `num_rows`. For each column it holds a group named by the path of the column, with the
+    /// fields `min` and `max`, both of the same type as the column, and `null_count`. All of them
+    /// are NULL, if the statistic is not known. The statistics are the ones written into the
+    /// footer of the output, i.e. no additional work is done to compute them. The file is written
+    /// after the output has been closed.
+    #[arg(long, conflicts_with = "stats_only")]
+    emit_stats_parquet: Option<PathBuf>,
+    /// Columns whose statistics are written by `--emit-stats-parquet`, separated by commas. Nested
+    /// columns are referred to by their path, e.g. `address.city`. Defaults to all columns.
+    #[arg(long, value_delimiter = ',', requires = "emit_stats_parquet")]
+    stats_parquet_columns: Vec<String>,
+    /// Cache the schema of the output (names, types and nullability of each column) in this JSON
+    /// file. If the file already exists, the schema of the result set is compared with the cached
+    /// one first. Differences are logged as a warning and the cache is replaced, unless
+    /// `--strict-schema` is set. The cache is only written once the output is complete, so a
+    /// failed export keeps the schema of the last successful one.
+    #[arg(long)]
+    schema_cache: Option<PathBuf>,
+    /// Fail if the schema of the result set differs from the one cached by a prior run, before
+    /// anything is written. The error lists the added, removed and changed columns. Without
+    /// `--schema-cache` the schema is cached next to the output, e.g. in `out.par.schema.json` for
+    /// `out.par`. The first run without an existing cache always succeeds and creates it.
+    #[arg(long)]
+    strict_schema: bool,
+    /// If a column can not be fetched, drop it from the output with a warning and export the
+    /// remaining columns, instead of failing. This applies to columns whose type can not be
+    /// mapped to parquet and to columns whose fetch buffer can not be allocated. A summary of all
+    /// skipped columns and the reasons is logged once the buffers are allocated. Errors reported
+    /// by the driver while binding the buffers can not be attributed to individual columns and
+    /// still cause the export to fail.
+    #[clap(long)]
+    skip_columns_on_error: bool,
+    /// Map columns of a vendor specific SQL type, which `odbc2parquet` does not know, to a parquet
+    /// type instead of fetching them as text. Format is `SQL_TYPE_CODE=TARGET`, with the numeric
+    /// type code as reported by the driver, e.g. `-151=binary`. Possible targets are `text`,
+    /// `binary`, `int32`, `int64`, `float` and `double`. Can be specified multiple times. Rules are
+    /// ignored if `--fetch-as-text-all` is set.
+    #[arg(long, value_parser = type_rule_from_str, action = ArgAction::Append)]
+    type_rule: Vec<TypeRule>,
+    /// Bind a column as an explicit ODBC C type, overriding the buffer which would be chosen based
+    /// on its SQL type. This is a low level escape hatch to work around driver bugs, e.g.
+    /// `--bind-as amount=SQL_C_CHAR` fetches a numeric column as text. Format is `COLUMN=C_TYPE`.
+    /// The C type is case insensitive and the `SQL_C_` prefix is optional. Each C type is written
+    /// as a fixed parquet type: `SQL_C_CHAR` and `SQL_C_WCHAR` as UTF-8 text, `SQL_C_BINARY` as
+    /// binary, `SQL_C_SLONG` as INT32, `SQL_C_SBIGINT` as INT64, `SQL_C_FLOAT` as FLOAT,
+    /// `SQL_C_DOUBLE` as DOUBLE, `SQL_C_BIT` as BOOLEAN, `SQL_C_TYPE_DATE` as DATE and
+    /// `SQL_C_TYPE_TIMESTAMP` as TIMESTAMP with microsecond precision. Other C types are rejected.
+    /// The export fails if ODBC does not define a conversion from the SQL type of the column into
+    /// the C type, e.g. for binding a DATE column as `SQL_C_SLONG`. Can be specified multiple
+    /// times, once for each column. Takes precedence over all other options mapping the column.
+    #[arg(
+        long,
+        value_parser = bind_as_from_str,
+        action = ArgAction::Append,
+        conflicts_with = "schema_from"
+    )]
+    bind_as: Vec<BindAs>,
+    /// Nest columns of the result set under a group (struct) column in the output. Format is
+    /// `NAME=COLUMN1,COLUMN2,...`, e.g. `--group-columns address=street,city,zip`. The grouped
+    /// columns are removed from the top level. Instead the group takes the position of the first
+    /// of them in the result set, and contains them in the order listed. The group itself is
+    /// `REQUIRED`, each nested column keeps its own nullability. Options referring to columns by
+    /// name, like `--sorted-by` or `--null-fill`, use the name of the nested column. Can be
+    /// specified multiple times, once for each group.
+    #[arg(
+        long,
+        value_parser = column_group_from_str,
+        action = ArgAction::Append,
+        conflicts_with_all = ["schema_from", "emit_ddl", "round_trip_check"]
+    )]
+    group_columns: Vec<ColumnGroup>,
+    /// Reorder the columns of the output, so columns of the same physical type and nullability
+    /// are adjacent. `REQUIRED` columns come first, followed by `OPTIONAL` ones, each ordered by
+    /// physical type (boolean, integers, floating points, binary and text). Columns of the same
+    /// kind keep the order of the result set. Derived and parameter columns are not moved. The
+    /// original order is stored in the key value metadata `odbc2parquet.original_column_order`:
+    /// For each column of the result set in its original order, its zero based index in the
+    /// output, separated by commas. E.g. `2,0,1` means the first column of the result set is the
+    /// third column of the file. Parquet encodes and compresses each column chunk on its own, so
+    /// this does not change the size of the column data written by this tool. On synthetic data
+    /// with interleaved integer and text columns the file sizes differed only by a few bytes of
+    /// metadata. Gains, if any, are seen by readers or tools processing the file as a whole.
+    #[arg(long, conflicts_with = "group_columns")]
+    reorder_for_size: bool,
+    /// Append a column computed from other columns of the same row. Format is `NAME=EXPRESSION`
+    /// or `NAME:TYPE=EXPRESSION`, e.g. `--derive "full_name=first || ' ' || last"` or
+    /// `--derive amount_cents:int64=amount*100`. Columns are referred to by their name in the
+    /// result set, which may be put in double quotes. String literals are put in single quotes.
+    /// Operators are, in order of increasing precedence: `||` (concatenation), `+` and `-`, `*`,
+    /// `/` and `%` (remainder), unary `-`. Parentheses group. Supported functions are `int(x)`,
+    /// `float(x)` and `text(x)` to cast and `coalesce(x, ...)` for the first argument which is not
+    /// NULL. `NULL` as an operand yields NULL. Integer columns are integers, floating point columns
+    /// floats, all others, including dates and timestamps, are text in ISO 8601 notation. Binary
+    /// columns can not be used. Arithmetic of two integers is integer arithmetic, otherwise float
+    /// arithmetic, with text parsed as a number. `||` yields text. Casting a float to an integer
+    /// rounds. TYPE may be `text`, `int64` or `double`, otherwise it is inferred from the
+    /// expression. Derived columns are OPTIONAL and follow the columns of the result set. Values
+    /// which can not be computed, e.g. due to a division by zero, fail the export, or are written
+    /// as NULL with `--skip-bad-rows`. Can be specified multiple times.
+    #[arg(long, value_parser = derive_from_str, action = ArgAction::Append)]
+    derive: Vec<Derivation>,
+    /// Path to a rhai script applied to each row, after it is fetched and before it is written.
+    /// The script must define a function `transform(row)`, which receives the row as a map from
+    /// column name to value and returns the map of the transformed row, e.g.
+    /// `fn transform(row) { row.name = row.name.to_upper(); row }`. Values are passed like to
+    /// `--derive`: integers as integers, floating point numbers as floats, everything else,
+    /// including dates and timestamps, as text in ISO 8601 notation and NULL as `()`. The returned
+    /// values are converted back into the type of their column, booleans become `1` or `0`. The
+    /// returned map must hold exactly the columns passed to the script, the schema of the output
+    /// is not changed. Binary columns, including decimals fetched with `--numeric-decimals`, are
+    /// not passed to the script and written unchanged. Rows the script fails for fail the
+    /// export, or are handled according to `--bad-row-action` with `--skip-bad-rows`, i.e. the
+    /// values of the row passed to the script are written as NULL, or the row is dropped. The
+    /// script is interpreted once for each row on the thread writing the row groups, which
+    /// usually makes it the bottleneck of the export: expect throughput to drop by an order of
+    /// magnitude compared to an export without a script. Requires odbc2parquet to be built with
+    /// the `rhai` feature, e.g. `cargo install odbc2parquet --features rhai`.
+    #[arg(long)]
+    transform_script: Option<PathBuf>,
+    /// Log a warning for each column, which is not written with a type exactly matching its type
+    /// in the data source, e.g. decimals written as `DOUBLE`, unsigned integers written as
+    /// decimals, text fetched as UTF-16 and transcoded into UTF-8, or types unknown to this tool
+    /// fetched as text. Each warning lists column name, source type, parquet type, ODBC buffer and
+    /// the reason in `key=value` format. The number of columns for each kind of conversion is
+    /// logged once all columns have been inspected. Never fails the export.
+    #[arg(long)]
+    warn_on_implicit_conversion: bool,
+    /// Replace NULLs of a column with a fill value and write the column as `REQUIRED`. Format is
+    /// `COLUMN=VALUE`, e.g. `--null-fill count=0` or `--null-fill category=UNKNOWN`. Can be
+    /// specified multiple times, once for each column. The value is parsed according to the
+    /// parquet type of the column. Dates are expected as `YYYY-MM-DD`, timestamps as
+    /// `YYYY-MM-DD HH:MM:SS` and times as `HH:MM:SS`, all with optional fractional seconds.
+    /// Columns without a fill value keep their NULLs.
+    #[arg(long, value_parser = column_value_from_str, action = ArgAction::Append)]
+    null_fill: Vec<(String, String)>,
+    /// Abort the export at the first NULL fetched for this column, reporting the batch and the
+    /// zero based row within the batch. A debugging aid for columns expected to be fully
+    /// populated. Combined with `--batch-size-row` the position of the row in the result set is
+    /// the number of rows in all previous batches plus the row within the batch. Can be specified
+    /// multiple times, once for each column watched.
+    #[arg(long, alias = "fail-fast-on-first-null", action = ArgAction::Append)]
+    fail_fast_on_null: Vec<String>,
+    /// Description of a column in format `COLUMN=TEXT`, e.g. `--column-comment "price=Net price in
+    /// EUR"`. Can be specified multiple times, once for each column. Stored in the key value
+    /// metadata of the file under the key `odbc2parquet.comment.<column>`. Fails if the output
+    /// has no column of that name.
+    #[arg(long, value_parser = column_value_from_str, action = ArgAction::Append)]
+    column_comment: Vec<(String, String)>,
+    /// Fetch the comments of the columns of `--table` from the catalog of the data source and
+    /// attach them to the output, like comments passed with `--column-comment`, which take
+    /// precedence. They are also written into the `CREATE TABLE` statement of `--emit-ddl`. Comments
+    /// are queried from `sys.extended_properties` (`MS_Description`) for Microsoft SQL Server,
+    /// `ALL_COL_COMMENTS` for Oracle and `col_description` for PostgreSQL. For other data sources
+    /// the remarks reported by the driver are used. If the comments can not be fetched, a warning
+    /// is logged and the export continues without them.
+    #[arg(long, requires = "table", conflicts_with = "query")]
+    include_ddl_comments_from_catalog: bool,
+    /// Name of the root group of the parquet schema, i.e. the name of its `message`. Some tools
+    /// validate it.
+    #[arg(long, default_value = "schema")]
+    schema_name: String,
+    /// Append a binary column with this name to the output, holding the bookmark the driver reports
+    /// for each row (`SQL_ATTR_USE_BOOKMARKS`). Intended to correlate exported rows with the rows
+    /// of the data source later on. Bookmarks are an optional feature of ODBC and many drivers
+    /// only support them for scrollable cursors. If the driver does not provide bookmarks for the
+    /// result set, a warning is logged and the output has no such column. What a bookmark
+    /// identifies is up to the driver, often it is merely the position of the row within the
+    /// result set. If the database offers a row identifier, like `ROWID` of Oracle, `ctid` of
+    /// PostgreSQL or `rowid` of SQLite, selecting it in the query is the more reliable choice.
+    #[arg(long)]
+    rowid_column: Option<String>,
+    /// Execute the query once for each line of this file and write all result sets into the same
+    /// output. Each line holds the positional parameters for one execution, separated by commas.
+    /// Values containing commas or double quotes can be enclosed in double quotes (`"`), with
+    /// double quotes inside escaped by doubling them. All lines must have the same number of
+    /// values, and all executions must return result sets with the same columns.
+    #[arg(long, conflicts_with_all = ["parameters", "verify_row_count", "count_query"])]
+    parameter_file: Option<PathBuf>,
+    /// Add a text column for each parameter of `--parameter-file`, holding the value the rows have
+    /// been fetched with. The columns are named `parameter_1`, `parameter_2`, ...
+    #[arg(long, requires = "parameter_file")]
+    parameter_columns: bool,
+    /// After the export, read back the first N rows of the output and compare them with the first
+    /// N rows of a fresh execution of the query. Rows are compared regardless of their order. Each
+    /// row of the source without a counterpart in the output is printed, and the command fails if
+    /// there are any. Unless N covers the entire result set, the query must have an `ORDER BY`
+    /// clause with a unique key, otherwise the two executions may start with different rows.
+    /// Columns are matched by name. Useful to catch conversion errors when adopting new type
+    /// mappings. The source values are fetched as text and parsed according to the parquet type of
+    /// the column, so values which the driver renders unusually (e.g. floats with excess digits)
+    /// may be reported, too. Requires the output to be a single file.
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with_all = [
+            "row_groups_per_file", "file_size_threshold", "stats_only", "parameter_file"
+        ]
+    )]
+    round_trip_check: Option<usize>,
+    /// Restart the export from scratch up to this many times, if Microsoft SQL Server chose the
+    /// query as the victim of a deadlock (SQLSTATE 40001, error 1205). Retrying such a query is
+    /// likely to succeed. Between attempts the export waits for an exponentially growing,
+    /// randomized delay. Files written by a failed attempt are overwritten by the next one, or
+    /// removed for nondeterministic queries, see `--nondeterministic`. Other errors are not
+    /// retried. Since partial output can not be discarded, this conflicts with writing to stdout
+    /// and with `--extract-blobs`.
+    #[arg(long, default_value = "0", conflicts_with = "extract_blobs")]
+    retry_on_deadlock: u32,
+    /// Treat the query as nondeterministic, i.e. its values may differ between executions. Before
+    /// `--retry-on-deadlock` restarts the export of such a query, all files written by the failed
+    /// attempt are removed, rather than overwritten. Otherwise e.g. a split output could mix files
+    /// of different attempts, if the first attempt wrote more files than the last one. Without this
+    /// flag, a query is considered nondeterministic if it calls a function known to be, e.g.
+    /// `NEWID`, `GETDATE`, `SYSDATETIME`, `CURRENT_TIMESTAMP`, `NOW`, `RAND`, `RANDOM`, `UUID`,
+    /// `NEXTVAL`, `SYSDATE`, `SYS_GUID` or `NEXT VALUE FOR`. The heuristic ignores string literals,
+    /// quoted identifiers and comments, but can not detect such functions called within views,
+    /// procedures or default values.
+    #[arg(long)]
+    nondeterministic: bool,
+    /// Text put in front of the query before executing it, e.g. a common table expression like
+    /// `WITH recent AS (...)` shared by many exports. Also applies to the query generated for
+    /// `--table`. Separated from the query by a line break. The SQL is not parsed, so you are
+    /// responsible for the combination being valid. `--count-query` and the query derived for
+    /// `--verify-row-count` are not affected.
+    #[arg(long, conflicts_with = "catalog_function")]
+    query_prefix: Option<String>,
+    /// Text put after the query before executing it, e.g. a hint like `OPTION (MAXDOP 1)` for
+    /// Microsoft SQL Server. Separated from the query by a line break, so it is not swallowed by a
+    /// trailing line comment. A trailing semicolon of the query is removed. Otherwise the SQL is not
+    /// parsed, so you are responsible for the combination being valid. Applies to the query
+    /// generated for `--table` and to the sorting query of `--stable-sort`, too. `--count-query`
+    /// and the query derived for `--verify-row-count` are not affected.
+    #[arg(long, conflicts_with = "catalog_function")]
+    query_suffix: Option<String>,
+    /// Refuse to execute the query (and `--count-query`), unless each of its statements starts
+    /// with `SELECT`, `WITH`, `VALUES` or `CALL`. Stored procedures can be called with `CALL` or
+    /// `{call ...}`. Statements containing keywords like `INSERT`, `UPDATE`, `DELETE`, `MERGE`,
+    /// `INTO` or `DROP` are refused as well. String literals, quoted identifiers and comments are
+    /// ignored. This guards against executing a modifying statement by accident, e.g. due to a
+    /// copy and paste error. It is not a security boundary: Procedures, functions or triggers
+    /// invoked by the query may still modify data. Use a connection without write permissions
+    /// for that. Can be enabled by default by setting the environment variable
+    /// `ODBC2PARQUET_READ_ONLY=true`, in which case `--read-only=false` disables it again.
+    #[arg(
+        long,
+        env = "ODBC2PARQUET_READ_ONLY",
+        default_value_t = false,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        action = ArgAction::Set
+    )]
+    read_only: bool,
+    /// After the export, count the rows of the result set independently and fail if the number
+    /// differs from the number of rows written. By default the count is obtained by wrapping the
+    /// query, i.e. `SELECT COUNT(*) FROM (<query>) t`. This may not work for all statements (e.g.
+    /// stored procedures), use `--count-query` to specify the count query explicitly in these
+    /// cases. Useful to catch partial reads from unreliable drivers.
+    #[clap(long)]
+    verify_row_count: bool,
+    /// Explicit query returning the expected number of rows in the first column of its first row.
+    /// Implies `--verify-row-count`. Receives the same positional parameters as the query.
+    #[clap(long)]
+    count_query: Option<String>,
+    /// Maximum absolute difference between the number of rows written and the number of rows
+    /// counted, which still passes the verification.
+    #[clap(long, default_value = "0")]
+    row_count_tolerance: u64,
+    /// Export all columns of this table, instead of executing a query. The columns are discovered
+    /// using the catalog functions of the driver and listed explicitly in a `SELECT` statement.
+    /// Identifiers are quoted according to the dialect of the data source: brackets for Microsoft
+    /// SQL Server, backticks for MySQL and MariaDB and double quotes otherwise. The name is
+    /// matched literally and is case sensitive for most data sources. Conflicts with passing a
+    /// query.
+    #[arg(long, conflicts_with_all = ["query", "query_text"])]
+    table: Option<String>,
+    /// Schema of the table passed to `--table`. If not specified, the table is searched in all
+    /// schemas and must be unique. Together with `--catalog-function` only the catalog of this
+    /// schema is exported.
+    #[arg(long, requires = "catalog_source", conflicts_with = "query")]
+    schema: Option<String>,
+    /// Catalog of the table passed to `--table`, or searched by `--catalog-function`. Defaults to
+    /// the current catalog of the connection.
+    #[arg(long, requires = "catalog_source", conflicts_with = "query")]
+    catalog: Option<String>,
+    /// Export the result set of a catalog function of the driver, instead of executing a query.
+    /// Useful to snapshot the schema of a database into parquet, e.g. for a data catalog. `tables`
+    /// (`SQLTables`) yields a row for each table, `columns` (`SQLColumns`) a row for each column.
+    /// The columns of the output are the ones defined by the ODBC standard for the function,
+    /// followed by driver specific ones. Narrow the search with `--catalog`, `--schema`,
+    /// `--table-pattern`, `--column-pattern` and `--table-type`.
+    #[arg(
+        long,
+        value_enum,
+        conflicts_with_all = [
+            "query", "table", "output_dir", "parameter_file", "verify_row_count", "count_query",
+            "round_trip_check"
+        ]
+    )]
+    catalog_function: Option<CatalogFunction>,
+    /// Search pattern for the names of the tables listed by `--catalog-function`. `%` matches any
+    /// sequence of characters and `_` a single character. Defaults to all tables.
+    #[arg(long, requires = "catalog_function", conflicts_with_all = ["query", "table"])]
+    table_pattern: Option<String>,
+    /// Search pattern for the names of the columns listed by `--catalog-function columns`.
+    /// Defaults to all columns.
+    #[arg(long, requires = "catalog_function", conflicts_with_all = ["query", "table"])]
+    column_pattern: Option<String>,
+    /// Comma separated list of table types listed by `--catalog-function tables`, e.g.
+    /// `TABLE,VIEW`. Defaults to all types.
+    #[arg(long, requires = "catalog_function", conflicts_with_all = ["query", "table"])]
+    table_type: Option<String>,
+    /// Write the output into this directory, instead of naming the output file on the command
+    /// line. Conflicts with the output argument, so the query and its parameters are passed with
+    /// `--query` and `--parameter` instead of positionally, unless `--table` is specified. The
+    /// file is named after the table if `--table` is specified, e.g. `dbo.Sales.par`. Otherwise it
+    /// is named after a hash of the query text and its parameters, e.g.
+    /// `query_1f2e3d4c5b6a7980.par`. The name is the same for every run of the same export, so
+    /// reruns overwrite the previous output. Parts of split outputs are written into the directory
+    /// as well. The directory is created if it does not exist.
+    #[arg(long)]
+    output_dir: Option<PathBuf>,
+    /// Extension of the output files named by this tool, i.e. the file within `--output-dir` and
+    /// the files of `--partition-by-date`, e.g. `--extension parquet`, since some readers ignore
+    /// files ending in `.par`. A leading dot is optional. Defaults to `par`, like previous
+    /// versions. An output file passed on the command line keeps its name. The parts of a split
+    /// output and sidecar files, like the `.crc` files of `--emit-hadoop-crc`, are always named
+    /// after the output file, so they share its extension.
+    #[arg(long, default_value = "par", value_parser = extension_from_str)]
+    extension: String,
     /// Name of the output parquet file. Use `-` to indicate that the output should be written to
     /// standard out instead. This option does nothing if the output is written to standard out.
-    output: IoArg,
+    /// Conflicts with `--output-dir`.
+    ///
+    /// If the output is an existing named pipe (FIFO), the parquet file is buffered in a temporary
+    /// file, and only streamed into the pipe once it is complete. So a consumer never reads a
+    /// partial file. This requires enough space in the temporary directory (see `TMPDIR`) to hold
+    /// the entire output. Splitting the output into multiple files is not supported for pipes.
+    output: Option<IoArg>,
     /// Query executed against the ODBC data source. Question marks (`?`) can be used as
     /// placeholders for positional parameters. E.g. "SELECT Name FROM Employees WHERE salary > ?;".
     /// Instead of passing a query verbatim, you may pass a plain dash (`-`), to indicate that the
     /// query should be read from standard input. In this case the entire input until EOF will be
-    /// considered the query.
-    query: String,
+    /// considered the query. Required, unless `--table`, `--query` or `--catalog-function` is
+    /// specified.
+    #[arg(required_unless_present_any = ["table", "query_text", "catalog_function"])]
+    query: Option<String>,
     /// For each placeholder question mark (`?`) in the query text one parameter must be passed at
     /// the end of the command line.
     parameters: Vec<String>,
+    /// Query executed against the ODBC data source, like the positional query argument. Needed
+    /// together with `--output-dir`, which takes the place of the output argument.
+    #[arg(
+        long = "query",
+        value_name = "QUERY",
+        conflicts_with_all = ["query", "catalog_function"]
+    )]
+    query_text: Option<String>,
+    /// Positional parameter of `--query`. Repeat the option for each placeholder question mark
+    /// (`?`) in the query text.
+    #[arg(
+        long,
+        value_name = "VALUE",
+        requires = "query_text",
+        conflicts_with = "parameter_file"
+    )]
+    parameter: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct DescribeParametersOpt {
+    #[clap(flatten)]
+    connect_opts: ConnectOpts,
+    /// Query containing placeholders (`?`). Pass a plain dash (`-`) to read the query from standard
+    /// input instead.
+    query: String,
+}
+
+#[derive(Args)]
+pub struct SelfTestOpt {
+    #[clap(flatten)]
+    connect_opts: ConnectOpts,
+    /// Select each value as a literal (`SELECT CAST('<literal>' AS <type>) AS a`), rather than
+    /// creating a table, inserting the value and a NULL, and dropping the table again. Works with
+    /// read only connections. Databases requiring a `FROM` clause, e.g. Oracle, are not supported
+    /// in this mode.
+    #[arg(long)]
+    use_literals_only: bool,
+    /// Name of the table created for each type and dropped afterwards. Must not exist.
+    #[arg(
+        long,
+        default_value = "odbc2parquet_self_test",
+        conflicts_with = "use_literals_only"
+    )]
+    table: String,
+    /// SQL type and literal to test, instead of the built in list of standard SQL types, e.g.
+    /// `--type 'DATETIME2(3)=2024-02-29 23:59:58.123'`. The literal is cast into the type. Can be
+    /// specified multiple times.
+    #[arg(long = "type", value_parser = self_test::sql_type_from_str, action = ArgAction::Append)]
+    types: Vec<(String, String)>,
 }
 
 #[derive(Args)]
@@ -277,7 +1448,20 @@ impl Cli {
     /// clap.
     pub fn perform_extra_validation(&self) -> Result<(), Error> {
         if let Command::Query { query_opt } = &self.command {
-            if !query_opt.output.is_file() {
+            if query_opt.max_file_count.is_some()
+                && query_opt.file_size_threshold.is_none()
+                && query_opt.row_groups_per_file == 0
+            {
+                bail!(
+                    "`--max-file-count` requires the output to be split by `--row-groups-per-file` \
+                    or `--file-size-threshold`."
+                )
+            }
+            // With `--output-dir` the output is always a file within the directory.
+            let Some(output) = query_opt.output.as_ref() else {
+                return Ok(());
+            };
+            if !output.is_file() {
                 if query_opt.file_size_threshold.is_some() {
                     bail!("file-size-threshold conflicts with specifying stdout ('-') as output.")
                 }
@@ -285,13 +1469,66 @@ impl Cli {
                     bail!("row-groups-per-file conflicts with specifying stdout ('-') as output.")
                 }
             }
+            if query_opt.round_trip_check.is_some() && !output.is_file() {
+                bail!("round-trip-check conflicts with specifying stdout ('-') as output.")
+            }
+            if query_opt.retry_on_deadlock != 0 && !output.is_file() {
+                bail!("retry-on-deadlock conflicts with specifying stdout ('-') as output.")
+            }
+            if query_opt.emit_hadoop_crc && !output.is_file() {
+                bail!("emit-hadoop-crc conflicts with specifying stdout ('-') as output.")
+            }
+            if let IoArg::File(path) = output {
+                if query_opt.emit_hadoop_crc && is_fifo(path) {
+                    bail!(
+                        "Output '{}' is a named pipe. `--emit-hadoop-crc` requires a regular file.",
+                        path.to_string_lossy()
+                    )
+                }
+                if query_opt.round_trip_check.is_some() && is_fifo(path) {
+                    bail!(
+                        "Output '{}' is a named pipe. `--round-trip-check` requires reading the \
+                        output back.",
+                        path.to_string_lossy()
+                    )
+                }
+                if is_fifo(path)
+                    && (query_opt.file_size_threshold.is_some()
+                        || query_opt.row_groups_per_file != 0)
+                {
+                    bail!(
+                        "Output '{}' is a named pipe. Splitting the output into multiple files \
+                        with `--file-size-threshold` or `--row-groups-per-file` is not supported \
+                        for named pipes.",
+                        path.to_string_lossy()
+                    )
+                }
+            }
         }
         Ok(())
     }
 }
 
-fn main() -> Result<(), Error> {
-    let opt = Cli::parse();
+fn main() -> ExitCode {
+    match run_cli() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("Error: {error:?}");
+            // An export stopped by `--max-file-count` is told apart from other errors by its exit
+            // status.
+            if error.chain().any(|cause| cause.is::<MaxFileCountReached>()) {
+                ExitCode::from(MaxFileCountReached::EXIT_CODE)
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+    }
+}
+
+/// Parses the command line, sets up logging and the ODBC environment and runs the subcommand.
+fn run_cli() -> Result<(), Error> {
+    let args = expand_job_file(Cli::command(), env::args_os().collect())?;
+    let opt = Cli::parse_from(args);
     opt.perform_extra_validation()?;
 
     let verbose = if opt.quiet {
@@ -309,25 +1546,48 @@ fn main() -> Result<(), Error> {
     };
 
     // Initialize logging
-    stderrlog::new()
+    let mut logger = stderrlog::new();
+    logger
         .module(module_path!())
         .module("odbc_api")
         .quiet(false) // Even if `opt.quiet` is true, we still want to print errors
         .verbosity(verbose)
         .color(color_choice)
-        .timestamp(stderrlog::Timestamp::Second)
-        .init()
-        .unwrap();
+        .timestamp(stderrlog::Timestamp::Second);
+    if opt.abort_on_warning {
+        abort_on_warning(logger, verbose).unwrap();
+    } else {
+        logger.init().unwrap();
+    }
+
+    if opt.connection_pooling {
+        // Safety: Connection pooling must be enabled before the environment is created. We are
+        // single threaded at this point, and the user vouches for the driver being thread safe.
+        unsafe { Environment::set_connection_pooling(AttrConnectionPooling::DriverAware)? };
+    }
 
     // Initialize ODBC environment used to create the connection to the Database
     let odbc_env = Environment::new()?;
 
-    match opt.command {
+    run(opt.command, &odbc_env)
+        .and_then(|()| check_warnings())
+        .map_err(|error| report_diagnostics(error, opt.error_log.as_deref()))
+}
+
+/// Execute the subcommand.
+fn run(command: Command, odbc_env: &Environment) -> Result<(), Error> {
+    match command {
         Command::Query { query_opt } => {
-            query::query(&odbc_env, query_opt)?;
+            query::query(odbc_env, *query_opt)?;
+        }
+        Command::DescribeParameters { describe_opt } => {
+            query::describe_parameters(odbc_env, describe_opt)?;
         }
         Command::Insert { insert_opt } => {
-            insert::insert(&odbc_env, &insert_opt)?;
+            insert::insert(odbc_env, &insert_opt)?;
+        }
+        Command::SelfTest { self_test_opt } => {
+            self_test::self_test(odbc_env, self_test_opt)?;
         }
         Command::ListDrivers => {
             for driver_info in odbc_env.drivers()? {
@@ -365,28 +1625,40 @@ fn open_connection<'e>(
     odbc_env: &'e Environment,
     opt: &ConnectOpts,
 ) -> Result<Connection<'e>, Error> {
+    let credentials = match opt.credential_helper.as_deref() {
+        Some(command) => {
+            Credentials::from_helper(command, opt.dsn.as_deref(), opt.user.as_deref())?
+        }
+        None => Credentials::default(),
+    };
+    let connection_string = credentials
+        .connection_string
+        .or_else(|| opt.connection_string.clone());
+    let user = credentials.user.or_else(|| opt.user.clone());
+    let password = credentials.password.or_else(|| opt.password.clone());
+
     // If a data source name has been given, try connecting with that.
     if let Some(dsn) = opt.dsn.as_deref() {
         let conn = odbc_env.connect(
             dsn,
-            opt.user.as_deref().unwrap_or(""),
-            opt.password.as_deref().unwrap_or(""),
+            user.as_deref().unwrap_or(""),
+            password.as_deref().unwrap_or(""),
             ConnectionOptions::default(),
         )?;
         return Ok(conn);
     }
 
     // There is no data source name, so at least there must be prompt or a connection string
-    if !opt.prompt && opt.connection_string.is_none() {
+    if !opt.prompt && connection_string.is_none() {
         bail!("Either DSN, connection string or prompt must be specified.")
     }
 
     // Append user and or password to connection string
-    let mut cs = opt.connection_string.clone().unwrap_or_default();
-    if let Some(uid) = opt.user.as_deref() {
+    let mut cs = connection_string.unwrap_or_default();
+    if let Some(uid) = user.as_deref() {
         cs = format!("{}UID={};", cs, &escape_attribute_value(uid));
     }
-    if let Some(pwd) = opt.password.as_deref() {
+    if let Some(pwd) = password.as_deref() {
         cs = format!("{}PWD={};", cs, &escape_attribute_value(pwd));
     }
 
@@ -416,3 +1688,20 @@ fn open_connection<'e>(
     let conn = odbc_env.driver_connect(&cs, &mut completed_connection_string, driver_completion)?;
     Ok(conn)
 }
+
+/// `true` if the path points to an existing named pipe (FIFO). Named pipes can not be seeked and
+/// must not be deleted or replaced by us.
+pub fn is_fifo(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        std::fs::metadata(path)
+            .map(|metadata| metadata.file_type().is_fifo())
+            .unwrap_or(false)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        false
+    }
+}
@@ -1,9 +1,22 @@
-use anyhow::Error;
+use anyhow::{bail, Error};
 use parquet::{
     column::{reader::ColumnReaderImpl, writer::ColumnWriterImpl},
-    data_type::{ByteArray, DataType, FixedLenByteArray, FixedLenByteArrayType},
+    data_type::{ByteArray, DataType, FixedLenByteArray, FixedLenByteArrayType, Int96},
 };
-use std::mem::size_of;
+use std::{fmt, mem::size_of};
+
+use crate::column_profile::ColumnProfile;
+
+/// A value written into a parquet column, in its physical representation.
+pub enum ProfileValue<'a> {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    I96(&'a [u32]),
+    F32(f32),
+    F64(f64),
+    Bytes(&'a [u8]),
+}
 
 /// Holds preallocated buffers for every possible physical parquet type. This way we do not need to
 /// reallocate them.
@@ -12,10 +25,61 @@ pub struct ParquetBuffer {
     pub values_i64: Vec<i64>,
     pub values_f32: Vec<f32>,
     pub values_f64: Vec<f64>,
+    pub values_i96: Vec<Int96>,
     pub values_bytes_array: Vec<ByteArray>,
     pub values_fixed_bytes_array: Vec<FixedLenByteArray>,
     pub values_bool: Vec<bool>,
     pub def_levels: Vec<i16>,
+    /// If `Some`, values which fail to convert are written as NULL instead of causing an error.
+    /// Row index within the batch and the error are collected here, until taken by the caller.
+    pub bad_values: Option<Vec<(usize, Error)>>,
+    /// If `true`, bad values of columns which are not nullable are collected in `bad_values`,
+    /// too, rather than causing an error. Set by [`Self::find_bad_values`].
+    pub bad_values_of_required: bool,
+    /// If `Some`, NULLs are replaced with this value. Set only while a column with a fill value is
+    /// written.
+    pub null_fill: Option<FillValue>,
+    /// If `true`, the first NULL fetched from the data source fails writing the column with an
+    /// [`UnexpectedNull`] error. Set only while a column watched with `--fail-fast-on-null` is
+    /// written.
+    pub fail_on_null: bool,
+    /// Row indices within the batch of values which have been truncated, while writing the
+    /// current column. Taken by the caller after each column.
+    pub truncated_values: Vec<usize>,
+    /// Row indices within the batch of decimals with more digits than their precision, while
+    /// writing the current column. Taken by the caller after each column.
+    pub precision_violations: Vec<usize>,
+    /// If `Some`, every value written is accounted for in this profile. Set only while a column is
+    /// written, whose profile is requested.
+    pub profile: Option<ColumnProfile>,
+}
+
+/// The first NULL in a column, which is expected to be fully populated.
+#[derive(Debug)]
+pub struct UnexpectedNull {
+    /// Zero based index of the row within the batch.
+    pub row_index: usize,
+}
+
+impl fmt::Display for UnexpectedNull {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unexpected NULL in row {} of the batch.", self.row_index)
+    }
+}
+
+impl std::error::Error for UnexpectedNull {}
+
+/// Value written instead of NULL, in the physical representation of its column.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FillValue {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    I96(Int96),
+    F32(f32),
+    F64(f64),
+    Bytes(ByteArray),
+    FixedLenBytes(FixedLenByteArray),
 }
 
 impl ParquetBuffer {
@@ -25,6 +89,7 @@ impl ParquetBuffer {
         + size_of::<i64>()
         + size_of::<f32>()
         + size_of::<f64>()
+        + size_of::<Int96>()
         + size_of::<ByteArray>()
         + size_of::<FixedLenByteArrayType>()
         + size_of::<bool>()
@@ -36,34 +101,68 @@ impl ParquetBuffer {
             values_i64: Vec::with_capacity(batch_size),
             values_f32: Vec::with_capacity(batch_size),
             values_f64: Vec::with_capacity(batch_size),
+            values_i96: Vec::with_capacity(batch_size),
             values_bytes_array: Vec::with_capacity(batch_size),
             values_fixed_bytes_array: Vec::with_capacity(batch_size),
             values_bool: Vec::with_capacity(batch_size),
             def_levels: Vec::with_capacity(batch_size),
+            bad_values: None,
+            bad_values_of_required: false,
+            null_fill: None,
+            fail_on_null: false,
+            truncated_values: Vec::new(),
+            precision_violations: Vec::new(),
+            profile: None,
         }
     }
 
+    /// Write values which fail to convert as NULL, rather than failing, if the column is nullable.
+    pub fn skip_bad_values(&mut self) {
+        self.bad_values = Some(Vec::new());
+    }
+
+    /// Collect all values which fail to convert, including the ones of columns which are not
+    /// nullable. They are written as NULL, so the output is only fit to find the rows holding bad
+    /// values and must be discarded.
+    pub fn find_bad_values(&mut self) {
+        self.bad_values = Some(Vec::new());
+        self.bad_values_of_required = true;
+    }
+
     pub fn set_num_rows_fetched(&mut self, num_rows: usize) {
         self.def_levels.resize(num_rows, 0);
         self.values_i32.resize(num_rows, 0);
         self.values_i64.resize(num_rows, 0);
         self.values_f32.resize(num_rows, 0.);
         self.values_f64.resize(num_rows, 0.);
+        self.values_i96.resize(num_rows, Int96::new());
         self.values_bytes_array.resize(num_rows, ByteArray::new());
         self.values_fixed_bytes_array
             .resize(num_rows, ByteArray::new().into());
         self.values_bool.resize(num_rows, false);
     }
 
-    /// Writes an i128 twos complement representation into a fixed sized byte array
+    /// Writes an i128 twos complement representation into a fixed sized byte array. Values not
+    /// fitting into `length_in_bytes` are an error if `check_fit` is `true`, otherwise their
+    /// leading bytes are cut off.
     pub fn write_twos_complement_i128(
         &mut self,
         cw: &mut ColumnWriterImpl<FixedLenByteArrayType>,
-        source: impl Iterator<Item = Option<i128>>,
+        source: impl Iterator<Item = Result<Option<i128>, Error>>,
         length_in_bytes: usize,
+        check_fit: bool,
     ) -> Result<(), Error> {
-        self.write_optional_any_fallible(cw, source.map(Ok), |num| {
-            let out = num.to_be_bytes()[(16 - length_in_bytes)..].to_owned();
+        let source = source.map(|num| {
+            num?.map(|num| {
+                if check_fit {
+                    twos_complement(num, length_in_bytes)
+                } else {
+                    Ok(num.to_be_bytes()[(16 - length_in_bytes)..].to_owned())
+                }
+            })
+            .transpose()
+        });
+        self.write_optional_any_fallible(cw, source, |out| {
             // Vec<u8> -> ByteArray -> FixedLenByteArray
             let out: ByteArray = out.into();
             out.into()
@@ -80,17 +179,56 @@ impl ParquetBuffer {
         T: DataType,
         T::T: BufferedDataType,
     {
+        // We can only replace bad values with NULL if the column is nullable, unless the output is
+        // discarded anyway.
+        let may_skip = cw.get_descriptor().max_def_level() > 0 || self.bad_values_of_required;
+        let mut bad_values = self.bad_values.take();
+        let mut profile = self.profile.take();
+        let fill = self.null_fill.as_ref().and_then(T::T::from_fill);
+        let fail_on_null = self.fail_on_null;
         let (values, def_levels) = T::T::mut_buf(self);
         let mut values_index = 0;
-        for (item, definition_level) in source.zip(&mut def_levels.iter_mut()) {
-            *definition_level = if let Some(value) = item? {
-                values[values_index] = into_physical(value);
+        let mut result = Ok(());
+        for (row_index, (item, definition_level)) in
+            source.zip(&mut def_levels.iter_mut()).enumerate()
+        {
+            let item = match (item, bad_values.as_mut()) {
+                (Ok(None), _) if fail_on_null => {
+                    result = Err(UnexpectedNull { row_index }.into());
+                    break;
+                }
+                (Ok(item), _) => item,
+                (Err(error), Some(bad_values)) if may_skip => {
+                    bad_values.push((row_index, error));
+                    None
+                }
+                (Err(error), _) => {
+                    result = Err(error);
+                    break;
+                }
+            };
+            let value = match item {
+                Some(value) => Some(into_physical(value)),
+                None => fill.clone(),
+            };
+            if let Some(profile) = profile.as_mut() {
+                match &value {
+                    Some(value) => profile.add(value.profile_value()),
+                    None => profile.add_null(),
+                }
+            }
+            *definition_level = if let Some(value) = value {
+                values[values_index] = value;
                 values_index += 1;
                 1
             } else {
                 0
             }
         }
+        self.bad_values = bad_values;
+        self.profile = profile;
+        result?;
+        let (values, def_levels) = T::T::mut_buf(self);
         cw.write_batch(values, Some(def_levels), None)?;
         Ok(())
     }
@@ -181,61 +319,242 @@ impl ParquetBuffer {
     }
 }
 
-pub trait BufferedDataType: Sized {
+pub trait BufferedDataType: Sized + Clone {
     /// The tuple returned is (Values, Definition levels)
     fn mut_buf(buffer: &mut ParquetBuffer) -> (&mut Vec<Self>, &mut Vec<i16>);
+
+    /// The fill value, if it has this physical type.
+    fn from_fill(fill: &FillValue) -> Option<Self>;
+
+    /// The value as accounted for in a column profile.
+    fn profile_value(&self) -> ProfileValue<'_>;
 }
 
 impl BufferedDataType for i32 {
     fn mut_buf(buffer: &mut ParquetBuffer) -> (&mut Vec<Self>, &mut Vec<i16>) {
         (&mut buffer.values_i32, &mut buffer.def_levels)
     }
+
+    fn from_fill(fill: &FillValue) -> Option<Self> {
+        match fill {
+            FillValue::I32(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn profile_value(&self) -> ProfileValue<'_> {
+        ProfileValue::I32(*self)
+    }
 }
 
 impl BufferedDataType for i64 {
     fn mut_buf(buffer: &mut ParquetBuffer) -> (&mut Vec<Self>, &mut Vec<i16>) {
         (&mut buffer.values_i64, &mut buffer.def_levels)
     }
+
+    fn from_fill(fill: &FillValue) -> Option<Self> {
+        match fill {
+            FillValue::I64(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn profile_value(&self) -> ProfileValue<'_> {
+        ProfileValue::I64(*self)
+    }
 }
 
 impl BufferedDataType for f32 {
     fn mut_buf(buffer: &mut ParquetBuffer) -> (&mut Vec<Self>, &mut Vec<i16>) {
         (&mut buffer.values_f32, &mut buffer.def_levels)
     }
+
+    fn from_fill(fill: &FillValue) -> Option<Self> {
+        match fill {
+            FillValue::F32(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn profile_value(&self) -> ProfileValue<'_> {
+        ProfileValue::F32(*self)
+    }
 }
 
 impl BufferedDataType for f64 {
     fn mut_buf(buffer: &mut ParquetBuffer) -> (&mut Vec<Self>, &mut Vec<i16>) {
         (&mut buffer.values_f64, &mut buffer.def_levels)
     }
+
+    fn from_fill(fill: &FillValue) -> Option<Self> {
+        match fill {
+            FillValue::F64(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn profile_value(&self) -> ProfileValue<'_> {
+        ProfileValue::F64(*self)
+    }
+}
+
+impl BufferedDataType for Int96 {
+    fn mut_buf(buffer: &mut ParquetBuffer) -> (&mut Vec<Self>, &mut Vec<i16>) {
+        (&mut buffer.values_i96, &mut buffer.def_levels)
+    }
+
+    fn from_fill(fill: &FillValue) -> Option<Self> {
+        match fill {
+            FillValue::I96(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn profile_value(&self) -> ProfileValue<'_> {
+        ProfileValue::I96(self.data())
+    }
 }
 
 impl BufferedDataType for bool {
     fn mut_buf(buffer: &mut ParquetBuffer) -> (&mut Vec<Self>, &mut Vec<i16>) {
         (&mut buffer.values_bool, &mut buffer.def_levels)
     }
+
+    fn from_fill(fill: &FillValue) -> Option<Self> {
+        match fill {
+            FillValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn profile_value(&self) -> ProfileValue<'_> {
+        ProfileValue::Bool(*self)
+    }
 }
 
 impl BufferedDataType for ByteArray {
     fn mut_buf(buffer: &mut ParquetBuffer) -> (&mut Vec<Self>, &mut Vec<i16>) {
         (&mut buffer.values_bytes_array, &mut buffer.def_levels)
     }
+
+    fn from_fill(fill: &FillValue) -> Option<Self> {
+        match fill {
+            FillValue::Bytes(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    fn profile_value(&self) -> ProfileValue<'_> {
+        ProfileValue::Bytes(self.data())
+    }
 }
 
 impl BufferedDataType for FixedLenByteArray {
     fn mut_buf(buffer: &mut ParquetBuffer) -> (&mut Vec<Self>, &mut Vec<i16>) {
         (&mut buffer.values_fixed_bytes_array, &mut buffer.def_levels)
     }
+
+    fn from_fill(fill: &FillValue) -> Option<Self> {
+        match fill {
+            FillValue::FixedLenBytes(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    fn profile_value(&self) -> ProfileValue<'_> {
+        ProfileValue::Bytes(self.data())
+    }
+}
+
+/// Big endian two's complement representation of `num` with exactly `length_in_bytes` bytes. Sign
+/// extended if necessary. Fails if `num` does not fit into `length_in_bytes`.
+pub fn twos_complement(num: i128, length_in_bytes: usize) -> Result<Vec<u8>, Error> {
+    let bytes = num.to_be_bytes();
+    let (truncated, out) = bytes.split_at(16 - length_in_bytes);
+    // Truncated bytes must be pure sign extension of the remaining ones.
+    let sign = if num < 0 { 0xff } else { 0x00 };
+    let sign_bit_matches = out
+        .first()
+        .is_some_and(|&msb| (msb & 0x80 != 0) == (num < 0));
+    if !truncated.iter().all(|&byte| byte == sign) || !sign_bit_matches {
+        bail!("Decimal value {num} (unscaled) does not fit into {length_in_bytes} bytes.")
+    }
+    Ok(out.to_owned())
 }
 
 #[cfg(test)]
 mod test {
 
-    use super::ParquetBuffer;
+    use std::sync::Arc;
+
+    use anyhow::anyhow;
+    use parquet::{
+        column::writer::get_typed_column_writer_mut, data_type::Int64Type,
+        file::writer::SerializedFileWriter, schema::parser::parse_message_type,
+    };
+
+    use super::{twos_complement, ParquetBuffer};
 
     #[test]
     #[cfg(target_pointer_width = "64")] // Memory usage is platform dependent
     fn memory_usage() {
-        assert_eq!(59, ParquetBuffer::MEMORY_USAGE_BYTES_PER_ROW);
+        assert_eq!(71, ParquetBuffer::MEMORY_USAGE_BYTES_PER_ROW);
+    }
+
+    #[test]
+    fn skip_bad_values() {
+        let schema = Arc::new(parse_message_type("message schema { OPTIONAL INT64 a; }").unwrap());
+        let mut writer = SerializedFileWriter::new(Vec::new(), schema, Default::default()).unwrap();
+        let mut row_group_writer = writer.next_row_group().unwrap();
+        let mut column_writer = row_group_writer.next_column().unwrap().unwrap();
+        let cw = get_typed_column_writer_mut::<Int64Type>(column_writer.untyped());
+
+        let mut pb = ParquetBuffer::new(3);
+        pb.skip_bad_values();
+        pb.set_num_rows_fetched(3);
+        let source = vec![Ok(Some(1)), Err(anyhow!("bad")), Ok(Some(3))];
+        pb.write_optional_fallible(cw, source.into_iter()).unwrap();
+
+        let bad_values = pb.bad_values.unwrap();
+        assert_eq!(1, bad_values.len());
+        assert_eq!(1, bad_values[0].0);
+        assert_eq!(&[1, 0, 1], pb.def_levels.as_slice());
+    }
+
+    #[test]
+    fn find_bad_values_of_required_column() {
+        let schema = Arc::new(parse_message_type("message schema { REQUIRED INT64 a; }").unwrap());
+        let mut writer = SerializedFileWriter::new(Vec::new(), schema, Default::default()).unwrap();
+        let mut row_group_writer = writer.next_row_group().unwrap();
+        let mut column_writer = row_group_writer.next_column().unwrap().unwrap();
+        let cw = get_typed_column_writer_mut::<Int64Type>(column_writer.untyped());
+
+        let mut pb = ParquetBuffer::new(3);
+        pb.find_bad_values();
+        pb.set_num_rows_fetched(3);
+        let source = vec![Ok(Some(1)), Ok(Some(2)), Err(anyhow!("bad"))];
+        pb.write_optional_fallible(cw, source.into_iter()).unwrap();
+
+        let bad_values = pb.bad_values.unwrap();
+        assert_eq!(1, bad_values.len());
+        assert_eq!(2, bad_values[0].0);
+    }
+
+    #[test]
+    fn sign_extend_twos_complement() {
+        assert_eq!(vec![0x00, 0x00, 0x01], twos_complement(1, 3).unwrap());
+        assert_eq!(vec![0xff, 0xff, 0xff], twos_complement(-1, 3).unwrap());
+        assert_eq!(vec![0xff, 0xff, 0x80], twos_complement(-128, 3).unwrap());
+        let mut minus_two = vec![0xff; 16];
+        minus_two[15] = 0xfe;
+        assert_eq!(minus_two, twos_complement(-2, 16).unwrap());
+    }
+
+    #[test]
+    fn value_does_not_fit_into_twos_complement() {
+        assert!(twos_complement(128, 1).is_err());
+        assert!(twos_complement(-129, 1).is_err());
+        assert_eq!(vec![0x7f], twos_complement(127, 1).unwrap());
+        assert_eq!(vec![0x80], twos_complement(-128, 1).unwrap());
     }
 }
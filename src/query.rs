@@ -1,101 +1,803 @@
+mod bad_rows;
 mod batch_size_limit;
 mod binary;
+mod blob;
 mod boolean;
+mod catalog_search;
+mod client_sort;
 mod column_strategy;
+mod conversion_audit;
 mod current_file;
 mod date;
+mod ddl;
+mod deadlock;
 mod decimal;
+mod delimited;
+mod delta;
+mod derive;
+mod driver_info;
+mod fail_on_null;
+mod geoparquet;
+mod hadoop_crc;
 mod identical;
+mod metrics;
+mod nondeterministic;
+mod null_fill;
+mod nullable_from_data;
+mod numeric;
+mod output_dir;
+mod padding;
+mod parameter_file;
 mod parquet_writer;
+mod partition_by_date;
+mod profile;
+mod quirks;
+mod read_only;
+mod round_trip;
+mod schema_cache;
+mod stats_only;
+mod stats_parquet;
+mod table_select;
 mod table_strategy;
+mod target_schema;
 mod text;
+mod throttle;
 mod time;
 mod timestamp;
+mod timestamp_in_zone;
 mod timestamp_precision;
 mod timestamp_tz;
+mod transform_script;
 
-use anyhow::Error;
+use anyhow::{anyhow, bail, Context, Error};
+use bytesize::ByteSize;
 use io_arg::IoArg;
-use log::info;
-use odbc_api::{Cursor, Environment, IntoParameter};
-use std::io::{stdin, Read};
+use log::{debug, info, warn};
+use odbc_api::{
+    handles::{AsStatementRef, Record, Statement, StatementImpl},
+    sys::{self, Pointer, SqlReturn, StatementAttribute},
+    Connection, Cursor, CursorImpl, Environment, IntoParameter, ParameterCollectionRef,
+    Preallocated, RowSetBuffer,
+};
+use parquet::file::metadata::KeyValue;
+use std::{
+    io::{stdin, Read},
+    sync::Arc,
+    thread::sleep,
+    time::Instant,
+};
 
 use self::{
-    batch_size_limit::{BatchSizeLimit, FileSizeLimit},
+    bad_rows::BadRowsFile,
+    batch_size_limit::{BatchSizeLimit, FileSizeLimit, MaxFileCount},
+    binary::EmptyBinary,
+    blob::BlobExtraction,
+    catalog_search::CatalogSearch,
     column_strategy::{ColumnStrategy, MappingOptions},
-    parquet_writer::{parquet_output, ParquetWriterOptions},
-    table_strategy::TableStrategy,
+    ddl::Ddl,
+    deadlock::{is_deadlock_victim, retry_delay},
+    delimited::{DelimitedFormat, JsonFormat, TextCompression},
+    delta::DeltaAgainst,
+    driver_info::{driver_name_and_version, search_pattern_escape},
+    metrics::Metrics,
+    nondeterministic::nondeterministic_function,
+    output_dir::output_in_dir,
+    padding::Padding,
+    parameter_file::read_parameter_file,
+    parquet_writer::{
+        parquet_output, CategoryCompression, CreatedFiles, OutputSize, ParquetWriterOptions,
+    },
+    quirks::Quirks,
+    read_only::check_read_only,
+    round_trip::round_trip_check,
+    schema_cache::SchemaCache,
+    table_select::{escape_pattern, quote_identifier, TableSelect},
+    table_strategy::{RowGroupOptions, TableStrategy},
+    target_schema::TargetSchema,
+    text::NullTokens,
+    throttle::Throttle,
+    transform_script::TransformScript,
 };
 
-use crate::{open_connection, QueryOpt};
+pub use self::{
+    batch_size_limit::MaxFileCountReached,
+    derive::{derive_from_str, Derivation},
+    quirks::Quirk,
+    stats_only::json_string,
+};
+
+use crate::{
+    enum_args::{BadRowAction, OutputFormat},
+    open_connection, DescribeParametersOpt, QueryOpt,
+};
 
 /// Execute a query and writes the result to parquet.
 pub fn query(environment: &Environment, opt: QueryOpt) -> Result<(), Error> {
+    // Measured for `--metrics-file`.
+    let start = Instant::now();
     let QueryOpt {
         connect_opts,
+        job_file: _,
         output,
+        output_dir,
         parameters,
         query,
+        query_text,
+        parameter,
         batch_size_row,
         batch_size_memory,
+        memory_limit,
         row_groups_per_file,
         file_size_threshold,
+        max_file_count,
+        on_max_files,
         encoding,
         prefer_varbinary,
+        fetch_as_text_all,
+        preserve_charset,
         column_compression_default,
         column_compression_level_default,
+        compression_strings,
+        compression_numeric,
+        compression_binary,
         parquet_column_encoding,
+        column_path_separator,
+        sorted_by,
+        page_row_count_limit,
+        page_index,
+        column_index_truncate_length,
         avoid_decimal,
         driver_does_not_support_64bit_integers,
+        ignore_driver_quirk,
         suffix_length,
         no_empty_file,
+        stats_only,
+        format,
+        csv_delimiter,
+        csv_quote,
+        csv_null,
+        csv_header,
+        csv_date_format,
+        csv_time_format,
+        csv_timestamp_format,
+        csv_decimal_separator,
+        json_decimal,
+        json_nulls,
+        text_compression,
+        column_nullable_from_data,
+        force_nullable_all,
+        force_required_all,
+        stable_sort,
+        stable_sort_client,
+        delta_against,
+        key_column,
+        delta_op_column,
+        delta_sorted,
+        partition_by_date,
+        max_open_partitions,
+        partition_concurrency,
+        extension,
+        skip_bad_rows,
+        bad_row_action,
+        bad_rows_file,
+        verify_row_count,
+        count_query,
+        row_count_tolerance,
         column_length_limit,
+        column_max_length,
+        timestamp_tz_offset_column,
+        schema_from,
+        schema_match_by,
+        emit_ddl,
+        table_name,
+        ddl_dialect,
+        coerce_date_out_of_range,
+        ts_tz_from,
+        unknown_timezone,
+        reorder_for_size,
+        decimal_byte_length,
+        decimal_rounding,
+        decimal_validate_precision,
+        extract_blobs,
+        blob_dir,
+        blob_key_column,
+        unsigned_column_order,
+        char_blank_as_null,
+        null_string_tokens,
+        null_string_tokens_ignore_case,
+        date_as_timestamp,
+        fast_decimals,
+        numeric_decimals,
+        coerce_bool_from_int,
+        coerce_int_from_bool,
+        coerce_empty_binary_as_null,
+        coerce_null_binary_as_empty,
+        geometry_column,
+        geometry_crs,
+        max_rows_per_second,
+        profile,
+        group_count,
+        group_count_max_values,
+        sample_values,
+        no_sample_columns,
+        metrics_file,
+        metrics_job,
+        limit,
+        compression_threads,
+        pad_to_rows,
+        pad_value,
+        pad_flag_column,
+        pad_strict,
+        debug_row_groups,
+        also_write,
+        emit_hadoop_crc,
+        emit_stats_parquet,
+        stats_parquet_columns,
+        schema_cache,
+        strict_schema,
+        skip_columns_on_error,
+        type_rule,
+        bind_as,
+        group_columns,
+        derive,
+        null_fill,
+        fail_fast_on_null,
+        column_comment,
+        include_ddl_comments_from_catalog,
+        warn_on_implicit_conversion,
+        transform_script,
+        schema_name,
+        rowid_column,
+        max_binary_length,
+        timestamp_int96,
+        int96_columns,
+        parameter_file,
+        parameter_columns,
+        round_trip_check: round_trip_rows,
+        retry_on_deadlock,
+        nondeterministic,
+        query_prefix,
+        query_suffix,
+        read_only,
+        table,
+        schema,
+        catalog,
+        catalog_function,
+        table_pattern,
+        column_pattern,
+        table_type,
     } = opt;
 
     let batch_size = BatchSizeLimit::new(batch_size_row, batch_size_memory);
     let file_size = FileSizeLimit::new(row_groups_per_file, file_size_threshold);
-    let query = query_statement_text(query)?;
+    // `--query` and `--parameter` conflict with their positional counterparts.
+    let query = query.or(query_text);
+    let parameters: Vec<_> = parameters.into_iter().chain(parameter).collect();
+    let query = query.map(query_statement_text).transpose()?;
+    if read_only {
+        for statement in query.iter().chain(&count_query) {
+            check_read_only(statement)?;
+        }
+    }
+    // Clap ensures exactly one of the output argument and `--output-dir` is present.
+    let output = match (output, &output_dir) {
+        (Some(output), _) => output,
+        (None, Some(dir)) => {
+            let table = table.as_ref().map(|table| match &schema {
+                Some(schema) => format!("{schema}.{table}"),
+                None => table.clone(),
+            });
+            output_in_dir(
+                dir,
+                table.as_deref(),
+                query.as_deref().unwrap_or_default(),
+                &parameters,
+                &extension,
+            )?
+        }
+        (None, None) => bail!("An output is required, unless --output-dir is specified."),
+    };
+    let schema_cache = match (schema_cache, &output) {
+        (Some(path), _) => Some(path),
+        (None, IoArg::File(path)) if strict_schema => {
+            let mut name = path.file_name().unwrap_or_default().to_owned();
+            name.push(".schema.json");
+            Some(path.with_file_name(name))
+        }
+        (None, IoArg::StdStream) if strict_schema => {
+            bail!("--strict-schema requires --schema-cache if writing to standard out.")
+        }
+        (None, _) => None,
+    }
+    .map(|path| SchemaCache {
+        path,
+        strict: strict_schema,
+    });
+    // Read the target schema before executing the query, so we fail fast if it can not be read.
+    let target_schema = schema_from
+        .map(|path| TargetSchema::from_file(&path, schema_match_by))
+        .transpose()?;
 
-    // Convert the input strings into parameters suitable for use with ODBC.
-    let params: Vec<_> = parameters
-        .iter()
-        .map(|param| param.as_str().into_parameter())
-        .collect();
+    let blob_extraction = match (blob_dir, blob_key_column) {
+        (Some(dir), Some(key_column)) => Some(BlobExtraction {
+            columns: extract_blobs,
+            dir,
+            key_column,
+        }),
+        (None, None) if extract_blobs.is_empty() => None,
+        _ => bail!("`--extract-blobs` requires both `--blob-dir` and `--blob-key-column`."),
+    };
+
+    let parameter_sets = if let Some(path) = &parameter_file {
+        read_parameter_file(path)?
+    } else {
+        vec![parameters]
+    };
 
     let odbc_conn = open_connection(environment, &connect_opts)?;
     let db_name = odbc_conn.database_management_system_name()?;
     info!("Database Management System Name: {db_name}");
+    match driver_name_and_version(&odbc_conn) {
+        Ok((name, version)) => info!("ODBC driver: {name}, version {version}"),
+        // Merely informational, so we do not fail the export over it.
+        Err(error) => warn!("Could not determine name and version of the ODBC driver: {error}"),
+    }
+    let catalog_search = catalog_function
+        .map(|function| -> Result<_, Error> {
+            Ok(CatalogSearch {
+                function,
+                catalog: match &catalog {
+                    Some(catalog) => catalog.clone(),
+                    None => odbc_conn.current_catalog()?,
+                },
+                schema: match schema.as_deref() {
+                    Some(schema) => escape_pattern(schema, &search_pattern_escape(&odbc_conn)?),
+                    None => "%".to_owned(),
+                },
+                table: table_pattern.unwrap_or_else(|| "%".to_owned()),
+                column: column_pattern.unwrap_or_else(|| "%".to_owned()),
+                table_type: table_type.unwrap_or_default(),
+            })
+        })
+        .transpose()?;
+    let mut catalog_comments = Vec::new();
+    let query = match (query, table) {
+        (Some(query), _) => query,
+        // The catalog function is executed instead of a query.
+        (None, None) if catalog_search.is_some() => String::new(),
+        (None, Some(table)) => {
+            let table_select = TableSelect {
+                table,
+                schema,
+                catalog,
+            };
+            let query = table_select.statement(&odbc_conn, &db_name)?;
+            info!("Query: {query}");
+            if include_ddl_comments_from_catalog {
+                match table_select.column_comments(&odbc_conn, &db_name) {
+                    Ok(comments) => {
+                        info!("Found {} column comments in the catalog.", comments.len());
+                        catalog_comments = comments;
+                    }
+                    Err(error) => {
+                        warn!("Could not fetch column comments from the catalog: {error:#}")
+                    }
+                }
+            }
+            query
+        }
+        (None, None) => unreachable!("Either query or table is required"),
+    };
+
+    let mut key_value_metadata = Vec::new();
+    if let Some(charset) = &preserve_charset {
+        key_value_metadata.push(KeyValue::new(
+            "odbc2parquet.charset".to_owned(),
+            charset.clone(),
+        ));
+    }
+
+    let quirks = Quirks::detect(&db_name, &ignore_driver_quirk);
 
     let parquet_format_options = ParquetWriterOptions {
         column_compression_default: column_compression_default
             .to_compression(column_compression_level_default)?,
+        category_compression: CategoryCompression {
+            strings: compression_strings
+                .map(|c| c.to_compression(column_compression_level_default))
+                .transpose()?,
+            numeric: compression_numeric
+                .map(|c| c.to_compression(column_compression_level_default))
+                .transpose()?,
+            binary: compression_binary
+                .map(|c| c.to_compression(column_compression_level_default))
+                .transpose()?,
+        },
         column_encodings: parquet_column_encoding,
+        column_path_separator,
+        sorted_by,
+        key_value_metadata,
+        column_comments: column_comment,
+        geometry_columns: geometry_column.clone(),
+        geometry_crs,
+        catalog_comments,
         file_size,
+        max_file_count: max_file_count.map(|max_files| MaxFileCount::new(max_files, on_max_files)),
         suffix_length,
         no_empty_file,
+        stats_only,
+        nullable_from_data: column_nullable_from_data,
+        client_sort: if stable_sort_client {
+            stable_sort.clone()
+        } else {
+            Vec::new()
+        },
+        partition_by_date,
+        max_open_partitions,
+        partition_concurrency,
+        extension: extension.clone(),
+        delta_against: delta_against.map(|previous| DeltaAgainst {
+            previous,
+            key_columns: key_column,
+            op_column: delta_op_column,
+            sorted: delta_sorted,
+        }),
+        delimited: match format {
+            OutputFormat::Parquet => None,
+            OutputFormat::Ndjson => Some(DelimitedFormat {
+                delimiter: ',',
+                quote: csv_quote,
+                null: csv_null,
+                header: csv_header,
+                date_format: JsonFormat::DATE_FORMAT.to_owned(),
+                time_format: JsonFormat::TIME_FORMAT.to_owned(),
+                timestamp_format: JsonFormat::TIMESTAMP_FORMAT.to_owned(),
+                decimal_separator: '.',
+                json: Some(JsonFormat {
+                    decimal: json_decimal,
+                    nulls: json_nulls,
+                }),
+                compression: TextCompression::new(
+                    text_compression,
+                    column_compression_level_default,
+                )?,
+            }),
+            OutputFormat::Csv | OutputFormat::Tsv => Some(DelimitedFormat {
+                delimiter: csv_delimiter.unwrap_or(if format == OutputFormat::Tsv {
+                    '\t'
+                } else {
+                    ','
+                }),
+                quote: csv_quote,
+                null: csv_null,
+                header: csv_header,
+                date_format: csv_date_format,
+                time_format: csv_time_format,
+                timestamp_format: csv_timestamp_format,
+                decimal_separator: csv_decimal_separator,
+                json: None,
+                compression: TextCompression::new(
+                    text_compression,
+                    column_compression_level_default,
+                )?,
+            }),
+        },
+        also_write: also_write
+            .into_iter()
+            .map(|(codec, path)| {
+                Ok((
+                    codec.to_compression(column_compression_level_default)?,
+                    path,
+                ))
+            })
+            .collect::<Result<_, Error>>()?,
+        ddl: emit_ddl.map(|path| Ddl {
+            path,
+            table_name: table_name.expect("Table name is required by DDL output"),
+            dialect: ddl_dialect,
+        }),
+        emit_hadoop_crc,
+        schema_cache,
+        emit_stats_parquet,
+        stats_parquet_columns,
+        created_files: CreatedFiles::default(),
+        page_row_count_limit,
+        page_index,
+        column_index_truncate_length,
     };
 
+    let null_tokens = NullTokens::new(null_string_tokens, null_string_tokens_ignore_case);
+
     let mapping_options = MappingOptions {
         db_name: &db_name,
         use_utf16: encoding.use_utf16(),
         prefer_varbinary,
         avoid_decimal,
-        driver_does_support_i64: !driver_does_not_support_64bit_integers,
+        driver_does_support_i64: !(driver_does_not_support_64bit_integers
+            || quirks.no_64bit_integers),
+        unsigned_bigint: quirks.unsigned_bigint,
+        unbounded_numeric: quirks.unbounded_numeric,
         column_length_limit,
+        column_max_length: &column_max_length,
+        timestamp_tz_offset_column,
+        preserve_charset: preserve_charset.is_some(),
+        fetch_as_text_all,
+        target_schema: target_schema.as_ref(),
+        date_out_of_range: coerce_date_out_of_range,
+        timestamp_tz_from: &ts_tz_from,
+        unknown_time_zone: unknown_timezone,
+        reorder_for_size,
+        fail_on_null: &fail_fast_on_null,
+        decimal_byte_length: decimal_byte_length.map(usize::from),
+        decimal_rounding,
+        decimal_validate_precision,
+        blob_extraction: blob_extraction.as_ref(),
+        unsigned_column_order,
+        char_blank_as_null,
+        skip_columns_on_error,
+        type_rules: &type_rule,
+        null_fill: &null_fill,
+        force_nullable_all,
+        force_required_all,
+        max_binary_length,
+        timestamp_int96,
+        int96_columns: &int96_columns,
+        null_tokens: &null_tokens,
+        date_as_timestamp,
+        fast_decimals,
+        numeric_decimals,
+        bool_from_int: &coerce_bool_from_int,
+        int_from_bool: &coerce_int_from_bool,
+        geometry_columns: &geometry_column,
+        empty_binary: match (coerce_empty_binary_as_null, coerce_null_binary_as_empty) {
+            (true, _) => EmptyBinary::AsNull,
+            (false, true) => EmptyBinary::NullAsEmpty,
+            (false, false) => EmptyBinary::Keep,
+        },
+        bind_as: &bind_as,
+        column_groups: &group_columns,
+        derive: &derive,
+        warn_on_implicit_conversion,
+        schema_name: &schema_name,
+        rowid_column: rowid_column.as_deref(),
+    };
+
+    let row_group_options = RowGroupOptions {
+        skip_bad_rows,
+        drop_bad_rows: bad_row_action == BadRowAction::Drop,
+        max_rows: batch_size.max_rows(),
+        debug_row_groups,
+        throttle: max_rows_per_second.map(Throttle::new),
+        profile,
+        group_count,
+        group_count_max_values,
+        sample_values,
+        no_sample_columns,
+        padding: pad_to_rows.map(|num_rows| Padding {
+            num_rows,
+            values: pad_value,
+            flag_column: pad_flag_column,
+            strict: pad_strict,
+        }),
+        limit,
+        compression_threads,
+        bad_rows_file: bad_rows_file
+            .as_deref()
+            .map(BadRowsFile::create)
+            .transpose()?
+            .map(Arc::new),
+        transform_script: transform_script
+            .as_deref()
+            .map(TransformScript::load)
+            .transpose()?
+            .map(Arc::new),
+    };
+
+    // Remember the path of the output, before it is consumed by the writer, so we can read it back.
+    let output_path = match &output {
+        IoArg::File(path) => Some(path.clone()),
+        IoArg::StdStream => None,
+    };
+
+    // The count query and the detection of nondeterministic functions refer to the query as
+    // written.
+    let executed_query = if stable_sort.is_empty() || stable_sort_client {
+        query.clone()
+    } else {
+        let sorted = sorted_query_text(&query, &stable_sort, &db_name);
+        info!("Query: {sorted}");
+        sorted
+    };
+    let executed_query = if query_prefix.is_some() || query_suffix.is_some() {
+        let wrapped = wrapped_query_text(
+            query_prefix.as_deref(),
+            &executed_query,
+            query_suffix.as_deref(),
+        );
+        info!("Query: {wrapped}");
+        if read_only {
+            check_read_only(&wrapped)?;
+        }
+        wrapped
+    } else {
+        executed_query
+    };
+    let executions = Executions {
+        conn: &odbc_conn,
+        query: &executed_query,
+        parameter_sets,
+        parameter_columns,
+        limit,
+        catalog_search,
+        use_bookmarks: rowid_column.is_some(),
     };
 
-    if let Some(cursor) = odbc_conn.execute(&query, params.as_slice())? {
-        cursor_to_parquet(
-            cursor,
-            output,
+    // Values of nondeterministic queries differ between attempts, so files of a failed attempt can
+    // not be mixed with the ones of the next attempt. Even if they would be overwritten, the failed
+    // attempt may have been split into more files.
+    let discard_failed_attempts = retry_on_deadlock != 0
+        && (nondeterministic
+            || nondeterministic_function(&query).is_some_and(|function| {
+                info!(
+                    "Query calls nondeterministic function {function}. Files of failed attempts \
+                    are removed before retrying."
+                );
+                true
+            }));
+    let created_files = parquet_format_options.created_files.clone();
+    let mut num_failed_attempts = 0;
+    let (num_rows_written, output_size) = loop {
+        // Each attempt starts from scratch, overwriting the output of previous attempts.
+        if num_failed_attempts != 0 {
+            if let Some(bad_rows_file) = &row_group_options.bad_rows_file {
+                bad_rows_file.truncate()?;
+            }
+        }
+        let result = cursor_to_parquet(
+            &executions,
+            output.clone(),
             batch_size,
+            memory_limit,
             mapping_options,
-            parquet_format_options,
-        )?;
-    } else {
-        eprintln!(
-            "Query came back empty (not even a schema has been returned). No file has been created"
+            parquet_format_options.clone(),
+            row_group_options.clone(),
         );
+        match result {
+            Err(error) if num_failed_attempts < retry_on_deadlock && is_deadlock_victim(&error) => {
+                num_failed_attempts += 1;
+                let delay = retry_delay(num_failed_attempts);
+                warn!(
+                    "Query has been chosen as deadlock victim. Restarting the export in {} ms \
+                    (retry {num_failed_attempts} of {retry_on_deadlock}). Error: {error:#}",
+                    delay.as_millis()
+                );
+                if discard_failed_attempts {
+                    created_files.remove_all()?;
+                } else {
+                    created_files.clear();
+                }
+                sleep(delay);
+            }
+            result => break result?,
+        }
+    };
+
+    if verify_row_count || count_query.is_some() {
+        let count_query = count_query.unwrap_or_else(|| count_query_text(&query));
+        // Convert the input strings into parameters suitable for use with ODBC.
+        let params: Vec<_> = executions.parameter_sets[0]
+            .iter()
+            .map(|param| param.as_str().into_parameter())
+            .collect();
+        let num_rows_counted = count_rows(&odbc_conn, &count_query, params.as_slice())?;
+        info!(
+            "Count query returned {num_rows_counted} rows. {num_rows_written} have been written."
+        );
+        if num_rows_counted.abs_diff(num_rows_written) > row_count_tolerance {
+            bail!(
+                "Row count verification failed. {num_rows_written} rows have been written, but the \
+                count query returned {num_rows_counted} rows. Count query: {count_query}"
+            )
+        }
+    }
+
+    if let (Some(num_rows), Some(path)) = (round_trip_rows, output_path) {
+        let mut statement = executions.statement()?;
+        let cursor = executions.execute(&mut statement, 0)?.ok_or_else(|| {
+            anyhow!("Query did not return a result set for the round trip check.")
+        })?;
+        round_trip_check(cursor, &path, num_rows)?;
+    }
+
+    if let Some(path) = metrics_file {
+        let metrics = Metrics {
+            num_rows: num_rows_written,
+            duration: start.elapsed(),
+            output: output_size,
+        };
+        metrics.write(&path, metrics_job.as_deref())?;
+    }
+    Ok(())
+}
+
+/// Derive a query counting the rows of the result set by wrapping the original query.
+fn count_query_text(query: &str) -> String {
+    let query = query.trim().trim_end_matches(';');
+    format!("SELECT COUNT(*) FROM ({query}) t")
+}
+
+/// Put `prefix` and `suffix` around the query, each separated by a line break. A trailing semicolon
+/// of the query would end the statement before the suffix, so it is removed.
+fn wrapped_query_text(prefix: Option<&str>, query: &str, suffix: Option<&str>) -> String {
+    let mut text = String::new();
+    if let Some(prefix) = prefix {
+        text.push_str(prefix);
+        text.push('\n');
+    }
+    match suffix {
+        Some(suffix) => {
+            text.push_str(query.trim_end().trim_end_matches(';'));
+            text.push('\n');
+            text.push_str(suffix);
+        }
+        None => text.push_str(query),
+    }
+    text
+}
+
+/// Derive a query returning the result set ordered by `columns`, by wrapping the original query.
+fn sorted_query_text(query: &str, columns: &[String], db_name: &str) -> String {
+    let query = query.trim().trim_end_matches(';');
+    let columns: Vec<_> = columns
+        .iter()
+        .map(|column| quote_identifier(column, db_name))
+        .collect();
+    format!("SELECT * FROM ({query}) t ORDER BY {}", columns.join(", "))
+}
+
+/// Execute a query and interpret the first column of its first row as a number of rows.
+fn count_rows(
+    conn: &Connection,
+    count_query: &str,
+    params: impl ParameterCollectionRef,
+) -> Result<u64, Error> {
+    let mut cursor = conn
+        .execute(count_query, params)?
+        .ok_or_else(|| anyhow!("Count query did not return a result set."))?;
+    let mut row = cursor
+        .next_row()?
+        .ok_or_else(|| anyhow!("Count query did not return any rows."))?;
+    let mut text = Vec::new();
+    row.get_text(1, &mut text)?;
+    let text = String::from_utf8_lossy(&text);
+    let count = text
+        .trim()
+        .parse()
+        .with_context(|| format!("Count query returned '{text}', which is not a row count."))?;
+    Ok(count)
+}
+
+/// Prepare the query and print the description of each parameter as reported by the driver.
+pub fn describe_parameters(
+    environment: &Environment,
+    opt: DescribeParametersOpt,
+) -> Result<(), Error> {
+    let DescribeParametersOpt {
+        connect_opts,
+        query,
+    } = opt;
+    let query = query_statement_text(query)?;
+    let odbc_conn = open_connection(environment, &connect_opts)?;
+    let mut prepared = odbc_conn.prepare(&query)?;
+    let num_params = prepared.num_params()?;
+    if num_params == 0 {
+        println!("Query has no parameters.");
+    }
+    for parameter_number in 1..=num_params {
+        let description = prepared.describe_param(parameter_number)?;
+        println!("Parameter {parameter_number}:");
+        println!("\tData type: {:?}", description.data_type);
+        println!("\tNullability: {:?}", description.nullability);
     }
     Ok(())
 }
@@ -113,18 +815,243 @@ fn query_statement_text(query: String) -> Result<String, Error> {
     })
 }
 
+/// Executions of the query, whose result sets are written into the output. Either a single one with
+/// the parameters passed at the command line, or one for each line of the parameter file.
+struct Executions<'a> {
+    conn: &'a Connection<'a>,
+    query: &'a str,
+    /// Positional parameters for each execution.
+    parameter_sets: Vec<Vec<String>>,
+    /// Add the parameter values as columns to the output.
+    parameter_columns: bool,
+    /// Maximum number of rows requested from the data source, if any.
+    limit: Option<u64>,
+    /// Executed instead of the query, if specified.
+    catalog_search: Option<CatalogSearch>,
+    /// Ask the driver for variable length bookmarks, in order to write them with `--rowid-column`.
+    use_bookmarks: bool,
+}
+
+impl<'a> Executions<'a> {
+    /// Allocate the statement the query is executed with. If a limit is specified, the driver is
+    /// asked to stop each result set after that many rows.
+    fn statement(&self) -> Result<Preallocated<'a>, Error> {
+        let mut statement = self.conn.preallocate()?;
+        // For ODBC a maximum of zero rows means no limit at all.
+        if let Some(limit) = self.limit.filter(|&limit| limit != 0) {
+            if set_max_rows(&mut statement, limit) {
+                info!("The data source has been asked to stop the result set after {limit} rows.");
+            } else {
+                info!(
+                    "The driver does not support limiting the number of rows of a result set. \
+                    Rows beyond {limit} are fetched, but discarded."
+                );
+            }
+        }
+        if self.use_bookmarks && !use_variable_bookmarks(&mut statement) {
+            debug!("The driver rejected enabling bookmarks.");
+        }
+        Ok(statement)
+    }
+
+    fn execute<'s>(
+        &self,
+        statement: &'s mut Preallocated<'a>,
+        index: usize,
+    ) -> Result<Option<CursorImpl<&'s mut StatementImpl<'a>>>, Error> {
+        if let Some(catalog_search) = &self.catalog_search {
+            return catalog_search.execute(statement).map(Some);
+        }
+        // Convert the input strings into parameters suitable for use with ODBC.
+        let params: Vec<_> = self.parameter_sets[index]
+            .iter()
+            .map(|param| param.as_str().into_parameter())
+            .collect();
+        Ok(statement.execute(self.query, params.as_slice())?)
+    }
+}
+
+/// Set `SQL_ATTR_MAX_ROWS`, so the data source stops producing rows once `limit` is reached.
+/// `false` if the driver did not accept the value as is.
+fn set_max_rows(statement: &mut Preallocated, limit: u64) -> bool {
+    let statement = statement.as_stmt_ref();
+    let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+    // Safety: `statement` is a valid statement handle and `MaxRows` expects an integer passed as
+    // pointer.
+    let ret = unsafe {
+        sys::SQLSetStmtAttr(
+            statement.as_sys(),
+            StatementAttribute::MaxRows,
+            limit as Pointer,
+            0,
+        )
+    };
+    // `SUCCESS_WITH_INFO` indicates the driver substituted a different value.
+    ret == SqlReturn::SUCCESS
+}
+
+/// Set `SQL_ATTR_USE_BOOKMARKS` to `SQL_UB_VARIABLE`, so bookmarks can be fetched as column zero.
+/// `false` if the driver did not accept it.
+fn use_variable_bookmarks(statement: &mut Preallocated) -> bool {
+    const SQL_UB_VARIABLE: usize = 2;
+    let statement = statement.as_stmt_ref();
+    // Safety: `statement` is a valid statement handle and `UseBookmarks` expects an integer passed
+    // as pointer.
+    let ret = unsafe {
+        sys::SQLSetStmtAttr(
+            statement.as_sys(),
+            StatementAttribute::UseBookmarks,
+            SQL_UB_VARIABLE as Pointer,
+            0,
+        )
+    };
+    ret == SqlReturn::SUCCESS
+}
+
+/// Smallest batch size tried, if the driver rejects the row array size of the fetch buffer.
+const MIN_ROW_ARRAY_SIZE: usize = 16;
+
+/// Largest row array size up to `requested`, which the driver accepts. Some drivers reject large
+/// row array sizes with an error, rather than clamping them. The size is halved until the driver
+/// accepts it, giving up below [`MIN_ROW_ARRAY_SIZE`].
+fn supported_row_array_size(
+    cursor: &mut impl AsStatementRef,
+    requested: usize,
+) -> Result<usize, Error> {
+    let mut size = requested;
+    loop {
+        let mut statement = cursor.as_stmt_ref();
+        // Safety: No buffers are bound to the cursor yet. Binding the fetch buffer sets the row
+        // array size again, to the capacity of the buffer.
+        if !unsafe { statement.set_row_array_size(size) }.is_err() {
+            return Ok(size);
+        }
+        let mut record = Record::default();
+        record.fill_from(&statement, 1);
+        let error = odbc_api::Error::Diagnostics {
+            record,
+            function: "SQLSetStmtAttr",
+        };
+        if size / 2 < MIN_ROW_ARRAY_SIZE {
+            let message = if size == requested {
+                format!("The driver rejected a batch size of {size} rows.")
+            } else {
+                format!(
+                    "The driver rejected a batch size of {requested} rows, as well as each smaller \
+                    one tried down to {size} rows."
+                )
+            };
+            return Err(Error::from(error).context(message));
+        }
+        warn!("The driver rejected a batch size of {size} rows: {error}");
+        size /= 2;
+    }
+}
+
 fn cursor_to_parquet(
-    mut cursor: impl Cursor,
+    executions: &Executions,
     path: IoArg,
     batch_size: BatchSizeLimit,
+    memory_limit: Option<ByteSize>,
     mapping_options: MappingOptions,
-    parquet_format_options: ParquetWriterOptions,
-) -> Result<(), Error> {
-    let table_strategy = TableStrategy::new(&mut cursor, mapping_options)?;
-    let mut odbc_buffer = table_strategy.allocate_fetch_buffer(batch_size)?;
-    let block_cursor = cursor.bind_buffer(&mut odbc_buffer)?;
+    mut parquet_format_options: ParquetWriterOptions,
+    row_group_options: RowGroupOptions,
+) -> Result<(u64, OutputSize), Error> {
+    let mut statement = executions.statement()?;
+    let Some(mut cursor) = executions.execute(&mut statement, 0)? else {
+        eprintln!(
+            "Query came back empty (not even a schema has been returned). No file has been created"
+        );
+        return Ok((0, OutputSize::default()));
+    };
+    let mut table_strategy = TableStrategy::new(&mut cursor, mapping_options)?;
+    if executions.parameter_columns {
+        let names = (1..=executions.parameter_sets[0].len())
+            .map(|n| format!("parameter_{n}"))
+            .collect();
+        table_strategy.add_parameter_columns(names)?;
+    }
+    if let Some(name) = row_group_options
+        .padding
+        .as_ref()
+        .and_then(|padding| padding.flag_column.clone())
+    {
+        table_strategy.add_pad_flag_column(name)?;
+    }
+    let compression_threads = row_group_options.compression_threads;
+    let mut odbc_buffer =
+        table_strategy.allocate_fetch_buffer(batch_size, memory_limit, compression_threads)?;
+    let row_array_size = supported_row_array_size(&mut cursor, odbc_buffer.row_array_size())?;
+    if row_array_size < odbc_buffer.row_array_size() {
+        info!("Batch size reduced to {row_array_size} rows, which the driver accepts.");
+        odbc_buffer = table_strategy.allocate_fetch_buffer(
+            BatchSizeLimit::Rows(row_array_size),
+            None,
+            compression_threads,
+        )?;
+    }
     let parquet_schema = table_strategy.parquet_schema();
+    parquet_format_options
+        .key_value_metadata
+        .extend(table_strategy.original_column_order());
     let writer = parquet_output(path, parquet_schema.clone(), parquet_format_options)?;
-    table_strategy.block_cursor_to_parquet(block_cursor, writer)?;
-    Ok(())
+    let mut export =
+        table_strategy.export(writer, row_group_options, odbc_buffer.row_array_size())?;
+    let parameter_sets = &executions.parameter_sets;
+    export.write_cursor(&parameter_sets[0], cursor, &mut odbc_buffer)?;
+    for (index, parameters) in parameter_sets.iter().enumerate().skip(1) {
+        info!("Executing query with parameter set {}.", index + 1);
+        let mut cursor = executions.execute(&mut statement, index)?.ok_or_else(|| {
+            anyhow!(
+                "Query did not return a result set for parameter set {}.",
+                index + 1
+            )
+        })?;
+        table_strategy
+            .check_same_schema(&mut cursor)
+            .with_context(|| format!("Schema differs for parameter set {}.", index + 1))?;
+        export.write_cursor(parameters, cursor, &mut odbc_buffer)?;
+    }
+    export.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{count_query_text, sorted_query_text, wrapped_query_text};
+
+    #[test]
+    fn wrap_query_to_count_rows() {
+        assert_eq!(
+            "SELECT COUNT(*) FROM (SELECT a FROM b) t",
+            count_query_text("SELECT a FROM b;\n")
+        );
+    }
+
+    #[test]
+    fn wrap_query_to_sort_rows() {
+        assert_eq!(
+            "SELECT * FROM (SELECT a, b FROM c) t ORDER BY [a], [b]",
+            sorted_query_text(
+                "SELECT a, b FROM c;",
+                &["a".to_owned(), "b".to_owned()],
+                "Microsoft SQL Server"
+            )
+        );
+    }
+
+    #[test]
+    fn wrap_query_in_prefix_and_suffix() {
+        assert_eq!(
+            "SELECT a FROM b -- all\nOPTION (MAXDOP 1)",
+            wrapped_query_text(None, "SELECT a FROM b -- all", Some("OPTION (MAXDOP 1)"))
+        );
+        assert_eq!(
+            "SELECT a FROM b\nOPTION (MAXDOP 1)",
+            wrapped_query_text(None, "SELECT a FROM b;\n", Some("OPTION (MAXDOP 1)"))
+        );
+        assert_eq!(
+            "WITH c AS (SELECT 1 AS a)\nSELECT a FROM c;",
+            wrapped_query_text(Some("WITH c AS (SELECT 1 AS a)"), "SELECT a FROM c;", None)
+        );
+    }
 }
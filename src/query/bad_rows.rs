@@ -0,0 +1,276 @@
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::{Context, Error};
+use odbc_api::{
+    buffers::{AnyBuffer, AnySlice, BinColumn, BufferDesc, ColumnarAnyBuffer, TextColumn},
+    RowSetBuffer,
+};
+
+use super::stats_only::{hex, json_string};
+
+/// Dead letter file of `--bad-rows-file`. Each value written as NULL or whose row is dropped,
+/// because it could not be converted, is appended as one JSON object per line. Lines are written
+/// as soon as the value has been converted, so earlier entries survive a crash of the export.
+/// Shared between the threads converting the columns of a row group.
+pub struct BadRowsFile {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl BadRowsFile {
+    pub fn create(path: &Path) -> Result<Self, Error> {
+        let file = File::create(path)
+            .with_context(|| format!("Could not create bad rows file '{}'.", path.display()))?;
+        Ok(Self {
+            path: path.to_owned(),
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Creates the file anew, discarding the entries of a previous attempt of the export.
+    pub fn truncate(&self) -> Result<(), Error> {
+        *self.file.lock().unwrap() = File::create(&self.path).with_context(|| {
+            format!("Could not create bad rows file '{}'.", self.path.display())
+        })?;
+        Ok(())
+    }
+
+    /// Appends the value in row `row_index` of batch `num_batch`. `raw` is the fetched value, if
+    /// it is available as bytes.
+    pub fn append(
+        &self,
+        num_batch: u32,
+        row_index: usize,
+        column: &str,
+        raw: Option<Vec<u8>>,
+        error: &Error,
+    ) -> Result<(), Error> {
+        let (hex, text) = match &raw {
+            Some(raw) => (
+                json_string(&hex(raw)),
+                json_string(&String::from_utf8_lossy(raw)),
+            ),
+            None => ("null".to_owned(), "null".to_owned()),
+        };
+        let line = format!(
+            "{{\"batch\":{num_batch},\"row\":{row_index},\"column\":{},\"hex\":{hex},\
+            \"text\":{text},\"error\":{}}}\n",
+            json_string(column),
+            json_string(&format!("{error:#}"))
+        );
+        self.file
+            .lock()
+            .unwrap()
+            .write_all(line.as_bytes())
+            .with_context(|| format!("Could not write bad rows file '{}'.", self.path.display()))
+    }
+}
+
+/// Bytes of the value in row `row_index` as fetched from the data source. Text fetched as UTF-16 is
+/// transcoded into UTF-8. `None` for NULL and for all other buffers, whose values are never
+/// rejected as such.
+pub fn raw_value(column: AnySlice, row_index: usize) -> Option<Vec<u8>> {
+    match column {
+        AnySlice::Text(view) => view.get(row_index).map(<[u8]>::to_vec),
+        AnySlice::WText(view) => view
+            .get(row_index)
+            .map(|utf16| String::from_utf16_lossy(utf16).into_bytes()),
+        AnySlice::Binary(view) => view.get(row_index).map(<[u8]>::to_vec),
+        _ => None,
+    }
+}
+
+/// Copy of the rows in `buffer` for which `keep` is `true`. Used to leave out rows holding values
+/// which can not be converted.
+pub fn retain_rows(buffer: &ColumnarAnyBuffer, keep: &[bool]) -> ColumnarAnyBuffer {
+    let num_rows = keep.iter().filter(|&&keep| keep).count();
+    let columns = (0..buffer.num_cols())
+        .map(|buffer_index| {
+            let column = retain_in_column(buffer.column(buffer_index), keep, num_rows);
+            // The copy is never bound to a cursor, so the column numbers only need to be unique.
+            ((buffer_index + 1).try_into().unwrap(), column)
+        })
+        .collect();
+    let mut retained = ColumnarAnyBuffer::new(columns);
+    *retained.mut_num_fetch_rows() = num_rows;
+    retained
+}
+
+/// Nullable buffer of type `$desc` holding the values of `$values` in the rows to keep.
+macro_rules! retain_nullable {
+    ($variant:ident, $desc:ident, $values:expr, $keep:expr, $num_rows:expr) => {{
+        let mut column = AnyBuffer::from_desc($num_rows, BufferDesc::$desc { nullable: true });
+        let AnyBuffer::$variant(retained) = &mut column else {
+            unreachable!("Nullable buffer description must yield a nullable buffer.")
+        };
+        let mut writer = retained.writer_n($num_rows);
+        let values = $values
+            .zip($keep)
+            .filter_map(|(value, &keep)| keep.then_some(value));
+        for (index, value) in values.enumerate() {
+            writer.set_cell(index, value.copied());
+        }
+        column
+    }};
+}
+
+/// Copy of the values in `column` of the rows for which `keep` is `true`. `num_rows` is the number
+/// of these rows.
+pub fn retain_in_column(column: AnySlice, keep: &[bool], num_rows: usize) -> AnyBuffer {
+    let rows = || (0..keep.len()).filter(|&row_index| keep[row_index]);
+    match column {
+        AnySlice::Text(view) => {
+            let mut text = TextColumn::new(num_rows, view.max_len());
+            for (index, row_index) in rows().enumerate() {
+                text.set_value(index, view.get(row_index));
+            }
+            AnyBuffer::Text(text)
+        }
+        AnySlice::WText(view) => {
+            let mut text = TextColumn::new(num_rows, view.max_len());
+            for (index, row_index) in rows().enumerate() {
+                text.set_value(index, view.get(row_index));
+            }
+            AnyBuffer::WText(text)
+        }
+        AnySlice::Binary(view) => {
+            let max_len = rows()
+                .filter_map(|row_index| view.get(row_index))
+                .map(<[u8]>::len)
+                .max()
+                .unwrap_or(0);
+            let mut binary = BinColumn::new(num_rows, max_len);
+            for (index, row_index) in rows().enumerate() {
+                binary.set_value(index, view.get(row_index));
+            }
+            AnyBuffer::Binary(binary)
+        }
+        AnySlice::Date(values) => AnyBuffer::Date(rows().map(|i| values[i]).collect()),
+        AnySlice::Time(values) => AnyBuffer::Time(rows().map(|i| values[i]).collect()),
+        AnySlice::Timestamp(values) => AnyBuffer::Timestamp(rows().map(|i| values[i]).collect()),
+        AnySlice::F64(values) => AnyBuffer::F64(rows().map(|i| values[i]).collect()),
+        AnySlice::F32(values) => AnyBuffer::F32(rows().map(|i| values[i]).collect()),
+        AnySlice::I8(values) => AnyBuffer::I8(rows().map(|i| values[i]).collect()),
+        AnySlice::I16(values) => AnyBuffer::I16(rows().map(|i| values[i]).collect()),
+        AnySlice::I32(values) => AnyBuffer::I32(rows().map(|i| values[i]).collect()),
+        AnySlice::I64(values) => AnyBuffer::I64(rows().map(|i| values[i]).collect()),
+        AnySlice::U8(values) => AnyBuffer::U8(rows().map(|i| values[i]).collect()),
+        AnySlice::Bit(values) => AnyBuffer::Bit(rows().map(|i| values[i]).collect()),
+        AnySlice::NullableDate(values) => {
+            retain_nullable!(NullableDate, Date, values, keep, num_rows)
+        }
+        AnySlice::NullableTime(values) => {
+            retain_nullable!(NullableTime, Time, values, keep, num_rows)
+        }
+        AnySlice::NullableTimestamp(values) => {
+            retain_nullable!(NullableTimestamp, Timestamp, values, keep, num_rows)
+        }
+        AnySlice::NullableF64(values) => retain_nullable!(NullableF64, F64, values, keep, num_rows),
+        AnySlice::NullableF32(values) => retain_nullable!(NullableF32, F32, values, keep, num_rows),
+        AnySlice::NullableI8(values) => retain_nullable!(NullableI8, I8, values, keep, num_rows),
+        AnySlice::NullableI16(values) => retain_nullable!(NullableI16, I16, values, keep, num_rows),
+        AnySlice::NullableI32(values) => retain_nullable!(NullableI32, I32, values, keep, num_rows),
+        AnySlice::NullableI64(values) => retain_nullable!(NullableI64, I64, values, keep, num_rows),
+        AnySlice::NullableU8(values) => retain_nullable!(NullableU8, U8, values, keep, num_rows),
+        AnySlice::NullableBit(values) => retain_nullable!(NullableBit, Bit, values, keep, num_rows),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+    use odbc_api::{
+        buffers::{AnyBuffer, AnySlice, BufferDesc, ColumnarAnyBuffer, TextColumn},
+        RowSetBuffer,
+    };
+    use tempfile::tempdir;
+
+    use super::{retain_rows, BadRowsFile};
+
+    #[test]
+    fn append_json_lines() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("bad_rows.jsonl");
+        let bad_rows = BadRowsFile::create(&path).unwrap();
+
+        bad_rows
+            .append(
+                2,
+                7,
+                "a",
+                Some(b"12x".to_vec()),
+                &anyhow!("Not a \"number\"."),
+            )
+            .unwrap();
+        bad_rows
+            .append(3, 0, "b", None, &anyhow!("Out of range."))
+            .unwrap();
+
+        assert_eq!(
+            "{\"batch\":2,\"row\":7,\"column\":\"a\",\"hex\":\"0x313278\",\"text\":\"12x\",\
+            \"error\":\"Not a \\\"number\\\".\"}\n\
+            {\"batch\":3,\"row\":0,\"column\":\"b\",\"hex\":null,\"text\":null,\
+            \"error\":\"Out of range.\"}\n",
+            std::fs::read_to_string(path).unwrap()
+        );
+    }
+
+    #[test]
+    fn truncate_discards_previous_entries() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("bad_rows.jsonl");
+        let bad_rows = BadRowsFile::create(&path).unwrap();
+        bad_rows
+            .append(1, 0, "a", None, &anyhow!("First attempt."))
+            .unwrap();
+
+        bad_rows.truncate().unwrap();
+        bad_rows
+            .append(1, 0, "a", None, &anyhow!("Second attempt."))
+            .unwrap();
+
+        assert_eq!(
+            "{\"batch\":1,\"row\":0,\"column\":\"a\",\"hex\":null,\"text\":null,\
+            \"error\":\"Second attempt.\"}\n",
+            std::fs::read_to_string(path).unwrap()
+        );
+    }
+
+    #[test]
+    fn retain_rows_of_each_column() {
+        let mut text = TextColumn::new(3, 2);
+        text.set_value(0, Some(b"a"));
+        text.set_value(1, Some(b"bc"));
+        text.set_value(2, None);
+        let mut nullable = AnyBuffer::from_desc(3, BufferDesc::I32 { nullable: true });
+        let AnyBuffer::NullableI32(column) = &mut nullable else {
+            panic!("Expected nullable column.")
+        };
+        let mut writer = column.writer_n(3);
+        writer.set_cell(0, Some(1));
+        writer.set_cell(1, None);
+        writer.set_cell(2, Some(3));
+        let mut buffer = ColumnarAnyBuffer::new(vec![
+            (1, AnyBuffer::Text(text)),
+            (2, AnyBuffer::I64(vec![10, 20, 30])),
+            (3, nullable),
+        ]);
+        *buffer.mut_num_fetch_rows() = 3;
+
+        let retained = retain_rows(&buffer, &[true, false, true]);
+
+        assert_eq!(2, retained.num_rows());
+        let AnySlice::Text(text) = retained.column(0) else {
+            panic!("Expected text column.")
+        };
+        assert_eq!(vec![Some(&b"a"[..]), None], text.iter().collect::<Vec<_>>());
+        assert_eq!(&[10, 30], retained.column(1).as_slice::<i64>().unwrap());
+        let nullable = retained.column(2).as_nullable_slice::<i32>().unwrap();
+        assert_eq!(vec![Some(&1), Some(&3)], nullable.collect::<Vec<_>>());
+    }
+}
@@ -1,7 +1,11 @@
-use std::cmp::min;
+use std::{cmp::min, fmt};
 
-use anyhow::bail;
+use anyhow::{bail, Error};
 use bytesize::ByteSize;
+use log::{info, warn};
+use odbc_api::buffers::BufferDesc;
+
+use crate::enum_args::OnMaxFiles;
 
 #[cfg(target_pointer_width = "64")]
 const DEFAULT_BATCH_SIZE_BYTES: ByteSize = ByteSize::gib(2); // 2GB
@@ -15,6 +19,7 @@ const DEFAULT_BATCH_SIZE_BYTES: ByteSize = ByteSize::gib(1); // 1GB
 const DEFAULT_BATCH_SIZE_ROWS: usize = u16::MAX as usize; // 65535 rows
 
 /// Describes how we limit the size of individual parquet files.
+#[derive(Clone, Copy)]
 pub enum FileSizeLimit {
     /// No file size limit is applied. The entire output is written to one parquet file.
     None,
@@ -45,17 +50,91 @@ impl FileSizeLimit {
     pub fn should_start_new_file(&self, num_batch: u32, current_file_size: ByteSize) -> bool {
         match self {
             FileSizeLimit::None => false,
-            FileSizeLimit::RowGroups(row_groups) => num_batch != 0 && num_batch % row_groups == 0,
+            FileSizeLimit::RowGroups(row_groups) => num_batch != 0 && num_batch % *row_groups == 0,
             FileSizeLimit::Size(size) => &current_file_size >= size,
             FileSizeLimit::Both { row_groups, size } => {
-                (num_batch != 0 && num_batch % row_groups == 0) || &current_file_size >= size
+                (num_batch != 0 && num_batch % *row_groups == 0) || &current_file_size >= size
             }
         }
     }
 }
 
+/// Caps the number of files of a split output, see `--max-file-count`.
+#[derive(Clone, Copy)]
+pub struct MaxFileCount {
+    max_files: u32,
+    policy: OnMaxFiles,
+    /// `true` once split boundaries are ignored, due to [`OnMaxFiles::Merge`].
+    merging: bool,
+}
+
+impl MaxFileCount {
+    pub fn new(max_files: u32, policy: OnMaxFiles) -> Self {
+        Self {
+            max_files,
+            policy,
+            merging: false,
+        }
+    }
+
+    /// `true` if a file which reached a split boundary is closed. `num_files` is the number of
+    /// files started so far, including the one which reached the boundary.
+    pub fn allow_split(&mut self, num_files: u32) -> bool {
+        if num_files < self.max_files || self.policy == OnMaxFiles::Stop {
+            return true;
+        }
+        if !self.merging {
+            warn!(
+                "Reached `--max-file-count` of {} files. Writing the remaining rows into the last \
+                file, ignoring further split boundaries (`--on-max-files merge`).",
+                self.max_files
+            );
+            self.merging = true;
+        }
+        false
+    }
+
+    /// Fails if starting another file would exceed the maximum. `num_files` is the number of files
+    /// started so far.
+    pub fn check_next_file(&self, num_files: u32) -> Result<(), Error> {
+        if num_files >= self.max_files {
+            return Err(MaxFileCountReached {
+                max_files: self.max_files,
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// Export stopped, since further rows would have required more files than `--max-file-count`. Its
+/// own exit status tells it apart from other errors.
+#[derive(Debug)]
+pub struct MaxFileCountReached {
+    max_files: u32,
+}
+
+impl MaxFileCountReached {
+    /// Exit status of the process, if the export has been stopped.
+    pub const EXIT_CODE: u8 = 3;
+}
+
+impl fmt::Display for MaxFileCountReached {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Stopped the export, since the result set does not fit into the {} files of \
+            `--max-file-count` (`--on-max-files stop`). The files written so far are complete.",
+            self.max_files
+        )
+    }
+}
+
+impl std::error::Error for MaxFileCountReached {}
+
 /// Batches can be limited by either number of rows or the total size of the rows in the batch in
 /// bytes.
+#[derive(Clone, Copy)]
 pub enum BatchSizeLimit {
     Rows(usize),
     Bytes(ByteSize),
@@ -76,6 +155,14 @@ impl BatchSizeLimit {
         }
     }
 
+    /// Maximum number of rows in a batch, if limited explicitly by a row count.
+    pub fn max_rows(&self) -> Option<usize> {
+        match self {
+            BatchSizeLimit::Rows(rows) | BatchSizeLimit::Both { rows, .. } => Some(*rows),
+            BatchSizeLimit::Bytes(_) => None,
+        }
+    }
+
     pub fn batch_size_in_rows(
         &self,
         total_mem_usage_per_row: usize,
@@ -106,3 +193,85 @@ impl BatchSizeLimit {
         }
     }
 }
+
+/// Memory required per row to hold copies of variadic values, while converting them from their ODBC
+/// into their parquet representation. Estimated using the maximum element length.
+pub fn conversion_bytes_per_row(desc: BufferDesc) -> usize {
+    match desc {
+        BufferDesc::Text { max_str_len } => max_str_len,
+        // A single UTF-16 code unit may take up to three bytes in UTF-8.
+        BufferDesc::WText { max_str_len } => max_str_len * 3,
+        BufferDesc::Binary { length } => length,
+        _ => 0,
+    }
+}
+
+/// Reduces the number of rows in a batch, so the memory required to fetch, convert and write it,
+/// does not exceed the global memory limit.
+pub fn apply_memory_limit(
+    batch_size_row: usize,
+    total_mem_usage_per_row: usize,
+    memory_limit: ByteSize,
+) -> Result<usize, anyhow::Error> {
+    let limit: usize = memory_limit.as_u64().try_into().unwrap_or(usize::MAX);
+    let max_rows = limit / total_mem_usage_per_row;
+    if max_rows == 0 {
+        bail!(
+            "Memory required to fetch and convert a single row is larger than the memory limit. \
+            Memory Limit: {} bytes, Memory per row: {} bytes.",
+            limit,
+            total_mem_usage_per_row
+        )
+    }
+    if max_rows < batch_size_row {
+        info!(
+            "Reducing batch size from {batch_size_row} to {max_rows} rows to stay within the memory \
+            limit of {memory_limit}."
+        );
+        Ok(max_rows)
+    } else {
+        Ok(batch_size_row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytesize::ByteSize;
+    use odbc_api::buffers::BufferDesc;
+
+    use crate::enum_args::OnMaxFiles;
+
+    use super::{apply_memory_limit, conversion_bytes_per_row, MaxFileCount};
+
+    #[test]
+    fn memory_limit_reduces_batch_size() {
+        assert_eq!(10, apply_memory_limit(100, 100, ByteSize::b(1000)).unwrap());
+        assert_eq!(5, apply_memory_limit(5, 100, ByteSize::b(1000)).unwrap());
+        assert!(apply_memory_limit(5, 100, ByteSize::b(99)).is_err());
+    }
+
+    #[test]
+    fn conversion_memory_of_variadic_columns() {
+        assert_eq!(
+            30,
+            conversion_bytes_per_row(BufferDesc::WText { max_str_len: 10 })
+        );
+        assert_eq!(
+            0,
+            conversion_bytes_per_row(BufferDesc::I64 { nullable: true })
+        );
+    }
+
+    #[test]
+    fn cap_number_of_files() {
+        let mut stop = MaxFileCount::new(2, OnMaxFiles::Stop);
+        assert!(stop.check_next_file(1).is_ok());
+        assert!(stop.allow_split(2));
+        assert!(stop.check_next_file(2).is_err());
+
+        let mut merge = MaxFileCount::new(2, OnMaxFiles::Merge);
+        assert!(merge.allow_split(1));
+        assert!(!merge.allow_split(2));
+        assert!(!merge.allow_split(2));
+    }
+}
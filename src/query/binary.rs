@@ -11,12 +11,39 @@ use parquet::{
 
 use crate::parquet_buffer::{BufferedDataType, ParquetBuffer};
 
-use super::column_strategy::ColumnStrategy;
+use super::column_strategy::{unexpected_view, ColumnStrategy};
 
 pub struct Binary<Pdt> {
     repetition: Repetition,
     length: usize,
-    _phantom: PhantomData<Pdt>,
+    /// Cut values longer than `length` and report them. To detect these, the buffer is one byte
+    /// larger than `length`.
+    truncate: bool,
+    empty: EmptyBinary,
+    _phantom: PhantomData<fn() -> Pdt>,
+}
+
+/// How zero-length values and NULLs of binary columns are written. Some drivers conflate the two.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EmptyBinary {
+    /// Write values as reported by the driver.
+    #[default]
+    Keep,
+    /// Write zero-length values as NULL.
+    AsNull,
+    /// Write NULLs as zero-length values. Only applies to `BYTE_ARRAY` columns, since fixed length
+    /// byte arrays can not be empty.
+    NullAsEmpty,
+}
+
+impl EmptyBinary {
+    fn apply(self, value: Option<&[u8]>) -> Option<&[u8]> {
+        match (self, value) {
+            (EmptyBinary::AsNull, Some([])) => None,
+            (EmptyBinary::NullAsEmpty, None) => Some(&[]),
+            (_, value) => value,
+        }
+    }
 }
 
 impl<Pdt> Binary<Pdt> {
@@ -24,9 +51,34 @@ impl<Pdt> Binary<Pdt> {
         Self {
             repetition,
             length,
+            truncate: false,
+            empty: EmptyBinary::Keep,
             _phantom: PhantomData,
         }
     }
+
+    /// Values longer than `length` are truncated to `length` bytes. The row index of each
+    /// truncated value is recorded in the `ParquetBuffer`.
+    pub fn with_truncation(repetition: Repetition, length: usize) -> Self {
+        Self {
+            truncate: true,
+            ..Self::new(repetition, length)
+        }
+    }
+
+    /// Decide how zero-length values and NULLs are written. Writing zero-length values as NULL
+    /// declares the column `OPTIONAL`, even if the data source reports it as not nullable.
+    pub fn with_empty(self, empty: EmptyBinary) -> Self {
+        let repetition = match empty {
+            EmptyBinary::AsNull => Repetition::OPTIONAL,
+            EmptyBinary::Keep | EmptyBinary::NullAsEmpty => self.repetition,
+        };
+        Self {
+            repetition,
+            empty,
+            ..self
+        }
+    }
 }
 
 impl<Pdt> ColumnStrategy for Binary<Pdt>
@@ -54,9 +106,12 @@ where
     }
 
     fn buffer_desc(&self) -> BufferDesc {
-        BufferDesc::Binary {
-            length: self.length,
-        }
+        let length = if self.truncate {
+            self.length + 1
+        } else {
+            self.length
+        };
+        BufferDesc::Binary { length }
     }
 
     fn copy_odbc_to_parquet(
@@ -66,24 +121,175 @@ where
         column_view: AnySlice,
     ) -> Result<(), Error> {
         let cw = get_typed_column_writer_mut::<Pdt>(column_writer);
+        let is_fixed = Pdt::get_physical_type() == PhysicalType::FIXED_LEN_BYTE_ARRAY;
+        let empty = match self.empty {
+            EmptyBinary::NullAsEmpty if is_fixed => EmptyBinary::Keep,
+            empty => empty,
+        };
         if let AnySlice::Binary(view) = column_view {
-            parquet_buffer.write_optional(
+            let mut truncated = Vec::new();
+            let result = parquet_buffer.write_optional(
                 cw,
-                view.iter().map(|maybe_bytes| {
-                    maybe_bytes.map(|bytes| {
-                        let byte_array: ByteArray = bytes.to_owned().into();
+                view.iter().enumerate().map(|(row_index, maybe_bytes)| {
+                    empty.apply(maybe_bytes).map(|bytes| {
+                        let bytes = if self.truncate && bytes.len() > self.length {
+                            truncated.push(row_index);
+                            &bytes[..self.length]
+                        } else {
+                            bytes
+                        };
+                        let mut bytes = bytes.to_owned();
+                        // Some drivers do not pad values of fixed length columns. Pad them like
+                        // the database would, since each value must have the length of the column.
+                        if is_fixed && bytes.len() < self.length {
+                            bytes.resize(self.length, 0);
+                        }
+                        let byte_array: ByteArray = bytes.into();
                         // Transforms ByteArray into FixedLenByteArray or does nothing depending `Pdt`.
                         let out: Pdt::T = byte_array.into();
                         out
                     })
                 }),
-            )?
+            );
+            parquet_buffer.truncated_values = truncated;
+            result?
         } else {
-            panic!(
-                "Invalid Column view type. This is not supposed to happen. Please open a Bug at \
-                https://github.com/pacman82/odbc2parquet/issues."
-            )
+            return Err(unexpected_view("binary", column_view));
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use odbc_api::buffers::{AnySlice, BinColumn};
+    use parquet::{
+        basic::Repetition,
+        data_type::{ByteArrayType, FixedLenByteArrayType},
+        file::{
+            properties::WriterProperties,
+            reader::{FileReader, SerializedFileReader},
+            writer::SerializedFileWriter,
+        },
+        record::Field,
+        schema::types::Type,
+    };
+
+    use crate::{parquet_buffer::ParquetBuffer, query::column_strategy::ColumnStrategy};
+
+    use super::{Binary, EmptyBinary};
+
+    #[test]
+    fn pad_short_values_of_fixed_length_binary() {
+        let strategy = Binary::<FixedLenByteArrayType>::new(Repetition::OPTIONAL, 4);
+        let field = strategy.parquet_type("a");
+        assert!(matches!(field, Type::PrimitiveType { type_length: 4, .. }));
+        let schema = Type::group_type_builder("schema")
+            .with_fields(vec![Arc::new(field)])
+            .build()
+            .unwrap();
+        let mut column = BinColumn::new(3, 4);
+        column.set_value(0, Some(&[1, 2, 3, 4]));
+        column.set_value(1, Some(&[1, 2]));
+        column.set_value(2, Some(&[]));
+        let properties = Arc::new(WriterProperties::builder().build());
+        let mut writer =
+            SerializedFileWriter::new(Vec::new(), Arc::new(schema), properties).unwrap();
+        let mut row_group_writer = writer.next_row_group().unwrap();
+        let mut column_writer = row_group_writer.next_column().unwrap().unwrap();
+        let mut parquet_buffer = ParquetBuffer::new(3);
+        parquet_buffer.set_num_rows_fetched(3);
+
+        strategy
+            .copy_odbc_to_parquet(
+                &mut parquet_buffer,
+                column_writer.untyped(),
+                AnySlice::Binary(column.view(3)),
+            )
+            .unwrap();
+        column_writer.close().unwrap();
+        row_group_writer.close().unwrap();
+        let bytes = writer.into_inner().unwrap();
+
+        let reader = SerializedFileReader::new(bytes::Bytes::from(bytes)).unwrap();
+        let values: Vec<_> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(
+                |row| match row.unwrap().get_column_iter().next().unwrap().1 {
+                    Field::Bytes(bytes) => bytes.data().to_vec(),
+                    other => panic!("Unexpected field {other:?}"),
+                },
+            )
+            .collect();
+        assert_eq!(
+            vec![vec![1, 2, 3, 4], vec![1, 2, 0, 0], vec![0, 0, 0, 0]],
+            values
+        );
+    }
+
+    #[test]
+    fn empty_values_of_required_column_as_null() {
+        let strategy =
+            Binary::<ByteArrayType>::new(Repetition::REQUIRED, 4).with_empty(EmptyBinary::AsNull);
+        let field = strategy.parquet_type("a");
+        assert_eq!(Repetition::OPTIONAL, field.get_basic_info().repetition());
+        let schema = Type::group_type_builder("schema")
+            .with_fields(vec![Arc::new(field)])
+            .build()
+            .unwrap();
+        let mut column = BinColumn::new(2, 4);
+        column.set_value(0, Some(&[]));
+        column.set_value(1, Some(&[1, 2]));
+        let properties = Arc::new(WriterProperties::builder().build());
+        let mut writer =
+            SerializedFileWriter::new(Vec::new(), Arc::new(schema), properties).unwrap();
+        let mut row_group_writer = writer.next_row_group().unwrap();
+        let mut column_writer = row_group_writer.next_column().unwrap().unwrap();
+        let mut parquet_buffer = ParquetBuffer::new(2);
+        parquet_buffer.set_num_rows_fetched(2);
+
+        strategy
+            .copy_odbc_to_parquet(
+                &mut parquet_buffer,
+                column_writer.untyped(),
+                AnySlice::Binary(column.view(2)),
+            )
+            .unwrap();
+        column_writer.close().unwrap();
+        row_group_writer.close().unwrap();
+        let bytes = writer.into_inner().unwrap();
+
+        let reader = SerializedFileReader::new(bytes::Bytes::from(bytes)).unwrap();
+        let values: Vec<_> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(
+                |row| match row.unwrap().get_column_iter().next().unwrap().1 {
+                    Field::Bytes(bytes) => Some(bytes.data().to_vec()),
+                    Field::Null => None,
+                    other => panic!("Unexpected field {other:?}"),
+                },
+            )
+            .collect();
+        assert_eq!(vec![None, Some(vec![1, 2])], values);
+    }
+
+    #[test]
+    fn tell_apart_empty_and_null_binary_values() {
+        let values: [Option<&[u8]>; 3] = [Some(&[]), None, Some(&[1, 2])];
+        let apply = |empty: EmptyBinary| values.map(|value| empty.apply(value));
+
+        assert_eq!(values, apply(EmptyBinary::Keep));
+        assert_eq!(
+            [None, None, Some(&[1u8, 2][..])],
+            apply(EmptyBinary::AsNull)
+        );
+        assert_eq!(
+            [Some(&[][..]), Some(&[][..]), Some(&[1u8, 2][..])],
+            apply(EmptyBinary::NullAsEmpty)
+        );
+    }
+}
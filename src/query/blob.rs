@@ -0,0 +1,323 @@
+use std::{
+    fs::{create_dir_all, File, OpenOptions},
+    io::{ErrorKind, Write},
+    path::PathBuf,
+};
+
+use anyhow::{bail, Context, Error};
+use odbc_api::{
+    buffers::{AnyBuffer, AnySlice, Indicator},
+    handles::{AsStatementRef, SqlResult, Statement},
+    parameter::VarBinarySliceMut,
+    DataType,
+};
+use parquet::basic::Repetition;
+
+use super::{column_strategy::ColumnStrategy, text::Utf8};
+
+/// Size of the chunks the values of extracted columns are fetched in with `SQLGetData`.
+pub const BLOB_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Initial length of the text buffer holding the paths of extracted files. Grown if a path does not
+/// fit.
+const INITIAL_PATH_LENGTH: usize = 256;
+
+/// Columns whose values are written into separate files, rather than into the parquet output.
+pub struct BlobExtraction {
+    /// Names of the binary columns to extract.
+    pub columns: Vec<String>,
+    /// Directory the files are written to. Each column gets a subdirectory of its own.
+    pub dir: PathBuf,
+    /// Name of the column whose values are used as file names.
+    pub key_column: String,
+}
+
+/// Strategy of an extracted column in the parquet output. The column holds the path of each file
+/// relative to the blob directory, or NULL if the value is NULL. Fails for columns which are not
+/// binary.
+pub fn path_strategy(data_type: DataType, name: &str) -> Result<Box<dyn ColumnStrategy>, Error> {
+    if !matches!(
+        data_type,
+        DataType::Binary { .. } | DataType::Varbinary { .. } | DataType::LongVarbinary { .. }
+    ) {
+        bail!("Column '{name}' can not be extracted into files, since it is not binary.")
+    }
+    Ok(Box::new(Utf8::with_bytes_length(
+        Repetition::OPTIONAL,
+        INITIAL_PATH_LENGTH,
+    )))
+}
+
+/// The extracted columns of a result set, together with the column naming their files.
+pub struct BlobFiles {
+    /// Zero based index of the key column in the fetch buffer.
+    pub key_buffer_index: usize,
+    /// Zero based index of each extracted column in the fetch buffer, which holds the paths of the
+    /// files, together with the writer of its files.
+    pub columns: Vec<(usize, BlobToFile)>,
+}
+
+/// Streams each non-NULL value of a binary column into a file of its own.
+pub struct BlobToFile {
+    /// Name of the column. Also the name of the subdirectory the files are written to.
+    column: String,
+    /// Directory of this column, i.e. blob directory joined with the column name.
+    dir: PathBuf,
+}
+
+impl BlobToFile {
+    pub fn new(blob_dir: PathBuf, column: String) -> Result<Self, Error> {
+        let dir = blob_dir.join(&column);
+        create_dir_all(&dir)
+            .with_context(|| format!("Could not create directory '{}'.", dir.display()))?;
+        Ok(Self { column, dir })
+    }
+
+    /// Streams the value of column `column_number` in the current row of `cursor` into a new file
+    /// named after `key`, `chunk` bytes at a time. Returns the path of the file relative to the
+    /// blob directory, or `None` if the value is NULL. The column must not be bound to a buffer.
+    pub fn extract(
+        &self,
+        cursor: &mut impl AsStatementRef,
+        column_number: u16,
+        key: Option<&str>,
+        chunk: &mut [u8],
+    ) -> Result<Option<String>, Error> {
+        let mut stmt = cursor.as_stmt_ref();
+        let mut file: Option<(File, String)> = None;
+        loop {
+            let mut target = VarBinarySliceMut::from_buffer(chunk, Indicator::NoTotal);
+            // Truncation of a chunk is reported as success with info, which is expected here, so it
+            // is not logged as a warning.
+            match stmt.get_data(column_number, &mut target) {
+                // The entire value has been fetched with the last call.
+                SqlResult::NoData => break,
+                SqlResult::Success(()) | SqlResult::SuccessWithInfo(()) => (),
+                error => error.into_result(&stmt)?,
+            }
+            let Some(bytes) = target.as_bytes() else {
+                return Ok(None);
+            };
+            let (writer, _) = match &mut file {
+                Some(file) => file,
+                None => {
+                    let Some(key) = key else {
+                        bail!(
+                            "Can not extract value of column '{}', since its key is NULL.",
+                            self.column
+                        )
+                    };
+                    file.insert(self.create_file(key)?)
+                }
+            };
+            writer.write_all(bytes)?;
+            if target.is_complete() {
+                break;
+            }
+        }
+        Ok(file.map(|(_, path)| path))
+    }
+
+    /// Creates a new file named after `key` and returns it, together with its path relative to the
+    /// blob directory. If a file with that name already exists, a numeric suffix is appended.
+    fn create_file(&self, key: &str) -> Result<(File, String), Error> {
+        let base_name = file_name_from_key(key);
+        let mut file_name = base_name.clone();
+        let mut num_collisions = 0;
+        loop {
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(self.dir.join(&file_name))
+            {
+                Ok(file) => return Ok((file, format!("{}/{file_name}", self.column))),
+                Err(error) if error.kind() == ErrorKind::AlreadyExists => {
+                    num_collisions += 1;
+                    file_name = format!("{base_name}_{num_collisions}");
+                }
+                Err(error) => {
+                    return Err(error).with_context(|| {
+                        format!(
+                            "Could not create file for key '{key}' in column '{}'.",
+                            self.column
+                        )
+                    })
+                }
+            }
+        }
+    }
+}
+
+/// Render the value in the first row of the key column as text, so it can be used as file name.
+pub fn key_as_text(key_view: AnySlice) -> Result<Option<String>, Error> {
+    fn render<T: ToString>(mut values: impl Iterator<Item = Option<T>>) -> Option<String> {
+        values.next().flatten().map(|value| value.to_string())
+    }
+    let key = match key_view {
+        AnySlice::Text(view) => render(
+            view.iter()
+                .map(|value| value.map(|bytes| String::from_utf8_lossy(bytes).into_owned())),
+        ),
+        AnySlice::WText(view) => render(
+            view.iter()
+                .map(|value| value.map(|chars| String::from_utf16_lossy(chars.as_slice()))),
+        ),
+        AnySlice::I8(values) => render(values.iter().map(Some)),
+        AnySlice::I16(values) => render(values.iter().map(Some)),
+        AnySlice::I32(values) => render(values.iter().map(Some)),
+        AnySlice::I64(values) => render(values.iter().map(Some)),
+        AnySlice::U8(values) => render(values.iter().map(Some)),
+        AnySlice::NullableI8(values) => render(values),
+        AnySlice::NullableI16(values) => render(values),
+        AnySlice::NullableI32(values) => render(values),
+        AnySlice::NullableI64(values) => render(values),
+        AnySlice::NullableU8(values) => render(values),
+        _ => bail!("Key column for extracting blobs must be of integer or text type."),
+    };
+    Ok(key)
+}
+
+/// Copies the value of a nullable column into row `$row` of `$target`.
+macro_rules! copy_nullable {
+    ($values:expr, $target:expr, $row:expr) => {{
+        let value = $values.next().flatten().copied();
+        $target.writer_n($row + 1).set_cell($row, value);
+    }};
+}
+
+/// Copies the value in the first row of `source` into row `row` of `target`. Used to gather the
+/// rows fetched one at a time into a batch. Both must have been allocated from the same buffer
+/// description.
+pub fn copy_first_value(source: AnySlice, target: &mut AnyBuffer, row: usize) {
+    match (source, target) {
+        (AnySlice::Text(view), AnyBuffer::Text(column)) => column.set_value(row, view.get(0)),
+        (AnySlice::WText(view), AnyBuffer::WText(column)) => column.set_value(row, view.get(0)),
+        (AnySlice::Binary(view), AnyBuffer::Binary(column)) => column.set_value(row, view.get(0)),
+        (AnySlice::Date(values), AnyBuffer::Date(column)) => column[row] = values[0],
+        (AnySlice::Time(values), AnyBuffer::Time(column)) => column[row] = values[0],
+        (AnySlice::Timestamp(values), AnyBuffer::Timestamp(column)) => column[row] = values[0],
+        (AnySlice::F64(values), AnyBuffer::F64(column)) => column[row] = values[0],
+        (AnySlice::F32(values), AnyBuffer::F32(column)) => column[row] = values[0],
+        (AnySlice::I8(values), AnyBuffer::I8(column)) => column[row] = values[0],
+        (AnySlice::I16(values), AnyBuffer::I16(column)) => column[row] = values[0],
+        (AnySlice::I32(values), AnyBuffer::I32(column)) => column[row] = values[0],
+        (AnySlice::I64(values), AnyBuffer::I64(column)) => column[row] = values[0],
+        (AnySlice::U8(values), AnyBuffer::U8(column)) => column[row] = values[0],
+        (AnySlice::Bit(values), AnyBuffer::Bit(column)) => column[row] = values[0],
+        (AnySlice::NullableDate(mut values), AnyBuffer::NullableDate(column)) => {
+            copy_nullable!(values, column, row)
+        }
+        (AnySlice::NullableTime(mut values), AnyBuffer::NullableTime(column)) => {
+            copy_nullable!(values, column, row)
+        }
+        (AnySlice::NullableTimestamp(mut values), AnyBuffer::NullableTimestamp(column)) => {
+            copy_nullable!(values, column, row)
+        }
+        (AnySlice::NullableF64(mut values), AnyBuffer::NullableF64(column)) => {
+            copy_nullable!(values, column, row)
+        }
+        (AnySlice::NullableF32(mut values), AnyBuffer::NullableF32(column)) => {
+            copy_nullable!(values, column, row)
+        }
+        (AnySlice::NullableI8(mut values), AnyBuffer::NullableI8(column)) => {
+            copy_nullable!(values, column, row)
+        }
+        (AnySlice::NullableI16(mut values), AnyBuffer::NullableI16(column)) => {
+            copy_nullable!(values, column, row)
+        }
+        (AnySlice::NullableI32(mut values), AnyBuffer::NullableI32(column)) => {
+            copy_nullable!(values, column, row)
+        }
+        (AnySlice::NullableI64(mut values), AnyBuffer::NullableI64(column)) => {
+            copy_nullable!(values, column, row)
+        }
+        (AnySlice::NullableU8(mut values), AnyBuffer::NullableU8(column)) => {
+            copy_nullable!(values, column, row)
+        }
+        (AnySlice::NullableBit(mut values), AnyBuffer::NullableBit(column)) => {
+            copy_nullable!(values, column, row)
+        }
+        _ => unreachable!("Source and target must have been allocated from the same description."),
+    }
+}
+
+/// Replace characters, which are not safe to use in file names on all platforms.
+pub fn file_name_from_key(key: &str) -> String {
+    let name: String = key
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    // Avoid names with special meaning, like `.` or `..`.
+    if name.is_empty() || name.chars().all(|c| c == '.') {
+        format!("_{name}")
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use odbc_api::{
+        buffers::{AnyBuffer, AnySlice, BufferDesc, ColumnarAnyBuffer, TextColumn},
+        RowSetBuffer,
+    };
+    use tempfile::tempdir;
+
+    use super::{copy_first_value, file_name_from_key, BlobToFile};
+
+    #[test]
+    fn sanitize_file_names() {
+        assert_eq!("invoice_42.pdf", file_name_from_key("invoice 42.pdf"));
+        assert_eq!(".._.._etc", file_name_from_key("../../etc"));
+        assert_eq!("_..", file_name_from_key(".."));
+        assert_eq!("_", file_name_from_key(""));
+    }
+
+    #[test]
+    fn suffix_file_names_of_colliding_keys() {
+        let dir = tempdir().unwrap();
+        let blobs = BlobToFile::new(dir.path().to_owned(), "b".to_owned()).unwrap();
+
+        let (_, first) = blobs.create_file("doc 1").unwrap();
+        let (_, second) = blobs.create_file("doc_1").unwrap();
+
+        assert_eq!("b/doc_1", first);
+        assert_eq!("b/doc_1_1", second);
+    }
+
+    #[test]
+    fn gather_rows_fetched_one_at_a_time() {
+        let mut text = TextColumn::new(1, 2);
+        text.set_value(0, Some(b"ab"));
+        let mut nullable = AnyBuffer::from_desc(1, BufferDesc::I32 { nullable: true });
+        let AnyBuffer::NullableI32(column) = &mut nullable else {
+            panic!("Expected nullable column.")
+        };
+        column.writer_n(1).set_cell(0, Some(42));
+        let mut row = ColumnarAnyBuffer::new(vec![(1, AnyBuffer::Text(text)), (2, nullable)]);
+        *row.mut_num_fetch_rows() = 1;
+        let mut text_batch = AnyBuffer::from_desc(2, BufferDesc::Text { max_str_len: 2 });
+        let mut int_batch = AnyBuffer::from_desc(2, BufferDesc::I32 { nullable: true });
+
+        copy_first_value(row.column(0), &mut text_batch, 1);
+        copy_first_value(row.column(1), &mut int_batch, 1);
+
+        let AnyBuffer::Text(text_batch) = text_batch else {
+            panic!("Expected text column.")
+        };
+        assert_eq!(Some(&b"ab"[..]), text_batch.value_at(1));
+        let mut batch = ColumnarAnyBuffer::new(vec![(1, int_batch)]);
+        *batch.mut_num_fetch_rows() = 2;
+        let AnySlice::NullableI32(mut values) = batch.column(0) else {
+            panic!("Expected nullable column.")
+        };
+        assert_eq!(Some(Some(&42)), values.nth(1));
+    }
+}
@@ -1,18 +1,18 @@
 use anyhow::Error;
 use odbc_api::{
-    buffers::{AnySlice, BufferDesc, Item},
+    buffers::{AnySlice, BufferDesc},
     Bit,
 };
 use parquet::{
-    basic::{Repetition, Type as PhysicalType},
+    basic::{LogicalType, Repetition, Type as PhysicalType},
     column::writer::{get_typed_column_writer_mut, ColumnWriter},
-    data_type::BoolType,
+    data_type::{BoolType, Int32Type},
     schema::types::Type,
 };
 
 use crate::parquet_buffer::ParquetBuffer;
 
-use super::column_strategy::ColumnStrategy;
+use super::column_strategy::{nullable_values, ColumnStrategy};
 
 /// Could be the identical strategy on most platform. Yet Rust does not give any guarantees with
 /// regard to the memory layout of a bool, so we do an explicit conversion from `Bit`.
@@ -44,9 +44,94 @@ impl ColumnStrategy for Boolean {
         column_writer: &mut ColumnWriter,
         column_view: AnySlice,
     ) -> Result<(), Error> {
-        let it = Bit::as_nullable_slice(column_view).unwrap();
+        let it = nullable_values::<Bit>(column_view, "bits")?;
         let column_writer = get_typed_column_writer_mut::<BoolType>(column_writer);
         parquet_buffer.write_optional(column_writer, it.map(|bit| bit.map(|bit| bit.as_bool())))?;
         Ok(())
     }
 }
+
+/// Fetches integers and writes them as booleans. Any value other than zero is `true`.
+pub struct BooleanFromInt {
+    repetition: Repetition,
+    /// Fetch values as 64-Bit integers, rather than 32-Bit ones.
+    wide: bool,
+}
+
+impl BooleanFromInt {
+    pub fn new(repetition: Repetition, wide: bool) -> Self {
+        Self { repetition, wide }
+    }
+}
+
+impl ColumnStrategy for BooleanFromInt {
+    fn parquet_type(&self, name: &str) -> Type {
+        Boolean::new(self.repetition).parquet_type(name)
+    }
+
+    fn buffer_desc(&self) -> BufferDesc {
+        if self.wide {
+            BufferDesc::I64 { nullable: true }
+        } else {
+            BufferDesc::I32 { nullable: true }
+        }
+    }
+
+    fn copy_odbc_to_parquet(
+        &self,
+        parquet_buffer: &mut ParquetBuffer,
+        column_writer: &mut ColumnWriter,
+        column_view: AnySlice,
+    ) -> Result<(), Error> {
+        let column_writer = get_typed_column_writer_mut::<BoolType>(column_writer);
+        if self.wide {
+            let it = nullable_values::<i64>(column_view, "64-Bit integers")?;
+            parquet_buffer.write_optional(column_writer, it.map(|v| v.map(|&v| v != 0)))
+        } else {
+            let it = nullable_values::<i32>(column_view, "32-Bit integers")?;
+            parquet_buffer.write_optional(column_writer, it.map(|v| v.map(|&v| v != 0)))
+        }
+    }
+}
+
+/// Fetches booleans and writes them as 32-Bit integers. `1` for `true` and `0` for `false`.
+pub struct IntFromBoolean {
+    repetition: Repetition,
+}
+
+impl IntFromBoolean {
+    pub fn new(repetition: Repetition) -> Self {
+        Self { repetition }
+    }
+}
+
+impl ColumnStrategy for IntFromBoolean {
+    fn parquet_type(&self, name: &str) -> Type {
+        Type::primitive_type_builder(name, PhysicalType::INT32)
+            .with_repetition(self.repetition)
+            .with_logical_type(Some(LogicalType::Integer {
+                bit_width: 32,
+                is_signed: true,
+            }))
+            .build()
+            .unwrap()
+    }
+
+    fn buffer_desc(&self) -> BufferDesc {
+        BufferDesc::Bit { nullable: true }
+    }
+
+    fn copy_odbc_to_parquet(
+        &self,
+        parquet_buffer: &mut ParquetBuffer,
+        column_writer: &mut ColumnWriter,
+        column_view: AnySlice,
+    ) -> Result<(), Error> {
+        let it = nullable_values::<Bit>(column_view, "bits")?;
+        let column_writer = get_typed_column_writer_mut::<Int32Type>(column_writer);
+        parquet_buffer.write_optional(
+            column_writer,
+            it.map(|bit| bit.map(|bit| i32::from(bit.as_bool()))),
+        )
+    }
+}
@@ -0,0 +1,36 @@
+use anyhow::Error;
+use odbc_api::{handles::StatementImpl, CursorImpl, Preallocated};
+
+use crate::enum_args::CatalogFunction;
+
+/// Arguments of a catalog function, whose result set is exported instead of the one of a query, as
+/// specified with `--catalog-function`.
+pub struct CatalogSearch {
+    pub function: CatalogFunction,
+    pub catalog: String,
+    /// Search pattern for schema names.
+    pub schema: String,
+    /// Search pattern for table names.
+    pub table: String,
+    /// Search pattern for column names. Only used by `SQLColumns`.
+    pub column: String,
+    /// Comma separated list of table types. Only used by `SQLTables`. Empty for all types.
+    pub table_type: String,
+}
+
+impl CatalogSearch {
+    pub fn execute<'s, 'o>(
+        &self,
+        statement: &'s mut Preallocated<'o>,
+    ) -> Result<CursorImpl<&'s mut StatementImpl<'o>>, Error> {
+        let cursor = match self.function {
+            CatalogFunction::Tables => {
+                statement.tables(&self.catalog, &self.schema, &self.table, &self.table_type)?
+            }
+            CatalogFunction::Columns => {
+                statement.columns(&self.catalog, &self.schema, &self.table, &self.column)?
+            }
+        };
+        Ok(cursor)
+    }
+}
@@ -0,0 +1,658 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, VecDeque},
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{anyhow, Context, Error};
+use log::info;
+use parquet::{
+    basic::{ConvertedType, LogicalType},
+    column::{
+        reader::{ColumnReader, ColumnReaderImpl},
+        writer::{ColumnWriter, ColumnWriterImpl},
+    },
+    data_type::{ByteArray, DataType, FixedLenByteArray, Int96},
+    file::{
+        properties::WriterProperties,
+        reader::{FileReader, RowGroupReader, SerializedFileReader},
+        writer::SerializedFileWriter,
+    },
+    schema::types::{SchemaDescriptor, Type},
+};
+use tempfile::NamedTempFile;
+
+use super::{
+    parquet_writer::{OutputSize, ParquetOutput, WrittenRowGroup},
+    table_strategy::ColumnExporter,
+};
+
+/// Rows read at once from each sorted row group while merging them.
+const MERGE_CHUNK_ROWS: usize = 1024;
+
+/// Sorts the rows of all files written by `output` by key columns, once the output is closed. Each
+/// row group is sorted in memory and spilled into a temporary file next to the output. The sorted
+/// row groups are then merged back into the files, keeping the number of rows of each row group.
+pub struct ClientSort {
+    output: Box<dyn ParquetOutput>,
+    sort: Sort,
+}
+
+struct Sort {
+    schema: Arc<Type>,
+    properties: Arc<WriterProperties>,
+    keys: Vec<SortKey>,
+    /// Files written by `output` in order, together with the number of rows of each of their row
+    /// groups.
+    files: Vec<(PathBuf, Vec<usize>)>,
+}
+
+impl ClientSort {
+    pub fn new(
+        output: Box<dyn ParquetOutput>,
+        schema: Arc<Type>,
+        properties: Arc<WriterProperties>,
+        key_columns: &[String],
+    ) -> Result<Self, Error> {
+        let descriptor = SchemaDescriptor::new(schema.clone());
+        let keys = key_columns
+            .iter()
+            .map(|name| {
+                descriptor
+                    .columns()
+                    .iter()
+                    .position(|column| column.path().string() == *name)
+                    .map(|index| SortKey::new(index, &descriptor))
+                    .ok_or_else(|| anyhow!("Unknown column '{name}' in `--stable-sort`."))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            output,
+            sort: Sort {
+                schema,
+                properties,
+                keys,
+                files: Vec::new(),
+            },
+        })
+    }
+}
+
+impl Sort {
+    fn cmp(&self, a: &[Cell], b: &[Cell]) -> Ordering {
+        cmp_rows(&self.keys, a, b)
+    }
+
+    /// Sorts each row group on its own and writes them into `spill`, in the same order.
+    fn sort_row_groups(&self, spill: &NamedTempFile) -> Result<(), Error> {
+        // Spilled data is only read once, so it is not worth compressing it.
+        let properties = Arc::new(WriterProperties::builder().build());
+        let mut writer = SerializedFileWriter::new(
+            BufWriter::new(spill.reopen()?),
+            self.schema.clone(),
+            properties,
+        )?;
+        for (path, _num_rows) in &self.files {
+            let reader = SerializedFileReader::new(File::open(path)?)?;
+            for index in 0..reader.num_row_groups() {
+                let mut rows = RowGroup::new(reader.get_row_group(index)?)?.read_all()?;
+                rows.sort_by(|a, b| self.cmp(a, b));
+                write_row_group(&mut writer, &rows)?;
+            }
+        }
+        writer.into_inner()?.into_inner()?;
+        Ok(())
+    }
+
+    /// Merges the sorted row groups of `spill` back into the files of the output.
+    fn merge(&self, spill: &NamedTempFile) -> Result<u64, Error> {
+        let reader = SerializedFileReader::new(spill.reopen()?)?;
+        let mut row_groups = (0..reader.num_row_groups())
+            .map(|index| RowGroup::new(reader.get_row_group(index)?))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut heap = BinaryHeap::new();
+        for (index, row_group) in row_groups.iter_mut().enumerate() {
+            if let Some(row) = row_group.next_row()? {
+                heap.push(Reverse(Head {
+                    row,
+                    row_group: index,
+                    sort: self,
+                }));
+            }
+        }
+        let mut num_bytes = 0;
+        for (path, row_group_sizes) in &self.files {
+            let directory = path.parent().unwrap_or(Path::new("."));
+            let temporary = NamedTempFile::new_in(directory)?;
+            let mut writer = SerializedFileWriter::new(
+                BufWriter::new(temporary.reopen()?),
+                self.schema.clone(),
+                self.properties.clone(),
+            )?;
+            for &num_rows in row_group_sizes {
+                let mut rows = Vec::with_capacity(num_rows);
+                while rows.len() < num_rows {
+                    let Reverse(head) = heap.pop().expect("Sorted row groups hold all rows");
+                    if let Some(row) = row_groups[head.row_group].next_row()? {
+                        heap.push(Reverse(Head {
+                            row,
+                            row_group: head.row_group,
+                            sort: self,
+                        }));
+                    }
+                    rows.push(head.row);
+                }
+                write_row_group(&mut writer, &rows)?;
+            }
+            writer.into_inner()?.into_inner()?;
+            temporary
+                .persist(path)
+                .with_context(|| format!("Could not replace '{}'.", path.display()))?;
+            num_bytes += fs::metadata(path)?.len();
+        }
+        Ok(num_bytes)
+    }
+}
+
+impl ParquetOutput for ClientSort {
+    fn write_row_group(
+        &mut self,
+        num_batch: u32,
+        column_exporter: &mut ColumnExporter,
+    ) -> Result<WrittenRowGroup, Error> {
+        let written = self.output.write_row_group(num_batch, column_exporter)?;
+        if let Some(path) = &written.path {
+            let files = &mut self.sort.files;
+            if files.last().map(|(last, _)| last) != Some(path) {
+                files.push((path.clone(), Vec::new()));
+            }
+            let num_rows = written.metadata.num_rows().try_into().unwrap();
+            files.last_mut().unwrap().1.push(num_rows);
+        }
+        Ok(written)
+    }
+
+    fn close(self) -> Result<OutputSize, Error> {
+        let mut written = self.output.close_box()?;
+        let sort = self.sort;
+        let Some((first, _)) = sort.files.first() else {
+            return Ok(written);
+        };
+        let num_row_groups: usize = sort.files.iter().map(|(_, sizes)| sizes.len()).sum();
+        info!("Sorting {num_row_groups} row groups on the client.");
+        let directory = first.parent().unwrap_or(Path::new("."));
+        let spill = NamedTempFile::new_in(directory)?;
+        sort.sort_row_groups(&spill)
+            .context("Could not sort the row groups of the output.")?;
+        let num_bytes_sorted = sort
+            .merge(&spill)
+            .context("Could not merge the sorted row groups into the output.")?;
+        written.num_bytes = num_bytes_sorted;
+        Ok(written)
+    }
+
+    fn close_box(self: Box<Self>) -> Result<OutputSize, Error> {
+        self.close()
+    }
+}
+
+/// The next row of a sorted row group, ordered by the key columns. Rows with equal keys are
+/// ordered by the row group, so the sort is stable.
+struct Head<'a> {
+    row: Vec<Cell>,
+    row_group: usize,
+    sort: &'a Sort,
+}
+
+impl PartialEq for Head<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Head<'_> {}
+
+impl PartialOrd for Head<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Head<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort
+            .cmp(&self.row, &other.row)
+            .then(self.row_group.cmp(&other.row_group))
+    }
+}
+
+/// Value of one column in one row.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cell {
+    pub def_level: i16,
+    /// `None` if the value is NULL.
+    pub value: Option<Value>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Int32(i32),
+    Int64(i64),
+    Int96(Int96),
+    Float(f32),
+    Double(f64),
+    Bytes(ByteArray),
+    FixedLenBytes(FixedLenByteArray),
+}
+
+/// Compares two rows by the key columns, in order of `keys`.
+pub fn cmp_rows(keys: &[SortKey], a: &[Cell], b: &[Cell]) -> Ordering {
+    keys.iter()
+        .map(|key| key.cmp(&a[key.column], &b[key.column]))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// A column to sort by.
+pub struct SortKey {
+    /// Index of the leaf column.
+    column: usize,
+    /// Integers are unsigned.
+    is_unsigned: bool,
+    /// Bytes hold a decimal as big endian two's complement.
+    is_decimal: bool,
+}
+
+impl SortKey {
+    pub fn new(column: usize, descriptor: &SchemaDescriptor) -> Self {
+        let column_descriptor = descriptor.column(column);
+        let is_unsigned = matches!(
+            column_descriptor.logical_type(),
+            Some(LogicalType::Integer {
+                is_signed: false,
+                ..
+            })
+        );
+        let is_decimal = column_descriptor.converted_type() == ConvertedType::DECIMAL;
+        Self {
+            column,
+            is_unsigned,
+            is_decimal,
+        }
+    }
+
+    /// NULL is ordered before any other value.
+    fn cmp(&self, a: &Cell, b: &Cell) -> Ordering {
+        let (a, b) = match (&a.value, &b.value) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => (a, b),
+        };
+        match (a, b) {
+            (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+            (Value::Int32(a), Value::Int32(b)) if self.is_unsigned => (*a as u32).cmp(&(*b as u32)),
+            (Value::Int32(a), Value::Int32(b)) => a.cmp(b),
+            (Value::Int64(a), Value::Int64(b)) if self.is_unsigned => (*a as u64).cmp(&(*b as u64)),
+            (Value::Int64(a), Value::Int64(b)) => a.cmp(b),
+            (Value::Int96(a), Value::Int96(b)) => {
+                a.to_seconds_and_nanos().cmp(&b.to_seconds_and_nanos())
+            }
+            (Value::Float(a), Value::Float(b)) => a.total_cmp(b),
+            (Value::Double(a), Value::Double(b)) => a.total_cmp(b),
+            (Value::Bytes(a), Value::Bytes(b)) => self.cmp_bytes(a.data(), b.data()),
+            (Value::FixedLenBytes(a), Value::FixedLenBytes(b)) => {
+                self.cmp_bytes(a.data(), b.data())
+            }
+            _ => unreachable!("Values of the same column have the same physical type"),
+        }
+    }
+
+    fn cmp_bytes(&self, a: &[u8], b: &[u8]) -> Ordering {
+        if self.is_decimal {
+            decimal_to_i128(a).cmp(&decimal_to_i128(b))
+        } else {
+            a.cmp(b)
+        }
+    }
+}
+
+fn decimal_to_i128(bytes: &[u8]) -> i128 {
+    let negative = bytes.first().is_some_and(|&byte| byte & 0x80 != 0);
+    bytes
+        .iter()
+        .fold(if negative { -1 } else { 0 }, |acc, &byte| {
+            (acc << 8) | byte as i128
+        })
+}
+
+/// Reads the rows of a row group, a few at a time.
+pub struct RowGroup {
+    columns: Vec<(ColumnReader, i16)>,
+    /// Rows which have not been read yet.
+    remaining: usize,
+    /// Rows which have been read, but not yet been consumed by `next_row`.
+    buffered: VecDeque<Vec<Cell>>,
+}
+
+impl RowGroup {
+    pub fn new(row_group: Box<dyn RowGroupReader + '_>) -> Result<Self, Error> {
+        let metadata = row_group.metadata();
+        let columns = (0..metadata.num_columns())
+            .map(|index| {
+                let max_def_level = metadata.column(index).column_descr().max_def_level();
+                Ok((row_group.get_column_reader(index)?, max_def_level))
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(Self {
+            columns,
+            remaining: metadata.num_rows().try_into().unwrap(),
+            buffered: VecDeque::new(),
+        })
+    }
+
+    pub fn next_row(&mut self) -> Result<Option<Vec<Cell>>, Error> {
+        if self.buffered.is_empty() && self.remaining != 0 {
+            let rows = self.read_rows(self.remaining.min(MERGE_CHUNK_ROWS))?;
+            self.buffered.extend(rows);
+        }
+        Ok(self.buffered.pop_front())
+    }
+
+    /// All rows which have not been read yet.
+    pub fn read_all(&mut self) -> Result<Vec<Vec<Cell>>, Error> {
+        self.read_rows(self.remaining)
+    }
+
+    fn read_rows(&mut self, num_rows: usize) -> Result<Vec<Vec<Cell>>, Error> {
+        let mut rows: Vec<Vec<Cell>> = (0..num_rows)
+            .map(|_| Vec::with_capacity(self.columns.len()))
+            .collect();
+        for (column_reader, max_def_level) in &mut self.columns {
+            let cells = read_cells(column_reader, *max_def_level, num_rows)?;
+            if cells.len() != num_rows {
+                return Err(anyhow!("Column chunk holds fewer rows than its row group."));
+            }
+            for (row, cell) in rows.iter_mut().zip(cells) {
+                row.push(cell);
+            }
+        }
+        self.remaining -= num_rows;
+        Ok(rows)
+    }
+}
+
+fn read_cells(
+    column_reader: &mut ColumnReader,
+    max_def_level: i16,
+    num_rows: usize,
+) -> Result<Vec<Cell>, Error> {
+    match column_reader {
+        ColumnReader::BoolColumnReader(cr) => read_typed(cr, max_def_level, num_rows, Value::Bool),
+        ColumnReader::Int32ColumnReader(cr) => {
+            read_typed(cr, max_def_level, num_rows, Value::Int32)
+        }
+        ColumnReader::Int64ColumnReader(cr) => {
+            read_typed(cr, max_def_level, num_rows, Value::Int64)
+        }
+        ColumnReader::Int96ColumnReader(cr) => {
+            read_typed(cr, max_def_level, num_rows, Value::Int96)
+        }
+        ColumnReader::FloatColumnReader(cr) => {
+            read_typed(cr, max_def_level, num_rows, Value::Float)
+        }
+        ColumnReader::DoubleColumnReader(cr) => {
+            read_typed(cr, max_def_level, num_rows, Value::Double)
+        }
+        ColumnReader::ByteArrayColumnReader(cr) => {
+            read_typed(cr, max_def_level, num_rows, Value::Bytes)
+        }
+        ColumnReader::FixedLenByteArrayColumnReader(cr) => {
+            read_typed(cr, max_def_level, num_rows, Value::FixedLenBytes)
+        }
+    }
+}
+
+fn read_typed<T: DataType>(
+    column_reader: &mut ColumnReaderImpl<T>,
+    max_def_level: i16,
+    num_rows: usize,
+    into_value: impl Fn(T::T) -> Value,
+) -> Result<Vec<Cell>, Error> {
+    let mut values = Vec::with_capacity(num_rows);
+    let mut def_levels = Vec::with_capacity(num_rows);
+    let mut num_read = 0;
+    while num_read < num_rows {
+        let (num_records, _num_values, _num_levels) = column_reader.read_records(
+            num_rows - num_read,
+            (max_def_level > 0).then_some(&mut def_levels),
+            None,
+            &mut values,
+        )?;
+        if num_records == 0 {
+            break;
+        }
+        num_read += num_records;
+    }
+    let mut values = values.into_iter().map(into_value);
+    if max_def_level == 0 {
+        return Ok(values
+            .map(|value| Cell {
+                def_level: 0,
+                value: Some(value),
+            })
+            .collect());
+    }
+    Ok(def_levels
+        .into_iter()
+        .map(|def_level| Cell {
+            def_level,
+            value: if def_level == max_def_level {
+                values.next()
+            } else {
+                None
+            },
+        })
+        .collect())
+}
+
+/// Writes the rows as the next row group of `writer`.
+pub fn write_row_group<W: Write + Send>(
+    writer: &mut SerializedFileWriter<W>,
+    rows: &[Vec<Cell>],
+) -> Result<(), Error> {
+    let mut row_group_writer = writer.next_row_group()?;
+    let mut column = 0;
+    while let Some(mut column_writer) = row_group_writer.next_column()? {
+        let cells = rows.iter().map(|row| &row[column]);
+        match column_writer.untyped() {
+            ColumnWriter::BoolColumnWriter(cw) => write_typed(cw, cells, |value| match value {
+                Value::Bool(value) => *value,
+                _ => unreachable!(),
+            }),
+            ColumnWriter::Int32ColumnWriter(cw) => write_typed(cw, cells, |value| match value {
+                Value::Int32(value) => *value,
+                _ => unreachable!(),
+            }),
+            ColumnWriter::Int64ColumnWriter(cw) => write_typed(cw, cells, |value| match value {
+                Value::Int64(value) => *value,
+                _ => unreachable!(),
+            }),
+            ColumnWriter::Int96ColumnWriter(cw) => write_typed(cw, cells, |value| match value {
+                Value::Int96(value) => *value,
+                _ => unreachable!(),
+            }),
+            ColumnWriter::FloatColumnWriter(cw) => write_typed(cw, cells, |value| match value {
+                Value::Float(value) => *value,
+                _ => unreachable!(),
+            }),
+            ColumnWriter::DoubleColumnWriter(cw) => write_typed(cw, cells, |value| match value {
+                Value::Double(value) => *value,
+                _ => unreachable!(),
+            }),
+            ColumnWriter::ByteArrayColumnWriter(cw) => {
+                write_typed(cw, cells, |value| match value {
+                    Value::Bytes(value) => value.clone(),
+                    _ => unreachable!(),
+                })
+            }
+            ColumnWriter::FixedLenByteArrayColumnWriter(cw) => {
+                write_typed(cw, cells, |value| match value {
+                    Value::FixedLenBytes(value) => value.clone(),
+                    _ => unreachable!(),
+                })
+            }
+        }?;
+        column_writer.close()?;
+        column += 1;
+    }
+    row_group_writer.close()?;
+    Ok(())
+}
+
+fn write_typed<'a, T: DataType>(
+    column_writer: &mut ColumnWriterImpl<T>,
+    cells: impl Iterator<Item = &'a Cell>,
+    from_value: impl Fn(&Value) -> T::T,
+) -> Result<(), Error> {
+    let mut def_levels = Vec::new();
+    let values: Vec<_> = cells
+        .inspect(|cell| def_levels.push(cell.def_level))
+        .filter_map(|cell| cell.value.as_ref().map(&from_value))
+        .collect();
+    let def_levels =
+        (column_writer.get_descriptor().max_def_level() > 0).then_some(def_levels.as_slice());
+    column_writer.write_batch(&values, def_levels, None)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cmp::Ordering, fs::File, sync::Arc};
+
+    use parquet::{
+        basic::{Repetition, Type as PhysicalType},
+        data_type::Int32Type,
+        file::{
+            properties::WriterProperties,
+            reader::{FileReader, SerializedFileReader},
+            writer::SerializedFileWriter,
+        },
+        record::Field,
+        schema::types::{SchemaDescriptor, Type},
+    };
+    use tempfile::{tempdir, NamedTempFile};
+
+    use super::{decimal_to_i128, Cell, Sort, SortKey, Value};
+
+    #[test]
+    fn merge_sorted_row_groups() {
+        let column = |name| {
+            Arc::new(
+                Type::primitive_type_builder(name, PhysicalType::INT32)
+                    .with_repetition(Repetition::OPTIONAL)
+                    .build()
+                    .unwrap(),
+            )
+        };
+        let schema = Arc::new(
+            Type::group_type_builder("schema")
+                .with_fields(vec![column("a"), column("b")])
+                .build()
+                .unwrap(),
+        );
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("out.par");
+        let properties = Arc::new(WriterProperties::builder().build());
+        let mut writer = SerializedFileWriter::new(
+            File::create(&path).unwrap(),
+            schema.clone(),
+            properties.clone(),
+        )
+        .unwrap();
+        // Row groups (a, b): (3, 1), (1, 2) and (2, 3), (NULL, 4)
+        let row_groups: [[(&[i32], &[i16]); 2]; 2] = [
+            [(&[3, 1], &[1, 1]), (&[1, 2], &[1, 1])],
+            [(&[2], &[1, 0]), (&[3, 4], &[1, 1])],
+        ];
+        for columns in row_groups {
+            let mut row_group_writer = writer.next_row_group().unwrap();
+            for (values, def_levels) in columns {
+                let mut column_writer = row_group_writer.next_column().unwrap().unwrap();
+                column_writer
+                    .typed::<Int32Type>()
+                    .write_batch(values, Some(def_levels), None)
+                    .unwrap();
+                column_writer.close().unwrap();
+            }
+            row_group_writer.close().unwrap();
+        }
+        writer.close().unwrap();
+
+        let descriptor = SchemaDescriptor::new(schema.clone());
+        let sort = Sort {
+            schema,
+            properties,
+            keys: vec![SortKey::new(0, &descriptor)],
+            files: vec![(path.clone(), vec![3, 1])],
+        };
+        let spill = NamedTempFile::new_in(dir.path()).unwrap();
+        sort.sort_row_groups(&spill).unwrap();
+        sort.merge(&spill).unwrap();
+
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        assert_eq!(3, reader.metadata().row_group(0).num_rows());
+        let rows: Vec<_> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| {
+                row.unwrap()
+                    .get_column_iter()
+                    .map(|(_name, field)| field.clone())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                vec![Field::Null, Field::Int(4)],
+                vec![Field::Int(1), Field::Int(2)],
+                vec![Field::Int(2), Field::Int(3)],
+                vec![Field::Int(3), Field::Int(1)],
+            ],
+            rows
+        );
+    }
+
+    #[test]
+    fn order_of_values() {
+        let key = SortKey {
+            column: 0,
+            is_unsigned: false,
+            is_decimal: true,
+        };
+        let cell = |value| Cell {
+            def_level: 1,
+            value,
+        };
+        let decimal = |bytes: &[u8]| cell(Some(Value::Bytes(bytes.to_vec().into())));
+        assert_eq!(Ordering::Less, key.cmp(&cell(None), &decimal(&[0])));
+        assert_eq!(
+            Ordering::Less,
+            key.cmp(&decimal(&[0xff]), &decimal(&[0x01]))
+        );
+        assert_eq!(-2, decimal_to_i128(&[0xff, 0xfe]));
+        let key = SortKey {
+            is_unsigned: true,
+            is_decimal: false,
+            ..key
+        };
+        assert_eq!(
+            Ordering::Greater,
+            key.cmp(&cell(Some(Value::Int32(-1))), &cell(Some(Value::Int32(1))))
+        );
+    }
+}
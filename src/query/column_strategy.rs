@@ -1,14 +1,14 @@
 use std::{cmp::min, convert::TryInto, num::NonZeroUsize};
 
-use anyhow::{bail, Error};
-use log::{debug, info};
+use anyhow::{anyhow, bail, Error};
+use log::{debug, info, warn};
 use odbc_api::{
-    buffers::{AnySlice, BufferDesc},
+    buffers::{AnySlice, BufferDesc, Item},
     sys::SqlDataType,
     ColumnDescription, DataType, Nullability, ResultSetMetadata,
 };
 use parquet::{
-    basic::{LogicalType, Repetition},
+    basic::{ConvertedType, LogicalType, Repetition, TimeUnit, Type as PhysicalType},
     column::writer::ColumnWriter,
     data_type::{
         ByteArrayType, DoubleType, FixedLenByteArrayType, FloatType, Int32Type, Int64Type,
@@ -17,24 +17,34 @@ use parquet::{
 };
 
 use crate::{
+    enum_args::{
+        BindAs, BindCType, ColumnGroup, DateOutOfRange, DecimalPrecisionCheck, DecimalRounding,
+        TimestampTzFrom, TypeRule, TypeRuleTarget, UnknownTimeZone,
+    },
     parquet_buffer::ParquetBuffer,
     query::{
-        binary::Binary,
-        boolean::Boolean,
+        binary::{Binary, EmptyBinary},
+        blob::BlobExtraction,
+        boolean::{Boolean, BooleanFromInt, IntFromBoolean},
         date::Date,
         decimal::decimal_fetch_strategy,
+        derive::Derivation,
+        geoparquet::geometry_strategy,
         identical::{fetch_identical, fetch_identical_with_logical_type},
-        text::text_strategy,
+        numeric::{describe_as_numeric, numeric_strategy},
+        target_schema::{same_type, TargetSchema},
+        text::{char_strategy, text_strategy, NullTokens, RawText},
         time::time_from_text,
-        timestamp::timestamp_without_tz,
-        timestamp_tz::timestamp_tz,
+        timestamp::{timestamp_int96, timestamp_without_tz},
+        timestamp_tz::{timestamp_tz, timestamp_tz_offset},
     },
 };
 
 /// Decisions on how to handle a particular column of the ODBC result set. What buffer to bind to it
 /// for fetching, into what parquet type it is going to be translated and how to translate it from
-/// the odbc buffer elements to aforementioned parquet type.
-pub trait ColumnStrategy {
+/// the odbc buffer elements to aforementioned parquet type. Strategies are shared between the
+/// threads writing the columns of a row group, see `--compression-threads`.
+pub trait ColumnStrategy: Sync {
     /// Parquet column type used in parquet schema
     fn parquet_type(&self, name: &str) -> Type;
     /// Description of the buffer bound to the ODBC data source.
@@ -46,6 +56,67 @@ pub trait ColumnStrategy {
         column_writer: &mut ColumnWriter,
         column_view: AnySlice,
     ) -> Result<(), Error>;
+    /// Like [`Self::copy_odbc_to_parquet`], for strategies which also require the values of a key
+    /// column, e.g. the time zone of each timestamp. Only called for columns a key column has been
+    /// assigned to.
+    fn copy_odbc_to_parquet_with_key(
+        &self,
+        parquet_buffer: &mut ParquetBuffer,
+        column_writer: &mut ColumnWriter,
+        column_view: AnySlice,
+        _key_view: AnySlice,
+    ) -> Result<(), Error> {
+        self.copy_odbc_to_parquet(parquet_buffer, column_writer, column_view)
+    }
+    /// Precision and scale, if the buffer is bound as `SQL_C_NUMERIC` rather than with the type of
+    /// [`Self::buffer_desc`]. See [`super::numeric::NumericBinding`].
+    fn numeric_binding(&self) -> Option<(i16, i16)> {
+        None
+    }
+}
+
+/// Name of an additional parquet column together with the strategy used to fill it.
+pub type Companion = (String, Box<dyn ColumnStrategy>);
+
+/// Error for values fetched into another type of buffer than the one the strategy bound, e.g.
+/// because a driver reports the type of a column differently between fetches. `expected`
+/// describes the values the strategy can write.
+pub fn unexpected_view(expected: &str, view: AnySlice) -> Error {
+    let actual = match view {
+        AnySlice::Text(_) => "text",
+        AnySlice::WText(_) => "wide text",
+        AnySlice::Binary(_) => "binary",
+        AnySlice::Date(_) | AnySlice::NullableDate(_) => "dates",
+        AnySlice::Time(_) | AnySlice::NullableTime(_) => "times",
+        AnySlice::Timestamp(_) | AnySlice::NullableTimestamp(_) => "timestamps",
+        AnySlice::F64(_) | AnySlice::NullableF64(_) => "64-Bit floats",
+        AnySlice::F32(_) | AnySlice::NullableF32(_) => "32-Bit floats",
+        AnySlice::I8(_) | AnySlice::NullableI8(_) => "8-Bit integers",
+        AnySlice::I16(_) | AnySlice::NullableI16(_) => "16-Bit integers",
+        AnySlice::I32(_) | AnySlice::NullableI32(_) => "32-Bit integers",
+        AnySlice::I64(_) | AnySlice::NullableI64(_) => "64-Bit integers",
+        AnySlice::U8(_) | AnySlice::NullableU8(_) => "unsigned 8-Bit integers",
+        AnySlice::Bit(_) | AnySlice::NullableBit(_) => "bits",
+    };
+    anyhow!(
+        "Expected {expected} to be fetched, but the buffer holds {actual}. The data source may \
+        report a different type for the column than the one it has been bound with."
+    )
+}
+
+/// Values of a buffer bound with indicators. Buffers bound without indicators hold the same values,
+/// so they are accepted too, with none of the values being NULL.
+pub fn nullable_values<'a, T: Item>(
+    view: AnySlice<'a>,
+    expected: &str,
+) -> Result<Box<dyn Iterator<Item = Option<&'a T>> + 'a>, Error> {
+    if let Some(values) = T::as_nullable_slice(view) {
+        Ok(Box::new(values))
+    } else if let Some(values) = T::as_slice(view) {
+        Ok(Box::new(values.iter().map(Some)))
+    } else {
+        Err(unexpected_view(expected, view))
+    }
 }
 
 /// Controls how columns a queried and mapped onto parquet columns
@@ -56,7 +127,91 @@ pub struct MappingOptions<'a> {
     pub prefer_varbinary: bool,
     pub avoid_decimal: bool,
     pub driver_does_support_i64: bool,
+    /// `BIGINT` columns might be unsigned and are checked for it.
+    pub unsigned_bigint: bool,
+    /// Numeric columns reported with precision zero do not have a declared precision.
+    pub unbounded_numeric: bool,
     pub column_length_limit: Option<usize>,
+    /// Maximum length of the values of individual columns, overriding `column_length_limit` and
+    /// `max_binary_length`. Longer values are truncated and reported.
+    pub column_max_length: &'a [(String, usize)],
+    pub timestamp_tz_offset_column: bool,
+    /// Write character data as raw bytes, rather than transcoding it into UTF-8.
+    pub preserve_charset: bool,
+    /// Fetch every column as text, ignoring its type.
+    pub fetch_as_text_all: bool,
+    /// If specified, the type of each column is taken from this schema, rather than derived from
+    /// the column description.
+    pub target_schema: Option<&'a TargetSchema>,
+    /// How to handle dates which can not be represented in the output.
+    pub date_out_of_range: DateOutOfRange,
+    /// Timestamp columns converted to UTC using the time zone named in another column.
+    pub timestamp_tz_from: &'a [TimestampTzFrom],
+    /// How to handle timestamps of `timestamp_tz_from` with an unknown time zone.
+    pub unknown_time_zone: UnknownTimeZone,
+    /// Cluster the columns of the output by physical type and nullability.
+    pub reorder_for_size: bool,
+    /// Columns whose first NULL aborts the export.
+    pub fail_on_null: &'a [String],
+    /// Store all decimals as fixed length byte arrays of this length.
+    pub decimal_byte_length: Option<usize>,
+    /// How to round decimals with more fractional digits than their scale.
+    pub decimal_rounding: DecimalRounding,
+    /// Whether and how to verify decimals fetched as text against their precision.
+    pub decimal_validate_precision: Option<DecimalPrecisionCheck>,
+    /// Binary columns to write into separate files instead of the parquet output.
+    pub blob_extraction: Option<&'a BlobExtraction>,
+    /// Map unsigned `SMALLINT` and `INTEGER` columns to unsigned types, so their statistics are
+    /// ordered correctly. Otherwise they are mapped to signed types.
+    pub unsigned_column_order: bool,
+    /// Write values of fixed width character columns consisting only of whitespace as NULL.
+    pub char_blank_as_null: bool,
+    /// Drop columns from the output, which can not be fetched, instead of failing.
+    pub skip_columns_on_error: bool,
+    /// User supplied mappings for vendor specific SQL types, consulted before falling back to text.
+    pub type_rules: &'a [TypeRule],
+    /// Name of each column, whose NULLs are replaced, together with the fill value.
+    pub null_fill: &'a [(String, String)],
+    /// Declare every column `OPTIONAL`, regardless of the nullability reported by the driver.
+    pub force_nullable_all: bool,
+    /// Declare every column `REQUIRED`, failing at the first NULL of a column without fill value.
+    pub force_required_all: bool,
+    /// Upper bound for the length of binary values. Longer values are truncated and counted.
+    pub max_binary_length: Option<usize>,
+    /// Write timestamps without time zone as deprecated `INT96` values.
+    pub timestamp_int96: bool,
+    /// Timestamp columns written as deprecated `INT96` values, independent of `timestamp_int96`.
+    pub int96_columns: &'a [String],
+    /// Values of character columns which are written as NULL.
+    pub null_tokens: &'a NullTokens,
+    /// Write dates as timestamps at midnight, rather than using the `DATE` logical type.
+    pub date_as_timestamp: bool,
+    /// Fetch all decimals as 64-Bit floating points, accepting the loss of precision.
+    pub fast_decimals: bool,
+    /// Fetch decimals with a scale other than zero as `SQL_C_NUMERIC` struct, rather than as text,
+    /// if the driver supports it.
+    pub numeric_decimals: bool,
+    /// Integer columns written as booleans.
+    pub bool_from_int: &'a [String],
+    /// Boolean columns written as integers.
+    pub int_from_bool: &'a [String],
+    /// Binary columns holding geometries as WKB.
+    pub geometry_columns: &'a [String],
+    /// How zero-length values and NULLs of binary columns are written.
+    pub empty_binary: EmptyBinary,
+    /// Columns bound as an explicit C type, overriding any other mapping.
+    pub bind_as: &'a [BindAs],
+    /// Columns nested under a group in the output.
+    pub column_groups: &'a [ColumnGroup],
+    /// Columns computed from other columns of the result set.
+    pub derive: &'a [Derivation],
+    /// Warn about each column, which is not written with a type matching its type in the data
+    /// source.
+    pub warn_on_implicit_conversion: bool,
+    /// Name of the root group of the parquet schema.
+    pub schema_name: &'a str,
+    /// Name of an additional column holding the bookmark of each row, if any.
+    pub rowid_column: Option<&'a str>,
 }
 
 /// Fetch strategies based on column description and environment arguments `MappingOptions`.
@@ -75,7 +230,7 @@ pub fn strategy_from_column_description(
     cd: &ColumnDescription,
     name: &str,
     mapping_options: MappingOptions,
-    cursor: &mut impl ResultSetMetadata,
+    cursor: &mut impl ColumnMetadata,
     index: i16,
 ) -> Result<Box<dyn ColumnStrategy>, Error> {
     let MappingOptions {
@@ -84,51 +239,186 @@ pub fn strategy_from_column_description(
         prefer_varbinary,
         avoid_decimal,
         driver_does_support_i64,
+        unsigned_bigint,
+        unbounded_numeric,
         column_length_limit,
+        column_max_length,
+        timestamp_tz_offset_column: _,
+        preserve_charset,
+        fetch_as_text_all,
+        target_schema: _,
+        date_out_of_range,
+        decimal_byte_length,
+        decimal_rounding,
+        decimal_validate_precision,
+        blob_extraction: _,
+        unsigned_column_order,
+        char_blank_as_null,
+        skip_columns_on_error: _,
+        type_rules,
+        null_fill: _,
+        force_nullable_all,
+        force_required_all: _,
+        max_binary_length,
+        timestamp_int96: use_int96,
+        int96_columns,
+        null_tokens,
+        date_as_timestamp,
+        fast_decimals,
+        numeric_decimals,
+        bool_from_int,
+        int_from_bool,
+        geometry_columns,
+        empty_binary,
+        bind_as,
+        column_groups: _,
+        derive: _,
+        warn_on_implicit_conversion: _,
+        schema_name: _,
+        rowid_column: _,
+        timestamp_tz_from: _,
+        unknown_time_zone: _,
+        reorder_for_size: _,
+        fail_on_null: _,
     } = mapping_options;
 
-    // Convert ODBC nullability to Parquet repetition. If the ODBC driver can not tell whether a
-    // given column in the result may contain NULLs we assume it does.
-    let repetition = match cd.nullability {
-        Nullability::Nullable | Nullability::Unknown => Repetition::OPTIONAL,
-        Nullability::NoNulls => Repetition::REQUIRED,
+    let repetition = if force_nullable_all {
+        Repetition::OPTIONAL
+    } else {
+        repetition(cd)
     };
 
-    let is_optional = cd.could_be_nullable();
+    let is_optional = force_nullable_all || cd.could_be_nullable();
+
+    let column_max_length = column_max_length
+        .iter()
+        .find(|(column, _)| column == name)
+        .map(|&(_, length)| length);
+    let column_length_limit = column_max_length.or(column_length_limit);
+    let max_binary_length = column_max_length.or(max_binary_length);
 
     let apply_length_limit = |reported_length: Option<NonZeroUsize>| {
-        match (reported_length, column_length_limit) {
-            (None, None) => bail!(
-                "Column '{}' with index {}. Driver reported a display length of 0. This can happen for \
-                variadic types without a fixed upper bound. You can manually specify an upper bound \
-                for variadic columns using the `--column-length-limit` command line argument.",
-                name, index
-            ),
-            // No upper bound has been reported by the driver, so we use the one supplied by the
-            // user.
-            (None, Some(column_length_limit)) => Ok(column_length_limit),
-            // Driver provided us with a length and no upper bound has been specified by the user.
-            (Some(reported_length), None) => Ok(reported_length.get()),
-            (Some(reported_length), Some(column_length_limit)) => {
-                Ok(min(reported_length.get(), column_length_limit))
+        length_with_limit(reported_length, column_length_limit, name, index)
+    };
+
+    if let Some(bind_as) = bind_as.iter().find(|bind_as| bind_as.column_name == name) {
+        let c_type = bind_as.c_type;
+        debug!("Binding column {index} as {}.", c_type.name());
+        let length = || {
+            let length = match cd.data_type {
+                DataType::Binary { length }
+                | DataType::Varbinary { length }
+                | DataType::LongVarbinary { length } => {
+                    length.map_or_else(|| cursor.display_size(index), |length| Ok(Some(length)))?
+                }
+                ref dt => text_buffer_length(dt, c_type == BindCType::WChar, || {
+                    cursor.display_size(index)
+                })?,
+            };
+            apply_length_limit(length)
+        };
+        return strategy_from_c_type(
+            c_type,
+            &cd.data_type,
+            name,
+            repetition,
+            date_out_of_range,
+            length,
+        );
+    }
+
+    if bool_from_int.iter().any(|column| column == name) {
+        return match cd.data_type {
+            DataType::TinyInt | DataType::SmallInt | DataType::Integer => {
+                Ok(Box::new(BooleanFromInt::new(repetition, false)))
             }
+            DataType::BigInt => Ok(Box::new(BooleanFromInt::new(
+                repetition,
+                driver_does_support_i64,
+            ))),
+            other => bail!(
+                "Column '{name}' passed to `--coerce-bool-from-int` must be an integer, but is of \
+                type {other:?}."
+            ),
+        };
+    }
+    if int_from_bool.iter().any(|column| column == name) {
+        return match cd.data_type {
+            DataType::Bit => Ok(Box::new(IntFromBoolean::new(repetition))),
+            other => bail!(
+                "Column '{name}' passed to `--coerce-int-from-bool` must be a BIT column, but is \
+                of type {other:?}."
+            ),
+        };
+    }
+
+    if geometry_columns.iter().any(|column| column == name) {
+        return geometry_strategy(cd.data_type, name, repetition, apply_length_limit);
+    }
+
+    if int96_columns.iter().any(|column| column == name) {
+        return match cd.data_type {
+            DataType::Timestamp { .. } => Ok(timestamp_int96(repetition)),
+            DataType::Date if date_as_timestamp => Ok(timestamp_int96(repetition)),
+            other => bail!(
+                "Column '{name}' passed to `--int96-columns` must be a timestamp without time \
+                zone, but is of type {other:?}."
+            ),
+        };
+    }
+
+    if let DataType::Other {
+        data_type,
+        column_size,
+        decimal_digits: _,
+    } = cd.data_type
+    {
+        let rule = type_rules.iter().find(|rule| rule.sql_type == data_type.0);
+        if let Some(rule) = rule.filter(|_| !fetch_as_text_all) {
+            debug!("Applying {rule:?} to column {index}.");
+            let length = || apply_length_limit(column_size);
+            return strategy_from_type_rule(rule.target, repetition, use_utf16, length);
         }
-    };
+    }
 
     let strategy: Box<dyn ColumnStrategy> = match cd.data_type {
+        _ if fetch_as_text_all => {
+            let length =
+                text_buffer_length(&cd.data_type, use_utf16, || cursor.display_size(index))?;
+            let length = apply_length_limit(length)?;
+            text_strategy(use_utf16, repetition, length)
+        }
         DataType::Float { precision: 0..=24 } | DataType::Real => {
             fetch_identical::<FloatType>(is_optional)
         }
         // Map all precisions larger than 24 to double. Double would be technically precision 53.
         DataType::Float { precision: _ } => fetch_identical::<DoubleType>(is_optional),
         DataType::Double => fetch_identical::<DoubleType>(is_optional),
-        DataType::SmallInt => fetch_identical_with_logical_type::<Int32Type>(
-            is_optional,
-            LogicalType::Integer {
-                bit_width: 16,
-                is_signed: true,
-            },
-        ),
+        DataType::SmallInt => {
+            // An unsigned 16-Bit integer still fits into the signed 32-Bit buffer, but must be
+            // annotated as unsigned, for statistics to be ordered correctly.
+            let is_signed = !unsigned_column_order || !cursor.is_unsigned(index)?;
+            fetch_identical_with_logical_type::<Int32Type>(
+                is_optional,
+                LogicalType::Integer {
+                    bit_width: 16,
+                    is_signed,
+                },
+            )
+        }
+        DataType::Integer if unsigned_column_order && cursor.is_unsigned(index)? => {
+            // Largest unsigned 32-Bit integer has 10 digits.
+            decimal_fetch_strategy(
+                is_optional,
+                0,
+                10,
+                avoid_decimal,
+                driver_does_support_i64,
+                decimal_byte_length,
+                decimal_rounding,
+                decimal_validate_precision,
+            )?
+        }
         DataType::Integer => fetch_identical_with_logical_type::<Int32Type>(
             is_optional,
             LogicalType::Integer {
@@ -136,7 +426,42 @@ pub fn strategy_from_column_description(
                 is_signed: true,
             },
         ),
-        DataType::Date => Box::new(Date::new(repetition)),
+        // The driver converts the date into a timestamp with all time components set to zero.
+        DataType::Date if date_as_timestamp && use_int96 => timestamp_int96(repetition),
+        DataType::Date if date_as_timestamp => timestamp_without_tz(repetition, 0),
+        DataType::Date => Box::new(Date::new(repetition, date_out_of_range)),
+        DataType::Numeric { precision, .. } | DataType::Decimal { precision, .. }
+            if fast_decimals =>
+        {
+            // A double has 53 significant bits, which suffices for 15 decimal digits.
+            if precision == 0 || precision > 15 {
+                warn!(
+                    "Column '{name}' has a precision of {precision} digits and is fetched as \
+                    DOUBLE. Digits beyond about 15 significant ones are lost."
+                );
+            }
+            fetch_identical::<DoubleType>(is_optional)
+        }
+        DataType::Numeric { precision: 0, .. } | DataType::Decimal { precision: 0, .. }
+            if unbounded_numeric =>
+        {
+            fetch_identical::<DoubleType>(is_optional)
+        }
+        DataType::Numeric { scale, precision } | DataType::Decimal { scale, precision }
+            if numeric_decimals
+                && !avoid_decimal
+                && scale > 0
+                && precision <= 38
+                && scale as usize <= precision
+                && accepts_numeric(cursor, name, index, precision, scale) =>
+        {
+            numeric_strategy(
+                is_optional,
+                scale.into(),
+                precision.try_into().unwrap(),
+                decimal_byte_length,
+            )?
+        }
         DataType::Numeric { scale, precision } | DataType::Decimal { scale, precision } => {
             decimal_fetch_strategy(
                 is_optional,
@@ -144,15 +469,32 @@ pub fn strategy_from_column_description(
                 precision.try_into().unwrap(),
                 avoid_decimal,
                 driver_does_support_i64,
-            )
+                decimal_byte_length,
+                decimal_rounding,
+                decimal_validate_precision,
+            )?
         }
+        DataType::Timestamp { .. } if use_int96 => timestamp_int96(repetition),
         DataType::Timestamp { precision } => {
             timestamp_without_tz(repetition, precision.try_into().unwrap())
         }
+        DataType::BigInt if unsigned_bigint && cursor.is_unsigned(index)? => {
+            // Largest unsigned 64-Bit integer has 20 digits.
+            decimal_fetch_strategy(
+                is_optional,
+                0,
+                20,
+                false,
+                driver_does_support_i64,
+                decimal_byte_length,
+                decimal_rounding,
+                decimal_validate_precision,
+            )?
+        }
         DataType::BigInt => fetch_identical::<Int64Type>(is_optional),
         DataType::Bit => Box::new(Boolean::new(repetition)),
         DataType::TinyInt => {
-            let is_signed = !cursor.column_is_unsigned(index.try_into().unwrap())?;
+            let is_signed = !cursor.is_unsigned(index)?;
             fetch_identical_with_logical_type::<Int32Type>(
                 is_optional,
                 LogicalType::Integer {
@@ -161,17 +503,40 @@ pub fn strategy_from_column_description(
                 },
             )
         }
+        DataType::Binary { length }
+        | DataType::Varbinary { length }
+        | DataType::LongVarbinary { length }
+            if max_binary_length.is_some() =>
+        {
+            let max = max_binary_length.unwrap();
+            let length = length.map_or(max, |length| length.get().min(max));
+            let length = column_length_limit.map_or(length, |limit| limit.min(length));
+            if matches!(cd.data_type, DataType::Binary { .. }) && !prefer_varbinary {
+                Box::new(
+                    Binary::<FixedLenByteArrayType>::with_truncation(repetition, length)
+                        .with_empty(empty_binary),
+                )
+            } else {
+                Box::new(
+                    Binary::<ByteArrayType>::with_truncation(repetition, length)
+                        .with_empty(empty_binary),
+                )
+            }
+        }
         DataType::Binary { length } => {
             let length = apply_length_limit(length)?;
             if prefer_varbinary {
-                Box::new(Binary::<ByteArrayType>::new(repetition, length))
+                Box::new(Binary::<ByteArrayType>::new(repetition, length).with_empty(empty_binary))
             } else {
-                Box::new(Binary::<FixedLenByteArrayType>::new(repetition, length))
+                Box::new(
+                    Binary::<FixedLenByteArrayType>::new(repetition, length)
+                        .with_empty(empty_binary),
+                )
             }
         }
         DataType::Varbinary { length } | DataType::LongVarbinary { length } => {
             let length = apply_length_limit(length)?;
-            Box::new(Binary::<ByteArrayType>::new(repetition, length))
+            Box::new(Binary::<ByteArrayType>::new(repetition, length).with_empty(empty_binary))
         }
         // For character data we consider binding to wide (16-Bit) buffers in order to avoid
         // depending on the system locale being utf-8. For other character buffers we always use
@@ -182,13 +547,22 @@ pub fn strategy_from_column_description(
         | DataType::WVarchar { length: _ }
         | DataType::LongVarchar { length: _ }
         | DataType::WChar { length: _ }) => {
-            let len_in_chars = if use_utf16 {
-                dt.utf16_len()
+            if preserve_charset {
+                let length = apply_length_limit(dt.utf8_len())?;
+                Box::new(RawText::with_bytes_length(repetition, length))
             } else {
-                dt.utf8_len()
-            };
-            let length = apply_length_limit(len_in_chars)?;
-            text_strategy(use_utf16, repetition, length)
+                let length = text_buffer_length(&dt, use_utf16, || Ok(None))?;
+                let length = apply_length_limit(length)?;
+                let is_fixed_width = matches!(dt, DataType::Char { .. } | DataType::WChar { .. });
+                char_strategy(
+                    use_utf16,
+                    repetition,
+                    length,
+                    char_blank_as_null && is_fixed_width,
+                    null_tokens,
+                    column_max_length.is_some(),
+                )
+            }
         }
         DataType::Other {
             data_type: SqlDataType(-154),
@@ -219,6 +593,19 @@ pub fn strategy_from_column_description(
                 unknown_non_char_type(cd, cursor, index, repetition, apply_length_limit)?
             }
         }
+        DataType::Other {
+            data_type: SqlDataType(-150),
+            column_size,
+            decimal_digits: _,
+        } if db_name == "Microsoft SQL Server" => {
+            // -150 is `SQL_VARIANT` on Microsoft SQL Server. The type of its values may differ
+            // from row to row, and the base type is only reported if fetching value by value. We
+            // fetch the canonical text representation instead. Using the same character encoding
+            // as for other text columns keeps the representation independent of the system locale.
+            let length = cursor.display_size(index)?.or(column_size);
+            let length = apply_length_limit(max_code_units(length, use_utf16))?;
+            text_strategy(use_utf16, repetition, length)
+        }
         DataType::Unknown | DataType::Time { .. } | DataType::Other { .. } => {
             unknown_non_char_type(cd, cursor, index, repetition, apply_length_limit)?
         }
@@ -233,9 +620,410 @@ pub fn strategy_from_column_description(
     Ok(strategy)
 }
 
+/// Fetch strategy for a column with a vendor specific SQL type, as configured by a `--type-rule`.
+///
+/// * `length`: Called to obtain the maximum length of text and binary values.
+fn strategy_from_type_rule(
+    target: TypeRuleTarget,
+    repetition: Repetition,
+    use_utf16: bool,
+    length: impl FnOnce() -> Result<usize, Error>,
+) -> Result<Box<dyn ColumnStrategy>, Error> {
+    let is_optional = repetition == Repetition::OPTIONAL;
+    let strategy = match target {
+        TypeRuleTarget::Text => text_strategy(use_utf16, repetition, length()?),
+        TypeRuleTarget::Binary => Box::new(Binary::<ByteArrayType>::new(repetition, length()?)),
+        TypeRuleTarget::Int32 => fetch_identical_with_logical_type::<Int32Type>(
+            is_optional,
+            LogicalType::Integer {
+                bit_width: 32,
+                is_signed: true,
+            },
+        ),
+        TypeRuleTarget::Int64 => fetch_identical::<Int64Type>(is_optional),
+        TypeRuleTarget::Float => fetch_identical::<FloatType>(is_optional),
+        TypeRuleTarget::Double => fetch_identical::<DoubleType>(is_optional),
+    };
+    Ok(strategy)
+}
+
+/// Fetch strategy for a column bound as an explicit C type with `--bind-as`. Fails if ODBC does not
+/// define a conversion from the SQL type of the column into the C type.
+///
+/// * `length`: Called to obtain the maximum length of text and binary values.
+fn strategy_from_c_type(
+    c_type: BindCType,
+    data_type: &DataType,
+    name: &str,
+    repetition: Repetition,
+    date_out_of_range: DateOutOfRange,
+    length: impl FnOnce() -> Result<usize, Error>,
+) -> Result<Box<dyn ColumnStrategy>, Error> {
+    if !is_conversion_defined(data_type, c_type) {
+        bail!(
+            "Column '{name}' of type {data_type:?} can not be bound as {}. ODBC does not define \
+            this conversion.",
+            c_type.name()
+        )
+    }
+    let is_optional = repetition == Repetition::OPTIONAL;
+    let strategy = match c_type {
+        BindCType::Char => text_strategy(false, repetition, length()?),
+        BindCType::WChar => text_strategy(true, repetition, length()?),
+        BindCType::Binary => Box::new(Binary::<ByteArrayType>::new(repetition, length()?)),
+        BindCType::SLong => fetch_identical_with_logical_type::<Int32Type>(
+            is_optional,
+            LogicalType::Integer {
+                bit_width: 32,
+                is_signed: true,
+            },
+        ),
+        BindCType::SBigInt => fetch_identical::<Int64Type>(is_optional),
+        BindCType::Float => fetch_identical::<FloatType>(is_optional),
+        BindCType::Double => fetch_identical::<DoubleType>(is_optional),
+        BindCType::Bit => Box::new(Boolean::new(repetition)),
+        BindCType::TypeDate => Box::new(Date::new(repetition, date_out_of_range)),
+        BindCType::TypeTimestamp => timestamp_without_tz(repetition, 6),
+    };
+    Ok(strategy)
+}
+
+/// Conversions from SQL to C types as defined in appendix D of the ODBC reference. Character and
+/// binary C types are defined for all SQL types. Vendor specific SQL types are left to the driver.
+fn is_conversion_defined(data_type: &DataType, c_type: BindCType) -> bool {
+    let is_character = matches!(
+        data_type,
+        DataType::Char { .. }
+            | DataType::WChar { .. }
+            | DataType::Varchar { .. }
+            | DataType::WVarchar { .. }
+            | DataType::LongVarchar { .. }
+    );
+    let is_numeric = matches!(
+        data_type,
+        DataType::Numeric { .. }
+            | DataType::Decimal { .. }
+            | DataType::Integer
+            | DataType::SmallInt
+            | DataType::TinyInt
+            | DataType::BigInt
+            | DataType::Float { .. }
+            | DataType::Real
+            | DataType::Double
+            | DataType::Bit
+    );
+    match c_type {
+        _ if is_character || matches!(data_type, DataType::Unknown | DataType::Other { .. }) => {
+            true
+        }
+        BindCType::Char | BindCType::WChar | BindCType::Binary => true,
+        BindCType::SLong
+        | BindCType::SBigInt
+        | BindCType::Float
+        | BindCType::Double
+        | BindCType::Bit => is_numeric,
+        BindCType::TypeDate => matches!(data_type, DataType::Date | DataType::Timestamp { .. }),
+        BindCType::TypeTimestamp => matches!(
+            data_type,
+            DataType::Date | DataType::Time { .. } | DataType::Timestamp { .. }
+        ),
+    }
+}
+
+/// Fetch strategy producing a parquet column of a given target type. Used to enforce an existing
+/// schema, rather than deriving the parquet type from the column description. The ODBC driver is
+/// asked to convert the values into a buffer suitable for the target type. Fails if there is no
+/// strategy producing the target type.
+///
+/// * `cd`: Description of the column for which we need to pick a fetch strategy
+/// * `target`: Parquet type the column is written as. Its name is used as the name of the column.
+/// * `index`: One based column index.
+pub fn strategy_from_target_type(
+    cd: &ColumnDescription,
+    target: &Type,
+    mapping_options: MappingOptions,
+    cursor: &mut impl ColumnMetadata,
+    index: i16,
+) -> Result<Box<dyn ColumnStrategy>, Error> {
+    let MappingOptions {
+        use_utf16,
+        driver_does_support_i64,
+        column_length_limit,
+        column_max_length,
+        date_out_of_range,
+        decimal_rounding,
+        decimal_validate_precision,
+        ..
+    } = mapping_options;
+    let name = target.name();
+    let column_length_limit = column_max_length
+        .iter()
+        .find(|(column, _)| column == name)
+        .map(|&(_, length)| length)
+        .or(column_length_limit);
+    if !target.is_primitive() {
+        bail!("Column '{name}' of the target schema is not a primitive type.")
+    }
+    let repetition = target.get_basic_info().repetition();
+    let is_optional = repetition == Repetition::OPTIONAL;
+    let source_length = |cursor: &mut _| -> Result<usize, Error> {
+        let length = if let Some(len) = cd.data_type.column_size() {
+            Some(len)
+        } else {
+            ColumnMetadata::display_size(cursor, index)?
+        };
+        length_with_limit(length, column_length_limit, name, index)
+    };
+    let logical_type = target.get_basic_info().logical_type();
+    // Files written by older writers may only carry a converted type.
+    let converted_type = target.get_basic_info().converted_type();
+    let strategy: Box<dyn ColumnStrategy> = match (target.get_physical_type(), logical_type) {
+        (PhysicalType::BOOLEAN, _) => Box::new(Boolean::new(repetition)),
+        (physical_type, _) if converted_type == ConvertedType::DECIMAL => {
+            // Fixed length byte arrays may be longer than required by the precision.
+            let decimal_byte_length = match *target {
+                Type::PrimitiveType { type_length, .. }
+                    if physical_type == PhysicalType::FIXED_LEN_BYTE_ARRAY =>
+                {
+                    Some(type_length.try_into()?)
+                }
+                _ => None,
+            };
+            decimal_fetch_strategy(
+                is_optional,
+                target.get_scale(),
+                target.get_precision().try_into()?,
+                false,
+                driver_does_support_i64,
+                decimal_byte_length,
+                decimal_rounding,
+                decimal_validate_precision,
+            )?
+        }
+        (PhysicalType::INT32, _) if converted_type == ConvertedType::DATE => {
+            Box::new(Date::new(repetition, date_out_of_range))
+        }
+        (
+            PhysicalType::INT64,
+            Some(LogicalType::Timestamp {
+                is_adjusted_to_u_t_c: false,
+                unit,
+            }),
+        ) => {
+            let precision = match unit {
+                TimeUnit::MILLIS(_) => 3,
+                TimeUnit::MICROS(_) => 6,
+                TimeUnit::NANOS(_) => 9,
+            };
+            timestamp_without_tz(repetition, precision)
+        }
+        (PhysicalType::INT96, _) => timestamp_int96(repetition),
+        (PhysicalType::INT32, Some(logical_type @ LogicalType::Integer { .. })) => {
+            fetch_identical_with_logical_type::<Int32Type>(is_optional, logical_type)
+        }
+        (PhysicalType::INT32, None) => fetch_identical::<Int32Type>(is_optional),
+        (PhysicalType::INT64, Some(logical_type @ LogicalType::Integer { .. })) => {
+            fetch_identical_with_logical_type::<Int64Type>(is_optional, logical_type)
+        }
+        (PhysicalType::INT64, None) => fetch_identical::<Int64Type>(is_optional),
+        (PhysicalType::FLOAT, None) => fetch_identical::<FloatType>(is_optional),
+        (PhysicalType::DOUBLE, None) => fetch_identical::<DoubleType>(is_optional),
+        (PhysicalType::BYTE_ARRAY, _) if converted_type == ConvertedType::UTF8 => {
+            let length =
+                text_buffer_length(&cd.data_type, use_utf16, || cursor.display_size(index))?;
+            let length = length_with_limit(length, column_length_limit, name, index)?;
+            text_strategy(use_utf16, repetition, length)
+        }
+        (PhysicalType::BYTE_ARRAY, None) => Box::new(Binary::<ByteArrayType>::new(
+            repetition,
+            source_length(cursor)?,
+        )),
+        (PhysicalType::FIXED_LEN_BYTE_ARRAY, None) => {
+            let Type::PrimitiveType { type_length, .. } = *target else {
+                unreachable!("Target type has been checked to be primitive")
+            };
+            Box::new(Binary::<FixedLenByteArrayType>::new(
+                repetition,
+                type_length.try_into()?,
+            ))
+        }
+        (physical_type, logical_type) => bail!(
+            "Column '{name}' can not be written as {physical_type} with logical type \
+            {logical_type:?} as required by the target schema."
+        ),
+    };
+
+    if !same_type(&strategy.parquet_type(name), target) {
+        bail!(
+            "Column '{name}' of type {:?} can not be coerced into the type of the target schema. \
+            Closest supported type: {:?}. Target type: {:?}.",
+            cd.data_type,
+            strategy.parquet_type(name),
+            target
+        )
+    }
+
+    debug!(
+        "ODBC buffer description for column {} matched with target schema: {:?}",
+        index,
+        strategy.buffer_desc()
+    );
+
+    Ok(strategy)
+}
+
+/// Additional parquet columns derived from the same ODBC column as the one described by `cd`. They
+/// share the fetch buffer of the strategy returned by [`strategy_from_column_description`]. Each
+/// companion is returned together with its column name.
+pub fn companion_strategies(
+    cd: &ColumnDescription,
+    name: &str,
+    mapping_options: MappingOptions,
+) -> Result<Vec<Companion>, Error> {
+    let mut companions: Vec<Companion> = Vec::new();
+    if let DataType::Other {
+        data_type: SqlDataType(-155),
+        column_size: _,
+        decimal_digits: precision,
+    } = cd.data_type
+    {
+        if mapping_options.timestamp_tz_offset_column
+            && mapping_options.db_name == "Microsoft SQL Server"
+        {
+            companions.push((
+                format!("{name}_offset"),
+                timestamp_tz_offset(
+                    precision.try_into().unwrap(),
+                    if mapping_options.force_nullable_all {
+                        Repetition::OPTIONAL
+                    } else {
+                        repetition(cd)
+                    },
+                ),
+            ));
+        }
+    }
+    Ok(companions)
+}
+
+/// Length of the buffer elements for a variadic column, taking the `--column-length-limit` into
+/// account.
+fn length_with_limit(
+    reported_length: Option<NonZeroUsize>,
+    column_length_limit: Option<usize>,
+    name: &str,
+    index: i16,
+) -> Result<usize, Error> {
+    match (reported_length, column_length_limit) {
+        (None, None) => bail!(
+            "Column '{}' with index {}. Driver reported a display length of 0. This can happen for \
+            variadic types without a fixed upper bound. You can manually specify an upper bound \
+            for variadic columns using the `--column-length-limit` command line argument.",
+            name,
+            index
+        ),
+        // No upper bound has been reported by the driver, so we use the one supplied by the
+        // user.
+        (None, Some(column_length_limit)) => Ok(column_length_limit),
+        // Driver provided us with a length and no upper bound has been specified by the user.
+        (Some(reported_length), None) => Ok(reported_length.get()),
+        (Some(reported_length), Some(column_length_limit)) => {
+            Ok(min(reported_length.get(), column_length_limit))
+        }
+    }
+}
+
+/// Information about a column of the result set, which is not part of its description and is
+/// therefore only queried if needed. Implemented for every cursor, and for stand-ins in tests.
+pub trait ColumnMetadata {
+    /// Maximum number of characters required to display the values of the column at the one based
+    /// `index`.
+    fn display_size(&mut self, index: i16) -> Result<Option<NonZeroUsize>, Error>;
+    /// `true` if the column at the one based `index` holds unsigned numbers.
+    fn is_unsigned(&mut self, index: i16) -> Result<bool, Error>;
+    /// Describes the column at the one based `index` as `SQL_C_NUMERIC` with `precision` and
+    /// `scale` in the application row descriptor. Fails if the driver does not support this.
+    fn describe_as_numeric(&mut self, index: i16, precision: i16, scale: i16) -> Result<(), Error>;
+}
+
+impl<T> ColumnMetadata for T
+where
+    T: ResultSetMetadata,
+{
+    fn display_size(&mut self, index: i16) -> Result<Option<NonZeroUsize>, Error> {
+        Ok(self.col_display_size(index.try_into().unwrap())?)
+    }
+
+    fn is_unsigned(&mut self, index: i16) -> Result<bool, Error> {
+        Ok(self.column_is_unsigned(index.try_into().unwrap())?)
+    }
+
+    fn describe_as_numeric(&mut self, index: i16, precision: i16, scale: i16) -> Result<(), Error> {
+        describe_as_numeric(self.as_stmt_ref(), index, precision, scale)
+    }
+}
+
+/// `true` if the driver accepts binding the decimal column at the one based `index` as
+/// `SQL_C_NUMERIC`. Otherwise a warning is logged, and the column is fetched as text.
+fn accepts_numeric(
+    cursor: &mut impl ColumnMetadata,
+    name: &str,
+    index: i16,
+    precision: usize,
+    scale: i16,
+) -> bool {
+    match cursor.describe_as_numeric(index, precision.try_into().unwrap(), scale) {
+        Ok(()) => true,
+        Err(error) => {
+            warn!(
+                "Column '{name}' can not be bound as numeric struct, fetching it as text: {error}"
+            );
+            false
+        }
+    }
+}
+
+/// Length of the buffer for text values of a column of `data_type`, in bytes if bound as UTF-8 or
+/// in 2-byte code units if bound as UTF-16. Lengths of character types and display sizes are given
+/// in characters, so they are multiplied with the maximum length of a character in the encoding.
+/// Other types are assumed to be rendered as ASCII. `display_size` is only asked, if the length can
+/// not be derived from the data type alone.
+fn text_buffer_length(
+    data_type: &DataType,
+    use_utf16: bool,
+    display_size: impl FnOnce() -> Result<Option<NonZeroUsize>, Error>,
+) -> Result<Option<NonZeroUsize>, Error> {
+    let length = match *data_type {
+        // Unlike other character types, `utf8_len` and `utf16_len` take its length as is.
+        DataType::LongVarchar { length } => return Ok(max_code_units(length, use_utf16)),
+        ref dt if use_utf16 => dt.utf16_len(),
+        ref dt => dt.utf8_len(),
+    };
+    length.map_or_else(
+        || Ok(max_code_units(display_size()?, use_utf16)),
+        |length| Ok(Some(length)),
+    )
+}
+
+/// Maximum number of code units required for `chars` characters. A character takes up to four bytes
+/// in UTF-8, or two code units in UTF-16.
+fn max_code_units(chars: Option<NonZeroUsize>, use_utf16: bool) -> Option<NonZeroUsize> {
+    let per_char = if use_utf16 { 2 } else { 4 };
+    chars.and_then(|chars| NonZeroUsize::new(chars.get().saturating_mul(per_char)))
+}
+
+/// Convert ODBC nullability to Parquet repetition. If the ODBC driver can not tell whether a given
+/// column in the result may contain NULLs we assume it does.
+fn repetition(cd: &ColumnDescription) -> Repetition {
+    match cd.nullability {
+        Nullability::Nullable | Nullability::Unknown => Repetition::OPTIONAL,
+        Nullability::NoNulls => Repetition::REQUIRED,
+    }
+}
+
 fn unknown_non_char_type(
     cd: &ColumnDescription,
-    cursor: &mut impl ResultSetMetadata,
+    cursor: &mut impl ColumnMetadata,
     index: i16,
     repetition: Repetition,
     apply_length_limit: impl FnOnce(Option<NonZeroUsize>) -> Result<usize, Error>,
@@ -243,9 +1031,508 @@ fn unknown_non_char_type(
     let length = if let Some(len) = cd.data_type.utf8_len() {
         Some(len)
     } else {
-        cursor.col_display_size(index.try_into().unwrap())?
+        cursor.display_size(index)?
     };
     let length = apply_length_limit(length)?;
     let use_utf16 = false;
     Ok(text_strategy(use_utf16, repetition, length))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use anyhow::{anyhow, Error};
+    use parquet::{
+        basic::{ConvertedType, LogicalType, Repetition, TimeUnit, Type as PhysicalType},
+        schema::types::Type,
+    };
+
+    use odbc_api::{
+        buffers::BufferDesc, sys::SqlDataType, ColumnDescription, DataType, Nullability,
+    };
+
+    use crate::{
+        enum_args::{
+            BindCType, DateOutOfRange, DecimalRounding, TypeRule, TypeRuleTarget, UnknownTimeZone,
+        },
+        query::{binary::EmptyBinary, text::NullTokens},
+    };
+
+    use super::{
+        is_conversion_defined, strategy_from_column_description, strategy_from_type_rule,
+        text_buffer_length, ColumnMetadata, MappingOptions,
+    };
+
+    /// Stands in for the cursor, answering every question about a column the same way.
+    struct Metadata {
+        is_unsigned: bool,
+        /// Whether the driver supports binding decimals as `SQL_C_NUMERIC`.
+        accepts_numeric: bool,
+    }
+
+    impl ColumnMetadata for Metadata {
+        fn display_size(&mut self, _index: i16) -> Result<Option<NonZeroUsize>, Error> {
+            Ok(NonZeroUsize::new(20))
+        }
+
+        fn is_unsigned(&mut self, _index: i16) -> Result<bool, Error> {
+            Ok(self.is_unsigned)
+        }
+
+        fn describe_as_numeric(
+            &mut self,
+            _index: i16,
+            _precision: i16,
+            _scale: i16,
+        ) -> Result<(), Error> {
+            if self.accepts_numeric {
+                Ok(())
+            } else {
+                Err(anyhow!("Optional feature not implemented"))
+            }
+        }
+    }
+
+    fn default_options(null_tokens: &NullTokens) -> MappingOptions<'_> {
+        MappingOptions {
+            db_name: "Microsoft SQL Server",
+            use_utf16: false,
+            prefer_varbinary: false,
+            avoid_decimal: false,
+            driver_does_support_i64: true,
+            unsigned_bigint: false,
+            unbounded_numeric: false,
+            column_length_limit: None,
+            column_max_length: &[],
+            timestamp_tz_offset_column: false,
+            preserve_charset: false,
+            fetch_as_text_all: false,
+            target_schema: None,
+            date_out_of_range: DateOutOfRange::Error,
+            timestamp_tz_from: &[],
+            unknown_time_zone: UnknownTimeZone::Error,
+            reorder_for_size: false,
+            fail_on_null: &[],
+            decimal_byte_length: None,
+            decimal_rounding: DecimalRounding::HalfUp,
+            decimal_validate_precision: None,
+            blob_extraction: None,
+            unsigned_column_order: false,
+            char_blank_as_null: false,
+            skip_columns_on_error: false,
+            type_rules: &[],
+            null_fill: &[],
+            force_nullable_all: false,
+            force_required_all: false,
+            max_binary_length: None,
+            timestamp_int96: false,
+            int96_columns: &[],
+            null_tokens,
+            date_as_timestamp: false,
+            fast_decimals: false,
+            numeric_decimals: false,
+            bool_from_int: &[],
+            int_from_bool: &[],
+            geometry_columns: &[],
+            empty_binary: EmptyBinary::Keep,
+            bind_as: &[],
+            column_groups: &[],
+            derive: &[],
+            warn_on_implicit_conversion: false,
+            schema_name: "schema",
+            rowid_column: None,
+        }
+    }
+
+    /// Parquet type of a nullable column of `data_type`.
+    fn parquet_type(data_type: DataType, is_unsigned: bool, options: MappingOptions) -> Type {
+        let cd = ColumnDescription::new("a", data_type, Nullability::Nullable);
+        let mut metadata = Metadata {
+            is_unsigned,
+            accepts_numeric: true,
+        };
+        let strategy =
+            strategy_from_column_description(&cd, "a", options, &mut metadata, 1).unwrap();
+        strategy.parquet_type("a")
+    }
+
+    /// Physical and logical type of the parquet column a nullable column of `data_type` is mapped
+    /// to.
+    fn map_column(
+        data_type: DataType,
+        is_unsigned: bool,
+        options: MappingOptions,
+    ) -> (PhysicalType, Option<LogicalType>) {
+        let parquet_type = parquet_type(data_type, is_unsigned, options);
+        (
+            parquet_type.get_physical_type(),
+            parquet_type.get_basic_info().logical_type(),
+        )
+    }
+
+    /// Text columns are only annotated with the converted type.
+    fn is_text(data_type: DataType, options: MappingOptions) -> bool {
+        let parquet_type = parquet_type(data_type, false, options);
+        parquet_type.get_physical_type() == PhysicalType::BYTE_ARRAY
+            && parquet_type.get_basic_info().converted_type() == ConvertedType::UTF8
+    }
+
+    #[test]
+    fn map_sql_types_to_parquet_types() {
+        let null_tokens = NullTokens::default();
+        let options = default_options(&null_tokens);
+        let nz = NonZeroUsize::new;
+        let map = |data_type| map_column(data_type, false, options);
+        let integer = |bit_width, is_signed| {
+            Some(LogicalType::Integer {
+                bit_width,
+                is_signed,
+            })
+        };
+        let timestamp = |unit, is_adjusted_to_u_t_c| {
+            Some(LogicalType::Timestamp {
+                is_adjusted_to_u_t_c,
+                unit,
+            })
+        };
+        let millis = TimeUnit::MILLIS(Default::default());
+        let micros = TimeUnit::MICROS(Default::default());
+        let nanos = TimeUnit::NANOS(Default::default());
+
+        assert_eq!(
+            (
+                PhysicalType::INT32,
+                Some(LogicalType::Decimal {
+                    scale: 2,
+                    precision: 5
+                })
+            ),
+            map(DataType::Decimal {
+                precision: 5,
+                scale: 2
+            })
+        );
+        assert_eq!(
+            (
+                PhysicalType::INT64,
+                Some(LogicalType::Decimal {
+                    scale: 0,
+                    precision: 18
+                })
+            ),
+            map(DataType::Numeric {
+                precision: 18,
+                scale: 0
+            })
+        );
+        assert_eq!((PhysicalType::BOOLEAN, None), map(DataType::Bit));
+        assert_eq!((PhysicalType::DOUBLE, None), map(DataType::Double));
+        assert_eq!((PhysicalType::FLOAT, None), map(DataType::Real));
+        assert_eq!(
+            (PhysicalType::FLOAT, None),
+            map(DataType::Float { precision: 24 })
+        );
+        assert_eq!(
+            (PhysicalType::DOUBLE, None),
+            map(DataType::Float { precision: 53 })
+        );
+        assert_eq!(
+            (PhysicalType::INT32, integer(8, true)),
+            map(DataType::TinyInt)
+        );
+        assert_eq!(
+            (PhysicalType::INT32, integer(8, false)),
+            map_column(DataType::TinyInt, true, options)
+        );
+        assert_eq!(
+            (PhysicalType::INT32, integer(16, true)),
+            map(DataType::SmallInt)
+        );
+        assert_eq!(
+            (PhysicalType::INT32, integer(32, true)),
+            map(DataType::Integer)
+        );
+        assert_eq!(
+            (PhysicalType::INT32, integer(16, true)),
+            map_column(DataType::SmallInt, true, options)
+        );
+        assert_eq!(
+            (PhysicalType::INT32, integer(32, true)),
+            map_column(DataType::Integer, true, options)
+        );
+        assert_eq!((PhysicalType::INT64, None), map(DataType::BigInt));
+        assert_eq!(
+            (PhysicalType::INT32, Some(LogicalType::Date)),
+            map(DataType::Date)
+        );
+        for (precision, unit) in [(3, &millis), (6, &micros), (7, &nanos)] {
+            assert_eq!(
+                timestamp(unit.clone(), false),
+                map(DataType::Timestamp { precision }).1
+            );
+            // -155 is `DATETIMEOFFSET` on Microsoft SQL Server
+            let datetimeoffset = DataType::Other {
+                data_type: SqlDataType(-155),
+                column_size: None,
+                decimal_digits: precision,
+            };
+            assert_eq!(timestamp(unit.clone(), true), map(datetimeoffset).1);
+            // -154 is `TIME` on Microsoft SQL Server
+            let time = DataType::Other {
+                data_type: SqlDataType(-154),
+                column_size: None,
+                decimal_digits: precision,
+            };
+            assert_eq!(
+                Some(LogicalType::Time {
+                    is_adjusted_to_u_t_c: false,
+                    unit: unit.clone()
+                }),
+                map(time).1
+            );
+        }
+        assert_eq!(
+            (PhysicalType::BYTE_ARRAY, None),
+            map(DataType::Varbinary { length: nz(10) })
+        );
+        assert_eq!(
+            (PhysicalType::BYTE_ARRAY, None),
+            map(DataType::LongVarbinary { length: nz(10) })
+        );
+        assert_eq!(
+            (PhysicalType::FIXED_LEN_BYTE_ARRAY, None),
+            map(DataType::Binary { length: nz(10) })
+        );
+        assert!(is_text(DataType::Varchar { length: nz(10) }, options));
+        assert!(is_text(DataType::WChar { length: nz(10) }, options));
+        assert!(is_text(DataType::Unknown, options));
+        let vendor_type = DataType::Other {
+            data_type: SqlDataType(-151),
+            column_size: nz(10),
+            decimal_digits: 0,
+        };
+        assert!(is_text(vendor_type, options));
+        // Binary columns are not annotated at all
+        assert!(!is_text(DataType::Varbinary { length: nz(10) }, options));
+    }
+
+    #[test]
+    fn map_unsigned_integers_with_unsigned_column_order() {
+        let null_tokens = NullTokens::default();
+        let options = MappingOptions {
+            unsigned_column_order: true,
+            ..default_options(&null_tokens)
+        };
+
+        assert_eq!(
+            (
+                PhysicalType::INT32,
+                Some(LogicalType::Integer {
+                    bit_width: 16,
+                    is_signed: false
+                })
+            ),
+            map_column(DataType::SmallInt, true, options)
+        );
+        assert_eq!(
+            (
+                PhysicalType::INT64,
+                Some(LogicalType::Decimal {
+                    scale: 0,
+                    precision: 10
+                })
+            ),
+            map_column(DataType::Integer, true, options)
+        );
+        // Signed columns are not affected
+        assert_eq!(
+            (
+                PhysicalType::INT32,
+                Some(LogicalType::Integer {
+                    bit_width: 32,
+                    is_signed: true
+                })
+            ),
+            map_column(DataType::Integer, false, options)
+        );
+    }
+
+    #[test]
+    fn map_vendor_type_by_rule_before_falling_back_to_text() {
+        let null_tokens = NullTokens::default();
+        let type_rules = [TypeRule {
+            sql_type: -151,
+            target: TypeRuleTarget::Binary,
+        }];
+        let options = MappingOptions {
+            type_rules: &type_rules,
+            ..default_options(&null_tokens)
+        };
+        let vendor_type = |sql_type| DataType::Other {
+            data_type: SqlDataType(sql_type),
+            column_size: NonZeroUsize::new(10),
+            decimal_digits: 0,
+        };
+
+        assert_eq!(
+            (PhysicalType::BYTE_ARRAY, None),
+            map_column(vendor_type(-151), false, options)
+        );
+        assert!(!is_text(vendor_type(-151), options));
+        // Rules only apply to the type code they name
+        assert!(is_text(vendor_type(-152), options));
+        // Rules do not apply to standard SQL types
+        assert_eq!(
+            (PhysicalType::INT64, None),
+            map_column(DataType::BigInt, false, options)
+        );
+        // `--fetch-as-text-all` takes precedence
+        let options = MappingOptions {
+            fetch_as_text_all: true,
+            ..options
+        };
+        assert!(is_text(vendor_type(-151), options));
+    }
+
+    fn physical_type(target: TypeRuleTarget) -> (PhysicalType, ConvertedType) {
+        let strategy =
+            strategy_from_type_rule(target, Repetition::OPTIONAL, false, || Ok(16)).unwrap();
+        let parquet_type = strategy.parquet_type("a");
+        (
+            parquet_type.get_physical_type(),
+            parquet_type.get_basic_info().converted_type(),
+        )
+    }
+
+    #[test]
+    fn map_vendor_type_by_rule() {
+        assert_eq!(
+            (PhysicalType::BYTE_ARRAY, ConvertedType::UTF8),
+            physical_type(TypeRuleTarget::Text)
+        );
+        assert_eq!(
+            (PhysicalType::BYTE_ARRAY, ConvertedType::NONE),
+            physical_type(TypeRuleTarget::Binary)
+        );
+        assert_eq!(
+            (PhysicalType::INT32, ConvertedType::INT_32),
+            physical_type(TypeRuleTarget::Int32)
+        );
+        assert_eq!(PhysicalType::INT64, physical_type(TypeRuleTarget::Int64).0);
+        assert_eq!(PhysicalType::FLOAT, physical_type(TypeRuleTarget::Float).0);
+        assert_eq!(
+            PhysicalType::DOUBLE,
+            physical_type(TypeRuleTarget::Double).0
+        );
+    }
+
+    #[test]
+    fn type_rule_length_only_required_for_text_and_binary() {
+        let fail = || Err(anyhow::anyhow!("Length unknown"));
+        assert!(
+            strategy_from_type_rule(TypeRuleTarget::Int64, Repetition::REQUIRED, false, fail)
+                .is_ok()
+        );
+        assert!(
+            strategy_from_type_rule(TypeRuleTarget::Binary, Repetition::REQUIRED, false, fail)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn size_text_buffers_for_characters_of_any_length() {
+        let nz = std::num::NonZeroUsize::new;
+        let unknown = || Ok(None);
+        // '😀世界' has three characters, but takes 10 bytes in UTF-8 and 4 code units in UTF-16.
+        let chars = "😀世界";
+        assert_eq!(10, chars.len());
+        assert_eq!(4, chars.encode_utf16().count());
+        for data_type in [
+            DataType::Varchar { length: nz(3) },
+            DataType::WVarchar { length: nz(3) },
+            DataType::LongVarchar { length: nz(3) },
+        ] {
+            assert_eq!(
+                nz(12),
+                text_buffer_length(&data_type, false, unknown).unwrap()
+            );
+            assert_eq!(
+                nz(6),
+                text_buffer_length(&data_type, true, unknown).unwrap()
+            );
+        }
+        // Display sizes are in characters, too
+        let display_size = || Ok(nz(3));
+        assert_eq!(
+            nz(12),
+            text_buffer_length(&DataType::Unknown, false, display_size).unwrap()
+        );
+        assert_eq!(
+            nz(6),
+            text_buffer_length(&DataType::Unknown, true, display_size).unwrap()
+        );
+        // Other types are rendered as ASCII
+        assert_eq!(
+            nz(11),
+            text_buffer_length(&DataType::Integer, false, unknown).unwrap()
+        );
+    }
+
+    #[test]
+    fn bind_only_as_c_types_odbc_converts_into() {
+        let nz = std::num::NonZeroUsize::new;
+        let varchar = DataType::Varchar { length: nz(10) };
+        let date = DataType::Date;
+        assert!(is_conversion_defined(&DataType::Integer, BindCType::Char));
+        assert!(is_conversion_defined(&DataType::BigInt, BindCType::Double));
+        assert!(is_conversion_defined(&varchar, BindCType::SLong));
+        assert!(is_conversion_defined(&varchar, BindCType::TypeTimestamp));
+        assert!(is_conversion_defined(&date, BindCType::TypeTimestamp));
+        assert!(!is_conversion_defined(&date, BindCType::SBigInt));
+        assert!(!is_conversion_defined(
+            &DataType::Integer,
+            BindCType::TypeDate
+        ));
+        assert!(!is_conversion_defined(
+            &DataType::Varbinary { length: nz(10) },
+            BindCType::Bit
+        ));
+    }
+
+    #[test]
+    fn bind_decimals_as_numeric_struct_if_the_driver_accepts_it() {
+        let null_tokens = NullTokens::default();
+        let options = MappingOptions {
+            numeric_decimals: true,
+            ..default_options(&null_tokens)
+        };
+        let cd = ColumnDescription::new(
+            "a",
+            DataType::Decimal {
+                precision: 30,
+                scale: 2,
+            },
+            Nullability::Nullable,
+        );
+        let buffer_desc = |accepts_numeric| {
+            let mut metadata = Metadata {
+                is_unsigned: false,
+                accepts_numeric,
+            };
+            let strategy =
+                strategy_from_column_description(&cd, "a", options, &mut metadata, 1).unwrap();
+            (strategy.buffer_desc(), strategy.numeric_binding())
+        };
+
+        assert_eq!(
+            (BufferDesc::Binary { length: 19 }, Some((30, 2))),
+            buffer_desc(true)
+        );
+        // Falls back to text
+        assert_eq!(
+            (BufferDesc::Text { max_str_len: 32 }, None),
+            buffer_desc(false)
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+
+use log::{info, warn};
+use odbc_api::{buffers::BufferDesc, DataType};
+use parquet::{
+    basic::{ConvertedType, Type as PhysicalType},
+    schema::types::Type,
+};
+
+use super::column_strategy::ColumnStrategy;
+
+/// Reason the type a column is written with does not exactly match its type in the data source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ImplicitConversion {
+    DecimalAsFloatingPoint,
+    DecimalAsText,
+    IntegerAsDecimal,
+    IntegerAsBoolean,
+    BooleanAsInteger,
+    DateAsTimestamp,
+    TimestampAsInt96,
+    FetchedAsText,
+    TranscodedFromUtf16,
+}
+
+impl ImplicitConversion {
+    /// The conversion of a column of `data_type`, written as `parquet_type` and fetched into
+    /// buffers described by `buffer_desc`. `None` if the column is written as it is.
+    fn detect(data_type: DataType, parquet_type: &Type, buffer_desc: BufferDesc) -> Option<Self> {
+        let physical_type = parquet_type.get_physical_type();
+        let converted_type = parquet_type.get_basic_info().converted_type();
+        let is_text = converted_type == ConvertedType::UTF8;
+        let conversion = match data_type {
+            DataType::Numeric { .. } | DataType::Decimal { .. } => match physical_type {
+                PhysicalType::FLOAT | PhysicalType::DOUBLE => Self::DecimalAsFloatingPoint,
+                _ if is_text => Self::DecimalAsText,
+                _ => return None,
+            },
+            DataType::TinyInt | DataType::SmallInt | DataType::Integer | DataType::BigInt => {
+                match physical_type {
+                    PhysicalType::BOOLEAN => Self::IntegerAsBoolean,
+                    _ if converted_type == ConvertedType::DECIMAL => Self::IntegerAsDecimal,
+                    _ if is_text => Self::FetchedAsText,
+                    _ => return None,
+                }
+            }
+            DataType::Bit if physical_type != PhysicalType::BOOLEAN => Self::BooleanAsInteger,
+            DataType::Date if physical_type != PhysicalType::INT32 => Self::DateAsTimestamp,
+            DataType::Timestamp { .. } if physical_type == PhysicalType::INT96 => {
+                Self::TimestampAsInt96
+            }
+            DataType::Char { .. }
+            | DataType::WChar { .. }
+            | DataType::Varchar { .. }
+            | DataType::WVarchar { .. }
+            | DataType::LongVarchar { .. }
+                if matches!(buffer_desc, BufferDesc::WText { .. }) =>
+            {
+                Self::TranscodedFromUtf16
+            }
+            DataType::Char { .. }
+            | DataType::WChar { .. }
+            | DataType::Varchar { .. }
+            | DataType::WVarchar { .. }
+            | DataType::LongVarchar { .. } => return None,
+            _ if is_text => Self::FetchedAsText,
+            _ => return None,
+        };
+        Some(conversion)
+    }
+
+    fn reason(self) -> &'static str {
+        match self {
+            Self::DecimalAsFloatingPoint => {
+                "decimal written as floating point, digits beyond about 15 significant ones are lost"
+            }
+            Self::DecimalAsText => "decimal written as text",
+            Self::IntegerAsDecimal => "unsigned integer written as decimal",
+            Self::IntegerAsBoolean => "integer written as boolean, any value but zero is true",
+            Self::BooleanAsInteger => "boolean written as integer",
+            Self::DateAsTimestamp => "date written as timestamp at midnight",
+            Self::TimestampAsInt96 => "timestamp written as deprecated INT96",
+            Self::FetchedAsText => "fetched and written as text",
+            Self::TranscodedFromUtf16 => "text fetched as UTF-16 and transcoded into UTF-8",
+        }
+    }
+}
+
+/// Reports columns which are not written with a type exactly matching their type in the data
+/// source. Only reports, never fails.
+#[derive(Default)]
+pub struct ConversionAudit {
+    /// Number of columns for each kind of conversion.
+    counts: BTreeMap<ImplicitConversion, usize>,
+    num_columns: usize,
+}
+
+impl ConversionAudit {
+    /// Logs a warning, if column `name` of `data_type` is converted by `strategy`.
+    pub fn check(&mut self, name: &str, data_type: DataType, strategy: &dyn ColumnStrategy) {
+        self.num_columns += 1;
+        let parquet_type = strategy.parquet_type(name);
+        let buffer_desc = strategy.buffer_desc();
+        let Some(conversion) = ImplicitConversion::detect(data_type, &parquet_type, buffer_desc)
+        else {
+            return;
+        };
+        *self.counts.entry(conversion).or_default() += 1;
+        let converted_type = parquet_type.get_basic_info().converted_type();
+        let parquet_type = if converted_type == ConvertedType::NONE {
+            parquet_type.get_physical_type().to_string()
+        } else {
+            format!("{} ({converted_type})", parquet_type.get_physical_type())
+        };
+        warn!(
+            "Implicit conversion: column=\"{name}\" source_type={data_type:?} \
+            parquet_type=\"{parquet_type}\" buffer={buffer_desc:?} reason=\"{}\"",
+            conversion.reason()
+        );
+    }
+
+    /// Logs the number of columns for each kind of conversion. As a warning, so it is visible
+    /// along with the warnings for the individual columns.
+    pub fn log_summary(&self) {
+        let num_converted: usize = self.counts.values().sum();
+        if num_converted == 0 {
+            info!(
+                "None of {} columns is converted implicitly.",
+                self.num_columns
+            );
+            return;
+        }
+        let counts: Vec<String> = self
+            .counts
+            .iter()
+            .map(|(conversion, count)| format!("{count} {}", conversion.reason()))
+            .collect();
+        warn!(
+            "{num_converted} of {} columns are converted implicitly: {}.",
+            self.num_columns,
+            counts.join("; ")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use odbc_api::{buffers::BufferDesc, DataType};
+    use parquet::schema::parser::parse_message_type;
+
+    use super::ImplicitConversion;
+
+    #[test]
+    fn detect_implicit_conversions() {
+        let schema = parse_message_type(
+            "message schema {
+                OPTIONAL DOUBLE a;
+                OPTIONAL INT64 b (DECIMAL(18,0));
+                OPTIONAL BYTE_ARRAY c (UTF8);
+                OPTIONAL INT64 d;
+            }",
+        )
+        .unwrap();
+        let field = |index: usize| schema.get_fields()[index].clone();
+        let text = BufferDesc::Text { max_str_len: 10 };
+        let i64 = BufferDesc::I64 { nullable: true };
+
+        let detect = ImplicitConversion::detect;
+        let decimal = DataType::Decimal {
+            precision: 20,
+            scale: 2,
+        };
+        assert_eq!(
+            Some(ImplicitConversion::DecimalAsFloatingPoint),
+            detect(decimal, &field(0), BufferDesc::F64 { nullable: true })
+        );
+        assert_eq!(
+            Some(ImplicitConversion::IntegerAsDecimal),
+            detect(DataType::BigInt, &field(1), text)
+        );
+        assert_eq!(
+            Some(ImplicitConversion::TranscodedFromUtf16),
+            detect(
+                DataType::WVarchar { length: None },
+                &field(2),
+                BufferDesc::WText { max_str_len: 10 }
+            )
+        );
+        assert_eq!(
+            None,
+            detect(DataType::Varchar { length: None }, &field(2), text)
+        );
+        assert_eq!(
+            Some(ImplicitConversion::FetchedAsText),
+            detect(DataType::Unknown, &field(2), text)
+        );
+        assert_eq!(None, detect(DataType::BigInt, &field(3), i64));
+    }
+}
@@ -1,20 +1,32 @@
-use std::{fs::File, io::Write, path::PathBuf, sync::Arc};
+use std::{
+    fs::{File, OpenOptions},
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use anyhow::Error;
 use bytesize::ByteSize;
 use log::info;
 use parquet::{
-    file::{properties::WriterProperties, writer::SerializedFileWriter},
+    file::{
+        metadata::RowGroupMetaDataPtr, properties::WriterProperties, writer::SerializedFileWriter,
+    },
     schema::types::Type,
 };
-use tempfile::TempPath;
+use tempfile::{NamedTempFile, TempPath};
+
+use crate::is_fifo;
 
-use super::table_strategy::ColumnExporter;
+use super::{hadoop_crc::ChecksumWriter, table_strategy::ColumnExporter};
 
 pub struct CurrentFile {
-    writer: SerializedFileWriter<Box<dyn Write + Send>>,
+    writer: SerializedFileWriter<ChecksumWriter<File>>,
     /// Path to the file currently being written to.
     path: TempPath,
+    /// Set if the output is a named pipe. Since we do not want a consumer to see partial output,
+    /// `path` then points to a temporary file, which is streamed into the pipe once finalized.
+    fifo: Option<PathBuf>,
     /// Keep track of current file size so we can split it, should it get too large.
     file_size: ByteSize,
     /// Keep track of the total number of rows written into the file so far.
@@ -26,19 +38,32 @@ impl CurrentFile {
         path: PathBuf,
         schema: Arc<Type>,
         properties: Arc<WriterProperties>,
+        emit_hadoop_crc: bool,
     ) -> Result<CurrentFile, Error> {
-        let output: Box<dyn Write + Send> = Box::new(File::create(&path).map_err(|io_err| {
-            Error::from(io_err).context(format!(
-                "Could not create output file '{}'",
-                path.to_string_lossy()
-            ))
-        })?);
-        let path = TempPath::from_path(path);
+        let (file, path, fifo) = if is_fifo(&path) {
+            let (file, temp_path) = NamedTempFile::new()
+                .map_err(|io_err| {
+                    Error::from(io_err)
+                        .context("Could not create temporary file to buffer output for named pipe")
+                })?
+                .into_parts();
+            (file, temp_path, Some(path))
+        } else {
+            let file = File::create(&path).map_err(|io_err| {
+                Error::from(io_err).context(format!(
+                    "Could not create output file '{}'",
+                    path.to_string_lossy()
+                ))
+            })?;
+            (file, TempPath::from_path(path), None)
+        };
+        let output = ChecksumWriter::new(file, emit_hadoop_crc);
         let writer = SerializedFileWriter::new(output, schema.clone(), properties.clone())?;
 
         Ok(Self {
             writer,
             path,
+            fifo,
             file_size: ByteSize::b(0),
             total_num_rows: 0,
         })
@@ -46,36 +71,51 @@ impl CurrentFile {
 
     pub fn write_row_group(
         &mut self,
-        mut column_exporter: ColumnExporter,
-    ) -> Result<ByteSize, Error> {
-        let mut col_index = 0;
-        let mut row_group_writer = self.writer.next_row_group()?;
-        while let Some(mut column_writer) = row_group_writer.next_column()? {
-            column_exporter.export_nth_column(col_index, &mut column_writer)?;
-            column_writer.close()?;
-            col_index += 1;
-        }
-        let metadata = row_group_writer.close()?;
+        column_exporter: &mut ColumnExporter,
+    ) -> Result<RowGroupMetaDataPtr, Error> {
+        let metadata = column_exporter.write_row_group(&mut self.writer)?;
         // Of course writing a row group increases file size. We keep track of it here, so we can
         // split on file size if we go over a threshold.
         self.file_size += ByteSize::b(metadata.compressed_size().try_into().unwrap());
         let rows_in_row_group: u64 = metadata.num_rows().try_into().unwrap();
         self.total_num_rows += rows_in_row_group;
-        Ok(self.file_size)
+        Ok(metadata)
+    }
+
+    /// Path of the output, i.e. of the named pipe rather than the temporary file buffering it.
+    pub fn path(&self) -> &Path {
+        self.fifo.as_deref().unwrap_or(&self.path)
+    }
+
+    /// Size of the file, so far. Only accounts for row groups, which have already been written.
+    pub fn file_size(&self) -> ByteSize {
+        self.file_size
     }
 
     /// Writes metadata at the end and persists the file. Called if we do not want to continue
-    /// writing batches into this file.
-    pub fn finalize(self) -> Result<(), Error> {
-        self.writer.close()?;
-        // Do not persist empty files
-        let path = self.path.keep()?;
+    /// writing batches into this file. Returns the size of the file in bytes.
+    pub fn finalize(mut self) -> Result<u64, Error> {
+        self.writer.finish()?;
+        let num_bytes = self.writer.bytes_written().try_into().unwrap();
+        let path = if let Some(fifo) = self.fifo {
+            let mut buffered = File::open(&self.path)?;
+            let mut pipe = OpenOptions::new().write(true).open(&fifo)?;
+            io::copy(&mut buffered, &mut pipe)?;
+            // Dropping `self.path` deletes the temporary file.
+            fifo
+        } else {
+            let path = self.path.keep()?;
+            if let Some(checksums) = self.writer.inner_mut().checksums() {
+                checksums.write_sidecar(&path)?;
+            }
+            path
+        };
         info!(
             "{} rows have been written to {} with a file size of {}.",
             self.total_num_rows,
             path.to_string_lossy(),
             self.file_size
         );
-        Ok(())
+        Ok(num_bytes)
     }
 }
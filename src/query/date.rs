@@ -1,7 +1,7 @@
-use anyhow::Error;
+use anyhow::{bail, Error};
 use chrono::NaiveDate;
 use odbc_api::{
-    buffers::{AnySlice, BufferDesc, Item},
+    buffers::{AnySlice, BufferDesc},
     sys::Date as OdbcDate,
 };
 use parquet::{
@@ -11,17 +11,21 @@ use parquet::{
     schema::types::Type,
 };
 
-use crate::parquet_buffer::ParquetBuffer;
+use crate::{enum_args::DateOutOfRange, parquet_buffer::ParquetBuffer};
 
-use super::column_strategy::ColumnStrategy;
+use super::column_strategy::{nullable_values, ColumnStrategy};
 
 pub struct Date {
     repetition: Repetition,
+    out_of_range: DateOutOfRange,
 }
 
 impl Date {
-    pub fn new(repetition: Repetition) -> Self {
-        Self { repetition }
+    pub fn new(repetition: Repetition, out_of_range: DateOutOfRange) -> Self {
+        Self {
+            repetition,
+            out_of_range,
+        }
     }
 }
 
@@ -44,18 +48,156 @@ impl ColumnStrategy for Date {
         column_writer: &mut ColumnWriter,
         column_view: AnySlice,
     ) -> Result<(), Error> {
-        let it = OdbcDate::as_nullable_slice(column_view).unwrap();
+        let it = nullable_values::<OdbcDate>(column_view, "dates")?;
         let column_writer = get_typed_column_writer_mut::<Int32Type>(column_writer);
-        parquet_buffer.write_optional(column_writer, it.map(|date| date.map(days_since_epoch)))?;
+        let is_nullable = self.repetition == Repetition::OPTIONAL;
+        parquet_buffer.write_optional_fallible(
+            column_writer,
+            it.map(|date| {
+                let Some(date) = date else { return Ok(None) };
+                let days = days_since_epoch(date, self.out_of_range)?;
+                if days.is_none() && !is_nullable {
+                    bail!(
+                        "Invalid date {} can not be replaced with NULL, because the column is not \
+                        nullable.",
+                        fmt_date(date)
+                    )
+                }
+                Ok(days)
+            }),
+        )?;
         Ok(())
     }
 }
 
-/// Transform date to days since unix epoch as i32
-fn days_since_epoch(date: &OdbcDate) -> i32 {
+/// Transform date to days since unix epoch as i32. Dates outside of `0001-01-01` to `9999-12-31`
+/// and invalid dates (e.g. `0000-00-00`) are handled according to `out_of_range`. `None` if the
+/// date should be written as NULL.
+fn days_since_epoch(date: &OdbcDate, out_of_range: DateOutOfRange) -> Result<Option<i32>, Error> {
+    let date = match valid_date(date) {
+        Some(date) => date,
+        None => match out_of_range {
+            DateOutOfRange::Null => return Ok(None),
+            DateOutOfRange::Clamp => clamp(date),
+            DateOutOfRange::Error => bail!(
+                "Invalid date {}. Valid dates range from 0001-01-01 to 9999-12-31. Use \
+                `--coerce-date-out-of-range` to write NULL or the closest valid date instead.",
+                fmt_date(date)
+            ),
+        },
+    };
     let unix_epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
-    let date =
-        NaiveDate::from_ymd_opt(date.year as i32, date.month as u32, date.day as u32).unwrap();
     let duration = date.signed_duration_since(unix_epoch);
-    duration.num_days().try_into().unwrap()
+    Ok(Some(duration.num_days().try_into().unwrap()))
+}
+
+/// `None` if the components do not form a valid date between `0001-01-01` and `9999-12-31`.
+fn valid_date(date: &OdbcDate) -> Option<NaiveDate> {
+    if !(1..=9999).contains(&date.year) {
+        return None;
+    }
+    NaiveDate::from_ymd_opt(date.year as i32, date.month as u32, date.day as u32)
+}
+
+/// Closest valid date to the components of `date`.
+fn clamp(date: &OdbcDate) -> NaiveDate {
+    if date.year < 1 {
+        return NaiveDate::from_ymd_opt(1, 1, 1).unwrap();
+    }
+    if date.year > 9999 {
+        return NaiveDate::from_ymd_opt(9999, 12, 31).unwrap();
+    }
+    let year = date.year as i32;
+    let month = (date.month as u32).clamp(1, 12);
+    // Last day of the month is the first day, which is valid, counting down from 31.
+    let last_day = (28..=31)
+        .rev()
+        .find(|&day| NaiveDate::from_ymd_opt(year, month, day).is_some())
+        .unwrap();
+    let day = (date.day as u32).clamp(1, last_day);
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+fn fmt_date(date: &OdbcDate) -> String {
+    format!("{:04}-{:02}-{:02}", date.year, date.month, date.day)
+}
+
+#[cfg(test)]
+mod tests {
+    use odbc_api::sys::Date as OdbcDate;
+
+    use crate::enum_args::DateOutOfRange;
+
+    use super::days_since_epoch;
+
+    const ZERO_DATE: OdbcDate = OdbcDate {
+        year: 0,
+        month: 0,
+        day: 0,
+    };
+
+    const LAST_DATE: OdbcDate = OdbcDate {
+        year: 9999,
+        month: 12,
+        day: 31,
+    };
+
+    #[test]
+    fn zero_date() {
+        assert_eq!(
+            None,
+            days_since_epoch(&ZERO_DATE, DateOutOfRange::Null).unwrap()
+        );
+        // 0001-01-01
+        assert_eq!(
+            Some(-719_162),
+            days_since_epoch(&ZERO_DATE, DateOutOfRange::Clamp).unwrap()
+        );
+        assert!(days_since_epoch(&ZERO_DATE, DateOutOfRange::Error).is_err());
+    }
+
+    #[test]
+    fn last_representable_date() {
+        for out_of_range in [
+            DateOutOfRange::Null,
+            DateOutOfRange::Clamp,
+            DateOutOfRange::Error,
+        ] {
+            assert_eq!(
+                Some(2_932_896),
+                days_since_epoch(&LAST_DATE, out_of_range).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn clamp_invalid_day_of_month() {
+        let feb_30 = OdbcDate {
+            year: 2023,
+            month: 2,
+            day: 30,
+        };
+        let feb_28 = OdbcDate {
+            year: 2023,
+            month: 2,
+            day: 28,
+        };
+        assert_eq!(
+            days_since_epoch(&feb_28, DateOutOfRange::Error).unwrap(),
+            days_since_epoch(&feb_30, DateOutOfRange::Clamp).unwrap()
+        );
+    }
+
+    #[test]
+    fn year_10000() {
+        let date = OdbcDate {
+            year: 10000,
+            month: 1,
+            day: 1,
+        };
+        assert_eq!(
+            days_since_epoch(&LAST_DATE, DateOutOfRange::Error).unwrap(),
+            days_since_epoch(&date, DateOutOfRange::Clamp).unwrap()
+        );
+    }
 }
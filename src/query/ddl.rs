@@ -0,0 +1,222 @@
+use std::{fmt::Write as _, fs, path::PathBuf};
+
+use anyhow::{bail, Context, Error};
+use parquet::{
+    basic::{ConvertedType, LogicalType, Type as PhysicalType},
+    schema::types::Type,
+};
+
+use crate::enum_args::DdlDialect;
+
+/// Placeholder for the location of the table, which is not known to `odbc2parquet`.
+const LOCATION_PLACEHOLDER: &str = "<location>";
+
+/// A `CREATE TABLE` statement matching the schema of the output, written into a file of its own.
+#[derive(Clone)]
+pub struct Ddl {
+    pub path: PathBuf,
+    pub table_name: String,
+    pub dialect: DdlDialect,
+}
+
+impl Ddl {
+    /// Writes the statement. `comments` holds tuples of column name and comment.
+    pub fn write(&self, schema: &Type, comments: &[(String, String)]) -> Result<(), Error> {
+        let statement = create_table_statement(schema, &self.table_name, self.dialect, comments)?;
+        fs::write(&self.path, statement)
+            .with_context(|| format!("Could not write DDL to '{}'.", self.path.display()))
+    }
+}
+
+fn create_table_statement(
+    schema: &Type,
+    table_name: &str,
+    dialect: DdlDialect,
+    comments: &[(String, String)],
+) -> Result<String, Error> {
+    let mut statement = match dialect {
+        DdlDialect::Hive | DdlDialect::Athena => {
+            format!("CREATE EXTERNAL TABLE {} (\n", quote(table_name))
+        }
+        DdlDialect::Spark => format!("CREATE TABLE {} (\n", quote(table_name)),
+    };
+    let fields = schema.get_fields();
+    for (index, field) in fields.iter().enumerate() {
+        let separator = if index + 1 < fields.len() { "," } else { "" };
+        let comment = comments
+            .iter()
+            .find(|(column, _)| column == field.name())
+            .map(|(_, comment)| format!(" COMMENT {}", string_literal(comment)))
+            .unwrap_or_default();
+        writeln!(
+            statement,
+            "  {} {}{comment}{separator}",
+            quote(field.name()),
+            column_type(field, dialect)?
+        )
+        .unwrap();
+    }
+    match dialect {
+        DdlDialect::Hive | DdlDialect::Athena => writeln!(
+            statement,
+            ")\nSTORED AS PARQUET\nLOCATION '{LOCATION_PLACEHOLDER}';"
+        )
+        .unwrap(),
+        DdlDialect::Spark => writeln!(
+            statement,
+            ")\nUSING PARQUET\nLOCATION '{LOCATION_PLACEHOLDER}';"
+        )
+        .unwrap(),
+    }
+    Ok(statement)
+}
+
+/// Quote an identifier with backticks, which all supported dialects understand.
+fn quote(identifier: &str) -> String {
+    format!("`{}`", identifier.replace('`', "``"))
+}
+
+/// String literal in single quotes, escaped with backslashes like Hive and Spark expect it.
+fn string_literal(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Type of the column in the catalog, derived from the physical and logical type of the parquet
+/// column.
+fn column_type(field: &Type, dialect: DdlDialect) -> Result<String, Error> {
+    let info = field.get_basic_info();
+    let name = field.name();
+    if !field.is_primitive() {
+        bail!("Column '{name}' is not a primitive type.")
+    }
+    if info.converted_type() == ConvertedType::DECIMAL {
+        let (precision, scale) = (field.get_precision(), field.get_scale());
+        if precision > 38 {
+            bail!(
+                "Column '{name}' is a decimal with precision {precision}, but the catalog supports \
+                at most 38 digits."
+            )
+        }
+        return Ok(format!("DECIMAL({precision},{scale})"));
+    }
+    let column_type = match (field.get_physical_type(), info.logical_type()) {
+        (PhysicalType::BOOLEAN, _) => "BOOLEAN",
+        (PhysicalType::INT32, Some(LogicalType::Date)) => "DATE",
+        (
+            PhysicalType::INT32 | PhysicalType::INT64,
+            Some(LogicalType::Integer {
+                bit_width,
+                is_signed,
+            }),
+        ) => match (bit_width, is_signed) {
+            (8, true) => "TINYINT",
+            (16, true) | (8, false) => "SMALLINT",
+            (32, true) | (16, false) => "INT",
+            (64, true) | (32, false) => "BIGINT",
+            _ => "DECIMAL(20,0)",
+        },
+        (
+            PhysicalType::INT64,
+            Some(LogicalType::Timestamp {
+                is_adjusted_to_u_t_c: false,
+                ..
+            }),
+        ) if dialect == DdlDialect::Spark => "TIMESTAMP_NTZ",
+        (PhysicalType::INT64, Some(LogicalType::Timestamp { .. })) | (PhysicalType::INT96, _) => {
+            "TIMESTAMP"
+        }
+        // The catalogs do not know a time type. Times are exposed as integers of their unit.
+        (PhysicalType::INT32, _) => "INT",
+        (PhysicalType::INT64, _) => "BIGINT",
+        (PhysicalType::FLOAT, _) => "FLOAT",
+        (PhysicalType::DOUBLE, _) => "DOUBLE",
+        (
+            PhysicalType::BYTE_ARRAY | PhysicalType::FIXED_LEN_BYTE_ARRAY,
+            Some(LogicalType::String | LogicalType::Json | LogicalType::Enum),
+        ) => "STRING",
+        (PhysicalType::BYTE_ARRAY | PhysicalType::FIXED_LEN_BYTE_ARRAY, _)
+            if info.converted_type() == ConvertedType::UTF8 =>
+        {
+            "STRING"
+        }
+        (PhysicalType::BYTE_ARRAY | PhysicalType::FIXED_LEN_BYTE_ARRAY, _) => "BINARY",
+    };
+    Ok(column_type.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use parquet::schema::parser::parse_message_type;
+
+    use crate::enum_args::DdlDialect;
+
+    use super::create_table_statement;
+
+    #[test]
+    fn map_parquet_types_to_catalog_types() {
+        let schema = parse_message_type(
+            "message schema {
+                REQUIRED INT32 id (INTEGER(32,true));
+                OPTIONAL BYTE_ARRAY name (UTF8);
+                OPTIONAL INT64 amount (DECIMAL(12,2));
+                OPTIONAL FIXED_LEN_BYTE_ARRAY (16) big (DECIMAL(38,10));
+                OPTIONAL INT32 day (DATE);
+                OPTIONAL INT64 created (TIMESTAMP(MICROS,false));
+                OPTIONAL INT64 changed (TIMESTAMP(MILLIS,true));
+                OPTIONAL BYTE_ARRAY raw;
+                OPTIONAL DOUBLE ratio;
+                OPTIONAL INT32 flags (INTEGER(16,false));
+            }",
+        )
+        .unwrap();
+
+        let expected = "CREATE EXTERNAL TABLE `sales` (
+  `id` INT,
+  `name` STRING,
+  `amount` DECIMAL(12,2),
+  `big` DECIMAL(38,10),
+  `day` DATE,
+  `created` TIMESTAMP,
+  `changed` TIMESTAMP,
+  `raw` BINARY,
+  `ratio` DOUBLE,
+  `flags` INT
+)
+STORED AS PARQUET
+LOCATION '<location>';
+";
+        assert_eq!(
+            expected,
+            create_table_statement(&schema, "sales", DdlDialect::Hive, &[]).unwrap()
+        );
+
+        let spark = create_table_statement(&schema, "sales", DdlDialect::Spark, &[]).unwrap();
+        assert!(spark.starts_with("CREATE TABLE `sales` (\n"));
+        assert!(spark.contains("  `created` TIMESTAMP_NTZ,\n  `changed` TIMESTAMP,\n"));
+        assert!(spark.ends_with(")\nUSING PARQUET\nLOCATION '<location>';\n"));
+    }
+
+    #[test]
+    fn write_column_comments() {
+        let schema =
+            parse_message_type("message schema { REQUIRED INT32 id; OPTIONAL DOUBLE price; }")
+                .unwrap();
+        let comments = [("price".to_owned(), "Net price in 'EUR'".to_owned())];
+
+        let statement =
+            create_table_statement(&schema, "sales", DdlDialect::Hive, &comments).unwrap();
+
+        assert!(
+            statement.contains("  `id` INT,\n  `price` DOUBLE COMMENT 'Net price in \\'EUR\\''\n)")
+        );
+    }
+
+    #[test]
+    fn decimal_precision_above_38_is_not_supported() {
+        let schema = parse_message_type(
+            "message schema { OPTIONAL FIXED_LEN_BYTE_ARRAY (20) a (DECIMAL(40,0)); }",
+        )
+        .unwrap();
+        assert!(create_table_statement(&schema, "t", DdlDialect::Athena, &[]).is_err());
+    }
+}
@@ -0,0 +1,76 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Error;
+use odbc_api::handles::State;
+
+/// State reported by Microsoft SQL Server, if a transaction has been chosen as deadlock victim.
+const SERIALIZATION_FAILURE: State = State(*b"40001");
+/// Native error code of Microsoft SQL Server for deadlock victims.
+const DEADLOCK_VICTIM: i32 = 1205;
+
+/// Base delay before the first retry. Doubled for each subsequent attempt.
+const BASE_DELAY: Duration = Duration::from_millis(200);
+
+/// `true` if the error is caused by the query being chosen as the victim of a deadlock by Microsoft
+/// SQL Server. Retrying such a query from scratch is likely to succeed.
+pub fn is_deadlock_victim(error: &Error) -> bool {
+    error.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<odbc_api::Error>(),
+            Some(odbc_api::Error::Diagnostics { record, .. })
+                if record.state == SERIALIZATION_FAILURE && record.native_error == DEADLOCK_VICTIM
+        )
+    })
+}
+
+/// Time to wait before retrying after the `attempt`-th failure (starting at one). Grows
+/// exponentially and is randomized by up to the same amount again, so concurrent exports which
+/// deadlocked with each other do not retry in lockstep.
+pub fn retry_delay(attempt: u32) -> Duration {
+    let delay = BASE_DELAY * 2u32.pow(attempt.saturating_sub(1).min(6));
+    // The sub second part of the clock is random enough for jitter. Avoids a dependency on a random
+    // number generator.
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    delay + delay.mul_f64(nanos as f64 / 1e9)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use anyhow::anyhow;
+    use odbc_api::handles::{Record, State};
+
+    use super::{is_deadlock_victim, retry_delay};
+
+    fn diagnostics(state: &[u8; 5], native_error: i32) -> odbc_api::Error {
+        odbc_api::Error::Diagnostics {
+            record: Record {
+                state: State(*state),
+                native_error,
+                message: Vec::new(),
+            },
+            function: "SQLFetch",
+        }
+    }
+
+    #[test]
+    fn recognize_deadlock_victim() {
+        let deadlock = anyhow::Error::from(diagnostics(b"40001", 1205)).context("Fetching batch");
+        assert!(is_deadlock_victim(&deadlock));
+        assert!(!is_deadlock_victim(&diagnostics(b"40001", 0).into()));
+        assert!(!is_deadlock_victim(&diagnostics(b"HY000", 1205).into()));
+        assert!(!is_deadlock_victim(&anyhow!("Other error")));
+    }
+
+    #[test]
+    fn retry_delay_grows_with_attempts() {
+        let first = retry_delay(1);
+        assert!(first >= Duration::from_millis(200) && first < Duration::from_millis(400));
+        let third = retry_delay(3);
+        assert!(third >= Duration::from_millis(800) && third < Duration::from_millis(1600));
+    }
+}
@@ -1,10 +1,10 @@
-use std::{convert::TryInto, marker::PhantomData};
+use std::{cmp::Ordering, convert::TryInto, marker::PhantomData};
 
-use anyhow::Error;
-use atoi::FromRadix10Signed;
+use anyhow::{anyhow, bail, Error};
+use log::info;
 use odbc_api::{
     buffers::{AnySlice, BufferDesc},
-    decimal_text_to_i128, DataType,
+    DataType,
 };
 use parquet::{
     basic::{LogicalType, Repetition, Type as PhysicalType},
@@ -13,20 +13,56 @@ use parquet::{
     schema::types::Type,
 };
 
-use crate::parquet_buffer::{BufferedDataType, ParquetBuffer};
+use crate::{
+    enum_args::{DecimalPrecisionCheck, DecimalRounding},
+    parquet_buffer::{BufferedDataType, ParquetBuffer},
+};
 
 use super::{
-    column_strategy::ColumnStrategy, identical::fetch_identical_with_logical_type, text::Utf8,
+    column_strategy::{unexpected_view, ColumnStrategy},
+    identical::fetch_identical_with_logical_type,
+    text::Utf8,
 };
 
 /// Choose how to fetch decimals from ODBC and store them in parquet
+///
+/// * `decimal_byte_length`: If specified, decimals are always stored as fixed length byte arrays
+///   of this length, instead of choosing the smallest physical type fitting the precision.
+/// * `rounding`: Applied to values fetched as text with more fractional digits than `scale`.
+/// * `check`: Whether and how to verify values fetched as text against `precision`.
+///
+/// A negative scale (e.g. Oracle `NUMBER(5,-2)`) implies the values are integers rounded to a
+/// power of ten. Parquet does not allow negative scales, so these are stored as decimals with scale
+/// zero, whose precision is increased by the number of implicit trailing zeros.
+#[allow(clippy::too_many_arguments)]
 pub fn decimal_fetch_strategy(
     is_optional: bool,
     scale: i32,
     precision: u8,
     avoid_decimal: bool,
     driver_does_support_i64: bool,
-) -> Box<dyn ColumnStrategy> {
+    decimal_byte_length: Option<usize>,
+    rounding: DecimalRounding,
+    check: Option<DecimalPrecisionCheck>,
+) -> Result<Box<dyn ColumnStrategy>, Error> {
+    if scale < 0 {
+        let integral_precision = u8::try_from(i32::from(precision) - scale).unwrap_or(u8::MAX);
+        info!(
+            "Decimal with precision {precision} and negative scale {scale} is written with \
+            precision {integral_precision} and scale 0."
+        );
+        return decimal_fetch_strategy(
+            is_optional,
+            0,
+            integral_precision,
+            avoid_decimal,
+            driver_does_support_i64,
+            decimal_byte_length,
+            rounding,
+            check,
+        );
+    }
+
     let repetition = if is_optional {
         Repetition::OPTIONAL
     } else {
@@ -36,10 +72,28 @@ pub fn decimal_fetch_strategy(
     if avoid_decimal && scale != 0 {
         // Precision + sign and radix character
         let length = precision as usize + 2;
-        return Box::new(Utf8::with_bytes_length(repetition, length));
+        return Ok(Box::new(Utf8::with_bytes_length(repetition, length)));
+    }
+
+    if let Some(length_in_bytes) = decimal_byte_length.filter(|_| precision <= 38) {
+        let min_length = min_length_in_bytes(precision);
+        if length_in_bytes < min_length {
+            bail!(
+                "Decimals with precision {precision} require at least {min_length} bytes, but \
+                `--decimal-byte-length` is {length_in_bytes}."
+            )
+        }
+        return Ok(Box::new(DecimalAsBinary::with_length_in_bytes(
+            repetition,
+            scale,
+            precision,
+            length_in_bytes,
+            rounding,
+            check,
+        )));
     }
 
-    match (precision, scale) {
+    let strategy = match (precision, scale) {
         (0..=9, 0) => {
             let logical_type = if avoid_decimal {
                 LogicalType::Integer {
@@ -67,6 +121,8 @@ pub fn decimal_fetch_strategy(
                     scale,
                     precision: precision as i32,
                 },
+                rounding,
+                check,
             ))
         }
         (10..=18, 0) => {
@@ -105,6 +161,8 @@ pub fn decimal_fetch_strategy(
                     0,
                     repetition,
                     logical_type,
+                    rounding,
+                    check,
                 ))
             }
         }
@@ -119,9 +177,13 @@ pub fn decimal_fetch_strategy(
                     scale,
                     precision: precision as i32,
                 },
+                rounding,
+                check,
             ))
         }
-        (0..=38, _) => Box::new(DecimalAsBinary::new(repetition, scale, precision)),
+        (0..=38, _) => Box::new(DecimalAsBinary::new(
+            repetition, scale, precision, rounding, check,
+        )),
         (_, _) => {
             let length = odbc_api::DataType::Decimal {
                 precision: precision as usize,
@@ -131,7 +193,8 @@ pub fn decimal_fetch_strategy(
             .unwrap();
             Box::new(Utf8::with_bytes_length(repetition, length.get()))
         }
-    }
+    };
+    Ok(strategy)
 }
 
 struct DecimalTextToInteger<Pdt> {
@@ -139,16 +202,27 @@ struct DecimalTextToInteger<Pdt> {
     scale: i32,
     repetition: Repetition,
     logical_type: LogicalType,
-    _pdt: PhantomData<Pdt>,
+    rounding: DecimalRounding,
+    check: Option<DecimalPrecisionCheck>,
+    _pdt: PhantomData<fn() -> Pdt>,
 }
 
 impl<Pdt> DecimalTextToInteger<Pdt> {
-    fn new(precision: u8, scale: i32, repetition: Repetition, logical_type: LogicalType) -> Self {
+    fn new(
+        precision: u8,
+        scale: i32,
+        repetition: Repetition,
+        logical_type: LogicalType,
+        rounding: DecimalRounding,
+        check: Option<DecimalPrecisionCheck>,
+    ) -> Self {
         Self {
             precision,
             scale,
             repetition,
             logical_type,
+            rounding,
+            check,
             _pdt: PhantomData,
         }
     }
@@ -157,7 +231,7 @@ impl<Pdt> DecimalTextToInteger<Pdt> {
 impl<Pdt> ColumnStrategy for DecimalTextToInteger<Pdt>
 where
     Pdt: ParquetDataType,
-    Pdt::T: FromRadix10Signed + BufferedDataType,
+    Pdt::T: TryFrom<i128> + BufferedDataType,
 {
     fn parquet_type(&self, name: &str) -> Type {
         Type::primitive_type_builder(name, Pdt::get_physical_type())
@@ -189,49 +263,122 @@ where
         column_writer: &mut ColumnWriter,
         column_view: AnySlice,
     ) -> Result<(), Error> {
-        // This vec is going to hold the digits with sign and decimal point. It is
-        // allocated once and reused for each value.
-        let mut digits: Vec<u8> = Vec::with_capacity(self.precision as usize + 2);
-
         let column_writer = Pdt::get_column_writer_mut(column_writer).unwrap();
-        let view = column_view.as_text_view().expect(
-            "Invalid Column view type. This is not supposed to happen. Please open a Bug at \
-            https://github.com/pacman82/odbc2parquet/issues.",
-        );
-        parquet_buffer.write_optional(
+        let view = column_view
+            .as_text_view()
+            .ok_or_else(|| unexpected_view("decimals as text", column_view))?;
+        let scale = self.scale.try_into().unwrap();
+        let mut violations = Vec::new();
+        let result = parquet_buffer.write_optional_fallible(
             column_writer,
-            view.iter().map(|value| {
-                value.map(|text| {
-                    digits.clear();
-                    digits.extend(text.iter().filter(|&&c| c != b'.'));
-                    Pdt::T::from_radix_10_signed(&digits).0
-                })
+            view.iter().enumerate().map(|(row_index, value)| {
+                value
+                    .map(|text| {
+                        let n = decimal_text_to_scaled(text, scale, self.rounding)?;
+                        if exceeds_precision(text, n, self.precision, self.check)? {
+                            violations.push(row_index);
+                        }
+                        Pdt::T::try_from(n).map_err(|_| {
+                            anyhow!(
+                                "Decimal '{}' exceeds a precision of {} digits.",
+                                String::from_utf8_lossy(text),
+                                self.precision
+                            )
+                        })
+                    })
+                    .transpose()
             }),
-        )
+        );
+        parquet_buffer.precision_violations = violations;
+        result
+    }
+}
+
+/// `true` if the scaled value `n` has more digits than `precision` and `check` is
+/// [`DecimalPrecisionCheck::Warn`]. An error naming `text` if it is
+/// [`DecimalPrecisionCheck::Strict`]. Always `false` without a `check`.
+fn exceeds_precision(
+    text: &[u8],
+    n: i128,
+    precision: u8,
+    check: Option<DecimalPrecisionCheck>,
+) -> Result<bool, Error> {
+    let Some(check) = check else {
+        return Ok(false);
+    };
+    // A precision beyond 38 digits can not be exceeded by an i128.
+    let is_within = 10u128
+        .checked_pow(precision.into())
+        .is_none_or(|limit| n.unsigned_abs() < limit);
+    match (is_within, check) {
+        (true, _) => Ok(false),
+        (false, DecimalPrecisionCheck::Warn) => Ok(true),
+        (false, DecimalPrecisionCheck::Strict) => bail!(
+            "Decimal '{}' has more digits than its precision of {precision}.",
+            String::from_utf8_lossy(text)
+        ),
     }
 }
 
+/// Smallest number of bytes holding the two's complement of every decimal with `precision` digits.
+pub fn min_length_in_bytes(precision: u8) -> usize {
+    // Length of the two's complement.
+    let num_binary_digits = precision as f64 * 10f64.log2();
+    // Plus one bit for the sign (+/-)
+    let length_in_bits = num_binary_digits + 1.0;
+    (length_in_bits / 8.0).ceil() as usize
+}
+
 /// Strategy for fetching decimal values which can not be represented as either 32Bit or 64Bit
 struct DecimalAsBinary {
     repetition: Repetition,
     scale: i32,
     precision: u8,
     length_in_bytes: usize,
+    /// `true` if `length_in_bytes` has been forced by `--decimal-byte-length`. Values which do not
+    /// fit are an error then.
+    forced_length: bool,
+    rounding: DecimalRounding,
+    check: Option<DecimalPrecisionCheck>,
 }
 
 impl DecimalAsBinary {
-    pub fn new(repetition: Repetition, scale: i32, precision: u8) -> Self {
-        // Length of the two's complement.
-        let num_binary_digits = precision as f64 * 10f64.log2();
-        // Plus one bit for the sign (+/-)
-        let length_in_bits = num_binary_digits + 1.0;
-        let length_in_bytes = (length_in_bits / 8.0).ceil() as usize;
+    pub fn new(
+        repetition: Repetition,
+        scale: i32,
+        precision: u8,
+        rounding: DecimalRounding,
+        check: Option<DecimalPrecisionCheck>,
+    ) -> Self {
+        Self {
+            repetition,
+            scale,
+            precision,
+            length_in_bytes: min_length_in_bytes(precision),
+            forced_length: false,
+            rounding,
+            check,
+        }
+    }
 
+    /// `length_in_bytes` must be at least [`min_length_in_bytes`] for `precision`. Values are sign
+    /// extended to the full length. Values which do not fit are an error.
+    pub fn with_length_in_bytes(
+        repetition: Repetition,
+        scale: i32,
+        precision: u8,
+        length_in_bytes: usize,
+        rounding: DecimalRounding,
+        check: Option<DecimalPrecisionCheck>,
+    ) -> Self {
         Self {
             repetition,
             scale,
             precision,
             length_in_bytes,
+            forced_length: true,
+            rounding,
+            check,
         }
     }
 }
@@ -269,37 +416,398 @@ impl ColumnStrategy for DecimalAsBinary {
         column_writer: &mut ColumnWriter,
         column_view: AnySlice,
     ) -> Result<(), Error> {
-        write_decimal_col(
-            parquet_buffer,
+        let column_writer = FixedLenByteArrayType::get_column_writer_mut(column_writer).unwrap();
+        let view = column_view
+            .as_text_view()
+            .ok_or_else(|| unexpected_view("decimals as text", column_view))?;
+        let scale = self.scale as usize;
+        let mut violations = Vec::new();
+        let result = parquet_buffer.write_twos_complement_i128(
             column_writer,
-            column_view,
+            view.iter().enumerate().map(|(row_index, field)| {
+                field
+                    .map(|text| {
+                        let n = decimal_text_to_scaled(text, scale, self.rounding)?;
+                        if exceeds_precision(text, n, self.precision, self.check)? {
+                            violations.push(row_index);
+                        }
+                        Ok(n)
+                    })
+                    .transpose()
+            }),
             self.length_in_bytes,
-            self.scale,
-        )
+            self.forced_length,
+        );
+        parquet_buffer.precision_violations = violations;
+        result
     }
 }
 
-fn write_decimal_col(
-    parquet_buffer: &mut ParquetBuffer,
-    column_writer: &mut ColumnWriter,
-    column_reader: AnySlice,
-    length_in_bytes: usize,
-    scale: i32,
-) -> Result<(), Error> {
-    let column_writer = FixedLenByteArrayType::get_column_writer_mut(column_writer).unwrap();
-    let view = column_reader.as_text_view().expect(
-        "Invalid Column view type. This is not supposed to happen. Please open a Bug at \
-        https://github.com/pacman82/odbc2parquet/issues.",
-    );
-
-    let scale = scale as usize;
-
-    parquet_buffer.write_twos_complement_i128(
-        column_writer,
-        view.iter()
-            .map(|field| field.map(|text| decimal_text_to_i128(text, scale))),
-        length_in_bytes,
-    )?;
-
-    Ok(())
+/// Value of the text representation of a decimal as integer, i.e. multiplied by `10^scale`. Excess
+/// fractional digits are rounded according to `rounding`. The first character other than a digit is
+/// regarded as radix character, with the exception of a sign at the beginning. Trailing whitespace,
+/// e.g. padding of fixed width columns, is ignored. Any other text, like an exponent or a group
+/// separator, as well as values beyond the range of an `i128` are an error.
+pub fn decimal_text_to_scaled(
+    text: &[u8],
+    scale: usize,
+    rounding: DecimalRounding,
+) -> Result<i128, Error> {
+    let invalid = || anyhow!("Invalid decimal '{}'.", String::from_utf8_lossy(text));
+    let (is_negative, digits) = match text.split_first() {
+        Some((b'-', rest)) => (true, rest),
+        Some((b'+', rest)) => (false, rest),
+        _ => (false, text),
+    };
+    let integer_len = digits.iter().take_while(|c| c.is_ascii_digit()).count();
+    let (integer, rest) = digits.split_at(integer_len);
+    let rest = rest.get(1..).unwrap_or_default();
+    let fraction_len = rest.iter().take_while(|c| c.is_ascii_digit()).count();
+    let (fraction, rest) = rest.split_at(fraction_len);
+    if !rest.iter().all(u8::is_ascii_whitespace) {
+        return Err(invalid());
+    }
+    let (kept, excess) = fraction.split_at(fraction.len().min(scale));
+
+    let mut n: i128 = 0;
+    for &digit in integer.iter().chain(kept) {
+        n = n
+            .checked_mul(10)
+            .and_then(|n| n.checked_add(i128::from(digit - b'0')))
+            .ok_or_else(invalid)?;
+    }
+    for _ in kept.len()..scale {
+        n = n.checked_mul(10).ok_or_else(invalid)?;
+    }
+
+    // Whether to increase the absolute value by one, given the digits which are dropped.
+    let first_excess = excess.first().map_or(0, |&digit| digit - b'0');
+    let is_exact = excess.iter().all(|&digit| digit == b'0');
+    let round_away_from_zero = !is_exact
+        && match rounding {
+            DecimalRounding::HalfUp => first_excess >= 5,
+            DecimalRounding::HalfEven => match first_excess.cmp(&5) {
+                Ordering::Greater => true,
+                Ordering::Less => false,
+                // A tie, unless any of the other digits dropped is not zero.
+                Ordering::Equal => excess[1..].iter().any(|&digit| digit != b'0') || n % 2 == 1,
+            },
+            DecimalRounding::Truncate => false,
+            DecimalRounding::Floor => is_negative,
+            DecimalRounding::Ceil => !is_negative,
+        };
+    if round_away_from_zero {
+        n = n.checked_add(1).ok_or_else(invalid)?;
+    }
+    Ok(if is_negative { -n } else { n })
+}
+
+/// Value of a decimal number as integer, i.e. multiplied by `10^scale`. `None` if the number can
+/// not be represented exactly with this scale.
+pub fn parse_scaled(text: &str, scale: usize) -> Option<i128> {
+    let (integer, fraction) = text.split_once('.').unwrap_or((text, ""));
+    if fraction.len() > scale && fraction[scale..].chars().any(|c| c != '0') {
+        return None;
+    }
+    let fraction = &fraction[..fraction.len().min(scale)];
+    let digits = format!("{integer}{fraction:0<scale$}");
+    // An empty integer part is allowed, e.g. `.5` or `-.5`.
+    match digits.as_str() {
+        "" | "-" | "+" => None,
+        _ => digits.parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Instant};
+
+    use odbc_api::{
+        buffers::{AnySlice, ColumnBuffer, TextColumn},
+        DataType,
+    };
+    use parquet::{
+        basic::LogicalType, data_type::DoubleType, file::properties::WriterProperties,
+        file::writer::SerializedFileWriter, schema::types::Type,
+    };
+
+    use crate::{parquet_buffer::ParquetBuffer, query::identical::fetch_identical};
+
+    use crate::enum_args::{DecimalPrecisionCheck, DecimalRounding};
+
+    use super::{decimal_fetch_strategy, decimal_text_to_scaled, parse_scaled, ColumnStrategy};
+
+    #[test]
+    fn round_excess_fractional_digits() {
+        let round =
+            |text: &str, rounding| decimal_text_to_scaled(text.as_bytes(), 2, rounding).unwrap();
+        assert_eq!(235, round("2.345", DecimalRounding::HalfUp));
+        assert_eq!(234, round("2.345", DecimalRounding::HalfEven));
+        assert_eq!(234, round("2.345", DecimalRounding::Truncate));
+        assert_eq!(234, round("2.345", DecimalRounding::Floor));
+        assert_eq!(235, round("2.345", DecimalRounding::Ceil));
+
+        assert_eq!(-235, round("-2.345", DecimalRounding::HalfUp));
+        assert_eq!(-234, round("-2.345", DecimalRounding::HalfEven));
+        assert_eq!(-234, round("-2.345", DecimalRounding::Truncate));
+        assert_eq!(-235, round("-2.345", DecimalRounding::Floor));
+        assert_eq!(-234, round("-2.345", DecimalRounding::Ceil));
+
+        // Ties round to even, anything above the tie rounds up.
+        assert_eq!(236, round("2.355", DecimalRounding::HalfEven));
+        assert_eq!(235, round("2.3451", DecimalRounding::HalfEven));
+        // Zeros dropped do not round, fewer digits are padded.
+        assert_eq!(230, round("2.3000", DecimalRounding::Ceil));
+        assert_eq!(230, round("2.3", DecimalRounding::Ceil));
+        assert_eq!(-1, round("-0.005", DecimalRounding::HalfUp));
+        assert_eq!(100, round("0.995", DecimalRounding::HalfUp));
+    }
+
+    #[test]
+    fn reject_decimal_text_with_exponent_or_too_many_digits() {
+        let parse =
+            |text: &str| decimal_text_to_scaled(text.as_bytes(), 2, DecimalRounding::HalfUp);
+        // Padding of fixed width columns
+        assert_eq!(150, parse("1.50 ").unwrap());
+        assert_eq!(
+            "Invalid decimal '1E+5'.",
+            parse("1E+5").unwrap_err().to_string()
+        );
+        assert!(parse("1,000.50").is_err());
+        // 40 digits exceed the range of an i128
+        assert!(parse("1234567890123456789012345678901234567890").is_err());
+        assert!(parse("-12345678901234567890123456789012345678.9").is_err());
+    }
+
+    #[test]
+    fn parse_decimal_text_with_scale() {
+        assert_eq!(Some(12345), parse_scaled("123.45", 2));
+        assert_eq!(Some(-12340), parse_scaled("-123.4", 2));
+        assert_eq!(Some(12300), parse_scaled("123.000", 2));
+        assert_eq!(None, parse_scaled("123.456", 2));
+        assert_eq!(Some(50), parse_scaled(".5", 2));
+        assert_eq!(None, parse_scaled("abc", 0));
+    }
+
+    #[test]
+    fn negative_scale_is_stored_as_integral_decimal() {
+        // Oracle `NUMBER(5,-2)`, i.e. values rounded to hundreds.
+        let DataType::Decimal { precision, scale } = (DataType::Decimal {
+            precision: 5,
+            scale: -2,
+        }) else {
+            unreachable!()
+        };
+
+        let strategy = decimal_fetch_strategy(
+            true,
+            scale.into(),
+            precision.try_into().unwrap(),
+            false,
+            true,
+            None,
+            DecimalRounding::HalfUp,
+            None,
+        )
+        .unwrap();
+
+        // Must not panic
+        strategy.buffer_desc();
+        let parquet_type = strategy.parquet_type("a");
+        assert_eq!(
+            Some(LogicalType::Decimal {
+                scale: 0,
+                precision: 7
+            }),
+            parquet_type.get_basic_info().logical_type()
+        );
+    }
+
+    #[test]
+    fn mismatched_column_view_is_an_error() {
+        // Fetched as text and written as fixed length byte array.
+        let strategy = decimal_fetch_strategy(
+            true,
+            2,
+            30,
+            false,
+            true,
+            None,
+            DecimalRounding::HalfUp,
+            None,
+        )
+        .unwrap();
+        let schema = Type::group_type_builder("schema")
+            .with_fields(vec![Arc::new(strategy.parquet_type("a"))])
+            .build()
+            .unwrap();
+        let properties = Arc::new(WriterProperties::builder().build());
+        let mut writer =
+            SerializedFileWriter::new(Vec::new(), Arc::new(schema), properties).unwrap();
+        let mut row_group_writer = writer.next_row_group().unwrap();
+        let mut column_writer = row_group_writer.next_column().unwrap().unwrap();
+        let mut parquet_buffer = ParquetBuffer::new(2);
+        parquet_buffer.set_num_rows_fetched(2);
+
+        let result = strategy.copy_odbc_to_parquet(
+            &mut parquet_buffer,
+            column_writer.untyped(),
+            AnySlice::I32(&[1, 2]),
+        );
+
+        assert_eq!(
+            "Expected decimals as text to be fetched, but the buffer holds 32-Bit integers. The \
+            data source may report a different type for the column than the one it has been \
+            bound with.",
+            result.unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn negative_scale_exceeding_max_precision() {
+        let strategy = decimal_fetch_strategy(
+            true,
+            -10,
+            38,
+            false,
+            true,
+            None,
+            DecimalRounding::HalfUp,
+            None,
+        )
+        .unwrap();
+
+        // Must not panic
+        strategy.buffer_desc();
+        strategy.parquet_type("a");
+    }
+
+    #[test]
+    fn validate_precision_of_decimals() {
+        // Written as 32-Bit integer and as fixed length byte array. The first value of each hits the
+        // precision exactly, the second exceeds it by one digit.
+        for (scale, precision, values) in [
+            (2, 5, ["-999.99", "1000.00"]),
+            (0, 20, ["99999999999999999999", "-100000000000000000000"]),
+        ] {
+            let write = |check| {
+                let strategy = decimal_fetch_strategy(
+                    true,
+                    scale,
+                    precision,
+                    false,
+                    true,
+                    None,
+                    DecimalRounding::HalfUp,
+                    Some(check),
+                )
+                .unwrap();
+                let mut parquet_buffer = ParquetBuffer::new(2);
+                let result = write_text(strategy.as_ref(), &mut parquet_buffer, &values);
+                result.map(|()| parquet_buffer.precision_violations)
+            };
+
+            assert_eq!(vec![1], write(DecimalPrecisionCheck::Warn).unwrap());
+            assert_eq!(
+                format!(
+                    "Decimal '{}' has more digits than its precision of {precision}.",
+                    values[1]
+                ),
+                write(DecimalPrecisionCheck::Strict)
+                    .unwrap_err()
+                    .to_string()
+            );
+        }
+    }
+
+    /// Compares the time spent by odbc2parquet on decimals fetched as text with the time spent on
+    /// decimals fetched as `DOUBLE` due to `--fast-decimals`. Not run by default, use:
+    /// `cargo test --release fast_decimals_benchmark -- --ignored --nocapture`. The time the driver
+    /// spends on converting the values is not part of it.
+    #[test]
+    #[ignore]
+    fn fast_decimals_benchmark() {
+        const NUM_ROWS: usize = 100_000;
+        const NUM_BATCHES: u32 = 50;
+        let texts: Vec<String> = (0..NUM_ROWS)
+            .map(|i| format!("{}.{:02}", i * 7919 % 10_000_000, i % 100))
+            .collect();
+        let mut text_column = TextColumn::new(NUM_ROWS, 16);
+        for (index, text) in texts.iter().enumerate() {
+            text_column.set_value(index, Some(text.as_bytes()));
+        }
+        let doubles: Vec<f64> = texts.iter().map(|text| text.parse().unwrap()).collect();
+
+        let measure = |strategy: Box<dyn ColumnStrategy>, view: AnySlice| {
+            let schema = Type::group_type_builder("schema")
+                .with_fields(vec![Arc::new(strategy.parquet_type("a"))])
+                .build()
+                .unwrap();
+            let properties = Arc::new(WriterProperties::builder().build());
+            let mut writer =
+                SerializedFileWriter::new(std::io::sink(), Arc::new(schema), properties).unwrap();
+            let mut row_group_writer = writer.next_row_group().unwrap();
+            let mut column_writer = row_group_writer.next_column().unwrap().unwrap();
+            let mut parquet_buffer = ParquetBuffer::new(NUM_ROWS);
+            parquet_buffer.set_num_rows_fetched(NUM_ROWS);
+            let start = Instant::now();
+            for _ in 0..NUM_BATCHES {
+                strategy
+                    .copy_odbc_to_parquet(&mut parquet_buffer, column_writer.untyped(), view)
+                    .unwrap();
+            }
+            start.elapsed() / NUM_BATCHES
+        };
+        let decimal = |precision| {
+            decimal_fetch_strategy(
+                false,
+                2,
+                precision,
+                false,
+                true,
+                None,
+                DecimalRounding::HalfUp,
+                None,
+            )
+            .unwrap()
+        };
+
+        let as_int64 = measure(decimal(18), AnySlice::Text(text_column.view(NUM_ROWS)));
+        let as_binary = measure(decimal(30), AnySlice::Text(text_column.view(NUM_ROWS)));
+        let as_double = measure(
+            fetch_identical::<DoubleType>(false),
+            AnySlice::F64(&doubles),
+        );
+        println!("{NUM_ROWS} decimals per batch:");
+        println!("  DECIMAL(18,2) as text, written as INT64: {as_int64:?}");
+        println!("  DECIMAL(30,2) as text, written as FIXED_LEN_BYTE_ARRAY: {as_binary:?}");
+        println!("  --fast-decimals, written as DOUBLE: {as_double:?}");
+    }
+
+    /// Writes `values` fetched as text with `strategy` into a column of an in memory file.
+    fn write_text(
+        strategy: &dyn ColumnStrategy,
+        parquet_buffer: &mut ParquetBuffer,
+        values: &[&str],
+    ) -> Result<(), anyhow::Error> {
+        let mut column = TextColumn::new(values.len(), 64);
+        for (index, value) in values.iter().enumerate() {
+            column.set_value(index, Some(value.as_bytes()));
+        }
+        let schema = Type::group_type_builder("schema")
+            .with_fields(vec![Arc::new(strategy.parquet_type("a"))])
+            .build()
+            .unwrap();
+        let properties = Arc::new(WriterProperties::builder().build());
+        let mut writer =
+            SerializedFileWriter::new(Vec::new(), Arc::new(schema), properties).unwrap();
+        let mut row_group_writer = writer.next_row_group().unwrap();
+        let mut column_writer = row_group_writer.next_column().unwrap().unwrap();
+        parquet_buffer.set_num_rows_fetched(values.len());
+        strategy.copy_odbc_to_parquet(
+            parquet_buffer,
+            column_writer.untyped(),
+            AnySlice::Text(column.view(values.len())),
+        )
+    }
 }
@@ -0,0 +1,870 @@
+use std::{
+    fmt::Write as _,
+    fs::{self, File},
+    io::{self, stdout, BufWriter, Write},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use anyhow::{anyhow, bail, Context, Error};
+use bytes::Bytes;
+use bytesize::ByteSize;
+use chrono::{DateTime, NaiveDate, NaiveTime};
+use flate2::write::GzEncoder;
+use io_arg::IoArg;
+use parquet::{
+    basic::{ConvertedType, LogicalType, TimeUnit, Type as PhysicalType},
+    column::reader::{ColumnReader, ColumnReaderImpl},
+    data_type::{DataType, Int96},
+    file::{
+        properties::WriterProperties,
+        reader::{FileReader, SerializedFileReader},
+        writer::SerializedFileWriter,
+    },
+    schema::types::{ColumnDescriptor, SchemaDescriptor, Type},
+};
+
+use crate::{
+    enum_args::{CompressionVariants, CsvHeader, CsvQuote, JsonDecimal, JsonNulls},
+    is_fifo,
+    parquet_buffer::{BufferedDataType, ParquetBuffer, ProfileValue},
+};
+
+use super::{
+    batch_size_limit::{FileSizeLimit, MaxFileCount},
+    parquet_writer::{
+        path_with_suffix, CreatedFiles, OutputSize, ParquetOutput, ParquetWriterOptions,
+        WrittenRowGroup,
+    },
+    stats_only::json_string,
+    table_strategy::ColumnExporter,
+};
+
+/// How values are rendered as delimited text, e.g. CSV, or as newline delimited JSON.
+#[derive(Clone)]
+pub struct DelimitedFormat {
+    /// Separates the values of a row.
+    pub delimiter: char,
+    pub quote: CsvQuote,
+    /// Written for NULL, never quoted.
+    pub null: String,
+    pub header: CsvHeader,
+    /// `strftime` like format of dates.
+    pub date_format: String,
+    /// `strftime` like format of times.
+    pub time_format: String,
+    /// `strftime` like format of timestamps.
+    pub timestamp_format: String,
+    /// Separates integer and fractional digits of decimals.
+    pub decimal_separator: char,
+    /// Write one JSON object per line instead of delimited values, if specified. Delimiter,
+    /// quoting, NULL representation and header do not apply then.
+    pub json: Option<JsonFormat>,
+    /// Compression of the text as a whole.
+    pub compression: TextCompression,
+}
+
+/// Options of newline delimited JSON output.
+#[derive(Clone, Copy)]
+pub struct JsonFormat {
+    pub decimal: JsonDecimal,
+    pub nulls: JsonNulls,
+}
+
+impl JsonFormat {
+    /// Dates, times and timestamps are rendered as ISO 8601 strings. Timestamps with time zone are
+    /// rendered in UTC with the suffix `Z`.
+    pub const DATE_FORMAT: &'static str = "%Y-%m-%d";
+    pub const TIME_FORMAT: &'static str = "%H:%M:%S%.f";
+    pub const TIMESTAMP_FORMAT: &'static str = "%Y-%m-%dT%H:%M:%S%.f";
+}
+
+/// Compression of text output as a whole, see `--text-compression`.
+#[derive(Clone, Copy)]
+pub enum TextCompression {
+    None,
+    Gzip(u32),
+    Zstd(i32),
+}
+
+impl TextCompression {
+    pub fn new(codec: CompressionVariants, level: Option<u32>) -> Result<Self, Error> {
+        let compression = match codec {
+            CompressionVariants::Uncompressed => TextCompression::None,
+            CompressionVariants::Gzip => {
+                let level = level.unwrap_or(6);
+                if level > 9 {
+                    bail!("Compression level of gzip must be between 0 and 9.")
+                }
+                TextCompression::Gzip(level)
+            }
+            CompressionVariants::Zstd => TextCompression::Zstd(level.unwrap_or(3).try_into()?),
+            other => bail!(
+                "Text output can only be compressed with `gzip` or `zstd`, not with `{}`.",
+                format!("{other:?}").to_lowercase()
+            ),
+        };
+        Ok(compression)
+    }
+}
+
+/// Writes the rows as delimited text, rather than parquet. Each batch is still converted into a
+/// parquet row group first, so all column strategies apply. The row group is then read back and
+/// each value rendered according to the logical type of its column.
+pub struct Delimited {
+    /// Base path of the files written, or standard out.
+    output: IoArg,
+    schema: Arc<Type>,
+    /// Used to encode the row group, which is read back in order to render the values.
+    properties: Arc<WriterProperties>,
+    format: DelimitedFormat,
+    file_size: FileSizeLimit,
+    max_file_count: Option<MaxFileCount>,
+    suffix_length: usize,
+    created_files: CreatedFiles,
+    /// Number of files started so far.
+    num_file: u32,
+    /// File currently written to. `None` between files, or before the first row group if no empty
+    /// file should be created.
+    current: Option<DelimitedFile>,
+    /// Used to read the values of the row group back.
+    buffer: ParquetBuffer,
+    /// Name of each column as JSON string, used as keys of JSON objects.
+    keys: Vec<String>,
+    /// Files which have already been closed.
+    written: OutputSize,
+}
+
+struct DelimitedFile {
+    writer: BufWriter<TextSink>,
+    /// `None` for standard out.
+    path: Option<PathBuf>,
+    /// Bytes of text written, before compression.
+    num_bytes: u64,
+}
+
+/// Destination of the text, compressing it as a whole if requested.
+enum TextSink {
+    Plain(Box<dyn Write + Send>),
+    Gzip(GzEncoder<Box<dyn Write + Send>>),
+    Zstd(zstd::Encoder<'static, Box<dyn Write + Send>>),
+}
+
+impl TextSink {
+    fn new(writer: Box<dyn Write + Send>, compression: TextCompression) -> Result<Self, Error> {
+        let sink = match compression {
+            TextCompression::None => TextSink::Plain(writer),
+            TextCompression::Gzip(level) => {
+                TextSink::Gzip(GzEncoder::new(writer, flate2::Compression::new(level)))
+            }
+            TextCompression::Zstd(level) => TextSink::Zstd(zstd::Encoder::new(writer, level)?),
+        };
+        Ok(sink)
+    }
+
+    /// Writes the end of the compressed stream, if any, and flushes the underlying writer.
+    fn finish(self) -> io::Result<()> {
+        let mut writer = match self {
+            TextSink::Plain(writer) => writer,
+            TextSink::Gzip(encoder) => encoder.finish()?,
+            TextSink::Zstd(encoder) => encoder.finish()?,
+        };
+        writer.flush()
+    }
+}
+
+impl Write for TextSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            TextSink::Plain(writer) => writer.write(buf),
+            TextSink::Gzip(encoder) => encoder.write(buf),
+            TextSink::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            TextSink::Plain(writer) => writer.flush(),
+            TextSink::Gzip(encoder) => encoder.flush(),
+            TextSink::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+impl Delimited {
+    pub fn new(
+        output: IoArg,
+        schema: Arc<Type>,
+        properties: Arc<WriterProperties>,
+        options: &ParquetWriterOptions,
+        format: DelimitedFormat,
+    ) -> Result<Self, Error> {
+        let keys = SchemaDescriptor::new(schema.clone())
+            .columns()
+            .iter()
+            .map(|column| json_string(&column.path().string()))
+            .collect();
+        let mut delimited = Self {
+            output,
+            schema,
+            properties,
+            format,
+            file_size: options.file_size,
+            max_file_count: options.max_file_count,
+            suffix_length: options.suffix_length,
+            created_files: options.created_files.clone(),
+            num_file: 0,
+            current: None,
+            buffer: ParquetBuffer::new(0),
+            keys,
+            written: OutputSize::default(),
+        };
+        if !options.no_empty_file {
+            delimited.next_file()?;
+        }
+        Ok(delimited)
+    }
+
+    fn next_file(&mut self) -> Result<(), Error> {
+        if let Some(max_file_count) = &self.max_file_count {
+            max_file_count.check_next_file(self.num_file)?;
+        }
+        let (writer, path): (Box<dyn Write + Send>, _) = match &self.output {
+            IoArg::StdStream => (Box::new(stdout()), None),
+            IoArg::File(base_path) => {
+                let path = if self.file_size.output_is_splitted() {
+                    path_with_suffix(base_path, self.num_file + 1, self.suffix_length)?
+                } else {
+                    base_path.clone()
+                };
+                let file = File::create(&path).with_context(|| {
+                    format!("Could not create output file '{}'", path.display())
+                })?;
+                if !is_fifo(&path) {
+                    self.created_files.push(path.clone());
+                }
+                (Box::new(file), Some(path))
+            }
+        };
+        let mut file = DelimitedFile {
+            writer: BufWriter::new(TextSink::new(writer, self.format.compression)?),
+            path,
+            num_bytes: 0,
+        };
+        let write_header = match self.format.header {
+            _ if self.format.json.is_some() => false,
+            CsvHeader::EveryFile => true,
+            CsvHeader::FirstFile => self.num_file == 0,
+            CsvHeader::None => false,
+        };
+        if write_header {
+            let names: Vec<String> = SchemaDescriptor::new(self.schema.clone())
+                .columns()
+                .iter()
+                .map(|column| column.path().string())
+                .collect();
+            let mut line = String::new();
+            write_line(
+                &mut line,
+                names.iter().map(|name| Some(name.as_str())),
+                &self.format,
+            );
+            file.write(&line)?;
+        }
+        self.current = Some(file);
+        self.num_file += 1;
+        Ok(())
+    }
+
+    /// Renders the values of the row group encoded in `bytes`. One entry for each column.
+    fn render_columns(&mut self, bytes: Vec<u8>) -> Result<Vec<Vec<Option<String>>>, Error> {
+        let reader = SerializedFileReader::new(Bytes::from(bytes))?;
+        let row_group = reader.get_row_group(0)?;
+        let num_rows: usize = row_group.metadata().num_rows().try_into().unwrap();
+        let buffer = &mut self.buffer;
+        let format = &self.format;
+        let mut columns = Vec::new();
+        for (index, descr) in row_group
+            .metadata()
+            .schema_descr()
+            .columns()
+            .iter()
+            .enumerate()
+        {
+            let kind = Kind::new(descr.self_type());
+            let column_reader = row_group.get_column_reader(index)?;
+            let is_int96 = matches!(column_reader, ColumnReader::Int96ColumnReader(_));
+            let values = match column_reader {
+                ColumnReader::BoolColumnReader(cr) => {
+                    read(buffer, cr, descr, num_rows, |value| Ok(value.to_string()))?
+                }
+                ColumnReader::Int32ColumnReader(cr) => {
+                    read(buffer, cr, descr, num_rows, |&value| {
+                        let value = match kind {
+                            Kind::Unsigned => value as u32 as i64,
+                            _ => value as i64,
+                        };
+                        kind.render_integer(value, format)
+                    })?
+                }
+                ColumnReader::Int64ColumnReader(cr) => {
+                    read(buffer, cr, descr, num_rows, |&value| match kind {
+                        Kind::Unsigned => Ok((value as u64).to_string()),
+                        _ => kind.render_integer(value, format),
+                    })?
+                }
+                ColumnReader::Int96ColumnReader(cr) => {
+                    read(buffer, cr, descr, num_rows, |value| {
+                        render_int96(value, format)
+                    })?
+                }
+                ColumnReader::FloatColumnReader(cr) => {
+                    read(buffer, cr, descr, num_rows, |value| Ok(value.to_string()))?
+                }
+                ColumnReader::DoubleColumnReader(cr) => {
+                    read(buffer, cr, descr, num_rows, |value| Ok(value.to_string()))?
+                }
+                ColumnReader::ByteArrayColumnReader(cr) => {
+                    read(buffer, cr, descr, num_rows, |value| {
+                        kind.render_bytes(value.data(), format)
+                    })?
+                }
+                ColumnReader::FixedLenByteArrayColumnReader(cr) => {
+                    read(buffer, cr, descr, num_rows, |value| {
+                        kind.render_bytes(value.data(), format)
+                    })?
+                }
+            };
+            let values = match &format.json {
+                Some(json) => {
+                    let kind = if is_int96 { Kind::INT96 } else { kind };
+                    values
+                        .into_iter()
+                        .map(|value| value.map(|text| json_value(kind, text, json)))
+                        .collect()
+                }
+                None => values,
+            };
+            columns.push(values);
+        }
+        Ok(columns)
+    }
+}
+
+/// Reads the values of a column chunk and renders each one not NULL.
+fn read<T>(
+    buffer: &mut ParquetBuffer,
+    mut column_reader: ColumnReaderImpl<T>,
+    descr: &ColumnDescriptor,
+    num_rows: usize,
+    render: impl Fn(&T::T) -> Result<String, Error>,
+) -> Result<Vec<Option<String>>, Error>
+where
+    T: DataType,
+    T::T: BufferedDataType,
+{
+    let name = descr.path().string();
+    let render = |value| {
+        render(value).with_context(|| format!("Could not render value of column '{name}'."))
+    };
+    if descr.max_def_level() > 0 {
+        buffer
+            .read_optional(&mut column_reader, num_rows)?
+            .map(|value| value.map(render).transpose())
+            .collect()
+    } else {
+        buffer
+            .read_required(&mut column_reader, num_rows)?
+            .iter()
+            .map(|value| render(value).map(Some))
+            .collect()
+    }
+}
+
+impl DelimitedFile {
+    fn write(&mut self, text: &str) -> Result<(), Error> {
+        self.writer.write_all(text.as_bytes())?;
+        self.num_bytes += text.len() as u64;
+        Ok(())
+    }
+
+    fn finish(self) -> Result<OutputSize, Error> {
+        self.writer
+            .into_inner()
+            .map_err(|error| error.into_error())?
+            .finish()?;
+        // The size of compressed text is only known once it has been written.
+        let num_bytes = match &self.path {
+            Some(path) if !is_fifo(path) => fs::metadata(path)?.len(),
+            _ => self.num_bytes,
+        };
+        Ok(OutputSize {
+            num_files: self.path.is_some().into(),
+            num_bytes,
+        })
+    }
+}
+
+impl ParquetOutput for Delimited {
+    fn write_row_group(
+        &mut self,
+        num_batch: u32,
+        column_exporter: &mut ColumnExporter,
+    ) -> Result<WrittenRowGroup, Error> {
+        let mut writer =
+            SerializedFileWriter::new(Vec::new(), self.schema.clone(), self.properties.clone())?;
+        let metadata = column_exporter.write_row_group(&mut writer)?;
+        let columns = self.render_columns(writer.into_inner()?)?;
+
+        if self.current.is_none() {
+            self.next_file()?;
+        }
+        let format = &self.format;
+        let current = self.current.as_mut().unwrap();
+        let num_rows = columns.first().map(Vec::len).unwrap_or_default();
+        let mut lines = String::new();
+        for row in 0..num_rows {
+            let values = columns.iter().map(|column| column[row].as_deref());
+            match &format.json {
+                Some(json) => write_json_line(&mut lines, &self.keys, values, json),
+                None => write_line(&mut lines, values, format),
+            }
+        }
+        current.write(&lines)?;
+        let path = current.path.clone();
+
+        let file_split = path.is_some()
+            && self
+                .file_size
+                .should_start_new_file(num_batch + 1, ByteSize::b(current.num_bytes))
+            && self
+                .max_file_count
+                .as_mut()
+                .is_none_or(|max_file_count| max_file_count.allow_split(self.num_file));
+        if file_split {
+            let written = self.current.take().unwrap().finish()?;
+            self.written.num_files += written.num_files;
+            self.written.num_bytes += written.num_bytes;
+        }
+        Ok(WrittenRowGroup {
+            metadata,
+            path,
+            file_split,
+        })
+    }
+
+    fn close(mut self) -> Result<OutputSize, Error> {
+        if let Some(current) = self.current.take() {
+            let written = current.finish()?;
+            self.written.num_files += written.num_files;
+            self.written.num_bytes += written.num_bytes;
+        }
+        Ok(self.written)
+    }
+
+    fn close_box(self: Box<Self>) -> Result<OutputSize, Error> {
+        self.close()
+    }
+}
+
+/// Appends one line of delimited values. `None` is written as the NULL representation.
+fn write_line<'a>(
+    line: &mut String,
+    values: impl Iterator<Item = Option<&'a str>>,
+    format: &DelimitedFormat,
+) {
+    for (index, value) in values.enumerate() {
+        if index != 0 {
+            line.push(format.delimiter);
+        }
+        let Some(value) = value else {
+            line.push_str(&format.null);
+            continue;
+        };
+        // RFC 4180. Values equal to the NULL representation (e.g. empty strings) are quoted, so
+        // they can be told apart from NULL.
+        let quote = match format.quote {
+            CsvQuote::Always => true,
+            CsvQuote::Never => false,
+            CsvQuote::Necessary => {
+                value == format.null || value.contains([format.delimiter, '"', '\n', '\r'])
+            }
+        };
+        if quote {
+            line.push('"');
+            line.push_str(&value.replace('"', "\"\""));
+            line.push('"');
+        } else {
+            line.push_str(value);
+        }
+    }
+    line.push('\n');
+}
+
+/// Appends one JSON object. Values are JSON already, `None` is NULL.
+fn write_json_line<'a>(
+    line: &mut String,
+    keys: &[String],
+    values: impl Iterator<Item = Option<&'a str>>,
+    json: &JsonFormat,
+) {
+    line.push('{');
+    let mut is_first = true;
+    for (key, value) in keys.iter().zip(values) {
+        let value = match value {
+            Some(value) => value,
+            None if json.nulls == JsonNulls::Omit => continue,
+            None => "null",
+        };
+        if !is_first {
+            line.push(',');
+        }
+        is_first = false;
+        line.push_str(key);
+        line.push(':');
+        line.push_str(value);
+    }
+    line.push_str("}\n");
+}
+
+/// Turns a value rendered as text into a JSON value. Booleans and numbers are written as they
+/// are, everything else as string. Floating points which are not finite are not valid JSON numbers
+/// and written as the strings `NaN`, `inf` and `-inf`.
+fn json_value(kind: Kind, text: String, json: &JsonFormat) -> String {
+    match kind {
+        Kind::Plain if !matches!(text.as_str(), "NaN" | "inf" | "-inf") => text,
+        Kind::Unsigned => text,
+        Kind::Decimal { .. } if json.decimal == JsonDecimal::Number => text,
+        Kind::Timestamp { is_utc: true, .. } => json_string(&(text + "Z")),
+        _ => json_string(&text),
+    }
+}
+
+/// How the values of a column are rendered, derived from its logical type.
+#[derive(Clone, Copy)]
+enum Kind {
+    Plain,
+    Unsigned,
+    Decimal { scale: u32 },
+    Date,
+    Time { per_second: i64 },
+    Timestamp { per_second: i64, is_utc: bool },
+    Text,
+    Uuid,
+    Binary,
+}
+
+impl Kind {
+    /// `INT96` columns hold timestamps with nanosecond precision, without a logical type.
+    const INT96: Kind = Kind::Timestamp {
+        per_second: 1_000_000_000,
+        is_utc: false,
+    };
+
+    fn new(parquet_type: &Type) -> Self {
+        let per_second = |unit: &TimeUnit| match unit {
+            TimeUnit::MILLIS(_) => 1_000,
+            TimeUnit::MICROS(_) => 1_000_000,
+            TimeUnit::NANOS(_) => 1_000_000_000,
+        };
+        let info = parquet_type.get_basic_info();
+        match (info.logical_type(), info.converted_type()) {
+            (Some(LogicalType::Decimal { scale, .. }), _) => Kind::Decimal {
+                scale: scale.try_into().unwrap(),
+            },
+            (_, ConvertedType::DECIMAL) => Kind::Decimal {
+                scale: parquet_type.get_scale().try_into().unwrap(),
+            },
+            (Some(LogicalType::Date), _) => Kind::Date,
+            (Some(LogicalType::Time { unit, .. }), _) => Kind::Time {
+                per_second: per_second(&unit),
+            },
+            (
+                Some(LogicalType::Timestamp {
+                    unit,
+                    is_adjusted_to_u_t_c,
+                }),
+                _,
+            ) => Kind::Timestamp {
+                per_second: per_second(&unit),
+                is_utc: is_adjusted_to_u_t_c,
+            },
+            (
+                Some(LogicalType::Integer {
+                    is_signed: false, ..
+                }),
+                _,
+            ) => Kind::Unsigned,
+            (Some(LogicalType::String | LogicalType::Json | LogicalType::Enum), _)
+            | (_, ConvertedType::UTF8 | ConvertedType::JSON | ConvertedType::ENUM) => Kind::Text,
+            (Some(LogicalType::Uuid), _) => Kind::Uuid,
+            _ => match parquet_type.get_physical_type() {
+                PhysicalType::BYTE_ARRAY | PhysicalType::FIXED_LEN_BYTE_ARRAY => Kind::Binary,
+                _ => Kind::Plain,
+            },
+        }
+    }
+
+    fn render_integer(self, value: i64, format: &DelimitedFormat) -> Result<String, Error> {
+        match self {
+            Kind::Decimal { scale } => Ok(render_decimal(value.into(), scale, format)),
+            Kind::Date => {
+                let date = NaiveDate::from_ymd_opt(1970, 1, 1)
+                    .unwrap()
+                    .checked_add_signed(chrono::Duration::days(value))
+                    .ok_or_else(|| anyhow!("Date out of range."))?;
+                format_value(date.format(&format.date_format))
+            }
+            Kind::Time { per_second } => {
+                let (seconds, nanos) = split(value, per_second);
+                let time = NaiveTime::from_num_seconds_from_midnight_opt(
+                    seconds.try_into().unwrap_or(u32::MAX),
+                    nanos,
+                )
+                .ok_or_else(|| anyhow!("Time out of range."))?;
+                format_value(time.format(&format.time_format))
+            }
+            Kind::Timestamp { per_second, is_utc } => {
+                let (seconds, nanos) = split(value, per_second);
+                let timestamp = DateTime::from_timestamp(seconds, nanos)
+                    .ok_or_else(|| anyhow!("Timestamp out of range."))?;
+                if is_utc {
+                    format_value(timestamp.format(&format.timestamp_format))
+                } else {
+                    format_value(timestamp.naive_utc().format(&format.timestamp_format))
+                }
+            }
+            _ => Ok(value.to_string()),
+        }
+    }
+
+    fn render_bytes(self, bytes: &[u8], format: &DelimitedFormat) -> Result<String, Error> {
+        let text = match self {
+            Kind::Decimal { scale } => {
+                let negative = bytes.first().is_some_and(|&byte| byte & 0x80 != 0);
+                let value = bytes
+                    .iter()
+                    .fold(if negative { -1i128 } else { 0 }, |acc, &byte| {
+                        (acc << 8) | byte as i128
+                    });
+                render_decimal(value, scale, format)
+            }
+            Kind::Text => String::from_utf8_lossy(bytes).into_owned(),
+            Kind::Uuid if bytes.len() == 16 => {
+                let hex = hex(bytes).to_lowercase();
+                format!(
+                    "{}-{}-{}-{}-{}",
+                    &hex[..8],
+                    &hex[8..12],
+                    &hex[12..16],
+                    &hex[16..20],
+                    &hex[20..]
+                )
+            }
+            _ if format.json.is_some() => base64(bytes),
+            _ => hex(bytes),
+        };
+        Ok(text)
+    }
+}
+
+fn render_int96(value: &Int96, format: &DelimitedFormat) -> Result<String, Error> {
+    let (seconds, nanos) = value.to_seconds_and_nanos();
+    let timestamp = DateTime::from_timestamp(seconds, nanos as u32)
+        .ok_or_else(|| anyhow!("Timestamp out of range."))?;
+    format_value(timestamp.naive_utc().format(&format.timestamp_format))
+}
+
+/// Renders a single value written into a column of type `parquet_type` as JSON, the same way
+/// `--format ndjson` does with its default options. E.g. dates are rendered as ISO 8601 strings and
+/// decimals as numbers with their scale.
+pub fn json_of_value(parquet_type: &Type, value: &ProfileValue) -> Result<String, Error> {
+    let json = JsonFormat {
+        decimal: JsonDecimal::Number,
+        nulls: JsonNulls::Null,
+    };
+    let format = DelimitedFormat {
+        delimiter: ',',
+        quote: CsvQuote::Necessary,
+        null: String::new(),
+        header: CsvHeader::None,
+        date_format: JsonFormat::DATE_FORMAT.to_owned(),
+        time_format: JsonFormat::TIME_FORMAT.to_owned(),
+        timestamp_format: JsonFormat::TIMESTAMP_FORMAT.to_owned(),
+        decimal_separator: '.',
+        json: Some(json),
+        compression: TextCompression::None,
+    };
+    let mut kind = Kind::new(parquet_type);
+    let text = match *value {
+        ProfileValue::Bool(value) => value.to_string(),
+        ProfileValue::I32(value) => match kind {
+            Kind::Unsigned => (value as u32).to_string(),
+            _ => kind.render_integer(value.into(), &format)?,
+        },
+        ProfileValue::I64(value) => match kind {
+            Kind::Unsigned => (value as u64).to_string(),
+            _ => kind.render_integer(value, &format)?,
+        },
+        ProfileValue::I96(value) => {
+            let mut int96 = Int96::new();
+            int96.set_data(value[0], value[1], value[2]);
+            kind = Kind::INT96;
+            render_int96(&int96, &format)?
+        }
+        ProfileValue::F32(value) => value.to_string(),
+        ProfileValue::F64(value) => value.to_string(),
+        ProfileValue::Bytes(bytes) => kind.render_bytes(bytes, &format)?,
+    };
+    Ok(json_value(kind, text, &json))
+}
+
+/// Splits a value in units of `1 / per_second` seconds into seconds and nanoseconds.
+fn split(value: i64, per_second: i64) -> (i64, u32) {
+    let nanos = value.rem_euclid(per_second) * (1_000_000_000 / per_second);
+    (value.div_euclid(per_second), nanos as u32)
+}
+
+fn render_decimal(value: i128, scale: u32, format: &DelimitedFormat) -> String {
+    let digits = value.unsigned_abs().to_string();
+    let scale = scale as usize;
+    let digits = format!("{digits:0>width$}", width = scale + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - scale);
+    let sign = if value < 0 { "-" } else { "" };
+    if fraction.is_empty() {
+        format!("{sign}{integer}")
+    } else {
+        format!("{sign}{integer}{}{fraction}", format.decimal_separator)
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        write!(hex, "{byte:02X}").unwrap();
+        hex
+    })
+}
+
+/// Standard base64 encoding with padding, as described in RFC 4648.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |group, (index, &byte)| {
+                group | (byte as u32) << (16 - 8 * index)
+            });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                text.push(ALPHABET[(group >> (18 - 6 * index) & 0x3f) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+/// Renders a value formatted by chrono. Fails, rather than panicking, if the format is invalid, or
+/// not applicable to the value, e.g. `%z` for timestamps without time zone.
+fn format_value(value: impl std::fmt::Display) -> Result<String, Error> {
+    let mut text = String::new();
+    write!(text, "{value}").map_err(|_| anyhow!("Invalid format for date, time or timestamp."))?;
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::enum_args::{CsvHeader, CsvQuote, JsonDecimal, JsonNulls};
+
+    use super::{
+        base64, json_value, render_decimal, write_json_line, write_line, DelimitedFormat,
+        JsonFormat, Kind, TextCompression,
+    };
+
+    fn csv() -> DelimitedFormat {
+        DelimitedFormat {
+            delimiter: ',',
+            quote: CsvQuote::Necessary,
+            null: String::new(),
+            header: CsvHeader::EveryFile,
+            date_format: "%Y-%m-%d".to_owned(),
+            time_format: "%H:%M:%S%.f".to_owned(),
+            timestamp_format: "%Y-%m-%d %H:%M:%S%.f".to_owned(),
+            decimal_separator: '.',
+            json: None,
+            compression: TextCompression::None,
+        }
+    }
+
+    #[test]
+    fn quote_as_in_rfc_4180() {
+        let mut line = String::new();
+        let values = [
+            Some("plain"),
+            Some("a,b"),
+            Some("say \"hi\""),
+            Some("two\nlines"),
+            Some(""),
+            None,
+        ];
+        write_line(&mut line, values.into_iter(), &csv());
+        assert_eq!(
+            "plain,\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\",\"\",\n",
+            line
+        );
+    }
+
+    #[test]
+    fn render_decimals() {
+        let mut format = csv();
+        assert_eq!("-0.05", render_decimal(-5, 2, &format));
+        assert_eq!("123", render_decimal(123, 0, &format));
+        format.decimal_separator = ',';
+        assert_eq!("12,345", render_decimal(12345, 3, &format));
+    }
+
+    #[test]
+    fn render_json_objects() {
+        let mut json = JsonFormat {
+            decimal: JsonDecimal::Number,
+            nulls: JsonNulls::Null,
+        };
+        let decimal = Kind::Decimal { scale: 2 };
+        assert_eq!("12.30", json_value(decimal, "12.30".to_owned(), &json));
+        assert_eq!("\"NaN\"", json_value(Kind::Plain, "NaN".to_owned(), &json));
+        let utc = Kind::Timestamp {
+            per_second: 1_000,
+            is_utc: true,
+        };
+        assert_eq!(
+            "\"2024-01-15T10:30:00Z\"",
+            json_value(utc, "2024-01-15T10:30:00".to_owned(), &json)
+        );
+
+        let keys = ["\"a\"".to_owned(), "\"b\"".to_owned(), "\"c\"".to_owned()];
+        let values = [Some("1"), None, Some("\"x\"")];
+        let mut line = String::new();
+        write_json_line(&mut line, &keys, values.into_iter(), &json);
+        json.nulls = JsonNulls::Omit;
+        json.decimal = JsonDecimal::String;
+        write_json_line(&mut line, &keys, values.into_iter(), &json);
+        assert_eq!(
+            "{\"a\":1,\"b\":null,\"c\":\"x\"}\n{\"a\":1,\"c\":\"x\"}\n",
+            line
+        );
+        assert_eq!("\"12.30\"", json_value(decimal, "12.30".to_owned(), &json));
+    }
+
+    #[test]
+    fn encode_base64() {
+        assert_eq!("", base64(b""));
+        assert_eq!("Zg==", base64(b"f"));
+        assert_eq!("Zm8=", base64(b"fo"));
+        assert_eq!("Zm9v", base64(b"foo"));
+        assert_eq!("Zm9vYmFy", base64(b"foobar"));
+        assert_eq!("/w==", base64(&[0xff]));
+    }
+}
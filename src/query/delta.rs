@@ -0,0 +1,831 @@
+use std::{
+    cmp::Ordering,
+    collections::{hash_map::DefaultHasher, HashMap},
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    io::BufWriter,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{anyhow, bail, Context, Error};
+use log::info;
+use parquet::{
+    basic::{LogicalType, Repetition, Type as PhysicalType},
+    data_type::ByteArray,
+    file::{
+        properties::WriterProperties,
+        reader::{FileReader, SerializedFileReader},
+        writer::SerializedFileWriter,
+    },
+    schema::types::{SchemaDescriptor, Type},
+};
+use tempfile::NamedTempFile;
+
+use super::{
+    client_sort::{cmp_rows, write_row_group, Cell, RowGroup, SortKey, Value},
+    parquet_writer::{OutputSize, ParquetOutput, WrittenRowGroup},
+    table_strategy::ColumnExporter,
+};
+
+/// Rows written into one row group of the output at most.
+const DELTA_ROWS: usize = 65536;
+
+/// Marks a row inserted since the previous export.
+pub const OP_INSERT: &str = "I";
+/// Marks a row whose values changed since the previous export.
+pub const OP_UPDATE: &str = "U";
+/// Marks a key which is missing in the current export.
+pub const OP_DELETE: &str = "D";
+
+/// Arguments of `--delta-against`.
+#[derive(Clone, Debug)]
+pub struct DeltaAgainst {
+    /// Parquet file holding a full previous export of the same query.
+    pub previous: PathBuf,
+    /// Columns identifying a row.
+    pub key_columns: Vec<String>,
+    /// Name of the column holding [`OP_INSERT`], [`OP_UPDATE`] or [`OP_DELETE`].
+    pub op_column: String,
+    /// Both exports are sorted by the key columns, so they can be compared in a single pass
+    /// without holding the keys in memory.
+    pub sorted: bool,
+}
+
+/// Reduces the output to the rows which changed compared to a previous export, once all rows are
+/// written. Rows are identified by their key columns and compared using a hash of all other
+/// columns. The output is rewritten with each column except the keys declared `OPTIONAL` and an
+/// additional column marking each row as inserted, updated or deleted. Deleted keys are written
+/// as rows, whose other columns are NULL.
+pub struct Delta {
+    output: Box<dyn ParquetOutput>,
+    path: PathBuf,
+    schema: Arc<Type>,
+    properties: Arc<WriterProperties>,
+    delta: DeltaAgainst,
+}
+
+impl Delta {
+    pub fn new(
+        output: Box<dyn ParquetOutput>,
+        path: PathBuf,
+        schema: Arc<Type>,
+        properties: Arc<WriterProperties>,
+        delta: DeltaAgainst,
+    ) -> Result<Self, Error> {
+        // Fail before anything is fetched, if the options do not match the schema.
+        delta_schema(&schema, &delta)?;
+        Ok(Self {
+            output,
+            path,
+            schema,
+            properties,
+            delta,
+        })
+    }
+}
+
+impl ParquetOutput for Delta {
+    fn write_row_group(
+        &mut self,
+        num_batch: u32,
+        column_exporter: &mut ColumnExporter,
+    ) -> Result<WrittenRowGroup, Error> {
+        self.output.write_row_group(num_batch, column_exporter)
+    }
+
+    fn close(self) -> Result<OutputSize, Error> {
+        let mut written = self.output.close_box()?;
+        let old_size = fs::metadata(&self.path)?.len();
+        rewrite(&self.path, &self.schema, self.properties, &self.delta).with_context(|| {
+            format!(
+                "Could not compare '{}' with previous export '{}'.",
+                self.path.display(),
+                self.delta.previous.display()
+            )
+        })?;
+        written.num_bytes = written.num_bytes - old_size + fs::metadata(&self.path)?.len();
+        Ok(written)
+    }
+
+    fn close_box(self: Box<Self>) -> Result<OutputSize, Error> {
+        self.close()
+    }
+}
+
+/// Schema of the output. Each column of `schema` except the keys is declared `OPTIONAL`, so it can
+/// be NULL for deleted rows, followed by the required op column.
+fn delta_schema(schema: &Type, delta: &DeltaAgainst) -> Result<Type, Error> {
+    let fields = schema.get_fields();
+    for key in &delta.key_columns {
+        if !fields.iter().any(|field| field.name() == key) {
+            bail!("Key column '{key}' of `--delta-against` is not part of the result set.")
+        }
+    }
+    if fields.iter().any(|field| field.name() == delta.op_column) {
+        bail!(
+            "The result set already has a column named '{}'. Choose another name with \
+            `--delta-op-column`.",
+            delta.op_column
+        )
+    }
+    let mut fields = fields
+        .iter()
+        .map(|field| {
+            let Type::PrimitiveType {
+                physical_type,
+                type_length,
+                scale,
+                precision,
+                ..
+            } = **field
+            else {
+                bail!(
+                    "Nested column '{}' is not supported by `--delta-against`.",
+                    field.name()
+                )
+            };
+            let info = field.get_basic_info();
+            let repetition = if delta.key_columns.iter().any(|key| key == field.name()) {
+                info.repetition()
+            } else {
+                Repetition::OPTIONAL
+            };
+            let id = info.has_id().then(|| info.id());
+            let field = Type::primitive_type_builder(info.name(), physical_type)
+                .with_repetition(repetition)
+                .with_logical_type(info.logical_type())
+                .with_converted_type(info.converted_type())
+                .with_length(type_length)
+                .with_scale(scale)
+                .with_precision(precision)
+                .with_id(id)
+                .build()?;
+            Ok(Arc::new(field))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    fields.push(Arc::new(
+        Type::primitive_type_builder(&delta.op_column, PhysicalType::BYTE_ARRAY)
+            .with_repetition(Repetition::REQUIRED)
+            .with_logical_type(Some(LogicalType::String))
+            .build()?,
+    ));
+    Ok(Type::group_type_builder(schema.name())
+        .with_fields(fields)
+        .build()?)
+}
+
+/// A row of the previous export, or of the current one, if its key is not part of the previous.
+struct Seen {
+    /// Values of the key columns, to write tombstones for keys missing in the current export.
+    key: Vec<Option<Value>>,
+    /// Hash of all other columns. `None` for rows only part of the current export.
+    hash: Option<u64>,
+    /// `true` once the key has been encountered in the current export.
+    is_current: bool,
+}
+
+/// Replaces the file at `path`, written with `schema`, with the rows which differ from the previous
+/// export.
+fn rewrite(
+    path: &Path,
+    schema: &Arc<Type>,
+    properties: Arc<WriterProperties>,
+    delta: &DeltaAgainst,
+) -> Result<(), Error> {
+    let descriptor = SchemaDescriptor::new(schema.clone());
+    let names: Vec<_> = descriptor.columns().iter().map(|c| c.name()).collect();
+    let is_key: Vec<bool> = names
+        .iter()
+        .map(|name| delta.key_columns.iter().any(|key| key == name))
+        .collect();
+
+    let (previous, positions) = open_previous(&delta.previous, &descriptor)?;
+    let current = SerializedFileReader::new(File::open(path)?)?;
+    let directory = path.parent().unwrap_or(Path::new("."));
+    let temporary = NamedTempFile::new_in(directory)?;
+    let delta_schema = Arc::new(delta_schema(schema, delta)?);
+    let mut changes = Changes {
+        is_optional: delta_schema
+            .get_fields()
+            .iter()
+            .map(|field| field.get_basic_info().repetition() == Repetition::OPTIONAL)
+            .collect(),
+        writer: SerializedFileWriter::new(
+            BufWriter::new(temporary.reopen()?),
+            delta_schema,
+            properties,
+        )?,
+        rows: Vec::new(),
+        num_inserted: 0,
+        num_updated: 0,
+        num_deleted: 0,
+    };
+    if delta.sorted {
+        // Key columns have been checked to be part of the schema.
+        let keys: Vec<_> = delta
+            .key_columns
+            .iter()
+            .map(|key| {
+                let column = names.iter().position(|name| name == key).unwrap();
+                SortKey::new(column, &descriptor)
+            })
+            .collect();
+        let sorted_rows = |reader, positions, export| SortedRows {
+            reader,
+            positions,
+            row_group: None,
+            next_row_group: 0,
+            keys: &keys,
+            last: None,
+            export,
+            names: &names,
+            is_key: &is_key,
+        };
+        merge_sorted(
+            sorted_rows(previous, Some(positions), "previous export"),
+            sorted_rows(current, None, "current export"),
+            &is_key,
+            &mut changes,
+        )?;
+    } else {
+        compare_with_keys_in_memory(
+            previous,
+            &positions,
+            &current,
+            &is_key,
+            &names,
+            &mut changes,
+        )?;
+    }
+    changes.finish()?;
+    temporary
+        .persist(path)
+        .with_context(|| format!("Could not replace '{}'.", path.display()))?;
+    Ok(())
+}
+
+/// Looks up each row of the current export in the keys of the previous one. Holds the keys of all
+/// rows of the previous export in memory, together with the keys of inserted rows. Inserted and
+/// updated rows keep their order, deleted keys follow at the end.
+fn compare_with_keys_in_memory(
+    previous: SerializedFileReader<File>,
+    positions: &[usize],
+    current: &SerializedFileReader<File>,
+    is_key: &[bool],
+    names: &[&str],
+    changes: &mut Changes,
+) -> Result<(), Error> {
+    let mut seen = read_previous(previous, positions, is_key)?;
+    for index in 0..current.num_row_groups() {
+        let rows = RowGroup::new(current.get_row_group(index)?)?.read_all()?;
+        for row in rows {
+            let values = values(row);
+            let (key, hash) = key_and_hash(values.iter(), is_key);
+            let op = match seen.get_mut(&key) {
+                Some(Seen {
+                    is_current: true, ..
+                }) => bail!(
+                    "Key {} is not unique.",
+                    describe_key(&values, is_key, names)
+                ),
+                Some(previous) => {
+                    previous.is_current = true;
+                    if previous.hash == Some(hash) {
+                        continue;
+                    }
+                    OP_UPDATE
+                }
+                None => {
+                    seen.insert(
+                        key,
+                        Seen {
+                            key: Vec::new(),
+                            hash: None,
+                            is_current: true,
+                        },
+                    );
+                    OP_INSERT
+                }
+            };
+            changes.push(values, op)?;
+        }
+        changes.flush()?;
+    }
+
+    // Deleted keys are written in the order of their encoding, so the output does not depend on the
+    // iteration order of the hash map.
+    let mut deleted: Vec<_> = seen
+        .into_iter()
+        .filter(|(_, seen)| !seen.is_current)
+        .collect();
+    deleted.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+    for (_, seen) in deleted {
+        let mut key = seen.key.into_iter();
+        let values = is_key
+            .iter()
+            .map(|&is_key| if is_key { key.next().unwrap() } else { None })
+            .collect();
+        changes.push(values, OP_DELETE)?;
+    }
+    Ok(())
+}
+
+/// Compares both exports in a single pass, like a merge join. Both must be sorted by the key
+/// columns. Holds only the next row of each export in memory. The output is sorted by the keys,
+/// too.
+fn merge_sorted(
+    mut previous: SortedRows,
+    mut current: SortedRows,
+    is_key: &[bool],
+    changes: &mut Changes,
+) -> Result<(), Error> {
+    let mut previous_row = previous.next_row()?;
+    let mut current_row = current.next_row()?;
+    loop {
+        let ordering = match (&previous_row, &current_row) {
+            (None, None) => break,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(previous_row), Some(current_row)) => {
+                cmp_rows(previous.keys, previous_row, current_row)
+            }
+        };
+        match ordering {
+            Ordering::Less => {
+                let values = values(previous_row.take().unwrap())
+                    .into_iter()
+                    .zip(is_key)
+                    .map(|(value, &is_key)| if is_key { value } else { None })
+                    .collect();
+                changes.push(values, OP_DELETE)?;
+                previous_row = previous.next_row()?;
+            }
+            Ordering::Greater => {
+                changes.push(values(current_row.take().unwrap()), OP_INSERT)?;
+                current_row = current.next_row()?;
+            }
+            Ordering::Equal => {
+                let previous_values = values(previous_row.take().unwrap());
+                let current_values = values(current_row.take().unwrap());
+                let (_, previous_hash) = key_and_hash(previous_values.iter(), is_key);
+                let (_, current_hash) = key_and_hash(current_values.iter(), is_key);
+                if previous_hash != current_hash {
+                    changes.push(current_values, OP_UPDATE)?;
+                }
+                previous_row = previous.next_row()?;
+                current_row = current.next_row()?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Rows of an export, which must be sorted by the key columns with each key occurring once. Reads
+/// one chunk of a row group at a time.
+struct SortedRows<'a> {
+    reader: SerializedFileReader<File>,
+    /// Position of each column of the current export within the rows of `reader`, if they differ.
+    positions: Option<Vec<usize>>,
+    row_group: Option<RowGroup>,
+    next_row_group: usize,
+    keys: &'a [SortKey],
+    /// Row returned last, to verify the order of the keys.
+    last: Option<Vec<Cell>>,
+    /// Names the export in error messages.
+    export: &'static str,
+    names: &'a [&'a str],
+    is_key: &'a [bool],
+}
+
+impl SortedRows<'_> {
+    fn next_row(&mut self) -> Result<Option<Vec<Cell>>, Error> {
+        let row = loop {
+            if let Some(row_group) = &mut self.row_group {
+                if let Some(row) = row_group.next_row()? {
+                    break row;
+                }
+            }
+            if self.next_row_group == self.reader.num_row_groups() {
+                return Ok(None);
+            }
+            self.row_group = Some(RowGroup::new(
+                self.reader.get_row_group(self.next_row_group)?,
+            )?);
+            self.next_row_group += 1;
+        };
+        let row = match &self.positions {
+            Some(positions) => {
+                let mut row: Vec<_> = row.into_iter().map(Some).collect();
+                positions
+                    .iter()
+                    .map(|&position| row[position].take().unwrap())
+                    .collect()
+            }
+            None => row,
+        };
+        if let Some(last) = &self.last {
+            let describe = |row: &[Cell]| {
+                let values: Vec<_> = row.iter().map(|cell| cell.value.clone()).collect();
+                describe_key(&values, self.is_key, self.names)
+            };
+            match cmp_rows(self.keys, last, &row) {
+                Ordering::Less => (),
+                Ordering::Equal => {
+                    bail!(
+                        "Key {} is not unique in the {}.",
+                        describe(&row),
+                        self.export
+                    )
+                }
+                Ordering::Greater => bail!(
+                    "The {} is not sorted by the key columns. Key {} follows key {}. \
+                    `--delta-sorted` requires both exports to be ordered by the columns of \
+                    `--key-column`.",
+                    self.export,
+                    describe(&row),
+                    describe(last)
+                ),
+            }
+        }
+        self.last = Some(row.clone());
+        Ok(Some(row))
+    }
+}
+
+/// Rows of the output, written in row groups of at most [`DELTA_ROWS`] rows.
+struct Changes {
+    writer: SerializedFileWriter<BufWriter<File>>,
+    /// Nullability of each column of the delta schema.
+    is_optional: Vec<bool>,
+    /// Rows which have not been written yet.
+    rows: Vec<Vec<Cell>>,
+    num_inserted: usize,
+    num_updated: usize,
+    num_deleted: usize,
+}
+
+impl Changes {
+    fn push(&mut self, values: Vec<Option<Value>>, op: &str) -> Result<(), Error> {
+        match op {
+            OP_INSERT => self.num_inserted += 1,
+            OP_UPDATE => self.num_updated += 1,
+            _ => self.num_deleted += 1,
+        }
+        self.rows.push(delta_row(values, op, &self.is_optional));
+        if self.rows.len() == DELTA_ROWS {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the pending rows as a row group, if any.
+    fn flush(&mut self) -> Result<(), Error> {
+        if !self.rows.is_empty() {
+            write_row_group(&mut self.writer, &self.rows)?;
+            self.rows.clear();
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<(), Error> {
+        self.flush()?;
+        info!(
+            "Compared with previous export: {} rows inserted, {} updated, {} deleted.",
+            self.num_inserted, self.num_updated, self.num_deleted
+        );
+        self.writer.into_inner()?.into_inner()?;
+        Ok(())
+    }
+}
+
+/// Opens the previous export and matches its columns with the leaf columns of `descriptor` by
+/// name. They must have the same physical type. Returns the position of each leaf column within the
+/// previous export.
+fn open_previous(
+    path: &Path,
+    descriptor: &SchemaDescriptor,
+) -> Result<(SerializedFileReader<File>, Vec<usize>), Error> {
+    let file = File::open(path).with_context(|| format!("Could not open '{}'.", path.display()))?;
+    let reader = SerializedFileReader::new(file)?;
+    let previous = reader.metadata().file_metadata().schema_descr();
+    let positions = descriptor
+        .columns()
+        .iter()
+        .map(|column| {
+            let position = previous
+                .columns()
+                .iter()
+                .position(|previous| previous.name() == column.name())
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Column '{}' is missing in the previous export.",
+                        column.name()
+                    )
+                })?;
+            if previous.column(position).physical_type() != column.physical_type() {
+                bail!(
+                    "Column '{}' has a different type in the previous export.",
+                    column.name()
+                )
+            }
+            Ok(position)
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    Ok((reader, positions))
+}
+
+/// Keys and hashes of the rows of the previous export. `positions` holds the position of each
+/// column of the current export within its rows.
+fn read_previous(
+    reader: SerializedFileReader<File>,
+    positions: &[usize],
+    is_key: &[bool],
+) -> Result<HashMap<Vec<u8>, Seen>, Error> {
+    let mut seen = HashMap::new();
+    for index in 0..reader.num_row_groups() {
+        for row in RowGroup::new(reader.get_row_group(index)?)?.read_all()? {
+            let mut row: Vec<_> = row.into_iter().map(|cell| Some(cell.value)).collect();
+            // Columns in the order of the current export.
+            let values: Vec<_> = positions
+                .iter()
+                .map(|&position| row[position].take().unwrap())
+                .collect();
+            let (key, hash) = key_and_hash(values.iter(), is_key);
+            let key_values = values
+                .into_iter()
+                .zip(is_key)
+                .filter_map(|(value, &is_key)| is_key.then_some(value))
+                .collect();
+            let previous = Seen {
+                key: key_values,
+                hash: Some(hash),
+                is_current: false,
+            };
+            if seen.insert(key, previous).is_some() {
+                bail!("Keys of the previous export are not unique.")
+            }
+        }
+    }
+    Ok(seen)
+}
+
+/// Values of the cells of `row`.
+fn values(row: Vec<Cell>) -> Vec<Option<Value>> {
+    row.into_iter().map(|cell| cell.value).collect()
+}
+
+/// Encoding of the key columns, used to look up rows, and hash of all other columns.
+fn key_and_hash<'a>(
+    values: impl Iterator<Item = &'a Option<Value>>,
+    is_key: &[bool],
+) -> (Vec<u8>, u64) {
+    let mut key = Vec::new();
+    let mut hasher = DefaultHasher::new();
+    for (value, &is_key) in values.zip(is_key) {
+        if is_key {
+            encode(value, &mut key);
+        } else {
+            let mut bytes = Vec::new();
+            encode(value, &mut bytes);
+            bytes.hash(&mut hasher);
+        }
+    }
+    (key, hasher.finish())
+}
+
+/// Appends a representation of `value` to `out`, which differs for different values of the same
+/// column.
+fn encode(value: &Option<Value>, out: &mut Vec<u8>) {
+    let Some(value) = value else {
+        out.push(0);
+        return;
+    };
+    out.push(1);
+    match value {
+        Value::Bool(value) => out.push(u8::from(*value)),
+        Value::Int32(value) => out.extend(value.to_le_bytes()),
+        Value::Int64(value) => out.extend(value.to_le_bytes()),
+        Value::Int96(value) => {
+            for part in value.data() {
+                out.extend(part.to_le_bytes())
+            }
+        }
+        Value::Float(value) => out.extend(value.to_bits().to_le_bytes()),
+        Value::Double(value) => out.extend(value.to_bits().to_le_bytes()),
+        Value::Bytes(value) => {
+            out.extend((value.len() as u64).to_le_bytes());
+            out.extend(value.data());
+        }
+        Value::FixedLenBytes(value) => out.extend(value.data()),
+    }
+}
+
+/// Human readable key of a row for error messages.
+fn describe_key(values: &[Option<Value>], is_key: &[bool], names: &[&str]) -> String {
+    values
+        .iter()
+        .zip(is_key)
+        .zip(names)
+        .filter(|((_, &is_key), _)| is_key)
+        .map(|((value, _), name)| match value {
+            None => format!("{name}=NULL"),
+            Some(Value::Bool(value)) => format!("{name}={value}"),
+            Some(Value::Int32(value)) => format!("{name}={value}"),
+            Some(Value::Int64(value)) => format!("{name}={value}"),
+            Some(Value::Bytes(value)) => {
+                format!("{name}='{}'", String::from_utf8_lossy(value.data()))
+            }
+            Some(value) => format!("{name}={value:?}"),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Row of the output, with definition levels matching the nullability of the delta schema.
+fn delta_row(values: Vec<Option<Value>>, op: &str, is_optional: &[bool]) -> Vec<Cell> {
+    values
+        .into_iter()
+        .chain([Some(Value::Bytes(ByteArray::from(op)))])
+        .zip(is_optional)
+        .map(|(value, &is_optional)| Cell {
+            def_level: i16::from(is_optional && value.is_some()),
+            value,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, path::Path, sync::Arc};
+
+    use parquet::{
+        basic::{Repetition, Type as PhysicalType},
+        data_type::Int32Type,
+        file::{
+            properties::WriterProperties,
+            reader::{FileReader, SerializedFileReader},
+            writer::SerializedFileWriter,
+        },
+        record::Field,
+        schema::types::Type,
+    };
+    use tempfile::tempdir;
+
+    use super::{rewrite, DeltaAgainst};
+
+    fn schema() -> Arc<Type> {
+        let column = |name| {
+            Arc::new(
+                Type::primitive_type_builder(name, PhysicalType::INT32)
+                    .with_repetition(Repetition::REQUIRED)
+                    .build()
+                    .unwrap(),
+            )
+        };
+        Arc::new(
+            Type::group_type_builder("schema")
+                .with_fields(vec![column("id"), column("a")])
+                .build()
+                .unwrap(),
+        )
+    }
+
+    /// Writes `ids` and `values` into a file with [`schema`], one row group per element of
+    /// `row_groups`, which holds the number of rows of each.
+    fn write(path: &Path, ids: &[i32], values: &[i32], row_groups: &[usize]) {
+        let properties = Arc::new(WriterProperties::builder().build());
+        let mut writer =
+            SerializedFileWriter::new(File::create(path).unwrap(), schema(), properties).unwrap();
+        let mut offset = 0;
+        for &num_rows in row_groups {
+            let mut row_group_writer = writer.next_row_group().unwrap();
+            for column in [ids, values] {
+                let mut column_writer = row_group_writer.next_column().unwrap().unwrap();
+                column_writer
+                    .typed::<Int32Type>()
+                    .write_batch(&column[offset..offset + num_rows], None, None)
+                    .unwrap();
+                column_writer.close().unwrap();
+            }
+            row_group_writer.close().unwrap();
+            offset += num_rows;
+        }
+        writer.close().unwrap();
+    }
+
+    fn read(path: &Path) -> Vec<Vec<Field>> {
+        let reader = SerializedFileReader::new(File::open(path).unwrap()).unwrap();
+        reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| {
+                row.unwrap()
+                    .get_column_iter()
+                    .map(|(_name, field)| field.clone())
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    fn op(op: &str) -> Field {
+        Field::Str(op.to_owned())
+    }
+
+    #[test]
+    fn keep_changed_rows_and_mark_deleted_keys() {
+        let schema = schema();
+        let properties = Arc::new(WriterProperties::builder().build());
+        let dir = tempdir().unwrap();
+        let previous = dir.path().join("previous.par");
+        let current = dir.path().join("current.par");
+        // 1 is unchanged, 2 updated, 3 deleted and 4 inserted.
+        write(&previous, &[1, 2, 3], &[10, 20, 30], &[3]);
+        write(&current, &[1, 2, 4], &[10, 21, 40], &[3]);
+        let delta = DeltaAgainst {
+            previous,
+            key_columns: vec!["id".to_owned()],
+            op_column: "op".to_owned(),
+            sorted: false,
+        };
+
+        rewrite(&current, &schema, properties.clone(), &delta).unwrap();
+
+        assert_eq!(
+            vec![
+                vec![Field::Int(2), Field::Int(21), op("U")],
+                vec![Field::Int(4), Field::Int(40), op("I")],
+                vec![Field::Int(3), Field::Null, op("D")],
+            ],
+            read(&current)
+        );
+
+        // Keys must identify a single row.
+        write(&current, &[1, 1], &[10, 11], &[2]);
+        let error = rewrite(&current, &schema, properties, &delta).unwrap_err();
+        assert_eq!("Key id=1 is not unique.", error.to_string());
+    }
+
+    #[test]
+    fn merge_exports_sorted_by_key() {
+        let schema = schema();
+        let properties = Arc::new(WriterProperties::builder().build());
+        let dir = tempdir().unwrap();
+        let previous = dir.path().join("previous.par");
+        let current = dir.path().join("current.par");
+        // 1 is deleted, 2 unchanged, 3 updated, 4 inserted and 5 deleted. Row groups of both
+        // exports end at different keys.
+        write(&previous, &[1, 2, 3, 5], &[10, 20, 30, 50], &[1, 3]);
+        write(&current, &[2, 3, 4], &[20, 31, 40], &[2, 1]);
+        let delta = DeltaAgainst {
+            previous,
+            key_columns: vec!["id".to_owned()],
+            op_column: "op".to_owned(),
+            sorted: true,
+        };
+
+        rewrite(&current, &schema, properties.clone(), &delta).unwrap();
+
+        assert_eq!(
+            vec![
+                vec![Field::Int(1), Field::Null, op("D")],
+                vec![Field::Int(3), Field::Int(31), op("U")],
+                vec![Field::Int(4), Field::Int(40), op("I")],
+                vec![Field::Int(5), Field::Null, op("D")],
+            ],
+            read(&current)
+        );
+    }
+
+    #[test]
+    fn sorted_merge_fails_for_keys_out_of_order() {
+        let schema = schema();
+        let properties = Arc::new(WriterProperties::builder().build());
+        let dir = tempdir().unwrap();
+        let previous = dir.path().join("previous.par");
+        let current = dir.path().join("current.par");
+        write(&previous, &[1, 2, 3], &[10, 20, 30], &[3]);
+        write(&current, &[1, 3, 2], &[10, 30, 20], &[2, 1]);
+        let delta = DeltaAgainst {
+            previous,
+            key_columns: vec!["id".to_owned()],
+            op_column: "op".to_owned(),
+            sorted: true,
+        };
+
+        let error = rewrite(&current, &schema, properties.clone(), &delta).unwrap_err();
+        assert_eq!(
+            "The current export is not sorted by the key columns. Key id=2 follows key id=3. \
+            `--delta-sorted` requires both exports to be ordered by the columns of \
+            `--key-column`.",
+            error.to_string()
+        );
+
+        write(&current, &[1, 2, 2], &[10, 20, 21], &[3]);
+        let error = rewrite(&current, &schema, properties, &delta).unwrap_err();
+        assert_eq!(
+            "Key id=2 is not unique in the current export.",
+            error.to_string()
+        );
+    }
+}
@@ -0,0 +1,852 @@
+use anyhow::{anyhow, bail, Context, Error};
+use odbc_api::{
+    buffers::{AnySlice, BufferDesc, ColumnarAnyBuffer, NullableSlice},
+    sys::{Date, Time, Timestamp, NULL_DATA},
+};
+use parquet::{
+    basic::{LogicalType, Repetition, Type as PhysicalType},
+    column::writer::{get_typed_column_writer_mut, ColumnWriter},
+    data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type},
+    schema::types::Type,
+};
+
+use crate::parquet_buffer::ParquetBuffer;
+
+/// A column of the output computed from other columns of the same row, as specified by
+/// `--derive`. Parsed from `NAME=EXPR` or `NAME:TYPE=EXPR`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Derivation {
+    pub name: String,
+    /// Type of the column, if declared. Otherwise it is inferred from the expression.
+    declared_type: Option<DerivedType>,
+    expr: Expr<String>,
+}
+
+/// Parses `NAME=EXPR` or `NAME:TYPE=EXPR`, e.g. `full_name=first || ' ' || last`.
+pub fn derive_from_str(source: &str) -> Result<Derivation, Error> {
+    let (head, expr) = source
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Derived column must be specified as 'NAME=EXPRESSION'."))?;
+    let (name, declared_type) = match head.rsplit_once(':') {
+        Some((name, type_name)) => {
+            let declared_type = DerivedType::from_name(type_name.trim()).ok_or_else(|| {
+                anyhow!("Unknown type '{type_name}'. Supported are 'text', 'int64' and 'double'.")
+            })?;
+            (name, Some(declared_type))
+        }
+        None => (head, None),
+    };
+    let name = name.trim();
+    if name.is_empty() {
+        bail!("Name of derived column must not be empty.")
+    }
+    let expr = Parser::new(expr)?
+        .parse()
+        .with_context(|| format!("Invalid expression of derived column '{name}'."))?;
+    Ok(Derivation {
+        name: name.to_owned(),
+        declared_type,
+        expr,
+    })
+}
+
+impl Derivation {
+    /// Resolves the columns the expression refers to. `column` maps the name of a column of the
+    /// output to the index and description of the buffer it is fetched into.
+    pub fn bind(
+        &self,
+        column: impl Fn(&str) -> Option<(usize, BufferDesc)>,
+    ) -> Result<DerivedColumn, Error> {
+        let (expr, kind) = self
+            .expr
+            .bind(&column)
+            .with_context(|| format!("Can not compute derived column '{}'.", self.name))?;
+        let column_type = self.declared_type.unwrap_or(match kind {
+            Kind::Int => DerivedType::Int64,
+            Kind::Float => DerivedType::Double,
+            Kind::Text | Kind::Null => DerivedType::Text,
+        });
+        Ok(DerivedColumn {
+            name: self.name.clone(),
+            column_type,
+            expr,
+        })
+    }
+}
+
+/// A derived column, whose expression refers to the fetch buffer by index.
+pub struct DerivedColumn {
+    pub name: String,
+    column_type: DerivedType,
+    expr: Expr<usize>,
+}
+
+impl DerivedColumn {
+    /// Derived columns are always `OPTIONAL`, since NULL propagates through the expression.
+    pub fn parquet_type(&self) -> Type {
+        let (physical_type, logical_type) = match self.column_type {
+            DerivedType::Text => (PhysicalType::BYTE_ARRAY, Some(LogicalType::String)),
+            DerivedType::Int64 => (PhysicalType::INT64, None),
+            DerivedType::Double => (PhysicalType::DOUBLE, None),
+        };
+        Type::primitive_type_builder(&self.name, physical_type)
+            .with_logical_type(logical_type)
+            .with_repetition(Repetition::OPTIONAL)
+            .build()
+            .unwrap()
+    }
+
+    /// `true` if the expression refers to the fetch buffer with this index.
+    pub fn uses_buffer(&self, buffer_index: usize) -> bool {
+        let mut used = false;
+        self.expr
+            .visit_columns(&mut |index| used |= index == buffer_index);
+        used
+    }
+
+    /// Adjusts the buffer indices after the buffer with index `removed` has been dropped.
+    pub fn shift_buffer_indices(&mut self, removed: usize) {
+        self.expr.visit_columns_mut(&mut |index| {
+            if *index > removed {
+                *index -= 1
+            }
+        });
+    }
+
+    /// Evaluates the expression for each row of `buffer` and writes the results. All values are
+    /// NULL if `buffer` is `None`, i.e. for filler rows.
+    pub fn write(
+        &self,
+        pb: &mut ParquetBuffer,
+        column_writer: &mut ColumnWriter,
+        buffer: Option<&ColumnarAnyBuffer>,
+        num_rows: usize,
+    ) -> Result<(), Error> {
+        let values = (0..num_rows).map(|row| match buffer {
+            Some(buffer) => self.expr.eval(buffer, row),
+            None => Ok(Value::Null),
+        });
+        match self.column_type {
+            DerivedType::Text => {
+                let cw = get_typed_column_writer_mut::<ByteArrayType>(column_writer);
+                pb.write_optional_fallible(
+                    cw,
+                    values.map(|value| {
+                        Ok(value?
+                            .into_text()
+                            .map(|text| ByteArray::from(text.into_bytes())))
+                    }),
+                )
+            }
+            DerivedType::Int64 => {
+                let cw = get_typed_column_writer_mut::<Int64Type>(column_writer);
+                pb.write_optional_fallible(cw, values.map(|value| value?.into_int()))
+            }
+            DerivedType::Double => {
+                let cw = get_typed_column_writer_mut::<DoubleType>(column_writer);
+                pb.write_optional_fallible(cw, values.map(|value| value?.into_float()))
+            }
+        }
+    }
+}
+
+/// Type of a derived column in the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DerivedType {
+    Text,
+    Int64,
+    Double,
+}
+
+impl DerivedType {
+    fn from_name(name: &str) -> Option<Self> {
+        [
+            ("text", DerivedType::Text),
+            ("int64", DerivedType::Int64),
+            ("double", DerivedType::Double),
+        ]
+        .into_iter()
+        .find_map(|(candidate, ty)| name.eq_ignore_ascii_case(candidate).then_some(ty))
+    }
+}
+
+/// Type of an expression, inferred before any row is evaluated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// The literal `NULL`, which is compatible with every other kind.
+    Null,
+    Int,
+    Float,
+    Text,
+}
+
+/// Expression with column references of type `C`. These are names after parsing and buffer
+/// indices after binding.
+#[derive(Debug, Clone, PartialEq)]
+enum Expr<C> {
+    Literal(Value),
+    Column(C),
+    Negate(Box<Expr<C>>),
+    Binary(BinaryOp, Box<Expr<C>>, Box<Expr<C>>),
+    Call(Function, Vec<Expr<C>>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Concat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+    Int,
+    Float,
+    Text,
+    Coalesce,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        [
+            ("int", Function::Int),
+            ("float", Function::Float),
+            ("text", Function::Text),
+            ("coalesce", Function::Coalesce),
+        ]
+        .into_iter()
+        .find_map(|(candidate, function)| name.eq_ignore_ascii_case(candidate).then_some(function))
+    }
+}
+
+/// Value of an expression for a single row.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl Value {
+    pub fn into_text(self) -> Option<String> {
+        match self {
+            Value::Null => None,
+            Value::Int(i) => Some(i.to_string()),
+            Value::Float(f) => Some(f.to_string()),
+            Value::Text(text) => Some(text),
+        }
+    }
+
+    /// Numbers are passed through, text is parsed as integer or, failing that, as float.
+    fn into_number(self) -> Result<Value, Error> {
+        match self {
+            Value::Text(text) => {
+                let trimmed = text.trim();
+                if let Ok(i) = trimmed.parse() {
+                    Ok(Value::Int(i))
+                } else if let Ok(f) = trimmed.parse() {
+                    Ok(Value::Float(f))
+                } else {
+                    bail!("'{text}' is not a number.")
+                }
+            }
+            other => Ok(other),
+        }
+    }
+
+    /// Floats are rounded to the nearest integer.
+    pub fn into_int(self) -> Result<Option<i64>, Error> {
+        match self.into_number()? {
+            Value::Null => Ok(None),
+            Value::Int(i) => Ok(Some(i)),
+            Value::Float(f) => {
+                let rounded = f.round();
+                // `i64::MAX as f64` rounds up to 2^63, which is out of range already.
+                if rounded.is_nan() || rounded < i64::MIN as f64 || rounded >= i64::MAX as f64 {
+                    bail!("{f} is out of range for a 64-Bit integer.")
+                }
+                Ok(Some(rounded as i64))
+            }
+            Value::Text(_) => unreachable!("Text has been parsed as number"),
+        }
+    }
+
+    pub fn into_float(self) -> Result<Option<f64>, Error> {
+        match self.into_number()? {
+            Value::Null => Ok(None),
+            Value::Int(i) => Ok(Some(i as f64)),
+            Value::Float(f) => Ok(Some(f)),
+            Value::Text(_) => unreachable!("Text has been parsed as number"),
+        }
+    }
+}
+
+impl Expr<String> {
+    /// Resolves column names into buffer indices and infers the kind of the expression.
+    fn bind(
+        &self,
+        column: &impl Fn(&str) -> Option<(usize, BufferDesc)>,
+    ) -> Result<(Expr<usize>, Kind), Error> {
+        let bound = match self {
+            Expr::Literal(value) => {
+                let kind = match value {
+                    Value::Null => Kind::Null,
+                    Value::Int(_) => Kind::Int,
+                    Value::Float(_) => Kind::Float,
+                    Value::Text(_) => Kind::Text,
+                };
+                (Expr::Literal(value.clone()), kind)
+            }
+            Expr::Column(name) => {
+                let (index, desc) = column(name)
+                    .ok_or_else(|| anyhow!("Column '{name}' is not part of the result set."))?;
+                let kind = match desc {
+                    BufferDesc::I8 { .. }
+                    | BufferDesc::I16 { .. }
+                    | BufferDesc::I32 { .. }
+                    | BufferDesc::I64 { .. }
+                    | BufferDesc::U8 { .. }
+                    | BufferDesc::Bit { .. } => Kind::Int,
+                    BufferDesc::F32 { .. } | BufferDesc::F64 { .. } => Kind::Float,
+                    BufferDesc::Text { .. }
+                    | BufferDesc::WText { .. }
+                    | BufferDesc::Date { .. }
+                    | BufferDesc::Time { .. }
+                    | BufferDesc::Timestamp { .. } => Kind::Text,
+                    BufferDesc::Binary { .. } => {
+                        bail!("Binary column '{name}' can not be used in an expression.")
+                    }
+                };
+                (Expr::Column(index), kind)
+            }
+            Expr::Negate(operand) => {
+                let (operand, kind) = operand.bind(column)?;
+                let kind = match kind {
+                    Kind::Null | Kind::Int => Kind::Int,
+                    Kind::Float | Kind::Text => Kind::Float,
+                };
+                (Expr::Negate(Box::new(operand)), kind)
+            }
+            Expr::Binary(op, left, right) => {
+                let (left, left_kind) = left.bind(column)?;
+                let (right, right_kind) = right.bind(column)?;
+                let kind = match (op, left_kind, right_kind) {
+                    (BinaryOp::Concat, _, _) => Kind::Text,
+                    (_, Kind::Null | Kind::Int, Kind::Null | Kind::Int) => Kind::Int,
+                    _ => Kind::Float,
+                };
+                (Expr::Binary(*op, Box::new(left), Box::new(right)), kind)
+            }
+            Expr::Call(function, args) => {
+                let (args, kinds): (Vec<_>, Vec<_>) = args
+                    .iter()
+                    .map(|arg| arg.bind(column))
+                    .collect::<Result<Vec<_>, Error>>()?
+                    .into_iter()
+                    .unzip();
+                let kind = match function {
+                    Function::Int => Kind::Int,
+                    Function::Float => Kind::Float,
+                    Function::Text => Kind::Text,
+                    // The most general kind of all arguments.
+                    Function::Coalesce => [Kind::Text, Kind::Float, Kind::Int]
+                        .into_iter()
+                        .find(|kind| kinds.contains(kind))
+                        .unwrap_or(Kind::Null),
+                };
+                (Expr::Call(*function, args), kind)
+            }
+        };
+        Ok(bound)
+    }
+}
+
+impl Expr<usize> {
+    fn visit_columns(&self, f: &mut impl FnMut(usize)) {
+        match self {
+            Expr::Literal(_) => (),
+            Expr::Column(index) => f(*index),
+            Expr::Negate(operand) => operand.visit_columns(f),
+            Expr::Binary(_, left, right) => {
+                left.visit_columns(f);
+                right.visit_columns(f);
+            }
+            Expr::Call(_, args) => args.iter().for_each(|arg| arg.visit_columns(f)),
+        }
+    }
+
+    fn visit_columns_mut(&mut self, f: &mut impl FnMut(&mut usize)) {
+        match self {
+            Expr::Literal(_) => (),
+            Expr::Column(index) => f(index),
+            Expr::Negate(operand) => operand.visit_columns_mut(f),
+            Expr::Binary(_, left, right) => {
+                left.visit_columns_mut(f);
+                right.visit_columns_mut(f);
+            }
+            Expr::Call(_, args) => args.iter_mut().for_each(|arg| arg.visit_columns_mut(f)),
+        }
+    }
+
+    fn eval(&self, buffer: &ColumnarAnyBuffer, row: usize) -> Result<Value, Error> {
+        let value = match self {
+            Expr::Literal(value) => value.clone(),
+            Expr::Column(index) => column_value(buffer.column(*index), row),
+            Expr::Negate(operand) => match operand.eval(buffer, row)?.into_number()? {
+                Value::Int(i) => Value::Int(
+                    i.checked_neg()
+                        .ok_or_else(|| anyhow!("Negating {i} overflows."))?,
+                ),
+                Value::Float(f) => Value::Float(-f),
+                other => other,
+            },
+            Expr::Binary(op, left, right) => {
+                let left = left.eval(buffer, row)?;
+                let right = right.eval(buffer, row)?;
+                apply(*op, left, right)?
+            }
+            Expr::Call(function, args) => match function {
+                Function::Int => args[0]
+                    .eval(buffer, row)?
+                    .into_int()?
+                    .map_or(Value::Null, Value::Int),
+                Function::Float => args[0]
+                    .eval(buffer, row)?
+                    .into_float()?
+                    .map_or(Value::Null, Value::Float),
+                Function::Text => args[0]
+                    .eval(buffer, row)?
+                    .into_text()
+                    .map_or(Value::Null, Value::Text),
+                Function::Coalesce => {
+                    let mut value = Value::Null;
+                    for arg in args {
+                        value = arg.eval(buffer, row)?;
+                        if value != Value::Null {
+                            break;
+                        }
+                    }
+                    value
+                }
+            },
+        };
+        Ok(value)
+    }
+}
+
+/// Applies a binary operator. NULL operands yield NULL.
+fn apply(op: BinaryOp, left: Value, right: Value) -> Result<Value, Error> {
+    if left == Value::Null || right == Value::Null {
+        return Ok(Value::Null);
+    }
+    if op == BinaryOp::Concat {
+        let left = left.into_text().unwrap();
+        let right = right.into_text().unwrap();
+        return Ok(Value::Text(left + right.as_str()));
+    }
+    let value = match (left.into_number()?, right.into_number()?) {
+        (Value::Int(a), Value::Int(b)) => {
+            if b == 0 && matches!(op, BinaryOp::Divide | BinaryOp::Remainder) {
+                bail!("Division by zero.")
+            }
+            let result = match op {
+                BinaryOp::Add => a.checked_add(b),
+                BinaryOp::Subtract => a.checked_sub(b),
+                BinaryOp::Multiply => a.checked_mul(b),
+                BinaryOp::Divide => a.checked_div(b),
+                BinaryOp::Remainder => a.checked_rem(b),
+                BinaryOp::Concat => unreachable!(),
+            };
+            Value::Int(result.ok_or_else(|| anyhow!("Integer overflow computing {a} and {b}."))?)
+        }
+        (left, right) => {
+            let a = left.into_float()?.unwrap();
+            let b = right.into_float()?.unwrap();
+            if b == 0.0 && matches!(op, BinaryOp::Divide | BinaryOp::Remainder) {
+                bail!("Division by zero.")
+            }
+            Value::Float(match op {
+                BinaryOp::Add => a + b,
+                BinaryOp::Subtract => a - b,
+                BinaryOp::Multiply => a * b,
+                BinaryOp::Divide => a / b,
+                BinaryOp::Remainder => a % b,
+                BinaryOp::Concat => unreachable!(),
+            })
+        }
+    };
+    Ok(value)
+}
+
+/// Value of a column in the fetch buffer. Dates, times and timestamps are represented as ISO 8601
+/// text.
+pub fn column_value(slice: AnySlice, row: usize) -> Value {
+    let value = match slice {
+        AnySlice::Text(view) => view
+            .get(row)
+            .map(|bytes| Value::Text(String::from_utf8_lossy(bytes).into_owned())),
+        AnySlice::WText(view) => view
+            .get(row)
+            .map(|chars| Value::Text(String::from_utf16_lossy(chars))),
+        AnySlice::Date(values) => Some(date_text(&values[row])),
+        AnySlice::Time(values) => Some(time_text(&values[row])),
+        AnySlice::Timestamp(values) => Some(timestamp_text(&values[row])),
+        AnySlice::F64(values) => Some(Value::Float(values[row])),
+        AnySlice::F32(values) => Some(Value::Float(values[row].into())),
+        AnySlice::I8(values) => Some(Value::Int(values[row].into())),
+        AnySlice::I16(values) => Some(Value::Int(values[row].into())),
+        AnySlice::I32(values) => Some(Value::Int(values[row].into())),
+        AnySlice::I64(values) => Some(Value::Int(values[row])),
+        AnySlice::U8(values) => Some(Value::Int(values[row].into())),
+        AnySlice::Bit(values) => Some(Value::Int(values[row].as_bool().into())),
+        AnySlice::NullableDate(slice) => at(slice, row).map(date_text),
+        AnySlice::NullableTime(slice) => at(slice, row).map(time_text),
+        AnySlice::NullableTimestamp(slice) => at(slice, row).map(timestamp_text),
+        AnySlice::NullableF64(slice) => at(slice, row).map(|&f| Value::Float(f)),
+        AnySlice::NullableF32(slice) => at(slice, row).map(|&f| Value::Float(f.into())),
+        AnySlice::NullableI8(slice) => at(slice, row).map(|&i| Value::Int(i.into())),
+        AnySlice::NullableI16(slice) => at(slice, row).map(|&i| Value::Int(i.into())),
+        AnySlice::NullableI32(slice) => at(slice, row).map(|&i| Value::Int(i.into())),
+        AnySlice::NullableI64(slice) => at(slice, row).map(|&i| Value::Int(i)),
+        AnySlice::NullableU8(slice) => at(slice, row).map(|&i| Value::Int(i.into())),
+        AnySlice::NullableBit(slice) => at(slice, row).map(|b| Value::Int(b.as_bool().into())),
+        AnySlice::Binary(_) => unreachable!("Binary columns are rejected while binding"),
+    };
+    value.unwrap_or(Value::Null)
+}
+
+fn at<T>(slice: NullableSlice<'_, T>, row: usize) -> Option<&T> {
+    let (values, indicators) = slice.raw_values();
+    (indicators[row] != NULL_DATA).then(|| &values[row])
+}
+
+fn date_text(date: &Date) -> Value {
+    Value::Text(format!(
+        "{:04}-{:02}-{:02}",
+        date.year, date.month, date.day
+    ))
+}
+
+fn time_text(time: &Time) -> Value {
+    Value::Text(format!(
+        "{:02}:{:02}:{:02}",
+        time.hour, time.minute, time.second
+    ))
+}
+
+/// Fractional seconds are omitted if zero, otherwise written without trailing zeros.
+fn timestamp_text(ts: &Timestamp) -> Value {
+    let mut text = format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        ts.year, ts.month, ts.day, ts.hour, ts.minute, ts.second
+    );
+    if ts.fraction != 0 {
+        let fraction = format!("{:09}", ts.fraction);
+        text.push('.');
+        text.push_str(fraction.trim_end_matches('0'));
+    }
+    Value::Text(text)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(String),
+    /// String literal in single quotes.
+    Text(String),
+    Ident(String),
+    /// Identifier in double quotes. Always refers to a column, never to `NULL` or a function.
+    QuotedIdent(String),
+    Symbol(&'static str),
+}
+
+const SYMBOLS: &[&str] = &["||", "+", "-", "*", "/", "%", "(", ")", ","];
+
+/// Recursive descent parser. In order of increasing precedence: `||`, `+ -`, `* / %`, unary `-`.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(source: &str) -> Result<Self, Error> {
+        Ok(Parser {
+            tokens: tokenize(source)?,
+            pos: 0,
+        })
+    }
+
+    fn parse(mut self) -> Result<Expr<String>, Error> {
+        let expr = self.concat()?;
+        if let Some(token) = self.tokens.get(self.pos) {
+            bail!("Unexpected {token:?} after end of expression.")
+        }
+        Ok(expr)
+    }
+
+    fn eat(&mut self, symbol: &str) -> bool {
+        let matches = matches!(self.tokens.get(self.pos), Some(Token::Symbol(s)) if *s == symbol);
+        if matches {
+            self.pos += 1;
+        }
+        matches
+    }
+
+    fn binary_ops(
+        &mut self,
+        ops: &[(&str, BinaryOp)],
+        operand: fn(&mut Self) -> Result<Expr<String>, Error>,
+    ) -> Result<Expr<String>, Error> {
+        let mut expr = operand(self)?;
+        'outer: loop {
+            for &(symbol, op) in ops {
+                if self.eat(symbol) {
+                    expr = Expr::Binary(op, Box::new(expr), Box::new(operand(self)?));
+                    continue 'outer;
+                }
+            }
+            return Ok(expr);
+        }
+    }
+
+    fn concat(&mut self) -> Result<Expr<String>, Error> {
+        self.binary_ops(&[("||", BinaryOp::Concat)], Self::additive)
+    }
+
+    fn additive(&mut self) -> Result<Expr<String>, Error> {
+        self.binary_ops(
+            &[("+", BinaryOp::Add), ("-", BinaryOp::Subtract)],
+            Self::multiplicative,
+        )
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr<String>, Error> {
+        self.binary_ops(
+            &[
+                ("*", BinaryOp::Multiply),
+                ("/", BinaryOp::Divide),
+                ("%", BinaryOp::Remainder),
+            ],
+            Self::unary,
+        )
+    }
+
+    fn unary(&mut self) -> Result<Expr<String>, Error> {
+        if self.eat("-") {
+            Ok(Expr::Negate(Box::new(self.unary()?)))
+        } else if self.eat("+") {
+            self.unary()
+        } else {
+            self.primary()
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr<String>, Error> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| anyhow!("Unexpected end of expression."))?;
+        self.pos += 1;
+        let expr = match token {
+            Token::Number(digits) => Expr::Literal(parse_number(&digits)?),
+            Token::Text(text) => Expr::Literal(Value::Text(text)),
+            Token::QuotedIdent(name) => Expr::Column(name),
+            Token::Ident(name) if self.eat("(") => {
+                let function = Function::from_name(&name).ok_or_else(|| {
+                    anyhow!(
+                        "Unknown function '{name}'. Supported are 'int', 'float', 'text' and \
+                        'coalesce'."
+                    )
+                })?;
+                let mut args = Vec::new();
+                if !self.eat(")") {
+                    loop {
+                        args.push(self.concat()?);
+                        if self.eat(")") {
+                            break;
+                        }
+                        if !self.eat(",") {
+                            bail!("Expected ',' or ')' in arguments of '{name}'.")
+                        }
+                    }
+                }
+                let arity_ok = match function {
+                    Function::Coalesce => !args.is_empty(),
+                    _ => args.len() == 1,
+                };
+                if !arity_ok {
+                    bail!("Wrong number of arguments for function '{name}'.")
+                }
+                Expr::Call(function, args)
+            }
+            Token::Ident(name) if name.eq_ignore_ascii_case("NULL") => Expr::Literal(Value::Null),
+            Token::Ident(name) => Expr::Column(name),
+            Token::Symbol("(") => {
+                let expr = self.concat()?;
+                if !self.eat(")") {
+                    bail!("Missing closing parenthesis.")
+                }
+                expr
+            }
+            Token::Symbol(symbol) => bail!("Unexpected '{symbol}'."),
+        };
+        Ok(expr)
+    }
+}
+
+fn parse_number(digits: &str) -> Result<Value, Error> {
+    if digits.contains(['.', 'e', 'E']) {
+        Ok(Value::Float(digits.parse()?))
+    } else {
+        digits
+            .parse()
+            .map(Value::Int)
+            .map_err(|_| anyhow!("Integer literal {digits} is out of range."))
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '\'' || c == '"' {
+            chars.next();
+            // A quote is escaped by doubling it.
+            let mut text = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, q)) if q == c => {
+                        if chars.next_if(|&(_, next)| next == c).is_some() {
+                            text.push(c);
+                        } else {
+                            break;
+                        }
+                    }
+                    Some((_, other)) => text.push(other),
+                    None => bail!("Missing closing {c} of literal starting at position {start}."),
+                }
+            }
+            tokens.push(if c == '\'' {
+                Token::Text(text)
+            } else {
+                Token::QuotedIdent(text)
+            });
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut end = start;
+            let mut previous = ' ';
+            while let Some((index, next)) = chars.next_if(|&(_, next)| {
+                next.is_ascii_alphanumeric()
+                    || next == '.'
+                    || ((next == '+' || next == '-') && matches!(previous, 'e' | 'E'))
+            }) {
+                end = index + next.len_utf8();
+                previous = next;
+            }
+            tokens.push(Token::Number(source[start..end].to_owned()));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut end = start;
+            while let Some((index, next)) =
+                chars.next_if(|&(_, next)| next.is_alphanumeric() || next == '_' || next == '$')
+            {
+                end = index + next.len_utf8();
+            }
+            tokens.push(Token::Ident(source[start..end].to_owned()));
+        } else if let Some(&symbol) = SYMBOLS
+            .iter()
+            .find(|symbol| source[start..].starts_with(**symbol))
+        {
+            for _ in 0..symbol.len() {
+                chars.next();
+            }
+            tokens.push(Token::Symbol(symbol));
+        } else {
+            bail!("Unexpected character '{c}' at position {start}.")
+        }
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use odbc_api::buffers::{BufferDesc, ColumnarAnyBuffer};
+
+    use super::{derive_from_str, DerivedType, Kind, Value};
+
+    fn bind_kind(source: &str) -> Result<Kind, anyhow::Error> {
+        let derivation = derive_from_str(source)?;
+        let column = |name: &str| match name {
+            "first" | "last" => Some((0, BufferDesc::Text { max_str_len: 10 })),
+            "amount" => Some((1, BufferDesc::F64 { nullable: true })),
+            "id" => Some((2, BufferDesc::I32 { nullable: false })),
+            "blob" => Some((3, BufferDesc::Binary { length: 10 })),
+            _ => None,
+        };
+        derivation.expr.bind(&column).map(|(_, kind)| kind)
+    }
+
+    #[test]
+    fn parse_derivations() {
+        let derivation = derive_from_str("full_name=first || ' ' || last").unwrap();
+        assert_eq!("full_name", derivation.name);
+        assert_eq!(None, derivation.declared_type);
+
+        let derivation = derive_from_str("cents:int64=amount * 100").unwrap();
+        assert_eq!("cents", derivation.name);
+        assert_eq!(Some(DerivedType::Int64), derivation.declared_type);
+
+        assert!(derive_from_str("no_expression").is_err());
+        assert!(derive_from_str("=1").is_err());
+        assert!(derive_from_str("x:int32=1").is_err());
+        assert!(derive_from_str("x=(1 + 2").is_err());
+        assert!(derive_from_str("x=1 +").is_err());
+        assert!(derive_from_str("x='unterminated").is_err());
+        assert!(derive_from_str("x=upper(first)").is_err());
+        assert!(derive_from_str("x=int(1, 2)").is_err());
+    }
+
+    #[test]
+    fn infer_type_of_expression() {
+        assert_eq!(Kind::Text, bind_kind("x=first || ' ' || last").unwrap());
+        assert_eq!(Kind::Float, bind_kind("x=amount * 100").unwrap());
+        assert_eq!(Kind::Int, bind_kind("x=id * 100 + -1").unwrap());
+        assert_eq!(Kind::Int, bind_kind("x=int(amount * 100)").unwrap());
+        assert_eq!(Kind::Float, bind_kind("x=id / 2.0").unwrap());
+        assert_eq!(Kind::Text, bind_kind("x=coalesce(id, first)").unwrap());
+        assert_eq!(Kind::Int, bind_kind("x=coalesce(NULL, id)").unwrap());
+        assert_eq!(Kind::Text, bind_kind("x=\"first\"").unwrap());
+        assert!(bind_kind("x=missing + 1").is_err());
+        assert!(bind_kind("x=text(blob)").is_err());
+    }
+
+    #[test]
+    fn evaluate_expressions() {
+        let buffer = ColumnarAnyBuffer::from_descs(1, []);
+        let eval = |source: &str| {
+            let derivation = derive_from_str(&format!("x={source}")).unwrap();
+            let column = derivation.bind(|_| None).unwrap();
+            column.expr.eval(&buffer, 0)
+        };
+        assert_eq!(Value::Int(7), eval("1 + 2 * 3").unwrap());
+        assert_eq!(Value::Int(9), eval("(1 + 2) * 3").unwrap());
+        assert_eq!(Value::Int(2), eval("7 / 3").unwrap());
+        assert_eq!(Value::Float(3.5), eval("7 / 2.0").unwrap());
+        assert_eq!(Value::Int(-1), eval("-7 % 3 + 0").unwrap());
+        assert_eq!(Value::Text("a1".to_owned()), eval("'a' || 1").unwrap());
+        assert_eq!(Value::Text("it's".to_owned()), eval("'it''s'").unwrap());
+        assert_eq!(Value::Int(43), eval("'42' + 1").unwrap());
+        assert_eq!(Value::Int(3), eval("int(2.5)").unwrap());
+        assert_eq!(Value::Float(1e3), eval("float('1e3')").unwrap());
+        assert_eq!(Value::Null, eval("1 + NULL").unwrap());
+        assert_eq!(Value::Null, eval("'a' || NULL").unwrap());
+        assert_eq!(Value::Int(5), eval("coalesce(NULL, 5, 6)").unwrap());
+        assert!(eval("1 / 0").is_err());
+        assert!(eval("'abc' * 2").is_err());
+        assert!(eval("9223372036854775807 + 1").is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use std::mem::{size_of, transmute_copy};
+
+use anyhow::{bail, Error};
+use odbc_api::{
+    sys::{HDbc, Pointer, SqlReturn},
+    Connection,
+};
+
+/// `SQL_DRIVER_NAME`
+const DRIVER_NAME: u16 = 6;
+/// `SQL_DRIVER_VER`
+const DRIVER_VER: u16 = 7;
+/// `SQL_SEARCH_PATTERN_ESCAPE`
+const SEARCH_PATTERN_ESCAPE: u16 = 14;
+
+// `odbc-sys` does not list all information types in its `InfoType` enumeration, so we declare the
+// narrow variant of `SQLGetInfo` with a plain integer instead. The driver manager `odbc-sys` links
+// against exports it on all platforms.
+extern "system" {
+    fn SQLGetInfoA(
+        connection_handle: HDbc,
+        info_type: u16,
+        info_value: Pointer,
+        buffer_length: i16,
+        string_length: *mut i16,
+    ) -> SqlReturn;
+}
+
+// `odbc-api` does not hand out the handle of an open connection. Its `Connection` holds nothing but
+// the handle though, which we rely on in `connection_handle`.
+const _: () = assert!(size_of::<Connection>() == size_of::<HDbc>());
+
+/// Raw handle of `conn`. Only valid as long as `conn` is borrowed.
+fn connection_handle(conn: &Connection) -> HDbc {
+    // Safety: `Connection` has the size of its only field, which holds the handle. The handle is
+    // copied, ownership stays with `conn`.
+    unsafe { transmute_copy(conn) }
+}
+
+/// Value of a string information type of `SQLGetInfo`, e.g. `SQL_DRIVER_NAME`.
+fn info_string(conn: &Connection, info_type: u16) -> Result<String, Error> {
+    let mut buf = vec![0u8; 256];
+    loop {
+        let mut string_length = 0;
+        // Safety: `buf` outlives the call and its length is passed along.
+        let ret = unsafe {
+            SQLGetInfoA(
+                connection_handle(conn),
+                info_type,
+                buf.as_mut_ptr() as Pointer,
+                buf.len().try_into().unwrap(),
+                &mut string_length,
+            )
+        };
+        if ret != SqlReturn::SUCCESS && ret != SqlReturn::SUCCESS_WITH_INFO {
+            bail!("SQLGetInfo failed for information type {info_type}.")
+        }
+        let length = usize::try_from(string_length).unwrap_or(0);
+        // The terminating zero must fit, too.
+        if length < buf.len() {
+            buf.truncate(length);
+            return Ok(String::from_utf8_lossy(&buf).into_owned());
+        }
+        buf.resize(length + 1, 0);
+    }
+}
+
+/// Name and version of the ODBC driver `conn` has been opened with, e.g. `libmsodbcsql-17.10.so`
+/// and `17.10.0005`.
+pub fn driver_name_and_version(conn: &Connection) -> Result<(String, String), Error> {
+    Ok((
+        info_string(conn, DRIVER_NAME)?,
+        info_string(conn, DRIVER_VER)?,
+    ))
+}
+
+/// Character escaping the wildcards `%` and `_` in search patterns passed to catalog functions.
+/// Empty, if the driver does not support escaping them.
+pub fn search_pattern_escape(conn: &Connection) -> Result<String, Error> {
+    info_string(conn, SEARCH_PATTERN_ESCAPE)
+}
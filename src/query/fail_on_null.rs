@@ -0,0 +1,61 @@
+use anyhow::Error;
+use odbc_api::buffers::{AnySlice, BufferDesc};
+use parquet::{column::writer::ColumnWriter, schema::types::Type};
+
+use crate::parquet_buffer::ParquetBuffer;
+
+use super::column_strategy::ColumnStrategy;
+
+/// Wraps the strategy of a column, which is expected to be fully populated. Writing the column
+/// fails at the first NULL fetched from the data source.
+pub struct FailOnNull {
+    inner: Box<dyn ColumnStrategy>,
+}
+
+impl FailOnNull {
+    pub fn new(inner: Box<dyn ColumnStrategy>) -> Self {
+        Self { inner }
+    }
+}
+
+impl ColumnStrategy for FailOnNull {
+    fn parquet_type(&self, name: &str) -> Type {
+        self.inner.parquet_type(name)
+    }
+
+    fn buffer_desc(&self) -> BufferDesc {
+        self.inner.buffer_desc()
+    }
+
+    fn copy_odbc_to_parquet(
+        &self,
+        parquet_buffer: &mut ParquetBuffer,
+        column_writer: &mut ColumnWriter,
+        column_view: AnySlice,
+    ) -> Result<(), Error> {
+        parquet_buffer.fail_on_null = true;
+        let result = self
+            .inner
+            .copy_odbc_to_parquet(parquet_buffer, column_writer, column_view);
+        parquet_buffer.fail_on_null = false;
+        result
+    }
+
+    fn copy_odbc_to_parquet_with_key(
+        &self,
+        parquet_buffer: &mut ParquetBuffer,
+        column_writer: &mut ColumnWriter,
+        column_view: AnySlice,
+        key_view: AnySlice,
+    ) -> Result<(), Error> {
+        parquet_buffer.fail_on_null = true;
+        let result = self.inner.copy_odbc_to_parquet_with_key(
+            parquet_buffer,
+            column_writer,
+            column_view,
+            key_view,
+        );
+        parquet_buffer.fail_on_null = false;
+        result
+    }
+}
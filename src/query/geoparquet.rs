@@ -0,0 +1,96 @@
+use std::num::NonZeroUsize;
+
+use anyhow::{bail, Error};
+use odbc_api::DataType;
+use parquet::{basic::Repetition, data_type::ByteArrayType, schema::types::Type};
+
+use super::{binary::Binary, column_strategy::ColumnStrategy, stats_only::json_string};
+
+/// Version of the GeoParquet specification the `geo` metadata conforms to.
+const GEOPARQUET_VERSION: &str = "1.1.0";
+
+/// Strategy for a column of `--geometry-column`. The values are expected to be Well Known Binary
+/// already and are passed through as they are.
+pub fn geometry_strategy(
+    data_type: DataType,
+    name: &str,
+    repetition: Repetition,
+    apply_length_limit: impl FnOnce(Option<NonZeroUsize>) -> Result<usize, Error>,
+) -> Result<Box<dyn ColumnStrategy>, Error> {
+    match data_type {
+        DataType::Binary { length }
+        | DataType::Varbinary { length }
+        | DataType::LongVarbinary { length } => {
+            let length = apply_length_limit(length)?;
+            Ok(Box::new(Binary::<ByteArrayType>::new(repetition, length)))
+        }
+        other => bail!(
+            "Column '{name}' passed to `--geometry-column` must be binary, but is of type \
+            {other:?}. Select its Well Known Binary representation, e.g. `ST_AsBinary({name})` on \
+            PostGIS or `{name}.STAsBinary()` on Microsoft SQL Server."
+        ),
+    }
+}
+
+/// Value of the `geo` file metadata, describing the geometry columns as specified by GeoParquet.
+/// The first column is the primary one. Without `crs` the key is omitted, which readers interpret
+/// as longitude and latitude on WGS 84 (`OGC:CRS84`). Geometry types are not known in advance, so
+/// they are declared as an empty list, i.e. any type. `crs` is PROJJSON and embedded as it is.
+pub fn geo_metadata(schema: &Type, columns: &[String], crs: Option<&str>) -> Result<String, Error> {
+    for column in columns {
+        if !schema.get_fields().iter().any(|f| f.name() == column) {
+            bail!("Column '{column}' of `--geometry-column` is not part of the output.")
+        }
+    }
+    let Some(primary_column) = columns.first() else {
+        bail!("At least one geometry column is required for GeoParquet metadata.")
+    };
+    let crs = crs.map_or_else(String::new, |crs| format!(",\"crs\":{}", crs.trim()));
+    let columns: Vec<String> = columns
+        .iter()
+        .map(|column| {
+            format!(
+                "{}:{{\"encoding\":\"WKB\",\"geometry_types\":[]{crs}}}",
+                json_string(column)
+            )
+        })
+        .collect();
+    Ok(format!(
+        "{{\"version\":\"{GEOPARQUET_VERSION}\",\"primary_column\":{},\"columns\":{{{}}}}}",
+        json_string(primary_column),
+        columns.join(",")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use parquet::schema::parser::parse_message_type;
+
+    use super::geo_metadata;
+
+    #[test]
+    fn describe_geometry_columns() {
+        let schema = parse_message_type(
+            "message schema {
+                OPTIONAL INT32 id;
+                OPTIONAL BYTE_ARRAY geom;
+                OPTIONAL BYTE_ARRAY area;
+            }",
+        )
+        .unwrap();
+        let columns = ["geom".to_owned(), "area".to_owned()];
+
+        assert_eq!(
+            "{\"version\":\"1.1.0\",\"primary_column\":\"geom\",\"columns\":{\
+            \"geom\":{\"encoding\":\"WKB\",\"geometry_types\":[]},\
+            \"area\":{\"encoding\":\"WKB\",\"geometry_types\":[]}}}",
+            geo_metadata(&schema, &columns, None).unwrap()
+        );
+        assert_eq!(
+            "{\"version\":\"1.1.0\",\"primary_column\":\"geom\",\"columns\":{\
+            \"geom\":{\"encoding\":\"WKB\",\"geometry_types\":[],\"crs\":null}}}",
+            geo_metadata(&schema, &columns[..1], Some("null\n")).unwrap()
+        );
+        assert!(geo_metadata(&schema, &["id2".to_owned()], None).is_err());
+    }
+}
@@ -0,0 +1,179 @@
+use std::{
+    ffi::OsString,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error};
+
+/// Number of bytes covered by each checksum. Default of Hadoop's `file.bytes-per-checksum`.
+const BYTES_PER_CHECKSUM: usize = 512;
+
+/// Magic bytes at the start of a checksum file, identifying version 1 of the format.
+const VERSION_1: &[u8; 4] = b"crc\0";
+
+/// Passes everything written through to the inner writer. If enabled, it computes the checksums of
+/// Hadoop's `ChecksumFileSystem` along the way, so they can be written into a sidecar file, without
+/// reading the output again.
+pub struct ChecksumWriter<W> {
+    inner: W,
+    checksums: Option<HadoopChecksums>,
+}
+
+impl<W> ChecksumWriter<W> {
+    pub fn new(inner: W, emit_hadoop_crc: bool) -> Self {
+        Self {
+            inner,
+            checksums: emit_hadoop_crc.then(HadoopChecksums::default),
+        }
+    }
+
+    /// Checksums of everything written so far, if enabled.
+    pub fn checksums(&self) -> Option<&HadoopChecksums> {
+        self.checksums.as_ref()
+    }
+}
+
+impl<W: Write> Write for ChecksumWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let num_bytes = self.inner.write(buf)?;
+        if let Some(checksums) = &mut self.checksums {
+            checksums.update(&buf[..num_bytes]);
+        }
+        Ok(num_bytes)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// CRC32 checksums of each chunk of `BYTES_PER_CHECKSUM` bytes. Hadoop's `ChecksumFileSystem`
+/// reads version 1 checksum files with CRC32 (the polynomial of zlib), not CRC32C, which is only
+/// used by HDFS internally.
+#[derive(Default)]
+pub struct HadoopChecksums {
+    /// Checksums of completed chunks, big endian.
+    sums: Vec<u8>,
+    /// Checksum of the current chunk, before the final inversion.
+    crc: u32,
+    /// Number of bytes in the current chunk.
+    chunk_len: usize,
+}
+
+impl HadoopChecksums {
+    fn update(&mut self, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            if self.chunk_len == 0 {
+                self.crc = u32::MAX;
+            }
+            let take = bytes.len().min(BYTES_PER_CHECKSUM - self.chunk_len);
+            self.crc = crc32_update(self.crc, &bytes[..take]);
+            self.chunk_len += take;
+            bytes = &bytes[take..];
+            if self.chunk_len == BYTES_PER_CHECKSUM {
+                self.sums.extend_from_slice(&(!self.crc).to_be_bytes());
+                self.chunk_len = 0;
+            }
+        }
+    }
+
+    /// Content of the checksum file: The magic bytes `crc\0`, the number of bytes per checksum as
+    /// big endian 32-Bit integer, followed by the checksum of each chunk, including a trailing
+    /// partial one.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(12 + self.sums.len());
+        bytes.extend_from_slice(VERSION_1);
+        bytes.extend_from_slice(&(BYTES_PER_CHECKSUM as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.sums);
+        if self.chunk_len != 0 {
+            bytes.extend_from_slice(&(!self.crc).to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Write the checksum file for the file at `path`. It is named `.<file name>.crc` and placed
+    /// next to it, where Hadoop's `ChecksumFileSystem` expects it.
+    pub fn write_sidecar(&self, path: &Path) -> Result<(), Error> {
+        let crc_path = sidecar_path(path);
+        fs::write(&crc_path, self.to_bytes())
+            .with_context(|| format!("Could not write checksum file '{}'.", crc_path.display()))
+    }
+}
+
+pub fn sidecar_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(".crc");
+    path.with_file_name(name)
+}
+
+/// Table driven CRC32 with the reflected polynomial `0xEDB88320`, as used by zlib and Java's
+/// `CRC32`.
+fn crc32_update(mut crc: u32, bytes: &[u8]) -> u32 {
+    for &byte in bytes {
+        crc = CRC32_TABLE[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8);
+    }
+    crc
+}
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut crc = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[index] = crc;
+        index += 1;
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write, path::Path};
+
+    use super::{crc32_update, sidecar_path, ChecksumWriter};
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(0xCBF4_3926, !crc32_update(u32::MAX, b"123456789"));
+    }
+
+    #[test]
+    fn checksum_each_chunk_of_512_bytes() {
+        let content: Vec<u8> = (0..600).map(|i| (i % 251) as u8).collect();
+        let mut writer = ChecksumWriter::new(Vec::new(), true);
+        // Write in pieces, which do not align with the chunks.
+        for piece in content.chunks(100) {
+            writer.write_all(piece).unwrap();
+        }
+
+        let bytes = writer.checksums().unwrap().to_bytes();
+
+        let mut expected = b"crc\0\0\0\x02\x00".to_vec();
+        expected.extend_from_slice(&(!crc32_update(u32::MAX, &content[..512])).to_be_bytes());
+        expected.extend_from_slice(&(!crc32_update(u32::MAX, &content[512..])).to_be_bytes());
+        assert_eq!(expected, bytes);
+        assert_eq!(content, writer.inner);
+    }
+
+    #[test]
+    fn sidecar_is_hidden_file_next_to_output() {
+        assert_eq!(
+            Path::new("dir/.out.par.crc"),
+            sidecar_path(Path::new("dir/out.par"))
+        );
+    }
+}
@@ -13,12 +13,12 @@ use parquet::{
 
 use crate::parquet_buffer::{BufferedDataType, ParquetBuffer};
 
-use super::ColumnStrategy;
+use super::{column_strategy::nullable_values, ColumnStrategy};
 
 /// Copy identical optional data from ODBC to Parquet.
 pub struct IdenticalOptional<Pdt> {
     logical_type: Option<LogicalType>,
-    _parquet_data_type: PhantomData<Pdt>,
+    _parquet_data_type: PhantomData<fn() -> Pdt>,
 }
 
 /// Columnar fetch strategy to be applied if Parquet and Odbc value type are binary identical.
@@ -57,7 +57,7 @@ where
         column_writer: &mut ColumnWriter,
         column_view: AnySlice,
     ) -> Result<(), Error> {
-        let it = Pdt::T::as_nullable_slice(column_view).unwrap();
+        let it = nullable_values::<Pdt::T>(column_view, "values of the column type")?;
         let column_writer = get_typed_column_writer_mut::<Pdt>(column_writer);
         parquet_buffer.write_optional(column_writer, it.map(|opt_ref| opt_ref.copied()))?;
         Ok(())
@@ -68,7 +68,7 @@ where
 /// contain any NULLs.
 pub struct IdenticalRequired<Pdt> {
     logical_type: Option<LogicalType>,
-    _parquet_data_type: PhantomData<Pdt>,
+    _parquet_data_type: PhantomData<fn() -> Pdt>,
 }
 
 impl<Pdt> IdenticalRequired<Pdt> {
@@ -101,7 +101,7 @@ where
 
     fn copy_odbc_to_parquet(
         &self,
-        _parquet_buffer: &mut ParquetBuffer,
+        parquet_buffer: &mut ParquetBuffer,
         column_writer: &mut ColumnWriter,
         column_view: AnySlice,
     ) -> Result<(), Error> {
@@ -109,6 +109,11 @@ where
         // any gaps due to null, we can use the ODBC buffer directly to write the batch.
 
         let values = Pdt::T::as_slice(column_view).unwrap();
+        if let Some(profile) = parquet_buffer.profile.as_mut() {
+            for value in values {
+                profile.add(value.profile_value());
+            }
+        }
         let column_writer = get_typed_column_writer_mut::<Pdt>(column_writer);
         column_writer.write_batch(values, None, None)?;
         Ok(())
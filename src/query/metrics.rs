@@ -0,0 +1,115 @@
+use std::{fmt::Write as _, io::Write, path::Path, time::Duration};
+
+use anyhow::{Context, Error};
+use tempfile::NamedTempFile;
+
+use super::parquet_writer::OutputSize;
+
+/// Summary of a successful export, written in the Prometheus text format, so the textfile
+/// collector of the node exporter can pick it up.
+pub struct Metrics {
+    pub num_rows: u64,
+    pub duration: Duration,
+    pub output: OutputSize,
+}
+
+impl Metrics {
+    /// Write the metrics into `path`. The file is written next to `path` first and renamed
+    /// afterwards, so a scraper never sees a partially written file.
+    ///
+    /// * `job`: Value of the `job` label attached to each metric, if any.
+    pub fn write(&self, path: &Path, job: Option<&str>) -> Result<(), Error> {
+        let text = self.to_text(job);
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let context = || format!("Could not write metrics to '{}'.", path.display());
+        let mut file = NamedTempFile::new_in(dir).with_context(context)?;
+        file.write_all(text.as_bytes()).with_context(context)?;
+        file.persist(path).with_context(context)?;
+        Ok(())
+    }
+
+    fn to_text(&self, job: Option<&str>) -> String {
+        let labels = job
+            .map(|job| format!("{{job=\"{}\"}}", escape_label_value(job)))
+            .unwrap_or_default();
+        let metrics = [
+            (
+                "odbc2parquet_rows",
+                "Number of rows written into the output.",
+                self.num_rows.to_string(),
+            ),
+            (
+                "odbc2parquet_duration_seconds",
+                "Duration of the export, including connecting to the data source.",
+                format!("{:.3}", self.duration.as_secs_f64()),
+            ),
+            (
+                "odbc2parquet_bytes_written",
+                "Size of all files written, or of the stream written to standard out.",
+                self.output.num_bytes.to_string(),
+            ),
+            (
+                "odbc2parquet_files",
+                "Number of files written.",
+                self.output.num_files.to_string(),
+            ),
+        ];
+        let mut text = String::new();
+        for (name, help, value) in metrics {
+            // Each file describes a single run, so all metrics are gauges.
+            writeln!(
+                text,
+                "# HELP {name} {help}\n# TYPE {name} gauge\n{name}{labels} {value}"
+            )
+            .unwrap();
+        }
+        text
+    }
+}
+
+/// Label values are enclosed in double quotes, which must be escaped together with backslashes and
+/// line feeds.
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::query::parquet_writer::OutputSize;
+
+    use super::Metrics;
+
+    #[test]
+    fn render_metrics_in_text_format() {
+        let metrics = Metrics {
+            num_rows: 42,
+            duration: Duration::from_millis(1500),
+            output: OutputSize {
+                num_files: 2,
+                num_bytes: 1024,
+            },
+        };
+
+        let text = metrics.to_text(Some("nightly \"sales\""));
+
+        let expected = "# HELP odbc2parquet_rows Number of rows written into the output.
+# TYPE odbc2parquet_rows gauge
+odbc2parquet_rows{job=\"nightly \\\"sales\\\"\"} 42
+";
+        assert!(text.starts_with(expected));
+        assert!(
+            text.contains("\nodbc2parquet_duration_seconds{job=\"nightly \\\"sales\\\"\"} 1.500\n")
+        );
+        assert!(text.contains("\nodbc2parquet_bytes_written{job=\"nightly \\\"sales\\\"\"} 1024\n"));
+        assert!(text.contains("\nodbc2parquet_files{job=\"nightly \\\"sales\\\"\"} 2\n"));
+        assert!(metrics.to_text(None).contains("\nodbc2parquet_rows 42\n"));
+    }
+}
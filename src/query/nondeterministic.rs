@@ -0,0 +1,132 @@
+/// Functions whose result differs between executions of the same query, e.g. because they generate
+/// identifiers, read the clock or advance a sequence. Matched case insensitive against the words of
+/// the query.
+const NONDETERMINISTIC_FUNCTIONS: &[&str] = &[
+    // Microsoft SQL Server
+    "NEWID",
+    "NEWSEQUENTIALID",
+    "GETDATE",
+    "GETUTCDATE",
+    "SYSDATETIME",
+    "SYSUTCDATETIME",
+    "SYSDATETIMEOFFSET",
+    "CRYPT_GEN_RANDOM",
+    // Standard SQL
+    "CURRENT_TIMESTAMP",
+    "CURRENT_DATE",
+    "CURRENT_TIME",
+    "LOCALTIMESTAMP",
+    "LOCALTIME",
+    // PostgreSQL, MySQL and others
+    "NOW",
+    "RAND",
+    "RANDOM",
+    "UUID",
+    "UUID_SHORT",
+    "GEN_RANDOM_UUID",
+    "NEXTVAL",
+    "CLOCK_TIMESTAMP",
+    // Oracle
+    "SYSDATE",
+    "SYSTIMESTAMP",
+    "SYS_GUID",
+    "DBMS_RANDOM",
+];
+
+/// Name of the first nondeterministic function called by `query`, if any. `NEXT VALUE FOR` is
+/// reported as well. This is a heuristic: String literals, quoted identifiers and comments are
+/// ignored, but functions called within views or procedures can not be detected.
+pub fn nondeterministic_function(query: &str) -> Option<String> {
+    let words = words(query);
+    if let Some(word) = words.iter().find(|word| {
+        NONDETERMINISTIC_FUNCTIONS
+            .iter()
+            .any(|function| word.eq_ignore_ascii_case(function))
+    }) {
+        return Some(word.to_uppercase());
+    }
+    words
+        .windows(3)
+        .any(|window| {
+            window[0].eq_ignore_ascii_case("NEXT")
+                && window[1].eq_ignore_ascii_case("VALUE")
+                && window[2].eq_ignore_ascii_case("FOR")
+        })
+        .then(|| "NEXT VALUE FOR".to_owned())
+}
+
+/// Splits the query into unquoted words, skipping string literals, quoted identifiers and comments.
+/// Semicolons separating statements are returned as words of their own.
+pub fn words(query: &str) -> Vec<&str> {
+    let bytes = query.as_bytes();
+    let mut words = Vec::new();
+    let mut pos = 0;
+    let skip_until = |pos: usize, end: &str| {
+        query[pos..]
+            .find(end)
+            .map_or(query.len(), |offset| pos + offset + end.len())
+    };
+    while pos < bytes.len() {
+        pos = match bytes[pos] {
+            // An escaped quote within a literal, is seen as two adjacent literals.
+            b'\'' => skip_until(pos + 1, "'"),
+            b'"' => skip_until(pos + 1, "\""),
+            b'`' => skip_until(pos + 1, "`"),
+            b'[' => skip_until(pos + 1, "]"),
+            b'-' if bytes.get(pos + 1) == Some(&b'-') => skip_until(pos + 2, "\n"),
+            b'/' if bytes.get(pos + 1) == Some(&b'*') => skip_until(pos + 2, "*/"),
+            b';' => {
+                words.push(";");
+                pos + 1
+            }
+            byte if is_word_byte(byte) => {
+                let end = bytes[pos..]
+                    .iter()
+                    .position(|&byte| !is_word_byte(byte))
+                    .map_or(bytes.len(), |len| pos + len);
+                words.push(&query[pos..end]);
+                end
+            }
+            _ => pos + 1,
+        };
+    }
+    words
+}
+
+fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'$' || byte == b'#' || byte >= 0x80
+}
+
+#[cfg(test)]
+mod tests {
+    use super::nondeterministic_function;
+
+    #[test]
+    fn detect_nondeterministic_functions() {
+        assert_eq!(
+            Some("NEWID".to_owned()),
+            nondeterministic_function("SELECT newid() AS id, a FROM t")
+        );
+        assert_eq!(
+            Some("NEXT VALUE FOR".to_owned()),
+            nondeterministic_function("SELECT NEXT VALUE FOR dbo.seq, a FROM t")
+        );
+        assert_eq!(
+            Some("NEXTVAL".to_owned()),
+            nondeterministic_function("SELECT seq.nextval FROM dual")
+        );
+        assert_eq!(
+            None,
+            nondeterministic_function("SELECT a, b FROM t WHERE c > 5")
+        );
+    }
+
+    #[test]
+    fn ignore_literals_identifiers_and_comments() {
+        let query = "SELECT 'GETDATE()' AS \"now\", [rand], `uuid` -- NEWID()\n\
+            FROM t /* SYSDATE */ WHERE a = 'it''s'";
+        assert_eq!(None, nondeterministic_function(query));
+        // Part of a longer identifier
+        assert_eq!(None, nondeterministic_function("SELECT now_utc FROM t"));
+    }
+}
@@ -0,0 +1,312 @@
+use anyhow::{anyhow, bail, Context, Error};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use odbc_api::{
+    buffers::{AnySlice, BufferDesc},
+    sys::Timestamp,
+};
+use parquet::{
+    basic::{ConvertedType, LogicalType, Repetition, TimeUnit, Type as PhysicalType},
+    column::writer::ColumnWriter,
+    data_type::ByteArray,
+    schema::types::Type,
+};
+
+use crate::parquet_buffer::{twos_complement, FillValue, ParquetBuffer};
+
+use super::{
+    column_strategy::ColumnStrategy, decimal::parse_scaled, timestamp::timestamp_to_int96,
+};
+
+/// Wraps the strategy of a column, whose NULLs are replaced with a fill value. The column is
+/// written as `REQUIRED`. Without a fill value writing the column fails at the first NULL.
+pub struct NullFill {
+    inner: Box<dyn ColumnStrategy>,
+    fill: Option<FillValue>,
+}
+
+impl NullFill {
+    /// * `name`: Name of the column, used in error messages.
+    /// * `fill`: Fill value as specified by the user. Parsed according to the parquet type of the
+    ///   column.
+    pub fn new(inner: Box<dyn ColumnStrategy>, name: &str, fill: &str) -> Result<Self, Error> {
+        let fill = parse_fill_value(fill, &inner.parquet_type(name))
+            .with_context(|| format!("Invalid fill value for column '{name}'."))?;
+        Ok(Self {
+            inner,
+            fill: Some(fill),
+        })
+    }
+
+    /// Writes the column as `REQUIRED` without replacing its NULLs, as done by
+    /// `--force-required-all`.
+    pub fn required(inner: Box<dyn ColumnStrategy>) -> Self {
+        Self { inner, fill: None }
+    }
+
+    /// Runs `write` with the fill value, or if there is none, failing on NULL.
+    fn with_fill(
+        &self,
+        parquet_buffer: &mut ParquetBuffer,
+        write: impl FnOnce(&mut ParquetBuffer) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let fail_on_null = parquet_buffer.fail_on_null;
+        parquet_buffer.null_fill = self.fill.clone();
+        parquet_buffer.fail_on_null |= self.fill.is_none();
+        let result = write(parquet_buffer);
+        parquet_buffer.null_fill = None;
+        parquet_buffer.fail_on_null = fail_on_null;
+        result
+    }
+}
+
+impl ColumnStrategy for NullFill {
+    fn parquet_type(&self, name: &str) -> Type {
+        let optional = self.inner.parquet_type(name);
+        let info = optional.get_basic_info();
+        Type::primitive_type_builder(name, optional.get_physical_type())
+            .with_repetition(Repetition::REQUIRED)
+            .with_converted_type(info.converted_type())
+            .with_logical_type(info.logical_type())
+            .with_length(type_length(&optional))
+            .with_precision(optional.get_precision())
+            .with_scale(optional.get_scale())
+            .build()
+            .unwrap()
+    }
+
+    fn buffer_desc(&self) -> BufferDesc {
+        self.inner.buffer_desc()
+    }
+
+    fn copy_odbc_to_parquet(
+        &self,
+        parquet_buffer: &mut ParquetBuffer,
+        column_writer: &mut ColumnWriter,
+        column_view: AnySlice,
+    ) -> Result<(), Error> {
+        self.with_fill(parquet_buffer, |pb| {
+            self.inner
+                .copy_odbc_to_parquet(pb, column_writer, column_view)
+        })
+    }
+
+    fn copy_odbc_to_parquet_with_key(
+        &self,
+        parquet_buffer: &mut ParquetBuffer,
+        column_writer: &mut ColumnWriter,
+        column_view: AnySlice,
+        key_view: AnySlice,
+    ) -> Result<(), Error> {
+        self.with_fill(parquet_buffer, |pb| {
+            self.inner
+                .copy_odbc_to_parquet_with_key(pb, column_writer, column_view, key_view)
+        })
+    }
+}
+
+/// Parses the fill value according to the physical and logical type of the column. E.g. dates are
+/// expected as `YYYY-MM-DD`, timestamps as `YYYY-MM-DD HH:MM:SS[.fff]` and decimals must not have
+/// more fractional digits than the scale of the column.
+pub fn parse_fill_value(text: &str, parquet_type: &Type) -> Result<FillValue, Error> {
+    let info = parquet_type.get_basic_info();
+    let logical_type = info.logical_type();
+    let is_decimal = info.converted_type() == ConvertedType::DECIMAL;
+    let scale = parquet_type.get_scale().try_into().unwrap_or(0);
+    let invalid =
+        || anyhow!("'{text}' is not a valid value for a column of type {parquet_type:?}.");
+    let fill = match parquet_type.get_physical_type() {
+        PhysicalType::BOOLEAN => match text {
+            "1" | "true" => FillValue::Bool(true),
+            "0" | "false" => FillValue::Bool(false),
+            _ => return Err(invalid()),
+        },
+        PhysicalType::INT32 if is_decimal => {
+            let value = parse_scaled(text, scale).ok_or_else(invalid)?;
+            FillValue::I32(value.try_into().map_err(|_| invalid())?)
+        }
+        PhysicalType::INT64 if is_decimal => {
+            let value = parse_scaled(text, scale).ok_or_else(invalid)?;
+            FillValue::I64(value.try_into().map_err(|_| invalid())?)
+        }
+        PhysicalType::INT32 => match logical_type {
+            Some(LogicalType::Date) => {
+                let date = NaiveDate::parse_from_str(text, "%Y-%m-%d").map_err(|_| invalid())?;
+                let days = date.signed_duration_since(NaiveDate::default()).num_days();
+                FillValue::I32(days.try_into().map_err(|_| invalid())?)
+            }
+            Some(LogicalType::Time { unit, .. }) => {
+                FillValue::I32(time(text, &unit)?.try_into().map_err(|_| invalid())?)
+            }
+            Some(LogicalType::Integer {
+                bit_width,
+                is_signed,
+            }) => FillValue::I32(integer(text, bit_width, is_signed).ok_or_else(invalid)? as i32),
+            _ => FillValue::I32(text.parse().map_err(|_| invalid())?),
+        },
+        PhysicalType::INT64 => match logical_type {
+            Some(LogicalType::Timestamp { unit, .. }) => {
+                let utc = datetime(text)?.and_utc();
+                let value = match unit {
+                    TimeUnit::MILLIS(_) => Some(utc.timestamp_millis()),
+                    TimeUnit::MICROS(_) => Some(utc.timestamp_micros()),
+                    TimeUnit::NANOS(_) => utc.timestamp_nanos_opt(),
+                };
+                FillValue::I64(value.ok_or_else(invalid)?)
+            }
+            Some(LogicalType::Time { unit, .. }) => FillValue::I64(time(text, &unit)?),
+            Some(LogicalType::Integer {
+                bit_width,
+                is_signed,
+            }) => FillValue::I64(integer(text, bit_width, is_signed).ok_or_else(invalid)? as i64),
+            _ => FillValue::I64(text.parse().map_err(|_| invalid())?),
+        },
+        PhysicalType::INT96 => {
+            let datetime = datetime(text)?;
+            FillValue::I96(timestamp_to_int96(&Timestamp {
+                year: datetime.year().try_into().map_err(|_| invalid())?,
+                month: datetime.month() as u16,
+                day: datetime.day() as u16,
+                hour: datetime.hour() as u16,
+                minute: datetime.minute() as u16,
+                second: datetime.second() as u16,
+                fraction: datetime.nanosecond(),
+            }))
+        }
+        PhysicalType::FLOAT => FillValue::F32(text.parse().map_err(|_| invalid())?),
+        PhysicalType::DOUBLE => FillValue::F64(text.parse().map_err(|_| invalid())?),
+        PhysicalType::BYTE_ARRAY => FillValue::Bytes(ByteArray::from(text)),
+        PhysicalType::FIXED_LEN_BYTE_ARRAY => {
+            let length = type_length(parquet_type).try_into().unwrap();
+            let bytes = if is_decimal {
+                twos_complement(parse_scaled(text, scale).ok_or_else(invalid)?, length)?
+            } else if text.len() == length {
+                text.as_bytes().to_owned()
+            } else {
+                bail!("Fill value '{text}' must have exactly {length} bytes.")
+            };
+            FillValue::FixedLenBytes(ByteArray::from(bytes).into())
+        }
+    };
+    Ok(fill)
+}
+
+/// Length of fixed length byte arrays. `-1` for all other types.
+fn type_length(parquet_type: &Type) -> i32 {
+    match parquet_type {
+        Type::PrimitiveType { type_length, .. } => *type_length,
+        Type::GroupType { .. } => -1,
+    }
+}
+
+/// Parses an integer and checks it to be within the range of the logical integer type. The result
+/// is the two's complement representation, so unsigned values can be cast to the physical type.
+fn integer(text: &str, bit_width: i8, is_signed: bool) -> Option<i128> {
+    let value: i128 = text.parse().ok()?;
+    let (min, max) = if is_signed {
+        (-(1 << (bit_width - 1)), (1 << (bit_width - 1)) - 1)
+    } else {
+        (0, (1 << bit_width) - 1)
+    };
+    (min..=max).contains(&value).then_some(value)
+}
+
+fn datetime(text: &str) -> Result<NaiveDateTime, Error> {
+    DateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f %:z")
+        .map(|datetime| datetime.naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f"))
+        .or_else(|_| NaiveDate::parse_from_str(text, "%Y-%m-%d").map(|date| date.into()))
+        .map_err(|_| anyhow!("'{text}' is not a valid timestamp. Expected YYYY-MM-DD HH:MM:SS."))
+}
+
+fn time(text: &str, unit: &TimeUnit) -> Result<i64, Error> {
+    let time = NaiveTime::parse_from_str(text, "%H:%M:%S%.f")
+        .map_err(|_| anyhow!("'{text}' is not a valid time. Expected HH:MM:SS."))?;
+    let nanos = time
+        .signed_duration_since(NaiveTime::MIN)
+        .num_nanoseconds()
+        .unwrap();
+    Ok(match unit {
+        TimeUnit::MILLIS(_) => nanos / 1_000_000,
+        TimeUnit::MICROS(_) => nanos / 1_000,
+        TimeUnit::NANOS(_) => nanos,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use parquet::{
+        basic::{ConvertedType, LogicalType, Repetition, Type as PhysicalType},
+        data_type::{ByteArray, Int32Type},
+        schema::types::Type,
+    };
+
+    use odbc_api::buffers::BufferDesc;
+
+    use crate::{parquet_buffer::FillValue, query::identical::fetch_identical};
+
+    use super::{parse_fill_value, ColumnStrategy, NullFill};
+
+    fn column(physical_type: PhysicalType, logical_type: Option<LogicalType>) -> Type {
+        Type::primitive_type_builder("a", physical_type)
+            .with_repetition(Repetition::OPTIONAL)
+            .with_logical_type(logical_type)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn parse_fill_values_by_column_type() {
+        let int32 = column(PhysicalType::INT32, None);
+        assert_eq!(FillValue::I32(0), parse_fill_value("0", &int32).unwrap());
+        assert!(parse_fill_value("zero", &int32).is_err());
+
+        let text = column(PhysicalType::BYTE_ARRAY, Some(LogicalType::String));
+        assert_eq!(
+            FillValue::Bytes(ByteArray::from("UNKNOWN")),
+            parse_fill_value("UNKNOWN", &text).unwrap()
+        );
+
+        let date = column(PhysicalType::INT32, Some(LogicalType::Date));
+        assert_eq!(
+            FillValue::I32(1),
+            parse_fill_value("1970-01-02", &date).unwrap()
+        );
+        assert!(parse_fill_value("epoch", &date).is_err());
+
+        let decimal = Type::primitive_type_builder("a", PhysicalType::INT64)
+            .with_repetition(Repetition::OPTIONAL)
+            .with_converted_type(ConvertedType::DECIMAL)
+            .with_precision(10)
+            .with_scale(2)
+            .build()
+            .unwrap();
+        assert_eq!(
+            FillValue::I64(-150),
+            parse_fill_value("-1.5", &decimal).unwrap()
+        );
+        assert!(parse_fill_value("1.555", &decimal).is_err());
+
+        let tiny = column(
+            PhysicalType::INT32,
+            Some(LogicalType::Integer {
+                bit_width: 8,
+                is_signed: true,
+            }),
+        );
+        assert!(parse_fill_value("128", &tiny).is_err());
+    }
+
+    #[test]
+    fn required_keeps_type_of_optional_column() {
+        let strategy = NullFill::required(fetch_identical::<Int32Type>(true));
+        let parquet_type = strategy.parquet_type("a");
+
+        assert_eq!(
+            Repetition::REQUIRED,
+            parquet_type.get_basic_info().repetition()
+        );
+        assert_eq!(PhysicalType::INT32, parquet_type.get_physical_type());
+        // NULLs are still fetched, so they can be reported.
+        assert_eq!(BufferDesc::I32 { nullable: true }, strategy.buffer_desc());
+    }
+}
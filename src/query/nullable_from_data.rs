@@ -0,0 +1,344 @@
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{Context, Error};
+use log::info;
+use parquet::{
+    basic::Repetition,
+    column::{
+        reader::{ColumnReader, ColumnReaderImpl},
+        writer::{get_typed_column_writer_mut, ColumnWriter},
+    },
+    data_type::{
+        BoolType, ByteArrayType, DataType, DoubleType, FixedLenByteArrayType, FloatType, Int32Type,
+        Int64Type, Int96Type,
+    },
+    file::{
+        properties::WriterProperties,
+        reader::{FileReader, SerializedFileReader},
+        writer::SerializedFileWriter,
+    },
+    schema::types::{SchemaDescriptor, Type},
+};
+use tempfile::NamedTempFile;
+
+use super::{
+    parquet_writer::{OutputSize, ParquetOutput, WrittenRowGroup},
+    table_strategy::ColumnExporter,
+};
+
+/// Declares columns `REQUIRED`, which turned out not to contain any NULL, once all rows have been
+/// written. The schema of a parquet file can not be changed without encoding its pages anew, so
+/// each file of the output is rewritten after the output has been closed.
+pub struct NullableFromData {
+    output: Box<dyn ParquetOutput>,
+    schema: Arc<Type>,
+    properties: Arc<WriterProperties>,
+    /// Files written by `output`, in the order they have been written.
+    files: Vec<PathBuf>,
+    /// One element for each leaf column. `true` if the column may contain NULL, i.e. a NULL has
+    /// been written, or a row group lacks the statistics to tell.
+    has_null: Vec<bool>,
+}
+
+impl NullableFromData {
+    pub fn new(
+        output: Box<dyn ParquetOutput>,
+        schema: Arc<Type>,
+        properties: Arc<WriterProperties>,
+    ) -> Self {
+        let num_columns = SchemaDescriptor::new(schema.clone()).num_columns();
+        Self {
+            output,
+            schema,
+            properties,
+            files: Vec::new(),
+            has_null: vec![false; num_columns],
+        }
+    }
+}
+
+impl ParquetOutput for NullableFromData {
+    fn write_row_group(
+        &mut self,
+        num_batch: u32,
+        column_exporter: &mut ColumnExporter,
+    ) -> Result<WrittenRowGroup, Error> {
+        let written = self.output.write_row_group(num_batch, column_exporter)?;
+        for (has_null, column) in self.has_null.iter_mut().zip(written.metadata.columns()) {
+            let null_count = column.statistics().and_then(|stats| stats.null_count_opt());
+            *has_null |= null_count != Some(0);
+        }
+        if let Some(path) = &written.path {
+            if self.files.last() != Some(path) {
+                self.files.push(path.clone());
+            }
+        }
+        Ok(written)
+    }
+
+    fn close(self) -> Result<OutputSize, Error> {
+        let mut written = self.output.close_box()?;
+        let descriptor = SchemaDescriptor::new(self.schema.clone());
+        // Only columns without optional ancestors can be declared required on their own.
+        let required: Vec<bool> = descriptor
+            .columns()
+            .iter()
+            .zip(&self.has_null)
+            .map(|(column, &has_null)| {
+                !has_null
+                    && column.max_def_level() == 1
+                    && column.self_type().get_basic_info().repetition() == Repetition::OPTIONAL
+            })
+            .collect();
+        let num_required = required.iter().filter(|&&required| required).count();
+        if num_required == 0 || self.files.is_empty() {
+            info!("No column is declared required, since each one may hold NULL.");
+            return Ok(written);
+        }
+        let mut leaves = required.iter().copied();
+        let schema = Arc::new(with_required(&self.schema, &mut leaves));
+        info!(
+            "Declaring {num_required} columns without NULL required. Rewriting {} files.",
+            self.files.len()
+        );
+        for path in &self.files {
+            let old_size = fs::metadata(path)?.len();
+            rewrite(path, schema.clone(), self.properties.clone())
+                .with_context(|| format!("Could not rewrite '{}'.", path.display()))?;
+            written.num_bytes = written.num_bytes - old_size + fs::metadata(path)?.len();
+        }
+        Ok(written)
+    }
+
+    fn close_box(self: Box<Self>) -> Result<OutputSize, Error> {
+        self.close()
+    }
+}
+
+/// Copy of `field`, declaring the leaf columns required for which `leaves` yields `true`. `leaves`
+/// yields one element for each leaf, in the order of the schema descriptor.
+fn with_required(field: &Type, leaves: &mut impl Iterator<Item = bool>) -> Type {
+    let info = field.get_basic_info();
+    let id = info.has_id().then(|| info.id());
+    match field {
+        Type::PrimitiveType {
+            physical_type,
+            type_length,
+            scale,
+            precision,
+            ..
+        } => {
+            let repetition = if leaves.next().unwrap() {
+                Repetition::REQUIRED
+            } else {
+                info.repetition()
+            };
+            Type::primitive_type_builder(info.name(), *physical_type)
+                .with_repetition(repetition)
+                .with_logical_type(info.logical_type())
+                .with_converted_type(info.converted_type())
+                .with_length(*type_length)
+                .with_scale(*scale)
+                .with_precision(*precision)
+                .with_id(id)
+                .build()
+                .unwrap()
+        }
+        Type::GroupType { fields, .. } => {
+            let fields = fields
+                .iter()
+                .map(|field| Arc::new(with_required(field, leaves)))
+                .collect();
+            let mut builder = Type::group_type_builder(info.name())
+                .with_fields(fields)
+                .with_logical_type(info.logical_type())
+                .with_converted_type(info.converted_type())
+                .with_id(id);
+            // The root of the schema has no repetition.
+            if info.has_repetition() {
+                builder = builder.with_repetition(info.repetition());
+            }
+            builder.build().unwrap()
+        }
+    }
+}
+
+/// Replaces the file at `path` with a copy using `schema`. The values are copied as they are, so
+/// `schema` must only differ in the repetition of columns without NULL.
+fn rewrite(path: &Path, schema: Arc<Type>, properties: Arc<WriterProperties>) -> Result<(), Error> {
+    let reader = SerializedFileReader::new(File::open(path)?)?;
+    let directory = path.parent().unwrap_or(Path::new("."));
+    let temporary = NamedTempFile::new_in(directory)?;
+    let mut writer =
+        SerializedFileWriter::new(BufWriter::new(temporary.reopen()?), schema, properties)?;
+    for index in 0..reader.num_row_groups() {
+        let row_group = reader.get_row_group(index)?;
+        let num_rows: usize = row_group.metadata().num_rows().try_into().unwrap();
+        let mut row_group_writer = writer.next_row_group()?;
+        let mut column = 0;
+        while let Some(mut column_writer) = row_group_writer.next_column()? {
+            let is_optional = row_group
+                .metadata()
+                .column(column)
+                .column_descr()
+                .max_def_level()
+                > 0;
+            let column_reader = row_group.get_column_reader(column)?;
+            let cw = column_writer.untyped();
+            match column_reader {
+                ColumnReader::BoolColumnReader(cr) => {
+                    copy::<BoolType>(cr, cw, num_rows, is_optional)?
+                }
+                ColumnReader::Int32ColumnReader(cr) => {
+                    copy::<Int32Type>(cr, cw, num_rows, is_optional)?
+                }
+                ColumnReader::Int64ColumnReader(cr) => {
+                    copy::<Int64Type>(cr, cw, num_rows, is_optional)?
+                }
+                ColumnReader::Int96ColumnReader(cr) => {
+                    copy::<Int96Type>(cr, cw, num_rows, is_optional)?
+                }
+                ColumnReader::FloatColumnReader(cr) => {
+                    copy::<FloatType>(cr, cw, num_rows, is_optional)?
+                }
+                ColumnReader::DoubleColumnReader(cr) => {
+                    copy::<DoubleType>(cr, cw, num_rows, is_optional)?
+                }
+                ColumnReader::ByteArrayColumnReader(cr) => {
+                    copy::<ByteArrayType>(cr, cw, num_rows, is_optional)?
+                }
+                ColumnReader::FixedLenByteArrayColumnReader(cr) => {
+                    copy::<FixedLenByteArrayType>(cr, cw, num_rows, is_optional)?
+                }
+            }
+            column_writer.close()?;
+            column += 1;
+        }
+        row_group_writer.close()?;
+    }
+    writer.into_inner()?.into_inner()?;
+    temporary.persist(path)?;
+    Ok(())
+}
+
+/// Copies the values of a column chunk. `is_optional` refers to the column read. Definition levels
+/// are only written, if the column is still optional.
+fn copy<T: DataType>(
+    mut column_reader: ColumnReaderImpl<T>,
+    column_writer: &mut ColumnWriter,
+    num_rows: usize,
+    is_optional: bool,
+) -> Result<(), Error> {
+    let mut values = Vec::with_capacity(num_rows);
+    let mut def_levels = Vec::with_capacity(num_rows);
+    let mut num_read = 0;
+    while num_read < num_rows {
+        let (num_records, _num_values, _num_levels) = column_reader.read_records(
+            num_rows - num_read,
+            is_optional.then_some(&mut def_levels),
+            None,
+            &mut values,
+        )?;
+        if num_records == 0 {
+            break;
+        }
+        num_read += num_records;
+    }
+    let cw = get_typed_column_writer_mut::<T>(column_writer);
+    let keep_def_levels = cw.get_descriptor().max_def_level() > 0;
+    cw.write_batch(&values, keep_def_levels.then_some(&def_levels[..]), None)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, sync::Arc};
+
+    use parquet::{
+        basic::{Repetition, Type as PhysicalType},
+        data_type::Int32Type,
+        file::{
+            properties::WriterProperties,
+            reader::{FileReader, SerializedFileReader},
+            writer::SerializedFileWriter,
+        },
+        record::Field,
+        schema::types::Type,
+    };
+    use tempfile::tempdir;
+
+    use super::{rewrite, with_required};
+
+    #[test]
+    fn declare_column_without_null_required() {
+        let column = |name| {
+            Arc::new(
+                Type::primitive_type_builder(name, PhysicalType::INT32)
+                    .with_repetition(Repetition::OPTIONAL)
+                    .build()
+                    .unwrap(),
+            )
+        };
+        let schema = Arc::new(
+            Type::group_type_builder("schema")
+                .with_fields(vec![column("a"), column("b")])
+                .build()
+                .unwrap(),
+        );
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("out.par");
+        let properties = Arc::new(WriterProperties::builder().build());
+        let mut writer = SerializedFileWriter::new(
+            File::create(&path).unwrap(),
+            schema.clone(),
+            properties.clone(),
+        )
+        .unwrap();
+        let mut row_group_writer = writer.next_row_group().unwrap();
+        for def_levels in [[1, 1], [1, 0]] {
+            let mut column_writer = row_group_writer.next_column().unwrap().unwrap();
+            column_writer
+                .typed::<Int32Type>()
+                .write_batch(&[1, 2], Some(&def_levels), None)
+                .unwrap();
+            column_writer.close().unwrap();
+        }
+        row_group_writer.close().unwrap();
+        writer.close().unwrap();
+
+        let schema = Arc::new(with_required(&schema, &mut [true, false].into_iter()));
+        rewrite(&path, schema, properties).unwrap();
+
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        let columns = reader
+            .metadata()
+            .file_metadata()
+            .schema_descr()
+            .columns()
+            .to_vec();
+        assert_eq!(0, columns[0].max_def_level());
+        assert_eq!(1, columns[1].max_def_level());
+        let rows: Vec<_> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| {
+                row.unwrap()
+                    .get_column_iter()
+                    .map(|(_name, field)| field.clone())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                vec![Field::Int(1), Field::Int(1)],
+                vec![Field::Int(2), Field::Null]
+            ],
+            rows
+        );
+    }
+}
@@ -0,0 +1,392 @@
+use std::{mem::size_of, ptr::null_mut};
+
+use anyhow::{anyhow, bail, Error};
+use odbc_api::{
+    buffers::{AnySlice, BufferDesc, ColumnarAnyBuffer},
+    handles::{AsStatementRef, Descriptor, SqlResult, Statement, StatementRef},
+    sys::{CDataType, Desc, Numeric, Pointer, SQLGetDescFieldW, SqlReturn},
+    RowSetBuffer, TruncationInfo,
+};
+use parquet::{
+    basic::{LogicalType, Repetition, Type as PhysicalType},
+    column::writer::ColumnWriter,
+    data_type::{DataType as ParquetDataType, FixedLenByteArrayType, Int32Type, Int64Type},
+    schema::types::Type,
+};
+
+use crate::parquet_buffer::ParquetBuffer;
+
+use super::{
+    column_strategy::{unexpected_view, ColumnStrategy},
+    decimal::min_length_in_bytes,
+};
+
+/// Size of `SQL_NUMERIC_STRUCT`: precision, scale and sign, followed by the 16 bytes of the
+/// mantissa.
+const NUMERIC_LENGTH: usize = size_of::<Numeric>();
+
+/// Strategy for decimals with a scale other than zero, fetched as `SQL_C_NUMERIC` struct rather
+/// than as text. See `--numeric-decimals`.
+///
+/// * `decimal_byte_length`: If specified, decimals are always stored as fixed length byte arrays
+///   of this length, instead of choosing the smallest physical type fitting the precision.
+pub fn numeric_strategy(
+    is_optional: bool,
+    scale: i32,
+    precision: u8,
+    decimal_byte_length: Option<usize>,
+) -> Result<Box<dyn ColumnStrategy>, Error> {
+    let repetition = if is_optional {
+        Repetition::OPTIONAL
+    } else {
+        Repetition::REQUIRED
+    };
+    let min_length = min_length_in_bytes(precision);
+    let physical = match (decimal_byte_length, precision) {
+        (Some(length_in_bytes), _) if length_in_bytes < min_length => bail!(
+            "Decimals with precision {precision} require at least {min_length} bytes, but \
+            `--decimal-byte-length` is {length_in_bytes}."
+        ),
+        (Some(length_in_bytes), _) => NumericPhysicalType::Binary {
+            length_in_bytes,
+            forced_length: true,
+        },
+        (None, 0..=9) => NumericPhysicalType::Int32,
+        (None, 10..=18) => NumericPhysicalType::Int64,
+        (None, _) => NumericPhysicalType::Binary {
+            length_in_bytes: min_length,
+            forced_length: false,
+        },
+    };
+    Ok(Box::new(DecimalFromNumeric {
+        repetition,
+        scale,
+        precision,
+        physical,
+    }))
+}
+
+/// Physical type decimals fetched as `SQL_C_NUMERIC` are written as.
+#[derive(Clone, Copy)]
+enum NumericPhysicalType {
+    Int32,
+    Int64,
+    Binary {
+        length_in_bytes: usize,
+        /// `true` if `length_in_bytes` has been forced by `--decimal-byte-length`. Values which do
+        /// not fit are an error then.
+        forced_length: bool,
+    },
+}
+
+/// The buffer is bound as binary, with each element taking up one `SQL_NUMERIC_STRUCT`. Its record
+/// in the application row descriptor is changed to `SQL_C_NUMERIC` with the precision and scale of
+/// the column, once bound, see [`NumericBinding`].
+struct DecimalFromNumeric {
+    repetition: Repetition,
+    scale: i32,
+    precision: u8,
+    physical: NumericPhysicalType,
+}
+
+impl DecimalFromNumeric {
+    /// Values of `view` as integers, i.e. multiplied by `10^scale`.
+    fn values<'a>(
+        &'a self,
+        column_view: AnySlice<'a>,
+    ) -> Result<impl Iterator<Item = Result<Option<i128>, Error>> + 'a, Error> {
+        let view = column_view
+            .as_bin_view()
+            .ok_or_else(|| unexpected_view("decimals as numeric structs", column_view))?;
+        Ok(view.iter().map(|value| {
+            value
+                .map(|bytes| numeric_to_scaled(bytes, self.scale))
+                .transpose()
+        }))
+    }
+}
+
+impl ColumnStrategy for DecimalFromNumeric {
+    fn parquet_type(&self, name: &str) -> Type {
+        let (physical_type, length) = match self.physical {
+            NumericPhysicalType::Int32 => (PhysicalType::INT32, -1),
+            NumericPhysicalType::Int64 => (PhysicalType::INT64, -1),
+            NumericPhysicalType::Binary {
+                length_in_bytes, ..
+            } => (
+                PhysicalType::FIXED_LEN_BYTE_ARRAY,
+                length_in_bytes.try_into().unwrap(),
+            ),
+        };
+        Type::primitive_type_builder(name, physical_type)
+            .with_length(length)
+            .with_logical_type(Some(LogicalType::Decimal {
+                scale: self.scale,
+                precision: self.precision as i32,
+            }))
+            .with_precision(self.precision.into())
+            .with_scale(self.scale)
+            .with_repetition(self.repetition)
+            .build()
+            .unwrap()
+    }
+
+    fn buffer_desc(&self) -> BufferDesc {
+        BufferDesc::Binary {
+            length: NUMERIC_LENGTH,
+        }
+    }
+
+    fn numeric_binding(&self) -> Option<(i16, i16)> {
+        Some((self.precision.into(), self.scale.try_into().unwrap()))
+    }
+
+    fn copy_odbc_to_parquet(
+        &self,
+        parquet_buffer: &mut ParquetBuffer,
+        column_writer: &mut ColumnWriter,
+        column_view: AnySlice,
+    ) -> Result<(), Error> {
+        let values = self.values(column_view)?;
+        let exceeds = |n: i128| {
+            anyhow!(
+                "Decimal {n} with scale {} exceeds a precision of {} digits.",
+                self.scale,
+                self.precision
+            )
+        };
+        match self.physical {
+            NumericPhysicalType::Int32 => parquet_buffer.write_optional_fallible(
+                Int32Type::get_column_writer_mut(column_writer).unwrap(),
+                values.map(|n| {
+                    n?.map(|n| i32::try_from(n).map_err(|_| exceeds(n)))
+                        .transpose()
+                }),
+            ),
+            NumericPhysicalType::Int64 => parquet_buffer.write_optional_fallible(
+                Int64Type::get_column_writer_mut(column_writer).unwrap(),
+                values.map(|n| {
+                    n?.map(|n| i64::try_from(n).map_err(|_| exceeds(n)))
+                        .transpose()
+                }),
+            ),
+            NumericPhysicalType::Binary {
+                length_in_bytes,
+                forced_length,
+            } => parquet_buffer.write_twos_complement_i128(
+                FixedLenByteArrayType::get_column_writer_mut(column_writer).unwrap(),
+                values,
+                length_in_bytes,
+                forced_length,
+            ),
+        }
+    }
+}
+
+/// Value of a `SQL_NUMERIC_STRUCT` as integer, i.e. multiplied by `10^scale`. The mantissa is
+/// stored little endian, the sign is `1` for positive and `0` for negative values. Values with
+/// another scale than the one requested in the application row descriptor are an error, since
+/// the driver is expected to convert them.
+fn numeric_to_scaled(bytes: &[u8], scale: i32) -> Result<i128, Error> {
+    let bytes: &[u8; NUMERIC_LENGTH] = bytes.try_into().map_err(|_| {
+        anyhow!(
+            "Numeric struct has {} instead of {NUMERIC_LENGTH} bytes.",
+            bytes.len()
+        )
+    })?;
+    let [_precision, actual_scale, sign, mantissa @ ..] = *bytes;
+    if i32::from(actual_scale as i8) != scale {
+        bail!(
+            "The driver returned a numeric struct with scale {}, although scale {scale} has been \
+            requested.",
+            actual_scale as i8
+        )
+    }
+    let n = i128::try_from(u128::from_le_bytes(mantissa)).map_err(|_| {
+        anyhow!("Mantissa of numeric struct exceeds the range of 128-Bit integers.")
+    })?;
+    Ok(if sign == 0 { -n } else { n })
+}
+
+/// Describes the record of the column at the one based `index` in the application row descriptor
+/// of `stmt` as `SQL_C_NUMERIC` with `precision` and `scale`. Used to find out up front, whether
+/// the driver supports binding the column this way. Leaves the column unbound.
+pub fn describe_as_numeric(
+    mut stmt: StatementRef<'_>,
+    index: i16,
+    precision: i16,
+    scale: i16,
+) -> Result<(), Error> {
+    let mut ard = application_row_descriptor(&mut stmt)?;
+    // Safety: No data pointer is set, so the driver does not write any values with this type.
+    unsafe { describe_numeric(&mut ard, index, precision, scale)? };
+    Ok(())
+}
+
+/// Sets type, precision and scale of the record `index` in the application row descriptor `ard`.
+///
+/// # Safety
+///
+/// Setting the type unbinds the column. A data pointer set afterwards must point to a
+/// `SQL_NUMERIC_STRUCT` for each row of the row set.
+unsafe fn describe_numeric(
+    ard: &mut Descriptor,
+    index: i16,
+    precision: i16,
+    scale: i16,
+) -> Result<(), odbc_api::Error> {
+    ard.set_type(index, CDataType::Numeric).into_result(ard)?;
+    ard.set_precision(index, precision).into_result(ard)?;
+    ard.set_scale(index, scale).into_result(ard)
+}
+
+fn application_row_descriptor<'a>(
+    stmt: &'a mut StatementRef<'_>,
+) -> Result<Descriptor<'a>, odbc_api::Error> {
+    let hdesc = stmt
+        .application_row_descriptor()
+        .map(|ard| ard.as_sys())
+        .into_result(stmt)?;
+    // Safety: The descriptor is owned by `stmt`, which it borrows.
+    Ok(unsafe { Descriptor::new(hdesc) })
+}
+
+/// Data pointer of the record `index` in the application row descriptor `ard`. Null if the column
+/// is not bound.
+fn data_ptr(ard: &Descriptor, index: i16) -> Result<Pointer, odbc_api::Error> {
+    let mut data_ptr: Pointer = null_mut();
+    // Safety: `data_ptr` outlives the call and is large enough to hold a pointer.
+    let ret = unsafe {
+        SQLGetDescFieldW(
+            ard.as_sys(),
+            index,
+            Desc::DataPtr,
+            &mut data_ptr as *mut Pointer as Pointer,
+            0,
+            null_mut(),
+        )
+    };
+    if ret != SqlReturn::SUCCESS && ret != SqlReturn::SUCCESS_WITH_INFO {
+        SqlResult::<()>::Error {
+            function: "SQLGetDescField",
+        }
+        .into_result(ard)?;
+    }
+    Ok(data_ptr)
+}
+
+/// Column bound as `SQL_C_NUMERIC` by [`NumericBinding`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NumericColumn {
+    /// One based index of the column in the result set.
+    pub column_number: u16,
+    pub precision: i16,
+    pub scale: i16,
+}
+
+/// Binds `buffer` to a cursor like [`ColumnarAnyBuffer`] does, but changes the columns of
+/// `numeric_columns` into `SQL_C_NUMERIC` afterwards. Their buffers must have been allocated by
+/// [`DecimalFromNumeric`]. `odbc-api` does not offer buffers for numeric structs, so they are
+/// bound as binary first, which points the application row descriptor to the buffer.
+pub struct NumericBinding<'a> {
+    buffer: &'a mut ColumnarAnyBuffer,
+    numeric_columns: &'a [NumericColumn],
+}
+
+impl<'a> NumericBinding<'a> {
+    pub fn new(buffer: &'a mut ColumnarAnyBuffer, numeric_columns: &'a [NumericColumn]) -> Self {
+        Self {
+            buffer,
+            numeric_columns,
+        }
+    }
+
+    pub fn buffer(&self) -> &ColumnarAnyBuffer {
+        self.buffer
+    }
+
+    pub fn into_buffer(self) -> &'a mut ColumnarAnyBuffer {
+        self.buffer
+    }
+}
+
+unsafe impl RowSetBuffer for NumericBinding<'_> {
+    fn bind_type(&self) -> usize {
+        self.buffer.bind_type()
+    }
+
+    fn row_array_size(&self) -> usize {
+        self.buffer.row_array_size()
+    }
+
+    fn mut_num_fetch_rows(&mut self) -> &mut usize {
+        self.buffer.mut_num_fetch_rows()
+    }
+
+    unsafe fn bind_colmuns_to_cursor(
+        &mut self,
+        mut cursor: StatementRef<'_>,
+    ) -> Result<(), odbc_api::Error> {
+        self.buffer.bind_colmuns_to_cursor(cursor.as_stmt_ref())?;
+        if self.numeric_columns.is_empty() {
+            return Ok(());
+        }
+        let mut ard = application_row_descriptor(&mut cursor)?;
+        for column in self.numeric_columns {
+            let index = column.column_number.try_into().unwrap();
+            // Points to the binary buffer bound above. Columns dropped from the buffer, e.g. due to
+            // `--skip-columns-on-error`, are not bound.
+            let data_ptr = data_ptr(&ard, index)?;
+            if data_ptr.is_null() {
+                continue;
+            }
+            describe_numeric(&mut ard, index, column.precision, column.scale)?;
+            // Setting the type unbinds the column, so the data pointer is set last. Binding the
+            // buffer with `SQLBindCol` after setting the type does not suffice for all drivers, e.g.
+            // Microsoft SQL Server ignores precision and scale then.
+            ard.set_data_ptr(index, data_ptr).into_result(&ard)?;
+        }
+        Ok(())
+    }
+
+    fn find_truncation(&self) -> Option<TruncationInfo> {
+        self.buffer.find_truncation()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::numeric_to_scaled;
+
+    #[test]
+    fn numeric_struct_to_scaled_integer() {
+        // 25.212 as `DECIMAL(5,3)`: 25212 is 0x627C.
+        let mut bytes = [0u8; 19];
+        bytes[..5].copy_from_slice(&[5, 3, 1, 0x7C, 0x62]);
+        assert_eq!(25212, numeric_to_scaled(&bytes, 3).unwrap());
+
+        // Negative values have sign zero.
+        bytes[2] = 0;
+        assert_eq!(-25212, numeric_to_scaled(&bytes, 3).unwrap());
+
+        // Mantissa using all 16 bytes, i.e. 38 digits.
+        let max = 10i128.pow(38) - 1;
+        bytes[3..].copy_from_slice(&max.to_le_bytes());
+        bytes[2] = 1;
+        assert_eq!(max, numeric_to_scaled(&bytes, 3).unwrap());
+    }
+
+    #[test]
+    fn reject_numeric_struct_with_other_scale() {
+        let mut bytes = [0u8; 19];
+        bytes[..4].copy_from_slice(&[5, 2, 1, 1]);
+
+        let result = numeric_to_scaled(&bytes, 3);
+
+        assert_eq!(
+            "The driver returned a numeric struct with scale 2, although scale 3 has been \
+            requested.",
+            result.unwrap_err().to_string()
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use std::{fs::create_dir_all, path::Path};
+
+use anyhow::{Context, Error};
+use io_arg::IoArg;
+
+use super::blob::file_name_from_key;
+
+/// Output file within the directory passed to `--output-dir`. The file is named after the table
+/// if exporting with `--table`, otherwise after a hash of the query text and its parameters. So
+/// running the same export again overwrites the file of the previous run.
+pub fn output_in_dir(
+    dir: &Path,
+    table: Option<&str>,
+    query: &str,
+    parameters: &[String],
+    extension: &str,
+) -> Result<IoArg, Error> {
+    create_dir_all(dir)
+        .with_context(|| format!("Could not create output directory '{}'.", dir.display()))?;
+    Ok(IoArg::File(dir.join(output_file_name(
+        table, query, parameters, extension,
+    ))))
+}
+
+fn output_file_name(
+    table: Option<&str>,
+    query: &str,
+    parameters: &[String],
+    extension: &str,
+) -> String {
+    match table {
+        Some(table) => format!("{}.{extension}", file_name_from_key(table)),
+        None => {
+            let hash = parameters.iter().fold(
+                fnv1a(FNV_OFFSET_BASIS, query.as_bytes()),
+                |hash, parameter| {
+                    // Separate the parameters, so `a`, `bc` and `ab`, `c` yield different names.
+                    fnv1a(fnv1a(hash, &[0]), parameter.as_bytes())
+                },
+            );
+            format!("query_{hash:016x}.{extension}")
+        }
+    }
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// 64 Bit FNV-1a hash. Unlike the hasher of the standard library, it is guaranteed to be stable
+/// across versions of Rust, so file names do not change after upgrading.
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::output_file_name;
+
+    #[test]
+    fn name_output_after_table_or_query() {
+        assert_eq!(
+            "dbo.Sales_2024.par",
+            output_file_name(Some("dbo.Sales 2024"), "", &[], "par")
+        );
+        let name = output_file_name(None, "SELECT 1", &[], "par");
+        assert_eq!("query_", &name[..6]);
+        assert_eq!(name, output_file_name(None, "SELECT 1", &[], "par"));
+        assert_ne!(name, output_file_name(None, "SELECT 2", &[], "par"));
+        assert_ne!(
+            output_file_name(
+                None,
+                "SELECT ?, ?",
+                &["a".to_owned(), "bc".to_owned()],
+                "par"
+            ),
+            output_file_name(
+                None,
+                "SELECT ?, ?",
+                &["ab".to_owned(), "c".to_owned()],
+                "par"
+            )
+        );
+        assert_eq!(
+            "Sales.parquet",
+            output_file_name(Some("Sales"), "", &[], "parquet")
+        );
+    }
+}
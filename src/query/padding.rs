@@ -0,0 +1,124 @@
+use anyhow::{bail, Error};
+use parquet::{basic::Repetition, column::writer::ColumnWriter, schema::types::Type};
+
+use crate::parquet_buffer::{FillValue, ParquetBuffer};
+
+use super::null_fill::parse_fill_value;
+
+/// Filler rows appended to the output, until it holds a target number of rows.
+#[derive(Clone)]
+pub struct Padding {
+    /// Number of rows the output should hold.
+    pub num_rows: u64,
+    /// Value of each column in the filler rows, as specified by the user. Columns without a value
+    /// are NULL.
+    pub values: Vec<(String, String)>,
+    /// Name of an additional boolean column, which is `true` for filler rows only.
+    pub flag_column: Option<String>,
+    /// Fail if the result set holds more than `num_rows` rows.
+    pub strict: bool,
+}
+
+impl Padding {
+    /// Parses the value of each column in the filler rows according to its parquet type.
+    ///
+    /// * `columns`: Names and parquet types of the columns of the result set.
+    pub fn fill_values<'a>(
+        &self,
+        columns: impl Iterator<Item = (&'a str, Type)> + Clone,
+    ) -> Result<Vec<Option<FillValue>>, Error> {
+        if let Some((name, _)) = self
+            .values
+            .iter()
+            .find(|(name, _)| columns.clone().all(|(column, _)| column != name))
+        {
+            bail!("Column '{name}' of `--pad-value` is not part of the result set.")
+        }
+        columns
+            .map(|(name, parquet_type)| {
+                let value = self.values.iter().find(|(column, _)| column == name);
+                match value {
+                    Some((_, value)) => parse_fill_value(value, &parquet_type).map(Some),
+                    None if parquet_type.get_basic_info().repetition() == Repetition::REQUIRED => {
+                        bail!(
+                            "Column '{name}' does not allow NULL, so filler rows require a value \
+                            for it passed to `--pad-value`."
+                        )
+                    }
+                    None => Ok(None),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Writes `num_rows` times the fill value into the column. NULL if there is no fill value.
+pub fn write_filler(
+    pb: &mut ParquetBuffer,
+    column_writer: &mut ColumnWriter,
+    fill: Option<&FillValue>,
+    num_rows: usize,
+) -> Result<(), Error> {
+    pb.null_fill = fill.cloned();
+    let result = match column_writer {
+        ColumnWriter::BoolColumnWriter(cw) => pb.write_optional(cw, (0..num_rows).map(|_| None)),
+        ColumnWriter::Int32ColumnWriter(cw) => pb.write_optional(cw, (0..num_rows).map(|_| None)),
+        ColumnWriter::Int64ColumnWriter(cw) => pb.write_optional(cw, (0..num_rows).map(|_| None)),
+        ColumnWriter::Int96ColumnWriter(cw) => pb.write_optional(cw, (0..num_rows).map(|_| None)),
+        ColumnWriter::FloatColumnWriter(cw) => pb.write_optional(cw, (0..num_rows).map(|_| None)),
+        ColumnWriter::DoubleColumnWriter(cw) => pb.write_optional(cw, (0..num_rows).map(|_| None)),
+        ColumnWriter::ByteArrayColumnWriter(cw) => {
+            pb.write_optional(cw, (0..num_rows).map(|_| None))
+        }
+        ColumnWriter::FixedLenByteArrayColumnWriter(cw) => {
+            pb.write_optional(cw, (0..num_rows).map(|_| None))
+        }
+    };
+    pb.null_fill = None;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use parquet::{
+        basic::{Repetition, Type as PhysicalType},
+        schema::types::Type,
+    };
+
+    use crate::parquet_buffer::FillValue;
+
+    use super::Padding;
+
+    fn column(repetition: Repetition) -> Type {
+        Type::primitive_type_builder("a", PhysicalType::INT32)
+            .with_repetition(repetition)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn parse_pad_values() {
+        let padding = Padding {
+            num_rows: 10,
+            values: vec![("a".to_owned(), "0".to_owned())],
+            flag_column: None,
+            strict: false,
+        };
+        let columns = [
+            ("a", column(Repetition::REQUIRED)),
+            ("b", column(Repetition::OPTIONAL)),
+        ];
+        assert_eq!(
+            vec![Some(FillValue::I32(0)), None],
+            padding.fill_values(columns.iter().cloned()).unwrap()
+        );
+
+        // Required columns need a value
+        let padding = Padding {
+            values: Vec::new(),
+            ..padding
+        };
+        let columns = [("b", column(Repetition::REQUIRED))];
+        assert!(padding.fill_values(columns.iter().cloned()).is_err());
+    }
+}
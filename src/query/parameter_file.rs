@@ -0,0 +1,86 @@
+use std::{fs, path::Path};
+
+use anyhow::{bail, Context, Error};
+
+/// Reads a file with one set of positional parameters per line. Values are separated by commas.
+/// Values containing commas or double quotes can be enclosed in double quotes, with double quotes
+/// inside escaped by doubling them. Empty lines are ignored. All lines must have the same number
+/// of values.
+pub fn read_parameter_file(path: &Path) -> Result<Vec<Vec<String>>, Error> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Could not read parameter file '{}'.", path.display()))?;
+    let mut parameter_sets: Vec<Vec<String>> = Vec::new();
+    for (line_index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let values = parse_line(line)
+            .with_context(|| format!("Invalid line {} in parameter file.", line_index + 1))?;
+        if let Some(first) = parameter_sets.first() {
+            if first.len() != values.len() {
+                bail!(
+                    "Line {} of the parameter file has {} values, but previous lines have {}.",
+                    line_index + 1,
+                    values.len(),
+                    first.len()
+                )
+            }
+        }
+        parameter_sets.push(values);
+    }
+    if parameter_sets.is_empty() {
+        bail!("Parameter file '{}' is empty.", path.display())
+    }
+    Ok(parameter_sets)
+}
+
+fn parse_line(line: &str) -> Result<Vec<String>, Error> {
+    let mut values = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        let mut value = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        value.push('"');
+                    }
+                    Some('"') => break,
+                    Some(c) => value.push(c),
+                    None => bail!("Missing closing quote."),
+                }
+            }
+            if !matches!(chars.peek(), None | Some(',')) {
+                bail!("Unexpected characters after closing quote.")
+            }
+        } else {
+            while let Some(c) = chars.next_if(|&c| c != ',') {
+                value.push(c);
+            }
+        }
+        values.push(value);
+        if chars.next().is_none() {
+            break;
+        }
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_line;
+
+    #[test]
+    fn parse_parameter_lines() {
+        assert_eq!(vec!["1", "a"], parse_line("1,a").unwrap());
+        assert_eq!(
+            vec!["a,b", "say \"hi\""],
+            parse_line(r#""a,b","say ""hi""""#).unwrap()
+        );
+        assert_eq!(vec!["", ""], parse_line(",").unwrap());
+        assert!(parse_line("\"a").is_err());
+        assert!(parse_line("\"a\"b").is_err());
+    }
+}
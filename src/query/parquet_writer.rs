@@ -1,37 +1,127 @@
 use std::{
-    io::{stdout, Write},
+    fs,
+    io::{self, stdout, Write},
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, Mutex},
 };
 
-use anyhow::{format_err, Error};
+use anyhow::{bail, format_err, Context, Error};
+use bytesize::ByteSize;
 use io_arg::IoArg;
+use log::info;
 use parquet::{
-    basic::{Compression, Encoding},
+    basic::{Compression, ConvertedType, Encoding, LogicalType, Type as PhysicalType},
     file::{
-        properties::{WriterProperties, WriterVersion},
+        metadata::{KeyValue, RowGroupMetaDataPtr},
+        properties::{
+            EnabledStatistics, WriterProperties, WriterPropertiesBuilder, WriterVersion,
+            DEFAULT_WRITE_BATCH_SIZE,
+        },
         writer::SerializedFileWriter,
     },
-    schema::types::{ColumnPath, Type},
+    schema::types::{ColumnPath, SchemaDescriptor, Type},
+};
+
+use crate::{
+    enum_args::{PartitionByDate, SortedBy},
+    is_fifo,
 };
 
 use super::{
-    batch_size_limit::FileSizeLimit, current_file::CurrentFile, table_strategy::ColumnExporter,
+    batch_size_limit::{FileSizeLimit, MaxFileCount},
+    client_sort::ClientSort,
+    current_file::CurrentFile,
+    ddl::Ddl,
+    delimited::{Delimited, DelimitedFormat},
+    delta::{Delta, DeltaAgainst},
+    geoparquet::geo_metadata,
+    hadoop_crc::sidecar_path,
+    nullable_from_data::NullableFromData,
+    partition_by_date::DatePartitions,
+    schema_cache::{CacheSchema, SchemaCache},
+    stats_only::StatsOnly,
+    stats_parquet::StatsParquet,
+    table_strategy::ColumnExporter,
 };
 
 /// Options influencing the output parquet file independent of schema or row content.
+#[derive(Clone)]
 pub struct ParquetWriterOptions {
     /// Directly correlated to the `--column-compression-default` command line option
     pub column_compression_default: Compression,
+    /// Compression of columns by category, overriding the default compression.
+    pub category_compression: CategoryCompression,
     /// Tuples of column name and encoding which control the encoding for the associated columns.
     pub column_encodings: Vec<(String, Encoding)>,
+    /// Separates the components of nested column paths in per column options. If `None` column
+    /// names are never split.
+    pub column_path_separator: Option<String>,
+    /// Columns the result set is declared to be sorted by. Written as metadata for each row group.
+    pub sorted_by: Vec<SortedBy>,
+    /// Application defined metadata written into the footer of each file.
+    pub key_value_metadata: Vec<KeyValue>,
+    /// Description of columns by name. Written into the key value metadata.
+    pub column_comments: Vec<(String, String)>,
+    /// Description of columns by name, fetched from the catalog of the data source. Unlike
+    /// `column_comments`, comments of columns which are not part of the output are ignored.
+    pub catalog_comments: Vec<(String, String)>,
+    /// Columns holding geometries as WKB, described in the GeoParquet `geo` metadata.
+    pub geometry_columns: Vec<String>,
+    /// Coordinate reference system of the geometry columns as PROJJSON.
+    pub geometry_crs: Option<String>,
     /// Number of digits in the suffix, appended to the end of a file in case they are numbered.
     pub suffix_length: usize,
     /// A fuzzy limit for file size, causing the rest of the query to be written into new files if a
     /// threshold is passed.
     pub file_size: FileSizeLimit,
+    /// Caps the number of files, if the output is split.
+    pub max_file_count: Option<MaxFileCount>,
     /// Do not create a file if no row was in the result set.
     pub no_empty_file: bool,
+    /// Only write the statistics of each row group as JSON, rather than a parquet file.
+    pub stats_only: bool,
+    /// Write delimited text, e.g. CSV, rather than parquet.
+    pub delimited: Option<DelimitedFormat>,
+    /// Rewrite the files once all rows are written, declaring columns without NULL required.
+    pub nullable_from_data: bool,
+    /// Sort the rows of all files by these columns, once all rows are written. Empty, if rows are
+    /// written in the order they are fetched.
+    pub client_sort: Vec<String>,
+    /// Reduce the output to the rows which changed compared to a previous export, once all rows
+    /// are written.
+    pub delta_against: Option<DeltaAgainst>,
+    /// Write the rows into Hive style partitions by the date of this column, rather than into the
+    /// output itself.
+    pub partition_by_date: Option<PartitionByDate>,
+    /// Maximum number of partition files open at the same time.
+    pub max_open_partitions: usize,
+    /// Number of threads writing partition files. `1` writes them on the thread fetching the rows.
+    pub partition_concurrency: usize,
+    /// Extension of the partition files, without leading dot.
+    pub extension: String,
+    /// Additional files written from the same batches, each with its own compression for all
+    /// columns. Used to compare codecs without reading the data source twice.
+    pub also_write: Vec<(Compression, PathBuf)>,
+    /// `CREATE TABLE` statement matching the schema, written alongside the output.
+    pub ddl: Option<Ddl>,
+    /// Write a Hadoop checksum file next to each file.
+    pub emit_hadoop_crc: bool,
+    /// Records each file created, except named pipes.
+    pub created_files: CreatedFiles,
+    /// Maximum number of rows in each data page. `None` keeps the default of the parquet writer.
+    pub page_row_count_limit: Option<usize>,
+    /// Write the column index with the statistics of each page. The offset index is always
+    /// written.
+    pub page_index: bool,
+    /// Maximum length of minimum and maximum values in the column index. `None` keeps the default
+    /// of the parquet writer.
+    pub column_index_truncate_length: Option<usize>,
+    /// Compare the schema with the one of a prior run and cache it for the next one.
+    pub schema_cache: Option<SchemaCache>,
+    /// Write the statistics of each row group into a parquet file at this path.
+    pub emit_stats_parquet: Option<PathBuf>,
+    /// Leaf columns whose statistics are written by `emit_stats_parquet`. All if empty.
+    pub stats_parquet_columns: Vec<String>,
 }
 
 pub fn parquet_output(
@@ -39,29 +129,325 @@ pub fn parquet_output(
     schema: Arc<Type>,
     options: ParquetWriterOptions,
 ) -> Result<Box<dyn ParquetOutput>, Error> {
+    // Check the schema first, so a strict mismatch fails before anything is written.
+    let schema_to_cache = match &options.schema_cache {
+        Some(schema_cache) => schema_cache.check(&schema)?,
+        None => None,
+    };
+    if let Some(ddl) = &options.ddl {
+        ddl.write(&schema, &column_comments(&schema, &options)?)?;
+    }
+
+    // These options read the files back once they are written.
+    if options.nullable_from_data
+        || !options.client_sort.is_empty()
+        || options.delta_against.is_some()
+    {
+        let is_parquet_file =
+            matches!(&output, IoArg::File(path) if !is_fifo(path)) && options.delimited.is_none();
+        if !is_parquet_file {
+            bail!(
+                "`--column-nullable-from-data`, `--stable-sort-client` and `--delta-against` \
+                require the output to be a parquet file. They can not be used with standard out, \
+                named pipes or delimited text."
+            );
+        }
+    }
+
+    let delta_path = match &output {
+        IoArg::File(path) => Some(path.clone()),
+        IoArg::StdStream => None,
+    };
+    let output = data_output(output, schema.clone(), &options)?;
+    let output: Box<dyn ParquetOutput> = if options.client_sort.is_empty() {
+        output
+    } else {
+        Box::new(ClientSort::new(
+            output,
+            schema.clone(),
+            writer_properties(&schema, &options)?,
+            &options.client_sort,
+        )?)
+    };
+    let output: Box<dyn ParquetOutput> = if options.nullable_from_data {
+        Box::new(NullableFromData::new(
+            output,
+            schema.clone(),
+            writer_properties(&schema, &options)?,
+        ))
+    } else {
+        output
+    };
+    let output: Box<dyn ParquetOutput> = match (&options.delta_against, delta_path) {
+        (Some(delta), Some(path)) => Box::new(Delta::new(
+            output,
+            path,
+            schema.clone(),
+            writer_properties(&schema, &options)?,
+            delta.clone(),
+        )?),
+        _ => output,
+    };
+    let output: Box<dyn ParquetOutput> = match &options.emit_stats_parquet {
+        Some(path) => Box::new(StatsParquet::new(
+            output,
+            path.clone(),
+            schema,
+            &options.stats_parquet_columns,
+        )?),
+        None => output,
+    };
+    match (&options.schema_cache, schema_to_cache) {
+        (Some(cache), Some(schema)) => {
+            Ok(Box::new(CacheSchema::new(output, cache.clone(), schema)))
+        }
+        _ => Ok(output),
+    }
+}
+
+/// Output of the row groups themselves, i.e. one or more parquet files or standard out.
+fn data_output(
+    output: IoArg,
+    schema: Arc<Type>,
+    options: &ParquetWriterOptions,
+) -> Result<Box<dyn ParquetOutput>, Error> {
+    let properties = writer_properties(&schema, options)?;
+
+    if options.stats_only {
+        return Ok(Box::new(StatsOnly::new(output, schema, properties)?));
+    }
+    if let Some(partition_by) = &options.partition_by_date {
+        let directory = match output {
+            IoArg::File(path) if !is_fifo(&path) && options.delimited.is_none() => path,
+            _ => bail!(
+                "`--partition-by-date` requires the output to be a directory. It can not be used \
+                with standard out, named pipes or delimited text."
+            ),
+        };
+        return Ok(Box::new(DatePartitions::new(
+            directory,
+            schema,
+            properties,
+            partition_by,
+            options.max_open_partitions,
+            options.partition_concurrency,
+            &options.extension,
+            options.suffix_length,
+            options.created_files.clone(),
+        )?));
+    }
+    if let Some(format) = &options.delimited {
+        return Ok(Box::new(Delimited::new(
+            output,
+            schema,
+            properties,
+            options,
+            format.clone(),
+        )?));
+    }
+
+    // Remember the path of the output, so we can report its size in case we compare codecs.
+    let single_file = match &output {
+        IoArg::File(path) if !options.file_size.output_is_splitted() && !is_fifo(path) => {
+            Some(path.clone())
+        }
+        _ => None,
+    };
+
+    let writer: Box<dyn ParquetOutput> = match output {
+        IoArg::StdStream => Box::new(StandardOut::new(schema.clone(), properties)?),
+        IoArg::File(path) => Box::new(FileWriter::new(
+            path,
+            schema.clone(),
+            options.file_size,
+            options,
+            properties,
+        )?),
+    };
+
+    if options.also_write.is_empty() {
+        return Ok(writer);
+    }
+
+    let mut fan_out = FanOut {
+        outputs: vec![writer],
+        files: Vec::new(),
+    };
+    if let Some(path) = single_file {
+        fan_out
+            .files
+            .push((options.column_compression_default, path));
+    }
+    for (compression, path) in &options.also_write {
+        let properties = Arc::new(
+            writer_properties_builder(&schema, options)?
+                .set_compression(*compression)
+                .build(),
+        );
+        fan_out.outputs.push(Box::new(FileWriter::new(
+            path.clone(),
+            schema.clone(),
+            FileSizeLimit::None,
+            options,
+            properties,
+        )?));
+        fan_out.files.push((*compression, path.clone()));
+    }
+    Ok(Box::new(fan_out))
+}
+
+/// Properties of the output specified as output argument, including its compression.
+fn writer_properties(
+    schema: &Arc<Type>,
+    options: &ParquetWriterOptions,
+) -> Result<Arc<WriterProperties>, Error> {
+    let mut wpb = writer_properties_builder(schema, options)?
+        .set_compression(options.column_compression_default);
+    // Leaf columns, so columns nested by `--group-columns` are categorized by their own type.
+    for column in SchemaDescriptor::new(schema.clone()).columns() {
+        let category = category(column.self_type());
+        if let Some(compression) = options.category_compression.for_category(category) {
+            wpb = wpb.set_column_compression(column.path().clone(), compression);
+        }
+    }
+    Ok(Arc::new(wpb.build()))
+}
+
+/// Properties shared by all outputs, i.e. everything but compression.
+fn writer_properties_builder(
+    schema: &Type,
+    options: &ParquetWriterOptions,
+) -> Result<WriterPropertiesBuilder, Error> {
     // Write properties
     // Seems to also work fine without setting the batch size explicitly, but what the heck. Just to
     // be on the safe side.
-    let mut wpb = WriterProperties::builder()
-        .set_writer_version(WriterVersion::PARQUET_2_0)
-        .set_compression(options.column_compression_default);
-    for (column_name, encoding) in options.column_encodings.clone() {
-        let col = ColumnPath::new(vec![column_name]);
-        wpb = wpb.set_column_encoding(col, encoding)
+    let mut wpb = WriterProperties::builder().set_writer_version(WriterVersion::PARQUET_2_0);
+    if !options.page_index {
+        // The column index is derived from page level statistics, so it is omitted if statistics
+        // are only collected for each column chunk.
+        wpb = wpb.set_statistics_enabled(EnabledStatistics::Chunk);
+    }
+    if let Some(length) = options.column_index_truncate_length {
+        wpb = wpb.set_column_index_truncate_length(Some(length));
+    }
+    if let Some(limit) = options.page_row_count_limit {
+        // The limit is only checked between the batches values are handed to the column writer in,
+        // so the batches must not be larger than the limit.
+        wpb = wpb
+            .set_data_page_row_count_limit(limit)
+            .set_write_batch_size(limit.min(DEFAULT_WRITE_BATCH_SIZE));
     }
-    let properties = Arc::new(wpb.build());
+    let separator = options.column_path_separator.as_deref();
+    for (column_name, encoding) in &options.column_encodings {
+        let col = column_path(column_name, separator);
+        wpb = wpb.set_column_encoding(col, *encoding)
+    }
+    if !options.sorted_by.is_empty() {
+        let sorting_columns = options
+            .sorted_by
+            .iter()
+            .map(|sorted_by| sorted_by.to_sorting_column(schema))
+            .collect::<Result<_, _>>()?;
+        wpb = wpb.set_sorting_columns(Some(sorting_columns));
+    }
+    let mut key_value_metadata = options.key_value_metadata.clone();
+    for (column, comment) in column_comments(schema, options)? {
+        key_value_metadata.push(KeyValue::new(
+            format!("odbc2parquet.comment.{column}"),
+            comment,
+        ));
+    }
+    if !options.geometry_columns.is_empty() {
+        key_value_metadata.push(KeyValue::new(
+            "geo".to_owned(),
+            geo_metadata(
+                schema,
+                &options.geometry_columns,
+                options.geometry_crs.as_deref(),
+            )?,
+        ));
+    }
+    if !key_value_metadata.is_empty() {
+        wpb = wpb.set_key_value_metadata(Some(key_value_metadata));
+    }
+    Ok(wpb)
+}
 
-    let writer: Box<dyn ParquetOutput> = match output {
-        IoArg::StdStream => Box::new(StandardOut::new(schema, properties)?),
-        IoArg::File(path) => Box::new(FileWriter::new(path, schema, options, properties)?),
-    };
+/// Comments of the columns of the output, passed on the command line or fetched from the catalog.
+fn column_comments(
+    schema: &Type,
+    options: &ParquetWriterOptions,
+) -> Result<Vec<(String, String)>, Error> {
+    let is_output = |column: &str| schema.get_fields().iter().any(|f| f.name() == column);
+    let mut comments = Vec::new();
+    for (column, comment) in &options.column_comments {
+        if !is_output(column) {
+            bail!("Column '{column}' of `--column-comment` is not part of the output.")
+        }
+        comments.push((column.clone(), comment.clone()));
+    }
+    for (column, comment) in &options.catalog_comments {
+        let is_explicit = options.column_comments.iter().any(|(c, _)| c == column);
+        if is_output(column) && !is_explicit {
+            comments.push((column.clone(), comment.clone()));
+        }
+    }
+    Ok(comments)
+}
+
+/// Compression for each category of columns. `None` means the default compression is used.
+#[derive(Clone)]
+pub struct CategoryCompression {
+    pub strings: Option<Compression>,
+    pub numeric: Option<Compression>,
+    pub binary: Option<Compression>,
+}
+
+impl CategoryCompression {
+    fn for_category(&self, category: ColumnCategory) -> Option<Compression> {
+        match category {
+            ColumnCategory::String => self.strings,
+            ColumnCategory::Numeric => self.numeric,
+            ColumnCategory::Binary => self.binary,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnCategory {
+    String,
+    Numeric,
+    Binary,
+}
 
-    Ok(writer)
+/// Classifies a column for the purpose of choosing its compression. Byte arrays annotated as text
+/// are strings, decimals are numeric regardless of their physical type and all other byte arrays
+/// are binary. Everything else is numeric.
+fn category(field: &Type) -> ColumnCategory {
+    let info = field.get_basic_info();
+    let is_string = matches!(
+        info.logical_type(),
+        Some(LogicalType::String | LogicalType::Json | LogicalType::Enum)
+    ) || matches!(
+        info.converted_type(),
+        ConvertedType::UTF8 | ConvertedType::JSON | ConvertedType::ENUM
+    );
+    let is_decimal = matches!(info.logical_type(), Some(LogicalType::Decimal { .. }))
+        || info.converted_type() == ConvertedType::DECIMAL;
+    match field.get_physical_type() {
+        PhysicalType::BYTE_ARRAY | PhysicalType::FIXED_LEN_BYTE_ARRAY if is_string => {
+            ColumnCategory::String
+        }
+        PhysicalType::BYTE_ARRAY | PhysicalType::FIXED_LEN_BYTE_ARRAY if !is_decimal => {
+            ColumnCategory::Binary
+        }
+        _ => ColumnCategory::Numeric,
+    }
 }
 
 /// Writes row groups to the output, which could be either standard out, a single parquet file or
 /// multiple parquet files with incrementing number suffixes.
-pub trait ParquetOutput {
+pub trait ParquetOutput: Send {
     /// Retrieve the next row group writer. May trigger creation of a new file if limit of the
     /// previous one is reached.
     ///
@@ -71,23 +457,84 @@ pub trait ParquetOutput {
     fn write_row_group(
         &mut self,
         num_batch: u32,
-        export_nth_column: ColumnExporter,
-    ) -> Result<(), Error>;
+        export_nth_column: &mut ColumnExporter,
+    ) -> Result<WrittenRowGroup, Error>;
 
     /// Indicate that no further output is written. this triggers writing the parquet meta data and
     /// potentially persists a temporary file.
-    fn close(self) -> Result<(), Error>;
+    fn close(self) -> Result<OutputSize, Error>;
+
+    fn close_box(self: Box<Self>) -> Result<OutputSize, Error>;
+}
 
-    fn close_box(self: Box<Self>) -> Result<(), Error>;
+/// Files and bytes written into an output, once it is closed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OutputSize {
+    /// Number of files written. Zero if the output is standard out.
+    pub num_files: u64,
+    pub num_bytes: u64,
+}
+
+impl OutputSize {
+    pub(super) fn add(&mut self, other: OutputSize) {
+        self.num_files += other.num_files;
+        self.num_bytes += other.num_bytes;
+    }
+}
+
+/// Paths of the files created by outputs. Clones share the same list, so files written by a failed
+/// attempt can still be removed after the output has been dropped.
+#[derive(Clone, Default)]
+pub struct CreatedFiles(Arc<Mutex<Vec<PathBuf>>>);
+
+impl CreatedFiles {
+    pub(super) fn push(&self, path: PathBuf) {
+        self.0.lock().unwrap().push(path);
+    }
+
+    /// Removes all files created so far, together with their checksum files, and forgets them.
+    /// Files which no longer exist, e.g. because an unfinished file has already been discarded, are
+    /// ignored.
+    pub fn remove_all(&self) -> Result<(), Error> {
+        for path in self.0.lock().unwrap().drain(..) {
+            for path in [sidecar_path(&path), path] {
+                match fs::remove_file(&path) {
+                    Ok(()) => info!("Removed '{}'.", path.display()),
+                    Err(error) if error.kind() == io::ErrorKind::NotFound => (),
+                    Err(error) => {
+                        return Err(Error::from(error)
+                            .context(format!("Could not remove '{}'.", path.display())))
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Forget about the files created so far, without removing them.
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
+
+/// Describes a row group after it has been written to the output.
+pub struct WrittenRowGroup {
+    pub metadata: RowGroupMetaDataPtr,
+    /// Path of the file the row group has been written to. `None` for standard out.
+    pub path: Option<PathBuf>,
+    /// `true` if the file has been closed after this row group, because it reached its size limit.
+    pub file_split: bool,
 }
 
 /// Wraps parquet SerializedFileWriter. Handles splitting into new files after maximum amount of
-/// batches is reached.
+/// batches is reached. Row groups are written strictly in the order they are received, so each file
+/// holds a contiguous part of the result set in source order.
 struct FileWriter {
     base_path: PathBuf,
     schema: Arc<Type>,
     properties: Arc<WriterProperties>,
     file_size: FileSizeLimit,
+    max_file_count: Option<MaxFileCount>,
     num_file: u32,
     /// Length of the suffix, appended to the end of a file in case they are numbered.
     suffix_length: usize,
@@ -95,23 +542,32 @@ struct FileWriter {
     /// closed, due to the size threshold, but a new row group has not yet been received from the
     /// database.
     current_file: Option<CurrentFile>,
+    emit_hadoop_crc: bool,
+    created_files: CreatedFiles,
+    /// Files which have already been finalized.
+    written: OutputSize,
 }
 
 impl FileWriter {
     pub fn new(
         path: PathBuf,
         schema: Arc<Type>,
-        options: ParquetWriterOptions,
+        file_size: FileSizeLimit,
+        options: &ParquetWriterOptions,
         properties: Arc<WriterProperties>,
     ) -> Result<Self, Error> {
         let mut file_writer = Self {
             base_path: path,
             schema,
             properties,
-            file_size: options.file_size,
+            file_size,
+            max_file_count: options.max_file_count,
             num_file: 0,
             suffix_length: options.suffix_length,
             current_file: None,
+            emit_hadoop_crc: options.emit_hadoop_crc,
+            created_files: options.created_files.clone(),
+            written: OutputSize::default(),
         };
 
         if !options.no_empty_file {
@@ -122,15 +578,22 @@ impl FileWriter {
     }
 
     fn next_file(&mut self) -> Result<(), Error> {
+        if let Some(max_file_count) = &self.max_file_count {
+            max_file_count.check_next_file(self.num_file)?;
+        }
         let suffix = self
             .file_size
             .output_is_splitted()
             .then_some((self.num_file + 1, self.suffix_length));
         let path = Self::current_path(&self.base_path, suffix)?;
+        if !is_fifo(&path) {
+            self.created_files.push(path.clone());
+        }
         self.current_file = Some(CurrentFile::new(
             path,
             self.schema.clone(),
             self.properties.clone(),
+            self.emit_hadoop_crc,
         )?);
         self.num_file += 1;
         Ok(())
@@ -150,43 +613,109 @@ impl ParquetOutput for FileWriter {
     fn write_row_group(
         &mut self,
         num_batch: u32,
-        column_exporter: ColumnExporter,
-    ) -> Result<(), Error> {
+        column_exporter: &mut ColumnExporter,
+    ) -> Result<WrittenRowGroup, Error> {
         // There is no file. Let us create one so we can write the row group.
         if self.current_file.is_none() {
             self.next_file()?
         }
 
         // Write next row group
-        let file_size = self
-            .current_file
-            .as_mut()
-            .unwrap()
-            .write_row_group(column_exporter)?;
+        let current_file = self.current_file.as_mut().unwrap();
+        let metadata = current_file.write_row_group(column_exporter)?;
+        let path = current_file.path().to_owned();
 
-        if self
+        let file_split = self
             .file_size
-            .should_start_new_file(num_batch + 1, file_size)
-        {
-            self.current_file.take().unwrap().finalize()?;
+            .should_start_new_file(num_batch + 1, current_file.file_size())
+            && self
+                .max_file_count
+                .as_mut()
+                .is_none_or(|max_file_count| max_file_count.allow_split(self.num_file));
+        if file_split {
+            let num_bytes = self.current_file.take().unwrap().finalize()?;
+            self.written.add(OutputSize {
+                num_files: 1,
+                num_bytes,
+            });
         }
 
-        Ok(())
+        Ok(WrittenRowGroup {
+            metadata,
+            path: Some(path),
+            file_split,
+        })
     }
 
-    fn close(self) -> Result<(), Error> {
+    fn close(mut self) -> Result<OutputSize, Error> {
         // An active file might, or might not exist at this point, depending on whether the
         // file splitting due to size thresholds coincides with the data source being consumed and
         // all data being read from it. If our data source ran out of data, just after we closed the
         // current file due to its size threshold it is `None`. In this case there is nothing to do
         // though.
         if let Some(open_file) = self.current_file {
-            open_file.finalize()?;
+            let num_bytes = open_file.finalize()?;
+            self.written.add(OutputSize {
+                num_files: 1,
+                num_bytes,
+            });
         }
-        Ok(())
+        Ok(self.written)
+    }
+
+    fn close_box(self: Box<Self>) -> Result<OutputSize, Error> {
+        self.close()
+    }
+}
+
+/// Writes every row group into several outputs, e.g. to compare the effect of different
+/// compressions.
+struct FanOut {
+    /// The first output is the one specified by the user as output argument, the others have
+    /// been specified using `--also-write`.
+    outputs: Vec<Box<dyn ParquetOutput>>,
+    /// Compression and path of each output written into a single file. Their sizes are reported
+    /// once all outputs are closed.
+    files: Vec<(Compression, PathBuf)>,
+}
+
+impl ParquetOutput for FanOut {
+    fn write_row_group(
+        &mut self,
+        num_batch: u32,
+        column_exporter: &mut ColumnExporter,
+    ) -> Result<WrittenRowGroup, Error> {
+        let mut outputs = self.outputs.iter_mut();
+        // Report the row group of the primary output.
+        let written = outputs
+            .next()
+            .unwrap()
+            .write_row_group(num_batch, column_exporter)?;
+        for output in outputs {
+            output.write_row_group(num_batch, column_exporter)?;
+        }
+        Ok(written)
+    }
+
+    fn close(self) -> Result<OutputSize, Error> {
+        let mut written = OutputSize::default();
+        for output in self.outputs {
+            written.add(output.close_box()?);
+        }
+        for (compression, path) in self.files {
+            let size = fs::metadata(&path)
+                .with_context(|| format!("Could not determine size of '{}'.", path.display()))?
+                .len();
+            info!(
+                "{}: {} with {compression}.",
+                path.display(),
+                ByteSize::b(size)
+            );
+        }
+        Ok(written)
     }
 
-    fn close_box(self: Box<Self>) -> Result<(), Error> {
+    fn close_box(self: Box<Self>) -> Result<OutputSize, Error> {
         self.close()
     }
 }
@@ -209,30 +738,65 @@ impl ParquetOutput for StandardOut {
     fn write_row_group(
         &mut self,
         _num_batch: u32,
-        mut column_exporter: ColumnExporter,
-    ) -> Result<(), Error> {
-        let mut row_group_writer = self.writer.next_row_group()?;
-        let mut col_index = 0;
-        while let Some(mut column_writer) = row_group_writer.next_column()? {
-            column_exporter.export_nth_column(col_index, &mut column_writer)?;
-            column_writer.close()?;
-            col_index += 1;
-        }
-        row_group_writer.close()?;
-        Ok(())
+        column_exporter: &mut ColumnExporter,
+    ) -> Result<WrittenRowGroup, Error> {
+        let metadata = column_exporter.write_row_group(&mut self.writer)?;
+        Ok(WrittenRowGroup {
+            metadata,
+            path: None,
+            file_split: false,
+        })
     }
 
-    fn close(self) -> Result<(), Error> {
-        self.writer.close()?;
-        Ok(())
+    fn close(mut self) -> Result<OutputSize, Error> {
+        self.writer.finish()?;
+        Ok(OutputSize {
+            num_files: 0,
+            num_bytes: self.writer.bytes_written().try_into().unwrap(),
+        })
     }
 
-    fn close_box(self: Box<Self>) -> Result<(), Error> {
+    fn close_box(self: Box<Self>) -> Result<OutputSize, Error> {
         self.close()
     }
 }
 
-fn path_with_suffix(path: &Path, num_file: u32, suffix_length: usize) -> Result<PathBuf, Error> {
+/// Translates a column name passed in a per column option into a column path. If a separator is
+/// specified the name is split at each occurrence of it, unless the separator is escaped with a
+/// backslash (`\`).
+fn column_path(name: &str, separator: Option<&str>) -> ColumnPath {
+    let Some(separator) = separator.filter(|sep| !sep.is_empty()) else {
+        return ColumnPath::new(vec![name.to_owned()]);
+    };
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut rest = name;
+    while !rest.is_empty() {
+        if let Some(after_escape) = rest.strip_prefix('\\') {
+            if let Some(after_separator) = after_escape.strip_prefix(separator) {
+                current.push_str(separator);
+                rest = after_separator;
+                continue;
+            }
+        }
+        if let Some(after_separator) = rest.strip_prefix(separator) {
+            parts.push(std::mem::take(&mut current));
+            rest = after_separator;
+            continue;
+        }
+        let next = rest.chars().next().unwrap();
+        current.push(next);
+        rest = &rest[next.len_utf8()..];
+    }
+    parts.push(current);
+    ColumnPath::new(parts)
+}
+
+pub(super) fn path_with_suffix(
+    path: &Path,
+    num_file: u32,
+    suffix_length: usize,
+) -> Result<PathBuf, Error> {
     let suffix = format!("_{:0width$}", num_file, width = suffix_length);
     let mut stem = path
         .file_stem()
@@ -246,3 +810,87 @@ fn path_with_suffix(path: &Path, num_file: u32, suffix_length: usize) -> Result<
     }
     Ok(path_with_suffix)
 }
+
+#[cfg(test)]
+mod tests {
+    use parquet::{
+        basic::{ConvertedType, Repetition, Type as PhysicalType},
+        schema::types::{ColumnPath, Type},
+    };
+
+    use super::{category, column_path, ColumnCategory, CreatedFiles};
+
+    fn column(physical_type: PhysicalType, converted_type: ConvertedType) -> Type {
+        let mut builder = Type::primitive_type_builder("a", physical_type)
+            .with_repetition(Repetition::OPTIONAL)
+            .with_converted_type(converted_type);
+        if converted_type == ConvertedType::DECIMAL {
+            builder = builder.with_precision(10).with_scale(2).with_length(5);
+        }
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn classify_columns_for_compression() {
+        assert_eq!(
+            ColumnCategory::String,
+            category(&column(PhysicalType::BYTE_ARRAY, ConvertedType::UTF8))
+        );
+        assert_eq!(
+            ColumnCategory::Binary,
+            category(&column(PhysicalType::BYTE_ARRAY, ConvertedType::NONE))
+        );
+        assert_eq!(
+            ColumnCategory::Numeric,
+            category(&column(
+                PhysicalType::FIXED_LEN_BYTE_ARRAY,
+                ConvertedType::DECIMAL
+            ))
+        );
+        assert_eq!(
+            ColumnCategory::Numeric,
+            category(&column(PhysicalType::INT32, ConvertedType::DATE))
+        );
+    }
+
+    #[test]
+    fn remove_created_files_with_checksums() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("out_01.par");
+        let second = dir.path().join("out_02.par");
+        std::fs::write(&first, b"PAR1").unwrap();
+        std::fs::write(dir.path().join(".out_01.par.crc"), b"crc\0").unwrap();
+        let created_files = CreatedFiles::default();
+        created_files.clone().push(first);
+        // Unfinished files are already discarded if the output is dropped.
+        created_files.clone().push(second);
+
+        created_files.remove_all().unwrap();
+
+        assert_eq!(0, std::fs::read_dir(dir.path()).unwrap().count());
+    }
+
+    #[test]
+    fn column_path_without_separator() {
+        assert_eq!(
+            ColumnPath::new(vec!["a.b".to_owned()]),
+            column_path("a.b", None)
+        );
+    }
+
+    #[test]
+    fn column_path_with_separator() {
+        assert_eq!(
+            ColumnPath::new(vec!["a".to_owned(), "b".to_owned()]),
+            column_path("a.b", Some("."))
+        );
+    }
+
+    #[test]
+    fn column_path_with_escaped_separator() {
+        assert_eq!(
+            ColumnPath::new(vec!["a.b".to_owned(), "c".to_owned()]),
+            column_path("a\\.b.c", Some("."))
+        );
+    }
+}
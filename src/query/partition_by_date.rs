@@ -0,0 +1,493 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::BufWriter,
+    path::PathBuf,
+    sync::{
+        mpsc::{sync_channel, SyncSender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use anyhow::{anyhow, Context, Error};
+use bytes::Bytes;
+use chrono::{DateTime, NaiveDate, Utc};
+use log::info;
+use parquet::{
+    basic::{ConvertedType, LogicalType, TimeUnit, Type as PhysicalType},
+    file::{
+        properties::WriterProperties,
+        reader::{FileReader, SerializedFileReader},
+        writer::SerializedFileWriter,
+    },
+    schema::types::{ColumnDescriptor, SchemaDescriptor, Type},
+};
+
+use crate::enum_args::{DateGranularity, PartitionByDate};
+
+use super::{
+    client_sort::{write_row_group, Cell, RowGroup, Value},
+    parquet_writer::{path_with_suffix, CreatedFiles, OutputSize, ParquetOutput, WrittenRowGroup},
+    table_strategy::ColumnExporter,
+};
+
+/// Name of the partition holding rows whose date is NULL. Same as the one used by Hive.
+const NULL_PARTITION: &str = "dt=__HIVE_DEFAULT_PARTITION__";
+
+/// Operations queued for each thread writing partition files. Once reached, the conversion of the
+/// next batch waits for the thread to catch up.
+const MAX_QUEUED_OPERATIONS: usize = 16;
+
+/// Writes the rows into Hive style partitions, i.e. one directory for each date like
+/// `dt=2024-01-15/part.par` below the output directory. Each batch is converted into a parquet row
+/// group first and read back, so all column strategies apply. The rows of the batch are then split
+/// by the truncated date of the partition column, which is written as part of the data, too.
+pub struct DatePartitions {
+    /// Directory holding one subdirectory for each partition.
+    directory: PathBuf,
+    schema: Arc<Type>,
+    properties: Arc<WriterProperties>,
+    /// Index of the leaf column the partition is derived from.
+    column: usize,
+    kind: DateKind,
+    granularity: DateGranularity,
+    /// Files open at the same time. If a row of another partition is written, the least recently
+    /// written file is closed first.
+    max_open: usize,
+    /// Open files, the least recently written one first.
+    open: Vec<PartitionFile>,
+    /// Number of files started so far for each partition. A partition gets another file if its
+    /// previous one had to be closed in order to stay below `max_open`.
+    num_files: HashMap<String, u32>,
+    /// Number of files started so far for all partitions. Identifies the next file.
+    num_files_total: usize,
+    /// Name of the first file in each partition directory, e.g. `part.par`.
+    file_name: String,
+    suffix_length: usize,
+    created_files: CreatedFiles,
+    /// Writes the files, either on this thread or on a pool of threads.
+    files: Files,
+}
+
+struct PartitionFile {
+    partition: String,
+    /// Identifies the file in operations on it.
+    file: usize,
+}
+
+/// Physical representation of the partition column.
+enum DateKind {
+    /// Days since epoch.
+    Date,
+    /// Timestamp as `INT64` with unit.
+    Timestamp(TimeUnit),
+    /// Legacy `INT96` timestamp without time zone.
+    Int96,
+}
+
+impl DatePartitions {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        directory: PathBuf,
+        schema: Arc<Type>,
+        properties: Arc<WriterProperties>,
+        partition_by: &PartitionByDate,
+        max_open: usize,
+        concurrency: usize,
+        extension: &str,
+        suffix_length: usize,
+        created_files: CreatedFiles,
+    ) -> Result<Self, Error> {
+        let descriptor = SchemaDescriptor::new(schema.clone());
+        let column = descriptor
+            .columns()
+            .iter()
+            .position(|column| column.path().string() == partition_by.column)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Unknown column '{}' in `--partition-by-date`.",
+                    partition_by.column
+                )
+            })?;
+        let kind = DateKind::new(descriptor.column(column).as_ref()).ok_or_else(|| {
+            anyhow!(
+                "Column '{}' of `--partition-by-date` must be a date or a timestamp.",
+                partition_by.column
+            )
+        })?;
+        fs::create_dir_all(&directory).with_context(|| {
+            format!(
+                "Could not create output directory '{}'.",
+                directory.display()
+            )
+        })?;
+        let files = if concurrency > 1 {
+            Files::Concurrent(Workers::new(
+                concurrency,
+                schema.clone(),
+                properties.clone(),
+            ))
+        } else {
+            Files::Sequential(PartitionFiles::new(schema.clone(), properties.clone()))
+        };
+        Ok(Self {
+            directory,
+            schema,
+            properties,
+            column,
+            kind,
+            granularity: partition_by.granularity,
+            max_open: max_open.max(1),
+            open: Vec::new(),
+            num_files: HashMap::new(),
+            num_files_total: 0,
+            file_name: format!("part.{extension}"),
+            suffix_length,
+            created_files,
+            files,
+        })
+    }
+
+    /// Name of the partition directory of a row, e.g. `dt=2024-01`.
+    fn partition(&self, row: &[Cell]) -> Result<String, Error> {
+        let Some(value) = &row[self.column].value else {
+            return Ok(NULL_PARTITION.to_owned());
+        };
+        let date = self
+            .kind
+            .date(value)
+            .ok_or_else(|| anyhow!("Date of partition column out of range."))?;
+        let format = match self.granularity {
+            DateGranularity::Day => "%Y-%m-%d",
+            DateGranularity::Month => "%Y-%m",
+            DateGranularity::Year => "%Y",
+        };
+        Ok(format!("dt={}", date.format(format)))
+    }
+
+    /// File of `partition`, marked as the most recently written one. Opens a new file if
+    /// necessary. Which files are opened and closed only depends on the order of the rows, never
+    /// on the number of threads writing them.
+    fn file(&mut self, partition: &str) -> Result<usize, Error> {
+        if let Some(index) = self
+            .open
+            .iter()
+            .position(|file| file.partition == partition)
+        {
+            let file = self.open.remove(index);
+            self.open.push(file);
+        } else {
+            if self.open.len() >= self.max_open {
+                let file = self.open.remove(0);
+                self.files
+                    .execute(FileOperation::Close { file: file.file })?;
+            }
+            let num_file = self.num_files.entry(partition.to_owned()).or_default();
+            *num_file += 1;
+            let directory = self.directory.join(partition);
+            fs::create_dir_all(&directory)?;
+            let path = if *num_file == 1 {
+                directory.join(&self.file_name)
+            } else {
+                path_with_suffix(
+                    &directory.join(&self.file_name),
+                    *num_file,
+                    self.suffix_length,
+                )?
+            };
+            self.created_files.push(path.clone());
+            let file = self.num_files_total;
+            self.num_files_total += 1;
+            self.files.execute(FileOperation::Open { file, path })?;
+            self.open.push(PartitionFile {
+                partition: partition.to_owned(),
+                file,
+            });
+        }
+        Ok(self.open.last().unwrap().file)
+    }
+}
+
+impl ParquetOutput for DatePartitions {
+    fn write_row_group(
+        &mut self,
+        _num_batch: u32,
+        column_exporter: &mut ColumnExporter,
+    ) -> Result<WrittenRowGroup, Error> {
+        let mut writer =
+            SerializedFileWriter::new(Vec::new(), self.schema.clone(), self.properties.clone())?;
+        let metadata = column_exporter.write_row_group(&mut writer)?;
+        let reader = SerializedFileReader::new(Bytes::from(writer.into_inner()?))?;
+        let rows = RowGroup::new(reader.get_row_group(0)?)?.read_all()?;
+
+        // Partitions in order of their first row, so the output does not depend on hashing.
+        let mut partitions: Vec<(String, Vec<Vec<Cell>>)> = Vec::new();
+        for row in rows {
+            let partition = self.partition(&row)?;
+            match partitions.iter_mut().find(|(name, _)| *name == partition) {
+                Some((_, rows)) => rows.push(row),
+                None => partitions.push((partition, vec![row])),
+            }
+        }
+        for (partition, rows) in partitions {
+            let file = self.file(&partition)?;
+            self.files.execute(FileOperation::Write { file, rows })?;
+        }
+
+        Ok(WrittenRowGroup {
+            metadata,
+            path: None,
+            file_split: false,
+        })
+    }
+
+    fn close(mut self) -> Result<OutputSize, Error> {
+        for file in std::mem::take(&mut self.open) {
+            self.files
+                .execute(FileOperation::Close { file: file.file })?;
+        }
+        let written = self.files.finish()?;
+        info!(
+            "Wrote {} partitions into '{}'.",
+            self.num_files.len(),
+            self.directory.display()
+        );
+        Ok(written)
+    }
+
+    fn close_box(self: Box<Self>) -> Result<OutputSize, Error> {
+        self.close()
+    }
+}
+
+/// Operation on a partition file. All operations on the same file are executed in order by the
+/// same thread, so the contents of the files do not depend on the number of threads.
+enum FileOperation {
+    Open { file: usize, path: PathBuf },
+    Write { file: usize, rows: Vec<Vec<Cell>> },
+    Close { file: usize },
+}
+
+impl FileOperation {
+    fn file(&self) -> usize {
+        match self {
+            FileOperation::Open { file, .. }
+            | FileOperation::Write { file, .. }
+            | FileOperation::Close { file } => *file,
+        }
+    }
+}
+
+/// Writers of the partition files, either owned by this thread or distributed over a pool of
+/// threads.
+enum Files {
+    Sequential(PartitionFiles),
+    Concurrent(Workers),
+}
+
+impl Files {
+    fn execute(&mut self, operation: FileOperation) -> Result<(), Error> {
+        match self {
+            Files::Sequential(files) => files.execute(operation),
+            Files::Concurrent(workers) => workers.send(operation),
+        }
+    }
+
+    /// Waits for all operations to complete. All files must have been closed before.
+    fn finish(self) -> Result<OutputSize, Error> {
+        match self {
+            Files::Sequential(files) => Ok(files.written),
+            Files::Concurrent(mut workers) => workers.join(),
+        }
+    }
+}
+
+/// Open partition files of one thread.
+struct PartitionFiles {
+    schema: Arc<Type>,
+    properties: Arc<WriterProperties>,
+    writers: HashMap<usize, SerializedFileWriter<BufWriter<File>>>,
+    /// Files which have already been closed.
+    written: OutputSize,
+}
+
+impl PartitionFiles {
+    fn new(schema: Arc<Type>, properties: Arc<WriterProperties>) -> Self {
+        Self {
+            schema,
+            properties,
+            writers: HashMap::new(),
+            written: OutputSize::default(),
+        }
+    }
+
+    fn execute(&mut self, operation: FileOperation) -> Result<(), Error> {
+        match operation {
+            FileOperation::Open { file, path } => {
+                let output = File::create(&path).with_context(|| {
+                    format!("Could not create output file '{}'", path.display())
+                })?;
+                let writer = SerializedFileWriter::new(
+                    BufWriter::new(output),
+                    self.schema.clone(),
+                    self.properties.clone(),
+                )?;
+                self.writers.insert(file, writer);
+            }
+            FileOperation::Write { file, rows } => {
+                let writer = self
+                    .writers
+                    .get_mut(&file)
+                    .expect("Partition file must be open before it is written.");
+                write_row_group(writer, &rows)?;
+            }
+            FileOperation::Close { file } => {
+                let mut writer = self
+                    .writers
+                    .remove(&file)
+                    .expect("Partition file must be open before it is closed.");
+                writer.finish()?;
+                self.written.num_files += 1;
+                self.written.num_bytes += writer.bytes_written() as u64;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Pool of threads writing partition files. Each file is assigned to one thread, which owns its
+/// writer, so encoding, compression and I/O of different partitions overlap.
+struct Workers {
+    /// Bounded queues of the threads, so at most `MAX_QUEUED_OPERATIONS` batches of rows are held
+    /// in memory for each thread.
+    senders: Vec<SyncSender<FileOperation>>,
+    threads: Vec<JoinHandle<Result<OutputSize, Error>>>,
+}
+
+impl Workers {
+    fn new(num_threads: usize, schema: Arc<Type>, properties: Arc<WriterProperties>) -> Self {
+        let (senders, threads) = (0..num_threads)
+            .map(|_| {
+                let (sender, receiver) = sync_channel(MAX_QUEUED_OPERATIONS);
+                let mut files = PartitionFiles::new(schema.clone(), properties.clone());
+                let thread = thread::spawn(move || {
+                    for operation in receiver {
+                        files.execute(operation)?;
+                    }
+                    Ok(files.written)
+                });
+                (sender, thread)
+            })
+            .unzip();
+        Self { senders, threads }
+    }
+
+    /// Queues the operation for the thread owning the file.
+    fn send(&mut self, operation: FileOperation) -> Result<(), Error> {
+        let index = operation.file() % self.senders.len();
+        if self.senders[index].send(operation).is_err() {
+            // A thread only stops receiving after it failed. Report its error.
+            self.join()?;
+            return Err(anyhow!(
+                "Thread writing partition files stopped unexpectedly."
+            ));
+        }
+        Ok(())
+    }
+
+    /// Waits for all threads to execute their queued operations. Returns the first error, if any.
+    fn join(&mut self) -> Result<OutputSize, Error> {
+        self.senders.clear();
+        let mut written = OutputSize::default();
+        let mut first_error = None;
+        for thread in self.threads.drain(..) {
+            match thread
+                .join()
+                .expect("Thread writing partition files must not panic.")
+            {
+                Ok(size) => written.add(size),
+                Err(error) => {
+                    first_error.get_or_insert(error);
+                }
+            }
+        }
+        match first_error {
+            Some(error) => Err(error),
+            None => Ok(written),
+        }
+    }
+}
+
+impl Drop for Workers {
+    fn drop(&mut self) {
+        // Do not leave threads writing files behind, e.g. if the export failed and the created
+        // files are about to be removed.
+        self.senders.clear();
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl DateKind {
+    fn new(column: &ColumnDescriptor) -> Option<Self> {
+        match (column.physical_type(), column.logical_type()) {
+            (PhysicalType::INT32, Some(LogicalType::Date)) => Some(DateKind::Date),
+            (PhysicalType::INT64, Some(LogicalType::Timestamp { unit, .. })) => {
+                Some(DateKind::Timestamp(unit))
+            }
+            (PhysicalType::INT96, _) => Some(DateKind::Int96),
+            (PhysicalType::INT32, None) if column.converted_type() == ConvertedType::DATE => {
+                Some(DateKind::Date)
+            }
+            _ => None,
+        }
+    }
+
+    /// Date the partition is derived from. Timestamps adjusted to UTC are truncated in UTC. All
+    /// other timestamps hold the local time assumed by the data source, which is truncated as it
+    /// is. `None` if out of range.
+    fn date(&self, value: &Value) -> Option<NaiveDate> {
+        let timestamp: DateTime<Utc> = match (self, value) {
+            (DateKind::Date, Value::Int32(days)) => {
+                return NaiveDate::from_ymd_opt(1970, 1, 1)?
+                    .checked_add_signed(chrono::Duration::days((*days).into()));
+            }
+            (DateKind::Timestamp(unit), Value::Int64(value)) => match unit {
+                TimeUnit::MILLIS(_) => DateTime::from_timestamp_millis(*value)?,
+                TimeUnit::MICROS(_) => DateTime::from_timestamp_micros(*value)?,
+                TimeUnit::NANOS(_) => DateTime::from_timestamp_nanos(*value),
+            },
+            (DateKind::Int96, Value::Int96(value)) => {
+                let (seconds, nanos) = value.to_seconds_and_nanos();
+                DateTime::from_timestamp(seconds, nanos as u32)?
+            }
+            _ => return None,
+        };
+        Some(timestamp.date_naive())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parquet::{basic::TimeUnit, format::MicroSeconds};
+
+    use crate::query::client_sort::Value;
+
+    use super::DateKind;
+
+    #[test]
+    fn truncate_timestamps_to_dates() {
+        let date = |kind: &DateKind, value| kind.date(&value).unwrap().to_string();
+        assert_eq!("2024-01-15", date(&DateKind::Date, Value::Int32(19737)));
+        let timestamp = DateKind::Timestamp(TimeUnit::MICROS(MicroSeconds {}));
+        // 2024-01-15 23:59:59.999999 UTC
+        assert_eq!(
+            "2024-01-15",
+            date(&timestamp, Value::Int64(1_705_363_199_999_999))
+        );
+        // 1969-12-31 23:59:59 UTC
+        assert_eq!("1969-12-31", date(&timestamp, Value::Int64(-1_000_000)));
+    }
+}
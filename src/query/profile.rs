@@ -0,0 +1,209 @@
+use std::{fmt::Write as _, fs, path::Path};
+
+use anyhow::{Context, Error};
+
+use crate::column_profile::{ColumnProfile, Order, Ordered, Samples};
+
+use super::{delimited::json_of_value, stats_only::json_string};
+
+// Rendering the profiles depends on the output formats, so it lives here rather than next to the
+// statistics in `crate::column_profile`.
+
+impl Samples {
+    fn to_json(&self) -> String {
+        let values: Vec<String> = self
+            .values
+            .iter()
+            // Values the export wrote, but which can not be rendered (e.g. dates beyond the range
+            // of chrono), are no useful examples anyway.
+            .filter_map(|sample| json_of_value(&self.parquet_type, &sample.as_value()).ok())
+            .collect();
+        format!("[{}]", values.join(","))
+    }
+}
+
+impl ColumnProfile {
+    fn render(&self, value: &Option<Ordered<Vec<u8>>>) -> String {
+        match value {
+            Some(value) => self.render_value(value),
+            None => "null".to_owned(),
+        }
+    }
+
+    fn render_value(&self, value: &Ordered<Vec<u8>>) -> String {
+        match (value, self.order) {
+            (Ordered::Bool(value), _) => value.to_string(),
+            (Ordered::Int(value), Order::Decimal { scale }) => with_scale(*value, scale),
+            (Ordered::Int(value), _) => value.to_string(),
+            (Ordered::Float(value), _) if value.is_finite() => value.to_string(),
+            (Ordered::Float(value), _) => json_string(&value.to_string()),
+            (Ordered::Bytes(bytes), Order::Bytes { is_text: true }) => {
+                json_string(&String::from_utf8_lossy(bytes))
+            }
+            (Ordered::Bytes(bytes), _) => {
+                let mut hex = "0x".to_owned();
+                for byte in bytes {
+                    write!(hex, "{byte:02x}").unwrap();
+                }
+                json_string(&hex)
+            }
+        }
+    }
+
+    fn to_json(&self, name: &str) -> String {
+        let samples = match &self.samples {
+            Some(samples) => format!(",\"samples\":{}", samples.to_json()),
+            None => String::new(),
+        };
+        format!(
+            "{{\"name\":{},\"null_count\":{},\"distinct_count_estimate\":{},\"min\":{},\
+            \"max\":{}{samples}}}",
+            json_string(name),
+            self.null_count,
+            self.distinct_count_estimate(),
+            self.render(&self.min),
+            self.render(&self.max)
+        )
+    }
+
+    /// Number of rows for each value of the column, sorted by descending count, one line each.
+    /// `None` if no histogram has been requested for the column.
+    pub fn histogram_report(&self, name: &str) -> Option<String> {
+        let histogram = self.histogram.as_ref()?;
+        let mut counts: Vec<_> = histogram.counts.iter().collect();
+        counts.sort_by(|(_, a), (_, b)| b.cmp(a));
+        let mut report = format!("Number of rows per value of column '{name}':\n");
+        for (value, count) in counts {
+            writeln!(report, "  {}: {count}", self.render_value(value)).unwrap();
+        }
+        if self.null_count != 0 {
+            writeln!(report, "  NULL: {}", self.null_count).unwrap();
+        }
+        if histogram.num_other != 0 {
+            writeln!(
+                report,
+                "  other (beyond the first {} distinct values): {}",
+                histogram.max_values, histogram.num_other
+            )
+            .unwrap();
+        }
+        Some(report)
+    }
+}
+
+/// Renders an unscaled decimal as a JSON number with `scale` fractional digits.
+fn with_scale(value: i128, scale: u32) -> String {
+    if scale == 0 {
+        return value.to_string();
+    }
+    let digits = format!(
+        "{:0>width$}",
+        value.unsigned_abs(),
+        width = scale as usize + 1
+    );
+    let (integer, fraction) = digits.split_at(digits.len() - scale as usize);
+    let sign = if value < 0 { "-" } else { "" };
+    format!("{sign}{integer}.{fraction}")
+}
+
+/// Writes the profile of each column as JSON into the file at `path`.
+pub fn write_profile<'a>(
+    path: &Path,
+    num_rows: u64,
+    columns: impl Iterator<Item = (&'a str, &'a ColumnProfile)>,
+) -> Result<(), Error> {
+    let columns: Vec<String> = columns
+        .map(|(name, profile)| profile.to_json(name))
+        .collect();
+    let json = format!(
+        "{{\"num_rows\":{num_rows},\"columns\":[{}]}}\n",
+        columns.join(",")
+    );
+    fs::write(path, json)
+        .with_context(|| format!("Could not write profile to '{}'.", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use parquet::schema::parser::parse_message_type;
+
+    use crate::{column_profile::ColumnProfile, parquet_buffer::ProfileValue};
+
+    use super::with_scale;
+
+    fn profile(column: &str) -> ColumnProfile {
+        let schema = parse_message_type(&format!("message schema {{ {column} }}")).unwrap();
+        ColumnProfile::new(&schema.get_fields()[0])
+    }
+
+    #[test]
+    fn min_max_and_null_count() {
+        let mut profile = profile("OPTIONAL INT32 a (INTEGER(32,false));");
+        profile.add(ProfileValue::I32(1));
+        profile.add(ProfileValue::I32(-1)); // u32::MAX
+        profile.add_null();
+        assert_eq!(
+            "{\"name\":\"a\",\"null_count\":1,\"distinct_count_estimate\":2,\"min\":1,\
+            \"max\":4294967295}",
+            profile.to_json("a")
+        );
+    }
+
+    #[test]
+    fn render_decimals_and_text() {
+        let mut decimal = profile("OPTIONAL FIXED_LEN_BYTE_ARRAY (2) a (DECIMAL(4,2));");
+        decimal.add(ProfileValue::Bytes(&[0xff, 0x6a])); // -1.50
+        decimal.add(ProfileValue::Bytes(&[0x00, 0x05])); // 0.05
+        assert!(decimal
+            .to_json("a")
+            .ends_with("\"min\":-1.50,\"max\":0.05}"));
+
+        let mut text = profile("OPTIONAL BYTE_ARRAY a (UTF8);");
+        text.add(ProfileValue::Bytes(b"b"));
+        text.add(ProfileValue::Bytes(b"a"));
+        assert!(text.to_json("a").ends_with("\"min\":\"a\",\"max\":\"b\"}"));
+
+        assert_eq!("12", with_scale(12, 0));
+        assert_eq!("-0.012", with_scale(-12, 3));
+    }
+
+    #[test]
+    fn count_rows_per_value() {
+        let mut profile = profile("OPTIONAL BYTE_ARRAY a (UTF8);").with_histogram(2);
+        for value in ["x", "y", "x", "z", "x", "w"] {
+            profile.add(ProfileValue::Bytes(value.as_bytes()));
+        }
+        profile.add_null();
+        assert_eq!(
+            "Number of rows per value of column 'a':\n  \"x\": 3\n  \"y\": 1\n  NULL: 1\n  \
+            other (beyond the first 2 distinct values): 2\n",
+            profile.histogram_report("a").unwrap()
+        );
+    }
+
+    #[test]
+    fn sample_distinct_values_formatted_by_logical_type() {
+        let schema = parse_message_type(
+            "message schema { OPTIONAL INT32 a (DATE); OPTIONAL INT64 b (DECIMAL(10,2)); }",
+        )
+        .unwrap();
+        let date_type = &schema.get_fields()[0];
+        let mut date = ColumnProfile::new(date_type).with_samples(date_type, 2);
+        for days in [19_782, 19_782, 0, 1] {
+            date.add(ProfileValue::I32(days));
+        }
+        assert!(date
+            .to_json("a")
+            .ends_with(",\"samples\":[\"2024-02-29\",\"1970-01-01\"]}"));
+
+        let decimal_type = &schema.get_fields()[1];
+        let mut decimal = ColumnProfile::new(decimal_type).with_samples(decimal_type, 5);
+        decimal.add(ProfileValue::I64(-150));
+        assert!(decimal.to_json("b").ends_with(",\"samples\":[-1.50]}"));
+
+        // No samples requested
+        assert!(!profile("OPTIONAL INT64 a;")
+            .to_json("a")
+            .contains("samples"));
+    }
+}
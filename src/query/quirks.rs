@@ -0,0 +1,83 @@
+use clap::ValueEnum;
+use log::info;
+
+/// Known deviations of data sources from the behaviour we would expect from reading the ODBC
+/// standard, which we can work around.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Quirk {
+    /// Driver does not support binding 64-Bit integers (aka `SQL_C_SBIGINT`). Same workaround as
+    /// `--driver-does-not-support-64bit-integers`.
+    No64BitIntegers,
+    /// `BIGINT` columns may be unsigned and hold values larger than the maximum of a signed 64-Bit
+    /// integer. These columns are written as `DECIMAL(20,0)` instead.
+    UnsignedBigint,
+    /// Numeric columns without declared precision are reported with precision zero. They are
+    /// fetched as 64-Bit floating points instead.
+    UnboundedNumeric,
+}
+
+/// Workarounds applied for the data source we are connected to.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Quirks {
+    pub no_64bit_integers: bool,
+    pub unsigned_bigint: bool,
+    pub unbounded_numeric: bool,
+}
+
+impl Quirks {
+    /// Infer the quirks of the data source from the database management system name reported by
+    /// the driver. Quirks listed in `ignore` are never applied.
+    pub fn detect(db_name: &str, ignore: &[Quirk]) -> Self {
+        let known: &[Quirk] = match db_name {
+            "Oracle" => &[Quirk::No64BitIntegers, Quirk::UnboundedNumeric],
+            "MySQL" | "MariaDB" => &[Quirk::UnsignedBigint],
+            _ => &[],
+        };
+        let mut quirks = Quirks::default();
+        for &quirk in known {
+            if ignore.contains(&quirk) {
+                info!("Ignoring quirk {quirk:?} of {db_name}.");
+                continue;
+            }
+            info!("Applying workaround for quirk {quirk:?} of {db_name}.");
+            quirks.set(quirk);
+        }
+        quirks
+    }
+
+    fn set(&mut self, quirk: Quirk) {
+        match quirk {
+            Quirk::No64BitIntegers => self.no_64bit_integers = true,
+            Quirk::UnsignedBigint => self.unsigned_bigint = true,
+            Quirk::UnboundedNumeric => self.unbounded_numeric = true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Quirk, Quirks};
+
+    #[test]
+    fn detect_oracle_quirks() {
+        let quirks = Quirks::detect("Oracle", &[]);
+        assert!(quirks.no_64bit_integers);
+        assert!(quirks.unbounded_numeric);
+        assert!(!quirks.unsigned_bigint);
+    }
+
+    #[test]
+    fn ignore_quirk() {
+        let quirks = Quirks::detect("Oracle", &[Quirk::No64BitIntegers]);
+        assert!(!quirks.no_64bit_integers);
+        assert!(quirks.unbounded_numeric);
+    }
+
+    #[test]
+    fn no_quirks_for_unknown_dbms() {
+        let quirks = Quirks::detect("Microsoft SQL Server", &[]);
+        assert!(!quirks.no_64bit_integers);
+        assert!(!quirks.unsigned_bigint);
+        assert!(!quirks.unbounded_numeric);
+    }
+}
@@ -0,0 +1,71 @@
+use anyhow::{bail, Error};
+
+use super::nondeterministic::words;
+
+/// Leading keywords of statements permitted by `--read-only`. Stored procedures are invoked with
+/// `CALL`, or the ODBC escape sequence `{call ...}`.
+const READ_ONLY_VERBS: &[&str] = &["SELECT", "WITH", "VALUES", "CALL"];
+
+/// Keywords which modify data or schema, if they occur anywhere within a statement. E.g.
+/// PostgreSQL allows `DELETE` within a `WITH` clause and `SELECT ... INTO` creates a table.
+const MODIFYING_KEYWORDS: &[&str] = &[
+    "INSERT", "UPDATE", "DELETE", "MERGE", "UPSERT", "INTO", "TRUNCATE", "DROP", "CREATE", "ALTER",
+    "GRANT", "REVOKE",
+];
+
+/// Fails unless each statement of `query` starts with a keyword of `READ_ONLY_VERBS` and contains
+/// none of `MODIFYING_KEYWORDS`. String literals, quoted identifiers and comments are ignored. This
+/// guards against executing a modifying statement by accident. It is not a security boundary,
+/// e.g. a procedure or function called by the query may still modify data.
+pub fn check_read_only(query: &str) -> Result<(), Error> {
+    for statement in words(query).split(|&word| word == ";") {
+        let Some(verb) = statement.first() else {
+            continue;
+        };
+        if !READ_ONLY_VERBS
+            .iter()
+            .any(|allowed| verb.eq_ignore_ascii_case(allowed))
+        {
+            bail!(
+                "Refusing to execute a statement starting with '{}' due to --read-only. Only \
+                statements starting with SELECT, WITH, VALUES or CALL are permitted.",
+                verb.to_uppercase()
+            )
+        }
+        if let Some(keyword) = statement.iter().find(|word| {
+            MODIFYING_KEYWORDS
+                .iter()
+                .any(|keyword| word.eq_ignore_ascii_case(keyword))
+        }) {
+            bail!(
+                "Refusing to execute a statement containing '{}' due to --read-only.",
+                keyword.to_uppercase()
+            )
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_read_only;
+
+    #[test]
+    fn permit_statements_returning_rows() {
+        assert!(check_read_only("SELECT a FROM t WHERE b = 'DELETE'").is_ok());
+        assert!(check_read_only("-- DROP TABLE t\n/* update */ select \"into\" FROM t;").is_ok());
+        assert!(check_read_only("WITH c AS (SELECT a FROM t) SELECT * FROM c").is_ok());
+        assert!(check_read_only("(SELECT a FROM t) UNION (SELECT a FROM u)").is_ok());
+        assert!(check_read_only("{call report(?)}").is_ok());
+    }
+
+    #[test]
+    fn refuse_modifying_statements() {
+        assert!(check_read_only("DELETE FROM t").is_err());
+        assert!(check_read_only("/* SELECT */ UPDATE t SET a = 1").is_err());
+        assert!(check_read_only("SELECT a FROM t; DROP TABLE t").is_err());
+        assert!(check_read_only("WITH d AS (DELETE FROM t RETURNING a) SELECT * FROM d").is_err());
+        assert!(check_read_only("SELECT a INTO u FROM t").is_err());
+        assert!(check_read_only("EXEC sp_rename 't', 'u'").is_err());
+    }
+}
@@ -0,0 +1,321 @@
+use std::{fs::File, path::Path};
+
+use anyhow::{bail, Context, Error};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+use log::info;
+use odbc_api::{buffers::TextRowSet, Cursor};
+use parquet::{
+    basic::{LogicalType, TimeUnit},
+    file::reader::{FileReader, SerializedFileReader},
+    record::Field,
+};
+
+use super::decimal::parse_scaled;
+
+/// Upper bound for the length of values fetched from the source for the comparison. Longer values
+/// are only compared up to this length.
+const MAX_STR_LEN: usize = 4096;
+
+/// Reads back the first `num_rows` rows of the parquet file at `path` and compares them with the
+/// first rows of `cursor`, which is a fresh execution of the query. Columns are matched by name.
+/// Columns of the output without a counterpart in the result set (e.g. offset columns) are ignored.
+/// Rows are compared regardless of their order, since neither fetch is required to return the rows
+/// in the same order. Every row of the source without a counterpart in the output is printed to
+/// standard error. Fails if there are any.
+pub fn round_trip_check(
+    mut cursor: impl Cursor,
+    path: &Path,
+    num_rows: usize,
+) -> Result<(), Error> {
+    let mut buffer = TextRowSet::for_cursor(num_rows, &mut cursor, Some(MAX_STR_LEN))?;
+    let source_names = cursor.column_names()?.collect::<Result<Vec<_>, _>>()?;
+    let mut block_cursor = cursor.bind_buffer(&mut buffer)?;
+    let expected = match block_cursor.fetch()? {
+        Some(batch) => (0..batch.num_rows())
+            .map(|row| {
+                (0..batch.num_cols())
+                    .map(|col| {
+                        batch.at(col, row).map(|value| {
+                            let truncated = batch.indicator_at(col, row).is_truncated(value.len());
+                            (String::from_utf8_lossy(value).into_owned(), truncated)
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>(),
+        None => Vec::new(),
+    };
+
+    // The output file is missing, if `--no-empty-file` is set and the result set is empty.
+    let actual = if path.exists() {
+        let file =
+            File::open(path).with_context(|| format!("Could not open '{}'.", path.display()))?;
+        let reader = SerializedFileReader::new(file)?;
+        let schema = reader.metadata().file_metadata().schema_descr_ptr();
+        let logical_types = schema
+            .columns()
+            .iter()
+            .map(|column| (column.name().to_owned(), column.logical_type()))
+            .collect::<Vec<_>>();
+        let rows = reader
+            .get_row_iter(None)?
+            .take(num_rows)
+            .map(|row| {
+                row.map(|row| {
+                    row.into_columns()
+                        .into_iter()
+                        .map(|(_name, field)| field)
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Some((logical_types, rows))
+    } else {
+        None
+    };
+    let (logical_types, actual) = actual.unwrap_or_default();
+
+    let mut num_mismatches = 0;
+    if expected.len() != actual.len() {
+        eprintln!(
+            "Round trip mismatch: Expected {} rows, actual {} rows.",
+            expected.len(),
+            actual.len()
+        );
+        num_mismatches += 1;
+    }
+    // Source index, output index and logical type of each column which is compared.
+    let mut columns = Vec::new();
+    for (source_index, source_name) in source_names.iter().enumerate() {
+        let Some(output_index) = logical_types
+            .iter()
+            .position(|(name, _)| name == source_name)
+        else {
+            info!("Column '{source_name}' is not part of the output and is not checked.");
+            continue;
+        };
+        columns.push((
+            source_index,
+            output_index,
+            logical_types[output_index].1.as_ref(),
+        ));
+    }
+    // Rows of the output, which have not been matched with a row of the source yet.
+    let mut unmatched = actual.iter().collect::<Vec<_>>();
+    for (row_index, expected) in expected.iter().enumerate() {
+        let is_match = |actual: &&Vec<Field>| {
+            columns
+                .iter()
+                .all(|&(source_index, output_index, logical_type)| {
+                    match (&expected[source_index], &actual[output_index]) {
+                        (None, actual) => matches!(actual, Field::Null),
+                        (Some((text, truncated)), actual) => {
+                            same_value(text, *truncated, actual, logical_type)
+                        }
+                    }
+                })
+        };
+        if let Some(position) = unmatched.iter().position(is_match) {
+            unmatched.swap_remove(position);
+            continue;
+        }
+        let values = columns
+            .iter()
+            .map(|&(source_index, _, _)| {
+                let value = expected[source_index]
+                    .as_ref()
+                    .map_or("NULL", |(text, _)| text.as_str());
+                format!("{}={value}", source_names[source_index])
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        eprintln!(
+            "Round trip mismatch: Row {} of the source has no counterpart in the output: \
+            {values}.",
+            row_index + 1
+        );
+        num_mismatches += 1;
+    }
+
+    if num_mismatches != 0 {
+        bail!(
+            "Round trip check failed. Found {num_mismatches} mismatches within the first \
+            {num_rows} rows."
+        )
+    }
+    info!(
+        "Round trip check passed for the first {} rows.",
+        expected.len()
+    );
+    Ok(())
+}
+
+/// `true` if the value written to parquet represents the text fetched from the source.
+///
+/// * `expected`: Text representation of the value as fetched from the data source.
+/// * `truncated`: The text has been truncated, only its prefix is compared.
+/// * `actual`: Value read back from the parquet file.
+/// * `logical_type`: Logical type of the parquet column.
+fn same_value(
+    expected: &str,
+    truncated: bool,
+    actual: &Field,
+    logical_type: Option<&LogicalType>,
+) -> bool {
+    let expected_trimmed = expected.trim();
+    match actual {
+        Field::Null => false,
+        Field::Str(text) if truncated => text.starts_with(expected),
+        Field::Str(text) => text == expected,
+        Field::Bytes(bytes) => {
+            let hex: String = bytes.data().iter().map(|b| format!("{b:02X}")).collect();
+            bytes.data() == expected.as_bytes() || hex.eq_ignore_ascii_case(expected_trimmed)
+        }
+        Field::Bool(value) => match expected_trimmed {
+            "1" => *value,
+            "0" => !*value,
+            other => other.parse::<bool>().ok() == Some(*value),
+        },
+        Field::Int(value) if is_time(logical_type) => {
+            same_time(expected_trimmed, *value as i64, logical_type)
+        }
+        Field::Long(value) if is_time(logical_type) => {
+            same_time(expected_trimmed, *value, logical_type)
+        }
+        Field::Long(value) => match logical_type {
+            Some(LogicalType::Timestamp { unit, .. }) => {
+                same_timestamp(expected_trimmed, *value, unit)
+            }
+            _ => same_integer(expected_trimmed, *value as i128),
+        },
+        Field::TimestampMillis(value) => same_timestamp(
+            expected_trimmed,
+            *value,
+            &TimeUnit::MILLIS(Default::default()),
+        ),
+        Field::TimestampMicros(value) => same_timestamp(
+            expected_trimmed,
+            *value,
+            &TimeUnit::MICROS(Default::default()),
+        ),
+        Field::Byte(value) => same_integer(expected_trimmed, *value as i128),
+        Field::Short(value) => same_integer(expected_trimmed, *value as i128),
+        Field::Int(value) => same_integer(expected_trimmed, *value as i128),
+        Field::UByte(value) => same_integer(expected_trimmed, *value as i128),
+        Field::UShort(value) => same_integer(expected_trimmed, *value as i128),
+        Field::UInt(value) => same_integer(expected_trimmed, *value as i128),
+        Field::ULong(value) => same_integer(expected_trimmed, *value as i128),
+        Field::Float16(value) => expected_trimmed.parse::<f32>().ok() == Some(value.to_f32()),
+        Field::Float(value) => expected_trimmed.parse::<f32>().ok() == Some(*value),
+        Field::Double(value) => expected_trimmed.parse::<f64>().ok() == Some(*value),
+        Field::Decimal(decimal) => {
+            let data = decimal.data();
+            let sign = if data.first().is_some_and(|&msb| msb & 0x80 != 0) {
+                0xff
+            } else {
+                0
+            };
+            let mut bytes = [sign; 16];
+            bytes[16 - data.len()..].copy_from_slice(data);
+            parse_scaled(
+                expected_trimmed,
+                decimal.scale().try_into().unwrap_or_default(),
+            ) == Some(i128::from_be_bytes(bytes))
+        }
+        Field::Date(days) => {
+            let epoch = NaiveDate::default();
+            NaiveDate::parse_from_str(expected_trimmed, "%Y-%m-%d")
+                .is_ok_and(|date| date.signed_duration_since(epoch).num_days() == *days as i64)
+        }
+        // Nested types are not produced by `odbc2parquet`.
+        Field::Group(_) | Field::ListInternal(_) | Field::MapInternal(_) => true,
+    }
+}
+
+fn is_time(logical_type: Option<&LogicalType>) -> bool {
+    matches!(logical_type, Some(LogicalType::Time { .. }))
+}
+
+fn same_integer(expected: &str, actual: i128) -> bool {
+    parse_scaled(expected, 0) == Some(actual)
+}
+
+fn same_timestamp(expected: &str, actual: i64, unit: &TimeUnit) -> bool {
+    let utc = DateTime::parse_from_str(expected, "%Y-%m-%d %H:%M:%S%.f %:z")
+        .map(|datetime| datetime.naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(expected, "%Y-%m-%d %H:%M:%S%.f"))
+        .map(|datetime| datetime.and_utc());
+    let Ok(utc) = utc else {
+        return false;
+    };
+    let value = match unit {
+        TimeUnit::MILLIS(_) => Some(utc.timestamp_millis()),
+        TimeUnit::MICROS(_) => Some(utc.timestamp_micros()),
+        TimeUnit::NANOS(_) => utc.timestamp_nanos_opt(),
+    };
+    value == Some(actual)
+}
+
+fn same_time(expected: &str, actual: i64, logical_type: Option<&LogicalType>) -> bool {
+    let Some(LogicalType::Time { unit, .. }) = logical_type else {
+        return false;
+    };
+    let Ok(time) = NaiveTime::parse_from_str(expected, "%H:%M:%S%.f") else {
+        return false;
+    };
+    let nanos = time.signed_duration_since(NaiveTime::MIN).num_nanoseconds();
+    let value = match unit {
+        TimeUnit::MILLIS(_) => nanos.map(|n| n / 1_000_000),
+        TimeUnit::MICROS(_) => nanos.map(|n| n / 1_000),
+        TimeUnit::NANOS(_) => nanos,
+    };
+    value == Some(actual)
+}
+
+#[cfg(test)]
+mod tests {
+    use parquet::{
+        basic::{LogicalType, TimeUnit},
+        data_type::Decimal,
+        format::MicroSeconds,
+        record::Field,
+    };
+
+    use super::same_value;
+
+    #[test]
+    fn compare_text_with_parquet_values() {
+        assert!(same_value("42", false, &Field::Int(42), None));
+        assert!(!same_value("43", false, &Field::Int(42), None));
+        assert!(same_value(
+            "-1.50",
+            false,
+            &Field::Decimal(Decimal::from_i32(-150, 5, 2)),
+            None
+        ));
+        assert!(same_value("1", false, &Field::Bool(true), None));
+        assert!(same_value(
+            "Hello",
+            true,
+            &Field::Str("Hello, World!".to_owned()),
+            None
+        ));
+        assert!(!same_value(
+            "Hello",
+            false,
+            &Field::Str("Hello, World!".to_owned()),
+            None
+        ));
+        assert!(same_value("1970-01-02", false, &Field::Date(1), None));
+        let timestamp_micros = LogicalType::Timestamp {
+            is_adjusted_to_u_t_c: false,
+            unit: TimeUnit::MICROS(MicroSeconds {}),
+        };
+        assert!(same_value(
+            "1970-01-01 00:00:01.000002",
+            false,
+            &Field::Long(1_000_002),
+            Some(&timestamp_micros)
+        ));
+    }
+}
@@ -0,0 +1,297 @@
+use std::{fmt::Write as _, fs, path::PathBuf, sync::Arc};
+
+use anyhow::{bail, Context, Error};
+use log::{info, warn};
+use parquet::{
+    basic::{ConvertedType, LogicalType, Repetition, TimeUnit, Type as PhysicalType},
+    schema::types::{SchemaDescriptor, Type},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    parquet_writer::{OutputSize, ParquetOutput, WrittenRowGroup},
+    table_strategy::ColumnExporter,
+};
+
+/// Schema of the output, cached in a JSON file between runs, so changes of the result set are
+/// detected.
+#[derive(Clone)]
+pub struct SchemaCache {
+    pub path: PathBuf,
+    /// Fail if the schema differs from the cached one, instead of replacing it.
+    pub strict: bool,
+}
+
+impl SchemaCache {
+    /// Compare `schema` with the cached schema, if any. Returns the schema to cache for the next
+    /// run, or `None` if the cached one is up to date. It is only written by [`CacheSchema`], once
+    /// the output is complete.
+    pub fn check(&self, schema: &Type) -> Result<Option<CachedSchema>, Error> {
+        let columns = columns_of(schema);
+        let cached = match fs::read_to_string(&self.path) {
+            Ok(text) => Some(
+                serde_json::from_str::<CachedSchema>(&text)
+                    .with_context(|| {
+                        format!("Could not read cached schema '{}'.", self.path.display())
+                    })?
+                    .columns,
+            ),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+            Err(error) => {
+                return Err(Error::from(error).context(format!(
+                    "Could not read cached schema '{}'.",
+                    self.path.display()
+                )))
+            }
+        };
+        match cached.map(|cached| schema_diff(&cached, &columns)) {
+            Some(diff) if diff.is_empty() => return Ok(None),
+            Some(diff) if self.strict => bail!(
+                "The schema of the result set differs from the one cached in '{}':\n{diff}",
+                self.path.display()
+            ),
+            Some(diff) => warn!(
+                "The schema of the result set differs from the one cached in '{}'. Replacing \
+                it.\n{diff}",
+                self.path.display()
+            ),
+            None => info!("Caching schema in '{}'.", self.path.display()),
+        }
+        Ok(Some(CachedSchema { columns }))
+    }
+}
+
+/// Contents of the cache file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CachedSchema {
+    columns: Vec<CachedColumn>,
+}
+
+/// A column of the output as it is compared between runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CachedColumn {
+    name: String,
+    /// Physical and logical type in the notation of parquet message types, e.g. `INT64
+    /// DECIMAL(10,2)`.
+    #[serde(rename = "type")]
+    column_type: String,
+    nullable: bool,
+}
+
+/// Writes the schema into the cache, once the output it wraps has been closed successfully. This
+/// way a failed export does not replace the cached schema.
+pub struct CacheSchema {
+    inner: Box<dyn ParquetOutput>,
+    cache: SchemaCache,
+    schema: CachedSchema,
+}
+
+impl CacheSchema {
+    pub fn new(inner: Box<dyn ParquetOutput>, cache: SchemaCache, schema: CachedSchema) -> Self {
+        Self {
+            inner,
+            cache,
+            schema,
+        }
+    }
+}
+
+impl ParquetOutput for CacheSchema {
+    fn write_row_group(
+        &mut self,
+        num_batch: u32,
+        column_exporter: &mut ColumnExporter,
+    ) -> Result<WrittenRowGroup, Error> {
+        self.inner.write_row_group(num_batch, column_exporter)
+    }
+
+    fn close(self) -> Result<OutputSize, Error> {
+        let written = self.inner.close_box()?;
+        let path = &self.cache.path;
+        let mut json = serde_json::to_string_pretty(&self.schema)?;
+        json.push('\n');
+        fs::write(path, json)
+            .with_context(|| format!("Could not write schema to '{}'.", path.display()))?;
+        Ok(written)
+    }
+
+    fn close_box(self: Box<Self>) -> Result<OutputSize, Error> {
+        self.close()
+    }
+}
+
+impl CachedColumn {
+    fn describe(&self) -> String {
+        let nullability = if self.nullable { "NULL" } else { "NOT NULL" };
+        format!("{} {nullability}", self.column_type)
+    }
+}
+
+/// One entry for each leaf column. Columns nested with `--group-columns` are named by their path,
+/// e.g. `address.city`.
+fn columns_of(schema: &Type) -> Vec<CachedColumn> {
+    SchemaDescriptor::new(Arc::new(schema.clone()))
+        .columns()
+        .iter()
+        .map(|column| {
+            let field = column.self_type();
+            CachedColumn {
+                name: column.path().string(),
+                column_type: column_type(field),
+                nullable: field.get_basic_info().repetition() != Repetition::REQUIRED,
+            }
+        })
+        .collect()
+}
+
+fn column_type(field: &Type) -> String {
+    let Type::PrimitiveType {
+        physical_type,
+        type_length,
+        ..
+    } = field
+    else {
+        unreachable!("Leaf columns are primitive")
+    };
+    let mut text = physical_type.to_string();
+    if *physical_type == PhysicalType::FIXED_LEN_BYTE_ARRAY {
+        write!(text, "({type_length})").unwrap();
+    }
+    let unit = |unit: &TimeUnit| match unit {
+        TimeUnit::MILLIS(_) => "MILLIS",
+        TimeUnit::MICROS(_) => "MICROS",
+        TimeUnit::NANOS(_) => "NANOS",
+    };
+    let info = field.get_basic_info();
+    let annotation = match info.logical_type() {
+        Some(LogicalType::Decimal { scale, precision }) => format!("DECIMAL({precision},{scale})"),
+        Some(LogicalType::Integer {
+            bit_width,
+            is_signed,
+        }) => format!("INTEGER({bit_width},{is_signed})"),
+        Some(LogicalType::Timestamp {
+            is_adjusted_to_u_t_c,
+            unit: time_unit,
+        }) => format!("TIMESTAMP({},{is_adjusted_to_u_t_c})", unit(&time_unit)),
+        Some(LogicalType::Time {
+            is_adjusted_to_u_t_c,
+            unit: time_unit,
+        }) => format!("TIME({},{is_adjusted_to_u_t_c})", unit(&time_unit)),
+        Some(other) => format!("{other:?}").to_uppercase(),
+        None if info.converted_type() == ConvertedType::DECIMAL => {
+            format!("DECIMAL({},{})", field.get_precision(), field.get_scale())
+        }
+        None if info.converted_type() != ConvertedType::NONE => info.converted_type().to_string(),
+        None => return text,
+    };
+    write!(text, " {annotation}").unwrap();
+    text
+}
+
+/// Lists added, removed and changed columns, one per line. Empty if the schemas are identical.
+fn schema_diff(cached: &[CachedColumn], current: &[CachedColumn]) -> String {
+    let mut diff = String::new();
+    let find = |columns: &[CachedColumn], name: &str| {
+        columns.iter().position(|column| column.name == name)
+    };
+    for column in current {
+        if find(cached, &column.name).is_none() {
+            writeln!(diff, "  added: {} {}", column.name, column.describe()).unwrap();
+        }
+    }
+    for column in cached {
+        if find(current, &column.name).is_none() {
+            writeln!(diff, "  removed: {} {}", column.name, column.describe()).unwrap();
+        }
+    }
+    for column in current {
+        if let Some(index) = find(cached, &column.name) {
+            let before = &cached[index];
+            if before.column_type != column.column_type || before.nullable != column.nullable {
+                writeln!(
+                    diff,
+                    "  changed: {} {} -> {}",
+                    column.name,
+                    before.describe(),
+                    column.describe()
+                )
+                .unwrap();
+            }
+        }
+    }
+    let names = |columns: &[CachedColumn]| -> Vec<String> {
+        columns.iter().map(|column| column.name.clone()).collect()
+    };
+    if diff.is_empty() && names(cached) != names(current) {
+        writeln!(
+            diff,
+            "  reordered: {} -> {}",
+            names(cached).join(", "),
+            names(current).join(", ")
+        )
+        .unwrap();
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use parquet::schema::parser::parse_message_type;
+
+    use super::{columns_of, schema_diff, CachedSchema};
+
+    #[test]
+    fn cached_schema_round_trips() {
+        let schema = parse_message_type(
+            "message schema {
+                REQUIRED INT32 id (INTEGER(32,true));
+                OPTIONAL BYTE_ARRAY \"name\" (UTF8);
+                OPTIONAL INT64 amount (DECIMAL(12,2));
+                OPTIONAL INT64 created (TIMESTAMP(MICROS,false));
+            }",
+        )
+        .unwrap();
+        let columns = columns_of(&schema);
+
+        let json = serde_json::to_string(&CachedSchema {
+            columns: columns.clone(),
+        })
+        .unwrap();
+
+        let parsed: CachedSchema = serde_json::from_str(&json).unwrap();
+        assert_eq!(columns, parsed.columns);
+        assert!(json.contains(
+            "{\"name\":\"created\",\"type\":\"INT64 TIMESTAMP(MICROS,false)\",\"nullable\":true}"
+        ));
+    }
+
+    #[test]
+    fn list_added_removed_and_changed_columns() {
+        let cached = columns_of(
+            &parse_message_type(
+                "message schema {
+                    REQUIRED INT32 a;
+                    OPTIONAL INT32 b;
+                    OPTIONAL DOUBLE c;
+                }",
+            )
+            .unwrap(),
+        );
+        let current = columns_of(
+            &parse_message_type(
+                "message schema {
+                    REQUIRED INT64 a;
+                    OPTIONAL DOUBLE c;
+                    OPTIONAL BYTE_ARRAY d (UTF8);
+                }",
+            )
+            .unwrap(),
+        );
+
+        let expected = "  added: d BYTE_ARRAY UTF8 NULL\n  removed: b INT32 NULL\n  changed: a \
+            INT32 NOT NULL -> INT64 NOT NULL\n";
+        assert_eq!(expected, schema_diff(&cached, &current));
+        assert_eq!("", schema_diff(&current, &current));
+    }
+}
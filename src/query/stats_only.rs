@@ -0,0 +1,212 @@
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{self, stdout, Write},
+    sync::Arc,
+};
+
+use anyhow::Error;
+use io_arg::IoArg;
+use parquet::{
+    file::{
+        metadata::RowGroupMetaData, properties::WriterProperties, statistics::Statistics,
+        writer::SerializedFileWriter,
+    },
+    schema::types::Type,
+};
+
+use super::{
+    parquet_writer::{OutputSize, ParquetOutput, WrittenRowGroup},
+    table_strategy::ColumnExporter,
+};
+
+/// Fetches and encodes every row group like a regular parquet output would, but discards the data
+/// pages. Only the statistics of each row group are kept and written as JSON to the output once
+/// the result set is consumed.
+pub struct StatsOnly {
+    output: IoArg,
+    /// Encodes the row groups so we get statistics. Everything written to it is discarded.
+    writer: SerializedFileWriter<io::Sink>,
+    /// Statistics of each row group, already rendered as JSON objects.
+    row_groups: Vec<String>,
+}
+
+impl StatsOnly {
+    pub fn new(
+        output: IoArg,
+        schema: Arc<Type>,
+        properties: Arc<WriterProperties>,
+    ) -> Result<Self, Error> {
+        let writer = SerializedFileWriter::new(io::sink(), schema, properties)?;
+        Ok(Self {
+            output,
+            writer,
+            row_groups: Vec::new(),
+        })
+    }
+}
+
+impl ParquetOutput for StatsOnly {
+    fn write_row_group(
+        &mut self,
+        _num_batch: u32,
+        column_exporter: &mut ColumnExporter,
+    ) -> Result<WrittenRowGroup, Error> {
+        let metadata = column_exporter.write_row_group(&mut self.writer)?;
+        self.row_groups.push(row_group_to_json(&metadata));
+        Ok(WrittenRowGroup {
+            metadata,
+            path: None,
+            file_split: false,
+        })
+    }
+
+    fn close(self) -> Result<OutputSize, Error> {
+        let json = format!("{{\"row_groups\":[{}]}}\n", self.row_groups.join(","));
+        let num_files = match self.output {
+            IoArg::StdStream => {
+                stdout().lock().write_all(json.as_bytes())?;
+                0
+            }
+            IoArg::File(path) => {
+                File::create(path)?.write_all(json.as_bytes())?;
+                1
+            }
+        };
+        Ok(OutputSize {
+            num_files,
+            num_bytes: json.len().try_into().unwrap(),
+        })
+    }
+
+    fn close_box(self: Box<Self>) -> Result<OutputSize, Error> {
+        self.close()
+    }
+}
+
+fn row_group_to_json(metadata: &RowGroupMetaData) -> String {
+    let columns: Vec<String> = metadata
+        .columns()
+        .iter()
+        .map(|column| {
+            let mut json = format!("{{\"name\":{}", json_string(&column.column_path().string()));
+            if let Some(stats) = column.statistics() {
+                let (min, max) = min_max_to_json(stats);
+                let null_count = stats
+                    .null_count_opt()
+                    .map(|count| count.to_string())
+                    .unwrap_or_else(|| "null".to_owned());
+                write!(
+                    json,
+                    ",\"null_count\":{null_count},\"min\":{min},\"max\":{max}"
+                )
+                .unwrap();
+            }
+            json.push('}');
+            json
+        })
+        .collect();
+    format!(
+        "{{\"num_rows\":{},\"columns\":[{}]}}",
+        metadata.num_rows(),
+        columns.join(",")
+    )
+}
+
+/// Renders minimum and maximum of the statistics as JSON values. Values are rendered in their
+/// physical representation. Binary values which are not valid UTF-8 are rendered as hex strings.
+fn min_max_to_json(stats: &Statistics) -> (String, String) {
+    fn render<T>(value: Option<&T>, f: impl Fn(&T) -> String) -> String {
+        value.map(f).unwrap_or_else(|| "null".to_owned())
+    }
+    fn float(value: f64) -> String {
+        if value.is_finite() {
+            value.to_string()
+        } else {
+            json_string(&value.to_string())
+        }
+    }
+    fn bytes(value: &[u8]) -> String {
+        match std::str::from_utf8(value) {
+            Ok(text) => json_string(text),
+            Err(_) => json_string(&hex(value)),
+        }
+    }
+    match stats {
+        Statistics::Boolean(s) => (
+            render(s.min_opt(), bool::to_string),
+            render(s.max_opt(), bool::to_string),
+        ),
+        Statistics::Int32(s) => (
+            render(s.min_opt(), i32::to_string),
+            render(s.max_opt(), i32::to_string),
+        ),
+        Statistics::Int64(s) => (
+            render(s.min_opt(), i64::to_string),
+            render(s.max_opt(), i64::to_string),
+        ),
+        Statistics::Int96(s) => (
+            render(s.min_opt(), |v| json_string(&v.to_string())),
+            render(s.max_opt(), |v| json_string(&v.to_string())),
+        ),
+        Statistics::Float(s) => (
+            render(s.min_opt(), |&v| float(v.into())),
+            render(s.max_opt(), |&v| float(v.into())),
+        ),
+        Statistics::Double(s) => (
+            render(s.min_opt(), |&v| float(v)),
+            render(s.max_opt(), |&v| float(v)),
+        ),
+        Statistics::ByteArray(s) => (
+            render(s.min_opt(), |v| bytes(v.data())),
+            render(s.max_opt(), |v| bytes(v.data())),
+        ),
+        Statistics::FixedLenByteArray(s) => (
+            render(s.min_opt(), |v| json_string(&hex(v.data()))),
+            render(s.max_opt(), |v| json_string(&hex(v.data()))),
+        ),
+    }
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(2 + bytes.len() * 2);
+    out.push_str("0x");
+    for byte in bytes {
+        write!(out, "{byte:02x}").unwrap();
+    }
+    out
+}
+
+/// Quotes and escapes text, so it can be used as a JSON string.
+pub fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hex, json_string};
+
+    #[test]
+    fn escape_json_string() {
+        assert_eq!(r#""a\"b\\c\nd\u0001""#, json_string("a\"b\\c\nd\u{1}"));
+    }
+
+    #[test]
+    fn render_hex() {
+        assert_eq!("0x00ff10", hex(&[0, 255, 16]));
+    }
+}
@@ -0,0 +1,415 @@
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{Context, Error};
+use log::info;
+use parquet::{
+    basic::{LogicalType, Repetition, Type as PhysicalType},
+    column::writer::{get_typed_column_writer_mut, ColumnWriter, ColumnWriterImpl},
+    data_type::{
+        BoolType, ByteArray, ByteArrayType, DataType, DoubleType, FixedLenByteArrayType, FloatType,
+        Int32Type, Int64Type, Int96Type,
+    },
+    file::{
+        properties::WriterProperties,
+        statistics::{Statistics, ValueStatistics},
+        writer::SerializedFileWriter,
+    },
+    schema::types::{ColumnDescPtr, SchemaDescriptor, Type, TypePtr},
+};
+
+use super::{
+    parquet_writer::{OutputSize, ParquetOutput, WrittenRowGroup},
+    table_strategy::ColumnExporter,
+};
+
+/// Passes row groups through to the inner output and keeps the statistics of each of them. Once
+/// the output is closed, these are written into a parquet file of their own, with one row per row
+/// group:
+///
+/// ```text
+/// message row_group_statistics {
+///   OPTIONAL BYTE_ARRAY file (STRING);
+///   REQUIRED INT32 row_group;
+///   REQUIRED INT64 num_rows;
+///   REQUIRED group <column> {
+///     OPTIONAL <type of column> min;
+///     OPTIONAL <type of column> max;
+///     OPTIONAL INT64 null_count;
+///   }
+///   ...
+/// }
+/// ```
+///
+/// `file` is the path of the file holding the row group, or NULL for standard out. `row_group` is
+/// the zero based index of the row group within that file. There is one group for each selected
+/// column, named by its path, whose minimum and maximum have the same type as the column.
+pub struct StatsParquet {
+    inner: Box<dyn ParquetOutput>,
+    path: PathBuf,
+    /// Index of each selected leaf column in the output, together with its descriptor.
+    columns: Vec<(usize, ColumnDescPtr)>,
+    row_groups: Vec<RowGroupStatistics>,
+}
+
+struct RowGroupStatistics {
+    file: Option<PathBuf>,
+    row_group: i32,
+    num_rows: i64,
+    /// Statistics of each selected column, in the same order as `StatsParquet::columns`.
+    statistics: Vec<Option<Statistics>>,
+}
+
+impl StatsParquet {
+    /// Keep statistics of the leaf columns with the given paths (e.g. `a` or `address.city`), or
+    /// of all leaf columns if `column_names` is empty.
+    pub fn new(
+        inner: Box<dyn ParquetOutput>,
+        path: PathBuf,
+        schema: TypePtr,
+        column_names: &[String],
+    ) -> Result<Self, Error> {
+        let schema = SchemaDescriptor::new(schema);
+        let columns: Vec<_> = schema.columns().iter().cloned().enumerate().collect();
+        let columns = if column_names.is_empty() {
+            columns
+        } else {
+            column_names
+                .iter()
+                .map(|name| {
+                    columns
+                        .iter()
+                        .find(|(_, column)| &column.path().string() == name)
+                        .cloned()
+                        .with_context(|| {
+                            format!(
+                                "Column '{name}' of `--stats-parquet-columns` is not part of the \
+                                output."
+                            )
+                        })
+                })
+                .collect::<Result<_, _>>()?
+        };
+        Ok(Self {
+            inner,
+            path,
+            columns,
+            row_groups: Vec::new(),
+        })
+    }
+}
+
+/// Schema of the statistics file, see [`StatsParquet`].
+fn stats_schema(columns: &[(usize, ColumnDescPtr)]) -> Result<TypePtr, Error> {
+    let mut fields = vec![
+        Type::primitive_type_builder("file", PhysicalType::BYTE_ARRAY)
+            .with_logical_type(Some(LogicalType::String))
+            .with_repetition(Repetition::OPTIONAL)
+            .build()?,
+        Type::primitive_type_builder("row_group", PhysicalType::INT32)
+            .with_repetition(Repetition::REQUIRED)
+            .build()?,
+        Type::primitive_type_builder("num_rows", PhysicalType::INT64)
+            .with_repetition(Repetition::REQUIRED)
+            .build()?,
+    ];
+    for (_, column) in columns {
+        let bound = |name| {
+            Type::primitive_type_builder(name, column.physical_type())
+                .with_logical_type(column.logical_type())
+                .with_converted_type(column.converted_type())
+                .with_length(column.type_length())
+                .with_precision(column.type_precision())
+                .with_scale(column.type_scale())
+                .with_repetition(Repetition::OPTIONAL)
+                .build()
+        };
+        let null_count = Type::primitive_type_builder("null_count", PhysicalType::INT64)
+            .with_repetition(Repetition::OPTIONAL)
+            .build()?;
+        fields.push(
+            Type::group_type_builder(&column.path().string())
+                .with_repetition(Repetition::REQUIRED)
+                .with_fields(vec![
+                    Arc::new(bound("min")?),
+                    Arc::new(bound("max")?),
+                    Arc::new(null_count),
+                ])
+                .build()?,
+        );
+    }
+    Ok(Arc::new(
+        Type::group_type_builder("row_group_statistics")
+            .with_fields(fields.into_iter().map(Arc::new).collect())
+            .build()?,
+    ))
+}
+
+fn write_stats_file(
+    path: &Path,
+    columns: &[(usize, ColumnDescPtr)],
+    row_groups: &[RowGroupStatistics],
+) -> Result<(), Error> {
+    let file = File::create(path)
+        .with_context(|| format!("Could not create statistics file '{}'.", path.display()))?;
+    let properties = Arc::new(WriterProperties::builder().build());
+    let mut writer = SerializedFileWriter::new(file, stats_schema(columns)?, properties)?;
+    if !row_groups.is_empty() {
+        let mut row_group_writer = writer.next_row_group()?;
+        let mut index = 0;
+        while let Some(mut column_writer) = row_group_writer.next_column()? {
+            write_nth_column(row_groups, index, column_writer.untyped())?;
+            column_writer.close()?;
+            index += 1;
+        }
+        row_group_writer.close()?;
+    }
+    writer.close()?;
+    info!(
+        "Statistics of {} row groups have been written to {}.",
+        row_groups.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+fn write_nth_column(
+    row_groups: &[RowGroupStatistics],
+    index: usize,
+    column_writer: &mut ColumnWriter,
+) -> Result<(), Error> {
+    match index {
+        0 => write_optional(
+            get_typed_column_writer_mut::<ByteArrayType>(column_writer),
+            row_groups.iter().map(|row_group| {
+                let file = row_group.file.as_ref()?;
+                Some(ByteArray::from(file.to_string_lossy().as_bytes().to_vec()))
+            }),
+        ),
+        1 => write_required::<Int32Type>(
+            column_writer,
+            row_groups.iter().map(|row_group| row_group.row_group),
+        ),
+        2 => write_required::<Int64Type>(
+            column_writer,
+            row_groups.iter().map(|row_group| row_group.num_rows),
+        ),
+        _ => {
+            let (column, field) = ((index - 3) / 3, (index - 3) % 3);
+            let statistics = row_groups
+                .iter()
+                .map(|row_group| row_group.statistics[column].as_ref());
+            match field {
+                0 => write_bound(column_writer, statistics, true),
+                1 => write_bound(column_writer, statistics, false),
+                _ => write_optional(
+                    get_typed_column_writer_mut::<Int64Type>(column_writer),
+                    statistics.map(|stats| {
+                        stats?
+                            .null_count_opt()
+                            .map(|count| count.try_into().unwrap())
+                    }),
+                ),
+            }
+        }
+    }
+}
+
+/// Writes the minimum, or maximum if `is_min` is `false`, of each statistics. The column has the
+/// same physical type as the statistics.
+fn write_bound<'a>(
+    column_writer: &mut ColumnWriter,
+    statistics: impl Iterator<Item = Option<&'a Statistics>>,
+    is_min: bool,
+) -> Result<(), Error> {
+    fn pick<T: DataType>(stats: &ValueStatistics<T::T>, is_min: bool) -> Option<T::T> {
+        if is_min {
+            stats.min_opt().cloned()
+        } else {
+            stats.max_opt().cloned()
+        }
+    }
+    let statistics: Vec<_> = statistics.collect();
+    let stats = statistics.iter();
+    match column_writer {
+        ColumnWriter::BoolColumnWriter(cw) => write_optional(
+            cw,
+            stats.map(|stats| match stats {
+                Some(Statistics::Boolean(s)) => pick::<BoolType>(s, is_min),
+                _ => None,
+            }),
+        ),
+        ColumnWriter::Int32ColumnWriter(cw) => write_optional(
+            cw,
+            stats.map(|stats| match stats {
+                Some(Statistics::Int32(s)) => pick::<Int32Type>(s, is_min),
+                _ => None,
+            }),
+        ),
+        ColumnWriter::Int64ColumnWriter(cw) => write_optional(
+            cw,
+            stats.map(|stats| match stats {
+                Some(Statistics::Int64(s)) => pick::<Int64Type>(s, is_min),
+                _ => None,
+            }),
+        ),
+        ColumnWriter::Int96ColumnWriter(cw) => write_optional(
+            cw,
+            stats.map(|stats| match stats {
+                Some(Statistics::Int96(s)) => pick::<Int96Type>(s, is_min),
+                _ => None,
+            }),
+        ),
+        ColumnWriter::FloatColumnWriter(cw) => write_optional(
+            cw,
+            stats.map(|stats| match stats {
+                Some(Statistics::Float(s)) => pick::<FloatType>(s, is_min),
+                _ => None,
+            }),
+        ),
+        ColumnWriter::DoubleColumnWriter(cw) => write_optional(
+            cw,
+            stats.map(|stats| match stats {
+                Some(Statistics::Double(s)) => pick::<DoubleType>(s, is_min),
+                _ => None,
+            }),
+        ),
+        ColumnWriter::ByteArrayColumnWriter(cw) => write_optional(
+            cw,
+            stats.map(|stats| match stats {
+                Some(Statistics::ByteArray(s)) => pick::<ByteArrayType>(s, is_min),
+                _ => None,
+            }),
+        ),
+        ColumnWriter::FixedLenByteArrayColumnWriter(cw) => write_optional(
+            cw,
+            stats.map(|stats| match stats {
+                Some(Statistics::FixedLenByteArray(s)) => pick::<FixedLenByteArrayType>(s, is_min),
+                _ => None,
+            }),
+        ),
+    }
+}
+
+fn write_required<T: DataType>(
+    column_writer: &mut ColumnWriter,
+    values: impl Iterator<Item = T::T>,
+) -> Result<(), Error> {
+    let values: Vec<_> = values.collect();
+    get_typed_column_writer_mut::<T>(column_writer).write_batch(&values, None, None)?;
+    Ok(())
+}
+
+fn write_optional<T: DataType>(
+    column_writer: &mut ColumnWriterImpl<T>,
+    values: impl Iterator<Item = Option<T::T>>,
+) -> Result<(), Error> {
+    let mut def_levels = Vec::new();
+    let values: Vec<_> = values
+        .inspect(|value| def_levels.push(i16::from(value.is_some())))
+        .flatten()
+        .collect();
+    column_writer.write_batch(&values, Some(&def_levels), None)?;
+    Ok(())
+}
+
+impl ParquetOutput for StatsParquet {
+    fn write_row_group(
+        &mut self,
+        num_batch: u32,
+        column_exporter: &mut ColumnExporter,
+    ) -> Result<WrittenRowGroup, Error> {
+        let written = self.inner.write_row_group(num_batch, column_exporter)?;
+        let metadata = &written.metadata;
+        self.row_groups.push(RowGroupStatistics {
+            file: written.path.clone(),
+            row_group: metadata.ordinal().unwrap_or_default().into(),
+            num_rows: metadata.num_rows(),
+            statistics: self
+                .columns
+                .iter()
+                .map(|&(index, _)| metadata.column(index).statistics().cloned())
+                .collect(),
+        });
+        Ok(written)
+    }
+
+    fn close(self) -> Result<OutputSize, Error> {
+        let written = self.inner.close_box()?;
+        write_stats_file(&self.path, &self.columns, &self.row_groups)?;
+        Ok(written)
+    }
+
+    fn close_box(self: Box<Self>) -> Result<OutputSize, Error> {
+        self.close()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, path::PathBuf, sync::Arc};
+
+    use parquet::{
+        file::{
+            reader::{FileReader, SerializedFileReader},
+            statistics::Statistics,
+        },
+        schema::{parser::parse_message_type, types::SchemaDescriptor},
+    };
+    use tempfile::tempdir;
+
+    use super::{write_stats_file, RowGroupStatistics};
+
+    #[test]
+    fn write_one_row_per_row_group() {
+        let schema = parse_message_type(
+            "message schema { OPTIONAL INT32 a; OPTIONAL BYTE_ARRAY b (STRING); }",
+        )
+        .unwrap();
+        let schema = SchemaDescriptor::new(Arc::new(schema));
+        let columns = vec![(1, schema.column(1))];
+        let row_groups = vec![
+            RowGroupStatistics {
+                file: Some(PathBuf::from("out.par")),
+                row_group: 0,
+                num_rows: 2,
+                statistics: vec![Some(Statistics::byte_array(
+                    Some("a".into()),
+                    Some("b".into()),
+                    None,
+                    Some(0),
+                    false,
+                ))],
+            },
+            RowGroupStatistics {
+                file: None,
+                row_group: 1,
+                num_rows: 1,
+                statistics: vec![None],
+            },
+        ];
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("stats.par");
+
+        write_stats_file(&path, &columns, &row_groups).unwrap();
+
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        let rows: Vec<String> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap().to_string())
+            .collect();
+        assert_eq!(
+            vec![
+                "{file: \"out.par\", row_group: 0, num_rows: 2, b: {min: \"a\", max: \"b\", \
+                null_count: 0}}",
+                "{file: null, row_group: 1, num_rows: 1, b: {min: null, max: null, null_count: \
+                null}}",
+            ],
+            rows
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use anyhow::{anyhow, bail, Error};
+use log::info;
+use odbc_api::{Connection, Cursor, IntoParameter};
+
+use super::driver_info::search_pattern_escape;
+
+/// A table to export in its entirety, as specified with `--table`, `--schema` and `--catalog`.
+pub struct TableSelect {
+    pub table: String,
+    pub schema: Option<String>,
+    pub catalog: Option<String>,
+}
+
+impl TableSelect {
+    /// A `SELECT` statement listing every column of the table explicitly. The columns are
+    /// discovered using the catalog functions of the driver. Identifiers are quoted according to
+    /// the dialect of the database management system.
+    pub fn statement(&self, conn: &Connection, db_name: &str) -> Result<String, Error> {
+        let Columns {
+            catalog,
+            schema,
+            columns,
+        } = self.columns(conn)?;
+        info!(
+            "Discovered {} columns of table '{}'.",
+            columns.len(),
+            self.table
+        );
+
+        let quote = |identifier: &str| quote_identifier(identifier, db_name);
+        let mut table = String::new();
+        if self.catalog.is_some() {
+            table.push_str(&quote(&catalog));
+            table.push('.');
+        }
+        if !schema.is_empty() {
+            table.push_str(&quote(&schema));
+            table.push('.');
+        }
+        table.push_str(&quote(&self.table));
+        let columns: Vec<_> = columns.iter().map(|(column, _)| quote(column)).collect();
+        Ok(format!("SELECT {} FROM {table}", columns.join(", ")))
+    }
+
+    /// Comments of the columns of the table, as tuples of column name and comment. Queried from
+    /// the system tables of Microsoft SQL Server, Oracle and PostgreSQL. For other data sources the
+    /// remarks reported by the catalog functions of the driver are used.
+    pub fn column_comments(
+        &self,
+        conn: &Connection,
+        db_name: &str,
+    ) -> Result<Vec<(String, String)>, Error> {
+        let Columns {
+            catalog,
+            schema,
+            columns,
+        } = self.columns(conn)?;
+        let catalog = self.catalog.is_some().then_some(catalog.as_str());
+        let Some((query, parameters)) = comments_query(db_name, catalog, &schema, &self.table)
+        else {
+            return Ok(columns
+                .into_iter()
+                .filter_map(|(column, remarks)| Some((column, remarks?)))
+                .collect());
+        };
+        let parameters: Vec<_> = parameters
+            .iter()
+            .map(|parameter| parameter.as_str().into_parameter())
+            .collect();
+        let mut cursor = conn
+            .execute(&query, parameters.as_slice())?
+            .ok_or_else(|| anyhow!("Query for column comments did not return a result set."))?;
+        let mut comments = Vec::new();
+        let mut buf = Vec::new();
+        while let Some(mut row) = cursor.next_row()? {
+            row.get_text(1, &mut buf)?;
+            let column = String::from_utf8_lossy(&buf).into_owned();
+            if row.get_text(2, &mut buf)? {
+                comments.push((column, String::from_utf8_lossy(&buf).into_owned()));
+            }
+        }
+        Ok(comments)
+    }
+
+    /// Columns of the table, as reported by the catalog functions of the driver.
+    fn columns(&self, conn: &Connection) -> Result<Columns, Error> {
+        let catalog = match &self.catalog {
+            Some(catalog) => catalog.clone(),
+            None => conn.current_catalog()?,
+        };
+        let escape = search_pattern_escape(conn)?;
+        let schema_pattern = self
+            .schema
+            .as_deref()
+            .map(|schema| escape_pattern(schema, &escape))
+            .unwrap_or_else(|| "%".to_owned());
+        let table_pattern = escape_pattern(&self.table, &escape);
+        let mut cursor = conn.columns(&catalog, &schema_pattern, &table_pattern, "%")?;
+
+        let mut schemas: Vec<String> = Vec::new();
+        let mut columns = Vec::new();
+        let mut buf = Vec::new();
+        while let Some(mut row) = cursor.next_row()? {
+            row.get_text(2, &mut buf)?;
+            let schema = String::from_utf8_lossy(&buf).into_owned();
+            row.get_text(4, &mut buf)?;
+            let column = String::from_utf8_lossy(&buf).into_owned();
+            let remarks = row
+                .get_text(12, &mut buf)?
+                .then(|| String::from_utf8_lossy(&buf).into_owned())
+                .filter(|remarks| !remarks.is_empty());
+            if !schemas.contains(&schema) {
+                schemas.push(schema);
+            }
+            columns.push((column, remarks));
+        }
+
+        let schema = match schemas.as_slice() {
+            [] => bail!(
+                "Could not find any columns of table '{}'. Check the spelling of the table name \
+                and specify the schema and catalog, if the table is not part of the default ones.",
+                self.table
+            ),
+            [schema] => schema.clone(),
+            _ => bail!(
+                "A table named '{}' exists in multiple schemas ({}). Specify one with `--schema`.",
+                self.table,
+                schemas.join(", ")
+            ),
+        };
+        Ok(Columns {
+            catalog,
+            schema,
+            columns,
+        })
+    }
+}
+
+/// Columns of a table, together with the catalog and schema the table has been found in.
+struct Columns {
+    catalog: String,
+    schema: String,
+    /// Name and remarks of each column.
+    columns: Vec<(String, Option<String>)>,
+}
+
+/// Query for the comments of the columns of a table, together with its parameters. The query
+/// returns the name of each column with a comment in the first column and the comment in the
+/// second. `None` if the dialect of the data source is not known. `catalog` is only specified if
+/// it differs from the current one.
+fn comments_query(
+    db_name: &str,
+    catalog: Option<&str>,
+    schema: &str,
+    table: &str,
+) -> Option<(String, Vec<String>)> {
+    let quote = |identifier: &str| quote_identifier(identifier, db_name);
+    let qualified = || {
+        let mut qualified = String::new();
+        for identifier in catalog.into_iter().chain([schema]) {
+            if !identifier.is_empty() {
+                qualified.push_str(&quote(identifier));
+                qualified.push('.');
+            }
+        }
+        qualified.push_str(&quote(table));
+        qualified
+    };
+    match db_name {
+        "Microsoft SQL Server" => {
+            let prefix = catalog
+                .map(|c| format!("{}.", quote(c)))
+                .unwrap_or_default();
+            Some((
+                format!(
+                    "SELECT c.name, CAST(p.value AS NVARCHAR(4000)) \
+                    FROM {prefix}sys.extended_properties p \
+                    JOIN {prefix}sys.columns c \
+                    ON c.object_id = p.major_id AND c.column_id = p.minor_id \
+                    WHERE p.class = 1 AND p.name = 'MS_Description' AND p.major_id = OBJECT_ID(?)"
+                ),
+                vec![qualified()],
+            ))
+        }
+        "Oracle" => Some((
+            "SELECT COLUMN_NAME, COMMENTS FROM ALL_COL_COMMENTS \
+            WHERE OWNER = ? AND TABLE_NAME = ? AND COMMENTS IS NOT NULL"
+                .to_owned(),
+            vec![schema.to_owned(), table.to_owned()],
+        )),
+        "PostgreSQL" => Some((
+            "SELECT a.attname, col_description(a.attrelid, a.attnum) \
+            FROM pg_catalog.pg_attribute a \
+            WHERE a.attrelid = CAST(? AS regclass) AND a.attnum > 0 AND NOT a.attisdropped \
+            AND col_description(a.attrelid, a.attnum) IS NOT NULL"
+                .to_owned(),
+            vec![qualified()],
+        )),
+        _ => None,
+    }
+}
+
+/// Quotes an identifier using the delimiters of the database management system. Brackets for
+/// Microsoft SQL Server, backticks for MySQL and MariaDB and double quotes (as defined by the SQL
+/// standard) for everything else.
+pub fn quote_identifier(identifier: &str, db_name: &str) -> String {
+    match db_name {
+        "Microsoft SQL Server" => format!("[{}]", identifier.replace(']', "]]")),
+        "MySQL" | "MariaDB" => format!("`{}`", identifier.replace('`', "``")),
+        _ => format!("\"{}\"", identifier.replace('"', "\"\"")),
+    }
+}
+
+/// Escapes the wildcards of search patterns used by catalog functions, so the name is matched
+/// literally.
+///
+/// * `escape`: Escape character reported by the driver for search patterns. If empty, the name is
+///   returned as is, and wildcards within it match any character.
+pub fn escape_pattern(name: &str, escape: &str) -> String {
+    if escape.is_empty() {
+        return name.to_owned();
+    }
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        if matches!(c, '%' | '_') || escape.starts_with(c) {
+            escaped.push_str(escape);
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{comments_query, escape_pattern, quote_identifier};
+
+    #[test]
+    fn query_column_comments_by_dialect() {
+        let (query, parameters) =
+            comments_query("Microsoft SQL Server", None, "dbo", "sales").unwrap();
+        assert!(query.contains(" FROM sys.extended_properties p "));
+        assert_eq!(vec!["[dbo].[sales]".to_owned()], parameters);
+
+        let (query, parameters) =
+            comments_query("PostgreSQL", Some("shop"), "public", "sales").unwrap();
+        assert!(query.contains("col_description"));
+        assert_eq!(vec!["\"shop\".\"public\".\"sales\"".to_owned()], parameters);
+
+        let (_query, parameters) = comments_query("Oracle", None, "SCOTT", "EMP").unwrap();
+        assert_eq!(vec!["SCOTT".to_owned(), "EMP".to_owned()], parameters);
+
+        assert!(comments_query("SQLite", None, "", "sales").is_none());
+    }
+
+    #[test]
+    fn quote_identifiers_by_dialect() {
+        assert_eq!("[a]]b]", quote_identifier("a]b", "Microsoft SQL Server"));
+        assert_eq!("`a``b`", quote_identifier("a`b", "MySQL"));
+        assert_eq!("\"a\"\"b\"", quote_identifier("a\"b", "PostgreSQL"));
+    }
+
+    #[test]
+    fn escape_wildcards_in_patterns() {
+        assert_eq!("my\\_table\\%", escape_pattern("my_table%", "\\"));
+        assert_eq!("a\\\\b", escape_pattern("a\\b", "\\"));
+        assert_eq!("my/_table//", escape_pattern("my_table/", "/"));
+        // Driver does not support escaping wildcards
+        assert_eq!("my_table", escape_pattern("my_table", ""));
+    }
+}
@@ -1,32 +1,120 @@
-use anyhow::{bail, Context, Error};
-use log::{debug, info};
+use anyhow::{anyhow, bail, Context, Error};
+use bytes::Bytes;
+use bytesize::ByteSize;
+use log::{debug, info, warn};
 use odbc_api::{
-    buffers::ColumnarAnyBuffer, BlockCursor, ColumnDescription, Cursor, ResultSetMetadata,
+    buffers::{AnyBuffer, BufferDesc, ColumnarAnyBuffer, ColumnarBuffer},
+    ColumnDescription, Cursor, DataType, ResultSetMetadata, RowSetBuffer,
 };
 use parquet::{
-    file::writer::SerializedColumnWriter,
-    schema::types::{Type, TypePtr},
+    basic::{Repetition, Type as PhysicalType},
+    column::writer::{get_column_writer, get_typed_column_writer_mut, ColumnCloseResult},
+    data_type::{BoolType, ByteArray, ByteArrayType},
+    file::{
+        metadata::{KeyValue, RowGroupMetaDataPtr},
+        properties::{EnabledStatistics, WriterProperties, WriterPropertiesPtr},
+        writer::{
+            SerializedColumnWriter, SerializedFileWriter, SerializedPageWriter, TrackedWrite,
+        },
+    },
+    schema::types::{ColumnDescPtr, SchemaDescriptor, Type, TypePtr},
+};
+use std::{
+    fmt,
+    io::{self, Write},
+    mem,
+    num::NonZeroUsize,
+    path::PathBuf,
+    sync::{mpsc::sync_channel, Arc},
+    thread,
 };
-use std::sync::Arc;
 
-use crate::parquet_buffer::ParquetBuffer;
+use crate::{
+    column_profile::ColumnProfile,
+    diagnostics::check_warnings,
+    enum_args::{ColumnGroup, TimestampTzFrom, UnknownTimeZone},
+    parquet_buffer::{FillValue, ParquetBuffer, UnexpectedNull},
+};
 
 use super::{
-    batch_size_limit::BatchSizeLimit,
-    column_strategy::{strategy_from_column_description, ColumnStrategy, MappingOptions},
-    parquet_writer::ParquetOutput,
+    bad_rows::{raw_value, retain_rows, BadRowsFile},
+    batch_size_limit::{apply_memory_limit, conversion_bytes_per_row, BatchSizeLimit},
+    binary::Binary,
+    blob::{
+        copy_first_value, key_as_text, path_strategy, BlobExtraction, BlobFiles, BlobToFile,
+        BLOB_CHUNK_SIZE,
+    },
+    column_strategy::{
+        companion_strategies, strategy_from_column_description, strategy_from_target_type,
+        ColumnStrategy, Companion, MappingOptions,
+    },
+    conversion_audit::ConversionAudit,
+    derive::DerivedColumn,
+    fail_on_null::FailOnNull,
+    null_fill::NullFill,
+    numeric::{NumericBinding, NumericColumn},
+    padding::{write_filler, Padding},
+    parquet_writer::{OutputSize, ParquetOutput, WrittenRowGroup},
+    profile::write_profile,
+    text::Utf8,
+    throttle::Throttle,
+    timestamp_in_zone::TimestampInZone,
+    transform_script::TransformScript,
 };
 
 /// Contains the decisions of how to fetch each columns of a table from an ODBC data source and copy
 /// it into a parquet file. This decisions include what kind of ODBC C_TYPE to use to fetch the data
 /// and in what these columns are transformed.
 pub struct TableStrategy {
+    /// One entry for each column in the parquet output.
     columns: Vec<ColumnInfo>,
-    parquet_schema: TypePtr,
+    /// One buffer description for each bound column in the ODBC result set, together with the one
+    /// based index of the column in the result set. Usually there is exactly one parquet column for
+    /// each of these, but companion columns may share the buffer of the column they are derived
+    /// from.
+    buffer_descs: Vec<(u16, BufferDesc)>,
+    /// Drop columns which can not be fetched, instead of failing.
+    skip_columns_on_error: bool,
+    /// Name of each column dropped due to `skip_columns_on_error` together with the reason.
+    skipped_columns: Vec<(String, Error)>,
+    /// Description of each column in the result set. Used to verify that further result sets
+    /// written into the same output have the same schema.
+    column_descriptions: Vec<ColumnDescription>,
+    /// Columns computed from the columns of the result set, following them in the output.
+    derived_columns: Vec<DerivedColumn>,
+    /// Names of additional text columns, following the derived columns, which hold the
+    /// values of the parameters the query has been executed with.
+    parameter_columns: Vec<String>,
+    /// Name of an additional boolean column following the parameter columns, which flags filler
+    /// rows appended by `--pad-to-rows`.
+    pad_flag_column: Option<String>,
+    /// Zero based position of each column in the order of the result set, if the columns have been
+    /// reordered with `--reorder-for-size`. One entry for each element of `columns`.
+    original_positions: Option<Vec<usize>>,
+    /// Name of the root group of the parquet schema.
+    schema_name: String,
+    /// Columns whose values are written into files, if any.
+    blob_files: Option<BlobFiles>,
+    /// Columns bound as `SQL_C_NUMERIC`, see `--numeric-decimals`.
+    numeric_columns: Vec<NumericColumn>,
 }
 
-/// Name, ColumnStrategy
-type ColumnInfo = (String, Box<dyn ColumnStrategy>);
+/// Buffer length for bookmarks of drivers, which do not report the length of their bookmarks.
+const MAX_BOOKMARK_LENGTH: usize = 256;
+
+/// A column in the parquet output and how to fill it.
+struct ColumnInfo {
+    /// Name of the column in the parquet output
+    name: String,
+    strategy: Box<dyn ColumnStrategy>,
+    /// Zero based index of the column in the ODBC fetch buffer the values are copied from.
+    buffer_index: usize,
+    /// Zero based index of a key column in the ODBC fetch buffer, whose values are passed to the
+    /// strategy alongside the values of this column.
+    key_buffer_index: Option<usize>,
+    /// Name of the group the column is nested in. Columns of the same group are adjacent.
+    group: Option<String>,
+}
 
 impl TableStrategy {
     pub fn new(
@@ -36,6 +124,13 @@ impl TableStrategy {
         let num_cols = cursor.num_result_cols()?;
 
         let mut columns = Vec::new();
+        let mut buffer_descs = Vec::new();
+        let mut skipped_columns = Vec::new();
+        let mut column_descriptions = Vec::new();
+        let mut numeric_columns = Vec::new();
+        let mut audit = mapping_options
+            .warn_on_implicit_conversion
+            .then(ConversionAudit::default);
 
         for index in 1..(num_cols + 1) {
             let mut cd = ColumnDescription::default();
@@ -53,40 +148,296 @@ impl TableStrategy {
                 name
             };
 
-            let column_fetch_strategy =
-                strategy_from_column_description(&cd, &name, mapping_options, cursor, index)?;
-            columns.push((name, column_fetch_strategy));
+            let strategies = match mapping_options.blob_extraction {
+                // Values of extracted columns are streamed into files, so their length does not
+                // matter.
+                Some(blobs) if blobs.columns.contains(&name) => path_strategy(cd.data_type, &name)
+                    .map(|strategy| vec![(name.clone(), strategy)]),
+                _ => column_strategies(&cd, &name, mapping_options, cursor, index),
+            };
+            let data_type = cd.data_type;
+            column_descriptions.push(cd);
+            let strategies = match strategies {
+                Ok(strategies) => strategies,
+                Err(error) if mapping_options.skip_columns_on_error => {
+                    warn!("Skipping column '{name}': {error:#}");
+                    skipped_columns.push((name, error));
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+            if let Some(audit) = &mut audit {
+                audit.check(&strategies[0].0, data_type, strategies[0].1.as_ref());
+            }
+            // The first strategy is the one of the column itself, all others are companions.
+            let buffer_index = buffer_descs.len();
+            buffer_descs.push((index as u16, strategies[0].1.buffer_desc()));
+            if let Some((precision, scale)) = strategies[0].1.numeric_binding() {
+                numeric_columns.push(NumericColumn {
+                    column_number: index as u16,
+                    precision,
+                    scale,
+                });
+            }
+            for (name, strategy) in strategies {
+                let strategy = match mapping_options.null_fill.iter().find(|(n, _)| n == &name) {
+                    Some((_, fill)) => Box::new(NullFill::new(strategy, &name, fill)?),
+                    None if mapping_options.force_required_all => {
+                        Box::new(NullFill::required(strategy))
+                    }
+                    None => strategy,
+                };
+                let strategy = if mapping_options.fail_on_null.contains(&name) {
+                    Box::new(FailOnNull::new(strategy))
+                } else {
+                    strategy
+                };
+                columns.push(ColumnInfo {
+                    name,
+                    strategy,
+                    buffer_index,
+                    key_buffer_index: None,
+                    group: None,
+                })
+            }
+        }
+        if let Some(audit) = &audit {
+            audit.log_summary();
+        }
+
+        if let Some(name) = mapping_options.rowid_column {
+            if columns.iter().any(|c| c.name == name) {
+                bail!(
+                    "Column '{name}' of `--rowid-column` collides with a column of the result set."
+                )
+            }
+            // Describing the bookmark column fails, unless the driver supports bookmarks and they
+            // have been enabled for the statement.
+            let mut cd = ColumnDescription::default();
+            match cursor.describe_col(0, &mut cd) {
+                Ok(()) => {
+                    debug!("ODBC column description for bookmark column: {:?}", cd);
+                    let length = cd
+                        .data_type
+                        .column_size()
+                        .map_or(MAX_BOOKMARK_LENGTH, NonZeroUsize::get);
+                    columns.push(ColumnInfo {
+                        name: name.to_owned(),
+                        strategy: Box::new(Binary::<ByteArrayType>::new(
+                            Repetition::OPTIONAL,
+                            length,
+                        )),
+                        buffer_index: buffer_descs.len(),
+                        key_buffer_index: None,
+                        group: None,
+                    });
+                    buffer_descs.push((0, BufferDesc::Binary { length }));
+                }
+                Err(error) => warn!(
+                    "The driver does not provide bookmarks for the result set, so the output has \
+                    no column '{name}'. {error}"
+                ),
+            }
         }
 
         if columns.is_empty() {
             bail!("Resulting parquet file would not have any columns!")
         }
 
-        let fields = columns
+        if let Some((name, _)) = mapping_options
+            .null_fill
             .iter()
-            .map(|(name, s)| Arc::new(s.parquet_type(name)))
-            .collect();
-        let parquet_schema = Arc::new(
-            Type::group_type_builder("schema")
-                .with_fields(fields)
-                .build()
-                .unwrap(),
-        );
+            .find(|(name, _)| columns.iter().all(|c| &c.name != name))
+        {
+            bail!("Column '{name}' of `--null-fill` is not part of the result set.")
+        }
+
+        if let Some(name) = mapping_options
+            .fail_on_null
+            .iter()
+            .find(|&name| columns.iter().all(|c| &c.name != name))
+        {
+            bail!("Column '{name}' of `--fail-fast-on-null` is not part of the result set.")
+        }
+
+        if let Some(name) = mapping_options
+            .bool_from_int
+            .iter()
+            .chain(mapping_options.int_from_bool)
+            .find(|&name| columns.iter().all(|c| &c.name != name))
+        {
+            bail!("Column '{name}' to coerce is not part of the result set.")
+        }
+
+        if let Some((name, _)) = mapping_options
+            .column_max_length
+            .iter()
+            .find(|(name, _)| columns.iter().all(|c| &c.name != name))
+        {
+            bail!("Column '{name}' of `--column-max-length` is not part of the result set.")
+        }
+
+        if let Some(name) = mapping_options
+            .int96_columns
+            .iter()
+            .find(|&name| columns.iter().all(|c| &c.name != name))
+        {
+            bail!("Column '{name}' of `--int96-columns` is not part of the result set.")
+        }
+
+        if let Some(bind_as) = mapping_options
+            .bind_as
+            .iter()
+            .find(|bind_as| columns.iter().all(|c| c.name != bind_as.column_name))
+        {
+            bail!(
+                "Column '{}' of `--bind-as` is not part of the result set.",
+                bind_as.column_name
+            )
+        }
+
+        let blob_files = mapping_options
+            .blob_extraction
+            .map(|blob_extraction| blob_files(&columns, blob_extraction))
+            .transpose()?;
+
+        for ts_tz_from in mapping_options.timestamp_tz_from {
+            timestamp_in_zone(
+                &mut columns,
+                &buffer_descs,
+                &column_descriptions,
+                ts_tz_from,
+                mapping_options.unknown_time_zone,
+            )?;
+        }
+
+        if let Some(target_schema) = mapping_options.target_schema {
+            let names: Vec<&str> = columns.iter().map(|c| c.name.as_str()).collect();
+            target_schema.check_all_matched(&names)?;
+        }
+
+        let original_positions = mapping_options
+            .reorder_for_size
+            .then(|| reorder_for_size(&mut columns));
+
+        group_columns(&mut columns, mapping_options.column_groups)?;
+
+        let mut derived_columns: Vec<DerivedColumn> = Vec::new();
+        for derivation in mapping_options.derive {
+            let name = &derivation.name;
+            if columns
+                .iter()
+                .any(|c| c.group.as_deref().unwrap_or(&c.name) == name)
+                || derived_columns.iter().any(|d| &d.name == name)
+            {
+                bail!("Derived column '{name}' collides with another column of the output.")
+            }
+            derived_columns.push(derivation.bind(|name| {
+                columns
+                    .iter()
+                    .find(|c| c.name == name)
+                    .map(|c| (c.buffer_index, buffer_descs[c.buffer_index].1))
+            })?);
+        }
 
         Ok(TableStrategy {
             columns,
-            parquet_schema,
+            buffer_descs,
+            skip_columns_on_error: mapping_options.skip_columns_on_error,
+            skipped_columns,
+            column_descriptions,
+            derived_columns,
+            parameter_columns: Vec::new(),
+            pad_flag_column: None,
+            original_positions,
+            schema_name: mapping_options.schema_name.to_owned(),
+            blob_files,
+            numeric_columns,
         })
     }
 
+    /// Key value metadata recording the order of the columns in the result set, if they have been
+    /// reordered. For each column of the result set in its original order, the value lists its
+    /// zero based index in the output, separated by commas.
+    pub fn original_column_order(&self) -> Option<KeyValue> {
+        let positions = self.original_positions.as_ref()?;
+        let mut indices: Vec<usize> = (0..positions.len()).collect();
+        indices.sort_by_key(|&index| positions[index]);
+        let value = indices
+            .iter()
+            .map(usize::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        Some(KeyValue::new(
+            "odbc2parquet.original_column_order".to_owned(),
+            value,
+        ))
+    }
+
+    /// Append text columns holding the values of the parameters, the query has been executed with.
+    pub fn add_parameter_columns(&mut self, names: Vec<String>) -> Result<(), Error> {
+        if let Some(name) = names.iter().find(|&name| self.has_top_level_name(name)) {
+            bail!("Parameter column '{name}' collides with a column of the result set.")
+        }
+        self.parameter_columns = names;
+        Ok(())
+    }
+
+    /// Append a boolean column, which is `true` for filler rows and `false` for all other rows.
+    pub fn add_pad_flag_column(&mut self, name: String) -> Result<(), Error> {
+        if self.has_top_level_name(&name) || self.parameter_columns.contains(&name) {
+            bail!("Pad flag column '{name}' collides with another column of the output.")
+        }
+        self.pad_flag_column = Some(name);
+        Ok(())
+    }
+
+    /// `true` if a column or group at the top level of the output has this name.
+    fn has_top_level_name(&self, name: &str) -> bool {
+        self.columns
+            .iter()
+            .any(|c| c.group.as_deref().unwrap_or(&c.name) == name)
+            || self.derived_columns.iter().any(|d| d.name == name)
+    }
+
+    /// Fails if the result set of `cursor` differs from the one this strategy has been created for.
+    pub fn check_same_schema(&self, cursor: &mut impl ResultSetMetadata) -> Result<(), Error> {
+        let num_cols: usize = cursor.num_result_cols()?.try_into().unwrap();
+        if num_cols != self.column_descriptions.len() {
+            bail!(
+                "Result set has {num_cols} columns, but the first result set had {}.",
+                self.column_descriptions.len()
+            )
+        }
+        for (index, expected) in (1..).zip(&self.column_descriptions) {
+            let mut cd = ColumnDescription::default();
+            cursor.describe_col(index, &mut cd)?;
+            if &cd != expected {
+                bail!(
+                    "Column {index} of the result set is described as {cd:?}, but the first result \
+                    set described it as {expected:?}."
+                )
+            }
+        }
+        Ok(())
+    }
+
+    /// Allocates the buffers for fetching the result set. If `skip_columns_on_error` is set,
+    /// columns whose buffers can not be allocated are dropped from the output.
+    ///
+    /// * `compression_threads`: Number of threads writing the columns of a row group. Accounted
+    ///   for by `memory_limit`, since with more than one thread a second fetch buffer and a
+    ///   conversion buffer per thread are allocated.
     pub fn allocate_fetch_buffer(
-        &self,
+        &mut self,
         batch_size: BatchSizeLimit,
+        memory_limit: Option<ByteSize>,
+        compression_threads: usize,
     ) -> Result<ColumnarAnyBuffer, Error> {
         let mem_usage_odbc_buffer_per_row: usize = self
-            .columns
+            .buffer_descs
             .iter()
-            .map(|(_name, strategy)| strategy.buffer_desc().bytes_per_row())
+            .map(|(_, desc)| desc.bytes_per_row())
             .sum();
         let total_mem_usage_per_row =
             mem_usage_odbc_buffer_per_row + ParquetBuffer::MEMORY_USAGE_BYTES_PER_ROW;
@@ -96,96 +447,1465 @@ impl TableStrategy {
             total_mem_usage_per_row,
         );
 
-        let batch_size_row = batch_size.batch_size_in_rows(total_mem_usage_per_row)?;
+        let mut batch_size_row = batch_size.batch_size_in_rows(total_mem_usage_per_row)?;
+
+        if let Some(memory_limit) = memory_limit {
+            let conversion_mem_usage_per_row: usize = self
+                .buffer_descs
+                .iter()
+                .map(|&(_, desc)| conversion_bytes_per_row(desc))
+                .sum();
+            // See `Export::write_cursor_pipelined` and `ColumnExporter::export_in_parallel`.
+            let (num_fetch_buffers, num_conversion_buffers) = if compression_threads > 1 {
+                (2, compression_threads + 1)
+            } else {
+                (1, 1)
+            };
+            batch_size_row = apply_memory_limit(
+                batch_size_row,
+                num_fetch_buffers * mem_usage_odbc_buffer_per_row
+                    + num_conversion_buffers * ParquetBuffer::MEMORY_USAGE_BYTES_PER_ROW
+                    + conversion_mem_usage_per_row,
+                memory_limit,
+            )?;
+        }
 
         info!("Batch size set to {} rows.", batch_size_row);
 
-        let fetch_buffer = ColumnarAnyBuffer::from_descs(
-            batch_size_row,
-            self.columns
+        let fetch_buffer = if self.skip_columns_on_error {
+            self.allocate_or_skip_columns(batch_size_row)?
+        } else {
+            ColumnarAnyBuffer::from_descs_and_indices(
+                batch_size_row,
+                self.buffer_descs.iter().copied(),
+            )
+        };
+
+        if !self.skipped_columns.is_empty() {
+            let summary: Vec<String> = self
+                .skipped_columns
                 .iter()
-                .map(|(_name, strategy)| strategy.buffer_desc()),
-        );
+                .map(|(name, error)| format!("'{name}' ({error})"))
+                .collect();
+            warn!(
+                "{} columns have been skipped, due to errors: {}",
+                self.skipped_columns.len(),
+                summary.join(", ")
+            );
+        }
 
         Ok(fetch_buffer)
     }
 
+    /// Allocate the buffer of each column individually and drop columns for which this fails.
+    fn allocate_or_skip_columns(
+        &mut self,
+        batch_size_row: usize,
+    ) -> Result<ColumnarAnyBuffer, Error> {
+        let mut buffers = Vec::new();
+        let mut buffer_index = 0;
+        while buffer_index < self.buffer_descs.len() {
+            let (column_index, desc) = self.buffer_descs[buffer_index];
+            match AnyBuffer::try_from_desc(batch_size_row, desc) {
+                Ok(buffer) => {
+                    buffers.push((column_index, buffer));
+                    buffer_index += 1;
+                }
+                Err(error) => {
+                    // Column zero is the bookmark column.
+                    let error = Error::from(error.add_context(column_index.saturating_sub(1)));
+                    self.remove_buffer(buffer_index, error)?;
+                }
+            }
+        }
+        Ok(ColumnarBuffer::new(buffers))
+    }
+
+    /// Drops a bound column, together with all parquet columns filled from it.
+    fn remove_buffer(&mut self, buffer_index: usize, error: Error) -> Result<(), Error> {
+        if self
+            .blob_files
+            .as_ref()
+            .is_some_and(|blobs| blobs.key_buffer_index == buffer_index)
+        {
+            return Err(error.context("Key column of blob extraction can not be skipped."));
+        }
+        if self
+            .columns
+            .iter()
+            .any(|c| c.key_buffer_index == Some(buffer_index))
+        {
+            return Err(error.context("Time zone column of `--ts-tz-from` can not be skipped."));
+        }
+        if let Some(derived) = self
+            .derived_columns
+            .iter()
+            .find(|d| d.uses_buffer(buffer_index))
+        {
+            return Err(error.context(format!(
+                "Column used by derived column '{}' can not be skipped.",
+                derived.name
+            )));
+        }
+        let name = self
+            .columns
+            .iter()
+            .find(|c| c.buffer_index == buffer_index)
+            .map(|c| c.name.clone())
+            .unwrap_or_default();
+        warn!("Skipping column '{name}': {error:#}");
+        self.skipped_columns.push((name, error));
+        self.buffer_descs.remove(buffer_index);
+        if let Some(positions) = &mut self.original_positions {
+            let mut columns = self.columns.iter();
+            positions.retain(|_| columns.next().unwrap().buffer_index != buffer_index);
+        }
+        self.columns.retain(|c| c.buffer_index != buffer_index);
+        for column in &mut self.columns {
+            if column.buffer_index > buffer_index {
+                column.buffer_index -= 1;
+            }
+            if let Some(key) = column
+                .key_buffer_index
+                .as_mut()
+                .filter(|key| **key > buffer_index)
+            {
+                *key -= 1;
+            }
+        }
+        for derived in &mut self.derived_columns {
+            derived.shift_buffer_indices(buffer_index);
+        }
+        if let Some(blobs) = &mut self.blob_files {
+            blobs.columns.retain(|(index, _)| *index != buffer_index);
+            for index in blobs
+                .columns
+                .iter_mut()
+                .map(|(index, _)| index)
+                .chain([&mut blobs.key_buffer_index])
+                .filter(|index| **index > buffer_index)
+            {
+                *index -= 1;
+            }
+        }
+        if self.columns.is_empty() {
+            bail!("Resulting parquet file would not have any columns!")
+        }
+        Ok(())
+    }
+
+    /// Name of the column each buffer is fetched for, by buffer index.
+    fn buffer_names(&self) -> Vec<&str> {
+        (0..self.buffer_descs.len())
+            .map(|buffer_index| {
+                self.columns
+                    .iter()
+                    .find(|c| c.buffer_index == buffer_index)
+                    .map_or("", |c| c.name.as_str())
+            })
+            .collect()
+    }
+
     pub fn parquet_schema(&self) -> TypePtr {
-        self.parquet_schema.clone()
+        let parameter_type = Utf8::with_bytes_length(Repetition::OPTIONAL, 0);
+        let mut fields: Vec<TypePtr> = Vec::new();
+        let mut columns = self.columns.iter().peekable();
+        while let Some(column) = columns.next() {
+            let field = Arc::new(column.strategy.parquet_type(&column.name));
+            let Some(group) = &column.group else {
+                fields.push(field);
+                continue;
+            };
+            let mut members = vec![field];
+            while let Some(member) = columns.next_if(|c| c.group == column.group) {
+                members.push(Arc::new(member.strategy.parquet_type(&member.name)));
+            }
+            // The group itself is never NULL, nullability is a property of each nested column.
+            // This way definition levels of the nested columns stay the same as on the top level.
+            fields.push(Arc::new(
+                Type::group_type_builder(group)
+                    .with_repetition(Repetition::REQUIRED)
+                    .with_fields(members)
+                    .build()
+                    .unwrap(),
+            ));
+        }
+        fields.extend(
+            self.derived_columns
+                .iter()
+                .map(|derived| Arc::new(derived.parquet_type())),
+        );
+        fields.extend(
+            self.parameter_columns
+                .iter()
+                .map(|name| Arc::new(parameter_type.parquet_type(name))),
+        );
+        fields.extend(self.pad_flag_column.iter().map(|name| {
+            Arc::new(
+                Type::primitive_type_builder(name, PhysicalType::BOOLEAN)
+                    .with_repetition(Repetition::REQUIRED)
+                    .build()
+                    .unwrap(),
+            )
+        }));
+        Arc::new(
+            Type::group_type_builder(&self.schema_name)
+                .with_fields(fields)
+                .build()
+                .unwrap(),
+        )
     }
 
-    pub fn block_cursor_to_parquet(
+    /// Start writing result sets into `writer`.
+    ///
+    /// * `batch_capacity`: Maximum number of rows in each batch fetched.
+    pub fn export(
         &self,
-        mut row_set_cursor: BlockCursor<impl Cursor, &mut ColumnarAnyBuffer>,
-        mut writer: Box<dyn ParquetOutput>,
+        writer: Box<dyn ParquetOutput>,
+        options: RowGroupOptions,
+        batch_capacity: usize,
+    ) -> Result<Export<'_>, Error> {
+        let mut pb = ParquetBuffer::new(batch_capacity);
+        if options.skip_bad_rows {
+            pb.skip_bad_values();
+        }
+        for name in &options.group_count {
+            if !self.columns.iter().any(|column| &column.name == name) {
+                bail!("Column '{name}' passed to `--group-count` is not part of the result set.")
+            }
+        }
+        for name in &options.no_sample_columns {
+            if !self.columns.iter().any(|column| &column.name == name) {
+                bail!(
+                    "Column '{name}' passed to `--no-sample-columns` is not part of the result set."
+                )
+            }
+        }
+        let profiles = self
+            .columns
+            .iter()
+            .map(|column| {
+                let with_histogram = options.group_count.contains(&column.name);
+                if options.profile.is_none() && !with_histogram {
+                    return None;
+                }
+                let parquet_type = column.strategy.parquet_type(&column.name);
+                let mut profile = ColumnProfile::new(&parquet_type);
+                if with_histogram {
+                    profile = profile.with_histogram(options.group_count_max_values);
+                }
+                match options.sample_values {
+                    Some(max_values) if !options.no_sample_columns.contains(&column.name) => {
+                        Some(profile.with_samples(&parquet_type, max_values))
+                    }
+                    _ => Some(profile),
+                }
+            })
+            .collect();
+        let pad_values = match &options.padding {
+            Some(padding) => padding.fill_values(self.columns.iter().map(|column| {
+                (
+                    column.name.as_str(),
+                    column.strategy.parquet_type(&column.name),
+                )
+            }))?,
+            None => Vec::new(),
+        };
+        // With multiple compression threads the next batch is fetched while the last one is
+        // written, which requires a second fetch buffer.
+        let spare_buffer = (options.compression_threads > 1).then(|| {
+            ColumnarAnyBuffer::from_descs_and_indices(
+                batch_capacity,
+                self.buffer_descs.iter().copied(),
+            )
+        });
+        Ok(Export {
+            table_strategy: self,
+            writer,
+            options,
+            batch_capacity,
+            spare_buffer,
+            pb,
+            num_bad_values: vec![0u64; self.columns.len()],
+            num_dropped_rows: 0,
+            num_failed_transforms: 0,
+            num_truncated_values: vec![0u64; self.columns.len()],
+            num_precision_violations: vec![0u64; self.columns.len()],
+            profiles,
+            pad_values,
+            num_batch: 0,
+            total_rows_fetched: 0,
+        })
+    }
+}
+
+/// Writes the result sets of one or more cursors into the same output.
+pub struct Export<'a> {
+    table_strategy: &'a TableStrategy,
+    writer: Box<dyn ParquetOutput>,
+    options: RowGroupOptions,
+    batch_capacity: usize,
+    /// Second fetch buffer, filled with the next batch while the batch in the first one is written.
+    /// Only allocated for `--compression-threads` greater than one.
+    spare_buffer: Option<ColumnarAnyBuffer>,
+    pb: ParquetBuffer,
+    /// Number of values replaced with NULL, or whose row has been dropped, because they could not
+    /// be converted. One entry per column.
+    num_bad_values: Vec<u64>,
+    /// Number of rows left out, because they hold values which could not be converted.
+    num_dropped_rows: u64,
+    /// Number of rows the transform script failed for.
+    num_failed_transforms: u64,
+    /// Number of values which have been truncated. One entry per column.
+    num_truncated_values: Vec<u64>,
+    /// Number of decimals with more digits than their precision. One entry per column.
+    num_precision_violations: Vec<u64>,
+    /// Profile of each column, if requested.
+    profiles: Vec<Option<ColumnProfile>>,
+    /// Value of each column in filler rows, if `--pad-to-rows` is specified. `None` for NULL.
+    pad_values: Vec<Option<FillValue>>,
+    num_batch: u32,
+    /// Count the number of total rows fetched so far for logging. This should be identical to
+    /// `num_batch * batch_size_row + num_rows`.
+    total_rows_fetched: u64,
+}
+
+impl Export<'_> {
+    /// Write all rows of the result set as row groups into the output.
+    ///
+    /// * `parameters`: Values written into the parameter columns, if any.
+    /// * `buffer`: Fetch buffer allocated by [`TableStrategy::allocate_fetch_buffer`]. Not used if
+    ///   columns are extracted into files.
+    pub fn write_cursor(
+        &mut self,
+        parameters: &[String],
+        cursor: impl Cursor,
+        buffer: &mut ColumnarAnyBuffer,
+    ) -> Result<(), Error> {
+        if let Some(blob_files) = &self.table_strategy.blob_files {
+            return self.write_cursor_extracting_blobs(parameters, cursor, blob_files);
+        }
+        if let Some(mut spare_buffer) = self.spare_buffer.take() {
+            let result = self.write_cursor_pipelined(parameters, cursor, buffer, &mut spare_buffer);
+            self.spare_buffer = Some(spare_buffer);
+            return result;
+        }
+        let numeric_columns = &self.table_strategy.numeric_columns;
+        let mut row_set_cursor =
+            cursor.bind_buffer(NumericBinding::new(buffer, numeric_columns))?;
+        while self.remaining_rows() != Some(0) {
+            check_warnings()?;
+            let Some(binding) = row_set_cursor
+                .fetch()
+                .map_err(give_hint_about_flag_for_oracle_users)?
+            else {
+                break;
+            };
+            if let Some(remaining) = self.rows_within_limit(binding.buffer().num_rows()) {
+                // The driver did not stop the result set at the limit. Discard the rows beyond.
+                let (_cursor, binding) = row_set_cursor.unbind()?;
+                let buffer = binding.into_buffer();
+                *buffer.mut_num_fetch_rows() = remaining;
+                return self.write_fetched(buffer, parameters, true);
+            }
+            self.write_fetched(binding.buffer(), parameters, false)?;
+        }
+        Ok(())
+    }
+
+    /// Like [`Self::write_cursor`], but the batches are written by another thread, while the
+    /// next batch is fetched into the other buffer. The channels between the threads hold at most
+    /// one batch each, so no more than two batches are in flight: one being fetched and one being
+    /// written. Fetching waits for the writer to return a buffer, before it continues.
+    fn write_cursor_pipelined(
+        &mut self,
+        parameters: &[String],
+        mut cursor: impl Cursor,
+        buffer: &mut ColumnarAnyBuffer,
+        spare_buffer: &mut ColumnarAnyBuffer,
     ) -> Result<(), Error> {
-        let mut num_batch = 0;
-        // Count the number of total rows fetched so far for logging. This should be identical to
-        // `num_batch * batch_size_row + num_rows`.
-        let mut total_rows_fetched = 0;
+        if self.remaining_rows() == Some(0) {
+            return Ok(());
+        }
+        let numeric_columns = &self.table_strategy.numeric_columns;
+        let (send_batch, receive_batch) = sync_channel::<&mut ColumnarAnyBuffer>(1);
+        let (send_empty, receive_empty) = sync_channel::<&mut ColumnarAnyBuffer>(2);
+        send_empty.send(buffer).unwrap();
+        send_empty.send(spare_buffer).unwrap();
+        thread::scope(|scope| {
+            let writer = scope.spawn(move || {
+                for buffer in receive_batch {
+                    if let Some(remaining) = self.rows_within_limit(buffer.num_rows()) {
+                        *buffer.mut_num_fetch_rows() = remaining;
+                        // Dropping the channels stops the fetching.
+                        return self.write_fetched(buffer, parameters, true);
+                    }
+                    self.write_fetched(buffer, parameters, false)?;
+                    if self.remaining_rows() == Some(0) || send_empty.send(buffer).is_err() {
+                        break;
+                    }
+                }
+                Ok::<_, Error>(())
+            });
+            // A closed channel means the writer is done, either due to an error, or because it
+            // reached the limit.
+            let fetched = (|| {
+                while let Ok(buffer) = receive_empty.recv() {
+                    check_warnings()?;
+                    let mut block_cursor =
+                        cursor.bind_buffer(NumericBinding::new(buffer, numeric_columns))?;
+                    let has_batch = block_cursor
+                        .fetch()
+                        .map_err(give_hint_about_flag_for_oracle_users)?
+                        .is_some();
+                    let (unbound, binding) = block_cursor.unbind()?;
+                    cursor = unbound;
+                    if !has_batch || send_batch.send(binding.into_buffer()).is_err() {
+                        break;
+                    }
+                }
+                Ok::<_, Error>(())
+            })();
+            drop(send_batch);
+            writer
+                .join()
+                .expect("Thread writing row groups must not panic.")?;
+            fetched
+        })
+    }
+
+    /// Like [`Self::write_cursor`], but the values of the extracted columns are streamed into files
+    /// with `SQLGetData`, so their size is not limited by a buffer. Most drivers support
+    /// `SQLGetData` only for row sets of a single row, and only for columns following the last
+    /// bound column. So the other columns are fetched one row at a time and gathered into batches.
+    fn write_cursor_extracting_blobs(
+        &mut self,
+        parameters: &[String],
+        mut cursor: impl Cursor,
+        blob_files: &BlobFiles,
+    ) -> Result<(), Error> {
+        let buffer_descs = &self.table_strategy.buffer_descs;
+        let bound: Vec<usize> = (0..buffer_descs.len())
+            .filter(|&index| blob_files.columns.iter().all(|(blob, _)| *blob != index))
+            .collect();
+        let key_index = bound
+            .iter()
+            .position(|&index| index == blob_files.key_buffer_index)
+            .expect("Key column must not be extracted");
+        let mut row = ColumnarAnyBuffer::from_descs_and_indices(
+            1,
+            bound.iter().map(|&index| buffer_descs[index]),
+        );
+        let mut chunk = vec![0u8; BLOB_CHUNK_SIZE];
+        while self.remaining_rows() != Some(0) {
+            check_warnings()?;
+            let capacity = self
+                .rows_within_limit(self.batch_capacity)
+                .unwrap_or(self.batch_capacity);
+            let mut columns: Vec<AnyBuffer> = buffer_descs
+                .iter()
+                .map(|&(_, desc)| AnyBuffer::from_desc(capacity, desc))
+                .collect();
+            let mut num_rows = 0;
+            while num_rows < capacity {
+                let mut block_cursor = cursor.bind_buffer(NumericBinding::new(
+                    &mut row,
+                    &self.table_strategy.numeric_columns,
+                ))?;
+                let has_row = block_cursor
+                    .fetch()
+                    .map_err(give_hint_about_flag_for_oracle_users)?
+                    .is_some();
+                // The cursor stays on the fetched row, so the remaining columns can be read.
+                let (unbound, _) = block_cursor.unbind()?;
+                cursor = unbound;
+                if !has_row {
+                    break;
+                }
+                for (source_index, &buffer_index) in bound.iter().enumerate() {
+                    copy_first_value(
+                        row.column(source_index),
+                        &mut columns[buffer_index],
+                        num_rows,
+                    );
+                }
+                let key = key_as_text(row.column(key_index))?;
+                for (buffer_index, files) in &blob_files.columns {
+                    let column_number = buffer_descs[*buffer_index].0;
+                    let path =
+                        files.extract(&mut cursor, column_number, key.as_deref(), &mut chunk)?;
+                    let AnyBuffer::Text(paths) = &mut columns[*buffer_index] else {
+                        unreachable!("Paths of extracted files must be fetched as text.")
+                    };
+                    if let Some(path) = path.as_deref().filter(|path| path.len() > paths.max_len())
+                    {
+                        paths.resize_max_str(path.len(), num_rows);
+                    }
+                    paths.set_value(num_rows, path.as_deref().map(str::as_bytes));
+                }
+                num_rows += 1;
+            }
+            if num_rows == 0 {
+                break;
+            }
+            let mut buffer = ColumnarAnyBuffer::new(
+                buffer_descs
+                    .iter()
+                    .map(|&(column_number, _)| column_number)
+                    .zip(columns)
+                    .collect(),
+            );
+            *buffer.mut_num_fetch_rows() = num_rows;
+            let at_limit = self.remaining_rows() == Some(num_rows as u64);
+            self.write_fetched(&buffer, parameters, at_limit)?;
+            if num_rows < capacity {
+                break;
+            }
+        }
+        Ok(())
+    }
 
-        let mut pb = ParquetBuffer::new(row_set_cursor.row_array_size());
+    /// Number of rows of a batch with `num_rows` rows which may be written, if it reaches beyond
+    /// `--limit`.
+    fn rows_within_limit(&self, num_rows: usize) -> Option<usize> {
+        self.remaining_rows()
+            .filter(|&remaining| remaining < num_rows as u64)
+            .map(|remaining| remaining as usize)
+    }
 
-        while let Some(buffer) = row_set_cursor
-            .fetch()
-            .map_err(give_hint_about_flag_for_oracle_users)?
-        {
-            num_batch += 1;
-            let num_rows = buffer.num_rows();
-            total_rows_fetched += num_rows;
+    /// Write a batch fetched from the data source as row group. `at_limit` is `true` if the
+    /// batch has been cut to the rows remaining before `--limit`.
+    fn write_fetched(
+        &mut self,
+        buffer: &ColumnarAnyBuffer,
+        parameters: &[String],
+        at_limit: bool,
+    ) -> Result<(), Error> {
+        let full_batch_trigger = if self.options.max_rows == Some(self.batch_capacity) {
+            FlushTrigger::RowCount
+        } else {
+            FlushTrigger::ByteLimit
+        };
+        self.num_batch += 1;
+        let num_batch = self.num_batch;
+        let num_rows = buffer.num_rows();
+        self.total_rows_fetched += num_rows as u64;
+        if at_limit {
+            info!("Fetched batch {num_batch} with {num_rows} rows, reaching the limit.");
+        } else {
             info!("Fetched batch {num_batch} with {num_rows} rows.");
-            info!("Fetched {total_rows_fetched} rows in total.");
-            self.write_batch(&mut writer, num_batch, buffer, &mut pb)?;
+            info!("Fetched {} rows in total.", self.total_rows_fetched);
         }
-        writer.close_box()?;
+        let written = self.write_batch(buffer, parameters)?;
+        self.after_row_group(num_rows, &written, full_batch_trigger);
         Ok(())
     }
 
-    fn write_batch(
+    /// Throttle and log the row group, which has just been written.
+    fn after_row_group(
         &self,
-        writer: &mut Box<dyn ParquetOutput>,
-        num_batch: u32,
+        num_rows: usize,
+        written: &WrittenRowGroup,
+        full_batch_trigger: FlushTrigger,
+    ) {
+        if let Some(throttle) = &self.options.throttle {
+            throttle.wait(self.total_rows_fetched);
+        }
+        if self.options.debug_row_groups {
+            let trigger = if num_rows < self.batch_capacity {
+                FlushTrigger::EndOfData
+            } else if written.file_split {
+                FlushTrigger::FileSplit
+            } else {
+                full_batch_trigger
+            };
+            debug!(
+                "Row group {}: {} rows, {} bytes ({} bytes compressed), flushed due to {trigger}.",
+                self.num_batch,
+                written.metadata.num_rows(),
+                written.metadata.total_byte_size(),
+                written.metadata.compressed_size()
+            );
+        }
+    }
+
+    /// Number of rows which may still be written before reaching `--limit`. `None` if there is no
+    /// limit.
+    fn remaining_rows(&self) -> Option<u64> {
+        self.options
+            .limit
+            .map(|limit| limit.saturating_sub(self.total_rows_fetched))
+    }
+
+    /// Close the output and return the total number of rows written, together with the size of the
+    /// output.
+    pub fn finish(mut self) -> Result<(u64, OutputSize), Error> {
+        if let Some(padding) = self.options.padding.take() {
+            self.pad(&padding)?;
+        }
+        let num_rows_written = self.num_rows_written();
+        let output_size = self.writer.close_box()?;
+        let bad_value_outcome = if self.options.drop_bad_rows {
+            "their rows have been dropped"
+        } else {
+            "have been written as NULL"
+        };
+        for (column, num_bad) in self.table_strategy.columns.iter().zip(self.num_bad_values) {
+            if num_bad != 0 {
+                warn!(
+                    "{num_bad} values in column '{}' could not be converted and \
+                    {bad_value_outcome}.",
+                    column.name
+                );
+            }
+        }
+        if self.num_failed_transforms != 0 {
+            let outcome = if self.options.drop_bad_rows {
+                "have been dropped"
+            } else {
+                "have been written as NULL"
+            };
+            warn!(
+                "The transform script failed for {} rows, which {outcome}.",
+                self.num_failed_transforms
+            );
+        }
+        if self.num_dropped_rows != 0 {
+            warn!(
+                "{} rows have been dropped, since they hold values which could not be converted.",
+                self.num_dropped_rows
+            );
+        }
+        for (column, num_truncated) in self
+            .table_strategy
+            .columns
+            .iter()
+            .zip(self.num_truncated_values)
+        {
+            if num_truncated != 0 {
+                warn!(
+                    "{num_truncated} values in column '{}' exceeded the maximum length and have \
+                    been truncated.",
+                    column.name
+                );
+            }
+        }
+        for (column, num_violations) in self
+            .table_strategy
+            .columns
+            .iter()
+            .zip(self.num_precision_violations)
+        {
+            if num_violations != 0 {
+                warn!(
+                    "{num_violations} decimals in column '{}' have more digits than the precision \
+                    of the column.",
+                    column.name
+                );
+            }
+        }
+        for (column, profile) in self.table_strategy.columns.iter().zip(&self.profiles) {
+            if let Some(report) = profile
+                .as_ref()
+                .and_then(|profile| profile.histogram_report(&column.name))
+            {
+                eprint!("{report}");
+            }
+        }
+        if let Some(path) = &self.options.profile {
+            let columns = self
+                .table_strategy
+                .columns
+                .iter()
+                .zip(&self.profiles)
+                .filter_map(|(column, profile)| Some((column.name.as_str(), profile.as_ref()?)));
+            write_profile(path, num_rows_written, columns)?;
+        }
+        Ok((num_rows_written, output_size))
+    }
+
+    /// Number of rows fetched minus the ones dropped, since they hold values which could not be
+    /// converted. Filler rows are not included.
+    fn num_rows_written(&self) -> u64 {
+        self.total_rows_fetched - self.num_dropped_rows
+    }
+
+    /// Append filler rows, until the output holds the number of rows requested by `padding`.
+    fn pad(&mut self, padding: &Padding) -> Result<(), Error> {
+        let num_rows_written = self.num_rows_written();
+        if num_rows_written > padding.num_rows {
+            if padding.strict {
+                bail!(
+                    "The result set holds {num_rows_written} rows, which is more than the {} rows \
+                    passed to `--pad-to-rows`.",
+                    padding.num_rows
+                )
+            }
+            return Ok(());
+        }
+        let mut num_missing = padding.num_rows - num_rows_written;
+        info!("Appending {num_missing} filler rows.");
+        while num_missing != 0 {
+            let num_rows = num_missing.min(self.batch_capacity as u64) as usize;
+            self.num_batch += 1;
+            self.write_row_group(None, num_rows, &[])?;
+            num_missing -= num_rows as u64;
+        }
+        Ok(())
+    }
+
+    fn write_batch(
+        &mut self,
         buffer: &ColumnarAnyBuffer,
-        pb: &mut ParquetBuffer,
-    ) -> Result<(), Error> {
+        parameters: &[String],
+    ) -> Result<WrittenRowGroup, Error> {
+        let transformed;
+        let buffer = match self.options.transform_script.clone() {
+            Some(script) => {
+                transformed = self.transform(&script, buffer)?;
+                &transformed
+            }
+            None => buffer,
+        };
+        if self.options.drop_bad_rows {
+            let keep = self.find_rows_to_keep(buffer)?;
+            if keep.contains(&false) {
+                let retained = retain_rows(buffer, &keep);
+                return self.write_row_group(Some(&retained), retained.num_rows(), parameters);
+            }
+        }
+        self.write_row_group(Some(buffer), buffer.num_rows(), parameters)
+    }
+
+    /// Applies `script` to each row of `buffer`. Rows the script fails for fail the export, or are
+    /// handled like rows holding values which can not be converted, with `--skip-bad-rows`.
+    fn transform(
+        &mut self,
+        script: &TransformScript,
+        buffer: &ColumnarAnyBuffer,
+    ) -> Result<ColumnarAnyBuffer, Error> {
+        let names = self.table_strategy.buffer_names();
+        let num_batch = self.num_batch;
+        let skip_bad_rows = self.options.skip_bad_rows;
+        let bad_rows_file = self.options.bad_rows_file.as_deref();
+        let num_failed = &mut self.num_failed_transforms;
+        let transformed = script.apply(
+            buffer,
+            &names,
+            self.options.drop_bad_rows,
+            |row_index, error| {
+                if !skip_bad_rows {
+                    return Err(error.context(format!(
+                        "Transform script failed for row {row_index} of batch {num_batch}."
+                    )));
+                }
+                warn!("Transform script failed for row {row_index} of batch {num_batch}: {error}");
+                if let Some(bad_rows_file) = bad_rows_file {
+                    bad_rows_file.append(num_batch, row_index, script.name(), None, &error)?;
+                }
+                *num_failed += 1;
+                Ok(())
+            },
+        )?;
+        if self.options.drop_bad_rows {
+            self.num_dropped_rows += (buffer.num_rows() - transformed.num_rows()) as u64;
+        }
+        Ok(transformed)
+    }
+
+    /// Converts the values in `buffer` into column writers, whose output is discarded, to find the
+    /// rows holding values which can not be converted. Each such value is logged, counted and
+    /// recorded in the bad rows file. Returns `false` for each row to drop. Other errors are
+    /// ignored here, they are reported once the batch is written.
+    fn find_rows_to_keep(&mut self, buffer: &ColumnarAnyBuffer) -> Result<Vec<bool>, Error> {
         let num_rows = buffer.num_rows();
+        let schema = SchemaDescriptor::new(self.table_strategy.parquet_schema());
+        let properties = Arc::new(
+            WriterProperties::builder()
+                .set_dictionary_enabled(false)
+                .set_statistics_enabled(EnabledStatistics::None)
+                .build(),
+        );
+        let mut pb = ParquetBuffer::new(num_rows);
         pb.set_num_rows_fetched(num_rows);
+        pb.find_bad_values();
+        let columns = &self.table_strategy.columns;
+        let derived_columns = &self.table_strategy.derived_columns;
+        let mut keep = vec![true; num_rows];
+        for col_index in 0..columns.len() + derived_columns.len() {
+            let mut sink = TrackedWrite::new(io::sink());
+            let page_writer = Box::new(SerializedPageWriter::new(&mut sink));
+            let mut column_writer =
+                get_column_writer(schema.column(col_index), properties.clone(), page_writer);
+            let (name, buffer_index) = match columns.get(col_index) {
+                Some(column) => {
+                    let odbc_column = buffer.column(column.buffer_index);
+                    let _ = match column.key_buffer_index {
+                        Some(key_buffer_index) => column.strategy.copy_odbc_to_parquet_with_key(
+                            &mut pb,
+                            &mut column_writer,
+                            odbc_column,
+                            buffer.column(key_buffer_index),
+                        ),
+                        None => column.strategy.copy_odbc_to_parquet(
+                            &mut pb,
+                            &mut column_writer,
+                            odbc_column,
+                        ),
+                    };
+                    (&column.name, Some(column.buffer_index))
+                }
+                None => {
+                    let derived = &derived_columns[col_index - columns.len()];
+                    let _ = derived.write(&mut pb, &mut column_writer, Some(buffer), num_rows);
+                    (&derived.name, None)
+                }
+            };
+            for (row_index, error) in pb.bad_values.as_mut().unwrap().drain(..) {
+                warn!(
+                    "Dropping row {row_index} of batch {}, since the value in column '{name}' \
+                    could not be converted: {error}",
+                    self.num_batch
+                );
+                if let Some(bad_rows_file) = self.options.bad_rows_file.as_deref() {
+                    let raw = buffer_index
+                        .and_then(|buffer_index| raw_value(buffer.column(buffer_index), row_index));
+                    bad_rows_file.append(self.num_batch, row_index, name, raw, &error)?;
+                }
+                if let Some(num_bad) = self.num_bad_values.get_mut(col_index) {
+                    *num_bad += 1;
+                }
+                keep[row_index] = false;
+            }
+            pb.truncated_values.clear();
+            pb.precision_violations.clear();
+        }
+        self.num_dropped_rows += keep.iter().filter(|&&keep| !keep).count() as u64;
+        Ok(keep)
+    }
+
+    /// Write a row group with the rows fetched into `buffer`, or filler rows if `buffer` is `None`.
+    fn write_row_group(
+        &mut self,
+        buffer: Option<&ColumnarAnyBuffer>,
+        num_rows: usize,
+        parameters: &[String],
+    ) -> Result<WrittenRowGroup, Error> {
+        self.pb.set_num_rows_fetched(num_rows);
 
-        let column_exporter = ColumnExporter {
+        let mut column_exporter = ColumnExporter {
             buffer,
-            conversion_buffer: pb,
-            columns: &self.columns,
+            num_rows,
+            conversion_buffer: &mut self.pb,
+            columns: &self.table_strategy.columns,
+            derived_columns: &self.table_strategy.derived_columns,
+            parameters,
+            num_parameter_columns: self.table_strategy.parameter_columns.len(),
+            pad_values: &self.pad_values,
+            num_batch: self.num_batch,
+            num_bad_values: &mut self.num_bad_values,
+            num_truncated_values: &mut self.num_truncated_values,
+            num_precision_violations: &mut self.num_precision_violations,
+            profiles: &mut self.profiles,
+            compression_threads: self.options.compression_threads,
+            bad_rows_file: self.options.bad_rows_file.as_deref(),
         };
 
-        writer.write_row_group(num_batch, column_exporter)?;
-        Ok(())
+        self.writer
+            .write_row_group(self.num_batch, &mut column_exporter)
+    }
+}
+
+/// Options controlling how fetched batches are written as row groups.
+#[derive(Clone)]
+pub struct RowGroupOptions {
+    /// Write values which can not be converted as NULL, instead of failing.
+    pub skip_bad_rows: bool,
+    /// Leave out rows holding values which can not be converted, rather than writing these values
+    /// as NULL. Only in combination with `skip_bad_rows`.
+    pub drop_bad_rows: bool,
+    /// Row limit of a batch, if any. Used to tell apart batches which are full due to the row
+    /// limit from ones which are full due to the memory limit.
+    pub max_rows: Option<usize>,
+    /// Log each written row group together with the reason it has been flushed.
+    pub debug_row_groups: bool,
+    /// Limits the rate at which rows are fetched, if any.
+    pub throttle: Option<Throttle>,
+    /// Write a profile of each column as JSON into this file, once the export is finished.
+    pub profile: Option<PathBuf>,
+    /// Columns for which the number of rows per distinct value is reported at the end.
+    pub group_count: Vec<String>,
+    /// Maximum number of distinct values tracked for each column in `group_count`.
+    pub group_count_max_values: usize,
+    /// Number of distinct example values added to the profile of each column, if any.
+    pub sample_values: Option<usize>,
+    /// Columns without example values in their profile.
+    pub no_sample_columns: Vec<String>,
+    /// Filler rows appended at the end, if any.
+    pub padding: Option<Padding>,
+    /// Maximum number of rows written into the output, if any.
+    pub limit: Option<u64>,
+    /// Number of threads converting and compressing the columns of a row group.
+    pub compression_threads: usize,
+    /// Records each value skipped by `skip_bad_rows`, if any.
+    pub bad_rows_file: Option<Arc<BadRowsFile>>,
+    /// Applied to each row before it is written, if any.
+    pub transform_script: Option<Arc<TransformScript>>,
+}
+
+/// Reason for a row group to be written to the output.
+#[derive(Clone, Copy)]
+enum FlushTrigger {
+    /// The batch has been filled up to its maximum number of rows.
+    RowCount,
+    /// The batch has been filled up to the number of rows fitting into its memory limit.
+    ByteLimit,
+    /// The file reached its size limit after this row group and has been closed.
+    FileSplit,
+    /// The result set has been consumed.
+    EndOfData,
+}
+
+impl fmt::Display for FlushTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            FlushTrigger::RowCount => "row count",
+            FlushTrigger::ByteLimit => "byte limit",
+            FlushTrigger::FileSplit => "file split",
+            FlushTrigger::EndOfData => "end of data",
+        };
+        f.write_str(text)
+    }
+}
+
+/// Strategies of all parquet columns filled from a single column of the result set. The first one is
+/// the strategy for the column itself, followed by its companions.
+fn column_strategies(
+    cd: &ColumnDescription,
+    name: &str,
+    mapping_options: MappingOptions,
+    cursor: &mut impl ResultSetMetadata,
+    index: i16,
+) -> Result<Vec<Companion>, Error> {
+    if let Some(target_schema) = mapping_options.target_schema {
+        let target = target_schema.target_type((index - 1) as usize, name)?;
+        let strategy = strategy_from_target_type(cd, target, mapping_options, cursor, index)?;
+        return Ok(vec![(target.name().to_owned(), strategy)]);
+    }
+
+    let strategy = strategy_from_column_description(cd, name, mapping_options, cursor, index)?;
+    let mut strategies = vec![(name.to_owned(), strategy)];
+    strategies.extend(companion_strategies(cd, name, mapping_options)?);
+    Ok(strategies)
+}
+
+/// Nests the columns of each group under it. Columns of a group are moved next to each other, to the
+/// position of the first of them in the result set, and ordered as listed in the group.
+fn group_columns(columns: &mut Vec<ColumnInfo>, groups: &[ColumnGroup]) -> Result<(), Error> {
+    for (index, group) in groups.iter().enumerate() {
+        if groups[..index].iter().any(|other| other.name == group.name) {
+            bail!("Group '{}' is specified more than once.", group.name)
+        }
+        for name in &group.columns {
+            let column = columns
+                .iter_mut()
+                .find(|c| &c.name == name)
+                .ok_or_else(|| {
+                    anyhow!("Column '{name}' of `--group-columns` is not part of the result set.")
+                })?;
+            if let Some(other) = &column.group {
+                bail!(
+                    "Column '{name}' is listed in both groups '{other}' and '{}'.",
+                    group.name
+                )
+            }
+            column.group = Some(group.name.clone());
+        }
+    }
+    for group in groups {
+        if columns
+            .iter()
+            .any(|c| c.group.is_none() && c.name == group.name)
+        {
+            bail!(
+                "Group '{}' collides with a column of the result set.",
+                group.name
+            )
+        }
+    }
+
+    let mut remaining: Vec<Option<ColumnInfo>> = mem::take(columns).into_iter().map(Some).collect();
+    for index in 0..remaining.len() {
+        let Some(column) = remaining[index].take() else {
+            // Already moved along with the first column of its group.
+            continue;
+        };
+        let Some(group) = groups
+            .iter()
+            .find(|group| column.group.as_ref() == Some(&group.name))
+        else {
+            columns.push(column);
+            continue;
+        };
+        remaining[index] = Some(column);
+        for name in &group.columns {
+            let member = remaining
+                .iter_mut()
+                .find(|c| c.as_ref().is_some_and(|c| &c.name == name))
+                .and_then(Option::take)
+                .expect("Members of a group must be part of the result set");
+            columns.push(member);
+        }
     }
+    Ok(())
+}
+
+/// Creates the directories of the columns to extract and locates them, together with the key
+/// column, in the fetch buffer.
+fn blob_files(
+    columns: &[ColumnInfo],
+    blob_extraction: &BlobExtraction,
+) -> Result<BlobFiles, Error> {
+    let find = |name: &str| {
+        columns
+            .iter()
+            .find(|c| c.name == name)
+            .map(|c| c.buffer_index)
+            .with_context(|| format!("Column '{name}' is not part of the result set."))
+    };
+    let key_column = &blob_extraction.key_column;
+    if blob_extraction.columns.contains(key_column) {
+        bail!("Column '{key_column}' can not be both extracted and the key column.")
+    }
+    let key_buffer_index = find(key_column)?;
+    let mut blob_columns = Vec::new();
+    for name in &blob_extraction.columns {
+        let buffer_index = find(name)?;
+        let files = BlobToFile::new(blob_extraction.dir.clone(), name.clone())?;
+        blob_columns.push((buffer_index, files));
+    }
+    Ok(BlobFiles {
+        key_buffer_index,
+        columns: blob_columns,
+    })
+}
+
+/// Sorts the columns so that columns of the same physical type and nullability are adjacent.
+/// `REQUIRED` columns come first, followed by `OPTIONAL` ones, each ordered by physical type.
+/// Otherwise the order of the result set is kept. Returns the original position of each column.
+fn reorder_for_size(columns: &mut Vec<ColumnInfo>) -> Vec<usize> {
+    let mut indexed: Vec<(usize, ColumnInfo)> =
+        mem::take(columns).into_iter().enumerate().collect();
+    indexed.sort_by_cached_key(|(_, column)| {
+        let parquet_type = column.strategy.parquet_type(&column.name);
+        let is_optional = parquet_type.get_basic_info().repetition() == Repetition::OPTIONAL;
+        let physical = match parquet_type.get_physical_type() {
+            PhysicalType::BOOLEAN => 0,
+            PhysicalType::INT32 => 1,
+            PhysicalType::INT64 => 2,
+            PhysicalType::INT96 => 3,
+            PhysicalType::FLOAT => 4,
+            PhysicalType::DOUBLE => 5,
+            PhysicalType::FIXED_LEN_BYTE_ARRAY => 6,
+            PhysicalType::BYTE_ARRAY => 7,
+        };
+        (is_optional, physical)
+    });
+    let (positions, reordered) = indexed.into_iter().unzip();
+    *columns = reordered;
+    positions
+}
+
+/// Replaces the strategy of the timestamp column named in `ts_tz_from`, so its values are converted
+/// to UTC using the time zone named in the same row of the time zone column.
+fn timestamp_in_zone(
+    columns: &mut [ColumnInfo],
+    buffer_descs: &[(u16, BufferDesc)],
+    column_descriptions: &[ColumnDescription],
+    ts_tz_from: &TimestampTzFrom,
+    unknown: UnknownTimeZone,
+) -> Result<(), Error> {
+    let find = |columns: &[ColumnInfo], name: &str| {
+        columns
+            .iter()
+            .position(|c| c.name == name)
+            .with_context(|| format!("Column '{name}' is not part of the result set."))
+    };
+    let tz_index = find(columns, &ts_tz_from.tz_column)?;
+    let tz_buffer_index = columns[tz_index].buffer_index;
+    if !matches!(
+        buffer_descs[tz_buffer_index].1,
+        BufferDesc::Text { .. } | BufferDesc::WText { .. }
+    ) {
+        bail!(
+            "Column '{}' can not hold time zone names, since it is not of text type.",
+            ts_tz_from.tz_column
+        )
+    }
+    let name = &ts_tz_from.timestamp_column;
+    let column = &mut columns[find(columns, name)?];
+    let (column_number, BufferDesc::Timestamp { .. }) = buffer_descs[column.buffer_index] else {
+        bail!("Column '{name}' can not be converted to UTC, since it is not a timestamp.")
+    };
+    let precision = match column_descriptions[column_number as usize - 1].data_type {
+        DataType::Timestamp { precision } => precision.try_into().unwrap(),
+        _ => 0,
+    };
+    let repetition = column
+        .strategy
+        .parquet_type(name)
+        .get_basic_info()
+        .repetition();
+    column.strategy = Box::new(TimestampInZone::new(
+        repetition,
+        precision,
+        ts_tz_from.tz_column.clone(),
+        unknown,
+    ));
+    column.key_buffer_index = Some(tz_buffer_index);
+    Ok(())
 }
 
 /// Exposes the contents from a fetch buffer column by column to a parquet serializer
 pub struct ColumnExporter<'a> {
-    buffer: &'a ColumnarAnyBuffer,
+    /// Rows fetched from the data source. `None` while filler rows are written.
+    buffer: Option<&'a ColumnarAnyBuffer>,
+    num_rows: usize,
     conversion_buffer: &'a mut ParquetBuffer,
-    columns: &'a [(String, Box<dyn ColumnStrategy>)],
+    columns: &'a [ColumnInfo],
+    /// Computed from the fetched rows and written after the columns of the result set.
+    derived_columns: &'a [DerivedColumn],
+    /// Values of the parameter set the batch has been fetched with. Written into the parameter
+    /// columns following the columns of the result set. Empty for filler rows, whose parameter
+    /// columns are NULL.
+    parameters: &'a [String],
+    num_parameter_columns: usize,
+    /// Value of each column in filler rows.
+    pad_values: &'a [Option<FillValue>],
+    /// One based index of the batch, used in log messages.
+    num_batch: u32,
+    /// Number of values per column replaced with NULL, because they could not be converted.
+    num_bad_values: &'a mut [u64],
+    /// Number of values per column which have been truncated.
+    num_truncated_values: &'a mut [u64],
+    /// Number of decimals per column with more digits than their precision.
+    num_precision_violations: &'a mut [u64],
+    /// Profile of each column, if requested. Lend to the conversion buffer while the column is
+    /// written.
+    profiles: &'a mut [Option<ColumnProfile>],
+    /// Number of threads the columns are distributed across. If `1` columns are written directly
+    /// into the output, one after another.
+    compression_threads: usize,
+    /// Records each value written as NULL, because it could not be converted.
+    bad_rows_file: Option<&'a BadRowsFile>,
 }
 
 impl<'a> ColumnExporter<'a> {
-    pub fn export_nth_column(
+    /// Write all columns as the next row group of `writer`.
+    pub fn write_row_group<W: Write + Send>(
+        &mut self,
+        writer: &mut SerializedFileWriter<W>,
+    ) -> Result<RowGroupMetaDataPtr, Error> {
+        if self.compression_threads > 1 {
+            let chunks = self.export_in_parallel(writer.schema_descr(), writer.properties())?;
+            let mut row_group_writer = writer.next_row_group()?;
+            for (chunk, close_result) in chunks {
+                row_group_writer.append_column(&chunk, close_result)?;
+            }
+            return Ok(row_group_writer.close()?);
+        }
+        let mut row_group_writer = writer.next_row_group()?;
+        let mut col_index = 0;
+        while let Some(mut column_writer) = row_group_writer.next_column()? {
+            self.export_nth_column(col_index, &mut column_writer)?;
+            column_writer.close()?;
+            col_index += 1;
+        }
+        Ok(row_group_writer.close()?)
+    }
+
+    /// Encode and compress the columns into in-memory column chunks, using up to
+    /// `compression_threads` threads. Thread `i` takes every `i`-th column, using a conversion
+    /// buffer of its own. The chunks are returned in column order, so the row group does not depend
+    /// on the number of threads. Only the chunks of one row group are held in memory at a time.
+    fn export_in_parallel(
+        &mut self,
+        schema: &SchemaDescriptor,
+        properties: &WriterPropertiesPtr,
+    ) -> Result<Vec<(Bytes, ColumnCloseResult)>, Error> {
+        let num_columns = schema.num_columns();
+        let num_threads = self.compression_threads.min(num_columns).max(1);
+        let skip_bad_values = self.conversion_buffer.bad_values.is_some();
+        // Profiles of the columns are moved to the thread writing them, and back afterwards.
+        let mut profiles: Vec<Vec<Option<ColumnProfile>>> = (0..num_threads)
+            .map(|thread_index| {
+                self.profiles
+                    .iter_mut()
+                    .enumerate()
+                    .map(|(col_index, profile)| {
+                        if col_index % num_threads == thread_index {
+                            profile.take()
+                        } else {
+                            None
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let (buffer, num_rows, columns) = (self.buffer, self.num_rows, self.columns);
+        let derived_columns = self.derived_columns;
+        let (parameters, num_parameter_columns) = (self.parameters, self.num_parameter_columns);
+        let (pad_values, num_batch) = (self.pad_values, self.num_batch);
+        let bad_rows_file = self.bad_rows_file;
+        let results = thread::scope(|scope| {
+            let workers: Vec<_> = profiles
+                .iter_mut()
+                .enumerate()
+                .map(|(thread_index, profiles)| {
+                    scope.spawn(move || {
+                        let mut conversion_buffer = ParquetBuffer::new(num_rows);
+                        conversion_buffer.set_num_rows_fetched(num_rows);
+                        if skip_bad_values {
+                            conversion_buffer.skip_bad_values();
+                        }
+                        let mut num_bad_values = vec![0; columns.len()];
+                        let mut num_truncated_values = vec![0; columns.len()];
+                        let mut num_precision_violations = vec![0; columns.len()];
+                        let mut exporter = ColumnExporter {
+                            buffer,
+                            num_rows,
+                            conversion_buffer: &mut conversion_buffer,
+                            columns,
+                            derived_columns,
+                            parameters,
+                            num_parameter_columns,
+                            pad_values,
+                            num_batch,
+                            num_bad_values: &mut num_bad_values,
+                            num_truncated_values: &mut num_truncated_values,
+                            num_precision_violations: &mut num_precision_violations,
+                            profiles,
+                            compression_threads: 1,
+                            bad_rows_file,
+                        };
+                        let chunks = (thread_index..num_columns)
+                            .step_by(num_threads)
+                            .map(|col_index| {
+                                exporter.export_nth_chunk(
+                                    col_index,
+                                    schema.column(col_index),
+                                    properties,
+                                )
+                            })
+                            .collect::<Result<Vec<_>, Error>>()?;
+                        Ok::<_, Error>((
+                            chunks,
+                            num_bad_values,
+                            num_truncated_values,
+                            num_precision_violations,
+                        ))
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| {
+                    worker
+                        .join()
+                        .expect("Thread writing columns must not panic.")
+                })
+                .collect::<Result<Vec<_>, Error>>()
+        })?;
+
+        let mut chunks: Vec<Option<(Bytes, ColumnCloseResult)>> =
+            (0..num_columns).map(|_| None).collect();
+        for (
+            thread_index,
+            (thread_chunks, num_bad_values, num_truncated_values, num_precision_violations),
+        ) in results.into_iter().enumerate()
+        {
+            let col_indices = (thread_index..num_columns).step_by(num_threads);
+            for (col_index, chunk) in col_indices.zip(thread_chunks) {
+                chunks[col_index] = Some(chunk);
+            }
+            for (col_index, count) in num_bad_values.into_iter().enumerate() {
+                self.num_bad_values[col_index] += count;
+            }
+            for (col_index, count) in num_truncated_values.into_iter().enumerate() {
+                self.num_truncated_values[col_index] += count;
+            }
+            for (col_index, count) in num_precision_violations.into_iter().enumerate() {
+                self.num_precision_violations[col_index] += count;
+            }
+        }
+        for thread_profiles in profiles {
+            for (col_index, profile) in thread_profiles.into_iter().enumerate() {
+                if profile.is_some() {
+                    self.profiles[col_index] = profile;
+                }
+            }
+        }
+        Ok(chunks.into_iter().map(Option::unwrap).collect())
+    }
+
+    /// Write the column with index `col_index` into a column chunk in memory, which can be
+    /// appended to a row group.
+    fn export_nth_chunk(
+        &mut self,
+        col_index: usize,
+        descr: ColumnDescPtr,
+        properties: &WriterPropertiesPtr,
+    ) -> Result<(Bytes, ColumnCloseResult), Error> {
+        let mut sink = TrackedWrite::new(Vec::new());
+        let mut close_result = None;
+        let page_writer = Box::new(SerializedPageWriter::new(&mut sink));
+        let column_writer = get_column_writer(descr, properties.clone(), page_writer);
+        let mut column_writer = SerializedColumnWriter::new(
+            column_writer,
+            Some(Box::new(|result| {
+                close_result = Some(result);
+                Ok(())
+            })),
+        );
+        self.export_nth_column(col_index, &mut column_writer)?;
+        column_writer.close()?;
+        let close_result = close_result.expect("Column writer reports its result on close.");
+        Ok((Bytes::from(sink.into_inner()?), close_result))
+    }
+
+    fn export_nth_column(
         &mut self,
         col_index: usize,
         column_writer: &mut SerializedColumnWriter,
     ) -> Result<(), Error> {
-        let col_name = &self.columns[col_index].0;
+        let num_rows = self.num_rows;
+        let Some(column) = self.columns.get(col_index) else {
+            let index = col_index - self.columns.len();
+            if let Some(derived) = self.derived_columns.get(index) {
+                return self.export_derived_column(derived, column_writer);
+            }
+            let index = index - self.derived_columns.len();
+            if index == self.num_parameter_columns {
+                let is_filler = self.buffer.is_none();
+                let column_writer =
+                    get_typed_column_writer_mut::<BoolType>(column_writer.untyped());
+                return self
+                    .conversion_buffer
+                    .write_optional(column_writer, (0..num_rows).map(|_| Some(is_filler)));
+            }
+            let value = self.parameters.get(index);
+            let column_writer =
+                get_typed_column_writer_mut::<ByteArrayType>(column_writer.untyped());
+            return self.conversion_buffer.write_optional(
+                column_writer,
+                (0..num_rows).map(|_| value.map(|value| ByteArray::from(value.as_str()))),
+            );
+        };
+        let col_name = &column.name;
         debug!("Writing column with index {col_index} and name '{col_name}'.");
-        let odbc_column = self.buffer.column(col_index);
-        self.columns[col_index]
-            .1
-            .copy_odbc_to_parquet(self.conversion_buffer, column_writer.untyped(), odbc_column)
-            .with_context(|| {
-                format!("Failed to copy column '{col_name}' from ODBC representation into Parquet.")
-            })?;
+        let Some(buffer) = self.buffer else {
+            return write_filler(
+                self.conversion_buffer,
+                column_writer.untyped(),
+                self.pad_values[col_index].as_ref(),
+                num_rows,
+            );
+        };
+        let odbc_column = buffer.column(column.buffer_index);
+        mem::swap(
+            &mut self.conversion_buffer.profile,
+            &mut self.profiles[col_index],
+        );
+        let result = if let Some(key_buffer_index) = column.key_buffer_index {
+            column.strategy.copy_odbc_to_parquet_with_key(
+                self.conversion_buffer,
+                column_writer.untyped(),
+                odbc_column,
+                buffer.column(key_buffer_index),
+            )
+        } else {
+            column.strategy.copy_odbc_to_parquet(
+                self.conversion_buffer,
+                column_writer.untyped(),
+                odbc_column,
+            )
+        };
+        mem::swap(
+            &mut self.conversion_buffer.profile,
+            &mut self.profiles[col_index],
+        );
+        result.map_err(|error| match error.downcast_ref::<UnexpectedNull>() {
+            Some(null) => anyhow!(
+                "Column '{col_name}' is NULL in batch {}, row {}. This is the first NULL in a \
+                column watched with `--fail-fast-on-null` or declared `REQUIRED` by \
+                `--force-required-all`.",
+                self.num_batch,
+                null.row_index
+            ),
+            None => error.context(format!(
+                "Failed to copy column '{col_name}' from ODBC representation into Parquet."
+            )),
+        })?;
+        if let Some(bad_values) = self.conversion_buffer.bad_values.as_mut() {
+            for (row_index, error) in bad_values.drain(..) {
+                warn!(
+                    "Writing NULL for value in column '{col_name}', batch {}, row {row_index}: \
+                    {error}",
+                    self.num_batch
+                );
+                if let Some(bad_rows_file) = self.bad_rows_file {
+                    let raw = raw_value(buffer.column(column.buffer_index), row_index);
+                    bad_rows_file.append(self.num_batch, row_index, col_name, raw, &error)?;
+                }
+                self.num_bad_values[col_index] += 1;
+            }
+        }
+        for row_index in self.conversion_buffer.truncated_values.drain(..) {
+            debug!(
+                "Truncated value in column '{col_name}', batch {}, row {row_index}.",
+                self.num_batch
+            );
+            self.num_truncated_values[col_index] += 1;
+        }
+        for row_index in self.conversion_buffer.precision_violations.drain(..) {
+            debug!(
+                "Decimal in column '{col_name}', batch {}, row {row_index} exceeds the precision \
+                of the column.",
+                self.num_batch
+            );
+            self.num_precision_violations[col_index] += 1;
+        }
         Ok::<(), Error>(())
     }
+
+    fn export_derived_column(
+        &mut self,
+        derived: &DerivedColumn,
+        column_writer: &mut SerializedColumnWriter,
+    ) -> Result<(), Error> {
+        let name = &derived.name;
+        derived
+            .write(
+                self.conversion_buffer,
+                column_writer.untyped(),
+                self.buffer,
+                self.num_rows,
+            )
+            .with_context(|| format!("Failed to compute derived column '{name}'."))?;
+        if let Some(bad_values) = self.conversion_buffer.bad_values.as_mut() {
+            for (row_index, error) in bad_values.drain(..) {
+                warn!(
+                    "Writing NULL for value in derived column '{name}', batch {}, row \
+                    {row_index}: {error}",
+                    self.num_batch
+                );
+                if let Some(bad_rows_file) = self.bad_rows_file {
+                    bad_rows_file.append(self.num_batch, row_index, name, None, &error)?;
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 /// If we hit the issue with oracle not supporting 64Bit, let's tell our users that we have
@@ -202,3 +1922,191 @@ fn give_hint_about_flag_for_oracle_users(error: odbc_api::Error) -> Error {
         other => other.into(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Instant};
+
+    use odbc_api::{
+        buffers::{AnyBuffer, ColumnarAnyBuffer},
+        RowSetBuffer,
+    };
+    use parquet::{
+        basic::{Compression, ZstdLevel},
+        data_type::{Int32Type, Int64Type},
+        file::properties::WriterProperties,
+        file::writer::SerializedFileWriter,
+        schema::{parser::parse_message_type, types::Type},
+    };
+
+    use crate::{
+        enum_args::ColumnGroup,
+        parquet_buffer::{FillValue, ParquetBuffer},
+        query::identical::fetch_identical,
+    };
+
+    use super::{group_columns, ColumnExporter, ColumnInfo};
+
+    /// Writes two row groups of filler rows into an in-memory file.
+    fn write_filler_rows(compression_threads: usize) -> Vec<u8> {
+        let schema = parse_message_type(
+            "message schema {
+                OPTIONAL BYTE_ARRAY a (UTF8);
+                OPTIONAL BYTE_ARRAY b (UTF8);
+                REQUIRED BOOLEAN is_filler;
+            }",
+        )
+        .unwrap();
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let mut writer =
+            SerializedFileWriter::new(Vec::new(), Arc::new(schema), Arc::new(properties)).unwrap();
+        let num_rows = 100;
+        let mut conversion_buffer = ParquetBuffer::new(num_rows);
+        conversion_buffer.set_num_rows_fetched(num_rows);
+        let pad_values: [Option<FillValue>; 0] = [];
+        for (num_batch, parameters) in [["x", "y"], ["z", "w"]].iter().enumerate() {
+            let parameters = parameters.map(str::to_owned);
+            let mut exporter = ColumnExporter {
+                buffer: None,
+                num_rows,
+                conversion_buffer: &mut conversion_buffer,
+                columns: &[],
+                derived_columns: &[],
+                parameters: &parameters,
+                num_parameter_columns: 2,
+                pad_values: &pad_values,
+                num_batch: num_batch as u32 + 1,
+                num_bad_values: &mut [],
+                num_truncated_values: &mut [],
+                num_precision_violations: &mut [],
+                profiles: &mut [],
+                compression_threads,
+                bad_rows_file: None,
+            };
+            exporter.write_row_group(&mut writer).unwrap();
+        }
+        writer.into_inner().unwrap()
+    }
+
+    #[test]
+    fn move_grouped_columns_to_position_of_first_member() {
+        let mut columns: Vec<ColumnInfo> = ["a", "b", "c", "d"]
+            .iter()
+            .enumerate()
+            .map(|(buffer_index, name)| ColumnInfo {
+                name: name.to_string(),
+                strategy: fetch_identical::<Int32Type>(true),
+                buffer_index,
+                key_buffer_index: None,
+                group: None,
+            })
+            .collect();
+        let group = |name: &str, columns: &[&str]| ColumnGroup {
+            name: name.to_owned(),
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+        };
+
+        group_columns(&mut columns, &[group("g", &["d", "b"])]).unwrap();
+
+        let layout: Vec<_> = columns
+            .iter()
+            .map(|c| (c.name.as_str(), c.group.as_deref()))
+            .collect();
+        assert_eq!(
+            vec![("a", None), ("d", Some("g")), ("b", Some("g")), ("c", None)],
+            layout
+        );
+        assert!(group_columns(&mut columns, &[group("h", &["x"])]).is_err());
+        assert!(group_columns(&mut columns, &[group("a", &["c"])]).is_err());
+    }
+
+    #[test]
+    fn output_does_not_depend_on_number_of_compression_threads() {
+        let sequential = write_filler_rows(1);
+        assert_eq!(sequential, write_filler_rows(2));
+        assert_eq!(sequential, write_filler_rows(8));
+    }
+
+    /// Measures the time it takes to write a row group of a wide table with `zstd` compression,
+    /// depending on the number of `--compression-threads`. Not run by default, use:
+    /// `cargo test --release compression_threads_benchmark -- --ignored --nocapture`. Fetching is
+    /// not part of it, so it does not show the time saved by fetching while writing.
+    #[test]
+    #[ignore]
+    fn compression_threads_benchmark() {
+        const NUM_ROWS: usize = 100_000;
+        const NUM_COLUMNS: usize = 32;
+        const NUM_BATCHES: u32 = 5;
+        let columns: Vec<ColumnInfo> = (0..NUM_COLUMNS)
+            .map(|buffer_index| ColumnInfo {
+                name: format!("c{buffer_index}"),
+                strategy: fetch_identical::<Int64Type>(false),
+                buffer_index,
+                key_buffer_index: None,
+                group: None,
+            })
+            .collect();
+        let mut buffer = ColumnarAnyBuffer::new(
+            (0..NUM_COLUMNS)
+                .map(|col_index| {
+                    // Some, but not too much redundancy, so compression has to work for it.
+                    let values = (0..NUM_ROWS)
+                        .map(|row_index| ((row_index * 7919 + col_index) % 1_000_003) as i64)
+                        .collect();
+                    (col_index as u16 + 1, AnyBuffer::I64(values))
+                })
+                .collect(),
+        );
+        *buffer.mut_num_fetch_rows() = NUM_ROWS;
+        let schema = Type::group_type_builder("schema")
+            .with_fields(
+                columns
+                    .iter()
+                    .map(|column| Arc::new(column.strategy.parquet_type(&column.name)))
+                    .collect(),
+            )
+            .build()
+            .unwrap();
+        let schema = Arc::new(schema);
+        let properties = Arc::new(
+            WriterProperties::builder()
+                .set_compression(Compression::ZSTD(ZstdLevel::try_new(9).unwrap()))
+                .build(),
+        );
+
+        for compression_threads in [1, 2, 4, 8] {
+            let mut writer =
+                SerializedFileWriter::new(std::io::sink(), schema.clone(), properties.clone())
+                    .unwrap();
+            let mut conversion_buffer = ParquetBuffer::new(NUM_ROWS);
+            conversion_buffer.set_num_rows_fetched(NUM_ROWS);
+            let start = Instant::now();
+            for num_batch in 1..=NUM_BATCHES {
+                let mut exporter = ColumnExporter {
+                    buffer: Some(&buffer),
+                    num_rows: NUM_ROWS,
+                    conversion_buffer: &mut conversion_buffer,
+                    columns: &columns,
+                    derived_columns: &[],
+                    parameters: &[],
+                    num_parameter_columns: 0,
+                    pad_values: &[],
+                    num_batch,
+                    num_bad_values: &mut [0; NUM_COLUMNS],
+                    num_truncated_values: &mut [0; NUM_COLUMNS],
+                    num_precision_violations: &mut [0; NUM_COLUMNS],
+                    profiles: &mut (0..NUM_COLUMNS).map(|_| None).collect::<Vec<_>>(),
+                    compression_threads,
+                    bad_rows_file: None,
+                };
+                exporter.write_row_group(&mut writer).unwrap();
+            }
+            println!(
+                "{compression_threads} threads: {:?} per row group",
+                start.elapsed() / NUM_BATCHES
+            );
+        }
+    }
+}
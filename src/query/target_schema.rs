@@ -0,0 +1,200 @@
+use std::{fs::File, path::Path};
+
+use anyhow::{anyhow, bail, Context, Error};
+use parquet::{
+    file::reader::{FileReader, SerializedFileReader},
+    schema::types::{Type, TypePtr},
+};
+
+use crate::enum_args::SchemaMatchBy;
+
+/// Schema of an existing parquet file, which is enforced on the output. Columns of the result set
+/// are matched to the columns of this schema either by name or by position.
+pub struct TargetSchema {
+    fields: Vec<TypePtr>,
+    match_by: SchemaMatchBy,
+}
+
+impl TargetSchema {
+    /// Read the schema from the metadata of a parquet file.
+    pub fn from_file(path: &Path, match_by: SchemaMatchBy) -> Result<Self, Error> {
+        let file =
+            File::open(path).with_context(|| format!("Could not open '{}'.", path.display()))?;
+        let reader = SerializedFileReader::new(file)
+            .with_context(|| format!("Could not read parquet metadata of '{}'.", path.display()))?;
+        let fields = reader
+            .metadata()
+            .file_metadata()
+            .schema_descr()
+            .root_schema()
+            .get_fields()
+            .to_vec();
+        Self::new(fields, match_by)
+    }
+
+    pub fn new(fields: Vec<TypePtr>, match_by: SchemaMatchBy) -> Result<Self, Error> {
+        if let Some(group) = fields.iter().find(|field| field.is_group()) {
+            bail!(
+                "Column '{}' of the target schema is nested. Only flat schemas are supported.",
+                group.name()
+            )
+        }
+        Ok(Self { fields, match_by })
+    }
+
+    /// Target type of a column in the result set.
+    ///
+    /// * `index`: Zero based index of the column in the result set.
+    /// * `name`: Name of the column in the result set.
+    pub fn target_type(&self, index: usize, name: &str) -> Result<&Type, Error> {
+        let field = match self.match_by {
+            SchemaMatchBy::Name => self
+                .fields
+                .iter()
+                .find(|field| field.name() == name)
+                .ok_or_else(|| {
+                    anyhow!("Column '{name}' of the result set is not part of the target schema.")
+                })?,
+            SchemaMatchBy::Position => self.fields.get(index).ok_or_else(|| {
+                anyhow!(
+                    "Result set has more columns than the target schema. Target schema has {} \
+                    columns.",
+                    self.fields.len()
+                )
+            })?,
+        };
+        Ok(field)
+    }
+
+    /// Called with the names of all columns of the output, after all of them have been matched.
+    /// Fails if a column of the target schema has not been matched exactly once.
+    pub fn check_all_matched(&self, column_names: &[&str]) -> Result<(), Error> {
+        for field in &self.fields {
+            match column_names
+                .iter()
+                .filter(|&&name| name == field.name())
+                .count()
+            {
+                0 => bail!(
+                    "Column '{}' of the target schema is not part of the result set.",
+                    field.name()
+                ),
+                1 => (),
+                _ => bail!(
+                    "Column '{}' of the target schema is matched by more than one column of the \
+                    result set.",
+                    field.name()
+                ),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `true` if both types would be written into a parquet file identically. In contrast to `==` this
+/// ignores field ids, names and logical types missing on one side.
+pub fn same_type(left: &Type, right: &Type) -> bool {
+    match (left, right) {
+        (
+            Type::PrimitiveType {
+                basic_info: left_info,
+                physical_type: left_physical,
+                type_length: left_length,
+                scale: left_scale,
+                precision: left_precision,
+            },
+            Type::PrimitiveType {
+                basic_info: right_info,
+                physical_type: right_physical,
+                type_length: right_length,
+                scale: right_scale,
+                precision: right_precision,
+            },
+        ) => {
+            let logical_type_compatible =
+                match (left_info.logical_type(), right_info.logical_type()) {
+                    (Some(left), Some(right)) => left == right,
+                    _ => true,
+                };
+            left_physical == right_physical
+                && left_length == right_length
+                && left_scale == right_scale
+                && left_precision == right_precision
+                && left_info.repetition() == right_info.repetition()
+                && left_info.converted_type() == right_info.converted_type()
+                && logical_type_compatible
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use parquet::{
+        basic::{ConvertedType, Repetition, Type as PhysicalType},
+        schema::types::Type,
+    };
+
+    use crate::enum_args::SchemaMatchBy;
+
+    use super::{same_type, TargetSchema};
+
+    fn column(name: &str, physical_type: PhysicalType) -> Arc<Type> {
+        Arc::new(
+            Type::primitive_type_builder(name, physical_type)
+                .with_repetition(Repetition::OPTIONAL)
+                .build()
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn match_by_name() {
+        let schema = TargetSchema::new(
+            vec![
+                column("a", PhysicalType::INT32),
+                column("b", PhysicalType::DOUBLE),
+            ],
+            SchemaMatchBy::Name,
+        )
+        .unwrap();
+        assert_eq!("b", schema.target_type(0, "b").unwrap().name());
+        assert!(schema.target_type(0, "c").is_err());
+        assert!(schema.check_all_matched(&["b", "a"]).is_ok());
+        assert!(schema.check_all_matched(&["b"]).is_err());
+    }
+
+    #[test]
+    fn match_by_position() {
+        let schema = TargetSchema::new(
+            vec![column("a", PhysicalType::INT32)],
+            SchemaMatchBy::Position,
+        )
+        .unwrap();
+        assert_eq!("a", schema.target_type(0, "x").unwrap().name());
+        assert!(schema.target_type(1, "y").is_err());
+    }
+
+    #[test]
+    fn compare_types_ignoring_field_id() {
+        let with_id = Type::primitive_type_builder("a", PhysicalType::BYTE_ARRAY)
+            .with_repetition(Repetition::OPTIONAL)
+            .with_converted_type(ConvertedType::UTF8)
+            .with_id(Some(1))
+            .build()
+            .unwrap();
+        let without_id = Type::primitive_type_builder("a", PhysicalType::BYTE_ARRAY)
+            .with_repetition(Repetition::OPTIONAL)
+            .with_converted_type(ConvertedType::UTF8)
+            .build()
+            .unwrap();
+        let binary = Type::primitive_type_builder("a", PhysicalType::BYTE_ARRAY)
+            .with_repetition(Repetition::OPTIONAL)
+            .build()
+            .unwrap();
+        assert!(same_type(&with_id, &without_id));
+        assert!(!same_type(&with_id, &binary));
+    }
+}
@@ -12,17 +12,96 @@ use parquet::{
 
 use crate::parquet_buffer::ParquetBuffer;
 
-use super::column_strategy::ColumnStrategy;
+use super::column_strategy::{unexpected_view, ColumnStrategy};
 
 pub fn text_strategy(
     use_utf16: bool,
     repetition: Repetition,
     length: usize,
 ) -> Box<dyn ColumnStrategy> {
+    char_strategy(
+        use_utf16,
+        repetition,
+        length,
+        false,
+        &NullTokens::default(),
+        false,
+    )
+}
+
+/// Like [`text_strategy`], but optionally writes values consisting only of whitespace, or matching
+/// one of `null_tokens` as NULL. Treating blanks as NULL is intended for fixed width character
+/// columns, there an empty value and padding are indistinguishable. Both are ignored for required
+/// columns. If `truncate` is set, one more character is fetched than `length`, so longer values
+/// can be told apart, truncated to `length` and reported.
+pub fn char_strategy(
+    use_utf16: bool,
+    repetition: Repetition,
+    length: usize,
+    blank_as_null: bool,
+    null_tokens: &NullTokens,
+    truncate: bool,
+) -> Box<dyn ColumnStrategy> {
+    let is_optional = repetition == Repetition::OPTIONAL;
+    let blank_as_null = blank_as_null && is_optional;
+    let null_tokens = if is_optional {
+        null_tokens.clone()
+    } else {
+        NullTokens::default()
+    };
     if use_utf16 {
-        Box::new(Utf16ToUtf8::new(repetition, length))
+        Box::new(Utf16ToUtf8 {
+            repetition,
+            length,
+            blank_as_null,
+            null_tokens,
+            truncate,
+        })
     } else {
-        Box::new(Utf8::with_bytes_length(repetition, length))
+        Box::new(Utf8 {
+            repetition,
+            length,
+            blank_as_null,
+            null_tokens,
+            truncate,
+        })
+    }
+}
+
+/// Text values which are written as NULL, e.g. `N/A` or `\N`. Tokens are compared with the
+/// complete value as fetched from the data source, before any other transformation of the text
+/// takes place. E.g. a token does not match a value with surrounding whitespace.
+#[derive(Clone, Default)]
+pub struct NullTokens {
+    /// Lowercase, if `ignore_case` is `true`.
+    tokens: Vec<String>,
+    ignore_case: bool,
+}
+
+impl NullTokens {
+    pub fn new(tokens: Vec<String>, ignore_case: bool) -> Self {
+        let tokens = if ignore_case {
+            tokens.iter().map(|token| token.to_lowercase()).collect()
+        } else {
+            tokens
+        };
+        Self {
+            tokens,
+            ignore_case,
+        }
+    }
+
+    /// `true` if `text` is one of the tokens.
+    fn matches(&self, text: &str) -> bool {
+        if self.tokens.is_empty() {
+            return false;
+        }
+        if self.ignore_case {
+            let text = text.to_lowercase();
+            self.tokens.contains(&text)
+        } else {
+            self.tokens.iter().any(|token| token == text)
+        }
     }
 }
 
@@ -30,12 +109,11 @@ struct Utf16ToUtf8 {
     repetition: Repetition,
     /// Length of the column elements in `u16` (as opposed to code points).
     length: usize,
-}
-
-impl Utf16ToUtf8 {
-    pub fn new(repetition: Repetition, length: usize) -> Self {
-        Self { repetition, length }
-    }
+    /// Write values consisting only of whitespace as NULL.
+    blank_as_null: bool,
+    null_tokens: NullTokens,
+    /// Truncate values longer than `length` and report them.
+    truncate: bool,
 }
 
 impl ColumnStrategy for Utf16ToUtf8 {
@@ -49,7 +127,7 @@ impl ColumnStrategy for Utf16ToUtf8 {
 
     fn buffer_desc(&self) -> BufferDesc {
         BufferDesc::WText {
-            max_str_len: self.length,
+            max_str_len: self.length + usize::from(self.truncate),
         }
     }
 
@@ -59,47 +137,93 @@ impl ColumnStrategy for Utf16ToUtf8 {
         column_writer: &mut ColumnWriter,
         column_view: AnySlice,
     ) -> Result<(), Error> {
-        write_utf16_to_utf8(parquet_buffer, column_writer, column_view)
+        write_utf16_to_utf8(
+            parquet_buffer,
+            column_writer,
+            column_view,
+            self.blank_as_null,
+            &self.null_tokens,
+            self.truncate.then_some(self.length),
+        )
     }
 }
 
+/// * `truncate_at`: Values longer than this many characters are truncated and their rows recorded
+///   in the `truncated_values` of `pb`.
 fn write_utf16_to_utf8(
     pb: &mut ParquetBuffer,
     column_writer: &mut ColumnWriter,
     column_reader: AnySlice,
+    blank_as_null: bool,
+    null_tokens: &NullTokens,
+    truncate_at: Option<usize>,
 ) -> Result<(), Error> {
     let cw = get_typed_column_writer_mut::<ByteArrayType>(column_writer);
-    let view = column_reader.as_w_text_view().unwrap();
+    let view = match column_reader {
+        AnySlice::WText(view) => view,
+        // Narrow text is transcoded from the system encoding instead.
+        AnySlice::Text(_) => {
+            return write_to_utf8(
+                pb,
+                column_writer,
+                column_reader,
+                blank_as_null,
+                null_tokens,
+                truncate_at,
+            )
+        }
+        _ => return Err(unexpected_view("wide text", column_reader)),
+    };
 
-    pb.write_optional_fallible(
+    let mut truncated = Vec::new();
+    let result = pb.write_optional_fallible(
         cw,
-        view.iter().map(|item| {
+        view.iter().enumerate().map(|(row_index, item)| {
+            let item = item.filter(|ustr| !(blank_as_null && is_blank_utf16(ustr.as_slice())));
             if let Some(ustr) = item {
-                let byte_array: ByteArray = ustr
-                    .to_string()
-                    .map_err(|_utf_16_error| {
-                        anyhow!("Data source must return valid UTF16 in wide character buffer")
-                    })?
-                    .into_bytes()
-                    .into();
-                Ok(Some(byte_array))
+                let ustr = match truncate_at {
+                    Some(length) if ustr.len() > length => {
+                        truncated.push(row_index);
+                        truncate_utf16(ustr.as_slice(), length)
+                    }
+                    _ => ustr.as_slice(),
+                };
+                let text = String::from_utf16(ustr).map_err(|_utf_16_error| {
+                    anyhow!("Data source must return valid UTF16 in wide character buffer")
+                })?;
+                if null_tokens.matches(&text) {
+                    return Ok(None);
+                }
+                Ok(Some(text.into_bytes().into()))
             } else {
                 Ok(None)
             }
         }),
-    )?;
-    Ok(())
+    );
+    pb.truncated_values = truncated;
+    result
 }
 
 pub struct Utf8 {
     repetition: Repetition,
     // Maximum string length in bytes
     length: usize,
+    /// Write values consisting only of whitespace as NULL.
+    blank_as_null: bool,
+    null_tokens: NullTokens,
+    /// Truncate values longer than `length` and report them.
+    truncate: bool,
 }
 
 impl Utf8 {
     pub fn with_bytes_length(repetition: Repetition, length: usize) -> Self {
-        Self { repetition, length }
+        Self {
+            repetition,
+            length,
+            blank_as_null: false,
+            null_tokens: NullTokens::default(),
+            truncate: false,
+        }
     }
 }
 
@@ -114,7 +238,7 @@ impl ColumnStrategy for Utf8 {
 
     fn buffer_desc(&self) -> BufferDesc {
         BufferDesc::Text {
-            max_str_len: self.length,
+            max_str_len: self.length + usize::from(self.truncate),
         }
     }
 
@@ -124,24 +248,93 @@ impl ColumnStrategy for Utf8 {
         column_writer: &mut ColumnWriter,
         column_view: AnySlice,
     ) -> Result<(), Error> {
-        write_to_utf8(parquet_buffer, column_writer, column_view)
+        write_to_utf8(
+            parquet_buffer,
+            column_writer,
+            column_view,
+            self.blank_as_null,
+            &self.null_tokens,
+            self.truncate.then_some(self.length),
+        )
     }
 }
 
+/// * `truncate_at`: Values longer than this many bytes are truncated and their rows recorded in
+///   the `truncated_values` of `pb`.
 fn write_to_utf8(
     pb: &mut ParquetBuffer,
     column_writer: &mut ColumnWriter,
     column_reader: AnySlice,
+    blank_as_null: bool,
+    null_tokens: &NullTokens,
+    truncate_at: Option<usize>,
 ) -> Result<(), Error> {
+    let view = match column_reader {
+        AnySlice::Text(view) => view,
+        // Wide text is transcoded from UTF-16 instead.
+        AnySlice::WText(_) => {
+            return write_utf16_to_utf8(
+                pb,
+                column_writer,
+                column_reader,
+                blank_as_null,
+                null_tokens,
+                truncate_at,
+            )
+        }
+        _ => return Err(unexpected_view("text", column_reader)),
+    };
     let cw = get_typed_column_writer_mut::<ByteArrayType>(column_writer);
-    let view = column_reader.as_text_view().unwrap();
 
-    pb.write_optional(
+    let mut truncated = Vec::new();
+    let result = pb.write_optional(
         cw,
-        view.iter().map(|item| item.map(utf8_bytes_to_byte_array)),
-    )?;
+        view.iter().enumerate().map(|(row_index, item)| {
+            let item = item.map(|bytes| match truncate_at {
+                Some(length) if bytes.len() > length => {
+                    truncated.push(row_index);
+                    truncate_utf8(bytes, length)
+                }
+                _ => bytes,
+            });
+            item.filter(|bytes| !null_tokens.matches(&String::from_utf8_lossy(bytes)))
+                .filter(|bytes| !(blank_as_null && is_blank(bytes)))
+                .map(utf8_bytes_to_byte_array)
+        }),
+    );
+    pb.truncated_values = truncated;
+    result
+}
 
-    Ok(())
+/// The first `length` bytes of `text`, or less, so no character is split.
+fn truncate_utf8(text: &[u8], length: usize) -> &[u8] {
+    let mut end = length;
+    // Continuation bytes of a multi byte character start with `0b10`.
+    while end > 0 && text[end] & 0xC0 == 0x80 {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// The first `length` code units of `text`, or one less, so no surrogate pair is split.
+fn truncate_utf16(text: &[u16], length: usize) -> &[u16] {
+    let is_high_surrogate = |unit: u16| (0xD800..0xDC00).contains(&unit);
+    if length > 0 && is_high_surrogate(text[length - 1]) {
+        &text[..length - 1]
+    } else {
+        &text[..length]
+    }
+}
+
+/// `true` if the text is empty or consists only of whitespace.
+fn is_blank(text: &[u8]) -> bool {
+    text.iter().all(u8::is_ascii_whitespace)
+}
+
+/// `true` if the UTF-16 text is empty or consists only of whitespace.
+fn is_blank_utf16(text: &[u16]) -> bool {
+    text.iter()
+        .all(|&c| u8::try_from(c).is_ok_and(|c| c.is_ascii_whitespace()))
 }
 
 fn utf8_bytes_to_byte_array(bytes: &[u8]) -> ByteArray {
@@ -159,3 +352,103 @@ fn utf8_bytes_to_byte_array(bytes: &[u8]) -> ByteArray {
     }
     utf8_str.into_owned().into_bytes().into()
 }
+
+/// Fetches text using narrow characters, but does not interpret it as UTF-8. The bytes are written
+/// as is into a `BYTE_ARRAY` column without a `UTF8` annotation. Used if users want to preserve the
+/// original encoding of the data source.
+pub struct RawText {
+    repetition: Repetition,
+    // Maximum string length in bytes
+    length: usize,
+}
+
+impl RawText {
+    pub fn with_bytes_length(repetition: Repetition, length: usize) -> Self {
+        Self { repetition, length }
+    }
+}
+
+impl ColumnStrategy for RawText {
+    fn parquet_type(&self, name: &str) -> Type {
+        Type::primitive_type_builder(name, PhysicalType::BYTE_ARRAY)
+            .with_repetition(self.repetition)
+            .build()
+            .unwrap()
+    }
+
+    fn buffer_desc(&self) -> BufferDesc {
+        BufferDesc::Text {
+            max_str_len: self.length,
+        }
+    }
+
+    fn copy_odbc_to_parquet(
+        &self,
+        parquet_buffer: &mut ParquetBuffer,
+        column_writer: &mut ColumnWriter,
+        column_view: AnySlice,
+    ) -> Result<(), Error> {
+        let cw = get_typed_column_writer_mut::<ByteArrayType>(column_writer);
+        let view = column_view
+            .as_text_view()
+            .ok_or_else(|| unexpected_view("text", column_view))?;
+        parquet_buffer.write_optional(
+            cw,
+            view.iter()
+                .map(|item| item.map(|bytes| bytes.to_owned().into())),
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_blank, is_blank_utf16, truncate_utf16, truncate_utf8, NullTokens};
+
+    #[test]
+    fn blank_text() {
+        assert!(is_blank(b"   "));
+        assert!(is_blank(b""));
+        assert!(!is_blank(b" a "));
+    }
+
+    #[test]
+    fn blank_utf16_text() {
+        let utf16 = |text: &str| text.encode_utf16().collect::<Vec<_>>();
+        assert!(is_blank_utf16(&utf16("   ")));
+        assert!(is_blank_utf16(&utf16("")));
+        assert!(!is_blank_utf16(&utf16(" ä ")));
+    }
+
+    #[test]
+    fn truncate_at_character_boundary() {
+        assert_eq!(b"ab", truncate_utf8(b"abc", 2));
+        // `ä` is encoded in two bytes
+        assert_eq!("a".as_bytes(), truncate_utf8("aäb".as_bytes(), 2));
+        assert_eq!("aä".as_bytes(), truncate_utf8("aäb".as_bytes(), 3));
+
+        let utf16 = |text: &str| text.encode_utf16().collect::<Vec<_>>();
+        assert_eq!(&utf16("ab")[..], truncate_utf16(&utf16("abc"), 2));
+        // `😀` is encoded as surrogate pair
+        assert_eq!(&utf16("a")[..], truncate_utf16(&utf16("a😀"), 2));
+        assert_eq!(&utf16("a😀")[..], truncate_utf16(&utf16("a😀b"), 3));
+    }
+
+    #[test]
+    fn match_null_tokens() {
+        let tokens = || vec!["NULL".to_owned(), "N/A".to_owned(), "\\N".to_owned()];
+
+        let case_sensitive = NullTokens::new(tokens(), false);
+        assert!(case_sensitive.matches("N/A"));
+        assert!(case_sensitive.matches("\\N"));
+        assert!(!case_sensitive.matches("null"));
+        assert!(!case_sensitive.matches(" NULL"));
+
+        let ignore_case = NullTokens::new(tokens(), true);
+        assert!(ignore_case.matches("null"));
+        assert!(ignore_case.matches("n/a"));
+        assert!(!ignore_case.matches("n"));
+
+        assert!(!NullTokens::default().matches(""));
+    }
+}
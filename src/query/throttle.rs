@@ -0,0 +1,69 @@
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+use log::debug;
+
+/// Limits the rate at which rows are fetched from the data source, to reduce the load on it. The
+/// rate is computed over the entire export, so a slow batch allows for the next one to be fetched
+/// without delay.
+#[derive(Clone, Copy)]
+pub struct Throttle {
+    max_rows_per_second: u64,
+    start: Instant,
+}
+
+impl Throttle {
+    pub fn new(max_rows_per_second: u64) -> Self {
+        Self {
+            max_rows_per_second,
+            start: Instant::now(),
+        }
+    }
+
+    /// Blocks until `total_rows_fetched` rows are within the limit for the time elapsed since the
+    /// throttle has been created.
+    pub fn wait(&self, total_rows_fetched: u64) {
+        if let Some(delay) = delay(
+            total_rows_fetched,
+            self.max_rows_per_second,
+            self.start.elapsed(),
+        ) {
+            debug!(
+                "Throttling fetch for {} ms to stay below {} rows per second.",
+                delay.as_millis(),
+                self.max_rows_per_second
+            );
+            sleep(delay)
+        }
+    }
+}
+
+/// Time left until fetching `total_rows_fetched` rows no longer exceeds `max_rows_per_second`.
+/// `None` if the rows are already within the limit.
+fn delay(total_rows_fetched: u64, max_rows_per_second: u64, elapsed: Duration) -> Option<Duration> {
+    let target = Duration::from_secs_f64(total_rows_fetched as f64 / max_rows_per_second as f64);
+    target.checked_sub(elapsed).filter(|delay| !delay.is_zero())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::delay;
+
+    #[test]
+    fn delay_depends_on_elapsed_time() {
+        assert_eq!(
+            Some(Duration::from_secs(2)),
+            delay(500, 100, Duration::from_secs(3))
+        );
+        assert_eq!(None, delay(500, 100, Duration::from_secs(5)));
+        assert_eq!(None, delay(500, 100, Duration::from_secs(7)));
+        assert_eq!(
+            Some(Duration::from_millis(500)),
+            delay(50, 100, Duration::ZERO)
+        );
+    }
+}
@@ -14,7 +14,7 @@ use parquet::{
 
 use crate::parquet_buffer::{BufferedDataType, ParquetBuffer};
 
-use super::column_strategy::ColumnStrategy;
+use super::column_strategy::{unexpected_view, ColumnStrategy};
 
 /// Parse wallclock time with fractional seconds from text into time. E.g. 16:04:12.0000000
 pub fn time_from_text(repetition: Repetition, precision: u8) -> Box<dyn ColumnStrategy> {
@@ -119,7 +119,9 @@ where
         + Copy,
     <Pdt::T as TryFrom<u32>>::Error: std::fmt::Debug,
 {
-    let from = column_reader.as_text_view().unwrap();
+    let from = column_reader
+        .as_text_view()
+        .ok_or_else(|| unexpected_view("times as text", column_reader))?;
     let into = Pdt::get_column_writer_mut(column_writer).unwrap();
     pb.write_optional(
         into,
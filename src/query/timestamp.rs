@@ -1,4 +1,5 @@
 use anyhow::Error;
+use chrono::NaiveDate;
 use odbc_api::{
     buffers::{AnySlice, BufferDesc},
     sys::Timestamp,
@@ -6,13 +7,16 @@ use odbc_api::{
 use parquet::{
     basic::{LogicalType, Repetition},
     column::writer::ColumnWriter,
-    data_type::{DataType, Int64Type},
+    data_type::{DataType, Int64Type, Int96, Int96Type},
     schema::types::Type,
 };
 
 use crate::parquet_buffer::ParquetBuffer;
 
-use super::{column_strategy::ColumnStrategy, timestamp_precision::TimestampPrecision};
+use super::{
+    column_strategy::{nullable_values, ColumnStrategy},
+    timestamp_precision::TimestampPrecision,
+};
 
 pub fn timestamp_without_tz(repetition: Repetition, precision: u8) -> Box<dyn ColumnStrategy> {
     Box::new(TimestampToI64 {
@@ -21,6 +25,12 @@ pub fn timestamp_without_tz(repetition: Repetition, precision: u8) -> Box<dyn Co
     })
 }
 
+/// Writes timestamps using the deprecated `INT96` physical type, for legacy readers which do not
+/// understand `INT64` timestamps.
+pub fn timestamp_int96(repetition: Repetition) -> Box<dyn ColumnStrategy> {
+    Box::new(TimestampToInt96 { repetition })
+}
+
 struct TimestampToI64 {
     repetition: Repetition,
     precision: TimestampPrecision,
@@ -52,15 +62,96 @@ impl ColumnStrategy for TimestampToI64 {
     }
 }
 
+struct TimestampToInt96 {
+    repetition: Repetition,
+}
+
+impl ColumnStrategy for TimestampToInt96 {
+    fn parquet_type(&self, name: &str) -> Type {
+        Type::primitive_type_builder(name, Int96Type::get_physical_type())
+            .with_repetition(self.repetition)
+            .build()
+            .unwrap()
+    }
+
+    fn buffer_desc(&self) -> BufferDesc {
+        BufferDesc::Timestamp { nullable: true }
+    }
+
+    fn copy_odbc_to_parquet(
+        &self,
+        parquet_buffer: &mut ParquetBuffer,
+        column_writer: &mut ColumnWriter,
+        column_view: AnySlice,
+    ) -> Result<(), Error> {
+        let from = nullable_values::<Timestamp>(column_view, "timestamps")?;
+        let into = Int96Type::get_column_writer_mut(column_writer).unwrap();
+        parquet_buffer.write_optional(into, from.map(|option| option.map(timestamp_to_int96)))
+    }
+}
+
+/// Julian day number of 1970-01-01.
+const JULIAN_DAY_OF_EPOCH: i64 = 2_440_588;
+
+/// `INT96` representation of a timestamp. The first two words hold the nanoseconds since midnight
+/// (least significant first), the third one the Julian day number.
+pub fn timestamp_to_int96(ts: &Timestamp) -> Int96 {
+    let date = NaiveDate::from_ymd_opt(ts.year as i32, ts.month as u32, ts.day as u32).unwrap();
+    let days_since_epoch = date.signed_duration_since(NaiveDate::default()).num_days();
+    let julian_day = (days_since_epoch + JULIAN_DAY_OF_EPOCH) as u32;
+    let nanos_of_day = (ts.hour as u64 * 3600 + ts.minute as u64 * 60 + ts.second as u64)
+        * 1_000_000_000
+        + ts.fraction as u64;
+    let mut int96 = Int96::new();
+    int96.set_data(nanos_of_day as u32, (nanos_of_day >> 32) as u32, julian_day);
+    int96
+}
+
 fn write_timestamp_col(
     pb: &mut ParquetBuffer,
     column_writer: &mut ColumnWriter,
     column_reader: AnySlice,
     precision: TimestampPrecision,
 ) -> Result<(), Error> {
-    let from = column_reader.as_nullable_slice::<Timestamp>().unwrap();
+    let from = nullable_values::<Timestamp>(column_reader, "timestamps")?;
     let into = Int64Type::get_column_writer_mut(column_writer).unwrap();
     let from = from.map(|option| option.map(|ts| precision.timestamp_to_i64(ts)).transpose());
     pb.write_optional_fallible(into, from)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use odbc_api::sys::Timestamp;
+
+    use super::timestamp_to_int96;
+
+    #[test]
+    fn int96_of_epoch() {
+        let epoch = Timestamp {
+            year: 1970,
+            month: 1,
+            day: 1,
+            ..Timestamp::default()
+        };
+        assert_eq!(&[0, 0, 2_440_588], timestamp_to_int96(&epoch).data());
+    }
+
+    #[test]
+    fn int96_round_trip() {
+        let ts = Timestamp {
+            year: 2021,
+            month: 3,
+            day: 14,
+            hour: 15,
+            minute: 9,
+            second: 26,
+            fraction: 535_897_932,
+        };
+        let int96 = timestamp_to_int96(&ts);
+        // 15:09:26.535897932 in nanoseconds is 54_566_535_897_932 = 12_704 * 2^32 + 3_271_369_548
+        assert_eq!(&[3_271_369_548, 12_704, 2_459_288], int96.data());
+        // 2021-03-14 15:09:26.535897932 UTC
+        assert_eq!(1_615_734_566_535_897_932, int96.to_nanos());
+    }
+}
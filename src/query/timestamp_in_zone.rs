@@ -0,0 +1,217 @@
+use anyhow::{anyhow, bail, Error};
+use chrono::{DateTime, LocalResult, NaiveDate, NaiveDateTime, Offset, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+use odbc_api::{
+    buffers::{AnySlice, BufferDesc},
+    sys::Timestamp,
+};
+use parquet::{
+    basic::{LogicalType, Repetition},
+    column::writer::{get_typed_column_writer_mut, ColumnWriter},
+    data_type::{DataType, Int64Type},
+    schema::types::Type,
+};
+
+use crate::{enum_args::UnknownTimeZone, parquet_buffer::ParquetBuffer};
+
+use super::{
+    column_strategy::{nullable_values, ColumnStrategy},
+    timestamp_precision::TimestampPrecision,
+};
+
+/// Writes timestamps without time zone as UTC, interpreting each one as local time of the time
+/// zone named in the same row of another column.
+pub struct TimestampInZone {
+    repetition: Repetition,
+    precision: TimestampPrecision,
+    /// Name of the column holding the time zones, used in error messages.
+    tz_column: String,
+    unknown: UnknownTimeZone,
+}
+
+impl TimestampInZone {
+    pub fn new(
+        repetition: Repetition,
+        precision: u8,
+        tz_column: String,
+        unknown: UnknownTimeZone,
+    ) -> Self {
+        let repetition = if unknown == UnknownTimeZone::Null {
+            Repetition::OPTIONAL
+        } else {
+            repetition
+        };
+        Self {
+            repetition,
+            precision: TimestampPrecision::new(precision),
+            tz_column,
+            unknown,
+        }
+    }
+
+    fn to_utc(&self, ts: &Timestamp, tz: Option<&str>) -> Result<Option<i64>, Error> {
+        let local = NaiveDate::from_ymd_opt(ts.year as i32, ts.month as u32, ts.day as u32)
+            .and_then(|date| {
+                date.and_hms_nano_opt(
+                    ts.hour as u32,
+                    ts.minute as u32,
+                    ts.second as u32,
+                    ts.fraction,
+                )
+            })
+            .ok_or_else(|| anyhow!("Invalid timestamp: {ts:?}"))?;
+        let tz = tz.map(str::trim).unwrap_or_default();
+        let utc = match tz.parse::<Tz>() {
+            Ok(zone) => local_to_utc(zone, local),
+            Err(_) => match self.unknown {
+                UnknownTimeZone::Error => {
+                    bail!("Unknown time zone '{tz}' in column '{}'.", self.tz_column)
+                }
+                UnknownTimeZone::Null => return Ok(None),
+                UnknownTimeZone::AssumeUtc => Some(local.and_utc()),
+            },
+        }
+        .ok_or_else(|| anyhow!("Timestamp {local} is out of range once converted to UTC."))?;
+        self.precision.datetime_to_i64(&utc).map(Some)
+    }
+}
+
+/// Converts `local`, a local time of `zone`, to UTC. A local time occurring twice, as clocks are
+/// turned back, is resolved to the earlier instant. A local time skipped, as clocks are turned
+/// forward, is interpreted with the offset before the transition, i.e. it is shifted forward by the
+/// gap. `None` if the result is out of range.
+fn local_to_utc(zone: Tz, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    let offset = match zone.offset_from_local_datetime(&local) {
+        LocalResult::Single(offset) | LocalResult::Ambiguous(offset, _) => offset,
+        // Transitions are assumed to be at least a day apart.
+        LocalResult::None => zone
+            .offset_from_local_datetime(&local.checked_sub_signed(TimeDelta::days(1))?)
+            .earliest()?,
+    };
+    let offset = TimeDelta::seconds(offset.fix().local_minus_utc().into());
+    local.checked_sub_signed(offset).map(|utc| utc.and_utc())
+}
+
+impl ColumnStrategy for TimestampInZone {
+    fn parquet_type(&self, name: &str) -> Type {
+        Type::primitive_type_builder(name, Int64Type::get_physical_type())
+            .with_logical_type(Some(LogicalType::Timestamp {
+                is_adjusted_to_u_t_c: true,
+                unit: self.precision.as_time_unit(),
+            }))
+            .with_repetition(self.repetition)
+            .build()
+            .unwrap()
+    }
+
+    fn buffer_desc(&self) -> BufferDesc {
+        BufferDesc::Timestamp { nullable: true }
+    }
+
+    fn copy_odbc_to_parquet(
+        &self,
+        _parquet_buffer: &mut ParquetBuffer,
+        _column_writer: &mut ColumnWriter,
+        _column_view: AnySlice,
+    ) -> Result<(), Error> {
+        bail!(
+            "Timestamps can only be converted to UTC together with the time zone column '{}'.",
+            self.tz_column
+        )
+    }
+
+    fn copy_odbc_to_parquet_with_key(
+        &self,
+        parquet_buffer: &mut ParquetBuffer,
+        column_writer: &mut ColumnWriter,
+        column_view: AnySlice,
+        key_view: AnySlice,
+    ) -> Result<(), Error> {
+        let timestamps = nullable_values::<Timestamp>(column_view, "timestamps")?;
+        let zones: Vec<Option<String>> = match key_view {
+            AnySlice::Text(view) => view
+                .iter()
+                .map(|value| value.map(|bytes| String::from_utf8_lossy(bytes).into_owned()))
+                .collect(),
+            AnySlice::WText(view) => view
+                .iter()
+                .map(|value| value.map(|chars| String::from_utf16_lossy(chars.as_slice())))
+                .collect(),
+            _ => bail!(
+                "Time zone column '{}' must be of text type.",
+                self.tz_column
+            ),
+        };
+        let cw = get_typed_column_writer_mut::<Int64Type>(column_writer);
+        parquet_buffer.write_optional_fallible(
+            cw,
+            timestamps.zip(&zones).map(|(ts, tz)| match ts {
+                Some(ts) => self.to_utc(ts, tz.as_deref()),
+                None => Ok(None),
+            }),
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, NaiveDateTime, Utc};
+    use chrono_tz::Tz;
+
+    use super::local_to_utc;
+
+    fn convert(zone: &str, local: &str) -> DateTime<Utc> {
+        let zone: Tz = zone.parse().unwrap();
+        let local = NaiveDateTime::parse_from_str(local, "%Y-%m-%d %H:%M:%S").unwrap();
+        local_to_utc(zone, local).unwrap()
+    }
+
+    fn utc(text: &str) -> DateTime<Utc> {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S")
+            .unwrap()
+            .and_utc()
+    }
+
+    #[test]
+    fn convert_local_times_of_new_york() {
+        // Winter and summer
+        assert_eq!(
+            utc("2024-01-15 17:00:00"),
+            convert("America/New_York", "2024-01-15 12:00:00")
+        );
+        assert_eq!(
+            utc("2024-07-04 16:00:00"),
+            convert("America/New_York", "2024-07-04 12:00:00")
+        );
+        // Clocks are turned back on 2024-11-03 02:00 EDT. 01:30 occurs twice, we pick the earlier.
+        assert_eq!(
+            utc("2024-11-03 05:30:00"),
+            convert("America/New_York", "2024-11-03 01:30:00")
+        );
+        // Clocks are turned forward on 2024-03-10 02:00 EST. 02:30 does not exist.
+        assert_eq!(
+            utc("2024-03-10 07:30:00"),
+            convert("America/New_York", "2024-03-10 02:30:00")
+        );
+        // Before 2007 daylight saving time ended on the last Sunday of October.
+        assert_eq!(
+            utc("2006-10-30 17:00:00"),
+            convert("America/New_York", "2006-10-30 12:00:00")
+        );
+    }
+
+    #[test]
+    fn convert_local_times_of_southern_hemisphere() {
+        // Lord Howe Island shifts by half an hour, daylight saving time is in January.
+        assert_eq!(
+            utc("2024-01-15 01:00:00"),
+            convert("Australia/Lord_Howe", "2024-01-15 12:00:00")
+        );
+        assert_eq!(
+            utc("2024-07-15 01:30:00"),
+            convert("Australia/Lord_Howe", "2024-07-15 12:00:00")
+        );
+        assert!("America/Nowhere".parse::<Tz>().is_err());
+    }
+}
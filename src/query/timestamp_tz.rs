@@ -1,16 +1,19 @@
 use anyhow::Error;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, FixedOffset, Utc};
 use odbc_api::buffers::{AnySlice, BufferDesc};
 use parquet::{
     basic::{LogicalType, Repetition, Type as PhysicalType},
     column::writer::{get_typed_column_writer_mut, ColumnWriter},
-    data_type::Int64Type,
+    data_type::{Int32Type, Int64Type},
     schema::types::Type,
 };
 
 use crate::parquet_buffer::ParquetBuffer;
 
-use super::{column_strategy::ColumnStrategy, timestamp_precision::TimestampPrecision};
+use super::{
+    column_strategy::{unexpected_view, ColumnStrategy},
+    timestamp_precision::TimestampPrecision,
+};
 
 pub fn timestamp_tz(precision: u8, repetition: Repetition) -> Result<Box<TimestampTz>, Error> {
     Ok(Box::new(TimestampTz::with_bytes_length(
@@ -47,17 +50,7 @@ impl ColumnStrategy for TimestampTz {
     }
 
     fn buffer_desc(&self) -> BufferDesc {
-        // Text representation looks like e.g. 2022-09-07 16:04:12 +02:00
-        // Text representation looks like e.g. 2022-09-07 16:04:12.123 +02:00
-
-        let max_str_len = 26
-            + if self.precision == 0 {
-                0
-            } else {
-                // Radix character `.` and precision.
-                1 + self.precision as usize
-            };
-        BufferDesc::Text { max_str_len }
+        text_buffer_desc(self.precision)
     }
 
     fn copy_odbc_to_parquet(
@@ -70,16 +63,79 @@ impl ColumnStrategy for TimestampTz {
     }
 }
 
+/// Companion to [`TimestampTz`] preserving the original offset of the timestamp in minutes east of
+/// UTC, which is lost once the value is normalized to UTC.
+pub fn timestamp_tz_offset(precision: u8, repetition: Repetition) -> Box<TimestampTzOffset> {
+    Box::new(TimestampTzOffset {
+        repetition,
+        precision,
+    })
+}
+
+pub struct TimestampTzOffset {
+    repetition: Repetition,
+    // Needed to describe the same buffer as the `TimestampTz` strategy this column is derived from.
+    precision: u8,
+}
+
+impl ColumnStrategy for TimestampTzOffset {
+    fn parquet_type(&self, name: &str) -> Type {
+        Type::primitive_type_builder(name, PhysicalType::INT32)
+            .with_logical_type(Some(LogicalType::Integer {
+                bit_width: 16,
+                is_signed: true,
+            }))
+            .with_repetition(self.repetition)
+            .build()
+            .unwrap()
+    }
+
+    fn buffer_desc(&self) -> BufferDesc {
+        text_buffer_desc(self.precision)
+    }
+
+    fn copy_odbc_to_parquet(
+        &self,
+        parquet_buffer: &mut ParquetBuffer,
+        column_writer: &mut ColumnWriter,
+        column_view: AnySlice,
+    ) -> Result<(), Error> {
+        let view = column_view
+            .as_text_view()
+            .ok_or_else(|| unexpected_view("timestamps as text", column_view))?;
+        let cw = get_typed_column_writer_mut::<Int32Type>(column_writer);
+        parquet_buffer.write_optional_fallible(
+            cw,
+            view.iter()
+                .map(|item| item.map(offset_in_minutes).transpose()),
+        )?;
+        Ok(())
+    }
+}
+
+fn text_buffer_desc(precision: u8) -> BufferDesc {
+    // Text representation looks like e.g. 2022-09-07 16:04:12 +02:00
+    // Text representation looks like e.g. 2022-09-07 16:04:12.123 +02:00
+
+    let max_str_len = 26
+        + if precision == 0 {
+            0
+        } else {
+            // Radix character `.` and precision.
+            1 + precision as usize
+        };
+    BufferDesc::Text { max_str_len }
+}
+
 fn write_timestamp_tz(
     pb: &mut ParquetBuffer,
     column_writer: &mut ColumnWriter,
     column_reader: AnySlice,
     precision: u8,
 ) -> Result<(), Error> {
-    let view = column_reader.as_text_view().expect(
-        "Invalid Column view type. This is not supposed to happen. Please open a Bug at \
-        https://github.com/pacman82/odbc2parquet/issues.",
-    );
+    let view = column_reader
+        .as_text_view()
+        .ok_or_else(|| unexpected_view("timestamps as text", column_reader))?;
     let cw = get_typed_column_writer_mut::<Int64Type>(column_writer);
     pb.write_optional_fallible(
         cw,
@@ -90,13 +146,60 @@ fn write_timestamp_tz(
 }
 
 fn to_utc_epoch(bytes: &[u8], precision: u8) -> Result<i64, Error> {
-    // Text representation looks like e.g. 2022-09-07 16:04:12 +02:00
-    let utf8 = String::from_utf8_lossy(bytes);
-
-    // Parse to datetime
-    let date_time = DateTime::parse_from_str(&utf8, "%Y-%m-%d %H:%M:%S%.9f %:z")?;
-    // let utc = date_time.naive_utc();
+    let date_time = parse_timestamp_tz(bytes)?;
     let utc = date_time.with_timezone(&Utc);
     let integer = TimestampPrecision::new(precision).datetime_to_i64(&utc)?;
     Ok(integer)
 }
+
+fn offset_in_minutes(bytes: &[u8]) -> Result<i32, Error> {
+    let date_time = parse_timestamp_tz(bytes)?;
+    Ok(date_time.offset().local_minus_utc() / 60)
+}
+
+fn parse_timestamp_tz(bytes: &[u8]) -> Result<DateTime<FixedOffset>, Error> {
+    // Text representation looks like e.g. 2022-09-07 16:04:12 +02:00
+    let utf8 = String::from_utf8_lossy(bytes);
+    let date_time = DateTime::parse_from_str(&utf8, "%Y-%m-%d %H:%M:%S%.9f %:z")?;
+    Ok(date_time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{offset_in_minutes, to_utc_epoch};
+
+    #[test]
+    fn positive_offset_crossing_midnight() {
+        // 2022-09-07 00:10:00 +05:30 is 2022-09-06 18:40:00 UTC
+        assert_eq!(
+            1662489600000,
+            to_utc_epoch(b"2022-09-07 00:10:00 +05:30", 3).unwrap()
+        );
+        assert_eq!(
+            330,
+            offset_in_minutes(b"2022-09-07 00:10:00 +05:30").unwrap()
+        );
+    }
+
+    #[test]
+    fn negative_offset_crossing_midnight() {
+        // 2022-09-07 23:50:00.123 -08:00 is 2022-09-08 07:50:00.123 UTC
+        assert_eq!(
+            1662623400123,
+            to_utc_epoch(b"2022-09-07 23:50:00.123 -08:00", 3).unwrap()
+        );
+        assert_eq!(
+            -480,
+            offset_in_minutes(b"2022-09-07 23:50:00.123 -08:00").unwrap()
+        );
+    }
+
+    #[test]
+    fn offset_crossing_year_boundary() {
+        // 2022-12-31 23:59:59.9999999 -08:00 is 2023-01-01 07:59:59.9999999 UTC
+        assert_eq!(
+            1672559999999999900,
+            to_utc_epoch(b"2022-12-31 23:59:59.9999999 -08:00", 7).unwrap()
+        );
+    }
+}
@@ -0,0 +1,675 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{anyhow, bail, Context, Error};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use odbc_api::{
+    buffers::{AnyBuffer, AnySlice, BufferDesc, ColumnarAnyBuffer, TextColumn},
+    sys::{Date, Time, Timestamp},
+    Bit, RowSetBuffer,
+};
+
+use super::{
+    bad_rows::retain_in_column,
+    derive::{column_value, Value},
+};
+
+/// Name of the function a transform script must define. It receives the row as a map from column
+/// name to value and returns the transformed map.
+#[cfg(feature = "rhai")]
+const TRANSFORM_FN: &str = "transform";
+
+/// Script of `--transform-script`, applied to each row between fetching a batch and writing it.
+pub struct TransformScript {
+    /// Path of the script, to attribute errors to it.
+    name: String,
+    #[cfg(feature = "rhai")]
+    engine: rhai::Engine,
+    #[cfg(feature = "rhai")]
+    ast: rhai::AST,
+}
+
+impl TransformScript {
+    /// Compiles the rhai script at `path`, which must define a function `transform(row)`.
+    #[cfg(feature = "rhai")]
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let engine = rhai::Engine::new();
+        let ast = engine
+            .compile_file(path.to_owned())
+            .map_err(|error| anyhow!("{error}"))
+            .with_context(|| format!("Could not compile transform script '{}'.", path.display()))?;
+        if !ast
+            .iter_functions()
+            .any(|f| f.name == TRANSFORM_FN && f.params.len() == 1)
+        {
+            bail!(
+                "Transform script '{}' must define a function `{TRANSFORM_FN}(row)`.",
+                path.display()
+            )
+        }
+        Ok(Self {
+            name: path.display().to_string(),
+            engine,
+            ast,
+        })
+    }
+
+    #[cfg(not(feature = "rhai"))]
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        bail!(
+            "Can not run transform script '{}'. `--transform-script` requires odbc2parquet to be \
+            built with the `rhai` feature, e.g. `cargo install odbc2parquet --features rhai`.",
+            path.display()
+        )
+    }
+
+    /// Path of the script.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Transforms each row of `buffer`. `names` holds the name of the column of each buffer. Binary
+    /// columns are passed through unchanged, since the script has no representation for bytes.
+    ///
+    /// `on_failure` is called with the index and the error of each row the script fails for. Such
+    /// rows are left out of the result if `drop_failed` is `true`, otherwise all columns passed to
+    /// the script are NULL in these rows. The latter fails if one of these columns is not nullable.
+    pub fn apply(
+        &self,
+        buffer: &ColumnarAnyBuffer,
+        names: &[&str],
+        drop_failed: bool,
+        mut on_failure: impl FnMut(usize, Error) -> Result<(), Error>,
+    ) -> Result<ColumnarAnyBuffer, Error> {
+        let num_rows = buffer.num_rows();
+        let exposed: Vec<usize> = (0..buffer.num_cols())
+            .filter(|&index| !matches!(buffer.column(index), AnySlice::Binary(_)))
+            .collect();
+        let mut cells: Vec<Vec<Cell>> = exposed
+            .iter()
+            .map(|_| Vec::with_capacity(num_rows))
+            .collect();
+        let mut keep = vec![true; num_rows];
+        for (row_index, keep) in keep.iter_mut().enumerate() {
+            match self.transform_row(buffer, names, &exposed, row_index) {
+                Ok(row) => {
+                    for (column, cell) in cells.iter_mut().zip(row) {
+                        column.push(cell);
+                    }
+                }
+                Err(error) => {
+                    on_failure(row_index, error)?;
+                    if drop_failed {
+                        *keep = false;
+                        continue;
+                    }
+                    if let Some(&index) = exposed
+                        .iter()
+                        .find(|&&index| !is_nullable(buffer.column(index)))
+                    {
+                        bail!(
+                            "Row {row_index} could not be transformed and can not be written as \
+                            NULL, since column '{}' is not nullable.",
+                            names[index]
+                        )
+                    }
+                    for column in &mut cells {
+                        column.push(Cell::Null);
+                    }
+                }
+            }
+        }
+        let num_kept = keep.iter().filter(|&&keep| keep).count();
+        let mut cells = cells.into_iter();
+        let columns = (0..buffer.num_cols())
+            .map(|index| {
+                let source = buffer.column(index);
+                let column = if exposed.contains(&index) {
+                    column_from_cells(source, cells.next().unwrap())
+                } else {
+                    retain_in_column(source, &keep, num_kept)
+                };
+                // The copy is never bound to a cursor, so the column numbers only need to be
+                // unique.
+                ((index + 1).try_into().unwrap(), column)
+            })
+            .collect();
+        let mut transformed = ColumnarAnyBuffer::new(columns);
+        *transformed.mut_num_fetch_rows() = num_kept;
+        Ok(transformed)
+    }
+
+    /// Values of the row `row_index` of `buffer` after the transformation, one for each column in
+    /// `exposed`.
+    fn transform_row(
+        &self,
+        buffer: &ColumnarAnyBuffer,
+        names: &[&str],
+        exposed: &[usize],
+        row_index: usize,
+    ) -> Result<Vec<Cell>, Error> {
+        let row = exposed
+            .iter()
+            .map(|&index| (names[index], column_value(buffer.column(index), row_index)));
+        let mut transformed = self.call(row)?;
+        let cells = exposed
+            .iter()
+            .map(|&index| {
+                let name = names[index];
+                let value = transformed.remove(name).ok_or_else(|| {
+                    anyhow!("Column '{name}' is missing in the row returned by the script.")
+                })?;
+                cell(buffer.column(index), value)
+                    .with_context(|| format!("Invalid value for column '{name}'."))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        if let Some(name) = transformed.keys().next() {
+            bail!("The script returned column '{name}', which is not part of the result set.")
+        }
+        Ok(cells)
+    }
+
+    /// Calls the function `transform` of the script with `row` and returns the resulting row.
+    #[cfg(feature = "rhai")]
+    fn call<'a>(
+        &self,
+        row: impl Iterator<Item = (&'a str, Value)>,
+    ) -> Result<HashMap<String, Value>, Error> {
+        use rhai::{CallFnOptions, Dynamic, Map, Scope};
+
+        let row: Map = row
+            .map(|(name, value)| {
+                let value = match value {
+                    Value::Null => Dynamic::UNIT,
+                    Value::Int(i) => Dynamic::from(i),
+                    Value::Float(f) => Dynamic::from(f),
+                    Value::Text(text) => Dynamic::from(text),
+                };
+                (name.into(), value)
+            })
+            .collect();
+        // The statements at the top level of the script are not evaluated for each row.
+        let options = CallFnOptions::new().eval_ast(false);
+        let transformed: Map = self
+            .engine
+            .call_fn_with_options(options, &mut Scope::new(), &self.ast, TRANSFORM_FN, (row,))
+            .map_err(|error| anyhow!("{error}"))?;
+        transformed
+            .into_iter()
+            .map(|(name, value)| {
+                let value = if value.is_unit() {
+                    Value::Null
+                } else if let Ok(i) = value.as_int() {
+                    Value::Int(i)
+                } else if let Ok(f) = value.as_float() {
+                    Value::Float(f)
+                } else if let Ok(b) = value.as_bool() {
+                    Value::Int(b.into())
+                } else if value.is_string() {
+                    Value::Text(value.into_string().unwrap())
+                } else {
+                    bail!(
+                        "The script returned a value of type {} for column '{name}'.",
+                        value.type_name()
+                    )
+                };
+                Ok((name.to_string(), value))
+            })
+            .collect()
+    }
+
+    #[cfg(not(feature = "rhai"))]
+    fn call<'a>(
+        &self,
+        _row: impl Iterator<Item = (&'a str, Value)>,
+    ) -> Result<HashMap<String, Value>, Error> {
+        unreachable!("Transform scripts can not be loaded without the `rhai` feature.")
+    }
+}
+
+/// Value returned by the script, converted into the type of the buffer of its column.
+#[derive(Debug, Clone, PartialEq)]
+enum Cell {
+    Null,
+    Int(i64),
+    Float(f64),
+    Bit(bool),
+    Text(String),
+    Date(Date),
+    Time(Time),
+    Timestamp(Timestamp),
+}
+
+/// `false` for buffers of fixed size values without indicators. Text buffers are always nullable.
+fn is_nullable(column: AnySlice) -> bool {
+    !matches!(
+        column,
+        AnySlice::F32(_)
+            | AnySlice::F64(_)
+            | AnySlice::I8(_)
+            | AnySlice::I16(_)
+            | AnySlice::I32(_)
+            | AnySlice::I64(_)
+            | AnySlice::U8(_)
+            | AnySlice::Bit(_)
+            | AnySlice::Date(_)
+            | AnySlice::Time(_)
+            | AnySlice::Timestamp(_)
+    )
+}
+
+/// Converts `value` into the type of the values of `column`. Dates, times and timestamps are
+/// parsed from ISO 8601 text, like they are passed to the script.
+fn cell(column: AnySlice, value: Value) -> Result<Cell, Error> {
+    fn int<T: TryFrom<i64>>(value: Value) -> Result<Cell, Error> {
+        match value.into_int()? {
+            None => Ok(Cell::Null),
+            Some(i) if T::try_from(i).is_ok() => Ok(Cell::Int(i)),
+            Some(i) => bail!("{i} is out of range for the column."),
+        }
+    }
+    fn text(value: Value, kind: &str) -> Result<Option<String>, Error> {
+        match value {
+            Value::Null => Ok(None),
+            Value::Text(text) => Ok(Some(text)),
+            other => bail!("Expected {kind} as text, but got {other:?}."),
+        }
+    }
+    let cell = match column {
+        AnySlice::Text(_) | AnySlice::WText(_) => value.into_text().map_or(Cell::Null, Cell::Text),
+        AnySlice::F32(_)
+        | AnySlice::NullableF32(_)
+        | AnySlice::F64(_)
+        | AnySlice::NullableF64(_) => value.into_float()?.map_or(Cell::Null, Cell::Float),
+        AnySlice::I8(_) | AnySlice::NullableI8(_) => int::<i8>(value)?,
+        AnySlice::I16(_) | AnySlice::NullableI16(_) => int::<i16>(value)?,
+        AnySlice::I32(_) | AnySlice::NullableI32(_) => int::<i32>(value)?,
+        AnySlice::I64(_) | AnySlice::NullableI64(_) => int::<i64>(value)?,
+        AnySlice::U8(_) | AnySlice::NullableU8(_) => int::<u8>(value)?,
+        AnySlice::Bit(_) | AnySlice::NullableBit(_) => {
+            value.into_int()?.map_or(Cell::Null, |i| Cell::Bit(i != 0))
+        }
+        AnySlice::Date(_) | AnySlice::NullableDate(_) => match text(value, "date")? {
+            None => Cell::Null,
+            Some(text) => {
+                let date = NaiveDate::parse_from_str(&text, "%Y-%m-%d")
+                    .with_context(|| format!("Invalid date '{text}'."))?;
+                Cell::Date(Date {
+                    year: date.year().try_into()?,
+                    month: date.month().try_into()?,
+                    day: date.day().try_into()?,
+                })
+            }
+        },
+        AnySlice::Time(_) | AnySlice::NullableTime(_) => match text(value, "time")? {
+            None => Cell::Null,
+            Some(text) => {
+                let time = NaiveTime::parse_from_str(&text, "%H:%M:%S%.f")
+                    .with_context(|| format!("Invalid time '{text}'."))?;
+                Cell::Time(Time {
+                    hour: time.hour().try_into()?,
+                    minute: time.minute().try_into()?,
+                    second: time.second().try_into()?,
+                })
+            }
+        },
+        AnySlice::Timestamp(_) | AnySlice::NullableTimestamp(_) => {
+            match text(value, "timestamp")? {
+                None => Cell::Null,
+                Some(text) => {
+                    let ts = NaiveDateTime::parse_from_str(&text, "%Y-%m-%d %H:%M:%S%.f")
+                        .or_else(|_| NaiveDateTime::parse_from_str(&text, "%Y-%m-%dT%H:%M:%S%.f"))
+                        .with_context(|| format!("Invalid timestamp '{text}'."))?;
+                    Cell::Timestamp(Timestamp {
+                        year: ts.year().try_into()?,
+                        month: ts.month().try_into()?,
+                        day: ts.day().try_into()?,
+                        hour: ts.hour().try_into()?,
+                        minute: ts.minute().try_into()?,
+                        second: ts.second().try_into()?,
+                        fraction: ts.nanosecond(),
+                    })
+                }
+            }
+        }
+        AnySlice::Binary(_) => unreachable!("Binary columns are not passed to the script"),
+    };
+    if cell == Cell::Null && !is_nullable(column) {
+        bail!("The column is not nullable.")
+    }
+    Ok(cell)
+}
+
+/// Buffer holding the values `$convert` maps `$cells` to. `$variant` and `$nullable_variant` are
+/// the buffer variants without and with indicators, `$desc` is the matching buffer description.
+macro_rules! column {
+    (
+        $cells:expr,
+        $nullable:expr,
+        $variant:ident,
+        $nullable_variant:ident,
+        $desc:ident,
+        $convert:expr
+    ) => {{
+        let cells = $cells;
+        if $nullable {
+            let mut column =
+                AnyBuffer::from_desc(cells.len(), BufferDesc::$desc { nullable: true });
+            let AnyBuffer::$nullable_variant(values) = &mut column else {
+                unreachable!("Nullable buffer description must yield a nullable buffer.")
+            };
+            let mut writer = values.writer_n(cells.len());
+            for (index, cell) in cells.into_iter().enumerate() {
+                writer.set_cell(index, $convert(cell));
+            }
+            column
+        } else {
+            // NULL has been rejected for columns without indicators.
+            AnyBuffer::$variant(
+                cells
+                    .into_iter()
+                    .map(|cell| $convert(cell).unwrap())
+                    .collect(),
+            )
+        }
+    }};
+}
+
+/// Buffer holding `cells`, with the same type and nullability as `source`. Text buffers are grown
+/// to fit the longest value.
+fn column_from_cells(source: AnySlice, cells: Vec<Cell>) -> AnyBuffer {
+    let text = |cell: &Cell| match cell {
+        Cell::Text(text) => Some(text.clone()),
+        _ => None,
+    };
+    let nullable = is_nullable(source);
+    match source {
+        AnySlice::Text(view) => {
+            let max_len = cells
+                .iter()
+                .filter_map(text)
+                .map(|text| text.len())
+                .fold(view.max_len(), usize::max);
+            let mut column = TextColumn::new(cells.len(), max_len);
+            for (index, cell) in cells.iter().enumerate() {
+                column.set_value(index, text(cell).as_deref().map(str::as_bytes));
+            }
+            AnyBuffer::Text(column)
+        }
+        AnySlice::WText(view) => {
+            let values: Vec<Option<Vec<u16>>> = cells
+                .iter()
+                .map(|cell| text(cell).map(|text| text.encode_utf16().collect()))
+                .collect();
+            let max_len = values
+                .iter()
+                .flatten()
+                .map(Vec::len)
+                .fold(view.max_len(), usize::max);
+            let mut column = TextColumn::new(cells.len(), max_len);
+            for (index, value) in values.iter().enumerate() {
+                column.set_value(index, value.as_deref());
+            }
+            AnyBuffer::WText(column)
+        }
+        AnySlice::F32(_) | AnySlice::NullableF32(_) => {
+            column!(cells, nullable, F32, NullableF32, F32, |cell| match cell {
+                Cell::Float(f) => Some(f as f32),
+                _ => None,
+            })
+        }
+        AnySlice::F64(_) | AnySlice::NullableF64(_) => {
+            column!(cells, nullable, F64, NullableF64, F64, |cell| match cell {
+                Cell::Float(f) => Some(f),
+                _ => None,
+            })
+        }
+        // Integers have been checked to be in range of the column.
+        AnySlice::I8(_) | AnySlice::NullableI8(_) => {
+            column!(cells, nullable, I8, NullableI8, I8, |cell| match cell {
+                Cell::Int(i) => Some(i as i8),
+                _ => None,
+            })
+        }
+        AnySlice::I16(_) | AnySlice::NullableI16(_) => {
+            column!(cells, nullable, I16, NullableI16, I16, |cell| match cell {
+                Cell::Int(i) => Some(i as i16),
+                _ => None,
+            })
+        }
+        AnySlice::I32(_) | AnySlice::NullableI32(_) => {
+            column!(cells, nullable, I32, NullableI32, I32, |cell| match cell {
+                Cell::Int(i) => Some(i as i32),
+                _ => None,
+            })
+        }
+        AnySlice::I64(_) | AnySlice::NullableI64(_) => {
+            column!(cells, nullable, I64, NullableI64, I64, |cell| match cell {
+                Cell::Int(i) => Some(i),
+                _ => None,
+            })
+        }
+        AnySlice::U8(_) | AnySlice::NullableU8(_) => {
+            column!(cells, nullable, U8, NullableU8, U8, |cell| match cell {
+                Cell::Int(i) => Some(i as u8),
+                _ => None,
+            })
+        }
+        AnySlice::Bit(_) | AnySlice::NullableBit(_) => {
+            column!(cells, nullable, Bit, NullableBit, Bit, |cell| match cell {
+                Cell::Bit(b) => Some(Bit::from_bool(b)),
+                _ => None,
+            })
+        }
+        AnySlice::Date(_) | AnySlice::NullableDate(_) => {
+            column!(
+                cells,
+                nullable,
+                Date,
+                NullableDate,
+                Date,
+                |cell| match cell {
+                    Cell::Date(date) => Some(date),
+                    _ => None,
+                }
+            )
+        }
+        AnySlice::Time(_) | AnySlice::NullableTime(_) => {
+            column!(
+                cells,
+                nullable,
+                Time,
+                NullableTime,
+                Time,
+                |cell| match cell {
+                    Cell::Time(time) => Some(time),
+                    _ => None,
+                }
+            )
+        }
+        AnySlice::Timestamp(_) | AnySlice::NullableTimestamp(_) => {
+            column!(
+                cells,
+                nullable,
+                Timestamp,
+                NullableTimestamp,
+                Timestamp,
+                |cell| {
+                    match cell {
+                        Cell::Timestamp(ts) => Some(ts),
+                        _ => None,
+                    }
+                }
+            )
+        }
+        AnySlice::Binary(_) => unreachable!("Binary columns are not passed to the script"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use odbc_api::{
+        buffers::{AnyBuffer, ColumnarAnyBuffer, TextColumn},
+        sys::Date,
+    };
+
+    use crate::query::derive::Value;
+
+    use super::{cell, column_from_cells, Cell};
+
+    #[test]
+    fn convert_returned_values_into_column_types() {
+        let dates = ColumnarAnyBuffer::new(vec![(1, AnyBuffer::Date(vec![]))]);
+        let integers = ColumnarAnyBuffer::new(vec![(1, AnyBuffer::I8(vec![]))]);
+
+        assert_eq!(
+            Cell::Date(Date {
+                year: 2024,
+                month: 2,
+                day: 29
+            }),
+            cell(dates.column(0), Value::Text("2024-02-29".to_owned())).unwrap()
+        );
+        assert_eq!(
+            Cell::Int(7),
+            cell(integers.column(0), Value::Float(7.2)).unwrap()
+        );
+        assert!(cell(integers.column(0), Value::Int(300)).is_err());
+        assert!(cell(integers.column(0), Value::Null).is_err());
+        assert!(cell(dates.column(0), Value::Int(2024)).is_err());
+    }
+
+    #[test]
+    fn grow_text_column_to_longest_returned_value() {
+        let buffer = ColumnarAnyBuffer::new(vec![(1, AnyBuffer::Text(TextColumn::new(2, 2)))]);
+
+        let column = column_from_cells(
+            buffer.column(0),
+            vec![Cell::Text("longer".to_owned()), Cell::Null],
+        );
+
+        let AnyBuffer::Text(text) = column else {
+            panic!("Expected text column.")
+        };
+        assert_eq!(6, text.max_len());
+        assert_eq!(Some(&b"longer"[..]), text.value_at(0));
+        assert_eq!(None, text.value_at(1));
+    }
+
+    #[cfg(not(feature = "rhai"))]
+    #[test]
+    fn loading_a_script_requires_the_rhai_feature() {
+        let error = super::TransformScript::load("transform.rhai".as_ref())
+            .err()
+            .unwrap();
+
+        assert!(error.to_string().contains("`rhai` feature"));
+    }
+
+    #[cfg(feature = "rhai")]
+    mod rhai {
+        use std::fs;
+
+        use anyhow::Error;
+        use odbc_api::{
+            buffers::{AnyBuffer, AnySlice, BufferDesc, ColumnarAnyBuffer, TextColumn},
+            RowSetBuffer,
+        };
+        use tempfile::tempdir;
+
+        use super::super::TransformScript;
+
+        /// Loads a script with the body `source`.
+        fn script(source: &str) -> TransformScript {
+            let dir = tempdir().unwrap();
+            let path = dir.path().join("transform.rhai");
+            fs::write(&path, source).unwrap();
+            TransformScript::load(&path).unwrap()
+        }
+
+        /// Buffer with a text column `name` and a nullable integer column `amount`.
+        fn buffer() -> (ColumnarAnyBuffer, Vec<&'static str>) {
+            let mut names = TextColumn::new(2, 3);
+            names.set_value(0, Some(b"abc"));
+            names.set_value(1, Some(b"de"));
+            let mut amounts = AnyBuffer::from_desc(2, BufferDesc::I32 { nullable: true });
+            let AnyBuffer::NullableI32(column) = &mut amounts else {
+                panic!("Expected nullable column.")
+            };
+            let mut writer = column.writer_n(2);
+            writer.set_cell(0, Some(1));
+            writer.set_cell(1, Some(0));
+            let mut buffer =
+                ColumnarAnyBuffer::new(vec![(1, AnyBuffer::Text(names)), (2, amounts)]);
+            *buffer.mut_num_fetch_rows() = 2;
+            (buffer, vec!["name", "amount"])
+        }
+
+        #[test]
+        fn transform_each_row() {
+            let script = script(
+                "fn transform(row) { row.name = row.name.to_upper() + \"!\"; \
+                row.amount = 10 / row.amount; row }",
+            );
+            let (buffer, names) = buffer();
+
+            let mut failed = Vec::new();
+            let transformed = script
+                .apply(&buffer, &names, false, |row_index, _error| {
+                    failed.push(row_index);
+                    Ok(())
+                })
+                .unwrap();
+
+            assert_eq!(vec![1], failed);
+            assert_eq!(2, transformed.num_rows());
+            let AnySlice::Text(text) = transformed.column(0) else {
+                panic!("Expected text column.")
+            };
+            assert_eq!(
+                vec![Some(&b"ABC!"[..]), None],
+                text.iter().collect::<Vec<_>>()
+            );
+            let amounts = transformed.column(1).as_nullable_slice::<i32>().unwrap();
+            assert_eq!(vec![Some(&10), None], amounts.collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn drop_rows_the_script_fails_for() {
+            let script = script("fn transform(row) { row.amount = 10 / row.amount; row }");
+            let (buffer, names) = buffer();
+
+            let transformed = script.apply(&buffer, &names, true, |_, _| Ok(())).unwrap();
+
+            assert_eq!(1, transformed.num_rows());
+            let amounts = transformed.column(1).as_nullable_slice::<i32>().unwrap();
+            assert_eq!(vec![Some(&10)], amounts.collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn returned_columns_must_match_result_set() {
+            let script = script("fn transform(row) { #{ name: row.name, extra: 1 } }");
+            let (buffer, names) = buffer();
+
+            let result = script.apply(&buffer, &names, false, |_, error| Err(error));
+
+            let error: Error = result.err().unwrap();
+            assert_eq!(
+                "Column 'amount' is missing in the row returned by the script.",
+                error.to_string()
+            );
+        }
+
+        #[test]
+        fn script_must_define_transform() {
+            let dir = tempdir().unwrap();
+            let path = dir.path().join("transform.rhai");
+            fs::write(&path, "fn other(row) { row }").unwrap();
+
+            let error = TransformScript::load(&path).err().unwrap();
+
+            assert!(error
+                .to_string()
+                .contains("must define a function `transform(row)`"));
+        }
+    }
+}
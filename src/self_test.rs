@@ -0,0 +1,160 @@
+use anyhow::{anyhow, bail, Context, Error};
+use clap::{Args, FromArgMatches};
+use log::warn;
+use odbc_api::{Connection, Environment};
+
+use crate::{open_connection, query::query, ConnectOpts, QueryOpt, SelfTestOpt};
+
+/// SQL types exercised by `self-test`, if none are specified, together with the literal cast into
+/// each. Type names are those of the SQL standard, so each database may reject some of them.
+const STANDARD_TYPES: [(&str, &str); 14] = [
+    ("SMALLINT", "-32768"),
+    ("INTEGER", "2147483647"),
+    ("BIGINT", "-9223372036854775808"),
+    ("REAL", "1.5"),
+    ("DOUBLE PRECISION", "-0.125"),
+    ("DECIMAL(9,2)", "-1234567.89"),
+    ("DECIMAL(18,4)", "12345678901234.5678"),
+    ("DECIMAL(38,0)", "12345678901234567890123456789012345678"),
+    ("CHAR(5)", "odbc "),
+    ("VARCHAR(20)", "Grüße, 世界"),
+    ("DATE", "2024-02-29"),
+    ("TIME", "23:59:58"),
+    ("TIMESTAMP", "2024-02-29 23:59:58.123"),
+    ("BIT", "1"),
+];
+
+/// Exports a value of each type with `--round-trip-check`, so it takes the same path as any other
+/// export and is compared with its text representation afterwards. Failures of a single type are
+/// reported and do not stop the test of the others.
+pub fn self_test(environment: &Environment, opt: SelfTestOpt) -> Result<(), Error> {
+    let SelfTestOpt {
+        connect_opts,
+        use_literals_only,
+        table,
+        types,
+    } = opt;
+    let types = if types.is_empty() {
+        STANDARD_TYPES
+            .iter()
+            .map(|&(sql_type, literal)| (sql_type.to_owned(), literal.to_owned()))
+            .collect()
+    } else {
+        types
+    };
+    // Only needed to create and drop tables. Each export opens a connection of its own.
+    let conn = if use_literals_only {
+        None
+    } else {
+        Some(open_connection(environment, &connect_opts)?)
+    };
+    let out_dir = tempfile::tempdir()?;
+
+    let mut num_failed = 0;
+    for (index, (sql_type, literal)) in types.iter().enumerate() {
+        let output = out_dir.path().join(format!("{index}.par"));
+        let output = output.to_str().expect("Temporary file path must be utf8");
+        let literal = format!("CAST('{}' AS {sql_type})", literal.replace('\'', "''"));
+        let result = match &conn {
+            None => export(
+                environment,
+                &connect_opts,
+                output,
+                &format!("SELECT {literal} AS a"),
+            ),
+            Some(conn) => with_table(conn, &table, sql_type, &literal, |query| {
+                export(environment, &connect_opts, output, query)
+            }),
+        };
+        match result {
+            Ok(()) => println!("PASS {sql_type}"),
+            Err(error) => {
+                num_failed += 1;
+                println!("FAIL {sql_type}: {error:#}");
+            }
+        }
+    }
+    if num_failed != 0 {
+        bail!(
+            "{num_failed} of {} types failed the self test.",
+            types.len()
+        )
+    }
+    Ok(())
+}
+
+/// Creates `table` with a column of `sql_type`, holding the value and a NULL, and calls `f` with a
+/// query selecting them. The table is dropped afterwards, even if `f` fails.
+fn with_table(
+    conn: &Connection,
+    table: &str,
+    sql_type: &str,
+    literal: &str,
+    f: impl FnOnce(&str) -> Result<(), Error>,
+) -> Result<(), Error> {
+    conn.execute(
+        &format!("CREATE TABLE {table} (id INTEGER, a {sql_type})"),
+        (),
+    )
+    .with_context(|| format!("Could not create table '{table}'."))?;
+    let result = conn
+        .execute(
+            &format!("INSERT INTO {table} (id, a) VALUES (1, {literal}), (2, NULL)"),
+            (),
+        )
+        .with_context(|| format!("Could not insert into table '{table}'."))
+        .and_then(|_| f(&format!("SELECT a FROM {table} ORDER BY id")));
+    if let Err(error) = conn.execute(&format!("DROP TABLE {table}"), ()) {
+        warn!("Could not drop table '{table}': {error}");
+    }
+    result
+}
+
+/// Runs the `query` subcommand with `--round-trip-check`, as if invoked from the command line.
+fn export(
+    environment: &Environment,
+    connect_opts: &ConnectOpts,
+    output: &str,
+    query_text: &str,
+) -> Result<(), Error> {
+    let matches = QueryOpt::augment_args(clap::Command::new("query")).try_get_matches_from([
+        "query",
+        "--round-trip-check",
+        "2",
+        output,
+        query_text,
+    ])?;
+    let mut opt = QueryOpt::from_arg_matches(&matches)?;
+    opt.connect_opts = connect_opts.clone();
+    query(environment, opt)
+}
+
+/// Parses `--type` of `self-test`, in format `TYPE=LITERAL`. The literal may itself contain `=`.
+pub fn sql_type_from_str(source: &str) -> Result<(String, String), Error> {
+    let (sql_type, literal) = source
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Type must be specified in format: 'TYPE=LITERAL'"))?;
+    if sql_type.trim().is_empty() {
+        bail!("SQL type must not be empty.")
+    }
+    Ok((sql_type.trim().to_owned(), literal.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sql_type_from_str;
+
+    #[test]
+    fn parse_sql_type_and_literal() {
+        assert_eq!(
+            ("DECIMAL(9,2)".to_owned(), "-1.5".to_owned()),
+            sql_type_from_str("DECIMAL(9,2)=-1.5").unwrap()
+        );
+        assert_eq!(
+            ("VARCHAR(5)".to_owned(), "a=b".to_owned()),
+            sql_type_from_str("VARCHAR(5)=a=b").unwrap()
+        );
+        assert!(sql_type_from_str("INTEGER").is_err());
+        assert!(sql_type_from_str("=1").is_err());
+    }
+}
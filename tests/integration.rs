@@ -1,9 +1,13 @@
+// Some older tests borrow the arguments passed to `Command::args`.
+#![allow(clippy::needless_borrows_for_generic_args)]
+
 use std::{
     fs::File,
-    io::{ErrorKind, Write},
+    io::{ErrorKind, Read, Write},
     path::Path,
     str,
     sync::Arc,
+    time::{Duration, Instant},
 };
 
 use assert_cmd::{assert::Assert, Command};
@@ -15,12 +19,17 @@ use odbc_api::{
     Connection, ConnectionOptions, Cursor, Environment, IntoParameter,
 };
 use parquet::{
+    basic::{ColumnOrder, Compression, ConvertedType, SortOrder, Type as PhysicalType},
     column::writer::ColumnWriter,
     data_type::{ByteArray, FixedLenByteArray},
     file::{
-        properties::WriterProperties, reader::FileReader, serialized_reader::SerializedFileReader,
+        page_index::index::Index,
+        properties::WriterProperties,
+        reader::FileReader,
+        serialized_reader::{ReadOptionsBuilder, SerializedFileReader},
         writer::SerializedFileWriter,
     },
+    format::SortingColumn,
     schema::parser::parse_message_type,
 };
 use predicates::{ord::eq, str::contains};
@@ -180,6 +189,33 @@ fn foobar_connection_string() {
     .code(1);
 }
 
+#[test]
+fn write_diagnostic_records_to_error_log() {
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Tempfile path must be utf8");
+    let error_log_path = out_dir.path().join("error.json");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "--error-log",
+            error_log_path.to_str().unwrap(),
+            "query",
+            "-c",
+            MSSQL,
+            out_str,
+            "SELECT * FROM WriteDiagnosticRecordsToErrorLogDoesNotExist",
+        ])
+        .assert()
+        .failure();
+
+    let error_log = std::fs::read_to_string(error_log_path).unwrap();
+    assert!(error_log.starts_with("{\"error\":"));
+    assert!(error_log.contains("{\"state\":\"42S02\",\"native_error\":208,\"message\":"));
+}
+
 #[test]
 fn should_give_good_error_if_specifying_directory_for_output() {
     // A temporary directory, to be removed at the end of the test.
@@ -359,6 +395,43 @@ fn query_decimals() {
     ));
 }
 
+#[test]
+fn query_decimals_with_fixed_byte_length() {
+    // Setup table for test
+    let table_name = "QueryDecimalsWithFixedByteLength";
+    let mut table = TableMssql::new(table_name, &["DECIMAL(3,2) NOT NULL", "DECIMAL(20,0)"]);
+    table.insert_rows_as_text(&[["-1.23", "-12345678901234567890"]]);
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    let query = format!("SELECT a,b FROM {table_name};");
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            out_str,
+            "--connection-string",
+            MSSQL,
+            "--decimal-byte-length",
+            "16",
+            &query,
+        ])
+        .assert()
+        .success();
+
+    let expected_values = "{a: -1.23, b: -12345678901234567890.}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
+
+    parquet_schema_out(out_str).stdout(contains(
+        "message schema {\n  \
+                REQUIRED FIXED_LEN_BYTE_ARRAY (16) a (DECIMAL(3,2));\n  \
+                OPTIONAL FIXED_LEN_BYTE_ARRAY (16) b (DECIMAL(20,0));\n\
+            }",
+    ));
+}
+
 /// Produce output for downstream artefacts like polars which lack support for decimal. In effect
 /// logical type decimal should not show up in the output
 #[test]
@@ -643,12 +716,16 @@ fn query_timestamp_with_timezone_mssql() {
     parquet_schema_out(out_str).stdout(contains("OPTIONAL INT64 a (TIMESTAMP(NANOS,true));"));
 }
 
+/// Original offsets of `DATETIMEOFFSET` values are emitted into a companion column, if requested.
 #[test]
-fn query_timestamp_mssql_precision_7() {
+fn query_timestamp_with_timezone_offset_column_mssql() {
     // Setup table for test
-    let table_name = "QueryTimestampMssqlPrecision7";
-    let mut table = TableMssql::new(table_name, &["DATETIME2(7)"]);
-    table.insert_rows_as_text(&[["2022-09-07 16:04:12.1234567"]]);
+    let table_name = "QueryTimestampWithTimezoneOffsetColumn";
+    let mut table = TableMssql::new(table_name, &["DATETIMEOFFSET(3)"]);
+    table.insert_rows_as_text(&[
+        ["2022-09-07 00:10:00.000 +05:30"],
+        ["2022-09-07 23:50:00.123 -08:00"],
+    ]);
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
     // The name of the output parquet file we are going to write. Since it is in a temporary
@@ -656,7 +733,7 @@ fn query_timestamp_mssql_precision_7() {
     let out_path = out_dir.path().join("out.par");
     // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
-    let query = format!("SELECT a FROM {table_name};");
+    let query = format!("SELECT a FROM {table_name} ORDER BY id;");
 
     Command::cargo_bin("odbc2parquet")
         .unwrap()
@@ -666,23 +743,27 @@ fn query_timestamp_mssql_precision_7() {
             out_str,
             "--connection-string",
             MSSQL,
+            "--timestamp-tz-offset-column",
             &query,
         ])
         .assert()
         .success();
 
-    let expected_values = "{a: 1662566652123456700}\n";
+    let expected_values = "{a: 2022-09-06 18:40:00 +00:00, a_offset: 330}\n\
+        {a: 2022-09-08 07:50:00 +00:00, a_offset: -480}\n";
     parquet_read_out(out_str).stdout(eq(expected_values));
 
-    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT64 a (TIMESTAMP(NANOS,false));"));
+    parquet_schema_out(out_str).stdout(contains(
+        "OPTIONAL INT64 a (TIMESTAMP(MILLIS,true));\n  OPTIONAL INT32 a_offset (INTEGER(16,true));",
+    ));
 }
 
 #[test]
-fn query_unsigned_tinyint() {
+fn query_timestamp_mssql_precision_7() {
     // Setup table for test
-    let table_name = "QueryUnsignedTinyInt";
-    let mut table = TableMssql::new(table_name, &["TINYINT"]);
-    table.insert_rows_as_text(&[["255"]]);
+    let table_name = "QueryTimestampMssqlPrecision7";
+    let mut table = TableMssql::new(table_name, &["DATETIME2(7)"]);
+    table.insert_rows_as_text(&[["2022-09-07 16:04:12.1234567"]]);
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
     // The name of the output parquet file we are going to write. Since it is in a temporary
@@ -705,24 +786,20 @@ fn query_unsigned_tinyint() {
         .assert()
         .success();
 
-    let expected_values = "{a: 255}\n";
+    let expected_values = "{a: 1662566652123456700}\n";
     parquet_read_out(out_str).stdout(eq(expected_values));
 
-    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT32 a (INTEGER(8,false));"));
+    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT64 a (TIMESTAMP(NANOS,false));"));
 }
 
 #[test]
-fn should_error_if_timestamp_is_out_of_range() {
+fn query_timestamp_as_int96_mssql() {
     // Setup table for test
-    let table_name = "ShouldErrorIfTimestampIsOutOfRange";
+    let table_name = "QueryTimestampAsInt96";
     let mut table = TableMssql::new(table_name, &["DATETIME2(7)"]);
-    table.insert_rows_as_text(&[["2700-01-01 00:00:00"]]);
-    // A temporary directory, to be removed at the end of the test.
+    table.insert_rows_as_text(&[["2022-09-07 16:04:12.1234567"]]);
     let out_dir = tempdir().unwrap();
-    // The name of the output parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
     let out_path = out_dir.path().join("out.par");
-    // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
     let query = format!("SELECT a FROM {table_name};");
 
@@ -734,66 +811,72 @@ fn should_error_if_timestamp_is_out_of_range() {
             out_str,
             "--connection-string",
             MSSQL,
+            "--timestamp-int96",
             &query,
         ])
         .assert()
-        .failure()
-        .stderr(contains(
-            "Invalid timestamp: 2700-01-01 00:00:00. The valid range for timestamps with \
-            nano seconds precision is between 1677-09-21 00:12:44 and 2262-04-11 \
-            23:47:16.854775807. Other timestamps can not be represented in parquet. To mitigate \
-            this you could downcast the precision in the query or convert the column to text.",
-        ));
+        .success();
+
+    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT96 a;"));
 }
 
 #[test]
-fn should_correctly_fetch_upper_bound_timestamp() {
+fn int96_for_named_columns_mssql() {
     // Setup table for test
-    let table_name = "ShouldCorrectlyFetchUpperBoundTimestamp";
-    let mut table = TableMssql::new(table_name, &["DATETIME2(7)"]);
-    // Due to precision the last 7 nanoseconds are excluded from the upper bound.
-    table.insert_rows_as_text(&[["2262-04-11 23:47:16.854775807"]]);
-    // A temporary directory, to be removed at the end of the test.
+    let table_name = "Int96ForNamedColumns";
+    let mut table = TableMssql::new(table_name, &["DATETIME2(3)", "DATETIME2(3)", "INTEGER"]);
+    table.insert_rows_as_text(&[["2022-09-07 16:04:12.123", "2022-09-07 16:04:12.123", "42"]]);
     let out_dir = tempdir().unwrap();
-    // The name of the output parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
     let out_path = out_dir.path().join("out.par");
-    // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
-    let query = format!("SELECT a FROM {table_name};");
+    let query = format!("SELECT a, b, c FROM {table_name};");
 
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
-            "-vvvv",
             "query",
             out_str,
             "--connection-string",
             MSSQL,
+            "--int96-columns",
+            "a",
             &query,
         ])
         .assert()
         .success();
 
-    // Binary of the expectation is all `1`, except for the first (sign) and the last three digits
-    // (precision of MsSQL is not nanoseconds, but 7 digits).
-    let expected_values = "{a: 9223372036854775800}\n";
-    parquet_read_out(out_str).stdout(eq(expected_values));
+    parquet_schema_out(out_str).stdout(contains(
+        "message schema {\n  OPTIONAL INT96 a;\n  \
+        OPTIONAL INT64 b (TIMESTAMP(MILLIS,false));\n  OPTIONAL INT32 c;\n}",
+    ));
+
+    // Only timestamps can be written as INT96
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            out_str,
+            "--connection-string",
+            MSSQL,
+            "--int96-columns",
+            "a,c",
+            &query,
+        ])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "Column 'c' passed to `--int96-columns` must be a timestamp",
+        ));
 }
 
 #[test]
-fn should_correctly_fetch_lower_bound_timestamp() {
+fn query_date_as_timestamp_mssql() {
     // Setup table for test
-    let table_name = "ShouldCorrectlyFetchLowerBound";
-    let mut table = TableMssql::new(table_name, &["DATETIME2(7)"]);
-    // Due to precision the last 7 nanoseconds are excluded from the lower bound, so we round up
-    table.insert_rows_as_text(&[["1677-09-21 00:12:44"]]);
-    // A temporary directory, to be removed at the end of the test.
+    let table_name = "QueryDateAsTimestamp";
+    let mut table = TableMssql::new(table_name, &["DATE"]);
+    table.insert_rows_as_text(&[["2022-09-07"]]);
     let out_dir = tempdir().unwrap();
-    // The name of the output parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
     let out_path = out_dir.path().join("out.par");
-    // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
     let query = format!("SELECT a FROM {table_name};");
 
@@ -805,41 +888,27 @@ fn should_correctly_fetch_lower_bound_timestamp() {
             out_str,
             "--connection-string",
             MSSQL,
+            "--date-as-timestamp",
             &query,
         ])
         .assert()
         .success();
 
-    // Binary of the expectation is all `1`, execpt for the first (sign) and the last three digits
-    // (precision of MsSQL is not nano seconds, but 7 digits).
-    let expected_values = "{a: -9223372036000000000}\n";
+    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT64 a (TIMESTAMP(MILLIS,false));"));
+    let expected_values = "{a: 2022-09-07 00:00:00 +00:00}\n";
     parquet_read_out(out_str).stdout(eq(expected_values));
 }
 
 #[test]
-fn query_timestamp_ms_with_timezone_mssql() {
+fn query_fast_decimals_mssql() {
     // Setup table for test
-    let table_name = "QueryTimestampMsWithTimezone";
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-    // ODBC data type: SqlDataType(-155), decimal_digits: 3
-    setup_empty_table_mssql(&conn, table_name, &["DATETIMEOFFSET(3)"]).unwrap();
-    let insert = format!(
-        "INSERT INTO {table_name}
-        (a)
-        VALUES
-        ('2022-09-07 16:04:12 +02:00');"
-    );
-    conn.execute(&insert, ()).unwrap();
-    // A temporary directory, to be removed at the end of the test.
+    let table_name = "QueryFastDecimals";
+    let mut table = TableMssql::new(table_name, &["DECIMAL(5,2)", "DECIMAL(20,0)"]);
+    table.insert_rows_as_text(&[["1.50", "12345678901234567"]]);
     let out_dir = tempdir().unwrap();
-    // The name of the output parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
     let out_path = out_dir.path().join("out.par");
-    // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
-    let query = format!("SELECT a FROM {table_name};");
+    let query = format!("SELECT a, b FROM {table_name};");
 
     Command::cargo_bin("odbc2parquet")
         .unwrap()
@@ -849,40 +918,42 @@ fn query_timestamp_ms_with_timezone_mssql() {
             out_str,
             "--connection-string",
             MSSQL,
+            "--fast-decimals",
             &query,
         ])
         .assert()
-        .success();
+        .success()
+        .stderr(contains(
+            "Column 'b' has a precision of 20 digits and is fetched as DOUBLE.",
+        ));
 
-    let expected_values = "{a: 2022-09-07 14:04:12 +00:00}\n";
+    parquet_schema_out(out_str).stdout(contains("OPTIONAL DOUBLE a;\n  OPTIONAL DOUBLE b;"));
+    // Precision beyond 15 to 17 significant digits is lost.
+    let expected_values = "{a: 1.5, b: 12345678901234568.0}\n";
     parquet_read_out(out_str).stdout(eq(expected_values));
-
-    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT64 a (TIMESTAMP(MILLIS,true));"));
 }
 
 #[test]
-fn query_time_mssql() {
+fn query_numeric_decimals_mssql() {
     // Setup table for test
-    let table_name = "QueryTime";
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["TIME"]).unwrap();
-    let insert = format!(
-        "INSERT INTO {table_name}
-        (a)
-        VALUES
-        ('16:04:12');"
+    let table_name = "QueryNumericDecimals";
+    let mut table = TableMssql::new(
+        table_name,
+        &["DECIMAL(5,2)", "DECIMAL(15,3)", "DECIMAL(30,4)"],
     );
-    conn.execute(&insert, ()).unwrap();
-    // A temporary directory, to be removed at the end of the test.
+    table.insert_rows_as_text(&[
+        [
+            "-1.50",
+            "123456789012.345",
+            "-12345678901234567890123456.7890",
+        ],
+        ["999.99", "0.001", "0.0001"],
+    ]);
+    table.insert_rows_as_text(&[[None::<&str>, None, None]]);
     let out_dir = tempdir().unwrap();
-    // The name of the output parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
     let out_path = out_dir.path().join("out.par");
-    // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
-    let query = format!("SELECT a FROM {table_name};");
+    let query = format!("SELECT a, b, c FROM {table_name} ORDER BY id;");
 
     Command::cargo_bin("odbc2parquet")
         .unwrap()
@@ -892,40 +963,41 @@ fn query_time_mssql() {
             out_str,
             "--connection-string",
             MSSQL,
+            "--numeric-decimals",
             &query,
         ])
         .assert()
         .success();
 
-    let expected_values = "{a: 57852000000000}\n";
+    parquet_schema_out(out_str).stdout(contains(
+        "OPTIONAL INT32 a (DECIMAL(5,2));\n  OPTIONAL INT64 b (DECIMAL(15,3));\n  \
+        OPTIONAL FIXED_LEN_BYTE_ARRAY (13) c (DECIMAL(30,4));",
+    ));
+    let expected_values = "\
+        {a: -1.50, b: 123456789012.345, c: -12345678901234567890123456.7890}\n\
+        {a: 999.99, b: 0.001, c: 0.0001}\n\
+        {a: null, b: null, c: null}\n\
+    ";
     parquet_read_out(out_str).stdout(eq(expected_values));
-
-    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT64 a (TIME(NANOS,false));"));
 }
 
 #[test]
-fn query_time_0_mssql() {
+fn round_trip_check_passes() {
     // Setup table for test
-    let table_name = "QueryTime0";
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["TIME(0)"]).unwrap();
-    let insert = format!(
-        "INSERT INTO {table_name}
-        (a)
-        VALUES
-        ('16:04:12');"
+    let table_name = "RoundTripCheckPasses";
+    let mut table = TableMssql::new(
+        table_name,
+        &["INTEGER", "VARCHAR(20)", "DECIMAL(5,2)", "DATETIME2(3)"],
     );
-    conn.execute(&insert, ()).unwrap();
-    // A temporary directory, to be removed at the end of the test.
+    table.insert_rows_as_text(&[
+        ["42", "Hello", "-1.50", "2022-09-07 16:04:12.123"],
+        ["7", "World", "123.45", "1970-01-01 00:00:00.000"],
+    ]);
+    table.insert_rows_as_text(&[[None::<&str>, None, None, None]]);
     let out_dir = tempdir().unwrap();
-    // The name of the output parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
     let out_path = out_dir.path().join("out.par");
-    // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
-    let query = format!("SELECT a FROM {table_name};");
+    let query = format!("SELECT a, b, c, d FROM {table_name} ORDER BY id;");
 
     Command::cargo_bin("odbc2parquet")
         .unwrap()
@@ -935,41 +1007,28 @@ fn query_time_0_mssql() {
             out_str,
             "--connection-string",
             MSSQL,
+            "--round-trip-check",
+            "10",
             &query,
         ])
         .assert()
-        .success();
-
-    // We can not use parquet read, as it uses the record API, which does not allow for the TIME type.
-    // let expected_values = "{a: 57852000000000}\n";
-    // parquet_read_out(out_str).stdout(eq(expected_values));
-
-    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT32 a (TIME(MILLIS,false));"));
+        .success()
+        .stderr(contains("Round trip check passed for the first 3 rows."));
 }
 
 #[test]
-fn query_timestamp_with_timezone_postgres() {
+fn null_fill_per_column() {
     // Setup table for test
-    let table_name = "QueryTimestampWithTimezone";
-    let conn = ENV
-        .connect_with_connection_string(POSTGRES, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_pg(&conn, table_name, &["TIMESTAMPTZ"]).unwrap();
-    let insert = format!(
-        "INSERT INTO {table_name}
-        (a)
-        VALUES
-        ('2022-09-07 16:04:12 +02:00');"
-    );
-    conn.execute(&insert, ()).unwrap();
-    // A temporary directory, to be removed at the end of the test.
+    let table_name = "NullFillPerColumn";
+    let mut table = TableMssql::new(table_name, &["INTEGER", "VARCHAR(10)", "DATE"]);
+    table.insert_rows_as_text(&[
+        [Some("42"), Some("A"), Some("2024-02-29")],
+        [None, None, None],
+    ]);
     let out_dir = tempdir().unwrap();
-    // The name of the output parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
     let out_path = out_dir.path().join("out.par");
-    // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
-    let query = format!("SELECT a FROM {table_name};");
+    let query = format!("SELECT a, b, c FROM {table_name} ORDER BY id;");
 
     Command::cargo_bin("odbc2parquet")
         .unwrap()
@@ -978,33 +1037,87 @@ fn query_timestamp_with_timezone_postgres() {
             "query",
             out_str,
             "--connection-string",
-            POSTGRES,
+            MSSQL,
+            "--null-fill",
+            "a=0",
+            "--null-fill",
+            "b=UNKNOWN",
             &query,
         ])
         .assert()
         .success();
 
-    let expected_values = "{a: 2022-09-07 14:04:12 +00:00}\n";
+    let expected_values = "{a: 42, b: \"A\", c: 2024-02-29}\n{a: 0, b: \"UNKNOWN\", c: null}\n";
     parquet_read_out(out_str).stdout(eq(expected_values));
+    parquet_schema_out(out_str).stdout(contains("REQUIRED INT32 a;"));
+}
 
-    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT64 a (TIMESTAMP(MICROS,false));"));
+#[test]
+fn null_fill_value_must_match_column_type() {
+    // Setup table for test
+    let table_name = "NullFillValueMustMatchColumnType";
+    TableMssql::new(table_name, &["DATE"]);
+    let query = format!("SELECT a FROM {table_name};");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "query",
+            "-",
+            "--connection-string",
+            MSSQL,
+            "--null-fill",
+            "a=epoch",
+            &query,
+        ])
+        .assert()
+        .failure()
+        .stderr(contains("Invalid fill value for column 'a'."));
 }
 
 #[test]
-fn query_timestamp_postgres() {
+fn emit_ddl_matching_output() {
     // Setup table for test
-    let table_name = "QueryTimestamp";
-    let conn = ENV
-        .connect_with_connection_string(POSTGRES, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_pg(&conn, table_name, &["TIMESTAMP"]).unwrap();
-    let insert = format!(
-        "INSERT INTO {table_name}
-        (a)
-        VALUES
-        ('2022-09-07 16:04:12');"
+    let table_name = "EmitDdlMatchingOutput";
+    TableMssql::new(
+        table_name,
+        &["INTEGER NOT NULL", "DECIMAL(10,2)", "DATETIME2(3)"],
     );
-    conn.execute(&insert, ()).unwrap();
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let ddl_path = out_dir.path().join("create.sql");
+    let query = format!("SELECT a, b, c FROM {table_name};");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "query",
+            out_str,
+            "--connection-string",
+            MSSQL,
+            "--emit-ddl",
+            ddl_path.to_str().unwrap(),
+            "--table-name",
+            "sales",
+            &query,
+        ])
+        .assert()
+        .success();
+
+    let expected = "CREATE EXTERNAL TABLE `sales` (\n  `a` INT,\n  `b` DECIMAL(10,2),\n  \
+        `c` TIMESTAMP\n)\nSTORED AS PARQUET\nLOCATION '<location>';\n";
+    assert_eq!(expected, std::fs::read_to_string(ddl_path).unwrap());
+}
+
+#[test]
+fn query_unsigned_tinyint() {
+    // Setup table for test
+    let table_name = "QueryUnsignedTinyInt";
+    let mut table = TableMssql::new(table_name, &["TINYINT"]);
+    table.insert_rows_as_text(&[["255"]]);
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
     // The name of the output parquet file we are going to write. Since it is in a temporary
@@ -1021,67 +1134,50 @@ fn query_timestamp_postgres() {
             "query",
             out_str,
             "--connection-string",
-            POSTGRES,
+            MSSQL,
             &query,
         ])
         .assert()
         .success();
 
-    let expected_values = "{a: 2022-09-07 16:04:12 +00:00}\n";
+    let expected_values = "{a: 255}\n";
     parquet_read_out(out_str).stdout(eq(expected_values));
 
-    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT64 a (TIMESTAMP(MICROS,false));"));
+    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT32 a (INTEGER(8,false));"));
 }
 
 #[test]
-fn query_all_the_types() {
+fn column_order_of_unsigned_tinyint() {
     // Setup table for test
-    let table_name = "AllTheTypes";
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_mssql(
-        &conn,
-        table_name,
-        &[
-            "CHAR(5) NOT NULL",
-            "NUMERIC(3,2) NOT NULL",
-            "DECIMAL(3,2) NOT NULL",
-            "INTEGER NOT NULL",
-            "SMALLINT NOT NULL",
-            "FLOAT(3) NOT NULL",
-            "REAL NOT NULL",
-            "DOUBLE PRECISION NOT NULL",
-            "VARCHAR(100) NOT NULL",
-            "DATE NOT NULL",
-            "TIME NOT NULL",
-            "DATETIME NOT NULL",
+    let table_name = "ColumnOrderOfUnsignedTinyInt";
+    let mut table = TableMssql::new(table_name, &["TINYINT", "INTEGER"]);
+    table.insert_rows_as_text(&[["255", "-1"]]);
+    let query = format!("SELECT a, b FROM {table_name};");
+
+    let cmd = Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args(["query", "--connection-string", MSSQL, "-", &query])
+        .assert()
+        .success();
+
+    let bytes = Bytes::from(cmd.get_output().stdout.clone());
+    let reader = SerializedFileReader::new(bytes).unwrap();
+    let column_orders = reader.metadata().file_metadata().column_orders().unwrap();
+    assert_eq!(
+        &vec![
+            ColumnOrder::TYPE_DEFINED_ORDER(SortOrder::UNSIGNED),
+            ColumnOrder::TYPE_DEFINED_ORDER(SortOrder::SIGNED)
         ],
-    )
-    .unwrap();
-    let insert = format!(
-        "INSERT INTO {table_name}
-        (a,b,c,d,e,f,g,h,i,j,k,l)
-        VALUES
-        ('abcde', 1.23, 1.23, 42, 42, 1.23, 1.23, 1.23, 'Hello, World!', '2020-09-16', '03:54:12', '2020-09-16 03:54:12');"
+        column_orders
     );
-    conn.execute(&insert, ()).unwrap();
-
-    let expected_values = "{\
-        a: \"abcde\", \
-        b: 1.23, \
-        c: 1.23, \
-        d: 42, \
-        e: 42, \
-        f: 1.23, \
-        g: 1.23, \
-        h: 1.23, \
-        i: \"Hello, World!\", \
-        j: 2020-09-16, \
-        k: 14052000000000, \
-        l: 2020-09-16 03:54:12 +00:00\
-    }\n";
+}
 
+#[test]
+fn should_error_if_timestamp_is_out_of_range() {
+    // Setup table for test
+    let table_name = "ShouldErrorIfTimestampIsOutOfRange";
+    let mut table = TableMssql::new(table_name, &["DATETIME2(7)"]);
+    table.insert_rows_as_text(&[["2700-01-01 00:00:00"]]);
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
     // The name of the output parquet file we are going to write. Since it is in a temporary
@@ -1089,8 +1185,7 @@ fn query_all_the_types() {
     let out_path = out_dir.path().join("out.par");
     // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
-
-    let query = format!("SELECT a,b,c,d,e,f,g,h,i,j,k,l FROM {table_name};");
+    let query = format!("SELECT a FROM {table_name};");
 
     Command::cargo_bin("odbc2parquet")
         .unwrap()
@@ -1103,35 +1198,23 @@ fn query_all_the_types() {
             &query,
         ])
         .assert()
-        .success();
-
-    parquet_read_out(out_str).stdout(eq(expected_values));
+        .failure()
+        .stderr(contains(
+            "Invalid timestamp: 2700-01-01 00:00:00. The valid range for timestamps with \
+            nano seconds precision is between 1677-09-21 00:12:44 and 2262-04-11 \
+            23:47:16.854775807. Other timestamps can not be represented in parquet. To mitigate \
+            this you could downcast the precision in the query or convert the column to text.",
+        ));
 }
 
+/// Out of range timestamps are written as NULL with `--skip-bad-rows`, rather than failing the
+/// export.
 #[test]
-fn query_bits() {
+fn skip_bad_rows_writes_null_for_timestamp_out_of_range() {
     // Setup table for test
-    let table_name = "QueryBits";
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["BIT"]).unwrap();
-    let insert = format!(
-        "INSERT INTO {table_name}
-        (a)
-        VALUES
-        (0), (1), (NULL), (1), (0);"
-    );
-    conn.execute(&insert, ()).unwrap();
-
-    let expected_values = "\
-        {a: false}\n\
-        {a: true}\n\
-        {a: null}\n\
-        {a: true}\n\
-        {a: false}\n\
-    ";
-
+    let table_name = "SkipBadRowsWritesNullForTimestampOutOfRange";
+    let mut table = TableMssql::new(table_name, &["DATETIME2(7)"]);
+    table.insert_rows_as_text(&[["2020-01-01 00:00:00"], ["2700-01-01 00:00:00"]]);
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
     // The name of the output parquet file we are going to write. Since it is in a temporary
@@ -1139,132 +1222,113 @@ fn query_bits() {
     let out_path = out_dir.path().join("out.par");
     // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
-
-    let query = format!("SELECT a FROM {table_name};");
+    let query = format!("SELECT a FROM {table_name} ORDER BY id;");
 
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
-            "-vvvv",
             "query",
             out_str,
             "--connection-string",
             MSSQL,
+            "--skip-bad-rows",
             &query,
         ])
         .assert()
-        .success();
+        .success()
+        .stderr(contains(
+            "1 values in column 'a' could not be converted and have been written as NULL.",
+        ));
 
+    let expected_values = "{a: 2020-01-01 00:00:00 +00:00}\n{a: null}\n";
     parquet_read_out(out_str).stdout(eq(expected_values));
 }
 
+/// With `--bad-row-action drop` rows holding values which can not be converted are left out
+/// entirely, even if the column is not nullable.
 #[test]
-fn query_doubles() {
+fn skip_bad_rows_drops_rows_with_timestamp_out_of_range() {
     // Setup table for test
-    let table_name = "QueryDoubles";
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["DOUBLE PRECISION NOT NULL"]).unwrap();
-    let insert = format!(
-        "INSERT INTO {table_name}
-        (a)
-        VALUES
-        (0.1), (2.3);"
-    );
-    conn.execute(&insert, ()).unwrap();
-
-    let expected_values = "\
-        {a: 0.1}\n\
-        {a: 2.3}\n\
-    ";
-
-    let expected_schema = "REQUIRED DOUBLE a;";
-
+    let table_name = "SkipBadRowsDropsRowsWithTimestampOutOfRange";
+    let mut table = TableMssql::new(table_name, &["DATETIME2(7) NOT NULL", "INTEGER"]);
+    table.insert_rows_as_text(&[
+        ["2020-01-01 00:00:00", "1"],
+        ["2700-01-01 00:00:00", "2"],
+        ["2021-01-01 00:00:00", "3"],
+    ]);
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
-    // The name of the output parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
     let out_path = out_dir.path().join("out.par");
-    // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
-
-    let query = format!("SELECT a FROM {table_name};");
+    let query = format!("SELECT a, b FROM {table_name} ORDER BY id;");
 
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
-            "-vvvv",
             "query",
             out_str,
             "--connection-string",
             MSSQL,
+            "--skip-bad-rows",
+            "--bad-row-action",
+            "drop",
             &query,
         ])
         .assert()
-        .success();
+        .success()
+        .stderr(contains(
+            "1 rows have been dropped, since they hold values which could not be converted.",
+        ));
 
+    let expected_values = "{a: 2020-01-01 00:00:00 +00:00, b: 1}\n\
+        {a: 2021-01-01 00:00:00 +00:00, b: 3}\n";
     parquet_read_out(out_str).stdout(eq(expected_values));
-
-    // Also verify schema to ensure f64 is chosen and not f32
-    parquet_schema_out(out_str).stdout(contains(expected_schema));
 }
 
-/// Should not create a file if the query comes back empty `--no-empty-file` is set.
+/// Values written as NULL by `--skip-bad-rows` are recorded in the bad rows file.
 #[test]
-fn query_comes_back_with_no_rows() {
+fn bad_rows_file() {
     // Setup table for test
-    let table_name = "QueryComesBackWithNoRows";
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["DOUBLE PRECISION NOT NULL"]).unwrap();
-
+    let table_name = "BadRowsFile";
+    let mut table = TableMssql::new(table_name, &["DATETIME2(7)"]);
+    table.insert_rows_as_text(&[["2020-01-01 00:00:00"], ["2700-01-01 00:00:00"]]);
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
-    // The name of the output parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
     let out_path = out_dir.path().join("out.par");
-    // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
-
-    let query = format!("SELECT a FROM {table_name};");
+    let bad_rows_path = out_dir.path().join("bad_rows.jsonl");
+    let query = format!("SELECT a FROM {table_name} ORDER BY id;");
 
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
-            "-vvvv",
             "query",
+            out_str,
             "--connection-string",
             MSSQL,
-            "--no-empty-file",
-            out_str,
+            "--skip-bad-rows",
+            "--bad-rows-file",
+            bad_rows_path.to_str().unwrap(),
             &query,
         ])
         .assert()
         .success();
 
-    assert_eq!(
-        ErrorKind::NotFound,
-        File::open(out_str).err().unwrap().kind()
-    );
+    let bad_rows = std::fs::read_to_string(bad_rows_path).unwrap();
+    assert_eq!(1, bad_rows.lines().count());
+    assert!(bad_rows.starts_with(
+        "{\"batch\":1,\"row\":1,\"column\":\"a\",\"hex\":null,\"text\":null,\
+        \"error\":\"Invalid timestamp: 2700-01-01 00:00:00."
+    ));
 }
 
-/// Should not create a file if the query comes back empty `--no-empty-file` is set. This should
-/// still work, even in the presence of (potential) file splitting.
-///
-/// This test has been created after a bug report. Original implementation of `--no-empty-file`
-/// deleted the file after the fact. Yet it tried to delete the base path without taking the suffix
-/// from file splitting into account.
 #[test]
-fn no_empty_file_works_with_split_files() {
+fn should_correctly_fetch_upper_bound_timestamp() {
     // Setup table for test
-    let table_name = "NoEmptyFileWorksWithSplitFiles";
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["DOUBLE PRECISION NOT NULL"]).unwrap();
-
+    let table_name = "ShouldCorrectlyFetchUpperBoundTimestamp";
+    let mut table = TableMssql::new(table_name, &["DATETIME2(7)"]);
+    // Due to precision the last 7 nanoseconds are excluded from the upper bound.
+    table.insert_rows_as_text(&[["2262-04-11 23:47:16.854775807"]]);
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
     // The name of the output parquet file we are going to write. Since it is in a temporary
@@ -1272,7 +1336,6 @@ fn no_empty_file_works_with_split_files() {
     let out_path = out_dir.path().join("out.par");
     // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
-
     let query = format!("SELECT a FROM {table_name};");
 
     Command::cargo_bin("odbc2parquet")
@@ -1280,27 +1343,27 @@ fn no_empty_file_works_with_split_files() {
         .args([
             "-vvvv",
             "query",
+            out_str,
             "--connection-string",
             MSSQL,
-            "--row-groups-per-file",
-            "1",
-            "--no-empty-file",
-            out_str,
             &query,
         ])
         .assert()
         .success();
 
-    assert_eq!(
-        ErrorKind::NotFound,
-        File::open(out_str).err().unwrap().kind()
-    );
+    // Binary of the expectation is all `1`, except for the first (sign) and the last three digits
+    // (precision of MsSQL is not nanoseconds, but 7 digits).
+    let expected_values = "{a: 9223372036854775800}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
 }
 
-/// If the query does not come back empty, of course a file should be created, even if the
-/// `--no-empty-file` flag is set.
 #[test]
-fn emit_file_despite_no_empty_file_set() {
+fn should_correctly_fetch_lower_bound_timestamp() {
+    // Setup table for test
+    let table_name = "ShouldCorrectlyFetchLowerBound";
+    let mut table = TableMssql::new(table_name, &["DATETIME2(7)"]);
+    // Due to precision the last 7 nanoseconds are excluded from the lower bound, so we round up
+    table.insert_rows_as_text(&[["1677-09-21 00:12:44"]]);
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
     // The name of the output parquet file we are going to write. Since it is in a temporary
@@ -1308,83 +1371,86 @@ fn emit_file_despite_no_empty_file_set() {
     let out_path = out_dir.path().join("out.par");
     // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
-
-    let query = "SELECT 42 as a";
+    let query = format!("SELECT a FROM {table_name};");
 
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
             "-vvvv",
             "query",
+            out_str,
             "--connection-string",
             MSSQL,
-            "--no-empty-file",
-            out_str,
-            query,
+            &query,
         ])
         .assert()
         .success();
 
-    let expected = "{a: 42}\n";
-    parquet_read_out(out_str).stdout(eq(expected));
+    // Binary of the expectation is all `1`, execpt for the first (sign) and the last three digits
+    // (precision of MsSQL is not nano seconds, but 7 digits).
+    let expected_values = "{a: -9223372036000000000}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
 }
 
-/// Should read query from standard input if "-" is provided as query text.
 #[test]
-fn read_query_from_stdin() {
+fn query_timestamp_ms_with_timezone_mssql() {
     // Setup table for test
-    let table_name = "ReadQueryFromStdin";
+    let table_name = "QueryTimestampMsWithTimezone";
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["INT"]).unwrap();
+    // ODBC data type: SqlDataType(-155), decimal_digits: 3
+    setup_empty_table_mssql(&conn, table_name, &["DATETIMEOFFSET(3)"]).unwrap();
     let insert = format!(
         "INSERT INTO {table_name}
         (a)
         VALUES
-        (54),
-        (54),
-        (34),
-        (12);"
+        ('2022-09-07 16:04:12 +02:00');"
     );
     conn.execute(&insert, ()).unwrap();
-
-    let expected_values = "\
-        {a: 54}\n\
-        {a: 54}\n\
-        {a: 34}\n\
-        {a: 12}\n\
-    ";
-    let query = format!("SELECT a FROM {table_name} ORDER BY id");
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
     // The name of the output parquet file we are going to write. Since it is in a temporary
     // directory it will not outlive the end of the test.
     let out_path = out_dir.path().join("out.par");
     // We need to pass the output path as a string argument.
-    let out_str = out_path.to_str().expect("Tempfile path must be utf8");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a FROM {table_name};");
 
     Command::cargo_bin("odbc2parquet")
         .unwrap()
-        .args(["-vvvv", "query", out_str, "--connection-string", MSSQL, "-"])
-        .write_stdin(query)
+        .args([
+            "-vvvv",
+            "query",
+            out_str,
+            "--connection-string",
+            MSSQL,
+            &query,
+        ])
         .assert()
         .success();
 
+    let expected_values = "{a: 2022-09-07 14:04:12 +00:00}\n";
     parquet_read_out(out_str).stdout(eq(expected_values));
+
+    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT64 a (TIMESTAMP(MILLIS,true));"));
 }
 
 #[test]
-fn split_files_on_num_row_groups() {
+fn query_time_mssql() {
     // Setup table for test
-    let table_name = "SplitFilesOnNumRowGroups";
+    let table_name = "QueryTime";
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["INTEGER"]).unwrap();
-    let insert = format!("INSERT INTO {table_name} (A) VALUES(1),(2),(3)");
+    setup_empty_table_mssql(&conn, table_name, &["TIME"]).unwrap();
+    let insert = format!(
+        "INSERT INTO {table_name}
+        (a)
+        VALUES
+        ('16:04:12');"
+    );
     conn.execute(&insert, ()).unwrap();
-
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
     // The name of the output parquet file we are going to write. Since it is in a temporary
@@ -1392,8 +1458,7 @@ fn split_files_on_num_row_groups() {
     let out_path = out_dir.path().join("out.par");
     // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
-
-    let query = format!("SELECT a FROM {table_name}");
+    let query = format!("SELECT a FROM {table_name};");
 
     Command::cargo_bin("odbc2parquet")
         .unwrap()
@@ -1403,45 +1468,39 @@ fn split_files_on_num_row_groups() {
             out_str,
             "--connection-string",
             MSSQL,
-            "--batch-size-row",
-            "1",
-            "--row-groups-per-file",
-            "1",
             &query,
         ])
         .assert()
         .success();
 
-    // Expect one file per row in table (3)
+    let expected_values = "{a: 57852000000000}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
 
-    parquet_read_out(out_dir.path().join("out_01.par").to_str().unwrap());
-    parquet_read_out(out_dir.path().join("out_02.par").to_str().unwrap());
-    parquet_read_out(out_dir.path().join("out_03.par").to_str().unwrap());
+    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT64 a (TIME(NANOS,false));"));
 }
 
-/// Verify naming of the files is with successive numbers starting from 1 to 3 with split files and
-/// `--no-empty-file` flag set. This was messed up, with a refactoring once and file names started
-/// with `2` instead of `1``.
 #[test]
-fn file_name_no_empty_file_and_split_files() {
+fn query_sql_variant_mssql() {
     // Setup table for test
-    let table_name = "FileNameNoEmptyFileAndSplitFiles";
+    let table_name = "QuerySqlVariant";
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["INTEGER"]).unwrap();
-    let insert = format!("INSERT INTO {table_name} (A) VALUES(1),(2),(3)");
+    setup_empty_table_mssql(&conn, table_name, &["SQL_VARIANT"]).unwrap();
+    let insert = format!(
+        "INSERT INTO {table_name}
+        (a)
+        VALUES
+        (CAST(42 AS INT)),
+        (CAST('Hello' AS NVARCHAR(10))),
+        (CAST('2022-09-07' AS DATE));"
+    );
     conn.execute(&insert, ()).unwrap();
-
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
-    // The name of the output parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
     let out_path = out_dir.path().join("out.par");
-    // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
-
-    let query = format!("SELECT a FROM {table_name}");
+    let query = format!("SELECT a FROM {table_name} ORDER BY id;");
 
     Command::cargo_bin("odbc2parquet")
         .unwrap()
@@ -1451,43 +1510,32 @@ fn file_name_no_empty_file_and_split_files() {
             out_str,
             "--connection-string",
             MSSQL,
-            "--no-empty-file",
-            "--batch-size-row",
-            "1",
-            "--row-groups-per-file",
-            "1",
             &query,
         ])
         .assert()
         .success();
 
-    // Expect one file per row in table (3)
+    let expected_values = "{a: \"42\"}\n{a: \"Hello\"}\n{a: \"2022-09-07\"}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
 
-    parquet_read_out(out_dir.path().join("out_01.par").to_str().unwrap());
-    parquet_read_out(out_dir.path().join("out_02.par").to_str().unwrap());
-    parquet_read_out(out_dir.path().join("out_03.par").to_str().unwrap());
+    parquet_schema_out(out_str).stdout(contains("OPTIONAL BYTE_ARRAY a (STRING);"));
 }
 
 #[test]
-fn split_files_on_size_limit() {
+fn char_blank_as_null() {
     // Setup table for test
-    let table_name = "SplitFilesOnSizeLimit";
+    let table_name = "CharBlankAsNull";
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["INTEGER"]).unwrap();
-    let insert = format!("INSERT INTO {table_name} (A) VALUES(1),(2),(3)");
+    setup_empty_table_mssql(&conn, table_name, &["CHAR(5)"]).unwrap();
+    let insert = format!("INSERT INTO {table_name} (a) VALUES ('   '), (''), (NULL), ('a');");
     conn.execute(&insert, ()).unwrap();
-
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
-    // The name of the output parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
     let out_path = out_dir.path().join("out.par");
-    // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
-
-    let query = format!("SELECT a FROM {table_name}");
+    let query = format!("SELECT a FROM {table_name} ORDER BY id;");
 
     Command::cargo_bin("odbc2parquet")
         .unwrap()
@@ -1497,42 +1545,32 @@ fn split_files_on_size_limit() {
             out_str,
             "--connection-string",
             MSSQL,
-            "--batch-size-row",
-            "1",
-            "--file-size-threshold",
-            "1B",
+            "--char-blank-as-null",
             &query,
         ])
         .assert()
         .success();
 
-    // Expect one file per row in table (3)
-
-    parquet_read_out(out_dir.path().join("out_01.par").to_str().unwrap());
-    parquet_read_out(out_dir.path().join("out_02.par").to_str().unwrap());
-    parquet_read_out(out_dir.path().join("out_03.par").to_str().unwrap());
+    let expected_values = "{a: null}\n{a: null}\n{a: null}\n{a: \"a    \"}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
 }
 
 #[test]
-fn configurable_suffix_length() {
+fn null_string_tokens() {
     // Setup table for test
-    let table_name = "ConfigurableSuffixLength";
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["INTEGER"]).unwrap();
-    let insert = format!("INSERT INTO {table_name} (A) VALUES(1)");
-    conn.execute(&insert, ()).unwrap();
-
+    let table_name = "NullStringTokens";
+    let mut table = TableMssql::new(table_name, &["VARCHAR(10)", "NVARCHAR(10)"]);
+    table.insert_rows_as_text(&[
+        ["NULL", "n/a"],
+        ["null", "N/A"],
+        [" NULL", "\\N"],
+        ["a", "b"],
+    ]);
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
-    // The name of the output parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
     let out_path = out_dir.path().join("out.par");
-    // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
-
-    let query = format!("SELECT a FROM {table_name}");
+    let query = format!("SELECT a, b FROM {table_name} ORDER BY id;");
 
     Command::cargo_bin("odbc2parquet")
         .unwrap()
@@ -1542,47 +1580,16 @@ fn configurable_suffix_length() {
             out_str,
             "--connection-string",
             MSSQL,
-            "--batch-size-row",
-            "1",
-            "--file-size-threshold",
-            "1B",
-            "--suffix-length",
-            "4",
+            "--null-string-tokens",
+            "NULL,N/A,\\N",
             &query,
         ])
         .assert()
         .success();
 
-    // Expect one file per row in table (3)
-
-    parquet_read_out(out_dir.path().join("out_0001.par").to_str().unwrap());
-}
-
-#[test]
-fn varbinary_column() {
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-
-    setup_empty_table_mssql(&conn, "VarbinaryColumn", &["VARBINARY(10)"]).unwrap();
-    conn.execute(
-        "INSERT INTO VarbinaryColumn (a) Values \
-        (CONVERT(Binary(5), 'Hello')),\
-        (CONVERT(Binary(5), 'World')),\
-        (NULL)",
-        (),
-    )
-    .unwrap();
-
-    // A temporary directory, to be removed at the end of the test.
-    let out_dir = tempdir().unwrap();
-    // The name of the output parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
-    let out_path = out_dir.path().join("out.par");
-    // We need to pass the output path as a string argument.
-    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
-
-    let query = "SELECT a FROM VarbinaryColumn;";
+    let expected_values = "{a: null, b: \"n/a\"}\n{a: \"null\", b: null}\n\
+        {a: \" NULL\", b: null}\n{a: \"a\", b: \"b\"}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
 
     Command::cargo_bin("odbc2parquet")
         .unwrap()
@@ -1592,123 +1599,107 @@ fn varbinary_column() {
             out_str,
             "--connection-string",
             MSSQL,
-            query,
+            "--null-string-tokens",
+            "NULL,N/A",
+            "--null-string-tokens-ignore-case",
+            &query,
         ])
         .assert()
         .success();
 
-    let expected = "{a: [72, 101, 108, 108, 111]}\n{a: [87, 111, 114, 108, 100]}\n{a: null}\n";
-
-    parquet_read_out(out_str).stdout(eq(expected));
+    let expected_values = "{a: null, b: null}\n{a: null, b: null}\n\
+        {a: \" NULL\", b: \"\\N\"}\n{a: \"a\", b: \"b\"}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
 }
 
-/// Since VARCHARMAX reports a size of 0. odbc2parquet should detect this and give the user an error
-/// instead.
 #[test]
-fn query_varchar_max() {
+fn skip_columns_on_error() {
+    // Setup table for test
+    let table_name = "SkipColumnsOnError";
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    let table_name = "QueryVarcharMax";
-
-    setup_empty_table_mssql(&conn, table_name, &["VARCHAR(MAX)"]).unwrap();
-    conn.execute(
-        &format!("INSERT INTO {table_name} (a) Values ('Hello'), ('World');"),
-        (),
-    )
-    .unwrap();
-
+    setup_empty_table_mssql(&conn, table_name, &["INTEGER", "VARCHAR(MAX)", "INTEGER"]).unwrap();
+    let insert = format!("INSERT INTO {table_name} (a, b, c) VALUES (1, 'Hello', 2);");
+    conn.execute(&insert, ()).unwrap();
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
-    // The name of the output parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
     let out_path = out_dir.path().join("out.par");
-    // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a, b, c FROM {table_name};");
 
-    let query = format!("SELECT a FROM {table_name};");
-
-    // VARCHAR(max) has size 0. => Column is ignored and file would be empty and schemaless
+    // Without an upper bound for `b` the export fails by default. We skip the column instead.
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
-            "-vvvv",
             "query",
+            out_str,
             "--connection-string",
             MSSQL,
-            out_str,
+            "--skip-columns-on-error",
             &query,
         ])
         .assert()
-        .failure()
-        .stderr(contains(
-            "Column 'a' with index 1. Driver reported a display length of 0. This can happen for \
-        variadic types without a fixed upper bound. You can manually specify an upper bound for \
-        variadic columns using the `--column-length-limit` command line argument.",
-        ));
+        .success()
+        .stderr(contains("1 columns have been skipped, due to errors: 'b'"));
+
+    let expected_values = "{a: 1, c: 2}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
 }
 
-/// Since VARCHARMAX reports a size of 0, it will be ignored, resulting in an output file with no
-/// columns. Yet by setting a size limit we can make it work.
 #[test]
-fn query_varchar_max_with_column_length_limit() {
+fn execute_query_for_each_line_of_parameter_file() {
+    // Setup table for test
+    let table_name = "ExecuteQueryForEachLineOfParameterFile";
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    let table_name = "QueryVarcharMaxWithColumnLengthLimit";
-
-    setup_empty_table_mssql(&conn, table_name, &["VARCHAR(MAX)"]).unwrap();
-    conn.execute(
-        &format!("INSERT INTO {table_name} (a) Values ('Hello'), ('World');"),
-        (),
-    )
-    .unwrap();
-
+    setup_empty_table_mssql(&conn, table_name, &["INTEGER", "VARCHAR(10)"]).unwrap();
+    let insert =
+        format!("INSERT INTO {table_name} (a, b) VALUES (1, 'one'), (2, 'two'), (3, 'three');");
+    conn.execute(&insert, ()).unwrap();
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
-    // The name of the output parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
     let out_path = out_dir.path().join("out.par");
-    // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let parameter_path = out_dir.path().join("parameters.csv");
+    std::fs::write(&parameter_path, "3\n1\n").unwrap();
+    let query = format!("SELECT b FROM {table_name} WHERE a = ?;");
 
-    let query = format!("SELECT a FROM {table_name};");
-
-    // VARCHAR(max) has size 0. => Column is ignored and file would be empty and schemaless
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
-            "-vvvv",
             "query",
+            out_str,
             "--connection-string",
             MSSQL,
-            "--column-length-limit",
-            "4096",
-            out_str,
+            "--parameter-file",
+            parameter_path.to_str().unwrap(),
+            "--parameter-columns",
             &query,
         ])
         .assert()
         .success();
+
+    let expected_values = "{b: \"three\", parameter_1: \"3\"}\n{b: \"one\", parameter_1: \"1\"}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
 }
 
-/// Introduced after discovering a bug, that columns were not ignored on windows.
-///
-/// Since VARCHARMAX reports a size of 0, it will be ignored, resulting in an output file with no
-/// columns. Yet odbc2parquet should detect this and give the user an error instead.
 #[test]
-fn query_varchar_max_utf16() {
+fn query_time_0_mssql() {
+    // Setup table for test
+    let table_name = "QueryTime0";
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    let table_name = "QueryVarcharMaxUtf16";
-
-    setup_empty_table_mssql(&conn, table_name, &["VARCHAR(MAX)"]).unwrap();
-    conn.execute(
-        &format!("INSERT INTO {table_name} (a) Values ('Hello'), ('World');"),
-        (),
-    )
-    .unwrap();
-
+    setup_empty_table_mssql(&conn, table_name, &["TIME(0)"]).unwrap();
+    let insert = format!(
+        "INSERT INTO {table_name}
+        (a)
+        VALUES
+        ('16:04:12');"
+    );
+    conn.execute(&insert, ()).unwrap();
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
     // The name of the output parquet file we are going to write. Since it is in a temporary
@@ -1716,42 +1707,43 @@ fn query_varchar_max_utf16() {
     let out_path = out_dir.path().join("out.par");
     // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
-
     let query = format!("SELECT a FROM {table_name};");
 
-    // VARCHAR(max) has size 0. => Column is ignored and file would be empty and schemaless
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
             "-vvvv",
             "query",
-            "--encoding",
-            "Utf16",
+            out_str,
             "--connection-string",
             MSSQL,
-            out_str,
             &query,
         ])
         .assert()
-        .failure();
+        .success();
+
+    // We can not use parquet read, as it uses the record API, which does not allow for the TIME type.
+    // let expected_values = "{a: 57852000000000}\n";
+    // parquet_read_out(out_str).stdout(eq(expected_values));
+
+    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT32 a (TIME(MILLIS,false));"));
 }
 
 #[test]
-fn binary_column() {
+fn query_timestamp_with_timezone_postgres() {
+    // Setup table for test
+    let table_name = "QueryTimestampWithTimezone";
     let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .connect_with_connection_string(POSTGRES, ConnectionOptions::default())
         .unwrap();
-
-    setup_empty_table_mssql(&conn, "BinaryColumn", &["BINARY(5)"]).unwrap();
-    conn.execute(
-        "INSERT INTO BinaryColumn (a) Values \
-        (CONVERT(Binary(5), 'Hello')),\
-        (CONVERT(Binary(5), 'World')),\
-        (NULL)",
-        (),
-    )
-    .unwrap();
-
+    setup_empty_table_pg(&conn, table_name, &["TIMESTAMPTZ"]).unwrap();
+    let insert = format!(
+        "INSERT INTO {table_name}
+        (a)
+        VALUES
+        ('2022-09-07 16:04:12 +02:00');"
+    );
+    conn.execute(&insert, ()).unwrap();
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
     // The name of the output parquet file we are going to write. Since it is in a temporary
@@ -1759,8 +1751,7 @@ fn binary_column() {
     let out_path = out_dir.path().join("out.par");
     // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
-
-    let query = "SELECT a FROM BinaryColumn;";
+    let query = format!("SELECT a FROM {table_name};");
 
     Command::cargo_bin("odbc2parquet")
         .unwrap()
@@ -1769,38 +1760,33 @@ fn binary_column() {
             "query",
             out_str,
             "--connection-string",
-            MSSQL,
-            query,
+            POSTGRES,
+            &query,
         ])
         .assert()
         .success();
 
-    let expected = "{a: [72, 101, 108, 108, 111]}\n{a: [87, 111, 114, 108, 100]}\n{a: null}\n";
-    parquet_read_out(out_str).stdout(eq(expected));
+    let expected_values = "{a: 2022-09-07 14:04:12 +00:00}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
+
+    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT64 a (TIMESTAMP(MICROS,false));"));
 }
 
-/// The prefer-varbinary flag must enforce mapping of binary columns to BYTE_ARRAY instead of
-/// FIXED_LEN_BYTE_ARRAY.
 #[test]
-fn prefer_varbinary() {
+fn query_timestamp_postgres() {
+    // Setup table for test
+    let table_name = "QueryTimestamp";
     let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .connect_with_connection_string(POSTGRES, ConnectionOptions::default())
         .unwrap();
-
-    let table_name = "PreferVarbinary";
-
-    setup_empty_table_mssql(&conn, table_name, &["BINARY(5)"]).unwrap();
-    conn.execute(
-        &format!(
-            "INSERT INTO {table_name} (a) Values \
-        (CONVERT(Binary(5), 'Hello')),\
-        (CONVERT(Binary(5), 'World')),\
-        (NULL)"
-        ),
-        (),
-    )
-    .unwrap();
-
+    setup_empty_table_pg(&conn, table_name, &["TIMESTAMP"]).unwrap();
+    let insert = format!(
+        "INSERT INTO {table_name}
+        (a)
+        VALUES
+        ('2022-09-07 16:04:12');"
+    );
+    conn.execute(&insert, ()).unwrap();
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
     // The name of the output parquet file we are going to write. Since it is in a temporary
@@ -1808,7 +1794,6 @@ fn prefer_varbinary() {
     let out_path = out_dir.path().join("out.par");
     // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
-
     let query = format!("SELECT a FROM {table_name};");
 
     Command::cargo_bin("odbc2parquet")
@@ -1817,33 +1802,67 @@ fn prefer_varbinary() {
             "-vvvv",
             "query",
             out_str,
-            "--prefer-varbinary",
             "--connection-string",
-            MSSQL,
+            POSTGRES,
             &query,
         ])
         .assert()
         .success();
 
-    let expected = "{a: [72, 101, 108, 108, 111]}\n{a: [87, 111, 114, 108, 100]}\n{a: null}\n";
-    parquet_read_out(out_str).stdout(eq(expected));
+    let expected_values = "{a: 2022-09-07 16:04:12 +00:00}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
 
-    parquet_schema_out(out_str).stdout(contains("OPTIONAL BYTE_ARRAY a;"));
+    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT64 a (TIMESTAMP(MICROS,false));"));
 }
 
-/// Strings with interior nuls should be written into parquet file as they are.
 #[test]
-fn interior_nul_in_varchar() {
+fn query_all_the_types() {
+    // Setup table for test
+    let table_name = "AllTheTypes";
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, "InteriorNul", &["VARCHAR(10)"]).unwrap();
-
-    conn.execute(
-        "INSERT INTO InteriorNul (a) VALUES (?);",
-        &"a\0b".into_parameter(),
+    setup_empty_table_mssql(
+        &conn,
+        table_name,
+        &[
+            "CHAR(5) NOT NULL",
+            "NUMERIC(3,2) NOT NULL",
+            "DECIMAL(3,2) NOT NULL",
+            "INTEGER NOT NULL",
+            "SMALLINT NOT NULL",
+            "FLOAT(3) NOT NULL",
+            "REAL NOT NULL",
+            "DOUBLE PRECISION NOT NULL",
+            "VARCHAR(100) NOT NULL",
+            "DATE NOT NULL",
+            "TIME NOT NULL",
+            "DATETIME NOT NULL",
+        ],
     )
     .unwrap();
+    let insert = format!(
+        "INSERT INTO {table_name}
+        (a,b,c,d,e,f,g,h,i,j,k,l)
+        VALUES
+        ('abcde', 1.23, 1.23, 42, 42, 1.23, 1.23, 1.23, 'Hello, World!', '2020-09-16', '03:54:12', '2020-09-16 03:54:12');"
+    );
+    conn.execute(&insert, ()).unwrap();
+
+    let expected_values = "{\
+        a: \"abcde\", \
+        b: 1.23, \
+        c: 1.23, \
+        d: 42, \
+        e: 42, \
+        f: 1.23, \
+        g: 1.23, \
+        h: 1.23, \
+        i: \"Hello, World!\", \
+        j: 2020-09-16, \
+        k: 14052000000000, \
+        l: 2020-09-16 03:54:12 +00:00\
+    }\n";
 
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
@@ -1853,7 +1872,7 @@ fn interior_nul_in_varchar() {
     // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    let query = "SELECT a FROM InteriorNul;";
+    let query = format!("SELECT a,b,c,d,e,f,g,h,i,j,k,l FROM {table_name};");
 
     Command::cargo_bin("odbc2parquet")
         .unwrap()
@@ -1863,31 +1882,37 @@ fn interior_nul_in_varchar() {
             out_str,
             "--connection-string",
             MSSQL,
-            query,
+            &query,
         ])
         .assert()
         .success();
 
-    let expected = "{a: \"a\0b\"}\n";
-
-    parquet_read_out(out_str).stdout(eq(expected));
+    parquet_read_out(out_str).stdout(eq(expected_values));
 }
 
-/// Fixed size NCHAR column on database is not truncated then value is passed into a narrow buffer.
 #[test]
-#[cfg(not(target_os = "windows"))] // Windows does not use UTF-8 as default system encoding
-fn nchar_not_truncated() {
+fn query_bits() {
+    // Setup table for test
+    let table_name = "QueryBits";
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    let table_name = "NCharNotTruncated";
-    setup_empty_table_mssql(&conn, table_name, &["NCHAR(1)"]).unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["BIT"]).unwrap();
+    let insert = format!(
+        "INSERT INTO {table_name}
+        (a)
+        VALUES
+        (0), (1), (NULL), (1), (0);"
+    );
+    conn.execute(&insert, ()).unwrap();
 
-    conn.execute(
-        &format!("INSERT INTO {} (a) VALUES (?);", table_name),
-        &"Ü".into_parameter(),
-    )
-    .unwrap();
+    let expected_values = "\
+        {a: false}\n\
+        {a: true}\n\
+        {a: null}\n\
+        {a: true}\n\
+        {a: false}\n\
+    ";
 
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
@@ -1897,43 +1922,46 @@ fn nchar_not_truncated() {
     // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    let query = &format!("SELECT a FROM {};", table_name);
+    let query = format!("SELECT a FROM {table_name};");
 
     Command::cargo_bin("odbc2parquet")
         .unwrap()
-        .args(&[
+        .args([
             "-vvvv",
             "query",
-            "--encoding",
-            "system",
+            out_str,
             "--connection-string",
             MSSQL,
-            out_str,
-            query,
+            &query,
         ])
         .assert()
         .success();
 
-    let expected = "{a: \"Ü\"}\n";
-
-    parquet_read_out(out_str).stdout(eq(expected));
+    parquet_read_out(out_str).stdout(eq(expected_values));
 }
 
-/// Test non ASCII character with system encoding
 #[test]
-#[cfg(not(target_os = "windows"))] // Windows does not use UTF-8 as default system encoding
-fn system_encoding() {
+fn query_doubles() {
+    // Setup table for test
+    let table_name = "QueryDoubles";
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    let table_name = "SystemEncoding";
-    setup_empty_table_mssql(&conn, table_name, &["VARCHAR(10)"]).unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["DOUBLE PRECISION NOT NULL"]).unwrap();
+    let insert = format!(
+        "INSERT INTO {table_name}
+        (a)
+        VALUES
+        (0.1), (2.3);"
+    );
+    conn.execute(&insert, ()).unwrap();
 
-    conn.execute(
-        &format!("INSERT INTO {} (a) VALUES (?);", table_name),
-        &"Ü".into_parameter(),
-    )
-    .unwrap();
+    let expected_values = "\
+        {a: 0.1}\n\
+        {a: 2.3}\n\
+    ";
+
+    let expected_schema = "REQUIRED DOUBLE a;";
 
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
@@ -1943,42 +1971,36 @@ fn system_encoding() {
     // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    let query = &format!("SELECT a FROM {};", table_name);
+    let query = format!("SELECT a FROM {table_name};");
 
     Command::cargo_bin("odbc2parquet")
         .unwrap()
-        .args(&[
+        .args([
             "-vvvv",
             "query",
-            "--encoding",
-            "system",
+            out_str,
             "--connection-string",
             MSSQL,
-            out_str,
-            query,
+            &query,
         ])
         .assert()
         .success();
 
-    let expected = "{a: \"Ü\"}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
 
-    parquet_read_out(out_str).stdout(eq(expected));
+    // Also verify schema to ensure f64 is chosen and not f32
+    parquet_schema_out(out_str).stdout(contains(expected_schema));
 }
 
-/// Test non ASCII character with utf16 encoding
+/// Should not create a file if the query comes back empty `--no-empty-file` is set.
 #[test]
-fn utf_16_encoding() {
+fn query_comes_back_with_no_rows() {
+    // Setup table for test
+    let table_name = "QueryComesBackWithNoRows";
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    let table_name = "Utf16Encoding";
-    setup_empty_table_mssql(&conn, table_name, &["VARCHAR(10)"]).unwrap();
-
-    conn.execute(
-        &format!("INSERT INTO {table_name} (a) VALUES (?);"),
-        &"Ü".into_parameter(),
-    )
-    .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["DOUBLE PRECISION NOT NULL"]).unwrap();
 
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
@@ -1988,42 +2010,42 @@ fn utf_16_encoding() {
     // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    let query = &format!("SELECT a FROM {table_name};");
+    let query = format!("SELECT a FROM {table_name};");
 
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
             "-vvvv",
             "query",
-            "--encoding",
-            "utf16",
             "--connection-string",
             MSSQL,
+            "--no-empty-file",
             out_str,
-            query,
+            &query,
         ])
         .assert()
         .success();
 
-    let expected = "{a: \"Ü\"}\n";
-
-    parquet_read_out(out_str).stdout(eq(expected));
+    assert_eq!(
+        ErrorKind::NotFound,
+        File::open(out_str).err().unwrap().kind()
+    );
 }
 
-/// Test non ASCII character with automatic codec detection
+/// Should not create a file if the query comes back empty `--no-empty-file` is set. This should
+/// still work, even in the presence of (potential) file splitting.
+///
+/// This test has been created after a bug report. Original implementation of `--no-empty-file`
+/// deleted the file after the fact. Yet it tried to delete the base path without taking the suffix
+/// from file splitting into account.
 #[test]
-fn auto_encoding() {
+fn no_empty_file_works_with_split_files() {
+    // Setup table for test
+    let table_name = "NoEmptyFileWorksWithSplitFiles";
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    let table_name = "AutoEncoding";
-    setup_empty_table_mssql(&conn, table_name, &["VARCHAR(1)"]).unwrap();
-
-    conn.execute(
-        &format!("INSERT INTO {table_name} (a) VALUES (?);"),
-        &"Ü".into_parameter(),
-    )
-    .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["DOUBLE PRECISION NOT NULL"]).unwrap();
 
     // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
@@ -2033,1471 +2055,1443 @@ fn auto_encoding() {
     // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    let query = &format!("SELECT a FROM {table_name};");
+    let query = format!("SELECT a FROM {table_name};");
 
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
             "-vvvv",
             "query",
-            "--encoding",
-            "auto",
             "--connection-string",
             MSSQL,
+            "--row-groups-per-file",
+            "1",
+            "--no-empty-file",
             out_str,
-            query,
+            &query,
         ])
         .assert()
         .success();
 
-    let expected = "{a: \"Ü\"}\n";
-
-    parquet_read_out(out_str).stdout(eq(expected));
+    assert_eq!(
+        ErrorKind::NotFound,
+        File::open(out_str).err().unwrap().kind()
+    );
 }
 
+/// If the query does not come back empty, of course a file should be created, even if the
+/// `--no-empty-file` flag is set.
 #[test]
-pub fn insert_32_bit_integer() {
-    let table_name = "Insert32BitInteger";
-    // Prepare table
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["INTEGER"]).unwrap();
-    // Prepare file
+fn emit_file_despite_no_empty_file_set() {
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
     // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-    let message_type = "
-        message schema {
-            REQUIRED INT32 a;
-        }
-    ";
-    write_values_to_file(message_type, &input_path, &[42i32, 5, 1], None);
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    let query = "SELECT 42 as a";
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
             "-vvvv",
-            "insert",
+            "query",
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            "--no-empty-file",
+            out_str,
+            query,
         ])
         .assert()
         .success();
 
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
-    assert_eq!("42\n5\n1", actual);
+    let expected = "{a: 42}\n";
+    parquet_read_out(out_str).stdout(eq(expected));
 }
 
+/// Should read query from standard input if "-" is provided as query text.
 #[test]
-pub fn insert_optional_32_bit_integer() {
-    let table_name = "InsertOptional32BitInteger";
-    // Prepare table
+fn read_query_from_stdin() {
+    // Setup table for test
+    let table_name = "ReadQueryFromStdin";
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["INTEGER"]).unwrap();
-
-    // Prepare file
+    setup_empty_table_mssql(&conn, table_name, &["INT"]).unwrap();
+    let insert = format!(
+        "INSERT INTO {table_name}
+        (a)
+        VALUES
+        (54),
+        (54),
+        (34),
+        (12);"
+    );
+    conn.execute(&insert, ()).unwrap();
 
+    let expected_values = "\
+        {a: 54}\n\
+        {a: 54}\n\
+        {a: 34}\n\
+        {a: 12}\n\
+    ";
+    let query = format!("SELECT a FROM {table_name} ORDER BY id");
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
     // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            OPTIONAL INT32 a;
-        }
-    ";
-
-    write_values_to_file(message_type, &input_path, &[42i32, 1], Some(&[1, 0, 1]));
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Tempfile path must be utf8");
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
-        .args([
-            "-vvvv",
-            "insert",
-            "--connection-string",
-            MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
-        ])
+        .args(["-vvvv", "query", out_str, "--connection-string", MSSQL, "-"])
+        .write_stdin(query)
         .assert()
         .success();
 
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
-
-    assert_eq!("42\nNULL\n1", actual);
+    parquet_read_out(out_str).stdout(eq(expected_values));
 }
 
 #[test]
-pub fn insert_64_bit_integer() {
-    let table_name = "Insert64BitInteger";
-    // Prepare table
+fn split_files_on_num_row_groups() {
+    // Setup table for test
+    let table_name = "SplitFilesOnNumRowGroups";
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
     setup_empty_table_mssql(&conn, table_name, &["INTEGER"]).unwrap();
-
-    // Prepare file
+    let insert = format!("INSERT INTO {table_name} (A) VALUES(1),(2),(3)");
+    conn.execute(&insert, ()).unwrap();
 
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
     // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            REQUIRED INT64 a;
-        }
-    ";
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    write_values_to_file(message_type, &input_path, &[-42i64, 5, 1], None);
+    let query = format!("SELECT a FROM {table_name}");
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
             "-vvvv",
-            "insert",
+            "query",
+            out_str,
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            "--batch-size-row",
+            "1",
+            "--row-groups-per-file",
+            "1",
+            &query,
         ])
         .assert()
         .success();
 
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
+    // Expect one file per row in table (3)
 
-    assert_eq!("-42\n5\n1", actual);
+    parquet_read_out(out_dir.path().join("out_01.par").to_str().unwrap());
+    parquet_read_out(out_dir.path().join("out_02.par").to_str().unwrap());
+    parquet_read_out(out_dir.path().join("out_03.par").to_str().unwrap());
 }
 
+/// With `--on-max-files stop` the export fails with its own exit status, once the rows do not fit
+/// into the maximum number of files.
 #[test]
-pub fn insert_optional_64_bit_integer() {
-    let table_name = "InsertOptional64BitInteger";
-    // Prepare table
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["BIGINT"]).unwrap();
-
-    // Prepare file
-
+fn max_file_count_stop() {
+    // Setup table for test
+    let table_name = "MaxFileCountStop";
+    let mut table = TableMssql::new(table_name, &["INTEGER"]);
+    table.insert_rows_as_text(&[["1"], ["2"], ["3"]]);
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            OPTIONAL INT64 a;
-        }
-    ";
-
-    write_values_to_file(message_type, &input_path, &[-42i64, 1], Some(&[1, 0, 1]));
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a FROM {table_name} ORDER BY id");
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
-            "-vvvv",
-            "insert",
+            "query",
+            out_str,
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            "--batch-size-row",
+            "1",
+            "--row-groups-per-file",
+            "1",
+            "--max-file-count",
+            "2",
+            &query,
         ])
         .assert()
-        .success();
-
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
+        .code(3)
+        .stderr(contains("`--max-file-count`"));
 
-    assert_eq!("-42\nNULL\n1", actual);
+    parquet_read_out(out_dir.path().join("out_01.par").to_str().unwrap()).stdout(eq("{a: 1}\n"));
+    parquet_read_out(out_dir.path().join("out_02.par").to_str().unwrap()).stdout(eq("{a: 2}\n"));
+    assert!(!out_dir.path().join("out_03.par").exists());
 }
 
+/// With `--on-max-files merge` the remaining rows are written into the last file.
 #[test]
-pub fn insert_utf8() {
-    let table_name = "InsertUtf8";
-    // Prepare table
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["VARCHAR(50)"]).unwrap();
-
-    // Prepare file
-
+fn max_file_count_merge() {
+    // Setup table for test
+    let table_name = "MaxFileCountMerge";
+    let mut table = TableMssql::new(table_name, &["INTEGER"]);
+    table.insert_rows_as_text(&[["1"], ["2"], ["3"]]);
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a FROM {table_name} ORDER BY id");
 
-    let message_type = "
-        message schema {
-            REQUIRED BYTE_ARRAY a (UTF8);
-        }
-    ";
-
-    let text: ByteArray = "Hello, World!".into();
-    write_values_to_file(
-        message_type,
-        &input_path,
-        &[text, "Hallo, Welt!".into(), "Bonjour, Monde!".into()],
-        None,
-    );
-
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
-            "-vvvv",
-            "insert",
+            "query",
+            out_str,
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            "--batch-size-row",
+            "1",
+            "--row-groups-per-file",
+            "1",
+            "--max-file-count",
+            "2",
+            "--on-max-files",
+            "merge",
+            &query,
         ])
         .assert()
-        .success();
-
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
+        .success()
+        .stderr(contains("`--on-max-files merge`"));
 
-    assert_eq!("Hello, World!\nHallo, Welt!\nBonjour, Monde!", actual);
+    parquet_read_out(out_dir.path().join("out_01.par").to_str().unwrap()).stdout(eq("{a: 1}\n"));
+    parquet_read_out(out_dir.path().join("out_02.par").to_str().unwrap())
+        .stdout(eq("{a: 2}\n{a: 3}\n"));
 }
 
 #[test]
-pub fn insert_optional_utf8() {
-    let table_name = "InsertOptionalUtf8";
-    // Prepare table
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["VARCHAR(50)"]).unwrap();
-
-    // Prepare file
-
+fn max_rows_per_second() {
+    // Setup table for test
+    let table_name = "MaxRowsPerSecond";
+    let mut table = TableMssql::new(table_name, &["INTEGER"]);
+    table.insert_rows_as_text(&[["1"], ["2"], ["3"], ["4"]]);
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            OPTIONAL BYTE_ARRAY a (UTF8);
-        }
-    ";
-
-    let text: ByteArray = "Hello, World!".into();
-    write_values_to_file(
-        message_type,
-        &input_path,
-        &[text, "Hallo, Welt!".into()],
-        Some(&[1, 0, 1]),
-    );
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a FROM {table_name} ORDER BY id");
 
-    // Insert file into table
+    let start = Instant::now();
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
             "-vvvv",
-            "insert",
+            "query",
+            out_str,
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            "--batch-size-row",
+            "1",
+            "--max-rows-per-second",
+            "10",
+            &query,
         ])
         .assert()
         .success();
 
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
-
-    assert_eq!("Hello, World!\nNULL\nHallo, Welt!", actual);
+    // Fetching four rows at ten rows per second takes at least 400 milliseconds.
+    assert!(start.elapsed() >= Duration::from_millis(400));
+    let expected_values = "{a: 1}\n{a: 2}\n{a: 3}\n{a: 4}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
 }
 
 #[test]
-pub fn insert_utf16() {
-    let table_name = "InsertUtf16";
-    // Prepare table
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["VARCHAR(50)"]).unwrap();
-
-    // Prepare file
-
+fn profile_columns() {
+    // Setup table for test
+    let table_name = "ProfileColumns";
+    let mut table = TableMssql::new(table_name, &["INTEGER", "VARCHAR(10)"]);
+    table.insert_rows_as_text(&[
+        [Some("3"), Some("b")],
+        [Some("1"), None],
+        [Some("3"), Some("a")],
+        [None, Some("b")],
+    ]);
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            REQUIRED BYTE_ARRAY a (UTF8);
-        }
-    ";
-
-    let text: ByteArray = "Hello, World!".into();
-    write_values_to_file(
-        message_type,
-        &input_path,
-        &[text, "Hallo, Welt!".into(), "Bonjour, Monde!".into()],
-        None,
-    );
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let profile_path = out_dir.path().join("profile.json");
+    let profile_str = profile_path
+        .to_str()
+        .expect("Temporary file path must be utf8");
+    let query = format!("SELECT a, b FROM {table_name} ORDER BY id");
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
             "-vvvv",
-            "insert",
+            "query",
+            out_str,
             "--connection-string",
             MSSQL,
-            "--encoding",
-            "Utf16",
-            input_path.to_str().unwrap(),
-            table_name,
+            "--profile",
+            profile_str,
+            &query,
         ])
         .assert()
         .success();
 
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
-
-    assert_eq!("Hello, World!\nHallo, Welt!\nBonjour, Monde!", actual);
+    let expected = "{\"num_rows\":4,\"columns\":[\
+        {\"name\":\"a\",\"null_count\":1,\"distinct_count_estimate\":2,\"min\":1,\"max\":3},\
+        {\"name\":\"b\",\"null_count\":1,\"distinct_count_estimate\":2,\"min\":\"a\",\"max\":\"b\"}\
+        ]}\n";
+    assert_eq!(expected, std::fs::read_to_string(profile_path).unwrap());
 }
 
 #[test]
-pub fn insert_optional_utf16() {
-    let table_name = "InsertOptionalUtf16";
-    // Prepare table
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["VARCHAR(50)"]).unwrap();
-
-    // Prepare file
-
+fn sample_values() {
+    // Setup table for test
+    let table_name = "SampleValues";
+    let mut table = TableMssql::new(table_name, &["DATE", "DECIMAL(5,2)", "VARCHAR(10)"]);
+    table.insert_rows_as_text(&[
+        [Some("2024-02-29"), Some("1.50"), Some("secret")],
+        [Some("2024-02-29"), Some("-2.00"), Some("secret")],
+        [Some("2023-01-01"), None, Some("hidden")],
+    ]);
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            OPTIONAL BYTE_ARRAY a (UTF8);
-        }
-    ";
-
-    let text: ByteArray = "Hello, World!".into();
-    write_values_to_file(
-        message_type,
-        &input_path,
-        &[text, "Hallo, Welt!".into()],
-        Some(&[1, 0, 1]),
-    );
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let profile_path = out_dir.path().join("profile.json");
+    let profile_str = profile_path
+        .to_str()
+        .expect("Temporary file path must be utf8");
+    let query = format!("SELECT a, b, c FROM {table_name} ORDER BY id");
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
             "-vvvv",
-            "insert",
+            "query",
+            out_str,
             "--connection-string",
             MSSQL,
-            "--encoding",
-            "Utf16",
-            input_path.to_str().unwrap(),
-            table_name,
+            "--profile",
+            profile_str,
+            "--sample-values",
+            "1",
+            "--no-sample-columns",
+            "c",
+            &query,
         ])
         .assert()
         .success();
 
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
+    let profile = std::fs::read_to_string(profile_path).unwrap();
+    // Unlike minimum and maximum, samples are rendered according to the logical type
+    assert!(profile.contains("\"max\":19782,\"samples\":[\"2024-02-29\"]}"));
+    assert!(profile.contains("\"samples\":[1.50]}"));
+    assert!(!profile.contains("\"name\":\"c\",\"samples\""));
+    assert_eq!(2, profile.matches("samples").count());
+}
 
-    assert_eq!("Hello, World!\nNULL\nHallo, Welt!", actual);
+#[test]
+fn group_count() {
+    // Setup table for test
+    let table_name = "GroupCount";
+    let mut table = TableMssql::new(table_name, &["VARCHAR(10)"]);
+    table.insert_rows_as_text(&[[Some("x")], [Some("y")], [Some("x")], [None], [Some("z")]]);
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a FROM {table_name} ORDER BY id");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            out_str,
+            "--connection-string",
+            MSSQL,
+            "--group-count",
+            "a",
+            "--group-count-max-values",
+            "2",
+            &query,
+        ])
+        .assert()
+        .success()
+        .stderr(contains(
+            "Number of rows per value of column 'a':\n  \"x\": 2\n  \"y\": 1\n  NULL: 1\n  \
+            other (beyond the first 2 distinct values): 1\n",
+        ));
 }
 
 #[test]
-pub fn insert_bool() {
-    let table_name = "InsertBool";
-    // Prepare table
+fn debug_row_groups() {
+    // Setup table for test
+    let table_name = "DebugRowGroups";
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["BIT"]).unwrap();
-
-    // Prepare file
+    setup_empty_table_mssql(&conn, table_name, &["INTEGER"]).unwrap();
+    let insert = format!("INSERT INTO {table_name} (A) VALUES(1),(2),(3)");
+    conn.execute(&insert, ()).unwrap();
 
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            REQUIRED BOOLEAN a;
-        }
-    ";
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    write_values_to_file(message_type, &input_path, &[true, false, false], None);
+    let query = format!("SELECT a FROM {table_name}");
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
-            "-vvvv",
-            "insert",
+            "-vv",
+            "query",
+            out_str,
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            "--batch-size-row",
+            "2",
+            "--debug-row-groups",
+            &query,
         ])
         .assert()
-        .success();
-
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
-
-    assert_eq!("1\n0\n0", actual);
+        .success()
+        .stderr(contains("Row group 1: 2 rows,"))
+        .stderr(contains("flushed due to row count."))
+        .stderr(contains("Row group 2: 1 rows,"))
+        .stderr(contains("flushed due to end of data."));
 }
 
+/// Splitting the output must preserve the order of the result set within and across files.
 #[test]
-pub fn insert_optional_bool() {
-    let table_name = "InsertOptionalBool";
-    // Prepare table
+fn split_files_preserve_row_order() {
+    // Setup table for test
+    let table_name = "SplitFilesPreserveRowOrder";
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["BIT"]).unwrap();
-
-    // Prepare file
+    setup_empty_table_mssql(&conn, table_name, &["INTEGER"]).unwrap();
+    let insert = format!("INSERT INTO {table_name} (A) VALUES(5),(3),(1),(6),(2),(4)");
+    conn.execute(&insert, ()).unwrap();
 
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            OPTIONAL BOOLEAN a;
-        }
-    ";
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    write_values_to_file(message_type, &input_path, &[true, false], Some(&[1, 0, 1]));
+    let query = format!("SELECT a FROM {table_name} ORDER BY a");
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
-            "-vvvv",
-            "insert",
+            "query",
+            out_str,
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            "--batch-size-row",
+            "2",
+            "--row-groups-per-file",
+            "1",
+            &query,
         ])
         .assert()
         .success();
 
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
-
-    assert_eq!("1\nNULL\n0", actual);
+    parquet_read_out(out_dir.path().join("out_01.par").to_str().unwrap())
+        .stdout(eq("{a: 1}\n{a: 2}\n"));
+    parquet_read_out(out_dir.path().join("out_02.par").to_str().unwrap())
+        .stdout(eq("{a: 3}\n{a: 4}\n"));
+    parquet_read_out(out_dir.path().join("out_03.par").to_str().unwrap())
+        .stdout(eq("{a: 5}\n{a: 6}\n"));
 }
 
+/// Verify naming of the files is with successive numbers starting from 1 to 3 with split files and
+/// `--no-empty-file` flag set. This was messed up, with a refactoring once and file names started
+/// with `2` instead of `1``.
 #[test]
-pub fn insert_f32() {
-    let table_name = "InsertF32";
-    // Prepare table
+fn file_name_no_empty_file_and_split_files() {
+    // Setup table for test
+    let table_name = "FileNameNoEmptyFileAndSplitFiles";
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["FLOAT"]).unwrap();
-
-    // Prepare file
+    setup_empty_table_mssql(&conn, table_name, &["INTEGER"]).unwrap();
+    let insert = format!("INSERT INTO {table_name} (A) VALUES(1),(2),(3)");
+    conn.execute(&insert, ()).unwrap();
 
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
     // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            REQUIRED FLOAT a;
-        }
-    ";
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    write_values_to_file(message_type, &input_path, &[1.2f32, 3.4, 5.6], None);
+    let query = format!("SELECT a FROM {table_name}");
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
             "-vvvv",
-            "insert",
+            "query",
+            out_str,
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            "--no-empty-file",
+            "--batch-size-row",
+            "1",
+            "--row-groups-per-file",
+            "1",
+            &query,
         ])
         .assert()
         .success();
 
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
+    // Expect one file per row in table (3)
 
-    assert_eq!(
-        "1.2000000476837158\n3.4000000953674316\n5.5999999046325684",
-        actual
-    );
+    parquet_read_out(out_dir.path().join("out_01.par").to_str().unwrap());
+    parquet_read_out(out_dir.path().join("out_02.par").to_str().unwrap());
+    parquet_read_out(out_dir.path().join("out_03.par").to_str().unwrap());
 }
 
 #[test]
-pub fn insert_optional_f32() {
-    let table_name = "InsertOptionalF32";
-    // Prepare table
+fn split_files_on_size_limit() {
+    // Setup table for test
+    let table_name = "SplitFilesOnSizeLimit";
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["FLOAT"]).unwrap();
-
-    // Prepare file
+    setup_empty_table_mssql(&conn, table_name, &["INTEGER"]).unwrap();
+    let insert = format!("INSERT INTO {table_name} (A) VALUES(1),(2),(3)");
+    conn.execute(&insert, ()).unwrap();
 
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
     // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            OPTIONAL FLOAT a;
-        }
-    ";
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    write_values_to_file(message_type, &input_path, &[1.2f32, 3.4], Some(&[1, 0, 1]));
+    let query = format!("SELECT a FROM {table_name}");
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
             "-vvvv",
-            "insert",
+            "query",
+            out_str,
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            "--batch-size-row",
+            "1",
+            "--file-size-threshold",
+            "1B",
+            &query,
         ])
         .assert()
         .success();
 
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
+    // Expect one file per row in table (3)
 
-    assert_eq!("1.2000000476837158\nNULL\n3.4000000953674316", actual);
+    parquet_read_out(out_dir.path().join("out_01.par").to_str().unwrap());
+    parquet_read_out(out_dir.path().join("out_02.par").to_str().unwrap());
+    parquet_read_out(out_dir.path().join("out_03.par").to_str().unwrap());
 }
 
 #[test]
-pub fn insert_f64() {
-    let table_name = "InsertF64";
-    // Prepare table
+fn configurable_suffix_length() {
+    // Setup table for test
+    let table_name = "ConfigurableSuffixLength";
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["FLOAT(53)"]).unwrap();
-
-    // Prepare file
+    setup_empty_table_mssql(&conn, table_name, &["INTEGER"]).unwrap();
+    let insert = format!("INSERT INTO {table_name} (A) VALUES(1)");
+    conn.execute(&insert, ()).unwrap();
 
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
     // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            REQUIRED DOUBLE a;
-        }
-    ";
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    write_values_to_file(message_type, &input_path, &[1.2f64, 3.4, 5.6], None);
+    let query = format!("SELECT a FROM {table_name}");
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
             "-vvvv",
-            "insert",
+            "query",
+            out_str,
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            "--batch-size-row",
+            "1",
+            "--file-size-threshold",
+            "1B",
+            "--suffix-length",
+            "4",
+            &query,
         ])
         .assert()
         .success();
 
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
+    // Expect one file per row in table (3)
 
-    assert_eq!("1.2\n3.3999999999999999\n5.5999999999999996", actual);
+    parquet_read_out(out_dir.path().join("out_0001.par").to_str().unwrap());
 }
 
 #[test]
-pub fn insert_optional_f64() {
-    let table_name = "InsertOptionalF64";
-    // Prepare table
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+fn max_binary_length_truncates_and_counts() {
+    // Setup table for test
+    let table_name = "MaxBinaryLengthTruncatesAndCounts";
+    let mut table = TableMssql::new(table_name, &["VARBINARY(10)"]);
+    table.insert_rows_as_text(&[[None::<&str>]]);
+    table
+        .conn
+        .execute(
+            &format!(
+                "INSERT INTO {table_name} (a) Values (CONVERT(Binary(5), 'Hello')), \
+                (CONVERT(Binary(2), 'Hi'))"
+            ),
+            (),
+        )
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["FLOAT(53)"]).unwrap();
-
-    // Prepare file
-
-    // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            OPTIONAL DOUBLE a;
-        }
-    ";
-
-    write_values_to_file(message_type, &input_path, &[1.2f64, 3.4], Some(&[1, 0, 1]));
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a FROM {table_name} ORDER BY id;");
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
             "-vvvv",
-            "insert",
+            "query",
+            out_str,
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            "--max-binary-length",
+            "3",
+            &query,
         ])
         .assert()
-        .success();
-
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
+        .success()
+        .stderr(contains("Truncated value in column 'a', batch 1, row 1."))
+        .stderr(contains(
+            "1 values in column 'a' exceeded the maximum length and have been truncated.",
+        ));
 
-    assert_eq!("1.2\nNULL\n3.3999999999999999", actual);
+    let expected = "{a: null}\n{a: [72, 101, 108]}\n{a: [72, 105]}\n";
+    parquet_read_out(out_str).stdout(eq(expected));
 }
 
 #[test]
-pub fn insert_date() {
-    let table_name = "InsertDate";
-    // Prepare table
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["DATE"]).unwrap();
-
-    // Prepare file
-
-    // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            REQUIRED INT32 a (DATE);
-        }
-    ";
-
-    write_values_to_file(message_type, &input_path, &[0i32, 365, 18695], None);
+fn column_max_length() {
+    // Setup table for test
+    let table_name = "ColumnMaxLength";
+    let mut table = TableMssql::new(table_name, &["VARCHAR(20)", "VARCHAR(20)"]);
+    table.insert_rows_as_text(&[
+        [Some("Hello, World!"), Some("Hello, World!")],
+        [Some("abc"), Some("abc")],
+        [None, Some("abcd")],
+    ]);
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a, b FROM {table_name} ORDER BY id");
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
-            "-vvvv",
-            "insert",
-            "--connection-string",
+            "query",
+            out_str,
+            "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            "--column-length-limit",
+            "3",
+            "--column-max-length",
+            "a=5",
+            &query,
         ])
         .assert()
-        .success();
-
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
+        .success()
+        .stderr(contains(
+            "1 values in column 'a' exceeded the maximum length and have been truncated.",
+        ));
 
-    assert_eq!("1970-01-01\n1971-01-01\n2021-03-09", actual);
+    let expected = "{a: \"Hello\", b: \"Hel\"}\n{a: \"abc\", b: \"abc\"}\n{a: null, b: \"abc\"}\n";
+    parquet_read_out(out_str).stdout(eq(expected));
 }
 
 #[test]
-pub fn insert_optional_date() {
-    let table_name = "InsertOptionalDate";
-    // Prepare table
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["Date"]).unwrap();
-
-    // Prepare file
-
-    // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            OPTIONAL INT32 a (DATE);
-        }
-    ";
-
-    write_values_to_file(message_type, &input_path, &[0i32, 18695], Some(&[1, 0, 1]));
+fn query_prefix_and_suffix() {
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
-            "-vvvv",
-            "insert",
+            "query",
+            out_str,
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            "--query-prefix",
+            "WITH c AS (SELECT 42 AS a)",
+            "--query-suffix",
+            "OPTION (MAXDOP 1)",
+            "SELECT a FROM c -- trailing comment",
         ])
         .assert()
         .success();
 
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
-
-    assert_eq!("1970-01-01\nNULL\n2021-03-09", actual);
+    let expected = "{a: 42}\n";
+    parquet_read_out(out_str).stdout(eq(expected));
 }
 
 #[test]
-pub fn insert_time_ms() {
-    let table_name = "InsertTimeMs";
-    // Prepare table
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["TIME(3)"]).unwrap();
-
-    // Prepare file
-
-    // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
+fn rowid_column_must_not_collide() {
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    let message_type = "
-        message schema {
-            REQUIRED INT32 a (TIME_MILLIS);
-        }
-    ";
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            out_str,
+            "--connection-string",
+            MSSQL,
+            "--rowid-column",
+            "a",
+            "SELECT 1 AS a",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "Column 'a' of `--rowid-column` collides with a column of the result set.",
+        ));
+}
 
-    // Total number of milli seconds since midnight
-    write_values_to_file(
-        message_type,
-        &input_path,
-        &[0i32, 3_600_000, 82_800_000],
-        None,
-    );
+#[test]
+fn abort_on_warning() {
+    // Setup table for test
+    let table_name = "AbortOnWarning";
+    let mut table = TableMssql::new(table_name, &["VARCHAR(20)"]);
+    table.insert_rows_as_text(&[[Some("Hello, World!")]]);
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a FROM {table_name}");
 
-    // Insert file into table
+    // Informational messages of the driver, e.g. about the changed database context, are no
+    // reason to abort.
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
-            "-vvvv",
-            "insert",
+            "--abort-on-warning",
+            "query",
+            out_str,
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            &query,
         ])
         .assert()
         .success();
 
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
-
-    assert_eq!("00:00:00.000\n01:00:00.000\n23:00:00.000", actual);
+    // Truncation is reported as error, even if warnings are not printed.
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "--quiet",
+            "--abort-on-warning",
+            "query",
+            out_str,
+            "--connection-string",
+            MSSQL,
+            "--column-max-length",
+            "a=5",
+            &query,
+        ])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "Aborting, since `--abort-on-warning` is set. Warning: 1 values in column 'a' \
+            exceeded the maximum length and have been truncated.",
+        ));
 }
 
 #[test]
-pub fn insert_optional_time_ms() {
-    let table_name = "InsertOptionalTimeMs";
-    // Prepare table
+fn varbinary_column() {
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["TIME(3)"]).unwrap();
 
-    // Prepare file
+    setup_empty_table_mssql(&conn, "VarbinaryColumn", &["VARBINARY(10)"]).unwrap();
+    conn.execute(
+        "INSERT INTO VarbinaryColumn (a) Values \
+        (CONVERT(Binary(5), 'Hello')),\
+        (CONVERT(Binary(5), 'World')),\
+        (NULL)",
+        (),
+    )
+    .unwrap();
 
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
     // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            OPTIONAL INT32 a (TIME_MILLIS);
-        }
-    ";
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    // Total number of milli seconds since midnight
-    write_values_to_file(
-        message_type,
-        &input_path,
-        &[0i32, 82_800_000],
-        Some(&[1, 0, 1]),
-    );
+    let query = "SELECT a FROM VarbinaryColumn;";
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
             "-vvvv",
-            "insert",
+            "query",
+            out_str,
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            query,
         ])
         .assert()
         .success();
 
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
+    let expected = "{a: [72, 101, 108, 108, 111]}\n{a: [87, 111, 114, 108, 100]}\n{a: null}\n";
 
-    assert_eq!("00:00:00.000\nNULL\n23:00:00.000", actual);
+    parquet_read_out(out_str).stdout(eq(expected));
 }
 
+/// Since VARCHARMAX reports a size of 0. odbc2parquet should detect this and give the user an error
+/// instead.
 #[test]
-pub fn insert_time_us() {
-    let table_name = "InsertTimeUs";
-    // Prepare table
+fn query_varchar_max() {
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["TIME(6)"]).unwrap();
+    let table_name = "QueryVarcharMax";
 
-    // Prepare file
+    setup_empty_table_mssql(&conn, table_name, &["VARCHAR(MAX)"]).unwrap();
+    conn.execute(
+        &format!("INSERT INTO {table_name} (a) Values ('Hello'), ('World');"),
+        (),
+    )
+    .unwrap();
 
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
     // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            REQUIRED INT64 a (TIME_MICROS);
-        }
-    ";
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    // Total number of milli seconds since midnight
-    write_values_to_file(
-        message_type,
-        &input_path,
-        &[0i64, 3_600_000_000, 82_800_000_000],
-        None,
-    );
+    let query = format!("SELECT a FROM {table_name};");
 
-    // Insert file into table
+    // VARCHAR(max) has size 0. => Column is ignored and file would be empty and schemaless
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
             "-vvvv",
-            "insert",
+            "query",
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            out_str,
+            &query,
         ])
         .assert()
-        .success();
-
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
-
-    assert_eq!("00:00:00.000000\n01:00:00.000000\n23:00:00.000000", actual);
+        .failure()
+        .stderr(contains(
+            "Column 'a' with index 1. Driver reported a display length of 0. This can happen for \
+        variadic types without a fixed upper bound. You can manually specify an upper bound for \
+        variadic columns using the `--column-length-limit` command line argument.",
+        ));
 }
 
+/// Since VARCHARMAX reports a size of 0, it will be ignored, resulting in an output file with no
+/// columns. Yet by setting a size limit we can make it work.
 #[test]
-pub fn insert_optional_time_us() {
-    let table_name = "InsertOptionalTimeUs";
-    // Prepare table
+fn query_varchar_max_with_column_length_limit() {
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["TIME(6)"]).unwrap();
-
-    // Prepare file
+    let table_name = "QueryVarcharMaxWithColumnLengthLimit";
 
-    // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
+    setup_empty_table_mssql(&conn, table_name, &["VARCHAR(MAX)"]).unwrap();
+    conn.execute(
+        &format!("INSERT INTO {table_name} (a) Values ('Hello'), ('World');"),
+        (),
+    )
+    .unwrap();
 
-    let message_type = "
-        message schema {
-            OPTIONAL INT64 a (TIME_MICROS);
-        }
-    ";
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    // Total number of milli seconds since midnight
-    write_values_to_file(
-        message_type,
-        &input_path,
-        &[0i64, 82_800_000_000],
-        Some(&[1, 0, 1]),
-    );
+    let query = format!("SELECT a FROM {table_name};");
 
-    // Insert file into table
+    // VARCHAR(max) has size 0. => Column is ignored and file would be empty and schemaless
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
             "-vvvv",
-            "insert",
+            "query",
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            "--column-length-limit",
+            "4096",
+            out_str,
+            &query,
         ])
         .assert()
         .success();
-
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
-
-    assert_eq!("00:00:00.000000\nNULL\n23:00:00.000000", actual);
 }
 
+/// Introduced after discovering a bug, that columns were not ignored on windows.
+///
+/// Since VARCHARMAX reports a size of 0, it will be ignored, resulting in an output file with no
+/// columns. Yet odbc2parquet should detect this and give the user an error instead.
 #[test]
-pub fn insert_decimal_from_i32() {
-    let table_name = "InsertDecimalFromI32";
-    // Prepare table
+fn query_varchar_max_utf16() {
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["DECIMAL(9,2)"]).unwrap();
+    let table_name = "QueryVarcharMaxUtf16";
 
-    // Prepare file
+    setup_empty_table_mssql(&conn, table_name, &["VARCHAR(MAX)"]).unwrap();
+    conn.execute(
+        &format!("INSERT INTO {table_name} (a) Values ('Hello'), ('World');"),
+        (),
+    )
+    .unwrap();
 
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
     // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            REQUIRED INT32 a (DECIMAL(9,2));
-        }
-    ";
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    // Total number of milli seconds since midnight
-    write_values_to_file(message_type, &input_path, &[0i32, 123456789, -42], None);
+    let query = format!("SELECT a FROM {table_name};");
 
-    // Insert file into table
+    // VARCHAR(max) has size 0. => Column is ignored and file would be empty and schemaless
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
             "-vvvv",
-            "insert",
+            "query",
+            "--encoding",
+            "Utf16",
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            out_str,
+            &query,
         ])
         .assert()
-        .success();
-
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
-
-    assert_eq!(".00\n1234567.89\n-.42", actual);
+        .failure();
 }
 
 #[test]
-pub fn insert_decimal_from_i32_optional() {
-    let table_name = "InsertDecimalFromI32Optional";
-    // Prepare table
+fn binary_column() {
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["DECIMAL(9,2)"]).unwrap();
 
-    // Prepare file
+    setup_empty_table_mssql(&conn, "BinaryColumn", &["BINARY(5)"]).unwrap();
+    conn.execute(
+        "INSERT INTO BinaryColumn (a) Values \
+        (CONVERT(Binary(5), 'Hello')),\
+        (CONVERT(Binary(5), 'World')),\
+        (NULL)",
+        (),
+    )
+    .unwrap();
 
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
     // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            OPTIONAL INT32 a (DECIMAL(9,2));
-        }
-    ";
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    // Total number of milli seconds since midnight
-    write_values_to_file(message_type, &input_path, &[0i32, -42], Some(&[1, 0, 1]));
+    let query = "SELECT a FROM BinaryColumn;";
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
             "-vvvv",
-            "insert",
+            "query",
+            out_str,
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            query,
         ])
         .assert()
         .success();
 
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
-
-    assert_eq!(".00\nNULL\n-.42", actual);
+    let expected = "{a: [72, 101, 108, 108, 111]}\n{a: [87, 111, 114, 108, 100]}\n{a: null}\n";
+    parquet_read_out(out_str).stdout(eq(expected));
 }
 
+/// `BINARY(n)` keeps its fixed width, while `VARBINARY(n)` is written as variable length binary.
 #[test]
-pub fn insert_decimal_from_i64() {
-    let table_name = "InsertDecimalFromI64";
-    // Prepare table
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+fn fixed_length_binary_column() {
+    // Setup table for test
+    let table_name = "FixedLengthBinaryColumn";
+    let table = TableMssql::new(table_name, &["BINARY(16)", "VARBINARY(16)"]);
+    table
+        .conn
+        .execute(
+            &format!(
+                "INSERT INTO {table_name} (a, b) VALUES \
+                (CONVERT(BINARY(16), 'fixed'), CONVERT(VARBINARY(16), 'var'))"
+            ),
+            (),
+        )
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["DECIMAL(9,2)"]).unwrap();
-
-    // Prepare file
+    let query = format!("SELECT a, b FROM {table_name}");
 
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            REQUIRED INT64 a (DECIMAL(9,2));
-        }
-    ";
-
-    // Total number of milli seconds since midnight
-    write_values_to_file(message_type, &input_path, &[0i64, 123456789, -42], None);
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
-        .args([
-            "-vvvv",
-            "insert",
-            "--connection-string",
-            MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
-        ])
+        .args(["query", "--connection-string", MSSQL, out_str, &query])
         .assert()
         .success();
 
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
-
-    assert_eq!(".00\n1234567.89\n-.42", actual);
+    parquet_schema_out(out_str).stdout(contains(
+        "message schema {\n  \
+                OPTIONAL FIXED_LEN_BYTE_ARRAY (16) a;\n  \
+                OPTIONAL BYTE_ARRAY b;\n\
+            }",
+    ));
+    // The database pads the fixed length value with zeros.
+    parquet_read_out(out_str).stdout(eq(
+        "{a: [102, 105, 120, 101, 100, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0], b: [118, 97, 114]}\n",
+    ));
 }
 
+/// The prefer-varbinary flag must enforce mapping of binary columns to BYTE_ARRAY instead of
+/// FIXED_LEN_BYTE_ARRAY.
 #[test]
-pub fn insert_decimal_from_i64_optional() {
-    let table_name = "InsertDecimalFromI64Optional";
-    // Prepare table
+fn prefer_varbinary() {
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["DECIMAL(9,2)"]).unwrap();
 
-    // Prepare file
+    let table_name = "PreferVarbinary";
+
+    setup_empty_table_mssql(&conn, table_name, &["BINARY(5)"]).unwrap();
+    conn.execute(
+        &format!(
+            "INSERT INTO {table_name} (a) Values \
+        (CONVERT(Binary(5), 'Hello')),\
+        (CONVERT(Binary(5), 'World')),\
+        (NULL)"
+        ),
+        (),
+    )
+    .unwrap();
 
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
     // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            OPTIONAL INT64 a (DECIMAL(9,2));
-        }
-    ";
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    // Total number of milli seconds since midnight
-    write_values_to_file(message_type, &input_path, &[0i64, -42], Some(&[1, 0, 1]));
+    let query = format!("SELECT a FROM {table_name};");
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
             "-vvvv",
-            "insert",
+            "query",
+            out_str,
+            "--prefer-varbinary",
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            &query,
         ])
         .assert()
         .success();
 
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
+    let expected = "{a: [72, 101, 108, 108, 111]}\n{a: [87, 111, 114, 108, 100]}\n{a: null}\n";
+    parquet_read_out(out_str).stdout(eq(expected));
 
-    assert_eq!(".00\nNULL\n-.42", actual);
+    parquet_schema_out(out_str).stdout(contains("OPTIONAL BYTE_ARRAY a;"));
 }
 
+/// Strings with interior nuls should be written into parquet file as they are.
 #[test]
-pub fn insert_timestamp_ms() {
-    let table_name = "InsertTimestampMs";
-    // Prepare table
+fn interior_nul_in_varchar() {
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["DATETIME2"]).unwrap();
-
-    // Prepare file
+    setup_empty_table_mssql(&conn, "InteriorNul", &["VARCHAR(10)"]).unwrap();
 
-    // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
+    conn.execute(
+        "INSERT INTO InteriorNul (a) VALUES (?);",
+        &"a\0b".into_parameter(),
+    )
+    .unwrap();
 
-    let message_type = "
-        message schema {
-            REQUIRED INT64 a (TIMESTAMP_MILLIS);
-        }
-    ";
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    // Total number of milli seconds since unix epoch
-    write_values_to_file(message_type, &input_path, &[0i64, 1, 1616367053000], None);
+    let query = "SELECT a FROM InteriorNul;";
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
             "-vvvv",
-            "insert",
+            "query",
+            out_str,
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            query,
         ])
         .assert()
         .success();
 
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
+    let expected = "{a: \"a\0b\"}\n";
 
-    assert_eq!(
-        "1970-01-01 00:00:00.0000000\n1970-01-01 00:00:00.0010000\n2021-03-21 22:50:53.0000000",
-        actual
-    );
+    parquet_read_out(out_str).stdout(eq(expected));
 }
 
+/// Fixed size NCHAR column on database is not truncated then value is passed into a narrow buffer.
 #[test]
-pub fn insert_timestamp_ms_optional() {
-    let table_name = "InsertTimestampMsOptional";
-    // Prepare table
+#[cfg(not(target_os = "windows"))] // Windows does not use UTF-8 as default system encoding
+fn nchar_not_truncated() {
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["DATETIME2"]).unwrap();
+    let table_name = "NCharNotTruncated";
+    setup_empty_table_mssql(&conn, table_name, &["NCHAR(1)"]).unwrap();
 
-    // Prepare file
+    conn.execute(
+        &format!("INSERT INTO {} (a) VALUES (?);", table_name),
+        &"Ü".into_parameter(),
+    )
+    .unwrap();
 
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
     // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            OPTIONAL INT64 a (TIMESTAMP_MILLIS);
-        }
-    ";
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    // Total number of milli seconds since unix epoch
-    write_values_to_file(
-        message_type,
-        &input_path,
-        &[0i64, 1616367053000],
-        Some(&[1, 0, 1]),
-    );
+    let query = &format!("SELECT a FROM {};", table_name);
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
-        .args([
+        .args(&[
             "-vvvv",
-            "insert",
+            "query",
+            "--encoding",
+            "system",
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            out_str,
+            query,
         ])
         .assert()
         .success();
 
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
+    let expected = "{a: \"Ü\"}\n";
 
-    assert_eq!(
-        "1970-01-01 00:00:00.0000000\nNULL\n2021-03-21 22:50:53.0000000",
-        actual
-    );
+    parquet_read_out(out_str).stdout(eq(expected));
 }
 
+/// Characters outside the basic multilingual plane take four bytes in UTF-8. Buffers sized by the
+/// column length in characters must account for this, or the values are truncated.
 #[test]
-pub fn insert_timestamp_us() {
-    let table_name = "InsertTimestampUs";
-    // Prepare table
+#[cfg(not(target_os = "windows"))] // Windows does not use UTF-8 as default system encoding
+fn varchar_with_four_byte_characters_not_truncated() {
     let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .connect_with_connection_string(POSTGRES, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["DATETIME2"]).unwrap();
-
-    // Prepare file
-
+    let table_name = "VarcharWithFourByteCharactersNotTruncated";
+    setup_empty_table_pg(&conn, table_name, &["VARCHAR(3)"]).unwrap();
+    conn.execute(
+        &format!("INSERT INTO {table_name} (a) VALUES (?);"),
+        &"😀世界".into_parameter(),
+    )
+    .unwrap();
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
     // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            REQUIRED INT64 a (TIMESTAMP_MICROS);
-        }
-    ";
-
-    // Total number of milli seconds since unix epoch
-    write_values_to_file(
-        message_type,
-        &input_path,
-        &[0i64, 1, 1616367053000000],
-        None,
-    );
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a FROM {table_name};");
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
             "-vvvv",
-            "insert",
+            "query",
+            "--encoding",
+            "system",
             "--connection-string",
-            MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            POSTGRES,
+            out_str,
+            &query,
         ])
         .assert()
         .success();
 
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
-
-    assert_eq!(
-        "1970-01-01 00:00:00.0000000\n1970-01-01 00:00:00.0000010\n2021-03-21 22:50:53.0000000",
-        actual
-    );
+    parquet_read_out(out_str).stdout(eq("{a: \"😀世界\"}\n"));
 }
 
+/// Same as `varchar_with_four_byte_characters_not_truncated`, but for `NVARCHAR`, whose length
+/// Microsoft SQL Server counts in UTF-16 code units. The emoji takes two of them.
 #[test]
-pub fn insert_timestamp_us_optional() {
-    let table_name = "InsertTimestampUsOptional";
-    // Prepare table
+#[cfg(not(target_os = "windows"))] // Windows does not use UTF-8 as default system encoding
+fn nvarchar_with_four_byte_characters_not_truncated() {
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["DATETIME2"]).unwrap();
-
-    // Prepare file
-
+    let table_name = "NVarcharWithFourByteCharactersNotTruncated";
+    setup_empty_table_mssql(&conn, table_name, &["NVARCHAR(3)"]).unwrap();
+    conn.execute(
+        &format!("INSERT INTO {table_name} (a) VALUES (?);"),
+        &"😀世".into_parameter(),
+    )
+    .unwrap();
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
     // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            OPTIONAL INT64 a (TIMESTAMP_MICROS);
-        }
-    ";
-
-    // Total number of milli seconds since unix epoch
-    write_values_to_file(
-        message_type,
-        &input_path,
-        &[0i64, 1616367053000000],
-        Some(&[1, 0, 1]),
-    );
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a FROM {table_name};");
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
             "-vvvv",
-            "insert",
+            "query",
+            "--encoding",
+            "system",
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            out_str,
+            &query,
         ])
         .assert()
         .success();
 
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
-
-    assert_eq!(
-        "1970-01-01 00:00:00.0000000\nNULL\n2021-03-21 22:50:53.0000000",
-        actual
-    );
+    parquet_read_out(out_str).stdout(eq("{a: \"😀世\"}\n"));
 }
 
+/// Test non ASCII character with system encoding
 #[test]
-pub fn insert_binary() {
-    let table_name = "InsertBinary";
-    // Prepare table
+#[cfg(not(target_os = "windows"))] // Windows does not use UTF-8 as default system encoding
+fn system_encoding() {
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["VARBINARY(50)"]).unwrap();
+    let table_name = "SystemEncoding";
+    setup_empty_table_mssql(&conn, table_name, &["VARCHAR(10)"]).unwrap();
 
-    // Prepare file
+    conn.execute(
+        &format!("INSERT INTO {} (a) VALUES (?);", table_name),
+        &"Ü".into_parameter(),
+    )
+    .unwrap();
 
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
     // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            REQUIRED BYTE_ARRAY a;
-        }
-    ";
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    let text: ByteArray = "Hello, World!".into();
-    write_values_to_file(
-        message_type,
-        &input_path,
-        &[text, "Hallo, Welt!".into(), "Bonjour, Monde!".into()],
-        None,
-    );
+    let query = &format!("SELECT a FROM {};", table_name);
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
-        .args([
+        .args(&[
             "-vvvv",
-            "insert",
+            "query",
+            "--encoding",
+            "system",
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
-        ])
+            out_str,
+            query,
+        ])
+        .assert()
+        .success();
+
+    let expected = "{a: \"Ü\"}\n";
+
+    parquet_read_out(out_str).stdout(eq(expected));
+}
+
+/// Test non ASCII character with utf16 encoding
+#[test]
+fn utf_16_encoding() {
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    let table_name = "Utf16Encoding";
+    setup_empty_table_mssql(&conn, table_name, &["VARCHAR(10)"]).unwrap();
+
+    conn.execute(
+        &format!("INSERT INTO {table_name} (a) VALUES (?);"),
+        &"Ü".into_parameter(),
+    )
+    .unwrap();
+
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    let query = &format!("SELECT a FROM {table_name};");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "query",
+            "--encoding",
+            "utf16",
+            "--connection-string",
+            MSSQL,
+            out_str,
+            query,
+        ])
+        .assert()
+        .success();
+
+    let expected = "{a: \"Ü\"}\n";
+
+    parquet_read_out(out_str).stdout(eq(expected));
+}
+
+/// Test non ASCII character with automatic codec detection
+#[test]
+fn auto_encoding() {
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    let table_name = "AutoEncoding";
+    setup_empty_table_mssql(&conn, table_name, &["VARCHAR(1)"]).unwrap();
+
+    conn.execute(
+        &format!("INSERT INTO {table_name} (a) VALUES (?);"),
+        &"Ü".into_parameter(),
+    )
+    .unwrap();
+
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    let query = &format!("SELECT a FROM {table_name};");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "query",
+            "--encoding",
+            "auto",
+            "--connection-string",
+            MSSQL,
+            out_str,
+            query,
+        ])
+        .assert()
+        .success();
+
+    let expected = "{a: \"Ü\"}\n";
+
+    parquet_read_out(out_str).stdout(eq(expected));
+}
+
+#[test]
+pub fn insert_32_bit_integer() {
+    let table_name = "Insert32BitInteger";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["INTEGER"]).unwrap();
+    // Prepare file
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+    let message_type = "
+        message schema {
+            REQUIRED INT32 a;
+        }
+    ";
+    write_values_to_file(message_type, &input_path, &[42i32, 5, 1], None);
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
         .assert()
         .success();
 
@@ -3505,21 +3499,17 @@ pub fn insert_binary() {
     let query = format!("SELECT a FROM {table_name} ORDER BY Id");
     let cursor = conn.execute(&query, ()).unwrap().unwrap();
     let actual = cursor_to_string(cursor);
-
-    assert_eq!(
-        "48656C6C6F2C20576F726C6421\n48616C6C6F2C2057656C7421\n426F6E6A6F75722C204D6F6E646521",
-        actual
-    );
+    assert_eq!("42\n5\n1", actual);
 }
 
 #[test]
-pub fn insert_binary_optional() {
-    let table_name = "InsertBinaryOptional";
+pub fn insert_optional_32_bit_integer() {
+    let table_name = "InsertOptional32BitInteger";
     // Prepare table
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["VARBINARY(50)"]).unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["INTEGER"]).unwrap();
 
     // Prepare file
 
@@ -3531,17 +3521,11 @@ pub fn insert_binary_optional() {
 
     let message_type = "
         message schema {
-            OPTIONAL BYTE_ARRAY a;
+            OPTIONAL INT32 a;
         }
     ";
 
-    let text: ByteArray = "Hello, World!".into();
-    write_values_to_file(
-        message_type,
-        &input_path,
-        &[text, "Hallo, Welt!".into()],
-        Some(&[1, 0, 1]),
-    );
+    write_values_to_file(message_type, &input_path, &[42i32, 1], Some(&[1, 0, 1]));
 
     // Insert file into table
     Command::cargo_bin("odbc2parquet")
@@ -3562,20 +3546,17 @@ pub fn insert_binary_optional() {
     let cursor = conn.execute(&query, ()).unwrap().unwrap();
     let actual = cursor_to_string(cursor);
 
-    assert_eq!(
-        "48656C6C6F2C20576F726C6421\nNULL\n48616C6C6F2C2057656C7421",
-        actual
-    );
+    assert_eq!("42\nNULL\n1", actual);
 }
 
 #[test]
-pub fn insert_fixed_len_binary() {
-    let table_name = "InsertFixedLenBinary";
+pub fn insert_64_bit_integer() {
+    let table_name = "Insert64BitInteger";
     // Prepare table
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["BINARY(13)"]).unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["INTEGER"]).unwrap();
 
     // Prepare file
 
@@ -3587,26 +3568,11 @@ pub fn insert_fixed_len_binary() {
 
     let message_type = "
         message schema {
-            REQUIRED FIXED_LEN_BYTE_ARRAY(13) a;
+            REQUIRED INT64 a;
         }
     ";
 
-    let to_fixed_len_byte_array = |input: &str| -> FixedLenByteArray {
-        let ba: ByteArray = input.into();
-        ba.into()
-    };
-
-    let text: FixedLenByteArray = to_fixed_len_byte_array("Hello, World!");
-    write_values_to_file(
-        message_type,
-        &input_path,
-        &[
-            text,
-            to_fixed_len_byte_array("Hallo, Welt!!"),
-            to_fixed_len_byte_array("0123456789012"),
-        ],
-        None,
-    );
+    write_values_to_file(message_type, &input_path, &[-42i64, 5, 1], None);
 
     // Insert file into table
     Command::cargo_bin("odbc2parquet")
@@ -3627,20 +3593,17 @@ pub fn insert_fixed_len_binary() {
     let cursor = conn.execute(&query, ()).unwrap().unwrap();
     let actual = cursor_to_string(cursor);
 
-    assert_eq!(
-        "48656C6C6F2C20576F726C6421\n48616C6C6F2C2057656C742121\n30313233343536373839303132",
-        actual
-    );
+    assert_eq!("-42\n5\n1", actual);
 }
 
 #[test]
-pub fn insert_fixed_len_binary_optional() {
-    let table_name = "InsertFixedLenBinaryOptional";
+pub fn insert_optional_64_bit_integer() {
+    let table_name = "InsertOptional64BitInteger";
     // Prepare table
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["BINARY(13)"]).unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["BIGINT"]).unwrap();
 
     // Prepare file
 
@@ -3652,22 +3615,11 @@ pub fn insert_fixed_len_binary_optional() {
 
     let message_type = "
         message schema {
-            OPTIONAL FIXED_LEN_BYTE_ARRAY(13) a;
+            OPTIONAL INT64 a;
         }
     ";
 
-    let to_fixed_len_byte_array = |input: &str| -> FixedLenByteArray {
-        let ba: ByteArray = input.into();
-        ba.into()
-    };
-
-    let text: FixedLenByteArray = to_fixed_len_byte_array("Hello, World!");
-    write_values_to_file(
-        message_type,
-        &input_path,
-        &[text, to_fixed_len_byte_array("0123456789012")],
-        Some(&[1, 0, 1]),
-    );
+    write_values_to_file(message_type, &input_path, &[-42i64, 1], Some(&[1, 0, 1]));
 
     // Insert file into table
     Command::cargo_bin("odbc2parquet")
@@ -3688,20 +3640,17 @@ pub fn insert_fixed_len_binary_optional() {
     let cursor = conn.execute(&query, ()).unwrap().unwrap();
     let actual = cursor_to_string(cursor);
 
-    assert_eq!(
-        "48656C6C6F2C20576F726C6421\nNULL\n30313233343536373839303132",
-        actual
-    );
+    assert_eq!("-42\nNULL\n1", actual);
 }
 
 #[test]
-pub fn insert_decimal_from_binary() {
-    let table_name = "InsertDecimalFromBinary";
+pub fn insert_utf8() {
+    let table_name = "InsertUtf8";
     // Prepare table
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["DECIMAL(9,2)"]).unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["VARCHAR(50)"]).unwrap();
 
     // Prepare file
 
@@ -3713,21 +3662,15 @@ pub fn insert_decimal_from_binary() {
 
     let message_type = "
         message schema {
-            REQUIRED BYTE_ARRAY a (DECIMAL(9,2));
+            REQUIRED BYTE_ARRAY a (UTF8);
         }
     ";
 
-    let zero: ByteArray = vec![0u8].into();
+    let text: ByteArray = "Hello, World!".into();
     write_values_to_file(
         message_type,
         &input_path,
-        &[
-            zero,
-            vec![1].into(),
-            vec![1, 0, 0].into(),
-            vec![255, 255, 255].into(),
-            vec![255].into(),
-        ],
+        &[text, "Hallo, Welt!".into(), "Bonjour, Monde!".into()],
         None,
     );
 
@@ -3750,17 +3693,17 @@ pub fn insert_decimal_from_binary() {
     let cursor = conn.execute(&query, ()).unwrap().unwrap();
     let actual = cursor_to_string(cursor);
 
-    assert_eq!(".00\n.01\n655.36\n-.01\n-.01", actual);
+    assert_eq!("Hello, World!\nHallo, Welt!\nBonjour, Monde!", actual);
 }
 
 #[test]
-pub fn insert_decimal_from_binary_optional() {
-    let table_name = "InsertDecimalFromBinaryOptional";
+pub fn insert_optional_utf8() {
+    let table_name = "InsertOptionalUtf8";
     // Prepare table
     let conn = ENV
         .connect_with_connection_string(MSSQL, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["DECIMAL(9,2)"]).unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["VARCHAR(50)"]).unwrap();
 
     // Prepare file
 
@@ -3772,267 +3715,3899 @@ pub fn insert_decimal_from_binary_optional() {
 
     let message_type = "
         message schema {
-            OPTIONAL BYTE_ARRAY a (DECIMAL(9,2));
+            OPTIONAL BYTE_ARRAY a (UTF8);
         }
     ";
 
-    let zero: ByteArray = vec![0u8].into();
+    let text: ByteArray = "Hello, World!".into();
     write_values_to_file(
         message_type,
         &input_path,
-        &[zero, vec![1].into()],
+        &[text, "Hallo, Welt!".into()],
         Some(&[1, 0, 1]),
     );
 
-    // Insert file into table
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!("Hello, World!\nNULL\nHallo, Welt!", actual);
+}
+
+#[test]
+pub fn insert_utf16() {
+    let table_name = "InsertUtf16";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["VARCHAR(50)"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            REQUIRED BYTE_ARRAY a (UTF8);
+        }
+    ";
+
+    let text: ByteArray = "Hello, World!".into();
+    write_values_to_file(
+        message_type,
+        &input_path,
+        &[text, "Hallo, Welt!".into(), "Bonjour, Monde!".into()],
+        None,
+    );
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            "--encoding",
+            "Utf16",
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!("Hello, World!\nHallo, Welt!\nBonjour, Monde!", actual);
+}
+
+#[test]
+pub fn insert_optional_utf16() {
+    let table_name = "InsertOptionalUtf16";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["VARCHAR(50)"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            OPTIONAL BYTE_ARRAY a (UTF8);
+        }
+    ";
+
+    let text: ByteArray = "Hello, World!".into();
+    write_values_to_file(
+        message_type,
+        &input_path,
+        &[text, "Hallo, Welt!".into()],
+        Some(&[1, 0, 1]),
+    );
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            "--encoding",
+            "Utf16",
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!("Hello, World!\nNULL\nHallo, Welt!", actual);
+}
+
+#[test]
+pub fn insert_bool() {
+    let table_name = "InsertBool";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["BIT"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            REQUIRED BOOLEAN a;
+        }
+    ";
+
+    write_values_to_file(message_type, &input_path, &[true, false, false], None);
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!("1\n0\n0", actual);
+}
+
+#[test]
+pub fn insert_optional_bool() {
+    let table_name = "InsertOptionalBool";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["BIT"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            OPTIONAL BOOLEAN a;
+        }
+    ";
+
+    write_values_to_file(message_type, &input_path, &[true, false], Some(&[1, 0, 1]));
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!("1\nNULL\n0", actual);
+}
+
+#[test]
+pub fn insert_f32() {
+    let table_name = "InsertF32";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["FLOAT"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            REQUIRED FLOAT a;
+        }
+    ";
+
+    write_values_to_file(message_type, &input_path, &[1.2f32, 3.4, 5.6], None);
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!(
+        "1.2000000476837158\n3.4000000953674316\n5.5999999046325684",
+        actual
+    );
+}
+
+#[test]
+pub fn insert_optional_f32() {
+    let table_name = "InsertOptionalF32";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["FLOAT"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            OPTIONAL FLOAT a;
+        }
+    ";
+
+    write_values_to_file(message_type, &input_path, &[1.2f32, 3.4], Some(&[1, 0, 1]));
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!("1.2000000476837158\nNULL\n3.4000000953674316", actual);
+}
+
+#[test]
+pub fn insert_f64() {
+    let table_name = "InsertF64";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["FLOAT(53)"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            REQUIRED DOUBLE a;
+        }
+    ";
+
+    write_values_to_file(message_type, &input_path, &[1.2f64, 3.4, 5.6], None);
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!("1.2\n3.3999999999999999\n5.5999999999999996", actual);
+}
+
+#[test]
+pub fn insert_optional_f64() {
+    let table_name = "InsertOptionalF64";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["FLOAT(53)"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            OPTIONAL DOUBLE a;
+        }
+    ";
+
+    write_values_to_file(message_type, &input_path, &[1.2f64, 3.4], Some(&[1, 0, 1]));
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!("1.2\nNULL\n3.3999999999999999", actual);
+}
+
+#[test]
+pub fn insert_date() {
+    let table_name = "InsertDate";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["DATE"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            REQUIRED INT32 a (DATE);
+        }
+    ";
+
+    write_values_to_file(message_type, &input_path, &[0i32, 365, 18695], None);
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!("1970-01-01\n1971-01-01\n2021-03-09", actual);
+}
+
+#[test]
+pub fn insert_optional_date() {
+    let table_name = "InsertOptionalDate";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["Date"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            OPTIONAL INT32 a (DATE);
+        }
+    ";
+
+    write_values_to_file(message_type, &input_path, &[0i32, 18695], Some(&[1, 0, 1]));
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!("1970-01-01\nNULL\n2021-03-09", actual);
+}
+
+#[test]
+pub fn insert_time_ms() {
+    let table_name = "InsertTimeMs";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["TIME(3)"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            REQUIRED INT32 a (TIME_MILLIS);
+        }
+    ";
+
+    // Total number of milli seconds since midnight
+    write_values_to_file(
+        message_type,
+        &input_path,
+        &[0i32, 3_600_000, 82_800_000],
+        None,
+    );
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!("00:00:00.000\n01:00:00.000\n23:00:00.000", actual);
+}
+
+#[test]
+pub fn insert_optional_time_ms() {
+    let table_name = "InsertOptionalTimeMs";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["TIME(3)"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            OPTIONAL INT32 a (TIME_MILLIS);
+        }
+    ";
+
+    // Total number of milli seconds since midnight
+    write_values_to_file(
+        message_type,
+        &input_path,
+        &[0i32, 82_800_000],
+        Some(&[1, 0, 1]),
+    );
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!("00:00:00.000\nNULL\n23:00:00.000", actual);
+}
+
+#[test]
+pub fn insert_time_us() {
+    let table_name = "InsertTimeUs";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["TIME(6)"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            REQUIRED INT64 a (TIME_MICROS);
+        }
+    ";
+
+    // Total number of milli seconds since midnight
+    write_values_to_file(
+        message_type,
+        &input_path,
+        &[0i64, 3_600_000_000, 82_800_000_000],
+        None,
+    );
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!("00:00:00.000000\n01:00:00.000000\n23:00:00.000000", actual);
+}
+
+#[test]
+pub fn insert_optional_time_us() {
+    let table_name = "InsertOptionalTimeUs";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["TIME(6)"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            OPTIONAL INT64 a (TIME_MICROS);
+        }
+    ";
+
+    // Total number of milli seconds since midnight
+    write_values_to_file(
+        message_type,
+        &input_path,
+        &[0i64, 82_800_000_000],
+        Some(&[1, 0, 1]),
+    );
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!("00:00:00.000000\nNULL\n23:00:00.000000", actual);
+}
+
+#[test]
+pub fn insert_decimal_from_i32() {
+    let table_name = "InsertDecimalFromI32";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["DECIMAL(9,2)"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            REQUIRED INT32 a (DECIMAL(9,2));
+        }
+    ";
+
+    // Total number of milli seconds since midnight
+    write_values_to_file(message_type, &input_path, &[0i32, 123456789, -42], None);
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!(".00\n1234567.89\n-.42", actual);
+}
+
+#[test]
+pub fn insert_decimal_from_i32_optional() {
+    let table_name = "InsertDecimalFromI32Optional";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["DECIMAL(9,2)"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            OPTIONAL INT32 a (DECIMAL(9,2));
+        }
+    ";
+
+    // Total number of milli seconds since midnight
+    write_values_to_file(message_type, &input_path, &[0i32, -42], Some(&[1, 0, 1]));
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!(".00\nNULL\n-.42", actual);
+}
+
+#[test]
+pub fn insert_decimal_from_i64() {
+    let table_name = "InsertDecimalFromI64";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["DECIMAL(9,2)"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            REQUIRED INT64 a (DECIMAL(9,2));
+        }
+    ";
+
+    // Total number of milli seconds since midnight
+    write_values_to_file(message_type, &input_path, &[0i64, 123456789, -42], None);
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!(".00\n1234567.89\n-.42", actual);
+}
+
+#[test]
+pub fn insert_decimal_from_i64_optional() {
+    let table_name = "InsertDecimalFromI64Optional";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["DECIMAL(9,2)"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            OPTIONAL INT64 a (DECIMAL(9,2));
+        }
+    ";
+
+    // Total number of milli seconds since midnight
+    write_values_to_file(message_type, &input_path, &[0i64, -42], Some(&[1, 0, 1]));
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!(".00\nNULL\n-.42", actual);
+}
+
+#[test]
+pub fn insert_timestamp_ms() {
+    let table_name = "InsertTimestampMs";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["DATETIME2"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            REQUIRED INT64 a (TIMESTAMP_MILLIS);
+        }
+    ";
+
+    // Total number of milli seconds since unix epoch
+    write_values_to_file(message_type, &input_path, &[0i64, 1, 1616367053000], None);
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!(
+        "1970-01-01 00:00:00.0000000\n1970-01-01 00:00:00.0010000\n2021-03-21 22:50:53.0000000",
+        actual
+    );
+}
+
+#[test]
+pub fn insert_timestamp_ms_optional() {
+    let table_name = "InsertTimestampMsOptional";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["DATETIME2"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            OPTIONAL INT64 a (TIMESTAMP_MILLIS);
+        }
+    ";
+
+    // Total number of milli seconds since unix epoch
+    write_values_to_file(
+        message_type,
+        &input_path,
+        &[0i64, 1616367053000],
+        Some(&[1, 0, 1]),
+    );
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!(
+        "1970-01-01 00:00:00.0000000\nNULL\n2021-03-21 22:50:53.0000000",
+        actual
+    );
+}
+
+#[test]
+pub fn insert_timestamp_us() {
+    let table_name = "InsertTimestampUs";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["DATETIME2"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            REQUIRED INT64 a (TIMESTAMP_MICROS);
+        }
+    ";
+
+    // Total number of milli seconds since unix epoch
+    write_values_to_file(
+        message_type,
+        &input_path,
+        &[0i64, 1, 1616367053000000],
+        None,
+    );
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!(
+        "1970-01-01 00:00:00.0000000\n1970-01-01 00:00:00.0000010\n2021-03-21 22:50:53.0000000",
+        actual
+    );
+}
+
+#[test]
+pub fn insert_timestamp_us_optional() {
+    let table_name = "InsertTimestampUsOptional";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["DATETIME2"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            OPTIONAL INT64 a (TIMESTAMP_MICROS);
+        }
+    ";
+
+    // Total number of milli seconds since unix epoch
+    write_values_to_file(
+        message_type,
+        &input_path,
+        &[0i64, 1616367053000000],
+        Some(&[1, 0, 1]),
+    );
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!(
+        "1970-01-01 00:00:00.0000000\nNULL\n2021-03-21 22:50:53.0000000",
+        actual
+    );
+}
+
+#[test]
+pub fn insert_binary() {
+    let table_name = "InsertBinary";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["VARBINARY(50)"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            REQUIRED BYTE_ARRAY a;
+        }
+    ";
+
+    let text: ByteArray = "Hello, World!".into();
+    write_values_to_file(
+        message_type,
+        &input_path,
+        &[text, "Hallo, Welt!".into(), "Bonjour, Monde!".into()],
+        None,
+    );
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!(
+        "48656C6C6F2C20576F726C6421\n48616C6C6F2C2057656C7421\n426F6E6A6F75722C204D6F6E646521",
+        actual
+    );
+}
+
+#[test]
+pub fn insert_binary_optional() {
+    let table_name = "InsertBinaryOptional";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["VARBINARY(50)"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            OPTIONAL BYTE_ARRAY a;
+        }
+    ";
+
+    let text: ByteArray = "Hello, World!".into();
+    write_values_to_file(
+        message_type,
+        &input_path,
+        &[text, "Hallo, Welt!".into()],
+        Some(&[1, 0, 1]),
+    );
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!(
+        "48656C6C6F2C20576F726C6421\nNULL\n48616C6C6F2C2057656C7421",
+        actual
+    );
+}
+
+#[test]
+pub fn insert_fixed_len_binary() {
+    let table_name = "InsertFixedLenBinary";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["BINARY(13)"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            REQUIRED FIXED_LEN_BYTE_ARRAY(13) a;
+        }
+    ";
+
+    let to_fixed_len_byte_array = |input: &str| -> FixedLenByteArray {
+        let ba: ByteArray = input.into();
+        ba.into()
+    };
+
+    let text: FixedLenByteArray = to_fixed_len_byte_array("Hello, World!");
+    write_values_to_file(
+        message_type,
+        &input_path,
+        &[
+            text,
+            to_fixed_len_byte_array("Hallo, Welt!!"),
+            to_fixed_len_byte_array("0123456789012"),
+        ],
+        None,
+    );
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!(
+        "48656C6C6F2C20576F726C6421\n48616C6C6F2C2057656C742121\n30313233343536373839303132",
+        actual
+    );
+}
+
+#[test]
+pub fn insert_fixed_len_binary_optional() {
+    let table_name = "InsertFixedLenBinaryOptional";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["BINARY(13)"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            OPTIONAL FIXED_LEN_BYTE_ARRAY(13) a;
+        }
+    ";
+
+    let to_fixed_len_byte_array = |input: &str| -> FixedLenByteArray {
+        let ba: ByteArray = input.into();
+        ba.into()
+    };
+
+    let text: FixedLenByteArray = to_fixed_len_byte_array("Hello, World!");
+    write_values_to_file(
+        message_type,
+        &input_path,
+        &[text, to_fixed_len_byte_array("0123456789012")],
+        Some(&[1, 0, 1]),
+    );
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!(
+        "48656C6C6F2C20576F726C6421\nNULL\n30313233343536373839303132",
+        actual
+    );
+}
+
+#[test]
+pub fn insert_decimal_from_binary() {
+    let table_name = "InsertDecimalFromBinary";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["DECIMAL(9,2)"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            REQUIRED BYTE_ARRAY a (DECIMAL(9,2));
+        }
+    ";
+
+    let zero: ByteArray = vec![0u8].into();
+    write_values_to_file(
+        message_type,
+        &input_path,
+        &[
+            zero,
+            vec![1].into(),
+            vec![1, 0, 0].into(),
+            vec![255, 255, 255].into(),
+            vec![255].into(),
+        ],
+        None,
+    );
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!(".00\n.01\n655.36\n-.01\n-.01", actual);
+}
+
+#[test]
+pub fn insert_decimal_from_binary_optional() {
+    let table_name = "InsertDecimalFromBinaryOptional";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["DECIMAL(9,2)"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            OPTIONAL BYTE_ARRAY a (DECIMAL(9,2));
+        }
+    ";
+
+    let zero: ByteArray = vec![0u8].into();
+    write_values_to_file(
+        message_type,
+        &input_path,
+        &[zero, vec![1].into()],
+        Some(&[1, 0, 1]),
+    );
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!(".00\nNULL\n.01", actual);
+}
+
+#[test]
+pub fn insert_decimal_from_fixed_binary() {
+    let table_name = "InsertDecimalFromFixedBinary";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["DECIMAL(5,2)"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            REQUIRED FIXED_LEN_BYTE_ARRAY(3) a (DECIMAL(5,2));
+        }
+    ";
+
+    let to_fixed_len_byte_array = |input: Vec<u8>| -> FixedLenByteArray {
+        let ba: ByteArray = input.into();
+        ba.into()
+    };
+
+    let zero: FixedLenByteArray = to_fixed_len_byte_array(vec![0u8, 0, 0]);
+    write_values_to_file(
+        message_type,
+        &input_path,
+        &[
+            zero,
+            to_fixed_len_byte_array(vec![0, 0, 1]),
+            to_fixed_len_byte_array(vec![1, 0, 0]),
+            to_fixed_len_byte_array(vec![255, 255, 255]),
+        ],
+        None,
+    );
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!(".00\n.01\n655.36\n-.01", actual);
+}
+
+#[test]
+pub fn insert_decimal_from_fixed_binary_optional() {
+    let table_name = "InsertDecimalFromFixedBinaryOptional";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["DECIMAL(5,2)"]).unwrap();
+
+    // Prepare file
+
+    // A temporary directory, to be removed at the end of the test.
+    let tmp_dir = tempdir().unwrap();
+    // The name of the input parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let input_path = tmp_dir.path().join("input.par");
+
+    let message_type = "
+        message schema {
+            OPTIONAL FIXED_LEN_BYTE_ARRAY(3) a (DECIMAL(5,2));
+        }
+    ";
+
+    let to_fixed_len_byte_array = |input: Vec<u8>| -> FixedLenByteArray {
+        let ba: ByteArray = input.into();
+        ba.into()
+    };
+
+    write_values_to_file(
+        message_type,
+        &input_path,
+        &[
+            to_fixed_len_byte_array(vec![0, 0, 1]),
+            to_fixed_len_byte_array(vec![255, 255, 255]),
+        ],
+        Some(&[1, 0, 1]),
+    );
+
+    // Insert file into table
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input_path.to_str().unwrap(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, ()).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!(".01\nNULL\n-.01", actual);
+}
+
+/// Write query output to stdout
+#[test]
+pub fn write_query_result_to_stdout() {
+    // Given
+    let table_name = "WriteQueryResultToStdout";
+    // Prepare table
+    let conn = ENV
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["INTEGER"]).unwrap();
+    conn.execute(
+        &format!("INSERT INTO {table_name} (a) VALUES (?)"),
+        [42i32, 5, 64].as_slice(),
+    )
+    .unwrap();
+
+    // When
+
+    // Query table and write contents to stdout
+    let query = format!("SELECT a FROM {table_name} ORDER BY id");
+    let command = Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "query",
+            "--connection-string",
+            MSSQL,
+            "-", // Use `-` to explicitly write to stdout
+            &query,
+        ])
+        .assert()
+        .success();
+
+    // Then
+    let output = &command.get_output().stdout;
+    assert!(!output.is_empty());
+
+    // Write captured contents of stdout to temporary file so we can inspect it with read parquet
+    let mut output_file = NamedTempFile::new().unwrap();
+    output_file.write_all(output).unwrap();
+
+    let expected = "{a: 42}\n";
+
+    let output_path = output_file.path().to_str().unwrap();
+    parquet_read_out(output_path).stdout(eq(expected));
+}
+
+/// Write query output to stdout
+#[test]
+pub fn reject_writing_to_stdout_and_file_size_limit() {
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "query",
+            "--connection-string",
+            "FakeConnectionString",
+            "--file-size-threshold",
+            "1GiB",
+            "-", // Use `-` to explicitly write to stdout
+            "SELECT a FROM FakeTableName ORDER BY id",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "file-size-threshold conflicts with specifying stdout ('-') as output.",
+        ));
+}
+
+#[test]
+fn reject_writing_to_stdout_and_retry_on_deadlock() {
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "query",
+            "--connection-string",
+            "FakeConnectionString",
+            "--retry-on-deadlock",
+            "3",
+            "-", // Use `-` to explicitly write to stdout
+            "SELECT a FROM FakeTableName ORDER BY id",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "retry-on-deadlock conflicts with specifying stdout ('-') as output.",
+        ));
+}
+
+#[test]
+fn export_table_by_name() {
+    // Setup table for test
+    let table_name = "ExportTableByName";
+    let mut table = TableMssql::new(table_name, &["INTEGER", "VARCHAR(10)"]);
+    table.insert_rows_as_text(&[["42", "Hello"]]);
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "-vvvv",
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--table",
+            table_name,
+            "--schema",
+            "dbo",
+            out_str,
+        ])
+        .assert()
+        .success();
+
+    let expected_values = "{id: 1, a: 42, b: \"Hello\"}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
+}
+
+#[test]
+fn output_dir_names_file_after_table() {
+    // Setup table for test
+    let table_name = "OutputDirNamesFileAfterTable";
+    let mut table = TableMssql::new(table_name, &["INTEGER"]);
+    table.insert_rows_as_text(&[["42"]]);
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_dir_str = out_dir.path().to_str().unwrap();
+
+    // Run the export twice, to see the second run overwrites the output of the first one.
+    for _ in 0..2 {
+        Command::cargo_bin("odbc2parquet")
+            .unwrap()
+            .args([
+                "query",
+                "--connection-string",
+                MSSQL,
+                "--output-dir",
+                out_dir_str,
+                "--table",
+                table_name,
+                "--schema",
+                "dbo",
+            ])
+            .assert()
+            .success();
+    }
+
+    let out_path = out_dir.path().join(format!("dbo.{table_name}.par"));
+    let expected_values = "{id: 1, a: 42}\n";
+    parquet_read_out(out_path.to_str().unwrap()).stdout(eq(expected_values));
+    assert_eq!(1, std::fs::read_dir(out_dir.path()).unwrap().count());
+}
+
+#[test]
+fn output_dir_names_file_after_query() {
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_dir_str = out_dir.path().to_str().unwrap();
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--output-dir",
+            out_dir_str,
+            "--query",
+            "SELECT ? AS a",
+            "--parameter",
+            "42",
+        ])
+        .assert()
+        .success();
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--output-dir",
+            out_dir_str,
+            "--extension",
+            ".parquet",
+            "--query",
+            "SELECT ? AS a",
+            "--parameter",
+            "43",
+        ])
+        .assert()
+        .success();
+
+    let mut file_names: Vec<_> = std::fs::read_dir(out_dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    file_names.sort_by_key(|name| name.len());
+    assert_eq!(2, file_names.len());
+    assert!(file_names[0].starts_with("query_"));
+    assert!(file_names[0].ends_with(".par"));
+    assert!(file_names[1].ends_with(".parquet"));
+}
+
+#[test]
+fn pad_to_rows() {
+    // Setup table for test
+    let table_name = "PadToRows";
+    let mut table = TableMssql::new(table_name, &["INTEGER", "VARCHAR(10)"]);
+    table.insert_rows_as_text(&[["1", "one"], ["2", "two"]]);
+    let query = format!("SELECT a, b FROM {table_name} ORDER BY id");
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--pad-to-rows",
+            "4",
+            "--pad-value",
+            "a=0",
+            "--pad-flag-column",
+            "filler",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    let expected_values = "{a: 1, b: \"one\", filler: false}\n\
+        {a: 2, b: \"two\", filler: false}\n\
+        {a: 0, b: null, filler: true}\n\
+        {a: 0, b: null, filler: true}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
+
+    // The result set holds more rows than requested
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--pad-to-rows",
+            "1",
+            "--pad-strict",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .failure()
+        .stderr(contains("more than the 1 rows passed to `--pad-to-rows`"));
+}
+
+#[test]
+fn limit_number_of_rows() {
+    // Setup table for test
+    let table_name = "LimitNumberOfRows";
+    let mut table = TableMssql::new(table_name, &["INTEGER"]);
+    table.insert_rows_as_text(&[["1"], ["2"], ["3"]]);
+    let query = format!("SELECT a FROM {table_name} ORDER BY id");
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--limit",
+            "2",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    let expected_values = "{a: 1}\n{a: 2}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
+}
+
+#[test]
+fn coerce_between_booleans_and_integers() {
+    // Setup table for test
+    let table_name = "CoerceBetweenBooleansAndIntegers";
+    let mut table = TableMssql::new(table_name, &["INTEGER", "BIT"]);
+    table.insert_rows_as_text(&[["0", "1"], ["2", "0"]]);
+    table.insert_rows_as_text(&[[None::<&str>, None]]);
+    let query = format!("SELECT a, b FROM {table_name} ORDER BY id");
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--coerce-bool-from-int",
+            "a",
+            "--coerce-int-from-bool",
+            "b",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    let expected_values = "{a: false, b: 1}\n{a: true, b: 0}\n{a: null, b: null}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
+
+    // Only integer columns can be written as booleans
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--coerce-bool-from-int",
+            "b",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .failure()
+        .stderr(contains("must be an integer"));
+}
+
+#[test]
+fn compress_columns_in_multiple_threads() {
+    // Setup table for test
+    let table_name = "CompressColumnsInMultipleThreads";
+    let mut table = TableMssql::new(table_name, &["INTEGER", "VARCHAR(10)", "REAL"]);
+    table.insert_rows_as_text(&[
+        [Some("1"), Some("one"), Some("1.5")],
+        [Some("2"), None, Some("2.5")],
+        [None, Some("three"), None],
+    ]);
+    let query = format!("SELECT a, b, c FROM {table_name} ORDER BY id");
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--batch-size-row",
+            "2",
+            "--compression-threads",
+            "2",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    let expected_values = "{a: 1, b: \"one\", c: 1.5}\n\
+        {a: 2, b: null, c: 2.5}\n\
+        {a: null, b: \"three\", c: null}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
+}
+
+#[test]
+fn write_metrics_file() {
+    // Setup table for test
+    let table_name = "WriteMetricsFile";
+    let mut table = TableMssql::new(table_name, &["INTEGER"]);
+    table.insert_rows_as_text(&[["1"], ["2"]]);
+    let query = format!("SELECT a FROM {table_name}");
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let metrics_path = out_dir.path().join("odbc2parquet.prom");
+    let metrics_str = metrics_path
+        .to_str()
+        .expect("Temporary file path must be utf8");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--metrics-file",
+            metrics_str,
+            "--metrics-job",
+            "nightly",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    let metrics = std::fs::read_to_string(&metrics_path).unwrap();
+    let file_size = std::fs::metadata(&out_path).unwrap().len();
+    assert!(metrics.contains("\nodbc2parquet_rows{job=\"nightly\"} 2\n"));
+    assert!(metrics.contains("\nodbc2parquet_files{job=\"nightly\"} 1\n"));
+    assert!(metrics.contains(&format!(
+        "\nodbc2parquet_bytes_written{{job=\"nightly\"}} {file_size}\n"
+    )));
+    assert!(metrics.contains("\n# TYPE odbc2parquet_duration_seconds gauge\n"));
+}
+
+#[test]
+fn coerce_between_empty_and_null_binary() {
+    // Setup table for test
+    let table_name = "CoerceBetweenEmptyAndNullBinary";
+    let table = TableMssql::new(table_name, &["VARBINARY(10)"]);
+    table
+        .conn
+        .execute(
+            &format!("INSERT INTO {table_name} (a) Values (0x), (NULL), (0x0102)"),
+            (),
+        )
+        .unwrap();
+    let query = format!("SELECT a FROM {table_name} ORDER BY id");
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    let export = |flag: &str| {
+        Command::cargo_bin("odbc2parquet")
+            .unwrap()
+            .args(["query", "--connection-string", MSSQL, flag, out_str, &query])
+            .assert()
+            .success();
+    };
+
+    export("--coerce-empty-binary-as-null");
+    parquet_read_out(out_str).stdout(eq("{a: null}\n{a: null}\n{a: [1, 2]}\n"));
+
+    export("--coerce-null-binary-as-empty");
+    parquet_read_out(out_str).stdout(eq("{a: []}\n{a: []}\n{a: [1, 2]}\n"));
+}
+
+/// Columns which are not nullable can still hold zero-length values, so they are declared
+/// `OPTIONAL` with `--coerce-empty-binary-as-null`.
+#[test]
+fn coerce_empty_binary_as_null_in_not_nullable_column() {
+    // Setup table for test
+    let table_name = "CoerceEmptyBinaryAsNullInNotNullableColumn";
+    let table = TableMssql::new(table_name, &["VARBINARY(10) NOT NULL"]);
+    table
+        .conn
+        .execute(
+            &format!("INSERT INTO {table_name} (a) Values (0x), (0x0102)"),
+            (),
+        )
+        .unwrap();
+    let query = format!("SELECT a FROM {table_name} ORDER BY id");
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--coerce-empty-binary-as-null",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    parquet_schema_out(out_str).stdout(contains("OPTIONAL BYTE_ARRAY a;"));
+    parquet_read_out(out_str).stdout(eq("{a: null}\n{a: [1, 2]}\n"));
+}
+
+#[test]
+fn export_columns_from_catalog() {
+    // Setup table for test
+    let table_name = "ExportColumnsFromCatalog";
+    TableMssql::new(table_name, &["VARCHAR(10)"]);
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--catalog-function",
+            "columns",
+            "--schema",
+            "dbo",
+            "--table-pattern",
+            table_name,
+            out_str,
+        ])
+        .assert()
+        .success();
+
+    parquet_read_out(out_str)
+        .stdout(contains(format!("TABLE_NAME: \"{table_name}\"")))
+        .stdout(contains("COLUMN_NAME: \"id\""))
+        .stdout(contains("COLUMN_NAME: \"a\""))
+        .stdout(contains("TYPE_NAME: \"varchar\""));
+}
+
+#[test]
+fn emit_hadoop_crc() {
+    // Setup table for test
+    let table_name = "EmitHadoopCrc";
+    let mut table = TableMssql::new(table_name, &["INTEGER"]);
+    table.insert_rows_as_text(&[["1"], ["2"]]);
+    let query = format!("SELECT a FROM {table_name}");
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--emit-hadoop-crc",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    let file_size = std::fs::metadata(&out_path).unwrap().len();
+    let crc = std::fs::read(out_dir.path().join(".out.par.crc")).unwrap();
+    assert_eq!(b"crc\0\0\0\x02\x00", &crc[..8]);
+    // One checksum for each chunk of 512 bytes
+    assert_eq!(8 + 4 * file_size.div_ceil(512), crc.len() as u64);
+}
+
+#[test]
+fn strict_schema_fails_if_schema_changes() {
+    // Setup table for test
+    let table_name = "StrictSchemaFailsIfSchemaChanges";
+    let mut table = TableMssql::new(table_name, &["INTEGER", "VARCHAR(10)"]);
+    table.insert_rows_as_text(&[["1", "a"]]);
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let run = |query: &str| {
+        Command::cargo_bin("odbc2parquet")
+            .unwrap()
+            .args([
+                "query",
+                "--connection-string",
+                MSSQL,
+                "--strict-schema",
+                out_str,
+                query,
+            ])
+            .assert()
+    };
+
+    // First run creates the cache, second run matches it.
+    run(&format!("SELECT a, b FROM {table_name}")).success();
+    run(&format!("SELECT a, b FROM {table_name}")).success();
+    assert!(out_dir.path().join("out.par.schema.json").exists());
+
+    run(&format!(
+        "SELECT a, CAST(a AS BIGINT) AS c FROM {table_name}"
+    ))
+    .failure()
+    .stderr(contains("added: c INT64 NULL"))
+    .stderr(contains("removed: b BYTE_ARRAY STRING NULL"));
+}
+
+#[test]
+fn bind_column_as_explicit_c_type() {
+    // Setup table for test
+    let table_name = "BindColumnAsExplicitCType";
+    let mut table = TableMssql::new(table_name, &["DECIMAL(5,2)", "INTEGER"]);
+    table.insert_rows_as_text(&[["1.50", "42"]]);
+    let query = format!("SELECT a, b FROM {table_name}");
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--bind-as",
+            "a=SQL_C_CHAR",
+            "--bind-as",
+            "b=double",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    let expected_values = "{a: \"1.50\", b: 42.0}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
+}
+
+#[test]
+fn bind_as_rejects_undefined_conversion() {
+    // Setup table for test
+    let table_name = "BindAsRejectsUndefinedConversion";
+    let mut table = TableMssql::new(table_name, &["DATE"]);
+    table.insert_rows_as_text(&[["2024-01-01"]]);
+    let query = format!("SELECT a FROM {table_name}");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--bind-as",
+            "a=SQL_C_SBIGINT",
+            "-",
+            &query,
+        ])
+        .assert()
+        .failure()
+        .stderr(contains("can not be bound as SQL_C_SBIGINT"));
+}
+
+#[test]
+fn group_columns_into_struct() {
+    // Setup table for test
+    let table_name = "GroupColumnsIntoStruct";
+    let mut table = TableMssql::new(table_name, &["INTEGER", "VARCHAR(10)", "VARCHAR(10)"]);
+    table.insert_rows_as_text(&[
+        [Some("1"), Some("Main St"), Some("Berlin")],
+        [Some("2"), None, Some("Paris")],
+    ]);
+    let query = format!("SELECT a, b AS street, c AS city FROM {table_name} ORDER BY id");
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--group-columns",
+            "address=city,street",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    let expected_values = "\
+        {a: 1, address: {city: \"Berlin\", street: \"Main St\"}}\n\
+        {a: 2, address: {city: \"Paris\", street: null}}\n\
+    ";
+    parquet_read_out(out_str).stdout(eq(expected_values));
+}
+
+#[test]
+fn page_row_count_limit() {
+    // Setup table for test
+    let table_name = "PageRowCountLimit";
+    let mut table = TableMssql::new(table_name, &["INTEGER"]);
+    table.insert_rows_as_text(&[["1"], ["2"], ["3"], ["4"], ["5"]]);
+    let query = format!("SELECT a FROM {table_name} ORDER BY id");
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--page-row-count-limit",
+            "2",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    let options = ReadOptionsBuilder::new().with_page_index().build();
+    let reader =
+        SerializedFileReader::new_with_options(File::open(&out_path).unwrap(), options).unwrap();
+    let offset_index = &reader.metadata().offset_index().unwrap()[0][0];
+    let first_row_indices: Vec<i64> = offset_index
+        .page_locations
+        .iter()
+        .map(|page| page.first_row_index)
+        .collect();
+    assert_eq!(vec![0, 2, 4], first_row_indices);
+    parquet_read_out(out_str).stdout(eq("{a: 1}\n{a: 2}\n{a: 3}\n{a: 4}\n{a: 5}\n"));
+}
+
+#[test]
+fn write_page_index() {
+    // Setup table for test
+    let table_name = "WritePageIndex";
+    let mut table = TableMssql::new(table_name, &["INTEGER"]);
+    table.insert_rows_as_text(&[["1"], ["2"], ["3"]]);
+    let query = format!("SELECT a FROM {table_name} ORDER BY id");
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let with_index = out_dir.path().join("with_index.par");
+    let without_index = out_dir.path().join("without_index.par");
+    let export = |path: &Path, page_index: &str| {
+        Command::cargo_bin("odbc2parquet")
+            .unwrap()
+            .args([
+                "query",
+                "--connection-string",
+                MSSQL,
+                page_index,
+                path.to_str().unwrap(),
+                &query,
+            ])
+            .assert()
+            .success();
+    };
+
+    export(&with_index, "--page-index");
+    export(&without_index, "--page-index=false");
+
+    let read_metadata = |path: &Path| {
+        let options = ReadOptionsBuilder::new().with_page_index().build();
+        SerializedFileReader::new_with_options(File::open(path).unwrap(), options)
+            .unwrap()
+            .metadata()
+            .clone()
+    };
+    let metadata = read_metadata(&with_index);
+    let column_index = &metadata.column_index().unwrap()[0][0];
+    assert!(
+        matches!(column_index, Index::INT32(index) if index.indexes.len() == 1
+        && index.indexes[0].min == Some(1)
+        && index.indexes[0].max == Some(3))
+    );
+    assert_eq!(
+        1,
+        metadata.offset_index().unwrap()[0][0].page_locations.len()
+    );
+    let metadata = read_metadata(&without_index);
+    assert!(metadata
+        .column_index()
+        .is_none_or(|index| matches!(index[0][0], Index::NONE)));
+    assert_eq!(
+        1,
+        metadata.offset_index().unwrap()[0][0].page_locations.len()
+    );
+}
+
+#[test]
+fn derive_columns_from_expressions() {
+    // Setup table for test
+    let table_name = "DeriveColumnsFromExpressions";
+    let mut table = TableMssql::new(table_name, &["VARCHAR(10)", "VARCHAR(10)", "DECIMAL(5,2)"]);
+    table.insert_rows_as_text(&[
+        [Some("Ada"), Some("Lovelace"), Some("1.50")],
+        [Some("Alan"), None, Some("20.05")],
+    ]);
+    let query = format!("SELECT a AS first, b AS last, c AS amount FROM {table_name} ORDER BY id");
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--derive",
+            "full_name=first || ' ' || last",
+            "--derive",
+            "amount_cents:int64=amount * 100",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    let expected_values = "\
+        {first: \"Ada\", last: \"Lovelace\", amount: 1.50, full_name: \"Ada Lovelace\", \
+        amount_cents: 150}\n\
+        {first: \"Alan\", last: null, amount: 20.05, full_name: null, amount_cents: 2005}\n\
+    ";
+    parquet_read_out(out_str).stdout(eq(expected_values));
+}
+
+#[test]
+fn read_only_refuses_modifying_statements() {
+    // Setup table for test
+    let table_name = "ReadOnlyRefusesModifyingStatements";
+    let mut table = TableMssql::new(table_name, &["INTEGER"]);
+    table.insert_rows_as_text(&[["1"]]);
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--read-only",
+            out_str,
+            &format!("-- Copied by accident\nDELETE FROM {table_name}"),
+        ])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "Refusing to execute a statement starting with 'DELETE'",
+        ));
+
+    // The row has not been deleted
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--read-only",
+            out_str,
+            &format!("SELECT a FROM {table_name}"),
+        ])
+        .assert()
+        .success();
+    parquet_read_out(out_str).stdout(eq("{a: 1}\n"));
+}
+
+#[test]
+fn emit_stats_parquet() {
+    // Setup table for test
+    let table_name = "EmitStatsParquet";
+    let mut table = TableMssql::new(table_name, &["INTEGER", "VARCHAR(10)"]);
+    table.insert_rows_as_text(&[
+        [Some("1"), Some("a")],
+        [None, Some("b")],
+        [Some("3"), Some("c")],
+    ]);
+    let query = format!("SELECT a, b FROM {table_name} ORDER BY id");
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let stats_path = out_dir.path().join("stats.par");
+    let stats_str = stats_path
+        .to_str()
+        .expect("Temporary file path must be utf8");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--batch-size-row",
+            "2",
+            "--emit-stats-parquet",
+            stats_str,
+            "--stats-parquet-columns",
+            "a",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    let expected_values = format!(
+        "\
+        {{file: \"{out_str}\", row_group: 0, num_rows: 2, a: {{min: 1, max: 1, null_count: 1}}}}\n\
+        {{file: \"{out_str}\", row_group: 1, num_rows: 1, a: {{min: 3, max: 3, null_count: 0}}}}\n\
+        "
+    );
+    parquet_read_out(stats_str).stdout(eq(expected_values));
+}
+
+#[test]
+fn timestamps_in_time_zone_of_another_column() {
+    // Setup table for test
+    let table_name = "TimestampsInTimeZoneOfAnotherColumn";
+    let mut table = TableMssql::new(table_name, &["DATETIME2(3)", "VARCHAR(40)"]);
+    table.insert_rows_as_text(&[
+        ["2024-07-04 12:00:00.000", "America/New_York"],
+        // Occurs twice in New York, since clocks are turned back at 02:00 EDT. Earlier one wins.
+        ["2024-11-03 01:30:00.000", "America/New_York"],
+        ["2024-07-04 12:00:00.000", "UTC"],
+        ["2024-07-04 12:00:00.000", "Mars/Olympus_Mons"],
+    ]);
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a, b FROM {table_name} ORDER BY id;");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--ts-tz-from",
+            "a:b",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "Unknown time zone 'Mars/Olympus_Mons' in column 'b'.",
+        ));
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--ts-tz-from",
+            "a:b",
+            "--unknown-timezone",
+            "null",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    let expected_values = "{a: 2024-07-04 16:00:00 +00:00, b: \"America/New_York\"}\n\
+        {a: 2024-11-03 05:30:00 +00:00, b: \"America/New_York\"}\n\
+        {a: 2024-07-04 12:00:00 +00:00, b: \"UTC\"}\n\
+        {a: null, b: \"Mars/Olympus_Mons\"}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
+    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT64 a (TIMESTAMP(MILLIS,true));"));
+}
+
+#[test]
+fn reorder_columns_for_size() {
+    // Setup table for test
+    let table_name = "ReorderColumnsForSize";
+    let mut table = TableMssql::new(
+        table_name,
+        &["VARCHAR(10)", "INTEGER NOT NULL", "INTEGER", "BIT NOT NULL"],
+    );
+    table.insert_rows_as_text(&[["x", "1", "2", "1"]]);
+    let query = format!("SELECT a, b, c, d FROM {table_name}");
+
+    let command = Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--reorder-for-size",
+            "-", // Use `-` to explicitly write to stdout
+            &query,
+        ])
+        .assert()
+        .success();
+
+    // Then: required columns first, each kind ordered by physical type
+    let bytes = Bytes::from(command.get_output().stdout.clone());
+    let reader = SerializedFileReader::new(bytes).unwrap();
+    let file_metadata = reader.metadata().file_metadata();
+    let names: Vec<&str> = file_metadata
+        .schema_descr()
+        .columns()
+        .iter()
+        .map(|column| column.name())
+        .collect();
+    assert_eq!(["d", "b", "c", "a"], names.as_slice());
+    let key_value = &file_metadata.key_value_metadata().unwrap()[0];
+    assert_eq!("odbc2parquet.original_column_order", key_value.key);
+    assert_eq!(Some("3,1,2,0"), key_value.value.as_deref());
+}
+
+#[test]
+fn read_options_from_job_file() {
+    // Setup table for test
+    let table_name = "ReadOptionsFromJobFile";
+    let mut table = TableMssql::new(table_name, &["INTEGER"]);
+    table.insert_rows_as_text(&[["1"], ["2"]]);
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let job_path = out_dir.path().join("job.toml");
+    let job_str = job_path.to_str().expect("Temporary file path must be utf8");
+    let job = format!(
+        "connection-string = {{ env = \"JOB_FILE_CONNECTION_STRING\" }}\n\
+        output = '{out_str}'\n\
+        query = \"\"\"\n-- Ordered, so the output is deterministic\n\
+        SELECT a FROM {table_name} ORDER BY id\"\"\"\n\
+        batch-size-row = 1\n\
+        row-groups-per-file = 1 # Overridden on the command line\n"
+    );
+    std::fs::write(&job_path, job).unwrap();
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .env("JOB_FILE_CONNECTION_STRING", MSSQL)
+        .args(["query", "--job-file", job_str, "--row-groups-per-file", "0"])
+        .assert()
+        .success();
+
+    parquet_read_out(out_str).stdout(eq("{a: 1}\n{a: 2}\n"));
+
+    // Unknown keys are reported
+    std::fs::write(&job_path, "batch-size-rows = 1\n").unwrap();
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args(["query", "--job-file", job_str])
+        .assert()
+        .failure()
+        .stderr(contains("Unknown key 'batch-size-rows'"));
+}
+
+#[test]
+fn fail_fast_on_null() {
+    // Setup table for test
+    let table_name = "FailFastOnNull";
+    let mut table = TableMssql::new(table_name, &["INTEGER", "INTEGER"]);
+    table.insert_rows_as_text(&[
+        [Some("1"), None],
+        [Some("2"), Some("3")],
+        [Some("3"), Some("4")],
+        [None, Some("5")],
+    ]);
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a, b FROM {table_name} ORDER BY id;");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--batch-size-row",
+            "2",
+            "--fail-fast-on-null",
+            "a",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .failure()
+        .stderr(contains("Column 'a' is NULL in batch 2, row 1."));
+}
+
+#[test]
+fn write_csv() {
+    // Setup table for test
+    let table_name = "WriteCsv";
+    let mut table = TableMssql::new(table_name, &["VARCHAR(20)", "DATE", "DECIMAL(5,2)"]);
+    table.insert_rows_as_text(&[
+        [Some("a,b"), Some("2024-02-29"), Some("-1.50")],
+        [Some("say \"hi\""), None, Some("123.45")],
+        [Some("two\nlines"), Some("1970-01-01"), None],
+    ]);
+    let query = format!("SELECT a, b, c FROM {table_name} ORDER BY id");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--format",
+            "csv",
+            "--csv-null",
+            "NULL",
+            "-",
+            &query,
+        ])
+        .assert()
+        .success()
+        .stdout(eq("a,b,c\n\
+            \"a,b\",2024-02-29,-1.50\n\
+            \"say \"\"hi\"\"\",NULL,123.45\n\
+            \"two\nlines\",1970-01-01,NULL\n"));
+}
+
+#[test]
+fn write_tsv_split_into_files() {
+    // Setup table for test
+    let table_name = "WriteTsvSplitIntoFiles";
+    let mut table = TableMssql::new(table_name, &["INTEGER", "VARCHAR(10)"]);
+    table.insert_rows_as_text(&[[Some("1"), Some("a,b")], [Some("2"), None]]);
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.tsv");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a, b FROM {table_name} ORDER BY id");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--format",
+            "tsv",
+            "--batch-size-row",
+            "1",
+            "--row-groups-per-file",
+            "1",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    // Header is repeated in every file. Commas need no quotes with tabs as delimiter.
+    let read = |name| std::fs::read_to_string(out_dir.path().join(name)).unwrap();
+    assert_eq!("a\tb\n1\ta,b\n", read("out_01.tsv"));
+    assert_eq!("a\tb\n2\t\n", read("out_02.tsv"));
+}
+
+#[test]
+fn write_ndjson_gzip() {
+    // Setup table for test
+    let table_name = "WriteNdjsonGzip";
+    let mut table = TableMssql::new(table_name, &["VARCHAR(20)", "DATE", "DECIMAL(5,2)"]);
+    table.insert_rows_as_text(&[
+        [Some("say \"hi\""), Some("2024-02-29"), Some("-1.50")],
+        [Some("two\nlines"), None, Some("123.45")],
+    ]);
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.ndjson.gz");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a, b, c FROM {table_name} ORDER BY id");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--format",
+            "ndjson",
+            "--json-nulls",
+            "omit",
+            "--text-compression",
+            "gzip",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    let mut decoder = flate2::read::GzDecoder::new(File::open(&out_path).unwrap());
+    let mut actual = String::new();
+    decoder.read_to_string(&mut actual).unwrap();
+    assert_eq!(
+        "{\"a\":\"say \\\"hi\\\"\",\"b\":\"2024-02-29\",\"c\":-1.50}\n\
+        {\"a\":\"two\\nlines\",\"c\":123.45}\n",
+        actual
+    );
+}
+
+#[test]
+fn column_nullable_from_data() {
+    // Setup table for test
+    let table_name = "ColumnNullableFromData";
+    let mut table = TableMssql::new(table_name, &["INTEGER", "INTEGER"]);
+    table.insert_rows_as_text(&[[Some("1"), Some("2")], [Some("3"), None]]);
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a, b FROM {table_name} ORDER BY id");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--batch-size-row",
+            "1",
+            "--column-nullable-from-data",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    parquet_read_out(out_str).stdout(eq("{a: 1, b: 2}\n{a: 3, b: null}\n"));
+    parquet_schema_out(out_str).stdout(contains(
+        "message schema {\n  REQUIRED INT32 a;\n  OPTIONAL INT32 b;\n}",
+    ));
+}
+
+#[test]
+fn force_nullability_of_all_columns() {
+    // Setup table for test
+    let table_name = "ForceNullabilityOfAllColumns";
+    let mut table = TableMssql::new(table_name, &["INTEGER NOT NULL", "VARCHAR(10)", "BIT"]);
+    table.insert_rows_as_text(&[
+        [Some("1"), Some("a"), Some("1")],
+        [Some("2"), None, Some("0")],
+    ]);
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a, b, c FROM {table_name} ORDER BY id");
+    let export = |args: &[&str]| {
+        Command::cargo_bin("odbc2parquet")
+            .unwrap()
+            .args(["query", "--connection-string", MSSQL])
+            .args(args)
+            .args([out_str, &query])
+            .assert()
+    };
+
+    export(&["--force-nullable-all"]).success();
+    parquet_schema_out(out_str).stdout(contains(
+        "message schema {\n  OPTIONAL INT32 a;\n  OPTIONAL BYTE_ARRAY b (UTF8);\n  \
+        OPTIONAL BOOLEAN c;\n}",
+    ));
+
+    // Per column fill values take precedence
+    export(&["--force-nullable-all", "--null-fill", "b=none"]).success();
+    parquet_schema_out(out_str).stdout(contains(
+        "message schema {\n  OPTIONAL INT32 a;\n  REQUIRED BYTE_ARRAY b (UTF8);\n  \
+        OPTIONAL BOOLEAN c;\n}",
+    ));
+
+    export(&["--force-required-all", "--null-fill", "b=none"]).success();
+    parquet_read_out(out_str).stdout(eq(
+        "{a: 1, b: \"a\", c: true}\n{a: 2, b: \"none\", c: false}\n",
+    ));
+    parquet_schema_out(out_str).stdout(contains(
+        "message schema {\n  REQUIRED INT32 a;\n  REQUIRED BYTE_ARRAY b (UTF8);\n  \
+        REQUIRED BOOLEAN c;\n}",
+    ));
+
+    export(&["--force-required-all"])
+        .failure()
+        .stderr(contains("Column 'b' is NULL in batch 1, row 1."));
+}
+
+#[test]
+fn stable_sort() {
+    // Setup table for test
+    let table_name = "StableSort";
+    let mut table = TableMssql::new(table_name, &["INTEGER", "VARCHAR(10)"]);
+    table.insert_rows_as_text(&[
+        [Some("2"), Some("b")],
+        [Some("1"), Some("z")],
+        [Some("2"), Some("a")],
+        [None, Some("c")],
+    ]);
+    let query = format!("SELECT a, b FROM {table_name}");
+
+    for client in [false, true] {
+        // A temporary directory, to be removed at the end of the test.
+        let out_dir = tempdir().unwrap();
+        let out_path = out_dir.path().join("out.par");
+        let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+        let mut args = vec![
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--batch-size-row",
+            "2",
+            "--stable-sort",
+            "a,b",
+            out_str,
+            &query,
+        ];
+        if client {
+            args.push("--stable-sort-client");
+        }
+
+        Command::cargo_bin("odbc2parquet")
+            .unwrap()
+            .args(args)
+            .assert()
+            .success();
+
+        parquet_read_out(out_str).stdout(eq("{a: null, b: \"c\"}\n\
+            {a: 1, b: \"z\"}\n\
+            {a: 2, b: \"a\"}\n\
+            {a: 2, b: \"b\"}\n"));
+    }
+}
+
+#[test]
+fn delta_against_previous_export() {
+    // Setup table for test
+    let table_name = "DeltaAgainstPreviousExport";
+    let mut table = TableMssql::new(table_name, &["INTEGER", "VARCHAR(10)"]);
+    table.insert_rows_as_text(&[
+        [Some("1"), Some("a")],
+        [Some("2"), Some("b")],
+        [Some("3"), Some("c")],
+    ]);
+    let query = format!("SELECT a, b FROM {table_name} ORDER BY a");
+
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let previous_path = out_dir.path().join("previous.par");
+    let previous_str = previous_path
+        .to_str()
+        .expect("Temporary file path must be utf8");
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args(["query", "--connection-string", MSSQL, previous_str, &query])
+        .assert()
+        .success();
+
+    // Update, delete and insert one row each.
+    table
+        .conn
+        .execute(&format!("UPDATE {table_name} SET b = 'B' WHERE a = 2"), ())
+        .unwrap();
+    table
+        .conn
+        .execute(&format!("DELETE FROM {table_name} WHERE a = 3"), ())
+        .unwrap();
+    table.insert_rows_as_text(&[[Some("4"), Some("d")]]);
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--delta-against",
+            previous_str,
+            "--key-column",
+            "a",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    parquet_read_out(out_str).stdout(eq("{a: 2, b: \"B\", op: \"U\"}\n\
+        {a: 4, b: \"d\", op: \"I\"}\n\
+        {a: 3, b: null, op: \"D\"}\n"));
+}
+
+#[test]
+fn partition_by_date() {
+    // Setup table for test
+    let table_name = "PartitionByDate";
+    let mut table = TableMssql::new(table_name, &["DATE", "INTEGER"]);
+    table.insert_rows_as_text(&[
+        [Some("2024-01-15"), Some("1")],
+        [Some("2024-01-16"), Some("2")],
+        [Some("2024-01-15"), Some("3")],
+        [None, Some("4")],
+    ]);
+    let query = format!("SELECT a, b FROM {table_name} ORDER BY b");
+
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--partition-by-date",
+            "a:day",
+            "--max-open-partitions",
+            "1",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    let part = |name: &str| out_path.join(name).to_str().unwrap().to_owned();
+    parquet_read_out(&part("dt=2024-01-15/part.par")).stdout(eq("{a: 2024-01-15, b: 1}\n"));
+    parquet_read_out(&part("dt=2024-01-16/part.par")).stdout(eq("{a: 2024-01-16, b: 2}\n"));
+    // The first file of the partition had to be closed, in order to open the one of 2024-01-16.
+    parquet_read_out(&part("dt=2024-01-15/part_02.par")).stdout(eq("{a: 2024-01-15, b: 3}\n"));
+    parquet_read_out(&part("dt=__HIVE_DEFAULT_PARTITION__/part.par"))
+        .stdout(eq("{a: null, b: 4}\n"));
+}
+
+#[test]
+fn partition_by_date_concurrently() {
+    // Setup table for test
+    let table_name = "PartitionByDateConcurrently";
+    let mut table = TableMssql::new(table_name, &["DATE", "INTEGER"]);
+    table.insert_rows_as_text(&[
+        [Some("2024-01-15"), Some("1")],
+        [Some("2024-01-16"), Some("2")],
+        [Some("2024-01-17"), Some("3")],
+        [Some("2024-01-15"), Some("4")],
+        [Some("2024-01-18"), Some("5")],
+        [None, Some("6")],
+        [Some("2024-01-16"), Some("7")],
+    ]);
+    let query = format!("SELECT a, b FROM {table_name} ORDER BY b");
+
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let export = |concurrency: &str| {
+        let out_path = out_dir.path().join(format!("out_{concurrency}"));
+        let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+        Command::cargo_bin("odbc2parquet")
+            .unwrap()
+            .args([
+                "query",
+                "--connection-string",
+                MSSQL,
+                "--batch-size-row",
+                "2",
+                "--partition-by-date",
+                "a:day",
+                "--max-open-partitions",
+                "2",
+                "--partition-concurrency",
+                concurrency,
+                out_str,
+                &query,
+            ])
+            .assert()
+            .success();
+        out_path
+    };
+    let sequential = export("1");
+    let concurrent = export("3");
+
+    // Same files with the same contents, independent of the number of threads writing them.
+    for part in [
+        "dt=2024-01-15/part.par",
+        "dt=2024-01-15/part_02.par",
+        "dt=2024-01-16/part.par",
+        "dt=2024-01-16/part_02.par",
+        "dt=2024-01-17/part.par",
+        "dt=2024-01-18/part.par",
+        "dt=__HIVE_DEFAULT_PARTITION__/part.par",
+    ] {
+        assert_eq!(
+            std::fs::read(sequential.join(part)).unwrap(),
+            std::fs::read(concurrent.join(part)).unwrap(),
+            "{part}"
+        );
+    }
+    let part = concurrent.join("dt=2024-01-16/part_02.par");
+    parquet_read_out(part.to_str().unwrap()).stdout(eq("{a: 2024-01-16, b: 7}\n"));
+}
+
+#[test]
+fn schema_name() {
+    // Setup table for test
+    let table_name = "SchemaName";
+    let mut table = TableMssql::new(table_name, &["INTEGER"]);
+    table.insert_rows_as_text(&[["42"]]);
+    let query = format!("SELECT a FROM {table_name}");
+
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--schema-name",
+            "hive_schema",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    parquet_schema_out(out_str).stdout(contains("message hive_schema {\n"));
+    parquet_read_out(out_str).stdout(eq("{a: 42}\n"));
+}
+
+#[test]
+fn write_statistics_for_text_columns() {
+    // Setup table for test
+    let table_name = "WriteStatisticsForTextColumns";
+    let mut table = TableMssql::new(table_name, &["VARCHAR(10)"]);
+    table.insert_rows_as_text(&[["aaa"], ["zzz"]]);
+    let query = format!("SELECT a FROM {table_name}");
+
+    let command = Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "-", // Use `-` to explicitly write to stdout
+            &query,
+        ])
+        .assert()
+        .success();
+
+    // Then
+    let bytes = Bytes::from(command.get_output().stdout.clone());
+    let reader = SerializedFileReader::new(bytes).unwrap();
+    let stats = reader
+        .metadata()
+        .row_group(0)
+        .column(0)
+        .statistics()
+        .unwrap();
+    assert_eq!("aaa", str::from_utf8(stats.min_bytes_opt().unwrap()).unwrap());
+    assert_eq!("zzz", str::from_utf8(stats.max_bytes_opt().unwrap()).unwrap());
+}
+
+/// This did not work in earlier versions there we set the batch write size of the parquet writer to
+/// the ODBC batch size.
+#[test]
+fn sorting_columns_in_row_group_metadata() {
+    // Setup table for test
+    let table_name = "SortingColumnsInRowGroupMetadata";
+    let mut table = TableMssql::new(table_name, &["INTEGER", "VARCHAR(10)"]);
+    table.insert_rows_as_text(&[["1", "zzz"], ["2", "aaa"]]);
+    let query = format!("SELECT a, b FROM {table_name} ORDER BY a, b DESC");
+
+    let command = Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--sorted-by",
+            "a",
+            "--sorted-by",
+            "b:desc:nulls-first",
+            "-", // Use `-` to explicitly write to stdout
+            &query,
+        ])
+        .assert()
+        .success();
+
+    // Then
+    let bytes = Bytes::from(command.get_output().stdout.clone());
+    let reader = SerializedFileReader::new(bytes).unwrap();
+    let sorting_columns = reader
+        .metadata()
+        .row_group(0)
+        .sorting_columns()
+        .unwrap()
+        .clone();
+    assert_eq!(
+        vec![
+            SortingColumn::new(0, false, false),
+            SortingColumn::new(1, true, true)
+        ],
+        sorting_columns
+    );
+}
+
+#[test]
+fn sorted_by_unknown_column() {
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--sorted-by",
+            "b",
+            "-",
+            "SELECT 42 AS a",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "Column 'b' specified in `--sorted-by` is not part of the result set.",
+        ));
+}
+
+#[test]
+fn preserve_charset() {
+    // Setup table for test
+    let table_name = "PreserveCharset";
+    let mut table = TableMssql::new(table_name, &["VARCHAR(10)"]);
+    table.insert_rows_as_text(&[["Hello"], ["World"]]);
+    let query = format!("SELECT a FROM {table_name} ORDER BY id");
+
+    let command = Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--preserve-charset",
+            "latin1",
+            "-", // Use `-` to explicitly write to stdout
+            &query,
+        ])
+        .assert()
+        .success();
+
+    // Then
+    let bytes = Bytes::from(command.get_output().stdout.clone());
+    let reader = SerializedFileReader::new(bytes).unwrap();
+    let file_metadata = reader.metadata().file_metadata();
+    let key_value = &file_metadata.key_value_metadata().unwrap()[0];
+    assert_eq!("odbc2parquet.charset", key_value.key);
+    assert_eq!(Some("latin1"), key_value.value.as_deref());
+    let column = file_metadata.schema_descr().column(0);
+    assert_eq!(PhysicalType::BYTE_ARRAY, column.physical_type());
+    assert_eq!(ConvertedType::NONE, column.converted_type());
+}
+
+#[test]
+fn geometry_column_as_geoparquet() {
+    // A point as Well Known Binary, with SRID 4326 which is not part of WKB
+    let query = "SELECT geometry::Point(1, 2, 4326).STAsBinary() AS a";
+
+    let command = Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--column-length-limit",
+            "100",
+            "--geometry-column",
+            "a",
+            "-", // Use `-` to explicitly write to stdout
+            query,
+        ])
+        .assert()
+        .success();
+
+    // Then
+    let bytes = Bytes::from(command.get_output().stdout.clone());
+    let reader = SerializedFileReader::new(bytes).unwrap();
+    let file_metadata = reader.metadata().file_metadata();
+    let key_value = &file_metadata.key_value_metadata().unwrap()[0];
+    assert_eq!("geo", key_value.key);
+    assert_eq!(
+        Some(
+            "{\"version\":\"1.1.0\",\"primary_column\":\"a\",\"columns\":{\"a\":{\
+            \"encoding\":\"WKB\",\"geometry_types\":[]}}}"
+        ),
+        key_value.value.as_deref()
+    );
+    let column = file_metadata.schema_descr().column(0);
+    assert_eq!(PhysicalType::BYTE_ARRAY, column.physical_type());
+}
+
+#[test]
+fn column_comment_in_key_value_metadata() {
+    // Setup table for test
+    let table_name = "ColumnCommentInKeyValueMetadata";
+    TableMssql::new(table_name, &["INTEGER"]);
+    let query = format!("SELECT a FROM {table_name}");
+
+    let command = Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--column-comment",
+            "a=Number of items",
+            "-", // Use `-` to explicitly write to stdout
+            &query,
+        ])
+        .assert()
+        .success();
+
+    // Then
+    let bytes = Bytes::from(command.get_output().stdout.clone());
+    let reader = SerializedFileReader::new(bytes).unwrap();
+    let key_value = &reader
+        .metadata()
+        .file_metadata()
+        .key_value_metadata()
+        .unwrap()[0];
+    assert_eq!("odbc2parquet.comment.a", key_value.key);
+    assert_eq!(Some("Number of items"), key_value.value.as_deref());
+}
+
+#[test]
+fn column_comments_from_catalog() {
+    // Setup table for test
+    let table_name = "ColumnCommentsFromCatalog";
+    let table = TableMssql::new(table_name, &["INTEGER"]);
+    table
+        .conn
+        .execute(
+            &format!(
+                "EXEC sp_addextendedproperty @name = N'MS_Description', \
+                @value = N'Number of items', @level0type = N'SCHEMA', @level0name = N'dbo', \
+                @level1type = N'TABLE', @level1name = N'{table_name}', \
+                @level2type = N'COLUMN', @level2name = N'a'"
+            ),
+            (),
+        )
+        .unwrap();
+
+    let command = Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--table",
+            table_name,
+            "--schema",
+            "dbo",
+            "--include-ddl-comments-from-catalog",
+            "-", // Use `-` to explicitly write to stdout
+        ])
+        .assert()
+        .success();
+
+    // Then
+    let bytes = Bytes::from(command.get_output().stdout.clone());
+    let reader = SerializedFileReader::new(bytes).unwrap();
+    let key_value = &reader
+        .metadata()
+        .file_metadata()
+        .key_value_metadata()
+        .unwrap()[0];
+    assert_eq!("odbc2parquet.comment.a", key_value.key);
+    assert_eq!(Some("Number of items"), key_value.value.as_deref());
+}
+
+#[test]
+fn column_comment_for_unknown_column() {
+    // Setup table for test
+    let table_name = "ColumnCommentForUnknownColumn";
+    TableMssql::new(table_name, &["INTEGER"]);
+    let query = format!("SELECT a FROM {table_name}");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--column-comment",
+            "b=Does not exist",
+            "-",
+            &query,
+        ])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "Column 'b' of `--column-comment` is not part of the output.",
+        ));
+}
+
+/// Parquet output is buffered and streamed into a named pipe once complete.
+#[test]
+#[cfg(unix)]
+fn write_to_named_pipe() {
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let fifo_path = out_dir.path().join("out.par");
+    let fifo_str = fifo_path
+        .to_str()
+        .expect("Temporary file path must be utf8");
+    std::process::Command::new("mkfifo")
+        .arg(fifo_str)
+        .status()
+        .unwrap();
+    // Consume the pipe in a separate thread, so writing it does not block forever.
+    let fifo_reader = {
+        let fifo_path = fifo_path.clone();
+        std::thread::spawn(move || std::fs::read(fifo_path).unwrap())
+    };
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            fifo_str,
+            "SELECT 42 AS a",
+        ])
+        .assert()
+        .success();
+
+    // Then
+    let bytes = Bytes::from(fifo_reader.join().unwrap());
+    let reader = SerializedFileReader::new(bytes).unwrap();
+    assert_eq!(1, reader.metadata().file_metadata().num_rows());
+}
+
+#[test]
+fn compression_per_category() {
+    let output = Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--column-compression-default",
+            "snappy",
+            "--compression-strings",
+            "gzip",
+            "--compression-numeric",
+            "uncompressed",
+            "-",
+            "SELECT 42 AS a, 'Hello' AS b, CAST(0x01 AS VARBINARY(10)) AS c",
+        ])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let reader = SerializedFileReader::new(Bytes::from(output)).unwrap();
+    let row_group = reader.metadata().row_group(0);
+    assert_eq!(Compression::UNCOMPRESSED, row_group.column(0).compression());
+    assert!(matches!(
+        row_group.column(1).compression(),
+        Compression::GZIP(_)
+    ));
+    assert_eq!(Compression::SNAPPY, row_group.column(2).compression());
+}
+
+#[test]
+fn also_write_with_different_codec() {
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let snappy_path = out_dir.path().join("snappy.par");
+    let snappy_str = snappy_path
+        .to_str()
+        .expect("Temporary file path must be utf8");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--also-write",
+            &format!("snappy:{snappy_str}"),
+            out_str,
+            "SELECT 42 AS a, 'Hello' AS b",
+        ])
+        .assert()
+        .success();
+
+    let expected_values = "{a: 42, b: \"Hello\"}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
+    parquet_read_out(snappy_str).stdout(eq(expected_values));
+    let reader = SerializedFileReader::new(File::open(&snappy_path).unwrap()).unwrap();
+    let row_group = reader.metadata().row_group(0);
+    assert_eq!(Compression::SNAPPY, row_group.column(0).compression());
+    assert_eq!(Compression::SNAPPY, row_group.column(1).compression());
+}
+
+#[test]
+#[cfg(unix)]
+fn named_pipe_conflicts_with_split_files() {
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let fifo_path = out_dir.path().join("out.par");
+    let fifo_str = fifo_path
+        .to_str()
+        .expect("Temporary file path must be utf8");
+    std::process::Command::new("mkfifo")
+        .arg(fifo_str)
+        .status()
+        .unwrap();
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--row-groups-per-file",
+            "1",
+            fifo_str,
+            "SELECT 42 AS a",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains("is a named pipe"));
+}
+
+#[test]
+fn verify_row_count() {
+    // Setup table for test
+    let table_name = "VerifyRowCount";
+    let mut table = TableMssql::new(table_name, &["INTEGER"]);
+    table.insert_rows_as_text(&[["1"], ["2"], ["3"]]);
+    let query = format!("SELECT a FROM {table_name} WHERE a > ?;");
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--verify-row-count",
+            "-",
+            &query,
+            "1",
+        ])
+        .assert()
+        .success();
+}
+
+#[test]
+fn verify_row_count_with_explicit_count_query() {
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--count-query",
+            "SELECT 3",
+            "--row-count-tolerance",
+            "1",
+            "-",
+            "SELECT 42 AS a",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "Row count verification failed. 1 rows have been written, but the count query \
+            returned 3 rows.",
+        ));
+}
+
+#[test]
+fn stats_only() {
+    // Setup table for test
+    let table_name = "StatsOnly";
+    let mut table = TableMssql::new(table_name, &["INTEGER", "VARCHAR(10)"]);
+    table.insert_rows_as_text(&[[Some("1"), Some("aaa")], [Some("3"), None]]);
+    let query = format!("SELECT a, b FROM {table_name}");
+
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
-            "-vvvv",
-            "insert",
+            "query",
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            "--stats-only",
+            "-",
+            &query,
         ])
         .assert()
-        .success();
-
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
-
-    assert_eq!(".00\nNULL\n.01", actual);
+        .success()
+        .stdout(eq(
+            "{\"row_groups\":[{\"num_rows\":2,\"columns\":[\
+            {\"name\":\"a\",\"null_count\":0,\"min\":1,\"max\":3},\
+            {\"name\":\"b\",\"null_count\":1,\"min\":\"aaa\",\"max\":\"aaa\"}]}]}\n",
+        ));
 }
 
 #[test]
-pub fn insert_decimal_from_fixed_binary() {
-    let table_name = "InsertDecimalFromFixedBinary";
-    // Prepare table
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["DECIMAL(5,2)"]).unwrap();
-
-    // Prepare file
+fn describe_parameters() {
+    // Setup table for test
+    let table_name = "DescribeParameters";
+    TableMssql::new(table_name, &["INTEGER NOT NULL"]);
+    let query = format!("SELECT a FROM {table_name} WHERE a > ?");
 
-    // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args(["describe-parameters", "--connection-string", MSSQL, &query])
+        .assert()
+        .success()
+        .stdout(eq(
+            "Parameter 1:\n\tData type: Integer\n\tNullability: Nullable\n",
+        ));
+}
 
-    let message_type = "
-        message schema {
-            REQUIRED FIXED_LEN_BYTE_ARRAY(3) a (DECIMAL(5,2));
+#[test]
+fn self_test() {
+    for literals_only in [false, true] {
+        let mut args = vec![
+            "self-test",
+            "--connection-string",
+            MSSQL,
+            "--type",
+            "INTEGER=42",
+            "--type",
+            "DATETIME2(3)=2024-02-29 23:59:58.123",
+            "--type",
+            "VARCHAR(10)=it's",
+            "--type",
+            "NO_SUCH_TYPE=1",
+        ];
+        if literals_only {
+            args.push("--use-literals-only");
+        } else {
+            args.extend(["--table", "SelfTest"]);
         }
-    ";
 
-    let to_fixed_len_byte_array = |input: Vec<u8>| -> FixedLenByteArray {
-        let ba: ByteArray = input.into();
-        ba.into()
-    };
+        Command::cargo_bin("odbc2parquet")
+            .unwrap()
+            .args(&args)
+            .assert()
+            .failure()
+            .stdout(contains(
+                "PASS INTEGER\nPASS DATETIME2(3)\nPASS VARCHAR(10)\nFAIL NO_SUCH_TYPE: ",
+            ))
+            .stderr(contains("1 of 4 types failed the self test."));
+    }
+}
 
-    let zero: FixedLenByteArray = to_fixed_len_byte_array(vec![0u8, 0, 0]);
-    write_values_to_file(
-        message_type,
-        &input_path,
-        &[
-            zero,
-            to_fixed_len_byte_array(vec![0, 0, 1]),
-            to_fixed_len_byte_array(vec![1, 0, 0]),
-            to_fixed_len_byte_array(vec![255, 255, 255]),
-        ],
-        None,
-    );
+#[test]
+fn fetch_as_text_all() {
+    // Setup table for test
+    let table_name = "FetchAsTextAll";
+    let mut table = TableMssql::new(table_name, &["INTEGER", "DATE"]);
+    table.insert_rows_as_text(&[["42", "2021-05-04"]]);
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a, b FROM {table_name}");
 
-    // Insert file into table
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
-            "-vvvv",
-            "insert",
+            "query",
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            "--fetch-as-text-all",
+            out_str,
+            &query,
         ])
         .assert()
         .success();
 
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
-
-    assert_eq!(".00\n.01\n655.36\n-.01", actual);
+    parquet_read_out(out_str).stdout(eq("{a: \"42\", b: \"2021-05-04\"}\n"));
+    parquet_schema_out(out_str).stdout(contains(
+        "OPTIONAL BYTE_ARRAY a (UTF8);\n  OPTIONAL BYTE_ARRAY b (UTF8);",
+    ));
 }
 
 #[test]
-pub fn insert_decimal_from_fixed_binary_optional() {
-    let table_name = "InsertDecimalFromFixedBinaryOptional";
-    // Prepare table
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["DECIMAL(5,2)"]).unwrap();
-
-    // Prepare file
-
+fn schema_from_existing_file() {
+    // Setup table for test
+    let table_name = "SchemaFromExistingFile";
+    let mut table = TableMssql::new(table_name, &["INTEGER", "VARCHAR(10)"]);
+    table.insert_rows_as_text(&[["42", "17"]]);
     // A temporary directory, to be removed at the end of the test.
-    let tmp_dir = tempdir().unwrap();
-    // The name of the input parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
-    let input_path = tmp_dir.path().join("input.par");
-
-    let message_type = "
-        message schema {
-            OPTIONAL FIXED_LEN_BYTE_ARRAY(3) a (DECIMAL(5,2));
-        }
-    ";
-
-    let to_fixed_len_byte_array = |input: Vec<u8>| -> FixedLenByteArray {
-        let ba: ByteArray = input.into();
-        ba.into()
-    };
-
-    write_values_to_file(
-        message_type,
-        &input_path,
-        &[
-            to_fixed_len_byte_array(vec![0, 0, 1]),
-            to_fixed_len_byte_array(vec![255, 255, 255]),
-        ],
-        Some(&[1, 0, 1]),
-    );
+    let out_dir = tempdir().unwrap();
+    let schema_path = out_dir.path().join("schema.par");
+    let schema_str = schema_path
+        .to_str()
+        .expect("Temporary file path must be utf8");
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    // Insert file into table
+    // Write file with the target schema. Column `a` is an INT32.
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
-            "-vvvv",
-            "insert",
+            "query",
             "--connection-string",
             MSSQL,
-            input_path.to_str().unwrap(),
-            table_name,
+            schema_str,
+            &format!("SELECT a FROM {table_name}"),
         ])
         .assert()
         .success();
 
-    // Query table and check for expected result
-    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
-    let cursor = conn.execute(&query, ()).unwrap().unwrap();
-    let actual = cursor_to_string(cursor);
+    // Column `b` is text, but we want it to be written with the same schema as `a`.
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--schema-from",
+            schema_str,
+            "--schema-match-by",
+            "position",
+            out_str,
+            &format!("SELECT b FROM {table_name}"),
+        ])
+        .assert()
+        .success();
 
-    assert_eq!(".01\nNULL\n-.01", actual);
+    parquet_read_out(out_str).stdout(eq("{a: 17}\n"));
+    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT32 a (INTEGER(32,true));"));
 }
 
-/// Write query output to stdout
 #[test]
-pub fn write_query_result_to_stdout() {
-    // Given
-    let table_name = "WriteQueryResultToStdout";
-    // Prepare table
-    let conn = ENV
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["INTEGER"]).unwrap();
-    conn.execute(
-        &format!("INSERT INTO {table_name} (a) VALUES (?)"),
-        [42i32, 5, 64].as_slice(),
-    )
-    .unwrap();
-
-    // When
+fn schema_from_with_unmatched_column() {
+    // Setup table for test
+    let table_name = "SchemaFromWithUnmatchedColumn";
+    let table = TableMssql::new(table_name, &["INTEGER", "INTEGER"]);
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let schema_path = out_dir.path().join("schema.par");
+    let schema_str = schema_path
+        .to_str()
+        .expect("Temporary file path must be utf8");
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
 
-    // Query table and write contents to stdout
-    let query = format!("SELECT a FROM {table_name} ORDER BY id");
-    let command = Command::cargo_bin("odbc2parquet")
+    Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
-            "-vvvv",
             "query",
             "--connection-string",
             MSSQL,
-            "-", // Use `-` to explicitly write to stdout
-            &query,
+            schema_str,
+            &format!("SELECT a FROM {}", table.name),
         ])
         .assert()
         .success();
 
-    // Then
-    let output = &command.get_output().stdout;
-    assert!(!output.is_empty());
-
-    // Write captured contents of stdout to temporary file so we can inspect it with read parquet
-    let mut output_file = NamedTempFile::new().unwrap();
-    output_file.write_all(output).unwrap();
-
-    let expected = "{a: 42}\n";
-
-    let output_path = output_file.path().to_str().unwrap();
-    parquet_read_out(output_path).stdout(eq(expected));
-}
-
-/// Write query output to stdout
-#[test]
-pub fn reject_writing_to_stdout_and_file_size_limit() {
     Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
-            "-vvvv",
             "query",
             "--connection-string",
-            "FakeConnectionString",
-            "--file-size-threshold",
-            "1GiB",
-            "-", // Use `-` to explicitly write to stdout
-            "SELECT a FROM FakeTableName ORDER BY id",
+            MSSQL,
+            "--schema-from",
+            schema_str,
+            out_str,
+            &format!("SELECT a, b FROM {}", table.name),
         ])
         .assert()
         .failure()
         .stderr(contains(
-            "file-size-threshold conflicts with specifying stdout ('-') as output.",
+            "Column 'b' of the result set is not part of the target schema.",
         ));
 }
 
 #[test]
-fn write_statistics_for_text_columns() {
+fn extract_blobs_into_files() {
     // Setup table for test
-    let table_name = "WriteStatisticsForTextColumns";
-    let mut table = TableMssql::new(table_name, &["VARCHAR(10)"]);
-    table.insert_rows_as_text(&[["aaa"], ["zzz"]]);
-    let query = format!("SELECT a FROM {table_name}");
+    let table_name = "ExtractBlobsIntoFiles";
+    // Column `b` holds the binary values in hex representation.
+    let mut table = TableMssql::new(table_name, &["VARCHAR(10)", "VARCHAR(10)"]);
+    table.insert_rows_as_text(&[
+        [Some("doc 1"), Some("0102")],
+        [Some("doc_2"), None],
+        [Some("doc_1"), Some("03")],
+    ]);
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let blob_dir = out_dir.path().join("blobs");
+    let blob_str = blob_dir.to_str().expect("Temporary file path must be utf8");
+    let query =
+        format!("SELECT a, CONVERT(VARBINARY(10), b, 2) AS b FROM {table_name} ORDER BY id");
 
-    let command = Command::cargo_bin("odbc2parquet")
+    Command::cargo_bin("odbc2parquet")
         .unwrap()
         .args([
             "query",
             "--connection-string",
             MSSQL,
-            "-", // Use `-` to explicitly write to stdout
+            "--extract-blobs",
+            "b",
+            "--blob-dir",
+            blob_str,
+            "--blob-key-column",
+            "a",
+            out_str,
             &query,
         ])
         .assert()
         .success();
 
-    // Then
-    let bytes = Bytes::from(command.get_output().stdout.clone());
-    let reader = SerializedFileReader::new(bytes).unwrap();
-    let stats = reader
-        .metadata()
-        .row_group(0)
-        .column(0)
-        .statistics()
-        .unwrap();
-    assert_eq!("aaa", str::from_utf8(stats.min_bytes_opt().unwrap()).unwrap());
-    assert_eq!("zzz", str::from_utf8(stats.max_bytes_opt().unwrap()).unwrap());
+    // `doc 1` and `doc_1` map to the same file name, so the second one gets a suffix.
+    parquet_read_out(out_str).stdout(eq("{a: \"doc 1\", b: \"b/doc_1\"}\n\
+        {a: \"doc_2\", b: null}\n\
+        {a: \"doc_1\", b: \"b/doc_1_1\"}\n"));
+    assert_eq!(
+        vec![1u8, 2],
+        std::fs::read(blob_dir.join("b/doc_1")).unwrap()
+    );
+    assert_eq!(
+        vec![3u8],
+        std::fs::read(blob_dir.join("b/doc_1_1")).unwrap()
+    );
+    assert!(!blob_dir.join("b/doc_2").exists());
+}
+
+#[test]
+fn extract_blobs_larger_than_one_chunk() {
+    // Setup table for test
+    let table_name = "ExtractBlobsLargerThanOneChunk";
+    let mut table = TableMssql::new(table_name, &["INTEGER", "VARBINARY(MAX)"]);
+    table.insert_rows_as_text(&[[Some("1"), None]]);
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let blob_dir = out_dir.path().join("blobs");
+    let blob_str = blob_dir.to_str().expect("Temporary file path must be utf8");
+    // 200000 bytes, more than fit into one chunk fetched with `SQLGetData`.
+    let query = format!(
+        "SELECT a, CONVERT(VARBINARY(MAX), REPLICATE(CONVERT(VARCHAR(MAX), 'ab'), 100000)) AS b \
+        FROM {table_name}"
+    );
+
+    Command::cargo_bin("odbc2parquet")
+        .unwrap()
+        .args([
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--extract-blobs",
+            "b",
+            "--blob-dir",
+            blob_str,
+            "--blob-key-column",
+            "a",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    parquet_read_out(out_str).stdout(eq("{a: 1, b: \"b/1\"}\n"));
+    assert_eq!(
+        b"ab".repeat(100_000),
+        std::fs::read(blob_dir.join("b/1")).unwrap()
+    );
 }
 
-/// This did not work in earlier versions there we set the batch write size of the parquet writer to
-/// the ODBC batch size.
 #[test]
 #[ignore = "Takes too long to run"]
 fn query_4097_bits() {